@@ -0,0 +1,97 @@
+"""Print the statsmodels reference vectors of the ADF and KPSS unit tests as Rust tables.
+
+Run with statsmodels 0.14 and numpy installed:
+
+    python scripts/statsmodels_vectors.py > /tmp/vectors.rs
+
+and paste STATSMODELS_ADFULLER and STATSMODELS_MAX_LAG into the tests of src/adf.rs and
+STATSMODELS_KPSS into the tests of src/kpss.rs. The series is STATSMODELS_SERIES from the test
+fixtures in src/rng.rs and must be kept in step with it.
+"""
+
+import warnings
+
+import numpy as np
+import statsmodels
+from statsmodels.tsa.stattools import adfuller, kpss
+
+SERIES = np.array([
+    9.561, 9.362, 8.781, 9.879, 11.229, 12.623, 13.149, 11.176, 9.944, 9.691, 10.7, 11.127,
+    12.443, 11.257, 10.163, 8.625, 7.436, 6.158, 7.381, 7.53, 8.372, 8.465, 8.241, 8.174,
+    8.271, 8.253, 6.736, 6.227, 7.228, 8.189, 7.631, 6.32, 7.51, 9.943, 9.516, 9.22, 11.944,
+    14.364, 12.88, 11.415, 12.17, 13.241, 13.815, 13.938, 14.241, 11.411, 10.092, 8.697, 9.3,
+    11.154, 12.25, 12.385, 12.276, 12.218, 10.812, 9.753, 9.716, 10.624, 9.965, 11.332, 12.496,
+    13.744, 14.442, 14.961, 13.798, 11.063, 8.244, 6.781, 6.11, 7.017, 6.619, 6.859, 7.157,
+    7.509, 8.618, 8.779, 7.842, 8.111, 10.333, 9.494,
+])
+
+REGRESSIONS = ["n", "c", "ct", "ctt"]
+# (autolag, maxlag) of each case, in the order of STATSMODELS_ADFULLER
+SETTINGS = [(False, 0), (False, 3), (True, None)]
+MAX_LAG_LENGTHS = [3, 4, 5, 6, 7, 8, 10, 20, 50, 99, 100, 101, 250, 1000, 10000]
+
+
+def adfuller_cases():
+    print("    const STATSMODELS_ADFULLER: [AdfullerCase; %d] = [" % (4 * len(SETTINGS)))
+    for regression in REGRESSIONS:
+        for autolag, maxlag in SETTINGS:
+            # autolag=None drops the trailing icbest from the returned tuple
+            statistic, p_value, used_lag, nobs, critical = adfuller(
+                SERIES, maxlag=maxlag, regression=regression, autolag="AIC" if autolag else None
+            )[:5]
+            print("        AdfullerCase {")
+            print('            regression: "%s",' % regression)
+            print("            autolag: %s," % ("true" if autolag else "false"))
+            print("            max_lag: %s," % ("None" if maxlag is None else "Some(%d)" % maxlag))
+            print("            statistic: %r," % float(statistic))
+            print("            used_lag: %d," % used_lag)
+            print("            nobs: %d," % nobs)
+            print("            p_value: %r," % float(p_value))
+            print(
+                "            critical_values: [%r, %r, %r],"
+                % tuple(float(critical[level]) for level in ("1%", "5%", "10%"))
+            )
+            print("        },")
+    print("    ];")
+
+
+def default_max_lags():
+    rng = np.random.default_rng(0)
+    walk = np.cumsum(rng.standard_normal(max(MAX_LAG_LENGTHS)))
+    print(
+        "    const STATSMODELS_MAX_LAG: [(&str, [Option<usize>; %d]); 4] = ["
+        % len(MAX_LAG_LENGTHS)
+    )
+    for regression in REGRESSIONS:
+        lags = []
+        for length in MAX_LAG_LENGTHS:
+            try:
+                used_lag = adfuller(walk[:length], regression=regression, autolag=None)[2]
+                lags.append("Some(%d)" % used_lag)
+            except ValueError:
+                lags.append("None")
+        print('        ("%s", [%s]),' % (regression, ", ".join(lags)))
+    print("    ];")
+
+
+def kpss_cases():
+    head = SERIES[:40]
+    trending = SERIES + 0.04 * np.arange(len(SERIES))
+    cases = [("full", SERIES, "c"), ("full", SERIES, "ct"), ("head", head, "c"),
+             ("head", head, "ct"), ("trending", trending, "c")]
+    print("    const STATSMODELS_KPSS: [(&str, &str, f64, usize, f64); %d] = [" % len(cases))
+    for name, series, regression in cases:
+        with warnings.catch_warnings():
+            # p-values outside the table come with an InterpolationWarning
+            warnings.simplefilter("ignore")
+            statistic, p_value, lags, _ = kpss(series, regression=regression, nlags="auto")
+        row = (name, regression, float(statistic), lags, float(p_value))
+        print('        ("%s", "%s", %r, %d, %r),' % row)
+    print("    ];")
+
+
+if __name__ == "__main__":
+    print("// statsmodels %s, numpy %s" % (statsmodels.__version__, np.__version__))
+    adfuller_cases()
+    default_max_lags()
+    kpss_cases()
@@ -0,0 +1,1493 @@
+// Augmented Dickey-Fuller regression computed directly from a series.
+//
+//   Δy_t = γ·y_{t-1} + Σ_{i=1..p} δ_i·Δy_{t-i} + deterministic terms + ε_t
+//
+// The test statistic is the t-ratio of γ. Column layout follows statsmodels'
+// `adfuller`: lagged level first, then the lagged differences, then the
+// deterministic terms.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::bootstrap::adf_bootstrap_p_value;
+use crate::buffer::Float64Buffer;
+use crate::cache::{get_or_compute, Cached, Fingerprint};
+use crate::error::Error;
+#[cfg(feature = "wasm")]
+use crate::get_property;
+#[cfg(feature = "wasm")]
+use crate::json::{parse_payload, payload_series};
+use crate::json::{JsonObject, RawJson};
+use crate::linalg::{least_squares, CovarianceType, LeastSquares};
+use crate::mackinnon::{mackinnon_critical_values, mackinnon_lookup};
+use crate::preprocess::{clean_series_with, MissingPolicy};
+use crate::pvalue::PValue;
+#[cfg(feature = "wasm")]
+use crate::rng::parse_seed;
+use crate::rng::DEFAULT_SEED;
+use crate::validate::{adf_min_length, require_finite, require_length, require_varying};
+use crate::{build_adf_result, AdfResult};
+
+// Deterministic terms included in the ADF regression
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Regression {
+    // No deterministic terms ("n"); also used for Engle-Granger residuals
+    NoConstant,
+    // Constant only ("c"), which is what the embedded p-value table was built for
+    Constant,
+    // Constant and linear time trend ("ct")
+    ConstantTrend,
+    // Constant, linear and quadratic time trend ("ctt")
+    ConstantQuadraticTrend,
+}
+
+impl Regression {
+    pub fn parse(regression: &str) -> Result<Regression, Error> {
+        match regression {
+            "n" => Ok(Regression::NoConstant),
+            "c" => Ok(Regression::Constant),
+            "ct" => Ok(Regression::ConstantTrend),
+            "ctt" => Ok(Regression::ConstantQuadraticTrend),
+            other => Err(Error::new(&format!(
+                "unknown regression \"{}\": expected \"n\", \"c\", \"ct\" or \"ctt\"",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Regression::NoConstant => "n",
+            Regression::Constant => "c",
+            Regression::ConstantTrend => "ct",
+            Regression::ConstantQuadraticTrend => "ctt",
+        }
+    }
+
+    pub(crate) fn deterministic_terms(&self) -> usize {
+        match self {
+            Regression::NoConstant => 0,
+            Regression::Constant => 1,
+            Regression::ConstantTrend => 2,
+            Regression::ConstantQuadraticTrend => 3,
+        }
+    }
+
+    // Append the deterministic columns for a regression on `nobs` observations: a constant,
+    // then the trend t = 1..=nobs, then t²
+    pub(crate) fn push_columns(&self, columns: &mut Vec<Vec<f64>>, nobs: usize) {
+        let terms = self.deterministic_terms();
+        if terms >= 1 {
+            columns.push(vec![1.0; nobs]);
+        }
+        for power in 1..terms as i32 {
+            columns.push((1..=nobs).map(|t| (t as f64).powi(power)).collect());
+        }
+    }
+}
+
+impl CovarianceType {
+    pub fn parse(cov_type: &str) -> Result<CovarianceType, Error> {
+        match cov_type {
+            "nonrobust" => Ok(CovarianceType::Classical),
+            "HC0" => Ok(CovarianceType::Hc0),
+            "HC1" => Ok(CovarianceType::Hc1),
+            "HAC" => Ok(CovarianceType::Hac(None)),
+            other => Err(Error::new(&format!(
+                "unsupported cov_type \"{}\": expected \"nonrobust\", \"HC0\", \"HC1\" or \"HAC\"",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CovarianceType::Classical => "nonrobust",
+            CovarianceType::Hc0 => "HC0",
+            CovarianceType::Hc1 => "HC1",
+            CovarianceType::Hac(_) => "HAC",
+        }
+    }
+}
+
+pub(crate) struct AdfFit {
+    pub statistic: f64,
+    pub used_lag: usize,
+    pub nobs: usize,
+    pub regression: Regression,
+}
+
+// Design matrix and response of the ADF regression with `lags` lagged differences on the
+// sample that remains after `sample_lags` differences are consumed (`sample_lags >= lags`),
+// so regressions with different lag orders can be compared on identical observations.
+fn adf_design(
+    series: &[f64],
+    lags: usize,
+    sample_lags: usize,
+    regression: Regression,
+) -> Option<(Vec<Vec<f64>>, Vec<f64>)> {
+    if series.len() < sample_lags + 3 {
+        return None;
+    }
+
+    let diff: Vec<f64> = series.windows(2).map(|w| w[1] - w[0]).collect();
+    // Observations usable once `sample_lags` differences have been consumed
+    let nobs = diff.len() - sample_lags;
+
+    let mut columns = Vec::with_capacity(lags + 2);
+    columns.push(series[sample_lags..sample_lags + nobs].to_vec());
+    for i in 1..=lags {
+        columns.push(diff[sample_lags - i..sample_lags - i + nobs].to_vec());
+    }
+    regression.push_columns(&mut columns, nobs);
+    Some((columns, diff[sample_lags..].to_vec()))
+}
+
+// Run the ADF regression with exactly `lags` lagged differences, using `cov` for the
+// standard error in the t-ratio. Returns None if the series is too short for the
+// requested lags or the regression is singular.
+pub(crate) fn adf_regression(
+    series: &[f64],
+    lags: usize,
+    regression: Regression,
+    cov: CovarianceType,
+) -> Option<AdfFit> {
+    let (columns, y) = adf_design(series, lags, lags, regression)?;
+    let ols = least_squares(&columns, &y)?;
+    let statistic = ols.coefficients[0] / ols.std_error_with(&columns, 0, cov);
+    if !statistic.is_finite() {
+        return None;
+    }
+
+    Some(AdfFit {
+        statistic,
+        used_lag: lags,
+        nobs: y.len(),
+        regression,
+    })
+}
+
+// How the number of lagged differences is chosen
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LagSelection {
+    // Use max_lag as given
+    Fixed,
+    // Minimise the Akaike information criterion over 0..=max_lag
+    Aic,
+    // Minimise the Schwarz (Bayesian) information criterion over 0..=max_lag
+    Bic,
+    // Start at max_lag and drop lags until the last one is significant at 5% (|t| >= 1.645)
+    TStat,
+}
+
+impl LagSelection {
+    pub fn parse(autolag: &str) -> Result<LagSelection, Error> {
+        match autolag {
+            "AIC" => Ok(LagSelection::Aic),
+            "BIC" => Ok(LagSelection::Bic),
+            "t-stat" => Ok(LagSelection::TStat),
+            other => Err(Error::new(&format!(
+                "unsupported autolag \"{}\": expected \"AIC\", \"BIC\", \"t-stat\" or null",
+                other
+            ))),
+        }
+    }
+}
+
+// Which comparisons decide `AdfResult.is_stationary` at a given significance level
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecisionRule {
+    // p-value at or below the significance level
+    PValue,
+    // Statistic below the critical value for the significance level
+    CriticalValue,
+    // Both of the above (the default)
+    Both,
+}
+
+impl DecisionRule {
+    pub fn parse(rule: &str) -> Result<DecisionRule, Error> {
+        match rule {
+            "p_value" => Ok(DecisionRule::PValue),
+            "critical_value" => Ok(DecisionRule::CriticalValue),
+            "both" => Ok(DecisionRule::Both),
+            other => Err(Error::new(&format!(
+                "unsupported decision_rule \"{}\": expected \"p_value\", \"critical_value\" or \"both\"",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DecisionRule::PValue => "p_value",
+            DecisionRule::CriticalValue => "critical_value",
+            DecisionRule::Both => "both",
+        }
+    }
+}
+
+// Whose conventions fill in the details where statsmodels' `adfuller` and this crate differ
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compatibility {
+    // Schwert's rule rounded down for the default max_lag (the default)
+    Native,
+    // Schwert's rule rounded up as `adfuller` does, and a max_lag above `adfuller`'s cap is an
+    // error instead of a regression on very few observations
+    Statsmodels,
+}
+
+impl Compatibility {
+    pub fn parse(compatibility: &str) -> Result<Compatibility, Error> {
+        match compatibility {
+            "native" => Ok(Compatibility::Native),
+            "statsmodels" => Ok(Compatibility::Statsmodels),
+            other => Err(Error::new(&format!(
+                "unknown compatibility \"{}\": expected \"native\" or \"statsmodels\"",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Compatibility::Native => "native",
+            Compatibility::Statsmodels => "statsmodels",
+        }
+    }
+}
+
+// Choose the lag order as statsmodels' `adfuller(autolag=...)` does: every candidate is fitted
+// on the sample left after `max_lag` differences so the criteria are comparable. Returns None
+// if no candidate regression can be fitted.
+pub(crate) fn select_lag(
+    series: &[f64],
+    max_lag: usize,
+    regression: Regression,
+    method: LagSelection,
+) -> Option<usize> {
+    let fit = |lags| {
+        let (columns, y) = adf_design(series, lags, max_lag, regression)?;
+        least_squares(&columns, &y)
+    };
+
+    match method {
+        LagSelection::Fixed => Some(max_lag),
+        LagSelection::Aic | LagSelection::Bic => {
+            let mut best: Option<(f64, usize)> = None;
+            for (lags, criterion) in lag_criteria(series, max_lag, regression, method)
+                .into_iter()
+                .enumerate()
+            {
+                if criterion.is_nan() {
+                    continue;
+                }
+                if best.is_none_or(|(b, _)| criterion < b) {
+                    best = Some((criterion, lags));
+                }
+            }
+            best.map(|(_, lags)| lags)
+        }
+        LagSelection::TStat => {
+            const SIGNIFICANT: f64 = 1.6448536269514722;
+            for lags in (1..=max_lag).rev() {
+                let ols = fit(lags)?;
+                // The last lagged difference is column `lags`
+                let t = ols.coefficients[lags] / ols.std_error(lags);
+                if t.abs() >= SIGNIFICANT {
+                    return Some(lags);
+                }
+            }
+            Some(0)
+        }
+    }
+}
+
+// AIC or BIC of the regression with 0..=max_lag lagged differences, all on the sample left
+// after max_lag differences as statsmodels' `autolag` compares them; NaN where a fit fails
+fn lag_criteria(
+    series: &[f64],
+    max_lag: usize,
+    regression: Regression,
+    method: LagSelection,
+) -> Vec<f64> {
+    (0..=max_lag)
+        .map(|lags| {
+            adf_design(series, lags, max_lag, regression)
+                .and_then(|(columns, y)| least_squares(&columns, &y))
+                .map_or(f64::NAN, |ols| {
+                    let [aic, bic, _] = information_criteria(&ols);
+                    if method == LagSelection::Aic {
+                        aic
+                    } else {
+                        bic
+                    }
+                })
+        })
+        .collect()
+}
+
+// Every candidate lag of the search over 0..=max_lag fitted on the common sample, with the lag
+// each selection rule would choose; `selected` is the lag actually used
+fn lag_table(series: &[f64], max_lag: usize, regression: Regression, selected: usize) -> LagTable {
+    const SIGNIFICANT: f64 = 1.6448536269514722;
+    let mut table = LagTable {
+        lags: (0..=max_lag as u32).collect(),
+        aic: Vec::with_capacity(max_lag + 1),
+        bic: Vec::with_capacity(max_lag + 1),
+        hqic: Vec::with_capacity(max_lag + 1),
+        last_lag_t: Vec::with_capacity(max_lag + 1),
+        statistics: Vec::with_capacity(max_lag + 1),
+        nobs: 0,
+        selected,
+        aic_lag: None,
+        bic_lag: None,
+        t_stat_lag: None,
+    };
+    let mut fitted = Vec::with_capacity(max_lag + 1);
+    for lags in 0..=max_lag {
+        let ols = adf_design(series, lags, max_lag, regression)
+            .and_then(|(columns, y)| least_squares(&columns, &y));
+        fitted.push(ols.is_some());
+        match ols {
+            Some(ols) => {
+                let [aic, bic, hqic] = information_criteria(&ols);
+                table.aic.push(aic);
+                table.bic.push(bic);
+                table.hqic.push(hqic);
+                table.last_lag_t.push(if lags > 0 {
+                    ols.coefficients[lags] / ols.std_error(lags)
+                } else {
+                    f64::NAN
+                });
+                table
+                    .statistics
+                    .push(ols.coefficients[0] / ols.std_error(0));
+                table.nobs = ols.nobs;
+            }
+            None => {
+                for column in [
+                    &mut table.aic,
+                    &mut table.bic,
+                    &mut table.hqic,
+                    &mut table.last_lag_t,
+                    &mut table.statistics,
+                ] {
+                    column.push(f64::NAN);
+                }
+            }
+        }
+    }
+
+    let minimum = |criteria: &[f64]| {
+        criteria
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| !c.is_nan())
+            .fold(None, |best: Option<(usize, f64)>, (lags, &c)| {
+                if best.is_none_or(|(_, b)| c < b) {
+                    Some((lags, c))
+                } else {
+                    best
+                }
+            })
+            .map(|(lags, _)| lags)
+    };
+    table.aic_lag = minimum(&table.aic);
+    table.bic_lag = minimum(&table.bic);
+    // As `select_lag`: the first lag from the top whose last lagged difference is significant,
+    // no answer if a fit on the way down failed
+    table.t_stat_lag = Some(0);
+    for lags in (1..=max_lag).rev() {
+        if !fitted[lags] {
+            table.t_stat_lag = None;
+            break;
+        }
+        if table.last_lag_t[lags].abs() >= SIGNIFICANT {
+            table.t_stat_lag = Some(lags);
+            break;
+        }
+    }
+    table
+}
+
+// AIC, BIC and HQIC of a Gaussian OLS fit, with statsmodels' parameter count (every column,
+// constant included)
+fn information_criteria(ols: &LeastSquares) -> [f64; 3] {
+    let n = ols.nobs as f64;
+    let k = ols.n_params() as f64;
+    let log_likelihood = -0.5 * n * ((2.0 * std::f64::consts::PI).ln() + (ols.ssr / n).ln() + 1.0);
+    [
+        -2.0 * log_likelihood + 2.0 * k,
+        -2.0 * log_likelihood + n.ln() * k,
+        -2.0 * log_likelihood + 2.0 * n.ln().ln() * k,
+    ]
+}
+
+// p-value and 1%/5%/10% critical values for a fitted regression, from MacKinnon's (1994)
+// p-value and (2010) finite-sample critical-value response surfaces, so no interpolation
+// table is involved and the critical values track the regression's sample size.
+pub(crate) fn adf_p_value_and_critical_values(fit: &AdfFit) -> (PValue, [f64; 3]) {
+    (
+        mackinnon_lookup(fit.statistic, fit.regression, 1),
+        // Every regression has a univariate surface
+        mackinnon_critical_values(fit.regression, 1, fit.nobs).unwrap_or([f64::NAN; 3]),
+    )
+}
+
+// AdfResult for a fitted regression, echoing its lag order and specification
+pub(crate) fn adf_result_from_fit(fit: &AdfFit) -> AdfResult {
+    let (p_value, critical_values) = adf_p_value_and_critical_values(fit);
+    build_adf_result(fit.statistic, p_value, critical_values).with_fit(
+        fit.used_lag,
+        fit.nobs,
+        fit.regression,
+    )
+}
+
+/// Augmented Dickey-Fuller test (with a constant) computed from the raw series: differencing,
+/// the lag matrix and the OLS fit all run in Rust, and the statistic, p-value and critical
+/// values come back in one call.
+///
+/// The regression uses `max_lag` lagged differences; when omitted, Schwert's rule
+/// `12·(n/100)^(1/4)` is used, capped so that at least half of the sample remains for the fit.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn adf_test(series: &[f64], max_lag: Option<usize>) -> Result<AdfResult, Error> {
+    let lags = max_lag.unwrap_or_else(|| default_max_lag(series.len(), Regression::Constant));
+    require_finite(series, "series")?;
+    require_varying(series, "series")?;
+    require_length(
+        series,
+        adf_min_length(lags, Regression::Constant),
+        "series",
+        &format!("the ADF regression with {} lags", lags),
+    )?;
+    let fit = adf_regression(
+        series,
+        lags,
+        Regression::Constant,
+        CovarianceType::Classical,
+    )
+    .ok_or_else(|| {
+        Error::new("ADF regression failed: series too short for the requested lags or degenerate")
+    })?;
+    Ok(adf_result_from_fit(&fit))
+}
+
+/// `adf_test` on the values in a `Float64Buffer`, read in place instead of copied into wasm
+/// memory.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn adf_test_buffer(series: &Float64Buffer, max_lag: Option<usize>) -> Result<AdfResult, Error> {
+    adf_test(series.as_slice(), max_lag)
+}
+
+/// ADF test configured by an options object, all keys optional:
+///
+/// - `max_lag`: largest number of lagged differences considered (default: Schwert's rule as in
+///   `adf_test`)
+/// - `autolag`: `"AIC"` (default), `"BIC"` or `"t-stat"` to pick the lag in `0..=max_lag` like
+///   statsmodels' `adfuller`, or `null` to use exactly `max_lag`
+/// - `regression`: deterministic terms, `"n"`, `"c"` (default), `"ct"` or `"ctt"`
+/// - `significance`: level of the stationarity decision (default 0.05)
+/// - `decision_rule`: `"both"` (default: p-value at most `significance` and statistic below
+///   its critical value), `"p_value"` or `"critical_value"`. Rules involving the critical
+///   value need `significance` between 0.01 and 0.10, where it can be interpolated.
+/// - `missing`: what to do with NaN or infinite observations, `"raise"` (default), `"drop"`,
+///   `"ffill"` or `"interpolate"` (see `clean_series`)
+/// - `bootstrap`: number of sieve-bootstrap replications for `AdfResult.bootstrap_p_value`
+///   (default 0, no bootstrap). Worth it below about 100 observations, where the tabulated
+///   p-value is unreliable; a few hundred to a thousand replications are typical.
+/// - `seed`: integer between 0 and 2³²-1 seeding the bootstrap (default 0); the same seed
+///   always gives the same bootstrap p-value
+/// - `compatibility`: `"native"` (default) or `"statsmodels"`. With `"statsmodels"` the default
+///   `max_lag` is `ceil(12·(n/100)^(1/4))` instead of the floor, and a `max_lag` above
+///   `n/2 - terms - 1` is rejected, exactly as `adfuller` treats them. Everything else already
+///   follows `adfuller`: the design matrix and trend, the `autolag` search on a common sample
+///   with statsmodels' AIC/BIC and tie-breaking, the refit at the chosen lag, MacKinnon's
+///   (1994) p-value and (2010) critical-value surfaces, so the statistic, `used_lag`, `nobs`,
+///   p-value and critical values agree with `adfuller(x, maxlag, regression, autolag)` up to
+///   floating-point rounding of the least-squares solve.
+///
+/// The chosen lag is reported in `AdfResult.used_lag`, and the rule and level behind
+/// `is_stationary` in `decision_rule` and `significance`. The same options as an `AdfOptions`
+/// object go to `adf_test_with_config`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn adf_test_with_options(series: &[f64], options: JsValue) -> Result<AdfResult, Error> {
+    adf_test_with_config(series, &AdfOptions::from_js(&options)?)
+}
+
+/// `adf_test_with_options` driven by JSON text: `payload` is `{"series": [...], "options": {...}}`
+/// with the options `adf_test_with_options` takes (null entries of the series are missing
+/// values), and the result is returned as `AdfResult.to_json()`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn adf_test_from_json(payload: &str) -> Result<String, Error> {
+    let payload = parse_payload(payload)?;
+    let series = payload_series(&payload, "series")?;
+    let options = AdfOptions::from_js(&get_property(&payload, "options")?)?;
+    Ok(adf_test_with_config(&series, &options)?.to_json())
+}
+
+/// `adf_test_with_options` with the options as an `AdfOptions` object.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn adf_test_with_config(series: &[f64], options: &AdfOptions) -> Result<AdfResult, Error> {
+    let cleaned = clean_series_with(series, options.missing)?;
+    let (fit, _) = configured_fit(cleaned.as_slice(), options)?;
+    configured_result(cleaned.as_slice(), &fit, options)
+}
+
+/// `adf_test_with_options`, answered from the result cache when the same series was tested with
+/// the same options before (see `cache_stats`). The series is identified by a hash of its exact
+/// values, so any change to it, however small, is a cache miss.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn adf_test_cached(series: &[f64], options: JsValue) -> Result<AdfResult, Error> {
+    adf_test_cached_with_config(series, &AdfOptions::from_js(&options)?)
+}
+
+/// `adf_test_cached` with the options as an `AdfOptions` object.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn adf_test_cached_with_config(
+    series: &[f64],
+    options: &AdfOptions,
+) -> Result<AdfResult, Error> {
+    let mut fingerprint = Fingerprint::new("adf_test_with_config");
+    fingerprint.series(series);
+    fingerprint.bytes(format!("{:?}", options).as_bytes());
+    let cached = get_or_compute(fingerprint.finish(), || {
+        adf_test_with_config(series, options).map(Cached::Adf)
+    })?;
+    match cached {
+        Cached::Adf(result) => Ok(result),
+        _ => Err(Error::new("the cached result is not an ADF result")),
+    }
+}
+
+// Lag selection and final regression of `adf_test_with_config` on a cleaned series; also
+// returns the max_lag the selection searched
+fn configured_fit(series: &[f64], options: &AdfOptions) -> Result<(AdfFit, usize), Error> {
+    let max_lag = match options.compatibility {
+        Compatibility::Native => options
+            .max_lag
+            .unwrap_or_else(|| default_max_lag(series.len(), options.regression)),
+        Compatibility::Statsmodels => {
+            statsmodels_max_lag(series.len(), options.regression, options.max_lag)?
+        }
+    };
+    require_varying(series, "series")?;
+    require_length(
+        series,
+        adf_min_length(max_lag, options.regression),
+        "series",
+        &format!(
+            "the ADF regression \"{}\" with max_lag {}",
+            options.regression.as_str(),
+            max_lag
+        ),
+    )?;
+    let lags =
+        select_lag(series, max_lag, options.regression, options.autolag).ok_or_else(|| {
+            Error::new("lag selection failed: series too short for max_lag or degenerate")
+        })?;
+    let fit = adf_regression(series, lags, options.regression, CovarianceType::Classical)
+        .ok_or_else(|| {
+            Error::new(
+                "ADF regression failed: series too short for the requested lags or degenerate",
+            )
+        })?;
+    Ok((fit, max_lag))
+}
+
+// AdfResult of a configured fit, with the bootstrap and decision rule of `options` applied
+fn configured_result(
+    series: &[f64],
+    fit: &AdfFit,
+    options: &AdfOptions,
+) -> Result<AdfResult, Error> {
+    let bootstrap_p_value = if options.bootstrap > 0 {
+        adf_bootstrap_p_value(
+            series,
+            fit.used_lag,
+            fit.regression,
+            fit.statistic,
+            options.bootstrap,
+            options.seed,
+        )
+    } else {
+        None
+    };
+    adf_result_from_fit(fit)
+        .with_bootstrap(bootstrap_p_value)
+        .with_decision(options.significance, options.decision_rule)
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct AdfRegressionSummary {
+    result: AdfResult,
+    names: Vec<String>,
+    coefficients: Vec<f64>,
+    std_errors: Vec<f64>,
+    t_values: Vec<f64>,
+    lag_criteria: Vec<f64>,
+    lag_table: LagTable,
+    pub used_lag: usize,
+    pub max_lag: usize,
+    // Observations in the final regression, after the used lags are consumed
+    pub nobs: usize,
+    pub aic: f64,
+    pub bic: f64,
+    pub hqic: f64,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl AdfRegressionSummary {
+    // The test itself, as `adf_test_with_options` returns it
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn result(&self) -> AdfResult {
+        self.result.clone()
+    }
+
+    // Regressor names in column order: "y_lag1", "dy_lag1".."dy_lag{p}", then "const",
+    // "trend" and "trend_squared" as the regression includes them
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn names(&self) -> Vec<String> {
+        self.names.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn coefficients(&self) -> Vec<f64> {
+        self.coefficients.clone()
+    }
+
+    // Classical OLS standard errors
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn std_errors(&self) -> Vec<f64> {
+        self.std_errors.clone()
+    }
+
+    // Coefficient over standard error; the first one is the ADF statistic
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn t_values(&self) -> Vec<f64> {
+        self.t_values.clone()
+    }
+
+    // AIC or BIC of each candidate lag 0..=max_lag on the common sample, as the autolag search
+    // compared them (NaN where a fit failed); empty with a fixed lag or "t-stat"
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn lag_criteria(&self) -> Vec<f64> {
+        self.lag_criteria.clone()
+    }
+
+    // Every candidate lag with all its criteria, whatever the autolag rule
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn lag_table(&self) -> LagTable {
+        self.lag_table.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("result", RawJson(self.result.to_json()))
+            .field("names", &self.names)
+            .field("coefficients", &self.coefficients)
+            .field("std_errors", &self.std_errors)
+            .field("t_values", &self.t_values)
+            .field("lag_criteria", &self.lag_criteria)
+            .field("lag_table", RawJson(self.lag_table.to_json()))
+            .field("used_lag", self.used_lag)
+            .field("max_lag", self.max_lag)
+            .field("nobs", self.nobs)
+            .field("aic", self.aic)
+            .field("bic", self.bic)
+            .field("hqic", self.hqic)
+            .finish()
+    }
+}
+
+/// Lag-order diagnostics of an ADF test: each candidate lag 0..=max_lag fitted on the sample
+/// left after max_lag differences, as the autolag search compares them.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug)]
+pub struct LagTable {
+    lags: Vec<u32>,
+    aic: Vec<f64>,
+    bic: Vec<f64>,
+    hqic: Vec<f64>,
+    last_lag_t: Vec<f64>,
+    statistics: Vec<f64>,
+    // Observations of the common sample
+    pub nobs: usize,
+    // Lag the test used
+    pub selected: usize,
+    // Lag each rule picks from the table; undefined where every fit failed
+    pub aic_lag: Option<usize>,
+    pub bic_lag: Option<usize>,
+    pub t_stat_lag: Option<usize>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl LagTable {
+    // Candidate lags 0..=max_lag, the row order of every column
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn lags(&self) -> Vec<u32> {
+        self.lags.clone()
+    }
+
+    // NaN where the fit failed, as in every column
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn aic(&self) -> Vec<f64> {
+        self.aic.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn bic(&self) -> Vec<f64> {
+        self.bic.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn hqic(&self) -> Vec<f64> {
+        self.hqic.clone()
+    }
+
+    // t-ratio of the last lagged difference, the "t-stat" rule's criterion; NaN at lag 0
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn last_lag_t(&self) -> Vec<f64> {
+        self.last_lag_t.clone()
+    }
+
+    // ADF statistic at each lag on the common sample, to show how much the choice matters
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn statistics(&self) -> Vec<f64> {
+        self.statistics.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("lags", &self.lags)
+            .field("aic", &self.aic)
+            .field("bic", &self.bic)
+            .field("hqic", &self.hqic)
+            .field("last_lag_t", &self.last_lag_t)
+            .field("statistics", &self.statistics)
+            .field("nobs", self.nobs)
+            .field("selected", self.selected)
+            .field("aic_lag", self.aic_lag)
+            .field("bic_lag", self.bic_lag)
+            .field("t_stat_lag", self.t_stat_lag)
+            .finish()
+    }
+}
+
+/// ADF test configured like `adf_test_with_options`, with the full output of the final
+/// regression: every coefficient with its standard error and t-value, the observations used,
+/// the selected and maximum lag, AIC, BIC and HQIC of the final fit (statsmodels' `OLS`
+/// definitions), the criterion of every lag the `autolag` search compared, and `lag_table`
+/// with AIC, BIC, HQIC and the last lag's t-ratio of every candidate lag, so a plot can show
+/// why the lag was chosen and a caller can override it with `max_lag` and no `autolag`. Meant for
+/// reconciling results with statsmodels' `adfuller(..., regresults=True)`, whose `resols`
+/// is the same final regression.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn adf_summary(series: &[f64], options: JsValue) -> Result<AdfRegressionSummary, Error> {
+    adf_summary_with_config(series, &AdfOptions::from_js(&options)?)
+}
+
+/// `adf_summary` with the options as an `AdfOptions` object.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn adf_summary_with_config(
+    series: &[f64],
+    options: &AdfOptions,
+) -> Result<AdfRegressionSummary, Error> {
+    let cleaned = clean_series_with(series, options.missing)?;
+    let series = cleaned.as_slice();
+    let (fit, max_lag) = configured_fit(series, options)?;
+    let result = configured_result(series, &fit, options)?;
+
+    let (columns, y) = adf_design(series, fit.used_lag, fit.used_lag, fit.regression)
+        .ok_or_else(|| Error::new("ADF regression failed: series too short"))?;
+    let ols = least_squares(&columns, &y)
+        .ok_or_else(|| Error::new("ADF regression failed: regression is degenerate"))?;
+    let mut names = vec!["y_lag1".to_string()];
+    names.extend((1..=fit.used_lag).map(|i| format!("dy_lag{}", i)));
+    names.extend(
+        ["const", "trend", "trend_squared"][..fit.regression.deterministic_terms()]
+            .iter()
+            .map(|name| name.to_string()),
+    );
+    let std_errors: Vec<f64> = (0..ols.n_params()).map(|j| ols.std_error(j)).collect();
+    let t_values = ols
+        .coefficients
+        .iter()
+        .zip(&std_errors)
+        .map(|(b, se)| b / se)
+        .collect();
+    let [aic, bic, hqic] = information_criteria(&ols);
+    let lag_criteria = match options.autolag {
+        LagSelection::Aic | LagSelection::Bic => {
+            lag_criteria(series, max_lag, options.regression, options.autolag)
+        }
+        _ => Vec::new(),
+    };
+
+    Ok(AdfRegressionSummary {
+        result,
+        names,
+        coefficients: ols.coefficients.clone(),
+        std_errors,
+        t_values,
+        lag_criteria,
+        lag_table: lag_table(series, max_lag, options.regression, fit.used_lag),
+        used_lag: fit.used_lag,
+        max_lag,
+        nobs: ols.nobs,
+        aic,
+        bic,
+        hqic,
+    })
+}
+
+/// Options of `adf_test_with_config`; the default matches an empty options object.
+///
+/// From JS, `new AdfOptions(options)` reads the plain object `adf_test_with_options` takes, and
+/// each `with_*` method validates and sets one option. The `with_*` methods consume the options
+/// they are called on and return the updated ones, so chain them rather than reusing the
+/// original.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug)]
+pub struct AdfOptions {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub max_lag: Option<usize>,
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub autolag: LagSelection,
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub regression: Regression,
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub significance: f64,
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub decision_rule: DecisionRule,
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub missing: MissingPolicy,
+    // Bootstrap replications; 0 disables the bootstrap
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub bootstrap: usize,
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub seed: u32,
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub compatibility: Compatibility,
+}
+
+impl Default for AdfOptions {
+    fn default() -> AdfOptions {
+        AdfOptions {
+            max_lag: None,
+            autolag: LagSelection::Aic,
+            regression: Regression::Constant,
+            significance: 0.05,
+            decision_rule: DecisionRule::Both,
+            missing: MissingPolicy::Raise,
+            bootstrap: 0,
+            seed: DEFAULT_SEED,
+            compatibility: Compatibility::Native,
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl AdfOptions {
+    /// Options read from a plain object with the fields `adf_test_with_options` documents;
+    /// undefined or null gives the defaults.
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(constructor)]
+    pub fn new(options: JsValue) -> Result<AdfOptions, Error> {
+        AdfOptions::from_js(&options)
+    }
+
+    /// Largest number of lagged differences considered; None for Schwert's rule.
+    pub fn with_max_lag(mut self, max_lag: Option<usize>) -> AdfOptions {
+        self.max_lag = max_lag;
+        self
+    }
+
+    /// Lag search "AIC", "BIC" or "t-stat", or None to use `max_lag` as given.
+    pub fn with_autolag(mut self, autolag: Option<String>) -> Result<AdfOptions, Error> {
+        self.autolag = match autolag {
+            Some(name) => LagSelection::parse(&name)?,
+            None => LagSelection::Fixed,
+        };
+        Ok(self)
+    }
+
+    /// Deterministic terms "n", "c", "ct" or "ctt".
+    pub fn with_regression(mut self, regression: &str) -> Result<AdfOptions, Error> {
+        self.regression = Regression::parse(regression)?;
+        Ok(self)
+    }
+
+    /// Level in (0, 1) at which `is_stationary` is decided.
+    pub fn with_significance(mut self, significance: f64) -> Result<AdfOptions, Error> {
+        if !(significance > 0.0 && significance < 1.0) {
+            return Err(Error::new("significance must be between 0 and 1"));
+        }
+        self.significance = significance;
+        Ok(self)
+    }
+
+    /// Decision rule "p_value", "critical_value" or "both".
+    pub fn with_decision_rule(mut self, decision_rule: &str) -> Result<AdfOptions, Error> {
+        self.decision_rule = DecisionRule::parse(decision_rule)?;
+        Ok(self)
+    }
+
+    /// Missing-data policy "raise", "drop", "ffill" or "interpolate".
+    pub fn with_missing(mut self, missing: &str) -> Result<AdfOptions, Error> {
+        self.missing = MissingPolicy::parse(missing)?;
+        Ok(self)
+    }
+
+    /// Sieve-bootstrap replications, 0 for none.
+    pub fn with_bootstrap(mut self, bootstrap: usize) -> AdfOptions {
+        self.bootstrap = bootstrap;
+        self
+    }
+
+    /// Seed of the bootstrap.
+    pub fn with_seed(mut self, seed: u32) -> AdfOptions {
+        self.seed = seed;
+        self
+    }
+
+    /// Conventions "native" or "statsmodels".
+    pub fn with_compatibility(mut self, compatibility: &str) -> Result<AdfOptions, Error> {
+        self.compatibility = Compatibility::parse(compatibility)?;
+        Ok(self)
+    }
+}
+
+impl AdfOptions {
+    #[cfg(feature = "wasm")]
+    pub(crate) fn from_js(options: &JsValue) -> Result<AdfOptions, Error> {
+        let mut parsed = AdfOptions::default();
+        if options.is_undefined() || options.is_null() {
+            return Ok(parsed);
+        }
+        if !options.is_object() {
+            return Err(Error::new("options must be an object"));
+        }
+
+        let max_lag = get_property(options, "max_lag")?;
+        if !max_lag.is_undefined() && !max_lag.is_null() {
+            let value = max_lag
+                .as_f64()
+                .filter(|v| *v >= 0.0 && v.fract() == 0.0)
+                .ok_or_else(|| Error::new("max_lag must be a non-negative integer"))?;
+            parsed = parsed.with_max_lag(Some(value as usize));
+        }
+
+        let autolag = get_property(options, "autolag")?;
+        if autolag.is_null() {
+            parsed = parsed.with_autolag(None)?;
+        } else if !autolag.is_undefined() {
+            let name = autolag
+                .as_string()
+                .ok_or_else(|| Error::new("autolag must be a string or null"))?;
+            parsed = parsed.with_autolag(Some(name))?;
+        }
+
+        let regression = get_property(options, "regression")?;
+        if !regression.is_undefined() {
+            let name = regression
+                .as_string()
+                .ok_or_else(|| Error::new("regression must be a string"))?;
+            parsed = parsed.with_regression(&name)?;
+        }
+
+        let significance = get_property(options, "significance")?;
+        if !significance.is_undefined() {
+            let value = significance
+                .as_f64()
+                .ok_or_else(|| Error::new("significance must be a number"))?;
+            parsed = parsed.with_significance(value)?;
+        }
+
+        let decision_rule = get_property(options, "decision_rule")?;
+        if !decision_rule.is_undefined() {
+            let name = decision_rule
+                .as_string()
+                .ok_or_else(|| Error::new("decision_rule must be a string"))?;
+            parsed = parsed.with_decision_rule(&name)?;
+        }
+
+        let missing = get_property(options, "missing")?;
+        if !missing.is_undefined() {
+            let name = missing
+                .as_string()
+                .ok_or_else(|| Error::new("missing must be a string"))?;
+            parsed = parsed.with_missing(&name)?;
+        }
+
+        let bootstrap = get_property(options, "bootstrap")?;
+        if !bootstrap.is_undefined() {
+            let value = bootstrap
+                .as_f64()
+                .filter(|v| *v >= 0.0 && v.fract() == 0.0)
+                .ok_or_else(|| Error::new("bootstrap must be a non-negative integer"))?;
+            parsed = parsed.with_bootstrap(value as usize);
+        }
+
+        let seed = get_property(options, "seed")?;
+        if !seed.is_undefined() {
+            parsed = parsed.with_seed(parse_seed(&seed)?);
+        }
+
+        let compatibility = get_property(options, "compatibility")?;
+        if !compatibility.is_undefined() {
+            let name = compatibility
+                .as_string()
+                .ok_or_else(|| Error::new("compatibility must be a string"))?;
+            parsed = parsed.with_compatibility(&name)?;
+        }
+        Ok(parsed)
+    }
+}
+
+// Schwert (1989) rule of thumb, capped as in statsmodels so that the regression keeps
+// at least half of the observations
+pub(crate) fn default_max_lag(len: usize, regression: Regression) -> usize {
+    let schwert = (12.0 * (len as f64 / 100.0).powf(0.25)).floor() as usize;
+    let cap = (len / 2).saturating_sub(regression.deterministic_terms() + 1);
+    schwert.min(cap)
+}
+
+// `adfuller`'s maxlag: ceil(12·(n/100)^(1/4)) capped at n/2 - terms - 1 by default, and an
+// error when a given maxlag exceeds that cap or the series is too short for any lag
+fn statsmodels_max_lag(
+    len: usize,
+    regression: Regression,
+    max_lag: Option<usize>,
+) -> Result<usize, Error> {
+    let cap = (len / 2)
+        .checked_sub(regression.deterministic_terms() + 1)
+        .ok_or_else(|| Error::new("series too short for the regression"))?;
+    match max_lag {
+        None => Ok(((12.0 * (len as f64 / 100.0).powf(0.25)).ceil() as usize).min(cap)),
+        Some(max_lag) if max_lag > cap => Err(Error::new(&format!(
+            "max_lag must be at most {} (n/2 - 1 - the number of deterministic terms)",
+            cap
+        ))),
+        Some(max_lag) => Ok(max_lag),
+    }
+}
+
+/// ADF test with exactly `lags` lagged differences and a selectable covariance estimator for
+/// the lagged-level coefficient: "nonrobust" (classical), "HC0" or "HC1" (White's
+/// heteroskedasticity-consistent standard errors, useful for spreads with volatility clustering)
+/// or "HAC" (Newey-West with an automatically selected lag truncation).
+///
+/// The robust variants change the statistic but the p-value and critical values still come
+/// from the standard Dickey-Fuller tables. That is only an approximation: the robust t-ratio
+/// does not follow the Dickey-Fuller distribution exactly, so treat borderline results with care.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn adf_test_robust(
+    series: &[f64],
+    lags: usize,
+    regression: &str,
+    cov_type: &str,
+) -> Result<AdfResult, Error> {
+    let regression = Regression::parse(regression)?;
+    let cov = CovarianceType::parse(cov_type)?;
+    let fit = adf_regression(series, lags, regression, cov).ok_or_else(|| {
+        Error::new("ADF regression failed: series too short for the requested lags or degenerate")
+    })?;
+    Ok(adf_result_from_fit(&fit))
+}
+
+/// Recommended minimum series length for an ADF-type test with `max_lag` lagged differences
+/// and the given deterministic specification ("n", "c", "ct" or "ctt").
+///
+/// Rule of thumb: ten observations per estimated coefficient plus a 20-observation buffer,
+/// which also keeps the regression inside the sample sizes the MacKinnon critical-value
+/// surfaces were calibrated on, plus the `max_lag + 1` observations lost to differencing and
+/// lagging. Shorter series still produce a statistic, but with little power.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn min_reliable_sample(max_lag: usize, regression: &str) -> Result<usize, Error> {
+    let deterministic_terms = Regression::parse(regression)?.deterministic_terms();
+    Ok(recommended_sample(max_lag, deterministic_terms))
+}
+
+pub(crate) fn recommended_sample(max_lag: usize, deterministic_terms: usize) -> usize {
+    let coefficients = 1 + max_lag + deterministic_terms;
+    20 + 10 * coefficients + max_lag + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::fixtures::STATSMODELS_SERIES;
+    use crate::rng::Rng;
+
+    // Stationary AR(1) whose shocks are five times larger in the second half
+    fn heteroskedastic_ar1(n: usize, seed: u32) -> Vec<f64> {
+        let mut rng = Rng::new(seed);
+        let mut s = vec![0.0; n];
+        for t in 1..n {
+            let scale = if t < n / 2 { 1.0 } else { 5.0 };
+            s[t] = 0.8 * s[t - 1] + scale * rng.normal();
+        }
+        s
+    }
+
+    #[test]
+    fn robust_and_classical_statistics_differ_under_heteroskedasticity() {
+        let series = heteroskedastic_ar1(400, 7);
+        let classical = adf_test_robust(&series, 1, "c", "nonrobust").unwrap();
+        let hc0 = adf_test_robust(&series, 1, "c", "HC0").unwrap();
+        let hc1 = adf_test_robust(&series, 1, "c", "HC1").unwrap();
+        assert!(classical.statistic.is_finite() && hc0.statistic.is_finite());
+        assert!(
+            (classical.statistic - hc0.statistic).abs() > 0.05 * classical.statistic.abs(),
+            "classical {} vs HC0 {}",
+            classical.statistic,
+            hc0.statistic
+        );
+        // HC1 only rescales HC0 by √((n - k) / n), so it is slightly smaller in magnitude
+        assert!(hc1.statistic.abs() < hc0.statistic.abs());
+        assert!((hc1.statistic / hc0.statistic - 1.0).abs() < 0.02);
+    }
+
+    #[test]
+    fn robust_and_classical_statistics_agree_under_homoskedasticity() {
+        let mut rng = Rng::new(8);
+        let mut series = vec![0.0; 4000];
+        for t in 1..series.len() {
+            series[t] = 0.8 * series[t - 1] + rng.normal();
+        }
+        let classical = adf_test_robust(&series, 1, "c", "nonrobust").unwrap();
+        let hc0 = adf_test_robust(&series, 1, "c", "HC0").unwrap();
+        assert!((classical.statistic / hc0.statistic - 1.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn min_reliable_sample_grows_with_lags_and_deterministic_terms() {
+        for regression in ["n", "c", "ct", "ctt"] {
+            let sizes: Vec<usize> = (0..=12)
+                .map(|lag| min_reliable_sample(lag, regression).unwrap())
+                .collect();
+            assert!(sizes.windows(2).all(|w| w[1] > w[0]), "{}", regression);
+        }
+        for lag in [0, 4, 12] {
+            let sizes: Vec<usize> = ["n", "c", "ct", "ctt"]
+                .iter()
+                .map(|regression| min_reliable_sample(lag, regression).unwrap())
+                .collect();
+            assert!(sizes.windows(2).all(|w| w[1] > w[0]), "lag {}", lag);
+        }
+        assert!(min_reliable_sample(1, "quadratic").is_err());
+    }
+
+    // statsmodels 0.14 `adfuller(STATSMODELS_SERIES, maxlag, regression, autolag)`, as printed
+    // by scripts/statsmodels_vectors.py. Until that script has been run against statsmodels,
+    // these are reproduced from `adfuller`'s algorithm in exact rational arithmetic: lag search
+    // on the common sample with ties to the shorter lag, refit at the chosen lag, then
+    // `mackinnonp` and `mackinnoncrit(nobs=nobs)`
+    struct AdfullerCase {
+        regression: &'static str,
+        // autolag="AIC" when set, autolag=None otherwise
+        autolag: bool,
+        max_lag: Option<usize>,
+        statistic: f64,
+        used_lag: usize,
+        nobs: usize,
+        p_value: f64,
+        critical_values: [f64; 3],
+    }
+
+    const STATSMODELS_ADFULLER: [AdfullerCase; 12] = [
+        AdfullerCase {
+            regression: "n",
+            autolag: false,
+            max_lag: Some(0),
+            statistic: -0.5234745355849006,
+            used_lag: 0,
+            nobs: 79,
+            p_value: 0.4864508204111365,
+            critical_values: [
+                -2.5946224226886714,
+                -1.9448758487665276,
+                -1.6138413966846434,
+            ],
+        },
+        AdfullerCase {
+            regression: "n",
+            autolag: false,
+            max_lag: Some(3),
+            statistic: -0.6823234476325396,
+            used_lag: 3,
+            nobs: 76,
+            p_value: 0.4200560038458614,
+            critical_values: [-2.595786364265928, -1.945045666733489, -1.613737358579968],
+        },
+        AdfullerCase {
+            regression: "n",
+            autolag: true,
+            max_lag: None,
+            statistic: -0.7500643969124903,
+            used_lag: 5,
+            nobs: 74,
+            p_value: 0.3915170719462493,
+            critical_values: [-2.5966158582907233, -1.9451671781533177, -1.613663835508262],
+        },
+        AdfullerCase {
+            regression: "c",
+            autolag: false,
+            max_lag: Some(0),
+            statistic: -2.307992019231091,
+            used_lag: 0,
+            nobs: 79,
+            p_value: 0.16940334078128344,
+            critical_values: [-3.5159766913976376, -2.898885703483903, -2.5866935058484217],
+        },
+        AdfullerCase {
+            regression: "c",
+            autolag: false,
+            max_lag: Some(3),
+            statistic: -2.751304353721585,
+            used_lag: 3,
+            nobs: 76,
+            p_value: 0.06557328188772545,
+            critical_values: [
+                -3.5194805351545413,
+                -2.9003945086747343,
+                -2.5874984279778395,
+            ],
+        },
+        AdfullerCase {
+            regression: "c",
+            autolag: true,
+            max_lag: None,
+            statistic: -2.001727886053456,
+            used_lag: 5,
+            nobs: 74,
+            p_value: 0.2858159956525105,
+            critical_values: [-3.5219803175527606, -2.9014701097664504, -2.58807215485756],
+        },
+        AdfullerCase {
+            regression: "ct",
+            autolag: false,
+            max_lag: Some(0),
+            statistic: -2.291485810270878,
+            used_lag: 0,
+            nobs: 79,
+            p_value: 0.438664910939223,
+            critical_values: [-4.078193339938625, -3.467604557671908, -3.160453411494831],
+        },
+        AdfullerCase {
+            regression: "ct",
+            autolag: false,
+            max_lag: Some(3),
+            statistic: -2.7279553606293736,
+            used_lag: 3,
+            nobs: 76,
+            p_value: 0.22465952892727517,
+            critical_values: [-4.083117090957137, -3.4699261887665838, -3.161801570928707],
+        },
+        AdfullerCase {
+            regression: "ct",
+            autolag: true,
+            max_lag: None,
+            statistic: -1.991237110231573,
+            used_lag: 5,
+            nobs: 74,
+            p_value: 0.6061991293163337,
+            critical_values: [-4.0866316335656325, -3.4715818119361144, -3.162762533314907],
+        },
+        AdfullerCase {
+            regression: "ctt",
+            autolag: false,
+            max_lag: Some(0),
+            statistic: -2.330649550491537,
+            used_lag: 0,
+            nobs: 79,
+            p_value: 0.6634627533682311,
+            critical_values: [-4.52423290707226, -3.909386886047554, -3.6048366097610938],
+        },
+        AdfullerCase {
+            regression: "ctt",
+            autolag: false,
+            max_lag: Some(3),
+            statistic: -2.8609848003456517,
+            used_lag: 3,
+            nobs: 76,
+            p_value: 0.3653676058912867,
+            critical_values: [-4.5305686349139815, -3.912528429435778, -3.606915966613209],
+        },
+        AdfullerCase {
+            regression: "ctt",
+            autolag: true,
+            max_lag: None,
+            statistic: -2.301645274400419,
+            used_lag: 5,
+            nobs: 74,
+            p_value: 0.6787895181785621,
+            critical_values: [-4.535092737152785, -3.914769516514323, -3.6083981053442047],
+        },
+    ];
+
+    // `adfuller`'s default maxlag at each of these lengths, or None where it raises "sample size
+    // is too short", also printed by scripts/statsmodels_vectors.py
+    const MAX_LAG_LENGTHS: [usize; 15] =
+        [3, 4, 5, 6, 7, 8, 10, 20, 50, 99, 100, 101, 250, 1000, 10000];
+    const STATSMODELS_MAX_LAG: [(&str, [Option<usize>; 15]); 4] = [
+        (
+            "n",
+            [
+                Some(0),
+                Some(1),
+                Some(1),
+                Some(2),
+                Some(2),
+                Some(3),
+                Some(4),
+                Some(9),
+                Some(11),
+                Some(12),
+                Some(12),
+                Some(13),
+                Some(16),
+                Some(22),
+                Some(38),
+            ],
+        ),
+        (
+            "c",
+            [
+                None,
+                Some(0),
+                Some(0),
+                Some(1),
+                Some(1),
+                Some(2),
+                Some(3),
+                Some(8),
+                Some(11),
+                Some(12),
+                Some(12),
+                Some(13),
+                Some(16),
+                Some(22),
+                Some(38),
+            ],
+        ),
+        (
+            "ct",
+            [
+                None,
+                None,
+                None,
+                Some(0),
+                Some(0),
+                Some(1),
+                Some(2),
+                Some(7),
+                Some(11),
+                Some(12),
+                Some(12),
+                Some(13),
+                Some(16),
+                Some(22),
+                Some(38),
+            ],
+        ),
+        (
+            "ctt",
+            [
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(0),
+                Some(1),
+                Some(6),
+                Some(11),
+                Some(12),
+                Some(12),
+                Some(13),
+                Some(16),
+                Some(22),
+                Some(38),
+            ],
+        ),
+    ];
+
+    fn statsmodels_options(regression: &str, autolag: bool, max_lag: Option<usize>) -> AdfOptions {
+        AdfOptions::default()
+            .with_max_lag(max_lag)
+            .with_autolag(autolag.then(|| "AIC".to_string()))
+            .unwrap()
+            .with_regression(regression)
+            .unwrap()
+            .with_compatibility("statsmodels")
+            .unwrap()
+    }
+
+    #[test]
+    fn statsmodels_compatibility_matches_adfuller() {
+        for expected in STATSMODELS_ADFULLER {
+            let options =
+                statsmodels_options(expected.regression, expected.autolag, expected.max_lag);
+            let result = adf_test_with_config(&STATSMODELS_SERIES, &options).unwrap();
+            let case = format!(
+                "{} autolag={} maxlag={:?}",
+                expected.regression, expected.autolag, expected.max_lag
+            );
+            assert_eq!(result.used_lag(), Some(expected.used_lag), "{}", case);
+            assert_eq!(result.nobs(), Some(expected.nobs), "{}", case);
+            assert!(
+                (result.statistic - expected.statistic).abs() < 1e-9,
+                "{}: {}",
+                case,
+                result.statistic
+            );
+            assert!(
+                (result.p_value - expected.p_value).abs() < 1e-9,
+                "{}: {}",
+                case,
+                result.p_value
+            );
+            for (got, want) in result
+                .critical_values_array()
+                .iter()
+                .zip(expected.critical_values)
+            {
+                assert!((got - want).abs() < 1e-12, "{}: {} vs {}", case, got, want);
+            }
+        }
+    }
+
+    #[test]
+    fn statsmodels_max_lag_matches_adfuller() {
+        for (regression, expected) in STATSMODELS_MAX_LAG {
+            let parsed = Regression::parse(regression).unwrap();
+            for (len, expected) in MAX_LAG_LENGTHS.into_iter().zip(expected) {
+                let got = statsmodels_max_lag(len, parsed, None).ok();
+                assert_eq!(got, expected, "{} with {} observations", regression, len);
+            }
+        }
+
+        // A given maxlag is accepted up to n/2 - 1 - terms and rejected above it
+        let cap = 80 / 2 - 1 - 1;
+        assert_eq!(
+            statsmodels_max_lag(80, Regression::Constant, Some(cap)),
+            Ok(cap)
+        );
+        assert!(statsmodels_max_lag(80, Regression::Constant, Some(cap + 1)).is_err());
+        let too_long = statsmodels_options("c", false, Some(cap + 1));
+        assert!(adf_test_with_config(&STATSMODELS_SERIES, &too_long).is_err());
+
+        // The native default rounds Schwert's rule down instead
+        assert_eq!(default_max_lag(80, Regression::Constant), 11);
+        assert_eq!(statsmodels_max_lag(80, Regression::Constant, None), Ok(12));
+    }
+}
@@ -0,0 +1,154 @@
+// Timestamp alignment of two price series.
+//
+// The tests pair observations by position, so two legs sampled on different
+// calendars (holidays, missing bars, different exchanges) must first be joined on
+// their timestamps. Pairing misaligned bars shifts one leg against the other and
+// turns a cointegrated pair into noise.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::error::Error;
+use crate::json::JsonObject;
+use crate::series::Series;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct AlignedPair {
+    timestamps: Vec<f64>,
+    y: Vec<f64>,
+    x: Vec<f64>,
+    // Observations of each leg without a partner in the other
+    pub unmatched_y: usize,
+    pub unmatched_x: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl AlignedPair {
+    // Timestamp of each matched pair, taken from the y leg
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn timestamps(&self) -> Vec<f64> {
+        self.timestamps.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn y(&self) -> Vec<f64> {
+        self.y.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn x(&self) -> Vec<f64> {
+        self.x.clone()
+    }
+
+    /// The y leg on the matched timestamps, as a `Series`.
+    pub fn y_series(&self) -> Series {
+        Series::from_parts(self.timestamps.clone(), self.y.clone())
+    }
+
+    /// The x leg on the matched timestamps (those of the y leg), as a `Series`.
+    pub fn x_series(&self) -> Series {
+        Series::from_parts(self.timestamps.clone(), self.x.clone())
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("timestamps", &self.timestamps)
+            .field("y", &self.y)
+            .field("x", &self.x)
+            .field("unmatched_y", self.unmatched_y)
+            .field("unmatched_x", self.unmatched_x)
+            .finish()
+    }
+}
+
+impl AlignedPair {
+    // The aligned legs without copying, for native callers
+    pub fn as_slices(&self) -> (&[f64], &[f64]) {
+        (&self.y, &self.x)
+    }
+}
+
+/// Inner join of two (timestamp, price) series on their timestamps.
+///
+/// Timestamps are numbers in any unit (typically epoch milliseconds) and must be strictly
+/// increasing within each series. Observations pair up when their timestamps differ by at most
+/// `tolerance` (default 0, exact matches only); each observation is used at most once and, within
+/// the tolerance, goes to its nearest partner. Unmatched observations are dropped and counted.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn align_series(
+    y_timestamps: &[f64],
+    y: &[f64],
+    x_timestamps: &[f64],
+    x: &[f64],
+    tolerance: Option<f64>,
+) -> Result<AlignedPair, Error> {
+    let tolerance = tolerance.unwrap_or(0.0);
+    if !(tolerance >= 0.0 && tolerance.is_finite()) {
+        return Err(Error::new("tolerance must be non-negative and finite"));
+    }
+    check_series(y_timestamps, y, "y")?;
+    check_series(x_timestamps, x, "x")?;
+
+    let gap = |i: usize, j: usize| (y_timestamps[i] - x_timestamps[j]).abs();
+    let mut aligned = AlignedPair {
+        timestamps: Vec::new(),
+        y: Vec::new(),
+        x: Vec::new(),
+        unmatched_y: 0,
+        unmatched_x: 0,
+    };
+    let (mut i, mut j) = (0, 0);
+    while i < y.len() && j < x.len() {
+        if gap(i, j) <= tolerance {
+            // Leave this pair to a closer partner of either side if there is one
+            if j + 1 < x.len() && gap(i, j + 1) < gap(i, j) {
+                j += 1;
+                aligned.unmatched_x += 1;
+                continue;
+            }
+            if i + 1 < y.len() && gap(i + 1, j) < gap(i, j) {
+                i += 1;
+                aligned.unmatched_y += 1;
+                continue;
+            }
+            aligned.timestamps.push(y_timestamps[i]);
+            aligned.y.push(y[i]);
+            aligned.x.push(x[j]);
+            i += 1;
+            j += 1;
+        } else if y_timestamps[i] < x_timestamps[j] {
+            i += 1;
+            aligned.unmatched_y += 1;
+        } else {
+            j += 1;
+            aligned.unmatched_x += 1;
+        }
+    }
+    aligned.unmatched_y += y.len() - i;
+    aligned.unmatched_x += x.len() - j;
+    Ok(aligned)
+}
+
+// Equal lengths, finite timestamps and strictly increasing timestamps
+pub(crate) fn check_series(timestamps: &[f64], values: &[f64], name: &str) -> Result<(), Error> {
+    if timestamps.len() != values.len() {
+        return Err(Error::new(&format!(
+            "{} has {} timestamps but {} values",
+            name,
+            timestamps.len(),
+            values.len()
+        )));
+    }
+    if timestamps.iter().any(|t| !t.is_finite()) {
+        return Err(Error::new(&format!("{} timestamps must be finite", name)));
+    }
+    if let Some(i) = timestamps.windows(2).position(|w| w[1] <= w[0]) {
+        return Err(Error::new(&format!(
+            "{} timestamps must be strictly increasing (index {})",
+            name,
+            i + 1
+        )));
+    }
+    Ok(())
+}
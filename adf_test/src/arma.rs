@@ -0,0 +1,482 @@
+// ARMA(p, q) models of a spread, fitted by conditional sum of squares.
+//
+//   (y_t - μ) = Σ_{i=1..p} φ_i·(y_{t-i} - μ) + ε_t + Σ_{j=1..q} θ_j·ε_{t-j}
+//
+// A spread that is stationary but reverts slowly, or with overshoot, is described
+// better by a low-order ARMA than by the AR(1) behind the half-life, and the
+// fitted model forecasts the path back to the mean with its uncertainty. The
+// residuals are computed recursively from the (p+1)-th observation with the
+// pre-sample shocks set to zero, and their sum of squares is minimised by
+// Levenberg-Marquardt from Hannan-Rissanen starting values.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::error::Error;
+use crate::json::JsonObject;
+use crate::linalg::{cholesky, cholesky_solve, least_squares};
+use crate::state::{StateKind, StateReader, StateWriter};
+use crate::stats::{nanmean, NanPolicy};
+use crate::validate::{require_finite, require_length, require_varying};
+
+const MAX_ORDER: usize = 3;
+const MAX_ITERATIONS: usize = 200;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct ArmaModel {
+    ar: Vec<f64>,
+    ma: Vec<f64>,
+    // 0 without a mean
+    pub mean: f64,
+    std_errors: Vec<f64>,
+    residuals: Vec<f64>,
+    // Innovation variance, the sum of squared residuals over nobs
+    pub sigma2: f64,
+    // Conditional Gaussian log-likelihood of the residuals
+    pub log_likelihood: f64,
+    pub aic: f64,
+    pub bic: f64,
+    // Residuals in the sum of squares, n - p
+    pub nobs: usize,
+    // Whether the AR polynomial has all its roots outside the unit circle
+    pub is_stationary: bool,
+    // Whether the MA polynomial has all its roots outside the unit circle
+    pub is_invertible: bool,
+    // Last p observations and last q residuals, oldest first, for forecasting
+    recent_values: Vec<f64>,
+    recent_residuals: Vec<f64>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ArmaModel {
+    // φ_1..φ_p
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn ar(&self) -> Vec<f64> {
+        self.ar.clone()
+    }
+
+    // θ_1..θ_q
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn ma(&self) -> Vec<f64> {
+        self.ma.clone()
+    }
+
+    // Standard errors of the mean (when estimated), then φ_1..φ_p, then θ_1..θ_q, from the
+    // Gauss-Newton approximation of the information matrix
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn std_errors(&self) -> Vec<f64> {
+        self.std_errors.clone()
+    }
+
+    // One-step-ahead residuals aligned with the series; the first p are NaN
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn residuals(&self) -> Vec<f64> {
+        self.residuals.clone()
+    }
+
+    /// Forecasts of the next `horizon` values after the end of the series, with future shocks
+    /// set to zero. For a stationary model they decay towards `mean`.
+    pub fn forecast(&self, horizon: usize) -> Vec<f64> {
+        let p = self.ar.len();
+        let q = self.ma.len();
+        let mut values: Vec<f64> = self.recent_values.iter().map(|v| v - self.mean).collect();
+        let mut shocks = self.recent_residuals.clone();
+        let mut forecasts = Vec::with_capacity(horizon);
+        for _ in 0..horizon {
+            let next = (0..p)
+                .map(|i| self.ar[i] * values[values.len() - 1 - i])
+                .sum::<f64>()
+                + (0..q)
+                    .map(|j| self.ma[j] * shocks[shocks.len() - 1 - j])
+                    .sum::<f64>();
+            values.push(next);
+            shocks.push(0.0);
+            forecasts.push(next + self.mean);
+        }
+        forecasts
+    }
+
+    /// Standard errors of `forecast` at each step, σ·√(Σ_{j<h} ψ_j²) with ψ the model's
+    /// moving-average weights; they ignore the uncertainty of the estimated coefficients.
+    pub fn forecast_std_errors(&self, horizon: usize) -> Vec<f64> {
+        let mut psi: Vec<f64> = Vec::with_capacity(horizon);
+        let mut cumulative = 0.0;
+        let mut std_errors = Vec::with_capacity(horizon);
+        for j in 0..horizon {
+            let weight = if j == 0 {
+                1.0
+            } else {
+                self.ma.get(j - 1).copied().unwrap_or(0.0)
+                    + (1..=self.ar.len().min(j))
+                        .map(|i| self.ar[i - 1] * psi[j - i])
+                        .sum::<f64>()
+            };
+            psi.push(weight);
+            cumulative += weight * weight;
+            std_errors.push((self.sigma2 * cumulative).sqrt());
+        }
+        std_errors
+    }
+
+    /// The fitted model, residuals included, as a compact byte buffer that
+    /// `ArmaModel.deserialize` turns back into the same model without refitting.
+    pub fn serialize(&self) -> Vec<u8> {
+        StateWriter::new(StateKind::ArmaModel)
+            .f64s(&self.ar)
+            .f64s(&self.ma)
+            .f64(self.mean)
+            .f64s(&self.std_errors)
+            .f64s(&self.residuals)
+            .f64(self.sigma2)
+            .f64(self.log_likelihood)
+            .f64(self.aic)
+            .f64(self.bic)
+            .usize(self.nobs)
+            .bool(self.is_stationary)
+            .bool(self.is_invertible)
+            .f64s(&self.recent_values)
+            .f64s(&self.recent_residuals)
+            .finish()
+    }
+
+    /// A model restored from the bytes of `serialize`.
+    pub fn deserialize(bytes: &[u8]) -> Result<ArmaModel, Error> {
+        let mut reader = StateReader::new(bytes, StateKind::ArmaModel)?;
+        let model = ArmaModel {
+            ar: reader.f64s()?,
+            ma: reader.f64s()?,
+            mean: reader.f64()?,
+            std_errors: reader.f64s()?,
+            residuals: reader.f64s()?,
+            sigma2: reader.f64()?,
+            log_likelihood: reader.f64()?,
+            aic: reader.f64()?,
+            bic: reader.f64()?,
+            nobs: reader.usize()?,
+            is_stationary: reader.bool()?,
+            is_invertible: reader.bool()?,
+            recent_values: reader.f64s()?,
+            recent_residuals: reader.f64s()?,
+        };
+        // `forecast` reads p past values and q past shocks
+        if model.ar.len() > MAX_ORDER
+            || model.ma.len() > MAX_ORDER
+            || model.recent_values.len() != model.ar.len()
+            || model.recent_residuals.len() != model.ma.len()
+        {
+            return Err(reader.invalid());
+        }
+        reader.finish()?;
+        Ok(model)
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("ar", &self.ar)
+            .field("ma", &self.ma)
+            .field("mean", self.mean)
+            .field("std_errors", &self.std_errors)
+            .field("residuals", &self.residuals)
+            .field("sigma2", self.sigma2)
+            .field("log_likelihood", self.log_likelihood)
+            .field("aic", self.aic)
+            .field("bic", self.bic)
+            .field("nobs", self.nobs)
+            .field("is_stationary", self.is_stationary)
+            .field("is_invertible", self.is_invertible)
+            .finish()
+    }
+}
+
+/// Fit an ARMA(`p`, `q`) model with `p` and `q` at most 3 to `series` by conditional sum of
+/// squares, with a mean unless `include_mean` is false.
+///
+/// Starting values come from the Hannan-Rissanen regression on the residuals of a long AR fit,
+/// refined by Levenberg-Marquardt, so with q = 0 the result is the OLS autoregression. The
+/// information criteria count every coefficient plus the innovation variance, as statsmodels'
+/// `ARIMA` does; the estimates are close to but not identical with its exact maximum
+/// likelihood. Check `is_stationary` and `is_invertible` before trusting a forecast, since the
+/// sum of squares does not constrain the roots.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn fit_arma(
+    series: &[f64],
+    p: usize,
+    q: usize,
+    include_mean: Option<bool>,
+) -> Result<ArmaModel, Error> {
+    if p > MAX_ORDER || q > MAX_ORDER {
+        return Err(Error::new("p and q must be at most 3"));
+    }
+    require_finite(series, "series")?;
+    let include_mean = include_mean.unwrap_or(true);
+    let k = usize::from(include_mean) + p + q;
+    require_length(
+        series,
+        p + 2 * k + 10,
+        "series",
+        &format!("an ARMA({}, {}) fit", p, q),
+    )?;
+    require_varying(series, "series")?;
+
+    let spec = Spec {
+        series,
+        p,
+        q,
+        include_mean,
+    };
+    let mut params = spec.starting_values();
+    let mut ssr = spec.sum_of_squares(&params);
+    if !ssr.is_finite() {
+        params = spec.neutral_values();
+        ssr = spec.sum_of_squares(&params);
+    }
+    if !ssr.is_finite() {
+        return Err(Error::new(
+            "ARMA residuals diverge for every starting value",
+        ));
+    }
+
+    let mut lambda = 1e-3;
+    for _ in 0..MAX_ITERATIONS {
+        let (jacobian, residuals) = spec.jacobian(&params);
+        let (a, g) = normal_equations(&jacobian, &residuals);
+        let mut improved = false;
+        while lambda < 1e10 {
+            let mut damped = a.clone();
+            for i in 0..k {
+                damped[i * k + i] += lambda * a[i * k + i].max(1e-12);
+            }
+            let Some(l) = cholesky(&damped, k) else {
+                lambda *= 10.0;
+                continue;
+            };
+            let step = cholesky_solve(&l, k, &g);
+            let candidate: Vec<f64> = params.iter().zip(&step).map(|(b, d)| b - d).collect();
+            let candidate_ssr = spec.sum_of_squares(&candidate);
+            if candidate_ssr < ssr {
+                let gain = (ssr - candidate_ssr) / ssr;
+                params = candidate;
+                ssr = candidate_ssr;
+                lambda = (lambda / 10.0).max(1e-12);
+                improved = gain > 1e-12;
+                break;
+            }
+            lambda *= 10.0;
+        }
+        if !improved {
+            break;
+        }
+    }
+    spec.model(&params, ssr)
+}
+
+// A model specification together with the series it is fitted to
+struct Spec<'a> {
+    series: &'a [f64],
+    p: usize,
+    q: usize,
+    include_mean: bool,
+}
+
+impl Spec<'_> {
+    fn n_params(&self) -> usize {
+        usize::from(self.include_mean) + self.p + self.q
+    }
+
+    // (μ, φ, θ) from a parameter vector
+    fn split<'b>(&self, params: &'b [f64]) -> (f64, &'b [f64], &'b [f64]) {
+        let offset = usize::from(self.include_mean);
+        let mean = if self.include_mean { params[0] } else { 0.0 };
+        (
+            mean,
+            &params[offset..offset + self.p],
+            &params[offset + self.p..],
+        )
+    }
+
+    // Residuals e_t for t = p..n, or None once they overflow
+    fn residuals(&self, params: &[f64]) -> Option<Vec<f64>> {
+        let (mean, ar, ma) = self.split(params);
+        let y = self.series;
+        let mut residuals: Vec<f64> = Vec::with_capacity(y.len() - self.p);
+        for t in self.p..y.len() {
+            let mut e = y[t] - mean;
+            for (i, phi) in ar.iter().enumerate() {
+                e -= phi * (y[t - 1 - i] - mean);
+            }
+            for (j, theta) in ma.iter().enumerate() {
+                if let Some(previous) = residuals.len().checked_sub(j + 1) {
+                    e -= theta * residuals[previous];
+                }
+            }
+            if !e.is_finite() || e.abs() > 1e150 {
+                return None;
+            }
+            residuals.push(e);
+        }
+        Some(residuals)
+    }
+
+    fn sum_of_squares(&self, params: &[f64]) -> f64 {
+        self.residuals(params)
+            .map_or(f64::INFINITY, |e| e.iter().map(|v| v * v).sum())
+    }
+
+    // Forward-difference Jacobian of the residuals, by column, with the residuals themselves
+    fn jacobian(&self, params: &[f64]) -> (Vec<Vec<f64>>, Vec<f64>) {
+        let base = self.residuals(params).unwrap_or_default();
+        let columns = (0..params.len())
+            .map(|i| {
+                let h = 1e-7 * params[i].abs().max(1.0);
+                let mut shifted = params.to_vec();
+                shifted[i] += h;
+                match self.residuals(&shifted) {
+                    Some(e) => e.iter().zip(&base).map(|(a, b)| (a - b) / h).collect(),
+                    None => vec![0.0; base.len()],
+                }
+            })
+            .collect();
+        (columns, base)
+    }
+
+    // Sample mean and zero coefficients
+    fn neutral_values(&self) -> Vec<f64> {
+        let mut params = vec![0.0; self.n_params()];
+        if self.include_mean {
+            params[0] = nanmean(self.series, NanPolicy::Propagate);
+        }
+        params
+    }
+
+    // Hannan-Rissanen: proxy the shocks by the residuals of a long autoregression, then regress
+    // the series on its own lags and the lagged proxies. Falls back to `neutral_values` when a
+    // regression fails or the MA part comes out non-invertible.
+    fn starting_values(&self) -> Vec<f64> {
+        let mut params = self.neutral_values();
+        let mean = if self.include_mean { params[0] } else { 0.0 };
+        let y: Vec<f64> = self.series.iter().map(|v| v - mean).collect();
+        let n = y.len();
+        let (p, q) = (self.p, self.q);
+        if p + q == 0 {
+            return params;
+        }
+
+        let long_order = if q == 0 {
+            0
+        } else {
+            ((n as f64).ln().powi(2).floor() as usize)
+                .max(2 * p.max(q))
+                .min(n / 4)
+        };
+        let mut shocks = vec![0.0; n];
+        if q > 0 {
+            let columns: Vec<Vec<f64>> = (1..=long_order)
+                .map(|i| y[long_order - i..n - i].to_vec())
+                .collect();
+            let Some(ols) = least_squares(&columns, &y[long_order..]) else {
+                return params;
+            };
+            shocks[long_order..].copy_from_slice(&ols.residuals);
+        }
+
+        let first = (long_order + q).max(p);
+        let mut columns: Vec<Vec<f64>> = (1..=p).map(|i| y[first - i..n - i].to_vec()).collect();
+        columns.extend((1..=q).map(|j| shocks[first - j..n - j].to_vec()));
+        let Some(ols) = least_squares(&columns, &y[first..]) else {
+            return params;
+        };
+        let offset = usize::from(self.include_mean);
+        params[offset..].copy_from_slice(&ols.coefficients);
+        let ma: Vec<f64> = ols.coefficients[p..].iter().map(|theta| -theta).collect();
+        if !roots_outside_unit_circle(&ma) {
+            params[offset + p..]
+                .iter_mut()
+                .for_each(|theta| *theta = 0.0);
+        }
+        params
+    }
+
+    fn model(&self, params: &[f64], ssr: f64) -> Result<ArmaModel, Error> {
+        let residuals = self
+            .residuals(params)
+            .ok_or_else(|| Error::new("ARMA residuals diverge at the estimates"))?;
+        let (mean, ar, ma) = self.split(params);
+        let nobs = residuals.len();
+        let nf = nobs as f64;
+        let sigma2 = ssr / nf;
+        let log_likelihood = -0.5 * nf * ((2.0 * std::f64::consts::PI * sigma2).ln() + 1.0);
+        let n_estimated = (self.n_params() + 1) as f64;
+
+        let k = self.n_params();
+        let (jacobian, _) = self.jacobian(params);
+        let (information, _) = normal_equations(&jacobian, &residuals);
+        let std_errors = match cholesky(&information, k) {
+            Some(l) => (0..k)
+                .map(|i| {
+                    let mut unit = vec![0.0; k];
+                    unit[i] = 1.0;
+                    (sigma2 * cholesky_solve(&l, k, &unit)[i]).sqrt()
+                })
+                .collect(),
+            None => vec![f64::NAN; k],
+        };
+
+        let n = self.series.len();
+        let mut aligned = vec![f64::NAN; self.p];
+        aligned.extend_from_slice(&residuals);
+        let negated_ma: Vec<f64> = ma.iter().map(|theta| -theta).collect();
+        Ok(ArmaModel {
+            ar: ar.to_vec(),
+            ma: ma.to_vec(),
+            mean,
+            std_errors,
+            sigma2,
+            log_likelihood,
+            aic: -2.0 * log_likelihood + 2.0 * n_estimated,
+            bic: -2.0 * log_likelihood + nf.ln() * n_estimated,
+            nobs,
+            is_stationary: roots_outside_unit_circle(ar),
+            is_invertible: roots_outside_unit_circle(&negated_ma),
+            recent_values: self.series[n - self.p..].to_vec(),
+            recent_residuals: residuals[nobs - self.q.min(nobs)..].to_vec(),
+            residuals: aligned,
+        })
+    }
+}
+
+// J'J (row-major) and J'e of a Jacobian given by columns
+fn normal_equations(jacobian: &[Vec<f64>], residuals: &[f64]) -> (Vec<f64>, Vec<f64>) {
+    let k = jacobian.len();
+    let dot = |a: &[f64], b: &[f64]| a.iter().zip(b).map(|(x, y)| x * y).sum::<f64>();
+    let mut a = vec![0.0; k * k];
+    for i in 0..k {
+        for j in 0..=i {
+            let value = dot(&jacobian[i], &jacobian[j]);
+            a[i * k + j] = value;
+            a[j * k + i] = value;
+        }
+    }
+    let g = jacobian
+        .iter()
+        .map(|column| dot(column, residuals))
+        .collect();
+    (a, g)
+}
+
+// Whether 1 - c_1·z - ... - c_m·z^m has every root outside the unit circle, by the step-down
+// recursion: the reflection coefficients of a stable polynomial all lie inside (-1, 1)
+fn roots_outside_unit_circle(coefficients: &[f64]) -> bool {
+    let mut c = coefficients.to_vec();
+    while let Some(&reflection) = c.last() {
+        if reflection.abs() >= 1.0 {
+            return false;
+        }
+        let m = c.len();
+        let scale = 1.0 - reflection * reflection;
+        c = (0..m - 1)
+            .map(|j| (c[j] + reflection * c[m - 2 - j]) / scale)
+            .collect();
+    }
+    true
+}
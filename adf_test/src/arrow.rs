@@ -0,0 +1,853 @@
+// Apache Arrow IPC input and output (the `arrow` feature).
+//
+// Arrow-based tools (DuckDB-WASM, Perspective, arrow-js) exchange tables as IPC
+// buffers: a stream of flatbuffer-encoded messages, a schema followed by record
+// batches whose columns are raw little-endian buffers. This module reads such a
+// buffer as a table of timestamps and prices and writes results back as one, so
+// a query result can be screened or backtested without first being converted to
+// JS arrays.
+//
+// Both the stream and the file format are read. Numeric columns of any integer
+// or floating-point type are widened to f64, with nulls as NaN; a Timestamp or
+// Date column becomes the table's timestamps, in epoch milliseconds. Flat
+// string, binary and boolean columns (such as a symbol column) are skipped.
+// Dictionary-encoded, compressed and big-endian data is rejected. Output is a
+// stream of one record batch with Float64, Int64, Utf8 and millisecond
+// Timestamp columns.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::error::Error;
+use crate::screen::{screen_pairs_with_criteria, PairScreenResult, ScreeningCriteria};
+use crate::walkforward::{walk_forward_with_options, WalkForwardOptions, WalkForwardResult};
+
+const CONTINUATION: u32 = 0xFFFF_FFFF;
+const FILE_MAGIC: &[u8] = b"ARROW1";
+const METADATA_V5: i16 = 4;
+
+// MessageHeader union
+const HEADER_SCHEMA: u8 = 1;
+const HEADER_DICTIONARY_BATCH: u8 = 2;
+const HEADER_RECORD_BATCH: u8 = 3;
+
+// Type union
+const TYPE_INT: u8 = 2;
+const TYPE_FLOATING_POINT: u8 = 3;
+const TYPE_BINARY: u8 = 4;
+const TYPE_UTF8: u8 = 5;
+const TYPE_BOOL: u8 = 6;
+const TYPE_DATE: u8 = 8;
+const TYPE_TIMESTAMP: u8 = 10;
+const TYPE_LARGE_BINARY: u8 = 19;
+const TYPE_LARGE_UTF8: u8 = 20;
+
+const MS_PER_DAY: f64 = 86_400_000.0;
+
+/// Numeric columns of an Arrow IPC buffer, as read by `read_arrow_prices`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug)]
+pub struct ArrowPrices {
+    timestamps: Option<Vec<f64>>,
+    symbols: Vec<String>,
+    columns: Vec<Vec<f64>>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ArrowPrices {
+    // Rows in the table
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn rows(&self) -> usize {
+        self.columns.first().map_or(0, Vec::len)
+    }
+
+    // Names of the numeric columns, in schema order
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn symbols(&self) -> Vec<String> {
+        self.symbols.clone()
+    }
+
+    // The Timestamp or Date column in epoch milliseconds, or undefined when there is none
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn timestamps(&self) -> Option<Vec<f64>> {
+        self.timestamps.clone()
+    }
+
+    /// The numeric columns as a row-major matrix of one row of `symbols.length` values per
+    /// time step, the layout `screen_pairs` and `johansen_test` take.
+    pub fn price_matrix(&self) -> Vec<f64> {
+        let mut matrix = Vec::with_capacity(self.rows() * self.columns.len());
+        for row in 0..self.rows() {
+            matrix.extend(self.columns.iter().map(|column| column[row]));
+        }
+        matrix
+    }
+
+    /// The values of column `name`.
+    pub fn column(&self, name: &str) -> Result<Vec<f64>, Error> {
+        self.column_slice(name).map(<[f64]>::to_vec)
+    }
+}
+
+impl ArrowPrices {
+    // Column `name` without copying, for native callers
+    pub fn column_slice(&self, name: &str) -> Result<&[f64], Error> {
+        self.symbols
+            .iter()
+            .position(|symbol| symbol == name)
+            .map(|i| self.columns[i].as_slice())
+            .ok_or_else(|| Error::new(&format!("no numeric column named {}", name)))
+    }
+}
+
+/// Read an Arrow IPC buffer, in the stream or the file format, into its timestamps and numeric
+/// columns. Multiple record batches are concatenated.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn read_arrow_prices(ipc: &[u8]) -> Result<ArrowPrices, Error> {
+    let mut reader = Reader::default();
+    let mut pos = if ipc.starts_with(FILE_MAGIC) { 8 } else { 0 };
+    while pos < ipc.len() {
+        let mut length = read_u32(ipc, pos)?;
+        pos += 4;
+        if length == CONTINUATION {
+            length = read_u32(ipc, pos)?;
+            pos += 4;
+        }
+        if length == 0 {
+            break;
+        }
+        let metadata = slice(ipc, pos, length as usize)?;
+        pos += length as usize;
+        let message = Table::root(metadata)?;
+        let body_length = usize::try_from(message.i64(3, 0)?)
+            .map_err(|_| Error::new("Arrow message has a negative body length"))?;
+        let body = slice(ipc, pos, body_length)?;
+        pos += body_length;
+        match message.u8(1, 0)? {
+            HEADER_SCHEMA => reader.schema(&message.table(2)?.ok_or_else(missing_header)?)?,
+            HEADER_RECORD_BATCH => {
+                reader.record_batch(&message.table(2)?.ok_or_else(missing_header)?, body)?
+            }
+            HEADER_DICTIONARY_BATCH => {
+                return Err(Error::new(
+                    "dictionary-encoded Arrow columns are not supported",
+                ))
+            }
+            _ => return Err(Error::new("unsupported Arrow IPC message")),
+        }
+    }
+    reader.finish()
+}
+
+/// `screen_pairs` on the numeric columns of an Arrow IPC buffer, with the column names as the
+/// symbols; the timestamp column and non-numeric columns are left out.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn screen_pairs_arrow(ipc: &[u8], criteria: JsValue) -> Result<PairScreenResult, Error> {
+    screen_pairs_arrow_with_criteria(ipc, &ScreeningCriteria::from_js(&criteria)?)
+}
+
+/// `screen_pairs_arrow` for native callers, with the criteria as a struct.
+pub fn screen_pairs_arrow_with_criteria(
+    ipc: &[u8],
+    criteria: &ScreeningCriteria,
+) -> Result<PairScreenResult, Error> {
+    let prices = read_arrow_prices(ipc)?;
+    screen_pairs_with_criteria(&prices.price_matrix(), &prices.symbols, criteria)
+}
+
+/// `walk_forward` on columns `column_a` and `column_b` of an Arrow IPC buffer.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn walk_forward_arrow(
+    ipc: &[u8],
+    column_a: &str,
+    column_b: &str,
+    options: JsValue,
+) -> Result<WalkForwardResult, Error> {
+    walk_forward_arrow_with_options(
+        ipc,
+        column_a,
+        column_b,
+        &WalkForwardOptions::from_js(&options)?,
+    )
+}
+
+/// `walk_forward_arrow` for native callers, with the options as a struct.
+pub fn walk_forward_arrow_with_options(
+    ipc: &[u8],
+    column_a: &str,
+    column_b: &str,
+    options: &WalkForwardOptions,
+) -> Result<WalkForwardResult, Error> {
+    let prices = read_arrow_prices(ipc)?;
+    walk_forward_with_options(
+        prices.column_slice(column_a)?,
+        prices.column_slice(column_b)?,
+        options,
+    )
+}
+
+fn missing_header() -> Error {
+    Error::new("Arrow message has no header")
+}
+
+fn truncated() -> Error {
+    Error::new("Arrow IPC buffer is truncated or malformed")
+}
+
+fn slice(bytes: &[u8], pos: usize, len: usize) -> Result<&[u8], Error> {
+    pos.checked_add(len)
+        .and_then(|end| bytes.get(pos..end))
+        .ok_or_else(truncated)
+}
+
+fn read_u16(bytes: &[u8], pos: usize) -> Result<u16, Error> {
+    let mut word = [0u8; 2];
+    word.copy_from_slice(slice(bytes, pos, 2)?);
+    Ok(u16::from_le_bytes(word))
+}
+
+fn read_u32(bytes: &[u8], pos: usize) -> Result<u32, Error> {
+    let mut word = [0u8; 4];
+    word.copy_from_slice(slice(bytes, pos, 4)?);
+    Ok(u32::from_le_bytes(word))
+}
+
+fn read_u64(bytes: &[u8], pos: usize) -> Result<u64, Error> {
+    let mut word = [0u8; 8];
+    word.copy_from_slice(slice(bytes, pos, 8)?);
+    Ok(u64::from_le_bytes(word))
+}
+
+// A flatbuffer table: an offset to its vtable followed by its inline fields
+struct Table<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Table<'a> {
+    fn root(buf: &'a [u8]) -> Result<Table<'a>, Error> {
+        Ok(Table {
+            buf,
+            pos: read_u32(buf, 0)? as usize,
+        })
+    }
+
+    // Position of field `id`, or None when it is absent (holding its default)
+    fn field(&self, id: usize) -> Result<Option<usize>, Error> {
+        let soffset = read_u32(self.buf, self.pos)? as i32 as isize;
+        let vtable = (self.pos as isize)
+            .checked_sub(soffset)
+            .filter(|v| *v >= 0)
+            .ok_or_else(truncated)? as usize;
+        let vtable_length = read_u16(self.buf, vtable)? as usize;
+        let slot = 4 + 2 * id;
+        if slot + 2 > vtable_length {
+            return Ok(None);
+        }
+        Ok(match read_u16(self.buf, vtable + slot)? {
+            0 => None,
+            offset => Some(self.pos + offset as usize),
+        })
+    }
+
+    fn u8(&self, id: usize, default: u8) -> Result<u8, Error> {
+        match self.field(id)? {
+            Some(pos) => Ok(*self.buf.get(pos).ok_or_else(truncated)?),
+            None => Ok(default),
+        }
+    }
+
+    fn i16(&self, id: usize, default: i16) -> Result<i16, Error> {
+        match self.field(id)? {
+            Some(pos) => Ok(read_u16(self.buf, pos)? as i16),
+            None => Ok(default),
+        }
+    }
+
+    fn i32(&self, id: usize, default: i32) -> Result<i32, Error> {
+        match self.field(id)? {
+            Some(pos) => Ok(read_u32(self.buf, pos)? as i32),
+            None => Ok(default),
+        }
+    }
+
+    fn i64(&self, id: usize, default: i64) -> Result<i64, Error> {
+        match self.field(id)? {
+            Some(pos) => Ok(read_u64(self.buf, pos)? as i64),
+            None => Ok(default),
+        }
+    }
+
+    // Target of the offset stored in field `id`
+    fn indirect(&self, id: usize) -> Result<Option<usize>, Error> {
+        match self.field(id)? {
+            Some(pos) => Ok(Some(pos + read_u32(self.buf, pos)? as usize)),
+            None => Ok(None),
+        }
+    }
+
+    fn table(&self, id: usize) -> Result<Option<Table<'a>>, Error> {
+        Ok(self.indirect(id)?.map(|pos| Table { buf: self.buf, pos }))
+    }
+
+    // Element count and position of the first element of the vector in field `id`
+    fn vector(&self, id: usize) -> Result<(usize, usize), Error> {
+        match self.indirect(id)? {
+            Some(pos) => Ok((read_u32(self.buf, pos)? as usize, pos + 4)),
+            None => Ok((0, 0)),
+        }
+    }
+
+    fn tables(&self, id: usize) -> Result<Vec<Table<'a>>, Error> {
+        let (count, start) = self.vector(id)?;
+        (0..count)
+            .map(|i| {
+                let slot = start + 4 * i;
+                Ok(Table {
+                    buf: self.buf,
+                    pos: slot + read_u32(self.buf, slot)? as usize,
+                })
+            })
+            .collect()
+    }
+
+    fn string(&self, id: usize) -> Result<String, Error> {
+        let (length, start) = self.vector(id)?;
+        Ok(String::from_utf8_lossy(slice(self.buf, start, length)?).into_owned())
+    }
+}
+
+// How a column's values are read
+#[derive(Clone, Copy)]
+enum Kind {
+    Int { bytes: usize, signed: bool },
+    Float { bytes: usize },
+    // Epoch values of `bytes` bytes, multiplied by `to_ms` to give milliseconds
+    Time { bytes: usize, to_ms: f64 },
+    // A column without numeric values that occupies `buffers` buffers
+    Skipped { buffers: usize },
+}
+
+struct Column {
+    name: String,
+    kind: Kind,
+    values: Vec<f64>,
+}
+
+#[derive(Default)]
+struct Reader {
+    columns: Option<Vec<Column>>,
+}
+
+impl Reader {
+    fn schema(&mut self, schema: &Table) -> Result<(), Error> {
+        if schema.i16(0, 0)? != 0 {
+            return Err(Error::new("big-endian Arrow data is not supported"));
+        }
+        let columns = schema
+            .tables(1)?
+            .iter()
+            .map(|field| {
+                let name = field.string(0)?;
+                let kind = column_kind(field).map_err(|_| {
+                    Error::new(&format!("column {} has an unsupported Arrow type", name))
+                })?;
+                Ok(Column {
+                    name,
+                    kind,
+                    values: Vec::new(),
+                })
+            })
+            .collect::<Result<Vec<Column>, Error>>()?;
+        self.columns = Some(columns);
+        Ok(())
+    }
+
+    fn record_batch(&mut self, batch: &Table, body: &[u8]) -> Result<(), Error> {
+        let columns = self
+            .columns
+            .as_mut()
+            .ok_or_else(|| Error::new("Arrow record batch before the schema"))?;
+        if batch.field(3)?.is_some() {
+            return Err(Error::new(
+                "compressed Arrow record batches are not supported",
+            ));
+        }
+        let rows = usize::try_from(batch.i64(0, 0)?).map_err(|_| truncated())?;
+        let (node_count, nodes) = batch.vector(1)?;
+        let (buffer_count, buffers) = batch.vector(2)?;
+        if node_count != columns.len() {
+            return Err(Error::new("Arrow record batch does not match its schema"));
+        }
+        let buffer = |i: usize| -> Result<&[u8], Error> {
+            if i >= buffer_count {
+                return Err(truncated());
+            }
+            let offset = read_u64(batch.buf, buffers + 16 * i)? as usize;
+            let length = read_u64(batch.buf, buffers + 16 * i + 8)? as usize;
+            slice(body, offset, length)
+        };
+
+        let mut next_buffer = 0;
+        for (i, column) in columns.iter_mut().enumerate() {
+            let length = read_u64(batch.buf, nodes + 16 * i)? as usize;
+            let null_count = read_u64(batch.buf, nodes + 16 * i + 8)?;
+            if length != rows {
+                return Err(Error::new("Arrow record batch does not match its schema"));
+            }
+            let bytes = match column.kind {
+                Kind::Skipped { buffers } => {
+                    next_buffer += buffers;
+                    continue;
+                }
+                Kind::Int { bytes, .. } | Kind::Float { bytes } | Kind::Time { bytes, .. } => bytes,
+            };
+            let validity = buffer(next_buffer)?;
+            let values = buffer(next_buffer + 1)?;
+            next_buffer += 2;
+            if values.len() < rows * bytes || (null_count > 0 && validity.len() * 8 < rows) {
+                return Err(truncated());
+            }
+            column.values.reserve(rows);
+            for row in 0..rows {
+                let valid = null_count == 0 || validity[row / 8] & (1 << (row % 8)) != 0;
+                let value = &values[row * bytes..(row + 1) * bytes];
+                column.values.push(if valid {
+                    decode(column.kind, value)
+                } else {
+                    f64::NAN
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<ArrowPrices, Error> {
+        let columns = self
+            .columns
+            .ok_or_else(|| Error::new("Arrow IPC buffer has no schema"))?;
+        let mut prices = ArrowPrices {
+            timestamps: None,
+            symbols: Vec::new(),
+            columns: Vec::new(),
+        };
+        for column in columns {
+            match column.kind {
+                Kind::Skipped { .. } => {}
+                Kind::Time { .. } if prices.timestamps.is_none() => {
+                    prices.timestamps = Some(column.values)
+                }
+                Kind::Time { .. } => {
+                    return Err(Error::new("Arrow table has more than one timestamp column"))
+                }
+                Kind::Int { .. } | Kind::Float { .. } => {
+                    prices.symbols.push(column.name);
+                    prices.columns.push(column.values);
+                }
+            }
+        }
+        Ok(prices)
+    }
+}
+
+// Err(()) for types this reader cannot skip or decode
+fn column_kind(field: &Table) -> Result<Kind, ()> {
+    let type_table = || field.table(3).ok().flatten().ok_or(());
+    let flat = field.vector(5).map_err(|_| ())?.0 == 0;
+    if field.field(4).map_err(|_| ())?.is_some() || !flat {
+        return Err(());
+    }
+    Ok(match field.u8(2, 0).map_err(|_| ())? {
+        TYPE_INT => {
+            let t = type_table()?;
+            let bits = t.i32(0, 0).map_err(|_| ())?;
+            if ![8, 16, 32, 64].contains(&bits) {
+                return Err(());
+            }
+            Kind::Int {
+                bytes: bits as usize / 8,
+                signed: t.u8(1, 0).map_err(|_| ())? != 0,
+            }
+        }
+        TYPE_FLOATING_POINT => match type_table()?.i16(0, 0).map_err(|_| ())? {
+            1 => Kind::Float { bytes: 4 },
+            2 => Kind::Float { bytes: 8 },
+            _ => return Err(()),
+        },
+        TYPE_DATE => match type_table()?.i16(0, 1).map_err(|_| ())? {
+            0 => Kind::Time {
+                bytes: 4,
+                to_ms: MS_PER_DAY,
+            },
+            _ => Kind::Time {
+                bytes: 8,
+                to_ms: 1.0,
+            },
+        },
+        TYPE_TIMESTAMP => Kind::Time {
+            bytes: 8,
+            to_ms: match type_table()?.i16(0, 0).map_err(|_| ())? {
+                0 => 1e3,
+                1 => 1.0,
+                2 => 1e-3,
+                _ => 1e-6,
+            },
+        },
+        TYPE_BOOL => Kind::Skipped { buffers: 2 },
+        TYPE_BINARY | TYPE_UTF8 | TYPE_LARGE_BINARY | TYPE_LARGE_UTF8 => {
+            Kind::Skipped { buffers: 3 }
+        }
+        _ => return Err(()),
+    })
+}
+
+fn decode(kind: Kind, value: &[u8]) -> f64 {
+    let mut word = [0u8; 8];
+    word[..value.len()].copy_from_slice(value);
+    let bits = u64::from_le_bytes(word);
+    // Sign-extend integers narrower than 64 bits
+    let signed = |bytes: usize| ((bits << (64 - 8 * bytes)) as i64 >> (64 - 8 * bytes)) as f64;
+    match kind {
+        Kind::Int {
+            bytes,
+            signed: true,
+        } => signed(bytes),
+        Kind::Int { .. } => bits as f64,
+        Kind::Float { bytes: 4 } => f32::from_bits(bits as u32) as f64,
+        Kind::Float { .. } => f64::from_bits(bits),
+        Kind::Time { bytes, to_ms } => signed(bytes) * to_ms,
+        Kind::Skipped { .. } => f64::NAN,
+    }
+}
+
+// A column of a record batch to write
+pub(crate) enum OutputColumn<'a> {
+    // NaN values are written as NaN, not as nulls
+    Float64(&'a [f64]),
+    Int64(Vec<i64>),
+    // Epoch milliseconds; NaN values are written as nulls
+    TimestampMs(&'a [f64]),
+    Utf8(Vec<&'a str>),
+}
+
+impl OutputColumn<'_> {
+    fn rows(&self) -> usize {
+        match self {
+            OutputColumn::Float64(values) | OutputColumn::TimestampMs(values) => values.len(),
+            OutputColumn::Int64(values) => values.len(),
+            OutputColumn::Utf8(values) => values.len(),
+        }
+    }
+}
+
+/// Arrow IPC stream of one record batch holding `columns`, which must all have the same length.
+pub(crate) fn write_arrow(columns: &[(&str, OutputColumn)]) -> Vec<u8> {
+    let rows = columns.first().map_or(0, |(_, column)| column.rows());
+    debug_assert!(columns.iter().all(|(_, column)| column.rows() == rows));
+
+    let fields = columns
+        .iter()
+        .map(|(name, column)| {
+            let (type_type, type_table) = match column {
+                OutputColumn::Float64(_) => (TYPE_FLOATING_POINT, vec![(0, Value::I16(2))]),
+                OutputColumn::Int64(_) => {
+                    (TYPE_INT, vec![(0, Value::I32(64)), (1, Value::Bool(true))])
+                }
+                OutputColumn::TimestampMs(_) => (TYPE_TIMESTAMP, vec![(0, Value::I16(1))]),
+                OutputColumn::Utf8(_) => (TYPE_UTF8, Vec::new()),
+            };
+            vec![
+                (0, Value::String(name.to_string())),
+                (1, Value::Bool(true)),
+                (2, Value::U8(type_type)),
+                (3, Value::Table(type_table)),
+                (5, Value::Tables(Vec::new())),
+            ]
+        })
+        .collect();
+    let schema = vec![(0, Value::I16(0)), (1, Value::Tables(fields))];
+
+    let mut body = Vec::new();
+    let mut nodes = Vec::new();
+    let mut buffers = Vec::new();
+    let mut push_buffer = |body: &mut Vec<u8>, bytes: &[u8]| {
+        buffers.extend((body.len() as u64).to_le_bytes());
+        buffers.extend((bytes.len() as u64).to_le_bytes());
+        body.extend_from_slice(bytes);
+        body.resize(body.len().next_multiple_of(8), 0);
+    };
+    for (_, column) in columns {
+        let mut null_count = 0u64;
+        let validity: Vec<u8> = match column {
+            OutputColumn::TimestampMs(values) if values.iter().any(|v| v.is_nan()) => {
+                let mut bitmap = vec![0u8; rows.div_ceil(8)];
+                for (row, value) in values.iter().enumerate() {
+                    if value.is_nan() {
+                        null_count += 1;
+                    } else {
+                        bitmap[row / 8] |= 1 << (row % 8);
+                    }
+                }
+                bitmap
+            }
+            _ => Vec::new(),
+        };
+        nodes.extend((rows as u64).to_le_bytes());
+        nodes.extend(null_count.to_le_bytes());
+        push_buffer(&mut body, &validity);
+        match column {
+            OutputColumn::Float64(values) => {
+                let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+                push_buffer(&mut body, &bytes);
+            }
+            OutputColumn::Int64(values) => {
+                let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+                push_buffer(&mut body, &bytes);
+            }
+            OutputColumn::TimestampMs(values) => {
+                let bytes: Vec<u8> = values
+                    .iter()
+                    .flat_map(|v| if v.is_nan() { 0 } else { *v as i64 }.to_le_bytes())
+                    .collect();
+                push_buffer(&mut body, &bytes);
+            }
+            OutputColumn::Utf8(values) => {
+                let mut offsets = vec![0i32];
+                let mut data = Vec::new();
+                for value in values {
+                    data.extend_from_slice(value.as_bytes());
+                    offsets.push(data.len() as i32);
+                }
+                let offsets: Vec<u8> = offsets.iter().flat_map(|v| v.to_le_bytes()).collect();
+                push_buffer(&mut body, &offsets);
+                push_buffer(&mut body, &data);
+            }
+        }
+    }
+    let record_batch = vec![
+        (0, Value::I64(rows as i64)),
+        (1, Value::Structs(nodes)),
+        (2, Value::Structs(buffers)),
+    ];
+
+    let mut out = Vec::new();
+    write_message(&mut out, HEADER_SCHEMA, schema, &[]);
+    write_message(&mut out, HEADER_RECORD_BATCH, record_batch, &body);
+    // End-of-stream marker
+    out.extend(CONTINUATION.to_le_bytes());
+    out.extend(0u32.to_le_bytes());
+    out
+}
+
+fn write_message(out: &mut Vec<u8>, header_type: u8, header: Vec<(u16, Value)>, body: &[u8]) {
+    let message = vec![
+        (0, Value::I16(METADATA_V5)),
+        (1, Value::U8(header_type)),
+        (2, Value::Table(header)),
+        (3, Value::I64(body.len() as i64)),
+    ];
+    let mut metadata = flatbuffer(message);
+    // The continuation marker, length and metadata end on an 8-byte boundary
+    metadata.resize(metadata.len().next_multiple_of(8), 0);
+    out.extend(CONTINUATION.to_le_bytes());
+    out.extend((metadata.len() as u32).to_le_bytes());
+    out.extend(metadata);
+    out.extend_from_slice(body);
+}
+
+// A field of a flatbuffer table being written
+enum Value {
+    U8(u8),
+    Bool(bool),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    String(String),
+    Table(Vec<(u16, Value)>),
+    Tables(Vec<Vec<(u16, Value)>>),
+    // Vector of 16-byte structs (FieldNode, Buffer), already encoded
+    Structs(Vec<u8>),
+}
+
+impl Value {
+    // Size, and alignment, of the field inline in its table; references are 4-byte offsets
+    fn inline_size(&self) -> usize {
+        match self {
+            Value::U8(_) | Value::Bool(_) => 1,
+            Value::I16(_) => 2,
+            Value::I64(_) => 8,
+            _ => 4,
+        }
+    }
+}
+
+// Flatbuffer with `root` as its root table. Everything is laid out front to back, each table
+// preceded by its vtable and followed by what it references, so every offset points forward.
+fn flatbuffer(root: Vec<(u16, Value)>) -> Vec<u8> {
+    let mut buf = vec![0u8; 4];
+    let root = write_table(&mut buf, root);
+    buf[..4].copy_from_slice(&(root as u32).to_le_bytes());
+    buf
+}
+
+fn pad_to(buf: &mut Vec<u8>, alignment: usize) {
+    buf.resize(buf.len().next_multiple_of(alignment), 0);
+}
+
+fn patch_offset(buf: &mut [u8], at: usize, target: usize) {
+    buf[at..at + 4].copy_from_slice(&((target - at) as u32).to_le_bytes());
+}
+
+fn write_table(buf: &mut Vec<u8>, mut fields: Vec<(u16, Value)>) -> usize {
+    // Largest fields first, so that each sits at a multiple of its size after the 4-byte
+    // vtable offset
+    fields.sort_by_key(|(_, value)| std::cmp::Reverse(value.inline_size()));
+    let mut layout = Vec::with_capacity(fields.len());
+    let mut size: usize = 4;
+    for (_, value) in &fields {
+        let field_size = value.inline_size();
+        size = size.next_multiple_of(field_size);
+        layout.push(size);
+        size += field_size;
+    }
+    let slots = fields
+        .iter()
+        .map(|(id, _)| *id as usize + 1)
+        .max()
+        .unwrap_or(0);
+    let mut vtable = vec![0u16; 2 + slots];
+    vtable[0] = (4 + 2 * slots) as u16;
+    vtable[1] = size as u16;
+    for ((id, _), offset) in fields.iter().zip(&layout) {
+        vtable[2 + *id as usize] = *offset as u16;
+    }
+
+    pad_to(buf, 2);
+    let vtable_pos = buf.len();
+    buf.extend(vtable.iter().flat_map(|v| v.to_le_bytes()));
+    pad_to(buf, 8);
+    let table_pos = buf.len();
+    buf.resize(table_pos + size, 0);
+    buf[table_pos..table_pos + 4].copy_from_slice(&((table_pos - vtable_pos) as i32).to_le_bytes());
+
+    let mut references = Vec::new();
+    for ((_, value), offset) in fields.into_iter().zip(layout) {
+        let at = table_pos + offset;
+        match value {
+            Value::U8(v) => buf[at] = v,
+            Value::Bool(v) => buf[at] = v as u8,
+            Value::I16(v) => buf[at..at + 2].copy_from_slice(&v.to_le_bytes()),
+            Value::I32(v) => buf[at..at + 4].copy_from_slice(&v.to_le_bytes()),
+            Value::I64(v) => buf[at..at + 8].copy_from_slice(&v.to_le_bytes()),
+            value => references.push((at, value)),
+        }
+    }
+    for (at, value) in references {
+        let target = match value {
+            Value::String(text) => {
+                pad_to(buf, 4);
+                let pos = buf.len();
+                buf.extend((text.len() as u32).to_le_bytes());
+                buf.extend(text.as_bytes());
+                buf.push(0);
+                pos
+            }
+            Value::Table(table) => write_table(buf, table),
+            Value::Tables(tables) => {
+                pad_to(buf, 4);
+                let pos = buf.len();
+                buf.extend((tables.len() as u32).to_le_bytes());
+                let slots = buf.len();
+                buf.resize(slots + 4 * tables.len(), 0);
+                for (i, table) in tables.into_iter().enumerate() {
+                    let element = write_table(buf, table);
+                    patch_offset(buf, slots + 4 * i, element);
+                }
+                pos
+            }
+            Value::Structs(bytes) => {
+                // The elements, after the 4-byte count, are 8-byte aligned
+                pad_to(buf, 4);
+                if buf.len().is_multiple_of(8) {
+                    buf.extend([0u8; 4]);
+                }
+                let pos = buf.len();
+                buf.extend(((bytes.len() / 16) as u32).to_le_bytes());
+                buf.extend(bytes);
+                pos
+            }
+            // Scalars were written inline above
+            _ => continue,
+        };
+        patch_offset(buf, at, target);
+    }
+    table_pos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> Vec<u8> {
+        write_arrow(&[
+            (
+                "timestamp",
+                OutputColumn::TimestampMs(&[1.7e12, f64::NAN, 1.7e12 + 86_400_000.0]),
+            ),
+            ("spy", OutputColumn::Float64(&[410.5, f64::NAN, 412.25])),
+            ("count", OutputColumn::Int64(vec![-3, 0, 7])),
+            ("symbol", OutputColumn::Utf8(vec!["a", "bb", ""])),
+        ])
+    }
+
+    #[test]
+    fn written_table_reads_back() {
+        let prices = read_arrow_prices(&table()).unwrap();
+        assert_eq!(prices.rows(), 3);
+        // The string column is skipped and the timestamp column is not a price
+        assert_eq!(prices.symbols(), vec!["spy", "count"]);
+        let timestamps = prices.timestamps().unwrap();
+        assert_eq!(timestamps[0], 1.7e12);
+        assert!(timestamps[1].is_nan());
+        assert_eq!(timestamps[2], 1.7e12 + 86_400_000.0);
+        let spy = prices.column_slice("spy").unwrap();
+        assert_eq!((spy[0], spy[2]), (410.5, 412.25));
+        assert!(spy[1].is_nan());
+        assert_eq!(prices.column("count").unwrap(), vec![-3.0, 0.0, 7.0]);
+        assert!(prices.column("symbol").is_err());
+        assert_eq!(prices.price_matrix()[4..], [412.25, 7.0]);
+    }
+
+    #[test]
+    fn truncated_buffer_is_an_error_not_a_panic() {
+        let ipc = table();
+        // Continuation marker, metadata length and the schema message, which has no body
+        let schema_end = 8 + read_u32(&ipc, 4).unwrap() as usize;
+        let end_of_stream = ipc.len() - 8;
+        for cut in 0..ipc.len() {
+            let result = read_arrow_prices(&ipc[..cut]);
+            if cut == schema_end {
+                assert_eq!(result.unwrap().rows(), 0);
+            } else if cut >= end_of_stream {
+                // Only the end-of-stream marker is missing, or part of it
+                assert_eq!(result.is_ok(), cut == end_of_stream, "cut at {}", cut);
+            } else {
+                assert!(result.is_err(), "cut at {} of {}", cut, ipc.len());
+            }
+        }
+    }
+
+    #[test]
+    fn oversized_lengths_are_an_error() {
+        let mut ipc = table();
+        ipc[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(read_arrow_prices(&ipc).is_err());
+        let mut ipc = table();
+        ipc.truncate(ipc.len() - 8);
+        ipc.extend(CONTINUATION.to_le_bytes());
+        ipc.extend(0x7FFF_FFF0u32.to_le_bytes());
+        assert!(read_arrow_prices(&ipc).is_err());
+    }
+}
@@ -0,0 +1,1209 @@
+// Bar-by-bar simulation of a pairs strategy from a position series.
+//
+// A long spread position of size s holds units_a = s·equity / (a + |β|·b) of leg A
+// and units_b = -β·units_a of leg B, so s times the equity is deployed as gross
+// exposure and the spread a - β·b is traded exactly. Units and β are fixed when the
+// trade opens and P&L is marked to market on every bar until it closes. By default
+// positions are taken at the close of the bar whose signal asks for them; the `fill`
+// and `delay` options move the fill to the next bar's open and later bars, since a
+// signal computed from a bar's close can rarely be traded at that same close.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "arrow")]
+use crate::arrow::{write_arrow, OutputColumn};
+use crate::error::Error;
+#[cfg(feature = "wasm")]
+use crate::get_property;
+use crate::json::JsonObject;
+#[cfg(feature = "wasm")]
+use crate::json::{parse_payload, payload_series};
+use crate::risk::{var_es, ValueAtRisk};
+use crate::stats::{nanmean, nanstd, NanPolicy};
+
+// Values per trade in `BacktestResult::trades`
+const TRADE_STRIDE: usize = 6;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct BacktestResult {
+    pub final_equity: f64,
+    pub total_return: f64,
+    // Sum of the transaction costs paid (fees, proportional costs and slippage), in the same
+    // units as the equity
+    pub total_costs: f64,
+    // Part of total_costs due to slippage
+    pub total_slippage: f64,
+    // Part of total_costs due to crossing the quoted bid/ask spreads
+    pub total_spread_cost: f64,
+    // Closed trades with positive P&L over all closed trades (NaN when none closed)
+    pub win_rate: f64,
+    // Fraction of bars that end with an open position
+    pub exposure: f64,
+    capital: f64,
+    equity: Vec<f64>,
+    gross_equity: Vec<f64>,
+    returns: Vec<f64>,
+    trades: Vec<f64>,
+    // Per trade: entry prices of A and B, exit prices of A and B, adverse and favourable
+    // excursions
+    trade_details: Vec<[f64; 6]>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl BacktestResult {
+    // Equity at each bar's close, after costs
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn equity(&self) -> Vec<f64> {
+        self.equity.clone()
+    }
+
+    // Equity plus the costs paid so far: what the same trades would have made without costs
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn gross_equity(&self) -> Vec<f64> {
+        self.gross_equity.clone()
+    }
+
+    // Simple per-bar returns of the equity curve; the first is relative to the starting capital
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn returns(&self) -> Vec<f64> {
+        self.returns.clone()
+    }
+
+    // Packed (entry_index, exit_index, direction, hedge_ratio, pnl, return) rows, where the
+    // indices are the bars the trade was filled on, pnl is net of both legs' costs and return
+    // is pnl over the equity at entry. A trade still open at the last bar has a NaN exit_index
+    // and is marked to market
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn trades(&self) -> Vec<f64> {
+        self.trades.clone()
+    }
+
+    // Return of each closed trade, the input `kelly_fraction` expects
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn trade_returns(&self) -> Vec<f64> {
+        self.trades
+            .chunks_exact(TRADE_STRIDE)
+            .filter(|trade| !trade[1].is_nan())
+            .map(|trade| trade[5])
+            .collect()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn trade_count(&self) -> usize {
+        self.trades.len() / TRADE_STRIDE
+    }
+
+    // Loss from the running equity peak at each bar, as a positive fraction of the peak; the
+    // starting capital is the first peak
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn drawdown(&self) -> Vec<f64> {
+        let mut peak = self.capital;
+        self.equity
+            .iter()
+            .map(|&e| {
+                peak = f64::max(peak, e);
+                1.0 - e / peak
+            })
+            .collect()
+    }
+
+    /// `performance_metrics` of the per-bar equity returns.
+    pub fn performance(&self, periods_per_year: f64) -> Result<PerformanceMetrics, Error> {
+        performance_metrics(&self.returns, periods_per_year)
+    }
+
+    /// `var_es` of the per-bar equity returns.
+    pub fn var_es(&self, confidence: f64, horizon: usize) -> Result<ValueAtRisk, Error> {
+        var_es(&self.returns, confidence, horizon)
+    }
+
+    /// The trades as a table of columns, one entry per trade. `timestamps`, one per bar (such
+    /// as epoch milliseconds), fills in `entry_time` and `exit_time`; without it they are NaN.
+    pub fn trade_log(&self, timestamps: Option<Vec<f64>>) -> Result<TradeLog, Error> {
+        if let Some(timestamps) = &timestamps {
+            if timestamps.len() != self.equity.len() {
+                return Err(Error::new("timestamps must hold one value per bar"));
+            }
+        }
+        let last = self.equity.len().saturating_sub(1) as f64;
+        let time_at = |index: f64| match &timestamps {
+            Some(timestamps) if !index.is_nan() => timestamps[index as usize],
+            _ => f64::NAN,
+        };
+        let rows = self
+            .trades
+            .chunks_exact(TRADE_STRIDE)
+            .zip(&self.trade_details);
+        let column = |f: &dyn Fn(&[f64], &[f64; 6]) -> f64| -> Vec<f64> {
+            rows.clone()
+                .map(|(trade, details)| f(trade, details))
+                .collect()
+        };
+        Ok(TradeLog {
+            entry_index: column(&|trade, _| trade[0]),
+            exit_index: column(&|trade, _| trade[1]),
+            entry_time: column(&|trade, _| time_at(trade[0])),
+            exit_time: column(&|trade, _| time_at(trade[1])),
+            direction: column(&|trade, _| trade[2]),
+            hedge_ratio: column(&|trade, _| trade[3]),
+            entry_price_a: column(&|_, details| details[0]),
+            entry_price_b: column(&|_, details| details[1]),
+            exit_price_a: column(&|_, details| details[2]),
+            exit_price_b: column(&|_, details| details[3]),
+            pnl: column(&|trade, _| trade[4]),
+            returns: column(&|trade, _| trade[5]),
+            holding_bars: column(&|trade, _| {
+                if trade[1].is_nan() {
+                    last - trade[0]
+                } else {
+                    trade[1] - trade[0]
+                }
+            }),
+            mae: column(&|_, details| details[4]),
+            mfe: column(&|_, details| details[5]),
+        })
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("final_equity", self.final_equity)
+            .field("total_return", self.total_return)
+            .field("total_costs", self.total_costs)
+            .field("total_slippage", self.total_slippage)
+            .field("total_spread_cost", self.total_spread_cost)
+            .field("win_rate", self.win_rate)
+            .field("exposure", self.exposure)
+            .field("trade_count", self.trade_count())
+            .field("equity", &self.equity)
+            .field("gross_equity", &self.gross_equity)
+            .field("returns", &self.returns)
+            .field("drawdown", self.drawdown())
+            .field("trades", &self.trades)
+            .field("trade_returns", self.trade_returns())
+            .finish()
+    }
+
+    /// The per-bar curves as an Arrow IPC stream with the columns `equity`, `gross_equity`,
+    /// `returns` and `drawdown`, preceded by a millisecond `timestamp` column when `timestamps`
+    /// (one per bar, in epoch milliseconds) is given.
+    #[cfg(feature = "arrow")]
+    pub fn to_arrow(&self, timestamps: Option<Vec<f64>>) -> Result<Vec<u8>, Error> {
+        let drawdown = self.drawdown();
+        let mut columns = Vec::new();
+        if let Some(timestamps) = &timestamps {
+            if timestamps.len() != self.equity.len() {
+                return Err(Error::new("timestamps must hold one value per bar"));
+            }
+            columns.push(("timestamp", OutputColumn::TimestampMs(timestamps)));
+        }
+        columns.extend([
+            ("equity", OutputColumn::Float64(&self.equity)),
+            ("gross_equity", OutputColumn::Float64(&self.gross_equity)),
+            ("returns", OutputColumn::Float64(&self.returns)),
+            ("drawdown", OutputColumn::Float64(&drawdown)),
+        ]);
+        Ok(write_arrow(&columns))
+    }
+}
+
+// Columns of `BacktestResult::trade_log`
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct TradeLog {
+    entry_index: Vec<f64>,
+    exit_index: Vec<f64>,
+    entry_time: Vec<f64>,
+    exit_time: Vec<f64>,
+    direction: Vec<f64>,
+    hedge_ratio: Vec<f64>,
+    entry_price_a: Vec<f64>,
+    entry_price_b: Vec<f64>,
+    exit_price_a: Vec<f64>,
+    exit_price_b: Vec<f64>,
+    pnl: Vec<f64>,
+    returns: Vec<f64>,
+    holding_bars: Vec<f64>,
+    mae: Vec<f64>,
+    mfe: Vec<f64>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl TradeLog {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn count(&self) -> usize {
+        self.entry_index.len()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn entry_index(&self) -> Vec<f64> {
+        self.entry_index.clone()
+    }
+
+    // NaN for a trade still open at the last bar
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn exit_index(&self) -> Vec<f64> {
+        self.exit_index.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn entry_time(&self) -> Vec<f64> {
+        self.entry_time.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn exit_time(&self) -> Vec<f64> {
+        self.exit_time.clone()
+    }
+
+    // +1 long spread, -1 short spread
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn direction(&self) -> Vec<f64> {
+        self.direction.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn hedge_ratio(&self) -> Vec<f64> {
+        self.hedge_ratio.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn entry_price_a(&self) -> Vec<f64> {
+        self.entry_price_a.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn entry_price_b(&self) -> Vec<f64> {
+        self.entry_price_b.clone()
+    }
+
+    // NaN for a trade still open at the last bar
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn exit_price_a(&self) -> Vec<f64> {
+        self.exit_price_a.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn exit_price_b(&self) -> Vec<f64> {
+        self.exit_price_b.clone()
+    }
+
+    // Net of costs, in the units of the equity
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn pnl(&self) -> Vec<f64> {
+        self.pnl.clone()
+    }
+
+    // pnl over the equity at entry
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn returns(&self) -> Vec<f64> {
+        self.returns.clone()
+    }
+
+    // Bars from entry to exit, or to the last bar for an open trade
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn holding_bars(&self) -> Vec<f64> {
+        self.holding_bars.clone()
+    }
+
+    // Maximum adverse excursion: the worst bar-close P&L while the trade was open, over the
+    // equity at entry (0 or negative, costs included)
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn mae(&self) -> Vec<f64> {
+        self.mae.clone()
+    }
+
+    // Maximum favourable excursion: the best bar-close P&L, likewise (0 or positive)
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn mfe(&self) -> Vec<f64> {
+        self.mfe.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("entry_index", &self.entry_index)
+            .field("exit_index", &self.exit_index)
+            .field("entry_time", &self.entry_time)
+            .field("exit_time", &self.exit_time)
+            .field("direction", &self.direction)
+            .field("hedge_ratio", &self.hedge_ratio)
+            .field("entry_price_a", &self.entry_price_a)
+            .field("entry_price_b", &self.entry_price_b)
+            .field("exit_price_a", &self.exit_price_a)
+            .field("exit_price_b", &self.exit_price_b)
+            .field("pnl", &self.pnl)
+            .field("returns", &self.returns)
+            .field("holding_bars", &self.holding_bars)
+            .field("mae", &self.mae)
+            .field("mfe", &self.mfe)
+            .finish()
+    }
+
+    /// The log as an Arrow IPC stream of one row per trade, with `entry_time` and `exit_time`
+    /// as millisecond timestamps (null where unknown) and every other column as Float64.
+    #[cfg(feature = "arrow")]
+    pub fn to_arrow(&self) -> Vec<u8> {
+        write_arrow(&[
+            ("entry_index", OutputColumn::Float64(&self.entry_index)),
+            ("exit_index", OutputColumn::Float64(&self.exit_index)),
+            ("entry_time", OutputColumn::TimestampMs(&self.entry_time)),
+            ("exit_time", OutputColumn::TimestampMs(&self.exit_time)),
+            ("direction", OutputColumn::Float64(&self.direction)),
+            ("hedge_ratio", OutputColumn::Float64(&self.hedge_ratio)),
+            ("entry_price_a", OutputColumn::Float64(&self.entry_price_a)),
+            ("entry_price_b", OutputColumn::Float64(&self.entry_price_b)),
+            ("exit_price_a", OutputColumn::Float64(&self.exit_price_a)),
+            ("exit_price_b", OutputColumn::Float64(&self.exit_price_b)),
+            ("pnl", OutputColumn::Float64(&self.pnl)),
+            ("returns", OutputColumn::Float64(&self.returns)),
+            ("holding_bars", OutputColumn::Float64(&self.holding_bars)),
+            ("mae", OutputColumn::Float64(&self.mae)),
+            ("mfe", OutputColumn::Float64(&self.mfe)),
+        ])
+    }
+}
+
+/// Options of `backtest_with_options`; the default matches an empty options object.
+///
+/// From JS, `new BacktestOptions(options)` reads the plain object `backtest` takes, and each
+/// `with_*` method validates and sets one option, consuming the options it is called on.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug)]
+pub struct BacktestOptions {
+    // Basis points of traded notional, charged on both legs at entry and at exit
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub cost_bps: f64,
+    // Fixed amount charged at every entry and every exit, whatever the size
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub fee: f64,
+    // Multiple of each leg's recent return volatility lost on its traded notional at entry and
+    // at exit, so that fills are worse when the market moves more
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub slippage: f64,
+    // Trailing bars of the slippage volatility estimate
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub slippage_window: usize,
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub capital: f64,
+    // Price a signal is filled at
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub fill: Fill,
+    // Further bars between the signal and its fill
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub delay: usize,
+}
+
+impl Default for BacktestOptions {
+    fn default() -> BacktestOptions {
+        BacktestOptions {
+            cost_bps: 0.0,
+            fee: 0.0,
+            slippage: 0.0,
+            slippage_window: 20,
+            capital: 1.0,
+            fill: Fill::Close,
+            delay: 0,
+        }
+    }
+}
+
+impl BacktestOptions {
+    // Bars from a signal to its fill
+    pub(crate) fn fill_lag(&self) -> usize {
+        self.delay
+            + match self.fill {
+                Fill::Close => 0,
+                Fill::NextOpen => 1,
+            }
+    }
+}
+
+// Where the backtester fills the position a signal asks for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fill {
+    // At the close of the signal's bar ("close")
+    Close,
+    // At the open of the bar after the signal ("next_open"), which needs the legs' opens
+    NextOpen,
+}
+
+impl Fill {
+    pub fn parse(fill: &str) -> Result<Fill, Error> {
+        match fill {
+            "close" => Ok(Fill::Close),
+            "next_open" => Ok(Fill::NextOpen),
+            other => Err(Error::new(&format!(
+                "unknown fill \"{}\": expected \"close\" or \"next_open\"",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Fill::Close => "close",
+            Fill::NextOpen => "next_open",
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl BacktestOptions {
+    /// Options read from a plain object with the fields `backtest` documents; undefined or null
+    /// gives the defaults.
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(constructor)]
+    pub fn new(options: JsValue) -> Result<BacktestOptions, Error> {
+        BacktestOptions::from_js(&options)
+    }
+
+    /// Cost in basis points of traded notional, per leg and per side.
+    pub fn with_cost_bps(mut self, cost_bps: f64) -> Result<BacktestOptions, Error> {
+        if !(cost_bps >= 0.0 && cost_bps.is_finite()) {
+            return Err(Error::new("cost_bps must be a non-negative number"));
+        }
+        self.cost_bps = cost_bps;
+        Ok(self)
+    }
+
+    /// Fixed cost per entry and per exit.
+    pub fn with_fee(mut self, fee: f64) -> Result<BacktestOptions, Error> {
+        if !(fee >= 0.0 && fee.is_finite()) {
+            return Err(Error::new("fee must be a non-negative number"));
+        }
+        self.fee = fee;
+        Ok(self)
+    }
+
+    /// Slippage as a multiple of each leg's return volatility over the trailing
+    /// `slippage_window` bars.
+    pub fn with_slippage(
+        mut self,
+        slippage: f64,
+        slippage_window: Option<usize>,
+    ) -> Result<BacktestOptions, Error> {
+        if !(slippage >= 0.0 && slippage.is_finite()) {
+            return Err(Error::new("slippage must be a non-negative number"));
+        }
+        if let Some(window) = slippage_window {
+            if !(2..=u32::MAX as usize).contains(&window) {
+                return Err(Error::new(
+                    "slippage_window must be an integer of at least 2",
+                ));
+            }
+            self.slippage_window = window;
+        }
+        self.slippage = slippage;
+        Ok(self)
+    }
+
+    /// Starting equity.
+    pub fn with_capital(mut self, capital: f64) -> Result<BacktestOptions, Error> {
+        if !(capital > 0.0 && capital.is_finite()) {
+            return Err(Error::new("capital must be a positive number"));
+        }
+        self.capital = capital;
+        Ok(self)
+    }
+
+    /// Fill at the signal bar's "close" (the default) or at the "next_open".
+    pub fn with_fill(mut self, fill: &str) -> Result<BacktestOptions, Error> {
+        self.fill = Fill::parse(fill)?;
+        Ok(self)
+    }
+
+    /// Bars to wait after the signal before filling it, on top of the `fill` timing.
+    pub fn with_delay(mut self, delay: usize) -> BacktestOptions {
+        self.delay = delay;
+        self
+    }
+}
+
+impl BacktestOptions {
+    #[cfg(feature = "wasm")]
+    pub(crate) fn from_js(options: &JsValue) -> Result<BacktestOptions, Error> {
+        let mut parsed = BacktestOptions::default();
+        if options.is_undefined() || options.is_null() {
+            return Ok(parsed);
+        }
+        if !options.is_object() {
+            return Err(Error::new("options must be an object"));
+        }
+
+        let cost_bps = get_property(options, "cost_bps")?;
+        if !cost_bps.is_undefined() {
+            let value = cost_bps
+                .as_f64()
+                .ok_or_else(|| Error::new("cost_bps must be a non-negative number"))?;
+            parsed = parsed.with_cost_bps(value)?;
+        }
+
+        let fee = get_property(options, "fee")?;
+        if !fee.is_undefined() {
+            let value = fee
+                .as_f64()
+                .ok_or_else(|| Error::new("fee must be a non-negative number"))?;
+            parsed = parsed.with_fee(value)?;
+        }
+
+        let slippage_window = get_property(options, "slippage_window")?;
+        let window = if slippage_window.is_undefined() {
+            None
+        } else {
+            let value = slippage_window
+                .as_f64()
+                .filter(|v| *v >= 2.0 && v.fract() == 0.0 && *v <= u32::MAX as f64)
+                .ok_or_else(|| Error::new("slippage_window must be an integer of at least 2"))?;
+            Some(value as usize)
+        };
+        let slippage = get_property(options, "slippage")?;
+        let slippage = if slippage.is_undefined() {
+            parsed.slippage
+        } else {
+            slippage
+                .as_f64()
+                .ok_or_else(|| Error::new("slippage must be a non-negative number"))?
+        };
+        parsed = parsed.with_slippage(slippage, window)?;
+
+        let capital = get_property(options, "capital")?;
+        if !capital.is_undefined() {
+            let value = capital
+                .as_f64()
+                .ok_or_else(|| Error::new("capital must be a positive number"))?;
+            parsed = parsed.with_capital(value)?;
+        }
+
+        let fill = get_property(options, "fill")?;
+        if !fill.is_undefined() {
+            let value = fill
+                .as_string()
+                .ok_or_else(|| Error::new("fill must be a string"))?;
+            parsed = parsed.with_fill(&value)?;
+        }
+
+        let delay = get_property(options, "delay")?;
+        if !delay.is_undefined() {
+            let value = delay
+                .as_f64()
+                .filter(|v| *v >= 0.0 && v.fract() == 0.0 && *v <= u32::MAX as f64)
+                .ok_or_else(|| Error::new("delay must be a non-negative integer"))?;
+            parsed = parsed.with_delay(value as usize);
+        }
+        Ok(parsed)
+    }
+}
+
+/// Per-bar execution prices of `backtest_with_quotes` beyond the closes: the legs' opens, which
+/// the "next_open" fill trades at, and their quoted bid/ask spreads. Each is optional; the
+/// default has neither.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, Default)]
+pub struct BacktestQuotes {
+    open_a: Option<Vec<f64>>,
+    open_b: Option<Vec<f64>>,
+    spread_a: Option<Vec<f64>>,
+    spread_b: Option<Vec<f64>>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl BacktestQuotes {
+    /// No opens and no spreads.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> BacktestQuotes {
+        BacktestQuotes::default()
+    }
+
+    /// Opening prices of each leg, one per bar, positive and finite.
+    pub fn with_opens(
+        mut self,
+        open_a: Vec<f64>,
+        open_b: Vec<f64>,
+    ) -> Result<BacktestQuotes, Error> {
+        if open_a
+            .iter()
+            .chain(&open_b)
+            .any(|p| !(*p > 0.0 && p.is_finite()))
+        {
+            return Err(Error::new("opens must be positive and finite"));
+        }
+        self.open_a = Some(open_a);
+        self.open_b = Some(open_b);
+        Ok(self)
+    }
+
+    /// Quoted bid/ask spread of each leg in price units, one per bar: every fill pays half of it
+    /// on each unit traded.
+    pub fn with_spreads(
+        mut self,
+        spread_a: Vec<f64>,
+        spread_b: Vec<f64>,
+    ) -> Result<BacktestQuotes, Error> {
+        if spread_a
+            .iter()
+            .chain(&spread_b)
+            .any(|s| !(*s >= 0.0 && s.is_finite()))
+        {
+            return Err(Error::new("spreads must be non-negative and finite"));
+        }
+        self.spread_a = Some(spread_a);
+        self.spread_b = Some(spread_b);
+        Ok(self)
+    }
+}
+
+/// Simulate a pairs strategy on price series `prices_a` and `prices_b`.
+///
+/// `signals` is the target spread position after each bar (positive = long A / short B,
+/// negative = short A / long B, 0 = flat), e.g. `generate_signals(...).positions`. A
+/// change of sign closes the open trade and opens the new one on the same bar. The magnitude
+/// on the entry bar is the trade's gross exposure as a multiple of the equity, so ±1 deploys
+/// the equity once and sized positions from `volatility_target_positions` or
+/// `kelly_positions` lever it up or down. `markov_regimes(...).gate_positions(signals)` keeps
+/// the strategy flat while the spread is in its broken regime.
+/// `hedge_ratio` is either a single static β or one value per bar (such as Kalman filter
+/// betas); the value on the entry bar is used for the whole trade.
+///
+/// `options` is an optional object with `cost_bps` (cost in basis points of traded notional,
+/// per leg and per side, default 0), `fee` (fixed cost per entry and per exit, default 0),
+/// `slippage` and `slippage_window` (see below), `capital` (starting equity, default 1), `fill`
+/// and `delay` (see below).
+///
+/// Slippage charges each leg `slippage` times the standard deviation of its simple returns over
+/// the trailing `slippage_window` bars (default 20, current bar included) of its traded notional,
+/// at entry and at exit. Until a leg has two returns its volatility is unknown and no slippage is
+/// charged. `equity` is net of every cost and `gross_equity` adds them back.
+///
+/// `fill` is "close" (default), filling each signal at the close of its own bar, or "next_open",
+/// filling it at the open of the following bar; `delay` (default 0) waits that many more bars.
+/// The position held after bar t is then the one `signals` asked for `delay` bars earlier (one
+/// more for "next_open"), sized from the equity and with the hedge ratio of the signal's bar.
+/// "next_open" needs the legs' opens, and a fill can also pay the quoted bid/ask spreads;
+/// both go to `backtest_with_quotes`. The indices in `trades` are the bars of the fills.
+///
+/// The same options as a `BacktestOptions` object go to `backtest_with_options`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn backtest(
+    prices_a: &[f64],
+    prices_b: &[f64],
+    hedge_ratio: &[f64],
+    signals: &[f64],
+    options: JsValue,
+) -> Result<BacktestResult, Error> {
+    let options = BacktestOptions::from_js(&options)?;
+    backtest_with_options(prices_a, prices_b, hedge_ratio, signals, &options)
+}
+
+/// `backtest` driven by JSON text: `payload` is
+/// `{"prices_a": [...], "prices_b": [...], "hedge_ratio": [...], "signals": [...], "options": {...}}`
+/// and the result is returned as `BacktestResult.to_json()`. Optional `open_a` and `open_b`,
+/// and `spread_a` and `spread_b`, carry the quotes of `backtest_with_quotes`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn backtest_from_json(payload: &str) -> Result<String, Error> {
+    let payload = parse_payload(payload)?;
+    let prices_a = payload_series(&payload, "prices_a")?;
+    let prices_b = payload_series(&payload, "prices_b")?;
+    let hedge_ratio = payload_series(&payload, "hedge_ratio")?;
+    let signals = payload_series(&payload, "signals")?;
+    let options = BacktestOptions::from_js(&get_property(&payload, "options")?)?;
+    let optional = |key: &str| -> Result<Option<Vec<f64>>, Error> {
+        if get_property(&payload, key)?.is_undefined() {
+            Ok(None)
+        } else {
+            payload_series(&payload, key).map(Some)
+        }
+    };
+    let mut quotes = BacktestQuotes::new();
+    match (optional("open_a")?, optional("open_b")?) {
+        (Some(open_a), Some(open_b)) => quotes = quotes.with_opens(open_a, open_b)?,
+        (None, None) => {}
+        _ => {
+            return Err(Error::new(
+                "payload needs both open_a and open_b, or neither",
+            ))
+        }
+    }
+    match (optional("spread_a")?, optional("spread_b")?) {
+        (Some(spread_a), Some(spread_b)) => quotes = quotes.with_spreads(spread_a, spread_b)?,
+        (None, None) => {}
+        _ => {
+            return Err(Error::new(
+                "payload needs both spread_a and spread_b, or neither",
+            ))
+        }
+    }
+    let result = backtest_with_quotes(
+        &prices_a,
+        &prices_b,
+        &hedge_ratio,
+        &signals,
+        &quotes,
+        &options,
+    )?;
+    Ok(result.to_json())
+}
+
+/// `backtest` with the options as a `BacktestOptions` object.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn backtest_with_options(
+    prices_a: &[f64],
+    prices_b: &[f64],
+    hedge_ratio: &[f64],
+    signals: &[f64],
+    options: &BacktestOptions,
+) -> Result<BacktestResult, Error> {
+    backtest_with_quotes(
+        prices_a,
+        prices_b,
+        hedge_ratio,
+        signals,
+        &BacktestQuotes::default(),
+        options,
+    )
+}
+
+/// `backtest_with_options` with the legs' opens, for the "next_open" fill, and their bid/ask
+/// spreads. `prices_a` and `prices_b` stay the closes the equity is marked at; a fill at bar t
+/// trades at the open or close of bar t and pays half of `spread_a[t]` and `spread_b[t]` on
+/// every unit of each leg, which `total_spread_cost` reports.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn backtest_with_quotes(
+    prices_a: &[f64],
+    prices_b: &[f64],
+    hedge_ratio: &[f64],
+    signals: &[f64],
+    quotes: &BacktestQuotes,
+    options: &BacktestOptions,
+) -> Result<BacktestResult, Error> {
+    if !(options.cost_bps >= 0.0 && options.cost_bps.is_finite()) {
+        return Err(Error::new("cost_bps must be a non-negative number"));
+    }
+    if !(options.fee >= 0.0 && options.fee.is_finite()) {
+        return Err(Error::new("fee must be a non-negative number"));
+    }
+    if !(options.slippage >= 0.0 && options.slippage.is_finite()) {
+        return Err(Error::new("slippage must be a non-negative number"));
+    }
+    if options.slippage_window < 2 {
+        return Err(Error::new("slippage_window must be at least 2"));
+    }
+    if !(options.capital > 0.0 && options.capital.is_finite()) {
+        return Err(Error::new("capital must be a positive number"));
+    }
+    let n = prices_a.len();
+    if prices_b.len() != n || signals.len() != n {
+        return Err(Error::new(
+            "prices_a, prices_b and signals must have the same length",
+        ));
+    }
+    if hedge_ratio.len() != 1 && hedge_ratio.len() != n {
+        return Err(Error::new(
+            "hedge_ratio must hold one value or one value per bar",
+        ));
+    }
+    if prices_a
+        .iter()
+        .chain(prices_b)
+        .any(|p| !(*p > 0.0 && p.is_finite()))
+    {
+        return Err(Error::new("prices must be positive and finite"));
+    }
+    if hedge_ratio.iter().chain(signals).any(|v| !v.is_finite()) {
+        return Err(Error::new(
+            "hedge_ratio and signals must contain only finite values",
+        ));
+    }
+    let per_bar = |quote: &Option<Vec<f64>>| quote.as_ref().is_none_or(|q| q.len() == n);
+    if !(per_bar(&quotes.open_a) && per_bar(&quotes.open_b)) {
+        return Err(Error::new("opens must hold one value per bar"));
+    }
+    if !(per_bar(&quotes.spread_a) && per_bar(&quotes.spread_b)) {
+        return Err(Error::new("spreads must hold one value per bar"));
+    }
+    // Prices the fills of each bar trade at
+    let (fill_a, fill_b) = match (options.fill, &quotes.open_a, &quotes.open_b) {
+        (Fill::Close, _, _) => (prices_a, prices_b),
+        (Fill::NextOpen, Some(open_a), Some(open_b)) => (open_a.as_slice(), open_b.as_slice()),
+        (Fill::NextOpen, _, _) => {
+            return Err(Error::new(
+                "fill \"next_open\" needs the opens of both legs",
+            ))
+        }
+    };
+    let spread_at = |spread: &Option<Vec<f64>>, t: usize| spread.as_ref().map_or(0.0, |s| s[t]);
+    let lag = options.fill_lag();
+
+    let cost_rate = options.cost_bps / 10_000.0;
+    let (volatility_a, volatility_b) = if options.slippage > 0.0 {
+        (
+            trailing_volatility(prices_a, options.slippage_window),
+            trailing_volatility(prices_b, options.slippage_window),
+        )
+    } else {
+        (vec![0.0; n], vec![0.0; n])
+    };
+    let beta_at = |t: usize| hedge_ratio[if hedge_ratio.len() == 1 { 0 } else { t }];
+    let mut equity = options.capital;
+    let mut total_costs = 0.0;
+    let mut total_slippage = 0.0;
+    let mut total_spread_cost = 0.0;
+    let mut equity_curve = Vec::with_capacity(n);
+    let mut gross_equity = Vec::with_capacity(n);
+    let mut returns = Vec::with_capacity(n);
+    let mut trades = Vec::new();
+    let mut trade_details: Vec<[f64; 6]> = Vec::new();
+    let mut bars_in_market = 0;
+    let (mut wins, mut closed) = (0, 0);
+
+    // Open trade: direction, leg units and equity before its entry cost
+    let mut direction = 0.0;
+    let (mut units_a, mut units_b) = (0.0, 0.0);
+    let mut entry_equity = 0.0;
+
+    for t in 0..n {
+        let previous = equity;
+        // Marked to the fill prices, then, once the bar's trades are done, on to the close
+        if t > 0 {
+            equity +=
+                units_a * (fill_a[t] - prices_a[t - 1]) + units_b * (fill_b[t] - prices_b[t - 1]);
+        }
+        let excursion = |details: &mut [f64; 6], equity: f64, entry_equity: f64| {
+            let excursion = equity / entry_equity - 1.0;
+            details[4] = details[4].min(excursion);
+            details[5] = details[5].max(excursion);
+        };
+
+        // The signal filled on this bar, if one is due yet
+        let signal = t.checked_sub(lag).map(|s| (s, signals[s]));
+        let target = match signal {
+            Some((_, signal)) if signal != 0.0 => signal.signum(),
+            _ => 0.0,
+        };
+        // Volatility known when the fill is made: an open fill precedes the bar's close
+        let volatility_bar = if options.fill == Fill::NextOpen {
+            t.saturating_sub(1)
+        } else {
+            t
+        };
+        // Cost of trading ua and ub units on this bar, and its slippage and spread parts
+        let cost = |ua: f64, ub: f64| {
+            let (notional_a, notional_b) = (ua.abs() * fill_a[t], ub.abs() * fill_b[t]);
+            let slippage = options.slippage
+                * (volatility_a[volatility_bar] * notional_a
+                    + volatility_b[volatility_bar] * notional_b);
+            let spread = 0.5
+                * (ua.abs() * spread_at(&quotes.spread_a, t)
+                    + ub.abs() * spread_at(&quotes.spread_b, t));
+            (
+                options.fee + cost_rate * (notional_a + notional_b) + slippage + spread,
+                slippage,
+                spread,
+            )
+        };
+
+        if direction != 0.0 && target != direction {
+            let (cost, slippage, spread) = cost(units_a, units_b);
+            equity -= cost;
+            total_costs += cost;
+            total_slippage += slippage;
+            total_spread_cost += spread;
+            let trade = trades.len() - TRADE_STRIDE;
+            let pnl = equity - entry_equity;
+            if let Some(details) = trade_details.last_mut() {
+                details[2] = fill_a[t];
+                details[3] = fill_b[t];
+                excursion(details, equity, entry_equity);
+            }
+            trades[trade + 1] = t as f64;
+            trades[trade + 4] = pnl;
+            trades[trade + 5] = pnl / entry_equity;
+            closed += 1;
+            if pnl > 0.0 {
+                wins += 1;
+            }
+            direction = 0.0;
+            units_a = 0.0;
+            units_b = 0.0;
+        }
+
+        // A wiped-out account cannot open new trades
+        let opening = direction == 0.0 && target != 0.0 && equity > 0.0;
+        if let Some((s, size)) = signal.filter(|_| opening) {
+            let beta = beta_at(s);
+            direction = target;
+            units_a = size * equity / (fill_a[t] + beta.abs() * fill_b[t]);
+            units_b = -beta * units_a;
+            entry_equity = equity;
+            let (cost, slippage, spread) = cost(units_a, units_b);
+            equity -= cost;
+            total_costs += cost;
+            total_slippage += slippage;
+            total_spread_cost += spread;
+            trades.extend([t as f64, f64::NAN, direction, beta, f64::NAN, f64::NAN]);
+            trade_details.push([fill_a[t], fill_b[t], f64::NAN, f64::NAN, 0.0, 0.0]);
+        }
+        equity += units_a * (prices_a[t] - fill_a[t]) + units_b * (prices_b[t] - fill_b[t]);
+
+        if direction != 0.0 {
+            bars_in_market += 1;
+            if let Some(details) = trade_details.last_mut() {
+                excursion(details, equity, entry_equity);
+            }
+        }
+        equity_curve.push(equity);
+        gross_equity.push(equity + total_costs);
+        returns.push(equity / previous - 1.0);
+    }
+
+    if direction != 0.0 {
+        let trade = trades.len() - TRADE_STRIDE;
+        let pnl = equity - entry_equity;
+        trades[trade + 4] = pnl;
+        trades[trade + 5] = pnl / entry_equity;
+    }
+
+    Ok(BacktestResult {
+        final_equity: equity,
+        total_return: equity / options.capital - 1.0,
+        total_costs,
+        total_slippage,
+        total_spread_cost,
+        win_rate: if closed > 0 {
+            wins as f64 / closed as f64
+        } else {
+            f64::NAN
+        },
+        exposure: if n > 0 {
+            bars_in_market as f64 / n as f64
+        } else {
+            f64::NAN
+        },
+        capital: options.capital,
+        equity: equity_curve,
+        gross_equity,
+        returns,
+        trades,
+        trade_details,
+    })
+}
+
+// Sample standard deviation of the simple returns over the trailing `window` bars ending at each
+// bar (window - 1 returns once enough history exists, fewer before); 0 until two returns exist
+pub(crate) fn trailing_volatility(prices: &[f64], window: usize) -> Vec<f64> {
+    let returns: Vec<f64> = prices.windows(2).map(|p| p[1] / p[0] - 1.0).collect();
+    (0..prices.len())
+        .map(|t| {
+            let slice = &returns[t.saturating_sub(window - 1)..t];
+            if slice.len() < 2 {
+                0.0
+            } else {
+                nanstd(slice, 1, NanPolicy::Propagate)
+            }
+        })
+        .collect()
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug)]
+pub struct PerformanceMetrics {
+    // Annualised mean over standard deviation of returns, zero risk-free rate
+    pub sharpe: f64,
+    // As sharpe, over the root mean square of the negative returns
+    pub sortino: f64,
+    // Largest peak-to-trough loss of the compounded equity, as a positive fraction
+    pub max_drawdown: f64,
+    // Longest run of bars spent below a previous equity peak
+    pub max_drawdown_duration: usize,
+    pub cagr: f64,
+    pub annualized_volatility: f64,
+    // Positive returns over non-zero returns (NaN when every return is zero)
+    pub hit_rate: f64,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl PerformanceMetrics {
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("sharpe", self.sharpe)
+            .field("sortino", self.sortino)
+            .field("max_drawdown", self.max_drawdown)
+            .field("max_drawdown_duration", self.max_drawdown_duration)
+            .field("cagr", self.cagr)
+            .field("annualized_volatility", self.annualized_volatility)
+            .field("hit_rate", self.hit_rate)
+            .finish()
+    }
+}
+
+/// Summary statistics of a simple-returns series with `periods_per_year` bars per year
+/// (252 for daily data), e.g. `backtest(...).returns`.
+///
+/// Returns are compounded for the drawdown and CAGR. Ratios are NaN when undefined (fewer
+/// than two returns, or no dispersion or downside).
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn performance_metrics(
+    returns: &[f64],
+    periods_per_year: f64,
+) -> Result<PerformanceMetrics, Error> {
+    if !(periods_per_year > 0.0 && periods_per_year.is_finite()) {
+        return Err(Error::new("periods_per_year must be positive"));
+    }
+    if returns.iter().any(|r| !r.is_finite()) {
+        return Err(Error::new("returns must contain only finite values"));
+    }
+
+    let n = returns.len();
+    let annualize = periods_per_year.sqrt();
+    let (mean, std) = if n >= 2 {
+        (
+            nanmean(returns, NanPolicy::Propagate),
+            nanstd(returns, 1, NanPolicy::Propagate),
+        )
+    } else {
+        (f64::NAN, f64::NAN)
+    };
+    let downside = (returns.iter().map(|r| r.min(0.0).powi(2)).sum::<f64>() / n as f64).sqrt();
+    let ratio = |denominator: f64| {
+        if denominator > 0.0 {
+            mean / denominator * annualize
+        } else {
+            f64::NAN
+        }
+    };
+
+    let mut equity = 1.0;
+    let mut peak = 1.0;
+    let mut max_drawdown = 0.0;
+    let mut underwater = 0;
+    let mut max_drawdown_duration = 0;
+    for r in returns {
+        equity *= 1.0 + r;
+        if equity >= peak {
+            peak = equity;
+            underwater = 0;
+        } else {
+            underwater += 1;
+            max_drawdown_duration = max_drawdown_duration.max(underwater);
+            max_drawdown = f64::max(max_drawdown, 1.0 - equity / peak);
+        }
+    }
+
+    let nonzero = returns.iter().filter(|r| **r != 0.0).count();
+    let positive = returns.iter().filter(|r| **r > 0.0).count();
+
+    Ok(PerformanceMetrics {
+        sharpe: ratio(std),
+        sortino: ratio(downside),
+        max_drawdown,
+        max_drawdown_duration,
+        cagr: if n > 0 && equity > 0.0 {
+            equity.powf(periods_per_year / n as f64) - 1.0
+        } else {
+            f64::NAN
+        },
+        annualized_volatility: std * annualize,
+        hit_rate: if nonzero > 0 {
+            positive as f64 / nonzero as f64
+        } else {
+            f64::NAN
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9 * b.abs().max(1.0)
+    }
+
+    // Five bars of a pair with a flat leg B: long the spread over bars 0-2, short over 3-4
+    fn five_bars(cost_bps: f64) -> BacktestResult {
+        let options = BacktestOptions {
+            cost_bps,
+            capital: 1000.0,
+            ..BacktestOptions::default()
+        };
+        backtest_with_options(
+            &[10.0, 11.0, 12.0, 11.0, 13.0],
+            &[10.0; 5],
+            &[1.0],
+            &[1.0, 1.0, 0.0, -1.0, 0.0],
+            &options,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn hand_computed_path_with_costs() {
+        let result = five_bars(10.0);
+
+        // Bar 0: 1000 / (10 + 10) = 50 units of A against -50 of B, 10 bps on 1000 notional.
+        // Bars 1-2: A gains 1 a bar; bar 2 closes 50·12 + 50·10 notional
+        let first_exit = 1000.0 - 1.0 + 50.0 + 50.0 - 1.1;
+        // Bar 3: short 1097.9 / (11 + 10) units, then A rises 2 and the trade closes at
+        // 13 + 10 a unit
+        let units = first_exit / 21.0;
+        let entry_cost = 0.001 * units * 21.0;
+        let exit_cost = 0.001 * units * 23.0;
+        let last = first_exit - entry_cost - 2.0 * units - exit_cost;
+        let expected = [999.0, 1049.0, first_exit, first_exit - entry_cost, last];
+        for (equity, expected) in result.equity().iter().zip(expected) {
+            assert!(close(*equity, expected), "{:?}", result.equity());
+        }
+
+        assert_eq!(result.trade_count(), 2);
+        let trades = result.trades();
+        assert_eq!(&trades[..4], &[0.0, 2.0, 1.0, 1.0]);
+        assert_eq!(&trades[6..10], &[3.0, 4.0, -1.0, 1.0]);
+        assert!(close(trades[4], first_exit - 1000.0) && trades[4] > 0.0);
+        assert!(close(trades[10], last - first_exit) && trades[10] < 0.0);
+        assert!(close(trades[11], (last - first_exit) / first_exit));
+        assert_eq!(result.win_rate, 0.5);
+        assert!(close(result.exposure, 0.6));
+
+        let costs = 1.0 + 1.1 + entry_cost + exit_cost;
+        assert!(close(result.total_costs, costs));
+        assert!(close(result.gross_equity()[4], last + costs));
+        assert!(close(result.final_equity, last));
+        assert!(close(result.total_return, last / 1000.0 - 1.0));
+
+        // Bar 0 is the entry cost below the starting capital, bars 1 and 2 are new peaks and
+        // the short trade draws down from bar 2's close
+        let drawdown = result.drawdown();
+        assert!(close(drawdown[0], 0.001));
+        assert_eq!(&drawdown[1..3], &[0.0, 0.0]);
+        assert!(close(drawdown[3], entry_cost / first_exit));
+        assert!(close(drawdown[4], 1.0 - last / first_exit));
+
+        let returns: Vec<f64> = [1000.0]
+            .iter()
+            .chain(&expected)
+            .collect::<Vec<_>>()
+            .windows(2)
+            .map(|pair| pair[1] / pair[0] - 1.0)
+            .collect();
+        let mean = returns.iter().sum::<f64>() / 5.0;
+        let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / 4.0;
+        let metrics = result.performance(252.0).unwrap();
+        assert!(close(metrics.sharpe, mean / variance.sqrt() * 252f64.sqrt()));
+        assert!(close(metrics.max_drawdown, 1.0 - last / first_exit));
+        assert_eq!(metrics.max_drawdown_duration, 2);
+    }
+
+    #[test]
+    fn costs_are_the_gap_to_the_cost_free_run() {
+        let (net, gross) = (five_bars(10.0), five_bars(0.0));
+        assert_eq!(gross.total_costs, 0.0);
+        assert_eq!(gross.equity(), gross.gross_equity());
+        // Both runs trade the same bars; the cost-free first trade earns exactly 100
+        assert_eq!(gross.trade_count(), net.trade_count());
+        assert!(close(gross.trades()[4], 100.0));
+        assert!(net.final_equity < gross.final_equity);
+    }
+}
@@ -0,0 +1,517 @@
+// Many-series evaluation in a single wasm call.
+//
+// Series are passed concatenated in one Float64Array with per-series offsets and
+// lengths, so the JS <-> wasm crossing is paid once per batch instead of once per
+// series, or pair of series for the Engle-Granger batch. With the `parallel`
+// feature on a native target the series of a batch are fitted on several
+// threads; wasm32 builds fit them in order (see `parallel`).
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::adf::{
+    adf_regression, adf_result_from_fit, default_max_lag, select_lag, AdfOptions, DecisionRule,
+    Regression,
+};
+use crate::coint::engle_granger;
+use crate::error::Error;
+use crate::linalg::CovarianceType;
+use crate::mean_reversion::half_life_fit;
+use crate::parallel;
+use crate::validate::require_same_length;
+use crate::AdfResult;
+
+// Row layouts of the packed batch results; keep in sync with the TypeScript section below
+pub(crate) const ADF_PACKED_STRIDE: usize = 9;
+pub(crate) const COINT_PACKED_STRIDE: usize = 8;
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(typescript_custom_section)]
+const ADF_PACKED_TS: &'static str = r#"
+/**
+ * One row of the Float64Array returned by `adf_batch_packed` and `adf_test_batch`. Row `i`
+ * occupies `packed.subarray(i * adf_packed_stride(), (i + 1) * adf_packed_stride())` with
+ * the fields in exactly this order. `is_stationary` is 1 or 0; a series that could not be
+ * tested has NaN in every field except `is_stationary` (0).
+ */
+export interface PackedAdfRow {
+  statistic: number;
+  p_value: number;
+  critical_1: number;
+  critical_5: number;
+  critical_10: number;
+  is_stationary: number;
+  half_life: number;
+  used_lag: number;
+  nobs: number;
+}
+
+/**
+ * One row of the Float64Array returned by `engle_granger_batch_packed`, laid out as
+ * `PackedAdfRow` with `coint_packed_stride()` slots. `is_cointegrated` is 1 or 0; a pair that
+ * could not be tested has NaN in every field except `is_cointegrated` (0).
+ */
+export interface PackedCointRow {
+  statistic: number;
+  p_value: number;
+  critical_1: number;
+  critical_5: number;
+  critical_10: number;
+  is_cointegrated: number;
+  hedge_ratio: number;
+  intercept: number;
+}
+"#;
+
+/// Number of f64 slots per series in the arrays returned by `adf_batch_packed` and
+/// `adf_test_batch`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn adf_packed_stride() -> usize {
+    ADF_PACKED_STRIDE
+}
+
+/// ADF test (fixed `lags` lagged differences) on every series in a batch, returned as an array
+/// of `AdfResult` objects, with `null` for series that are too short or degenerate.
+///
+/// `is_stationary` is decided at `significance` (default 0.05) under `decision_rule`
+/// ("both" by default, "p_value" or "critical_value"), as in `adf_test_with_options`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn adf_batch(
+    values: &[f64],
+    offsets: &[u32],
+    lengths: &[u32],
+    lags: usize,
+    regression: &str,
+    significance: Option<f64>,
+    decision_rule: Option<String>,
+) -> Result<js_sys::Array, Error> {
+    let results = js_sys::Array::new();
+    for result in adf_batch_results(
+        values,
+        offsets,
+        lengths,
+        lags,
+        regression,
+        significance,
+        decision_rule,
+    )? {
+        results.push(&result.map_or(JsValue::NULL, JsValue::from));
+    }
+    Ok(results)
+}
+
+/// `adf_batch` for native callers, with `None` for series that could not be tested.
+pub fn adf_batch_results(
+    values: &[f64],
+    offsets: &[u32],
+    lengths: &[u32],
+    lags: usize,
+    regression: &str,
+    significance: Option<f64>,
+    decision_rule: Option<String>,
+) -> Result<Vec<Option<AdfResult>>, Error> {
+    let regression = Regression::parse(regression)?;
+    let (significance, rule) = parse_decision(significance, decision_rule)?;
+    let series = split_series(values, offsets, lengths)?;
+    parallel::map(&series, |s| {
+        adf_regression(s, lags, regression, CovarianceType::Classical)
+            .map(|fit| adf_result_from_fit(&fit).with_decision(significance, rule))
+            .transpose()
+    })
+    .into_iter()
+    .collect()
+}
+
+/// Same as `adf_batch`, but packed into one flat Float64Array of `adf_packed_stride()` values per
+/// series (see `PackedAdfRow` for the layout) plus the half-life of each series. Reading a flat
+/// array avoids a getter call across the wasm boundary per field, which dominates for thousands
+/// of series. `significance` and `decision_rule` decide `is_stationary` as in `adf_batch`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn adf_batch_packed(
+    values: &[f64],
+    offsets: &[u32],
+    lengths: &[u32],
+    lags: usize,
+    regression: &str,
+    significance: Option<f64>,
+    decision_rule: Option<String>,
+) -> Result<Vec<f64>, Error> {
+    let regression = Regression::parse(regression)?;
+    let (significance, rule) = parse_decision(significance, decision_rule)?;
+    let series = split_series(values, offsets, lengths)?;
+
+    pack_results(&series, regression, significance, rule, |_| Some(lags))
+}
+
+/// Batch ADF screening: every series in `values` (located by `offsets` / `lengths`) is tested
+/// inside Rust and the results come back packed as in `adf_batch_packed`, amortising the
+/// JS <-> wasm crossing over the whole batch.
+///
+/// `max_lag` defaults to Schwert's rule per series (as in `adf_test`). With `autolag` set to
+/// "AIC", "BIC" or "t-stat" the lag is chosen per series in `0..=max_lag`; otherwise exactly
+/// `max_lag` lags are used. The chosen lag is in each row's `used_lag` slot.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn adf_test_batch(
+    values: &[f64],
+    offsets: &[u32],
+    lengths: &[u32],
+    max_lag: Option<usize>,
+    regression: &str,
+    autolag: Option<String>,
+) -> Result<Vec<f64>, Error> {
+    let options = AdfOptions::default()
+        .with_max_lag(max_lag)
+        .with_autolag(autolag)?
+        .with_regression(regression)?;
+    adf_test_batch_with_config(values, offsets, lengths, &options)
+}
+
+/// `adf_test_batch` with the lag search, deterministic terms and stationarity decision taken
+/// from an `AdfOptions`: `max_lag`, `autolag`, `regression`, `significance` and `decision_rule`
+/// apply to every series; the other options are ignored.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn adf_test_batch_with_config(
+    values: &[f64],
+    offsets: &[u32],
+    lengths: &[u32],
+    options: &AdfOptions,
+) -> Result<Vec<f64>, Error> {
+    let (regression, method) = (options.regression, options.autolag);
+    let series = split_series(values, offsets, lengths)?;
+
+    pack_results(
+        &series,
+        regression,
+        options.significance,
+        options.decision_rule,
+        |s| {
+            let max_lag = options
+                .max_lag
+                .unwrap_or_else(|| default_max_lag(s.len(), regression));
+            select_lag(s, max_lag, regression, method)
+        },
+    )
+}
+
+/// Number of f64 slots per pair in the array returned by `engle_granger_batch_packed`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn coint_packed_stride() -> usize {
+    COINT_PACKED_STRIDE
+}
+
+/// Engle-Granger test of every pair y_i ~ x_i in a batch, packed into one flat Float64Array of
+/// `coint_packed_stride()` values per pair (see `PackedCointRow` for the layout).
+///
+/// `y_values` and `x_values` hold the legs of every pair concatenated in the same order, so one
+/// set of `offsets` / `lengths` locates both legs of a pair. Each pair is tested as by
+/// `engle_granger`; a pair it rejects (too short, non-finite or constant) gets the NaN row.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn engle_granger_batch_packed(
+    y_values: &[f64],
+    x_values: &[f64],
+    offsets: &[u32],
+    lengths: &[u32],
+) -> Result<Vec<f64>, Error> {
+    require_same_length(y_values, x_values)?;
+    let ys = split_series(y_values, offsets, lengths)?;
+    let xs = split_series(x_values, offsets, lengths)?;
+    let pairs: Vec<(&[f64], &[f64])> = ys.into_iter().zip(xs).collect();
+
+    let rows = parallel::map(&pairs, |&(y, x)| {
+        let Ok(result) = engle_granger(y, x) else {
+            let mut row = [f64::NAN; COINT_PACKED_STRIDE];
+            row[5] = 0.0;
+            return row;
+        };
+        let [c1, c5, c10] = result.critical_values_array();
+        [
+            result.statistic,
+            result.p_value,
+            c1,
+            c5,
+            c10,
+            if result.is_cointegrated { 1.0 } else { 0.0 },
+            result.hedge_ratio,
+            result.intercept,
+        ]
+    });
+    Ok(rows.concat())
+}
+
+// Significance level and decision rule of the stationarity decision, with the defaults and
+// validation of `AdfOptions`
+fn parse_decision(
+    significance: Option<f64>,
+    decision_rule: Option<String>,
+) -> Result<(f64, DecisionRule), Error> {
+    let mut options = AdfOptions::default();
+    if let Some(significance) = significance {
+        options = options.with_significance(significance)?;
+    }
+    if let Some(rule) = decision_rule {
+        options = options.with_decision_rule(&rule)?;
+    }
+    Ok((options.significance, options.decision_rule))
+}
+
+// Fit every series with the lag order chosen by `lags_for` and pack the rows, deciding
+// `is_stationary` at `significance` under `rule`; a series whose lag cannot be chosen or whose
+// regression fails gets the NaN row
+fn pack_results(
+    series: &[&[f64]],
+    regression: Regression,
+    significance: f64,
+    rule: DecisionRule,
+    lags_for: impl Fn(&[f64]) -> Option<usize> + Sync,
+) -> Result<Vec<f64>, Error> {
+    let rows = parallel::map(series, |&s| {
+        let fit = lags_for(s)
+            .and_then(|lags| adf_regression(s, lags, regression, CovarianceType::Classical));
+        let Some(fit) = fit else {
+            let mut row = [f64::NAN; ADF_PACKED_STRIDE];
+            row[5] = 0.0;
+            return Ok(row);
+        };
+        // Decided exactly as the object results are
+        let result = adf_result_from_fit(&fit).with_decision(significance, rule)?;
+        let [c1, c5, c10] = result.critical_values_array();
+        let half_life = half_life_fit(s).map_or(f64::NAN, |h| h.half_life);
+        Ok([
+            result.statistic,
+            result.p_value,
+            c1,
+            c5,
+            c10,
+            if result.is_stationary { 1.0 } else { 0.0 },
+            half_life,
+            fit.used_lag as f64,
+            fit.nobs as f64,
+        ])
+    });
+    let rows = rows.into_iter().collect::<Result<Vec<_>, Error>>()?;
+    Ok(rows.concat())
+}
+
+// Slice the concatenated buffer into its series, validating every (offset, length) pair
+pub(crate) fn split_series<'a>(
+    values: &'a [f64],
+    offsets: &[u32],
+    lengths: &[u32],
+) -> Result<Vec<&'a [f64]>, Error> {
+    if offsets.len() != lengths.len() {
+        return Err(Error::new("offsets and lengths must have the same length"));
+    }
+    offsets
+        .iter()
+        .zip(lengths)
+        .enumerate()
+        .map(|(i, (&offset, &length))| {
+            let start = offset as usize;
+            let end = start + length as usize;
+            values.get(start..end).ok_or_else(|| {
+                Error::new(&format!(
+                    "series {} (offset {}, length {}) runs past the end of values ({})",
+                    i,
+                    offset,
+                    length,
+                    values.len()
+                ))
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    // A stationary AR(1), a random walk and a series too short for the regression,
+    // concatenated with their offsets and lengths
+    fn batch() -> (Vec<f64>, Vec<u32>, Vec<u32>) {
+        let mut rng = Rng::new(12);
+        let mut values = Vec::new();
+        let (mut offsets, mut lengths) = (Vec::new(), Vec::new());
+        for (phi, n) in [(0.5, 300), (1.0, 300), (0.5, 3)] {
+            offsets.push(values.len() as u32);
+            lengths.push(n as u32);
+            let mut level = 0.0;
+            for _ in 0..n {
+                level = phi * level + rng.normal();
+                values.push(level);
+            }
+        }
+        (values, offsets, lengths)
+    }
+
+    #[test]
+    fn packed_rows_decode_to_the_object_results() {
+        let (values, offsets, lengths) = batch();
+        let objects = adf_batch_results(&values, &offsets, &lengths, 2, "c", None, None).unwrap();
+        let packed = adf_batch_packed(&values, &offsets, &lengths, 2, "c", None, None).unwrap();
+        assert_eq!(packed.len(), objects.len() * adf_packed_stride());
+
+        for (i, (object, row)) in objects
+            .iter()
+            .zip(packed.chunks(ADF_PACKED_STRIDE))
+            .enumerate()
+        {
+            let Some(object) = object else {
+                assert_eq!(row[5], 0.0);
+                assert!(row[..5].iter().chain(&row[6..]).all(|v| v.is_nan()));
+                continue;
+            };
+            let [c1, c5, c10] = object.critical_values_array();
+            assert_eq!(row[0], object.statistic);
+            assert_eq!(row[1], object.p_value);
+            assert_eq!(row[2..5], [c1, c5, c10]);
+            assert_eq!(row[5], if object.is_stationary { 1.0 } else { 0.0 });
+            let start = offsets[i] as usize;
+            let series = &values[start..start + lengths[i] as usize];
+            let half_life = half_life_fit(series).map_or(f64::NAN, |h| h.half_life);
+            assert!(row[6] == half_life || (row[6].is_nan() && half_life.is_nan()));
+            assert_eq!(row[7], object.used_lag().unwrap() as f64);
+            assert_eq!(row[8], object.nobs().unwrap() as f64);
+        }
+
+        // The stationary series is flagged, the random walk is not and the short one failed
+        assert!(objects[0].as_ref().unwrap().is_stationary);
+        assert!(!objects[1].as_ref().unwrap().is_stationary);
+        assert!(objects[2].is_none());
+    }
+
+    #[test]
+    fn packed_decision_follows_the_significance_and_rule() {
+        // Near-unit-root AR(1)s, so the decision depends on the level
+        let mut rng = Rng::new(13);
+        let mut values = Vec::new();
+        let (mut offsets, mut lengths) = (Vec::new(), Vec::new());
+        for _ in 0..20 {
+            offsets.push(values.len() as u32);
+            lengths.push(200);
+            let mut level = 0.0;
+            for _ in 0..200 {
+                level = 0.95 * level + rng.normal();
+                values.push(level);
+            }
+        }
+
+        let mut decisions = Vec::new();
+        for significance in [0.01, 0.10] {
+            for rule in ["p_value", "critical_value", "both"] {
+                let packed = adf_batch_packed(
+                    &values,
+                    &offsets,
+                    &lengths,
+                    1,
+                    "c",
+                    Some(significance),
+                    Some(rule.to_string()),
+                )
+                .unwrap();
+                let objects = adf_batch_results(
+                    &values,
+                    &offsets,
+                    &lengths,
+                    1,
+                    "c",
+                    Some(significance),
+                    Some(rule.to_string()),
+                )
+                .unwrap();
+                let flags: Vec<bool> = packed
+                    .chunks(ADF_PACKED_STRIDE)
+                    .map(|r| r[5] == 1.0)
+                    .collect();
+                for (object, &flag) in objects.iter().zip(&flags) {
+                    let object = object.as_ref().unwrap();
+                    assert_eq!(object.is_stationary, flag);
+                    assert_eq!(object.significance(), significance);
+                    let by_p_value = object.p_value <= significance;
+                    let by_critical_value =
+                        object.statistic < object.critical_value(significance).unwrap();
+                    let expected = match rule {
+                        "p_value" => by_p_value,
+                        "critical_value" => by_critical_value,
+                        _ => by_p_value && by_critical_value,
+                    };
+                    assert_eq!(flag, expected);
+                }
+                decisions.push(flags);
+            }
+        }
+        // Some series are stationary at 10% but not at 1%
+        assert_ne!(decisions[2], decisions[5]);
+
+        let options = AdfOptions::default()
+            .with_max_lag(Some(1))
+            .with_autolag(None)
+            .unwrap()
+            .with_significance(0.10)
+            .unwrap();
+        let configured = adf_test_batch_with_config(&values, &offsets, &lengths, &options).unwrap();
+        let flags: Vec<bool> = configured
+            .chunks(ADF_PACKED_STRIDE)
+            .map(|r| r[5] == 1.0)
+            .collect();
+        assert_eq!(flags, decisions[5]);
+
+        let bad_rule = adf_batch_packed(
+            &values,
+            &offsets,
+            &lengths,
+            1,
+            "c",
+            None,
+            Some("neither".to_string()),
+        );
+        assert!(bad_rule.is_err());
+        assert!(adf_batch_packed(&values, &offsets, &lengths, 1, "c", Some(1.5), None).is_err());
+    }
+
+    #[test]
+    fn packed_engle_granger_rows_match_each_pair() {
+        // A cointegrated pair, two independent random walks and a pair with a constant x
+        let mut rng = Rng::new(14);
+        let (mut ys, mut xs) = (Vec::new(), Vec::new());
+        let (mut offsets, mut lengths) = (Vec::new(), Vec::new());
+        for kind in 0..3 {
+            offsets.push(ys.len() as u32);
+            lengths.push(250);
+            let (mut x, mut walk, mut spread) = (50.0, 0.0, 0.0);
+            for _ in 0..250 {
+                x += rng.normal();
+                walk += rng.normal();
+                spread = 0.5 * spread + rng.normal();
+                match kind {
+                    0 => ys.push(1.5 * x + spread),
+                    1 => ys.push(walk),
+                    _ => ys.push(spread),
+                }
+                xs.push(if kind == 2 { 1.0 } else { x });
+            }
+        }
+
+        let packed = engle_granger_batch_packed(&ys, &xs, &offsets, &lengths).unwrap();
+        assert_eq!(packed.len(), 3 * coint_packed_stride());
+        for (i, row) in packed.chunks(COINT_PACKED_STRIDE).enumerate() {
+            let start = offsets[i] as usize;
+            let end = start + lengths[i] as usize;
+            let Ok(result) = engle_granger(&ys[start..end], &xs[start..end]) else {
+                assert_eq!(i, 2);
+                assert_eq!(row[5], 0.0);
+                assert!(row[..5].iter().chain(&row[6..]).all(|v| v.is_nan()));
+                continue;
+            };
+            let [c1, c5, c10] = result.critical_values_array();
+            assert_eq!(row[..5], [result.statistic, result.p_value, c1, c5, c10]);
+            assert_eq!(row[5], if result.is_cointegrated { 1.0 } else { 0.0 });
+            assert_eq!(row[6..], [result.hedge_ratio, result.intercept]);
+        }
+        // The cointegrated pair is flagged, the random walks are not and the constant x failed
+        assert_eq!(packed[5], 1.0);
+        assert_eq!(packed[COINT_PACKED_STRIDE + 5], 0.0);
+        assert!(packed[2 * COINT_PACKED_STRIDE].is_nan());
+    }
+}
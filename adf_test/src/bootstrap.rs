@@ -0,0 +1,129 @@
+// Bootstrap null distributions for the unit-root and cointegration tests.
+//
+// The tabulated p-values are asymptotic and the MacKinnon surfaces only correct
+// them for sample size, not for the short-run dynamics of a particular series. On
+// short samples the bootstrap rebuilds the null from the series' own increments
+// and compares the statistic with the statistics of series that have a unit root
+// (or no cointegration) by construction.
+
+use crate::adf::{adf_regression, Regression};
+use crate::coint::engle_granger_fit;
+use crate::linalg::{least_squares, CovarianceType};
+use crate::rng::Rng;
+use crate::stats::{nanmean, NanPolicy};
+
+// Sieve bootstrap p-value of an ADF statistic: an AR(lags) fitted to the differences is driven
+// by resampled residuals and integrated, giving driftless unit-root series with the same
+// short-run dynamics, which are tested with the same regression. None when the sieve
+// regression fails or no replication produces a statistic.
+pub(crate) fn adf_bootstrap_p_value(
+    series: &[f64],
+    lags: usize,
+    regression: Regression,
+    statistic: f64,
+    replications: usize,
+    seed: u32,
+) -> Option<f64> {
+    let diff: Vec<f64> = series.windows(2).map(|w| w[1] - w[0]).collect();
+    let (coefficients, residuals) = sieve_fit(&diff, lags)?;
+    let mut rng = Rng::new(seed);
+
+    let mut simulated = vec![0.0; diff.len()];
+    let mut levels = vec![0.0; series.len()];
+    let statistics = (0..replications).filter_map(|_| {
+        for t in 0..diff.len() {
+            simulated[t] = if t < lags {
+                diff[t]
+            } else {
+                residuals[rng.below(residuals.len())]
+                    + coefficients
+                        .iter()
+                        .enumerate()
+                        .map(|(i, phi)| phi * simulated[t - 1 - i])
+                        .sum::<f64>()
+            };
+        }
+        levels[0] = series[0];
+        for t in 0..diff.len() {
+            levels[t + 1] = levels[t] + simulated[t];
+        }
+        adf_regression(&levels, lags, regression, CovarianceType::Classical)
+            .map(|fit| fit.statistic)
+    });
+    left_tail_p_value(statistics, statistic)
+}
+
+// Bootstrap p-value of an Engle-Granger statistic under no cointegration: pairs of centred
+// increments (Δy_t, Δx_t) are resampled together and integrated, so the legs keep their
+// volatilities and contemporaneous correlation but cannot be cointegrated.
+pub(crate) fn engle_granger_bootstrap_p_value(
+    y: &[f64],
+    x: &[f64],
+    regression: Regression,
+    statistic: f64,
+    replications: usize,
+    seed: u32,
+) -> Option<f64> {
+    let centred = |series: &[f64]| -> Vec<f64> {
+        let diff: Vec<f64> = series.windows(2).map(|w| w[1] - w[0]).collect();
+        let mean = nanmean(&diff, NanPolicy::Propagate);
+        diff.iter().map(|d| d - mean).collect()
+    };
+    let (dy, dx) = (centred(y), centred(x));
+    if dy.is_empty() {
+        return None;
+    }
+    let mut rng = Rng::new(seed);
+
+    let mut y_star = vec![0.0; y.len()];
+    let mut x_star = vec![0.0; x.len()];
+    let statistics = (0..replications).filter_map(|_| {
+        y_star[0] = y[0];
+        x_star[0] = x[0];
+        for t in 1..y.len() {
+            let draw = rng.below(dy.len());
+            y_star[t] = y_star[t - 1] + dy[draw];
+            x_star[t] = x_star[t - 1] + dx[draw];
+        }
+        engle_granger_fit(&y_star, &x_star, regression)
+            .ok()
+            .map(|result| result.statistic)
+    });
+    left_tail_p_value(statistics, statistic)
+}
+
+// AR(lags) coefficients of the differences (with a constant that is then dropped) and the
+// centred residuals to resample
+fn sieve_fit(diff: &[f64], lags: usize) -> Option<(Vec<f64>, Vec<f64>)> {
+    let mean = nanmean(diff, NanPolicy::Propagate);
+    if lags == 0 {
+        return Some((Vec::new(), diff.iter().map(|d| d - mean).collect()));
+    }
+    let n = diff.len();
+    if n <= 2 * lags + 1 {
+        return None;
+    }
+    let mut columns: Vec<Vec<f64>> = (1..=lags).map(|i| diff[lags - i..n - i].to_vec()).collect();
+    columns.push(vec![1.0; n - lags]);
+    let ols = least_squares(&columns, &diff[lags..])?;
+    let residual_mean = nanmean(&ols.residuals, NanPolicy::Propagate);
+    Some((
+        ols.coefficients[..lags].to_vec(),
+        ols.residuals.iter().map(|e| e - residual_mean).collect(),
+    ))
+}
+
+// (1 + #{τ* ≤ τ}) / (1 + B) over the B bootstrap statistics τ*
+fn left_tail_p_value(statistics: impl Iterator<Item = f64>, statistic: f64) -> Option<f64> {
+    let (mut count, mut below) = (0usize, 0usize);
+    for simulated in statistics {
+        count += 1;
+        if simulated <= statistic {
+            below += 1;
+        }
+    }
+    if count == 0 {
+        return None;
+    }
+    Some((1 + below) as f64 / (1 + count) as f64)
+}
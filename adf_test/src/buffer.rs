@@ -0,0 +1,82 @@
+// Caller-filled buffers in wasm linear memory.
+//
+// A `&[f64]` argument is copied from the JS heap into wasm memory on every call,
+// and a returned `Vec<f64>` is copied back out; for multi-megabyte price arrays
+// fed to several functions in turn, those copies cost more than the work. A
+// `Float64Buffer` lives in wasm memory for as long as JS holds it: JS writes the
+// prices once through a Float64Array view, passes the buffer by handle to the
+// `*_buffer` and `*_into` functions, and reads their results through a view of
+// an output buffer that is reused from call to call.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, Default)]
+pub struct Float64Buffer {
+    data: Vec<f64>,
+}
+
+/// Zero-filled buffer of `len` values in wasm memory, to be filled through `view()`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn alloc_f64(len: usize) -> Float64Buffer {
+    Float64Buffer {
+        data: vec![0.0; len],
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Float64Buffer {
+    // Number of values held
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn length(&self) -> usize {
+        self.data.len()
+    }
+
+    // Byte offset of the first value in wasm memory, for
+    // `new Float64Array(memory.buffer, ptr, length)`
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn ptr(&self) -> usize {
+        self.data.as_ptr() as usize
+    }
+
+    /// Float64Array viewing the values in place: writes through it change the buffer without a
+    /// copy.
+    ///
+    /// The view is detached whenever wasm memory grows, which any allocation may cause, and an
+    /// output buffer moves when a call grows it; take a fresh view after each call instead of
+    /// keeping one.
+    #[cfg(feature = "wasm")]
+    pub fn view(&self) -> js_sys::Float64Array {
+        // SAFETY: the view aliases `data`, which stays allocated while JS holds this buffer; the
+        // documented rule above keeps JS from using it across a reallocation
+        unsafe { js_sys::Float64Array::view(&self.data) }
+    }
+
+    /// Copy of the values, for when a detached array is wanted.
+    pub fn to_vec(&self) -> Vec<f64> {
+        self.data.clone()
+    }
+}
+
+impl Float64Buffer {
+    pub fn as_slice(&self) -> &[f64] {
+        &self.data
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [f64] {
+        &mut self.data
+    }
+
+    // The storage an `*_into` function writes its result to; it keeps its capacity between
+    // calls, so a same-sized result does not allocate
+    pub(crate) fn output(&mut self) -> &mut Vec<f64> {
+        &mut self.data
+    }
+}
+
+impl From<Vec<f64>> for Float64Buffer {
+    fn from(data: Vec<f64>) -> Float64Buffer {
+        Float64Buffer { data }
+    }
+}
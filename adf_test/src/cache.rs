@@ -0,0 +1,232 @@
+// Memoisation of test results by a fingerprint of their inputs.
+//
+// Re-rendering a chart or revisiting a pair in a UI asks for the same test on
+// the same series again. The `*_cached` entry points first look the result up
+// by a 128-bit hash of the series' bit patterns and the options, and the cache
+// keeps the most recently used results up to its capacity. Failed computations
+// are not cached, and a cached result is a copy, so callers can free it freely.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use std::sync::{Mutex, MutexGuard};
+
+use crate::coint::CointResult;
+use crate::error::Error;
+use crate::json::JsonObject;
+use crate::AdfResult;
+
+const DEFAULT_CAPACITY: usize = 256;
+
+// A cached result of any of the cached entry points
+#[derive(Clone)]
+pub(crate) enum Cached {
+    Adf(AdfResult),
+    Coint(CointResult),
+}
+
+struct Entry {
+    key: u128,
+    value: Cached,
+    // `Cache::clock` when the entry was last read or written
+    last_used: u64,
+}
+
+struct Cache {
+    entries: Vec<Entry>,
+    capacity: usize,
+    clock: u64,
+    hits: usize,
+    misses: usize,
+}
+
+// A linear scan over a few hundred keys costs far less than the regressions it saves, and keeps
+// the cache constructible in a static
+static CACHE: Mutex<Cache> = Mutex::new(Cache {
+    entries: Vec::new(),
+    capacity: DEFAULT_CAPACITY,
+    clock: 0,
+    hits: 0,
+    misses: 0,
+});
+
+fn cache() -> MutexGuard<'static, Cache> {
+    // A panic while holding the lock leaves the cache consistent, so poisoning is ignored
+    CACHE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+// Incremental 128-bit hash of a computation's inputs: two independent 64-bit lanes (FNV-1a and
+// a multiply-rotate mix) over 64-bit words, each finished with a murmur3 avalanche
+pub(crate) struct Fingerprint {
+    fnv: u64,
+    mix: u64,
+}
+
+impl Fingerprint {
+    // Start a fingerprint for the computation called `tag`, so that different computations on
+    // the same inputs never share a key
+    pub(crate) fn new(tag: &str) -> Fingerprint {
+        let mut fingerprint = Fingerprint {
+            fnv: 0xcbf2_9ce4_8422_2325,
+            mix: 0x9e37_79b9_7f4a_7c15,
+        };
+        fingerprint.bytes(tag.as_bytes());
+        fingerprint
+    }
+
+    fn word(&mut self, word: u64) {
+        self.fnv = (self.fnv ^ word).wrapping_mul(0x0000_0100_0000_01b3);
+        self.mix = (self.mix.rotate_left(23) ^ word).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    }
+
+    // The length and the exact bit pattern of every value
+    pub(crate) fn series(&mut self, values: &[f64]) {
+        self.word(values.len() as u64);
+        for value in values {
+            self.word(value.to_bits());
+        }
+    }
+
+    pub(crate) fn bytes(&mut self, bytes: &[u8]) {
+        self.word(bytes.len() as u64);
+        for chunk in bytes.chunks(8) {
+            let mut word = [0u8; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            self.word(u64::from_le_bytes(word));
+        }
+    }
+
+    pub(crate) fn finish(&self) -> u128 {
+        let avalanche = |mut h: u64| {
+            h ^= h >> 33;
+            h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+            h ^= h >> 33;
+            h = h.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+            h ^ (h >> 33)
+        };
+        ((avalanche(self.fnv) as u128) << 64) | avalanche(self.mix) as u128
+    }
+}
+
+// The cached value for `key`, or the result of `compute`, which is stored when it succeeds.
+// The lock is released while computing.
+pub(crate) fn get_or_compute(
+    key: u128,
+    compute: impl FnOnce() -> Result<Cached, Error>,
+) -> Result<Cached, Error> {
+    {
+        let mut cache = cache();
+        cache.clock += 1;
+        let clock = cache.clock;
+        if let Some(entry) = cache.entries.iter_mut().find(|entry| entry.key == key) {
+            entry.last_used = clock;
+            let value = entry.value.clone();
+            cache.hits += 1;
+            return Ok(value);
+        }
+        cache.misses += 1;
+    }
+
+    let value = compute()?;
+    let mut cache = cache();
+    if cache.capacity == 0 {
+        return Ok(value);
+    }
+    cache.clock += 1;
+    let clock = cache.clock;
+    if let Some(entry) = cache.entries.iter_mut().find(|entry| entry.key == key) {
+        // Another thread stored the same computation meanwhile
+        entry.last_used = clock;
+    } else {
+        let excess = (cache.entries.len() + 1).saturating_sub(cache.capacity);
+        cache.evict(excess);
+        cache.entries.push(Entry {
+            key,
+            value: value.clone(),
+            last_used: clock,
+        });
+    }
+    Ok(value)
+}
+
+impl Cache {
+    // Drop the `count` least recently used entries
+    fn evict(&mut self, count: usize) {
+        if count == 0 {
+            return;
+        }
+        if count >= self.entries.len() {
+            self.entries.clear();
+            return;
+        }
+        let mut ages: Vec<u64> = self.entries.iter().map(|entry| entry.last_used).collect();
+        let (_, &mut cutoff, _) = ages.select_nth_unstable(count - 1);
+        // Clock values are unique, so exactly `count` entries are at or below the cutoff
+        self.entries.retain(|entry| entry.last_used > cutoff);
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct CacheStats {
+    // Lookups answered from the cache
+    pub hits: usize,
+    // Lookups that had to compute the result
+    pub misses: usize,
+    // Results currently stored
+    pub entries: usize,
+    pub capacity: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl CacheStats {
+    // hits / (hits + misses), NaN before the first lookup
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn hit_rate(&self) -> f64 {
+        self.hits as f64 / (self.hits + self.misses) as f64
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("hits", self.hits)
+            .field("misses", self.misses)
+            .field("entries", self.entries)
+            .field("capacity", self.capacity)
+            .field("hit_rate", self.hit_rate())
+            .finish()
+    }
+}
+
+/// Counters and occupancy of the result cache shared by the `*_cached` functions.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn cache_stats() -> CacheStats {
+    let cache = cache();
+    CacheStats {
+        hits: cache.hits,
+        misses: cache.misses,
+        entries: cache.entries.len(),
+        capacity: cache.capacity,
+    }
+}
+
+/// Empty the result cache and reset its hit and miss counters; the capacity is kept.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn cache_clear() {
+    let mut cache = cache();
+    cache.entries.clear();
+    cache.hits = 0;
+    cache.misses = 0;
+}
+
+/// Keep at most `capacity` results (default 256), evicting the least recently used ones beyond
+/// it; 0 turns caching off. Each ADF result is a few dozen bytes, while an Engle-Granger result
+/// holds its residual spread, so size the cache for the series lengths involved.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn cache_set_capacity(capacity: usize) {
+    let mut cache = cache();
+    cache.capacity = capacity;
+    let excess = cache.entries.len().saturating_sub(capacity);
+    cache.evict(excess);
+}
@@ -0,0 +1,614 @@
+// Engle-Granger two-step cointegration test.
+//
+// Step 1 regresses y on x (plus the deterministic terms) to get the hedge ratio;
+// step 2 runs a Dickey-Fuller regression without deterministic terms on the
+// residuals. The residual statistic is compared against MacKinnon's
+// cointegration (two-variable) surfaces rather than the univariate ADF tables,
+// since the residuals were fitted to look as stationary as possible.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::adf::{adf_regression, Regression};
+use crate::bootstrap::engle_granger_bootstrap_p_value;
+use crate::buffer::Float64Buffer;
+use crate::cache::{get_or_compute, Cached, Fingerprint};
+use crate::diagnostics::{
+    arch_lm, durbin_watson, ljung_box, HeteroskedasticityTest, LjungBoxResult,
+};
+use crate::dols::{dols_hedge_ratio, CointegratingVector};
+use crate::error::Error;
+use crate::json::{levels, JsonObject, RawJson};
+#[cfg(feature = "wasm")]
+use crate::json::{parse_payload, payload_series};
+use crate::linalg::{least_squares, CovarianceType};
+use crate::mackinnon::{mackinnon_critical_values, mackinnon_p_value};
+use crate::pvalue::interpolate_critical_value;
+use crate::returns::checked_log;
+#[cfg(feature = "wasm")]
+use crate::rng::parse_seed;
+use crate::rng::DEFAULT_SEED;
+use crate::timing::{StageTimings, Stopwatch};
+use crate::validate::{require_finite, require_length, require_same_length, require_varying};
+use crate::CRITICAL_VALUE_KEYS;
+use crate::CRITICAL_VALUE_LEVELS;
+#[cfg(feature = "wasm")]
+use crate::{critical_values_object, get_property, set_property, CriticalValues};
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(typescript_custom_section)]
+const COINT_RESULT_TS: &'static str = r#"
+/** Plain-object snapshot of a `CointResult`, as returned by `CointResult.to_object()`. */
+export interface CointResultObject {
+  hedge_ratio: number;
+  intercept: number;
+  statistic: number;
+  p_value: number;
+  critical_values: CriticalValues;
+  is_cointegrated: boolean;
+  bootstrap_p_value: number | undefined;
+  dols: CointegratingVector | undefined;
+  timings: StageTimings | undefined;
+  residuals: Float64Array;
+}
+"#;
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "CointResultObject")]
+    pub type CointResultObject;
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone)]
+pub struct CointResult {
+    pub hedge_ratio: f64,
+    pub intercept: f64,
+    pub statistic: f64,
+    pub p_value: f64,
+    critical_values: [f64; 3],
+    pub is_cointegrated: bool,
+    residuals: Vec<f64>,
+    bootstrap_p_value: Option<f64>,
+    dols: Option<CointegratingVector>,
+    timings: Option<StageTimings>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl CointResult {
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(getter)]
+    pub fn critical_values(&self) -> CriticalValues {
+        critical_values_object(self.critical_values)
+    }
+
+    /// Every field of the result as a plain JS object (see `CointResultObject`).
+    #[cfg(feature = "wasm")]
+    pub fn to_object(&self) -> CointResultObject {
+        let object = js_sys::Object::new();
+        set_property(&object, "hedge_ratio", self.hedge_ratio);
+        set_property(&object, "intercept", self.intercept);
+        set_property(&object, "statistic", self.statistic);
+        set_property(&object, "p_value", self.p_value);
+        set_property(&object, "critical_values", self.critical_values());
+        set_property(&object, "is_cointegrated", self.is_cointegrated);
+        set_property(&object, "bootstrap_p_value", self.bootstrap_p_value);
+        set_property(&object, "dols", self.dols.clone());
+        set_property(&object, "timings", self.timings.clone());
+        set_property(
+            &object,
+            "residuals",
+            js_sys::Float64Array::from(self.residuals.as_slice()),
+        );
+        object.unchecked_into()
+    }
+
+    // Critical value at any significance level between 1% and 10%, interpolated across the
+    // 1%/5%/10% values in the normal quantile of the level
+    pub fn critical_value(&self, alpha: f64) -> Result<f64, Error> {
+        interpolate_critical_value(&CRITICAL_VALUE_LEVELS, &self.critical_values, alpha)
+    }
+
+    // Residuals of the cointegrating regression, i.e. the spread y - intercept - hedge_ratio·x
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn residuals(&self) -> Vec<f64> {
+        self.residuals.clone()
+    }
+
+    // Bootstrap p-value of the statistic, or undefined unless a bootstrap was requested
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn bootstrap_p_value(&self) -> Option<f64> {
+        self.bootstrap_p_value
+    }
+
+    // Dynamic OLS hedge ratio with its standard error and confidence interval, or undefined
+    // unless the `dols` option was set
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn dols(&self) -> Option<CointegratingVector> {
+        self.dols.clone()
+    }
+
+    // Time spent in each stage of the call, or undefined unless the `timings` option was set
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn timings(&self) -> Option<StageTimings> {
+        self.timings.clone()
+    }
+
+    /// `ljung_box` of the cointegrating-regression residuals.
+    pub fn ljung_box(&self, lags: usize) -> Result<LjungBoxResult, Error> {
+        ljung_box(&self.residuals, lags)
+    }
+
+    /// `durbin_watson` of the cointegrating-regression residuals.
+    pub fn durbin_watson(&self) -> f64 {
+        durbin_watson(&self.residuals)
+    }
+
+    /// `arch_lm` of the cointegrating-regression residuals.
+    pub fn arch_lm(&self, lags: usize) -> Result<HeteroskedasticityTest, Error> {
+        arch_lm(&self.residuals, lags)
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("hedge_ratio", self.hedge_ratio)
+            .field("intercept", self.intercept)
+            .field("statistic", self.statistic)
+            .field("p_value", self.p_value)
+            .field(
+                "critical_values",
+                levels(&CRITICAL_VALUE_KEYS, &self.critical_values),
+            )
+            .field("is_cointegrated", self.is_cointegrated)
+            .field("bootstrap_p_value", self.bootstrap_p_value)
+            .field(
+                "dols",
+                self.dols.as_ref().map(|dols| RawJson(dols.to_json())),
+            )
+            .field(
+                "timings",
+                self.timings
+                    .as_ref()
+                    .map(|timings| RawJson(timings.to_json())),
+            )
+            .field("residuals", &self.residuals)
+            .finish()
+    }
+}
+
+impl CointResult {
+    // Critical values as [1%, 5%, 10%], for native callers
+    pub fn critical_values_array(&self) -> [f64; 3] {
+        self.critical_values
+    }
+}
+
+pub(crate) fn engle_granger_fit(
+    y: &[f64],
+    x: &[f64],
+    regression: Regression,
+) -> Result<CointResult, Error> {
+    engle_granger_fit_timed(y, x, regression, &mut Stopwatch::new(false))
+}
+
+// `engle_granger_fit` with each stage closed on `stopwatch`
+fn engle_granger_fit_timed(
+    y: &[f64],
+    x: &[f64],
+    regression: Regression,
+    stopwatch: &mut Stopwatch,
+) -> Result<CointResult, Error> {
+    require_same_length(y, x)?;
+    require_finite(y, "y")?;
+    require_finite(x, "x")?;
+    require_length(
+        y,
+        (regression.deterministic_terms() + 2).max(3),
+        "y",
+        "the Engle-Granger test",
+    )?;
+    require_varying(x, "x")?;
+    stopwatch.lap("validation");
+
+    let nobs = y.len();
+    let mut columns = vec![x.to_vec()];
+    regression.push_columns(&mut columns, nobs);
+    let ols = least_squares(&columns, y).ok_or_else(|| {
+        Error::new("cointegrating regression failed: series too short or x is constant")
+    })?;
+    let hedge_ratio = ols.coefficients[0];
+    let intercept = ols.coefficients.get(1).copied().unwrap_or(0.0);
+    stopwatch.lap("regression");
+
+    // Plain Dickey-Fuller on the residuals, as in the original Engle-Granger procedure
+    let residual_adf = adf_regression(
+        &ols.residuals,
+        0,
+        Regression::NoConstant,
+        CovarianceType::Classical,
+    )
+    .ok_or_else(|| Error::new("residual unit-root regression failed: residuals are degenerate"))?;
+    let statistic = residual_adf.statistic;
+    stopwatch.lap("residual_test");
+
+    let p_value = mackinnon_p_value(statistic, regression, 2);
+    let critical_values = mackinnon_critical_values(regression, 2, nobs - 1)
+        .ok_or_else(|| Error::new("no cointegration critical values for this regression"))?;
+    let is_cointegrated = p_value <= 0.05 && statistic < critical_values[1];
+    stopwatch.lap("p_value");
+
+    Ok(CointResult {
+        hedge_ratio,
+        intercept,
+        statistic,
+        p_value,
+        critical_values,
+        is_cointegrated,
+        residuals: ols.residuals,
+        bootstrap_p_value: None,
+        dols: None,
+        timings: None,
+    })
+}
+
+/// Engle-Granger two-step cointegration test of y against x.
+///
+/// Runs the cointegrating regression y = intercept + hedge_ratio·x + u, then a Dickey-Fuller
+/// test on the residuals u judged against MacKinnon's two-variable cointegration critical
+/// values (about -3.34 at 5% asymptotically, stricter than the univariate -2.86). Returns the
+/// hedge ratio, intercept, residual spread and test outcome together.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn engle_granger(y: &[f64], x: &[f64]) -> Result<CointResult, Error> {
+    engle_granger_fit(y, x, Regression::Constant)
+}
+
+/// `engle_granger` driven by JSON text: `payload` is `{"y": [...], "x": [...]}` and the result
+/// is returned as `CointResult.to_json()`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn engle_granger_from_json(payload: &str) -> Result<String, Error> {
+    let payload = parse_payload(payload)?;
+    let y = payload_series(&payload, "y")?;
+    let x = payload_series(&payload, "x")?;
+    Ok(engle_granger(&y, &x)?.to_json())
+}
+
+/// `engle_granger` on the values in two `Float64Buffer`s, read in place instead of copied into
+/// wasm memory.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn engle_granger_buffer(y: &Float64Buffer, x: &Float64Buffer) -> Result<CointResult, Error> {
+    engle_granger_fit(y.as_slice(), x.as_slice(), Regression::Constant)
+}
+
+/// `engle_granger`, answered from the result cache when the same pair was tested before (see
+/// `cache_stats`). The legs are identified by a hash of their exact values.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn engle_granger_cached(y: &[f64], x: &[f64]) -> Result<CointResult, Error> {
+    let mut fingerprint = Fingerprint::new("engle_granger");
+    fingerprint.series(y);
+    fingerprint.series(x);
+    let cached = get_or_compute(fingerprint.finish(), || {
+        engle_granger(y, x).map(Cached::Coint)
+    })?;
+    match cached {
+        Cached::Coint(result) => Ok(result),
+        _ => Err(Error::new(
+            "the cached result is not an Engle-Granger result",
+        )),
+    }
+}
+
+/// `engle_granger` with a bootstrap p-value from `replications` resampled pairs of
+/// non-cointegrated legs, reported in `bootstrap_p_value` alongside the tabulated `p_value`.
+///
+/// The legs' increments are resampled jointly and integrated, which keeps their volatilities and
+/// correlation but rules out cointegration, and each replication repeats the full two-step test.
+/// Worth it below about 100 observations, where MacKinnon's asymptotic surfaces are unreliable.
+/// The same `seed` (default 0) always gives the same p-value.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn engle_granger_bootstrap(
+    y: &[f64],
+    x: &[f64],
+    replications: usize,
+    seed: Option<u32>,
+) -> Result<CointResult, Error> {
+    if replications == 0 {
+        return Err(Error::new("replications must be positive"));
+    }
+    let options = EgOptions {
+        bootstrap: replications,
+        seed: seed.unwrap_or(DEFAULT_SEED),
+        ..EgOptions::default()
+    };
+    engle_granger_with_config(y, x, &options)
+}
+
+/// Options of `engle_granger_with_config`; the default matches an empty options object and
+/// gives the plain `engle_granger` test.
+///
+/// From JS, `new EgOptions(options)` reads the plain object `engle_granger_with_options` takes,
+/// and each `with_*` method validates and sets one option, consuming the options it is called
+/// on.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug)]
+pub struct EgOptions {
+    // Deterministic terms of the cointegrating regression
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub regression: Regression,
+    // Log-transform both legs first, as `engle_granger_log`
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub log_prices: bool,
+    // Bootstrap replications; 0 disables the bootstrap
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub bootstrap: usize,
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub seed: u32,
+    // Also estimate the hedge ratio by dynamic OLS, for its standard error
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub dols: bool,
+    // Leads and lags of the DOLS regression; None for ⌊n^(1/3)⌋
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub leads_lags: Option<usize>,
+    // Record the time spent in each stage in `CointResult.timings`
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub timings: bool,
+}
+
+impl Default for EgOptions {
+    fn default() -> EgOptions {
+        EgOptions {
+            regression: Regression::Constant,
+            log_prices: false,
+            bootstrap: 0,
+            seed: DEFAULT_SEED,
+            dols: false,
+            leads_lags: None,
+            timings: false,
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl EgOptions {
+    /// Options read from a plain object with the fields `engle_granger_with_options` documents;
+    /// undefined or null gives the defaults.
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(constructor)]
+    pub fn new(options: JsValue) -> Result<EgOptions, Error> {
+        EgOptions::from_js(&options)
+    }
+
+    /// Deterministic terms "n", "c", "ct" or "ctt" of the cointegrating regression.
+    pub fn with_regression(mut self, regression: &str) -> Result<EgOptions, Error> {
+        self.regression = Regression::parse(regression)?;
+        Ok(self)
+    }
+
+    /// Whether both legs are log-transformed before the regression.
+    pub fn with_log_prices(mut self, log_prices: bool) -> EgOptions {
+        self.log_prices = log_prices;
+        self
+    }
+
+    /// Bootstrap replications, 0 for none.
+    pub fn with_bootstrap(mut self, bootstrap: usize) -> EgOptions {
+        self.bootstrap = bootstrap;
+        self
+    }
+
+    /// Seed of the bootstrap.
+    pub fn with_seed(mut self, seed: u32) -> EgOptions {
+        self.seed = seed;
+        self
+    }
+
+    /// Add a dynamic OLS hedge ratio with `leads_lags` leads and lags (None for ⌊n^(1/3)⌋) to
+    /// the result, as `dols_hedge_ratio`.
+    pub fn with_dols(mut self, leads_lags: Option<usize>) -> EgOptions {
+        self.dols = true;
+        self.leads_lags = leads_lags;
+        self
+    }
+
+    /// Whether the result carries the wall-clock time of each stage.
+    pub fn with_timings(mut self, timings: bool) -> EgOptions {
+        self.timings = timings;
+        self
+    }
+}
+
+impl EgOptions {
+    #[cfg(feature = "wasm")]
+    pub(crate) fn from_js(options: &JsValue) -> Result<EgOptions, Error> {
+        let mut parsed = EgOptions::default();
+        if options.is_undefined() || options.is_null() {
+            return Ok(parsed);
+        }
+        if !options.is_object() {
+            return Err(Error::new("options must be an object"));
+        }
+
+        let regression = get_property(options, "regression")?;
+        if !regression.is_undefined() {
+            let name = regression
+                .as_string()
+                .ok_or_else(|| Error::new("regression must be a string"))?;
+            parsed = parsed.with_regression(&name)?;
+        }
+
+        let log_prices = get_property(options, "log_prices")?;
+        if !log_prices.is_undefined() {
+            let value = log_prices
+                .as_bool()
+                .ok_or_else(|| Error::new("log_prices must be a boolean"))?;
+            parsed = parsed.with_log_prices(value);
+        }
+
+        let bootstrap = get_property(options, "bootstrap")?;
+        if !bootstrap.is_undefined() {
+            let value = bootstrap
+                .as_f64()
+                .filter(|v| *v >= 0.0 && v.fract() == 0.0)
+                .ok_or_else(|| Error::new("bootstrap must be a non-negative integer"))?;
+            parsed = parsed.with_bootstrap(value as usize);
+        }
+
+        let seed = get_property(options, "seed")?;
+        if !seed.is_undefined() {
+            parsed = parsed.with_seed(parse_seed(&seed)?);
+        }
+
+        let dols = get_property(options, "dols")?;
+        if !dols.is_undefined() {
+            let value = dols
+                .as_bool()
+                .ok_or_else(|| Error::new("dols must be a boolean"))?;
+            parsed.dols = value;
+        }
+
+        let leads_lags = get_property(options, "leads_lags")?;
+        if !leads_lags.is_undefined() && !leads_lags.is_null() {
+            let value = leads_lags
+                .as_f64()
+                .filter(|v| *v >= 0.0 && v.fract() == 0.0)
+                .ok_or_else(|| Error::new("leads_lags must be a non-negative integer"))?;
+            parsed.leads_lags = Some(value as usize);
+        }
+
+        let timings = get_property(options, "timings")?;
+        if !timings.is_undefined() {
+            let value = timings
+                .as_bool()
+                .ok_or_else(|| Error::new("timings must be a boolean"))?;
+            parsed.timings = value;
+        }
+        Ok(parsed)
+    }
+}
+
+/// Engle-Granger test of y against x configured by an optional `options` object:
+///
+/// - `regression`: deterministic terms of the cointegrating regression, `"n"`, `"c"`
+///   (default), `"ct"` or `"ctt"`
+/// - `log_prices`: log-transform both legs first, as `engle_granger_log` (default false)
+/// - `bootstrap`: replications of the bootstrap p-value of `engle_granger_bootstrap` (default
+///   0, no bootstrap)
+/// - `seed`: integer between 0 and 2³²-1 seeding the bootstrap (default 0)
+/// - `dols`: also estimate the hedge ratio by dynamic OLS, reported with its standard error and
+///   confidence interval in `CointResult.dols` (default false)
+/// - `leads_lags`: leads and lags of the DOLS regression (default ⌊n^(1/3)⌋)
+/// - `timings`: record the wall-clock milliseconds of each stage ("validation", "regression",
+///   "residual_test", "p_value", and "log_prices", "bootstrap" or "dols" when they run) in
+///   `CointResult.timings` (default false)
+///
+/// The same options as an `EgOptions` object go to `engle_granger_with_config`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn engle_granger_with_options(
+    y: &[f64],
+    x: &[f64],
+    options: JsValue,
+) -> Result<CointResult, Error> {
+    engle_granger_with_config(y, x, &EgOptions::from_js(&options)?)
+}
+
+/// `engle_granger_with_options` with the options as an `EgOptions` object.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn engle_granger_with_config(
+    y: &[f64],
+    x: &[f64],
+    options: &EgOptions,
+) -> Result<CointResult, Error> {
+    engle_granger_timed(y, x, options, Stopwatch::new(options.timings))
+}
+
+// `engle_granger_with_config` continuing on `stopwatch`, which may already hold earlier stages
+// such as the alignment of the legs
+pub(crate) fn engle_granger_timed(
+    y: &[f64],
+    x: &[f64],
+    options: &EgOptions,
+    mut stopwatch: Stopwatch,
+) -> Result<CointResult, Error> {
+    let logs;
+    let (y, x) = if options.log_prices {
+        logs = (checked_log(y, "y")?, checked_log(x, "x")?);
+        stopwatch.lap("log_prices");
+        (logs.0.as_slice(), logs.1.as_slice())
+    } else {
+        (y, x)
+    };
+    let mut result = engle_granger_fit_timed(y, x, options.regression, &mut stopwatch)?;
+    if options.bootstrap > 0 {
+        result.bootstrap_p_value = engle_granger_bootstrap_p_value(
+            y,
+            x,
+            options.regression,
+            result.statistic,
+            options.bootstrap,
+            options.seed,
+        );
+        stopwatch.lap("bootstrap");
+    }
+    if options.dols {
+        result.dols = Some(dols_hedge_ratio(y, x, options.leads_lags, None)?);
+        stopwatch.lap("dols");
+    }
+    result.timings = stopwatch.finish();
+    Ok(result)
+}
+
+/// Engle-Granger test on log-prices: both legs are log-transformed before the cointegrating
+/// regression, which is what most cointegration theory assumes for price series.
+///
+/// The hedge ratio is then an elasticity and is interpreted multiplicatively: the equilibrium
+/// is y ≈ e^intercept · x^hedge_ratio, so a 1% move in x corresponds to a hedge_ratio% move in
+/// y, and the residuals are log-spreads. Errors if any price is not strictly positive.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn engle_granger_log(y: &[f64], x: &[f64], regression: &str) -> Result<CointResult, Error> {
+    let options = EgOptions::default()
+        .with_regression(regression)?
+        .with_log_prices(true);
+    engle_granger_with_config(y, x, &options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    // log y = 0.5 + 1.5·log x + AR(1) noise, with log x a drifting random walk: the log-prices
+    // are cointegrated, but y ≈ e^0.5·x^1.5 is curved over the wide range x covers, so no
+    // linear combination of the levels is stationary
+    fn multiplicative_pair(n: usize, seed: u32) -> (Vec<f64>, Vec<f64>) {
+        let mut rng = Rng::new(seed);
+        let mut log_x = 3.0;
+        let mut noise = 0.0;
+        let mut y = Vec::with_capacity(n);
+        let mut x = Vec::with_capacity(n);
+        for _ in 0..n {
+            log_x += 0.003 + 0.01 * rng.normal();
+            noise = 0.5 * noise + 0.01 * rng.normal();
+            x.push(log_x.exp());
+            y.push((0.5 + 1.5 * log_x + noise).exp());
+        }
+        (y, x)
+    }
+
+    #[test]
+    fn log_prices_are_cointegrated_when_levels_are_not() {
+        let (y, x) = multiplicative_pair(1000, 11);
+
+        let log = engle_granger_log(&y, &x, "c").unwrap();
+        assert!(log.is_cointegrated);
+        assert!(log.p_value < 0.01);
+        assert!((log.hedge_ratio - 1.5).abs() < 0.01);
+        assert!((log.intercept - 0.5).abs() < 0.05);
+
+        let levels = engle_granger(&y, &x).unwrap();
+        assert!(!levels.is_cointegrated);
+        assert!(levels.p_value > 0.10);
+    }
+}
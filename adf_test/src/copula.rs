@@ -0,0 +1,450 @@
+// Copula dependence measures for pair selection and signals.
+//
+// A copula describes how two series move together separately from their
+// marginal distributions. The series are first mapped to pseudo-observations
+// u = rank / (n + 1) in (0, 1), which leaves only the dependence: how often they
+// crash together (lower tail dependence), rally together (upper), and how
+// surprising today's pair of values is under the fitted dependence. The
+// Gaussian copula has no tail dependence, Clayton has lower-tail and Gumbel
+// upper-tail dependence; each is fitted by maximum likelihood and compared on
+// AIC.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::correlation::{average_ranks, correlation_with, CorrelationMethod};
+use crate::distributions::{normal_cdf, normal_ppf};
+use crate::error::Error;
+use crate::json::{JsonObject, RawJson};
+use crate::validate::{require_finite, require_length, require_same_length};
+
+// Parametric family of a fitted copula
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CopulaFamily {
+    // Normal dependence with correlation ρ; no tail dependence
+    Gaussian,
+    // Lower-tail dependence 2^(-1/θ), θ > 0
+    Clayton,
+    // Upper-tail dependence 2 - 2^(1/θ), θ >= 1
+    Gumbel,
+}
+
+impl CopulaFamily {
+    pub fn parse(family: &str) -> Result<CopulaFamily, Error> {
+        match family {
+            "gaussian" => Ok(CopulaFamily::Gaussian),
+            "clayton" => Ok(CopulaFamily::Clayton),
+            "gumbel" => Ok(CopulaFamily::Gumbel),
+            other => Err(Error::new(&format!(
+                "unknown copula family \"{}\": expected \"gaussian\", \"clayton\" or \"gumbel\"",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CopulaFamily::Gaussian => "gaussian",
+            CopulaFamily::Clayton => "clayton",
+            CopulaFamily::Gumbel => "gumbel",
+        }
+    }
+
+    const ALL: [CopulaFamily; 3] = [
+        CopulaFamily::Gaussian,
+        CopulaFamily::Clayton,
+        CopulaFamily::Gumbel,
+    ];
+
+    // Range searched for the maximum-likelihood parameter
+    fn bounds(&self) -> (f64, f64) {
+        match self {
+            CopulaFamily::Gaussian => (-0.999, 0.999),
+            CopulaFamily::Clayton => (1e-4, 50.0),
+            CopulaFamily::Gumbel => (1.0, 50.0),
+        }
+    }
+
+    // Log density of the copula at (u, v)
+    fn log_density(&self, theta: f64, u: f64, v: f64) -> f64 {
+        match self {
+            CopulaFamily::Gaussian => {
+                let (x, y) = (normal_ppf(u), normal_ppf(v));
+                let r2 = theta * theta;
+                -0.5 * (1.0 - r2).ln()
+                    - (r2 * (x * x + y * y) - 2.0 * theta * x * y) / (2.0 * (1.0 - r2))
+            }
+            CopulaFamily::Clayton => {
+                (1.0 + theta).ln()
+                    - (1.0 + theta) * (u.ln() + v.ln())
+                    - (2.0 + 1.0 / theta) * (u.powf(-theta) + v.powf(-theta) - 1.0).ln()
+            }
+            CopulaFamily::Gumbel => {
+                let (a, b) = (-u.ln(), -v.ln());
+                let sum = a.powf(theta) + b.powf(theta);
+                let root = sum.powf(1.0 / theta);
+                -root - u.ln() - v.ln()
+                    + (theta - 1.0) * (a.ln() + b.ln())
+                    + (2.0 / theta - 2.0) * sum.ln()
+                    + (1.0 + (theta - 1.0) / root).ln()
+            }
+        }
+    }
+
+    // P(U <= u | V = v), the partial derivative ∂C(u, v)/∂v
+    fn conditional(&self, theta: f64, u: f64, v: f64) -> f64 {
+        match self {
+            CopulaFamily::Gaussian => {
+                normal_cdf((normal_ppf(u) - theta * normal_ppf(v)) / (1.0 - theta * theta).sqrt())
+            }
+            CopulaFamily::Clayton => {
+                v.powf(-theta - 1.0)
+                    * (u.powf(-theta) + v.powf(-theta) - 1.0).powf(-1.0 / theta - 1.0)
+            }
+            CopulaFamily::Gumbel => {
+                let (a, b) = (-u.ln(), -v.ln());
+                let sum = a.powf(theta) + b.powf(theta);
+                (-sum.powf(1.0 / theta)).exp() * sum.powf(1.0 / theta - 1.0) * b.powf(theta - 1.0)
+                    / v
+            }
+        }
+    }
+
+    // Lower and upper tail dependence implied by the parameter
+    fn tail_dependence(&self, theta: f64) -> (f64, f64) {
+        match self {
+            CopulaFamily::Gaussian => (0.0, 0.0),
+            CopulaFamily::Clayton => (2f64.powf(-1.0 / theta), 0.0),
+            CopulaFamily::Gumbel => (0.0, 2.0 - 2f64.powf(1.0 / theta)),
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug)]
+pub struct CopulaFit {
+    family: CopulaFamily,
+    // ρ of the Gaussian copula, θ of Clayton or Gumbel
+    pub parameter: f64,
+    pub kendall_tau: f64,
+    pub log_likelihood: f64,
+    pub aic: f64,
+    // Tail dependence implied by the fitted family and parameter
+    pub lower_tail: f64,
+    pub upper_tail: f64,
+    // AIC of each family at its own maximum-likelihood parameter
+    pub gaussian_aic: f64,
+    pub clayton_aic: f64,
+    pub gumbel_aic: f64,
+    pub nobs: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl CopulaFit {
+    // "gaussian", "clayton" or "gumbel"
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn family(&self) -> String {
+        self.family.as_str().to_string()
+    }
+
+    /// P(U <= u | V = v) under the fitted copula, for pseudo-observations `u` and `v` in (0, 1).
+    pub fn conditional(&self, u: f64, v: f64) -> f64 {
+        self.family.conditional(self.parameter, u, v)
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("family", self.family.as_str())
+            .field("parameter", self.parameter)
+            .field("kendall_tau", self.kendall_tau)
+            .field("log_likelihood", self.log_likelihood)
+            .field("aic", self.aic)
+            .field("lower_tail", self.lower_tail)
+            .field("upper_tail", self.upper_tail)
+            .field("gaussian_aic", self.gaussian_aic)
+            .field("clayton_aic", self.clayton_aic)
+            .field("gumbel_aic", self.gumbel_aic)
+            .field("nobs", self.nobs)
+            .finish()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct TailDependence {
+    // P(V <= q | U <= q): how often y is in its lowest q-quantile when x is
+    pub lower: f64,
+    // P(V > 1 - q | U > 1 - q)
+    pub upper: f64,
+    pub quantile: f64,
+    pub nobs: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl TailDependence {
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("lower", self.lower)
+            .field("upper", self.upper)
+            .field("quantile", self.quantile)
+            .field("nobs", self.nobs)
+            .finish()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct MispricingIndex {
+    fit: CopulaFit,
+    x_given_y: Vec<f64>,
+    y_given_x: Vec<f64>,
+    cumulative_x: Vec<f64>,
+    cumulative_y: Vec<f64>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl MispricingIndex {
+    // Copula fitted on the formation window
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn fit(&self) -> CopulaFit {
+        self.fit.clone()
+    }
+
+    // P(U <= u_t | V = v_t) - 0.5 at every bar: positive when x is high given y
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn x_given_y(&self) -> Vec<f64> {
+        self.x_given_y.clone()
+    }
+
+    // P(V <= v_t | U = u_t) - 0.5 at every bar: positive when y is high given x
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn y_given_x(&self) -> Vec<f64> {
+        self.y_given_x.clone()
+    }
+
+    // Running sums of `x_given_y`, the flag series of Xie et al. (2016)
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn cumulative_x(&self) -> Vec<f64> {
+        self.cumulative_x.clone()
+    }
+
+    // Running sums of `y_given_x`
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn cumulative_y(&self) -> Vec<f64> {
+        self.cumulative_y.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("fit", RawJson(self.fit.to_json()))
+            .field("x_given_y", &self.x_given_y)
+            .field("y_given_x", &self.y_given_x)
+            .field("cumulative_x", &self.cumulative_x)
+            .field("cumulative_y", &self.cumulative_y)
+            .finish()
+    }
+}
+
+/// Fit a copula to the dependence of `x` and `y`, typically the two legs' returns.
+///
+/// The series are reduced to pseudo-observations rank / (n + 1), and the Gaussian, Clayton and
+/// Gumbel copulas are each fitted by maximum likelihood. With `family` ("gaussian", "clayton"
+/// or "gumbel") that family is reported; without it, the one with the lowest AIC. Clayton and
+/// Gumbel only model positive dependence, so for negatively dependent series they settle at
+/// independence and the Gaussian copula wins.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn fit_copula(x: &[f64], y: &[f64], family: Option<String>) -> Result<CopulaFit, Error> {
+    check_pair(x, y)?;
+    let family = family.as_deref().map(CopulaFamily::parse).transpose()?;
+    let (u, v) = (pseudo_observations(x), pseudo_observations(y));
+    Ok(fit_pseudo(
+        &u,
+        &v,
+        correlation_with(x, y, CorrelationMethod::Kendall),
+        family,
+    ))
+}
+
+/// Empirical tail dependence of `x` and `y` at `quantile` q (default 0.05): the share of the
+/// bars where x is in its lowest q-quantile on which y is too (`lower`), and likewise for the
+/// highest q-quantiles (`upper`). Independent series give about q, perfectly dependent ones 1.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn tail_dependence(
+    x: &[f64],
+    y: &[f64],
+    quantile: Option<f64>,
+) -> Result<TailDependence, Error> {
+    check_pair(x, y)?;
+    let q = quantile.unwrap_or(0.05);
+    if !(q > 0.0 && q < 0.5) {
+        return Err(Error::new("quantile must be in (0, 0.5)"));
+    }
+    let (u, v) = (pseudo_observations(x), pseudo_observations(y));
+    let share = |in_tail: &dyn Fn(f64) -> bool| {
+        let conditioning = u.iter().filter(|&&a| in_tail(a)).count();
+        let joint = u
+            .iter()
+            .zip(&v)
+            .filter(|&(&a, &b)| in_tail(a) && in_tail(b))
+            .count();
+        joint as f64 / conditioning as f64
+    };
+    Ok(TailDependence {
+        lower: share(&|p| p <= q),
+        upper: share(&|p| p > 1.0 - q),
+        quantile: q,
+        nobs: x.len(),
+    })
+}
+
+/// Copula mispricing index of a pair (Liew & Wu 2013; Xie et al. 2016), from the legs' returns
+/// `x` and `y`.
+///
+/// The marginals (empirical distribution functions) and the copula, of `family` or the best on
+/// AIC as in `fit_copula`, are estimated on the first `formation` bars; every bar, including
+/// the later out-of-sample ones, is then mapped through them to the conditional probabilities
+/// P(U <= u | V = v) and P(V <= v | U = u), centred at 0. A large positive `x_given_y` says x
+/// rose more than y's move makes likely, i.e. x is rich relative to y; the cumulative indices
+/// add these up and are the usual trading flags.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn copula_mispricing(
+    x: &[f64],
+    y: &[f64],
+    formation: usize,
+    family: Option<String>,
+) -> Result<MispricingIndex, Error> {
+    require_same_length(x, y)?;
+    if formation > x.len() {
+        return Err(Error::new("formation exceeds the length of the series"));
+    }
+    let (formation_x, formation_y) = (&x[..formation], &y[..formation]);
+    let fit = fit_copula(formation_x, formation_y, family)?;
+    require_finite(x, "x")?;
+    require_finite(y, "y")?;
+
+    let (cdf_x, cdf_y) = (empirical_cdf(formation_x), empirical_cdf(formation_y));
+    let mut x_given_y = Vec::with_capacity(x.len());
+    let mut y_given_x = Vec::with_capacity(x.len());
+    for (&a, &b) in x.iter().zip(y) {
+        let (u, v) = (cdf_x(a), cdf_y(b));
+        x_given_y.push(fit.family.conditional(fit.parameter, u, v) - 0.5);
+        y_given_x.push(fit.family.conditional(fit.parameter, v, u) - 0.5);
+    }
+    let running = |values: &[f64]| -> Vec<f64> {
+        values
+            .iter()
+            .scan(0.0, |sum, v| {
+                *sum += v;
+                Some(*sum)
+            })
+            .collect()
+    };
+    Ok(MispricingIndex {
+        cumulative_x: running(&x_given_y),
+        cumulative_y: running(&y_given_x),
+        fit,
+        x_given_y,
+        y_given_x,
+    })
+}
+
+fn check_pair(x: &[f64], y: &[f64]) -> Result<(), Error> {
+    require_same_length(x, y)?;
+    require_finite(x, "x")?;
+    require_finite(y, "y")?;
+    require_length(x, 10, "x", "a copula fit")
+}
+
+// rank / (n + 1), ties sharing their average rank
+fn pseudo_observations(values: &[f64]) -> Vec<f64> {
+    let scale = values.len() as f64 + 1.0;
+    average_ranks(values)
+        .into_iter()
+        .map(|r| r / scale)
+        .collect()
+}
+
+// Empirical distribution function of `sample` rescaled to (0, 1) as the pseudo-observations
+// are, so values outside the sample map to 1 / (n + 1) and n / (n + 1) rather than 0 and 1
+fn empirical_cdf(sample: &[f64]) -> impl Fn(f64) -> f64 {
+    let mut sorted = sample.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let scale = sorted.len() as f64 + 1.0;
+    move |value| {
+        let below = sorted.partition_point(|&s| s <= value) as f64;
+        below.clamp(1.0, scale - 1.0) / scale
+    }
+}
+
+fn fit_pseudo(u: &[f64], v: &[f64], kendall_tau: f64, family: Option<CopulaFamily>) -> CopulaFit {
+    let log_likelihood = |family: CopulaFamily, theta: f64| -> f64 {
+        u.iter()
+            .zip(v)
+            .map(|(&a, &b)| family.log_density(theta, a, b))
+            .sum()
+    };
+    let fits: Vec<(CopulaFamily, f64, f64)> = CopulaFamily::ALL
+        .iter()
+        .map(|&family| {
+            let (low, high) = family.bounds();
+            let theta = golden_section(low, high, |theta| -log_likelihood(family, theta));
+            (family, theta, log_likelihood(family, theta))
+        })
+        .collect();
+    let aic = |ll: f64| 2.0 - 2.0 * ll;
+    let chosen = match family {
+        Some(family) => fits.iter().find(|fit| fit.0 == family).copied(),
+        None => fits
+            .iter()
+            .min_by(|a, b| aic(a.2).total_cmp(&aic(b.2)))
+            .copied(),
+    };
+    let (family, parameter, ll) = chosen.unwrap_or(fits[0]);
+    let (lower_tail, upper_tail) = family.tail_dependence(parameter);
+    CopulaFit {
+        family,
+        parameter,
+        kendall_tau,
+        log_likelihood: ll,
+        aic: aic(ll),
+        lower_tail,
+        upper_tail,
+        gaussian_aic: aic(fits[0].2),
+        clayton_aic: aic(fits[1].2),
+        gumbel_aic: aic(fits[2].2),
+        nobs: u.len(),
+    }
+}
+
+// Minimiser of `f` on [low, high] by golden-section search; NaN values count as +∞
+fn golden_section(mut low: f64, mut high: f64, f: impl Fn(f64) -> f64) -> f64 {
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let value = |x: f64| {
+        let y = f(x);
+        if y.is_nan() {
+            f64::INFINITY
+        } else {
+            y
+        }
+    };
+    let mut a = high - ratio * (high - low);
+    let mut b = low + ratio * (high - low);
+    let (mut fa, mut fb) = (value(a), value(b));
+    for _ in 0..100 {
+        if fa < fb {
+            high = b;
+            b = a;
+            fb = fa;
+            a = high - ratio * (high - low);
+            fa = value(a);
+        } else {
+            low = a;
+            a = b;
+            fa = fb;
+            b = low + ratio * (high - low);
+            fb = value(b);
+        }
+    }
+    (low + high) / 2.0
+}
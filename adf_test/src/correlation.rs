@@ -0,0 +1,227 @@
+// Pearson and rank (Spearman, Kendall) correlations.
+//
+// Rank correlations only look at the ordering of the observations, so a few
+// outliers or a non-linear but monotonic relationship between two prices do not
+// distort them. Spearman is Pearson on average ranks; Kendall's tau-b uses
+// Knight's O(n log n) merge-sort count of discordant pairs instead of comparing
+// every pair of observations.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::error::Error;
+use crate::stats::pearson;
+
+/// Correlation coefficient computed by `correlation`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CorrelationMethod {
+    Pearson,
+    Spearman,
+    /// Kendall's tau-b, which corrects for ties in either series.
+    Kendall,
+}
+
+impl CorrelationMethod {
+    pub fn parse(method: &str) -> Result<CorrelationMethod, Error> {
+        match method {
+            "pearson" => Ok(CorrelationMethod::Pearson),
+            "spearman" => Ok(CorrelationMethod::Spearman),
+            "kendall" => Ok(CorrelationMethod::Kendall),
+            other => Err(Error::new(&format!(
+                "unknown correlation method \"{}\": expected \"pearson\", \"spearman\" or \"kendall\"",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CorrelationMethod::Pearson => "pearson",
+            CorrelationMethod::Spearman => "spearman",
+            CorrelationMethod::Kendall => "kendall",
+        }
+    }
+}
+
+/// Correlation of `x` and `y` by `method`: "pearson", "spearman" or "kendall" (tau-b).
+///
+/// Ties get average ranks, as in scipy and pandas. The result is NaN if fewer than two pairs
+/// are given, any value is NaN, or either series is constant.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn correlation(x: &[f64], y: &[f64], method: &str) -> Result<f64, Error> {
+    let method = CorrelationMethod::parse(method)?;
+    if x.len() != y.len() {
+        return Err(Error::new("x and y must have the same length"));
+    }
+    Ok(correlation_with(x, y, method))
+}
+
+/// Correlation matrix of the `n_cols` columns of the row-major `matrix` (one row per
+/// observation), by `method` as in `correlation`. Returns `n_cols * n_cols` values, row-major,
+/// with 1 on the diagonal (NaN for a constant or NaN-containing column).
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn correlation_matrix(matrix: &[f64], n_cols: usize, method: &str) -> Result<Vec<f64>, Error> {
+    let method = CorrelationMethod::parse(method)?;
+    if n_cols == 0 || !matrix.len().is_multiple_of(n_cols) {
+        return Err(Error::new(
+            "matrix length must be a positive multiple of n_cols",
+        ));
+    }
+
+    let mut columns = vec![Vec::with_capacity(matrix.len() / n_cols); n_cols];
+    for row in matrix.chunks_exact(n_cols) {
+        for (column, &value) in columns.iter_mut().zip(row) {
+            column.push(value);
+        }
+    }
+    // Spearman is Pearson on ranks, so rank every column once instead of once per pair
+    if method == CorrelationMethod::Spearman {
+        for column in columns.iter_mut() {
+            *column = average_ranks(column);
+        }
+    }
+    let pairwise = match method {
+        CorrelationMethod::Kendall => CorrelationMethod::Kendall,
+        CorrelationMethod::Pearson | CorrelationMethod::Spearman => CorrelationMethod::Pearson,
+    };
+
+    let mut result = vec![f64::NAN; n_cols * n_cols];
+    for i in 0..n_cols {
+        for j in i..n_cols {
+            let value = if i == j {
+                // Exactly 1, except that a constant or NaN column stays NaN like its other entries
+                if correlation_with(&columns[i], &columns[i], pairwise).is_nan() {
+                    f64::NAN
+                } else {
+                    1.0
+                }
+            } else {
+                correlation_with(&columns[i], &columns[j], pairwise)
+            };
+            result[i * n_cols + j] = value;
+            result[j * n_cols + i] = value;
+        }
+    }
+    Ok(result)
+}
+
+/// `correlation` for native callers, with the method as an enum. Series of different lengths
+/// give NaN.
+pub fn correlation_with(x: &[f64], y: &[f64], method: CorrelationMethod) -> f64 {
+    if x.len() != y.len() || x.len() < 2 || x.iter().chain(y).any(|v| v.is_nan()) {
+        return f64::NAN;
+    }
+    match method {
+        CorrelationMethod::Pearson => pearson(x, y),
+        CorrelationMethod::Spearman => pearson(&average_ranks(x), &average_ranks(y)),
+        CorrelationMethod::Kendall => kendall_tau_b(x, y),
+    }
+}
+
+// 1-based ranks, tied values sharing the average of the ranks they span
+pub(crate) fn average_ranks(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && values[order[end]] == values[order[start]] {
+            end += 1;
+        }
+        // Positions start..end hold ranks start+1..=end
+        let rank = (start + end + 1) as f64 / 2.0;
+        for &index in &order[start..end] {
+            ranks[index] = rank;
+        }
+        start = end;
+    }
+    ranks
+}
+
+// Kendall's tau-b by Knight (1966): sort by (x, y), count the pairs tied in x and in both,
+// then merge-sort by y counting the swaps, which equal the discordant pairs
+fn kendall_tau_b(x: &[f64], y: &[f64]) -> f64 {
+    let n = x.len();
+    let mut pairs: Vec<(f64, f64)> = x.iter().copied().zip(y.iter().copied()).collect();
+    pairs.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+
+    let tied_pairs = |run: u64| run * run.saturating_sub(1) / 2;
+    let (mut ties_x, mut ties_xy) = (0u64, 0u64);
+    let (mut run_x, mut run_xy) = (1u64, 1u64);
+    for w in pairs.windows(2) {
+        if w[1].0 == w[0].0 {
+            run_x += 1;
+            if w[1].1 == w[0].1 {
+                run_xy += 1;
+            } else {
+                ties_xy += tied_pairs(run_xy);
+                run_xy = 1;
+            }
+        } else {
+            ties_x += tied_pairs(run_x);
+            ties_xy += tied_pairs(run_xy);
+            run_x = 1;
+            run_xy = 1;
+        }
+    }
+    ties_x += tied_pairs(run_x);
+    ties_xy += tied_pairs(run_xy);
+
+    let mut ys: Vec<f64> = pairs.iter().map(|p| p.1).collect();
+    let mut buffer = vec![0.0; n];
+    let swaps = merge_sort_swaps(&mut ys, &mut buffer);
+
+    let mut ties_y = 0u64;
+    let mut run_y = 1u64;
+    for w in ys.windows(2) {
+        if w[1] == w[0] {
+            run_y += 1;
+        } else {
+            ties_y += tied_pairs(run_y);
+            run_y = 1;
+        }
+    }
+    ties_y += tied_pairs(run_y);
+
+    let total = tied_pairs(n as u64);
+    let (untied_x, untied_y) = ((total - ties_x) as f64, (total - ties_y) as f64);
+    if untied_x == 0.0 || untied_y == 0.0 {
+        return f64::NAN;
+    }
+    // concordant - discordant = total - ties_x - ties_y + ties_xy - 2·discordant
+    let numerator =
+        total as f64 - ties_x as f64 - ties_y as f64 + ties_xy as f64 - 2.0 * swaps as f64;
+    (numerator / (untied_x * untied_y).sqrt()).clamp(-1.0, 1.0)
+}
+
+// Sort `values` ascending and return the number of inversions (strictly decreasing pairs)
+fn merge_sort_swaps(values: &mut [f64], buffer: &mut [f64]) -> u64 {
+    let n = values.len();
+    if n < 2 {
+        return 0;
+    }
+    let mid = n / 2;
+    let mut swaps = merge_sort_swaps(&mut values[..mid], &mut buffer[..mid])
+        + merge_sort_swaps(&mut values[mid..], &mut buffer[mid..]);
+
+    let (mut i, mut j, mut k) = (0, mid, 0);
+    while i < mid && j < n {
+        if values[j] < values[i] {
+            buffer[k] = values[j];
+            // values[j] jumps ahead of every element still waiting on the left
+            swaps += (mid - i) as u64;
+            j += 1;
+        } else {
+            buffer[k] = values[i];
+            i += 1;
+        }
+        k += 1;
+    }
+    buffer[k..k + mid - i].copy_from_slice(&values[i..mid]);
+    k += mid - i;
+    buffer[k..k + n - j].copy_from_slice(&values[j..n]);
+    values.copy_from_slice(&buffer[..n]);
+    swaps
+}
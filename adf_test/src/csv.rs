@@ -0,0 +1,766 @@
+// CSV parsing of price data inside wasm.
+//
+// Splitting a multi-megabyte CSV into numbers in JS and copying the arrays
+// across costs more than most of the tests run on them. `parse_price_csv` takes
+// the text once and returns the date column as epoch milliseconds with the
+// chosen price columns aligned to it row by row. Fields follow RFC 4180 (quoted
+// fields may hold delimiters, newlines and doubled quotes), and dates are read
+// as ISO 8601, epoch numbers or a strftime-style pattern.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use std::borrow::Cow;
+
+use crate::error::Error;
+#[cfg(feature = "wasm")]
+use crate::get_property;
+use crate::json::{JsonObject, RawJson};
+
+const MS_PER_DAY: i64 = 86_400_000;
+// The letters allowed after % in a date pattern
+const DIRECTIVES: &str = "YymdHMSbf%";
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+// Price fields read as NaN instead of rejected
+const MISSING: [&str; 8] = ["", "na", "n/a", "nan", "null", "none", "-", "#n/a"];
+
+/// Aligned columns parsed from a CSV by `parse_price_csv`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug)]
+pub struct CsvPrices {
+    timestamps: Vec<f64>,
+    symbols: Vec<String>,
+    columns: Vec<Vec<f64>>,
+    // Price fields that were empty or a missing-value marker such as "NA", read as NaN
+    pub missing: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl CsvPrices {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn rows(&self) -> usize {
+        self.timestamps.len()
+    }
+
+    // Date of each row in epoch milliseconds (UTC unless the dates carry an offset)
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn timestamps(&self) -> Vec<f64> {
+        self.timestamps.clone()
+    }
+
+    // The price columns, in the order they were requested
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn symbols(&self) -> Vec<String> {
+        self.symbols.clone()
+    }
+
+    /// The values of price column `name`.
+    pub fn column(&self, name: &str) -> Result<Vec<f64>, Error> {
+        self.column_slice(name).map(<[f64]>::to_vec)
+    }
+
+    /// The price columns as a row-major matrix of one row of `symbols.length` values per date,
+    /// the layout `screen_pairs` and `johansen_test` take.
+    pub fn price_matrix(&self) -> Vec<f64> {
+        let mut matrix = Vec::with_capacity(self.rows() * self.columns.len());
+        for row in 0..self.rows() {
+            matrix.extend(self.columns.iter().map(|column| column[row]));
+        }
+        matrix
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        let columns = self
+            .symbols
+            .iter()
+            .zip(&self.columns)
+            .fold(JsonObject::new(), |object, (symbol, column)| {
+                object.field(symbol, column)
+            });
+        JsonObject::new()
+            .field("timestamps", &self.timestamps)
+            .field("symbols", &self.symbols)
+            .field("columns", RawJson(columns.finish()))
+            .field("missing", self.missing)
+            .finish()
+    }
+}
+
+impl CsvPrices {
+    // Column `name` without copying, for native callers
+    pub fn column_slice(&self, name: &str) -> Result<&[f64], Error> {
+        self.symbols
+            .iter()
+            .position(|symbol| symbol == name)
+            .map(|i| self.columns[i].as_slice())
+            .ok_or_else(|| Error::new(&format!("no price column named {}", name)))
+    }
+}
+
+/// Options of `parse_price_csv_with_options`; the default matches an empty options object.
+#[derive(Clone, Debug)]
+pub struct CsvOptions {
+    pub delimiter: u8,
+    pub date_format: DateFormat,
+    // Whether the first record names the columns; without one, columns are named by their
+    // zero-based position ("0", "1", ...)
+    pub has_header: bool,
+    // Sort the rows into ascending date order (stable), for files written newest first
+    pub sort: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> CsvOptions {
+        CsvOptions {
+            delimiter: b',',
+            date_format: DateFormat::Iso,
+            has_header: true,
+            sort: true,
+        }
+    }
+}
+
+impl CsvOptions {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Result<CsvOptions, Error> {
+        let mut parsed = CsvOptions::default();
+        if options.is_undefined() || options.is_null() {
+            return Ok(parsed);
+        }
+        if !options.is_object() {
+            return Err(Error::new("options must be an object"));
+        }
+
+        let delimiter = get_property(options, "delimiter")?;
+        if !delimiter.is_undefined() {
+            parsed.delimiter = delimiter
+                .as_string()
+                .filter(|d| d.len() == 1 && !matches!(d.as_str(), "\"" | "\n" | "\r"))
+                .map(|d| d.as_bytes()[0])
+                .ok_or_else(|| {
+                    Error::new("delimiter must be a single ASCII character other than a quote")
+                })?;
+        }
+        let date_format = get_property(options, "date_format")?;
+        if !date_format.is_undefined() {
+            let date_format = date_format
+                .as_string()
+                .ok_or_else(|| Error::new("date_format must be a string"))?;
+            parsed.date_format = DateFormat::parse(&date_format)?;
+        }
+        for (key, slot) in [
+            ("has_header", &mut parsed.has_header),
+            ("sort", &mut parsed.sort),
+        ] {
+            let value = get_property(options, key)?;
+            if !value.is_undefined() {
+                *slot = value
+                    .as_bool()
+                    .ok_or_else(|| Error::new(&format!("{} must be a boolean", key)))?;
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+/// How the date column is read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DateFormat {
+    // "iso": YYYY-MM-DD, optionally followed by T or a space, hh:mm[:ss[.fff]] and Z or
+    // ±hh[:]mm
+    Iso,
+    // "epoch_ms" and "epoch_s": numbers of milliseconds or seconds since 1970-01-01 UTC
+    EpochMs,
+    EpochSeconds,
+    // A pattern of %Y (four-digit year), %y (two-digit year, 1970-2069), %m, %d, %H, %M, %S
+    // (one or two digits), %b (month name, by its first three letters), %f (fraction of a
+    // second) and %% among literal characters, such as "%d/%m/%Y" or "%b %d, %Y"
+    Pattern(String),
+}
+
+impl DateFormat {
+    pub fn parse(format: &str) -> Result<DateFormat, Error> {
+        match format {
+            "iso" => Ok(DateFormat::Iso),
+            "epoch_ms" => Ok(DateFormat::EpochMs),
+            "epoch_s" => Ok(DateFormat::EpochSeconds),
+            pattern if pattern.contains('%') => {
+                let mut chars = pattern.chars();
+                while let Some(c) = chars.next() {
+                    if c == '%' && !chars.next().is_some_and(|d| DIRECTIVES.contains(d)) {
+                        return Err(Error::new(&format!(
+                            "date_format \"{}\" has an unknown % directive",
+                            pattern
+                        )));
+                    }
+                }
+                Ok(DateFormat::Pattern(pattern.to_string()))
+            }
+            other => Err(Error::new(&format!(
+                "unknown date_format \"{}\": expected \"iso\", \"epoch_ms\", \"epoch_s\" \
+                 or a % pattern",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            DateFormat::Iso => "iso",
+            DateFormat::EpochMs => "epoch_ms",
+            DateFormat::EpochSeconds => "epoch_s",
+            DateFormat::Pattern(pattern) => pattern,
+        }
+    }
+
+    // Epoch milliseconds of `text`, or None when it does not match the format
+    fn timestamp(&self, text: &str) -> Option<f64> {
+        match self {
+            DateFormat::Iso => parse_iso(text),
+            DateFormat::EpochMs => text.parse::<f64>().ok().filter(|v| v.is_finite()),
+            DateFormat::EpochSeconds => text
+                .parse::<f64>()
+                .ok()
+                .filter(|v| v.is_finite())
+                .map(|v| v * 1000.0),
+            DateFormat::Pattern(pattern) => parse_pattern(pattern, text),
+        }
+    }
+}
+
+/// Parse CSV `text` into the timestamps of column `date_col` and the values of `price_cols`,
+/// aligned row by row.
+///
+/// `options` is an optional object with `delimiter` (default ","; "\t" for TSV), `date_format`
+/// (`"iso"` (default), `"epoch_ms"`, `"epoch_s"` or a pattern such as `"%m/%d/%Y"`; see
+/// `DateFormat`), `has_header` (true; without a header, columns are named "0", "1", ... by
+/// position) and `sort` (true: rows are put in ascending date order). Price fields that are
+/// empty or "NA", "N/A", "NaN", "null", "None", "-" or "#N/A" become NaN and are counted in
+/// `missing`; any other non-numeric or infinite price, or unreadable date, is an error naming
+/// its line.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn parse_price_csv(
+    text: &str,
+    date_col: &str,
+    price_cols: Vec<String>,
+    options: JsValue,
+) -> Result<CsvPrices, Error> {
+    parse_price_csv_with_options(text, date_col, &price_cols, &CsvOptions::from_js(&options)?)
+}
+
+/// `parse_price_csv` for native callers, with the options as a struct.
+pub fn parse_price_csv_with_options(
+    text: &str,
+    date_col: &str,
+    price_cols: &[String],
+    options: &CsvOptions,
+) -> Result<CsvPrices, Error> {
+    if price_cols.is_empty() {
+        return Err(Error::new("price_cols must name at least one column"));
+    }
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut records = Records {
+        text,
+        pos: 0,
+        line: 0,
+        delimiter: options.delimiter,
+    };
+    let mut fields = Vec::new();
+
+    let header: Vec<String> = if options.has_header {
+        records
+            .next(&mut fields)
+            .ok_or_else(|| Error::new("the CSV is empty"))?;
+        fields.iter().map(|f| f.trim().to_string()).collect()
+    } else {
+        Vec::new()
+    };
+    let position = |name: &str| -> Result<usize, Error> {
+        if options.has_header {
+            header.iter().position(|h| h == name)
+        } else {
+            name.parse().ok()
+        }
+        .ok_or_else(|| Error::new(&format!("the CSV has no column {}", name)))
+    };
+    let date_index = position(date_col)?;
+    let price_indices = price_cols
+        .iter()
+        .map(|name| position(name))
+        .collect::<Result<Vec<usize>, Error>>()?;
+    let width = price_indices.iter().copied().fold(date_index, usize::max) + 1;
+
+    let mut timestamps = Vec::new();
+    let mut columns = vec![Vec::new(); price_cols.len()];
+    let mut missing = 0;
+    while let Some(line) = records.next(&mut fields) {
+        if fields.len() == 1 && fields[0].trim().is_empty() {
+            continue;
+        }
+        if fields.len() < width {
+            return Err(Error::new(&format!(
+                "line {} has {} fields, fewer than the {} needed",
+                line,
+                fields.len(),
+                width
+            )));
+        }
+        let date = fields[date_index].trim();
+        let timestamp = options.date_format.timestamp(date).ok_or_else(|| {
+            Error::new(&format!(
+                "line {}: cannot read date \"{}\" as {}",
+                line,
+                date,
+                options.date_format.as_str()
+            ))
+        })?;
+        timestamps.push(timestamp);
+        for ((column, &index), name) in columns.iter_mut().zip(&price_indices).zip(price_cols) {
+            let field = fields[index].trim();
+            // The markers first: Rust's parser reads "NaN" and "inf" as numbers
+            let value = if MISSING.contains(&field.to_ascii_lowercase().as_str()) {
+                missing += 1;
+                f64::NAN
+            } else {
+                match field.parse::<f64>() {
+                    Ok(value) if value.is_finite() => value,
+                    _ => {
+                        return Err(Error::new(&format!(
+                            "line {}: {} value \"{}\" is not a finite number",
+                            line, name, field
+                        )))
+                    }
+                }
+            };
+            column.push(value);
+        }
+    }
+
+    if options.sort && timestamps.windows(2).any(|w| w[1] < w[0]) {
+        let mut order: Vec<usize> = (0..timestamps.len()).collect();
+        order.sort_by(|&a, &b| timestamps[a].total_cmp(&timestamps[b]));
+        timestamps = order.iter().map(|&i| timestamps[i]).collect();
+        for column in &mut columns {
+            *column = order.iter().map(|&i| column[i]).collect();
+        }
+    }
+    Ok(CsvPrices {
+        timestamps,
+        symbols: price_cols.to_vec(),
+        columns,
+        missing,
+    })
+}
+
+// RFC 4180 records of a CSV text
+struct Records<'a> {
+    text: &'a str,
+    pos: usize,
+    // Line the next record starts on, from 1
+    line: usize,
+    delimiter: u8,
+}
+
+impl<'a> Records<'a> {
+    // Read the next record into `fields`; the line it started on, or None at the end
+    fn next(&mut self, fields: &mut Vec<Cow<'a, str>>) -> Option<usize> {
+        if self.pos >= self.text.len() {
+            return None;
+        }
+        fields.clear();
+        self.line += 1;
+        let start_line = self.line;
+        let bytes = self.text.as_bytes();
+        loop {
+            let (field, end) = if bytes.get(self.pos) == Some(&b'"') {
+                self.quoted_field()
+            } else {
+                let start = self.pos;
+                let mut end = start;
+                while end < bytes.len() && bytes[end] != self.delimiter && bytes[end] != b'\n' {
+                    end += 1;
+                }
+                (Cow::Borrowed(&self.text[start..end]), end)
+            };
+            fields.push(match field {
+                Cow::Borrowed(f) => Cow::Borrowed(f.strip_suffix('\r').unwrap_or(f)),
+                owned => owned,
+            });
+            self.pos = end + 1;
+            if end >= bytes.len() || bytes[end] == b'\n' {
+                return Some(start_line);
+            }
+        }
+    }
+
+    // A field starting with a quote at `pos`, and the position of the delimiter or newline
+    // that ends it; text after the closing quote is kept, as most readers do
+    fn quoted_field(&mut self) -> (Cow<'a, str>, usize) {
+        let bytes = self.text.as_bytes();
+        let mut value = String::new();
+        let mut i = self.pos + 1;
+        let mut chunk = i;
+        loop {
+            match bytes.get(i) {
+                None => {
+                    value.push_str(&self.text[chunk..]);
+                    return (Cow::Owned(value), bytes.len());
+                }
+                Some(b'"') if bytes.get(i + 1) == Some(&b'"') => {
+                    value.push_str(&self.text[chunk..=i]);
+                    i += 2;
+                    chunk = i;
+                }
+                Some(b'"') => {
+                    value.push_str(&self.text[chunk..i]);
+                    i += 1;
+                    break;
+                }
+                Some(b'\n') => {
+                    self.line += 1;
+                    i += 1;
+                }
+                Some(_) => i += 1,
+            }
+        }
+        let rest = i;
+        while i < bytes.len() && bytes[i] != self.delimiter && bytes[i] != b'\n' {
+            i += 1;
+        }
+        value.push_str(self.text[rest..i].trim_end_matches('\r'));
+        (Cow::Owned(value), i)
+    }
+}
+
+// Days from 1970-01-01 to a proleptic Gregorian date, the inverse of `resample::year_month`'s
+// calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = (month + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Fields of a date being parsed
+#[derive(Default)]
+struct DateParts {
+    year: i64,
+    month: i64,
+    day: i64,
+    hour: i64,
+    minute: i64,
+    second: i64,
+    // Fraction of a second in milliseconds
+    millis: f64,
+    // Minutes east of UTC
+    offset_minutes: i64,
+}
+
+impl DateParts {
+    fn timestamp(&self) -> Option<f64> {
+        let valid = (1..=12).contains(&self.month)
+            && (1..=days_in_month(self.year, self.month)).contains(&self.day)
+            && (0..24).contains(&self.hour)
+            && (0..60).contains(&self.minute)
+            && (0..=60).contains(&self.second);
+        if !valid {
+            return None;
+        }
+        let seconds = self.hour * 3600 + self.minute * 60 + self.second - self.offset_minutes * 60;
+        let ms = days_from_civil(self.year, self.month, self.day) * MS_PER_DAY + seconds * 1000;
+        Some(ms as f64 + self.millis)
+    }
+}
+
+// Cursor over the bytes of a date field
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Cursor<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let matched = self.peek() == Some(byte);
+        if matched {
+            self.pos += 1;
+        }
+        matched
+    }
+
+    // Between `min` and `max` decimal digits
+    fn number(&mut self, min: usize, max: usize) -> Option<i64> {
+        let start = self.pos;
+        while self.pos - start < max && self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        if self.pos - start < min {
+            return None;
+        }
+        std::str::from_utf8(&self.bytes[start..self.pos])
+            .ok()?
+            .parse()
+            .ok()
+    }
+
+    // Digits after a decimal point, as milliseconds
+    fn fraction(&mut self) -> Option<f64> {
+        let start = self.pos;
+        while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let digits = std::str::from_utf8(&self.bytes[start..self.pos]).ok()?;
+        if digits.is_empty() {
+            return None;
+        }
+        Some(format!("0.{}", digits).parse::<f64>().ok()? * 1000.0)
+    }
+
+    fn month_name(&mut self) -> Option<i64> {
+        let start = self.pos;
+        while self.peek().is_some_and(|b| b.is_ascii_alphabetic()) {
+            self.pos += 1;
+        }
+        let name = std::str::from_utf8(&self.bytes[start..self.pos])
+            .ok()?
+            .to_ascii_lowercase();
+        let index = MONTHS
+            .iter()
+            .position(|m| name.len() >= 3 && name.starts_with(m))?;
+        Some(index as i64 + 1)
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos == self.bytes.len()
+    }
+}
+
+fn parse_iso(text: &str) -> Option<f64> {
+    let mut cursor = Cursor {
+        bytes: text.as_bytes(),
+        pos: 0,
+    };
+    let mut parts = DateParts {
+        year: cursor.number(4, 4)?,
+        ..DateParts::default()
+    };
+    (cursor.eat(b'-')).then_some(())?;
+    parts.month = cursor.number(2, 2)?;
+    (cursor.eat(b'-')).then_some(())?;
+    parts.day = cursor.number(2, 2)?;
+    if cursor.eat(b'T') || cursor.eat(b' ') {
+        parts.hour = cursor.number(2, 2)?;
+        (cursor.eat(b':')).then_some(())?;
+        parts.minute = cursor.number(2, 2)?;
+        if cursor.eat(b':') {
+            parts.second = cursor.number(2, 2)?;
+            if cursor.eat(b'.') || cursor.eat(b',') {
+                parts.millis = cursor.fraction()?;
+            }
+        }
+        if !cursor.eat(b'Z') {
+            let sign = match cursor.peek() {
+                Some(b'+') => 1,
+                Some(b'-') => -1,
+                _ => 0,
+            };
+            if sign != 0 {
+                cursor.pos += 1;
+                let hours = cursor.number(2, 2)?;
+                cursor.eat(b':');
+                let minutes = cursor.number(2, 2)?;
+                parts.offset_minutes = sign * (hours * 60 + minutes);
+            }
+        }
+    }
+    cursor.at_end().then_some(())?;
+    parts.timestamp()
+}
+
+fn parse_pattern(pattern: &str, text: &str) -> Option<f64> {
+    let mut cursor = Cursor {
+        bytes: text.as_bytes(),
+        pos: 0,
+    };
+    let mut parts = DateParts {
+        year: 1970,
+        month: 1,
+        day: 1,
+        ..DateParts::default()
+    };
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            let mut buffer = [0u8; 4];
+            for &byte in c.encode_utf8(&mut buffer).as_bytes() {
+                cursor.eat(byte).then_some(())?;
+            }
+            continue;
+        }
+        match chars.next()? {
+            'Y' => parts.year = cursor.number(4, 4)?,
+            'y' => {
+                let year = cursor.number(2, 2)?;
+                parts.year = if year < 70 { 2000 + year } else { 1900 + year };
+            }
+            'm' => parts.month = cursor.number(1, 2)?,
+            'd' => parts.day = cursor.number(1, 2)?,
+            'H' => parts.hour = cursor.number(1, 2)?,
+            'M' => parts.minute = cursor.number(1, 2)?,
+            'S' => parts.second = cursor.number(1, 2)?,
+            'f' => parts.millis = cursor.fraction()?,
+            'b' => parts.month = cursor.month_name()?,
+            '%' => cursor.eat(b'%').then_some(())?,
+            _ => return None,
+        }
+    }
+    cursor.at_end().then_some(())?;
+    parts.timestamp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JAN_2: f64 = 1_704_153_600_000.0;
+    const DAY: f64 = MS_PER_DAY as f64;
+
+    fn parse(text: &str, date_col: &str, price_cols: &[&str], options: &CsvOptions) -> CsvPrices {
+        let price_cols: Vec<String> = price_cols.iter().map(|c| c.to_string()).collect();
+        parse_price_csv_with_options(text, date_col, &price_cols, options).unwrap()
+    }
+
+    fn parse_error(text: &str) -> String {
+        let price_cols = ["a".to_string()];
+        match parse_price_csv_with_options(text, "date", &price_cols, &CsvOptions::default()) {
+            Ok(_) => panic!("expected an error"),
+            Err(error) => error.message().to_string(),
+        }
+    }
+
+    #[test]
+    fn missing_markers_are_counted_and_non_finite_prices_rejected() {
+        let text = "date,a\n2024-01-02,NaN\n2024-01-03,NA\n2024-01-04,1.0\n2024-01-05,\n";
+        let prices = parse(text, "date", &["a"], &CsvOptions::default());
+        assert_eq!(prices.missing, 3);
+        let a = prices.column("a").unwrap();
+        assert!(a[0].is_nan() && a[1].is_nan() && a[3].is_nan());
+        assert_eq!(a[2], 1.0);
+
+        for field in ["inf", "-Infinity", "abc"] {
+            let text = format!("date,a\n2024-01-02,1.0\n2024-01-03,{}\n", field);
+            let message = parse_error(&text);
+            assert!(message.starts_with("line 3: a value"), "{}", message);
+        }
+    }
+
+    #[test]
+    fn quoted_fields_follow_rfc_4180() {
+        // A quoted header, a delimiter, a doubled quote and a newline inside quoted fields
+        let text = "date,\"A, Inc.\",note\n\
+                    2024-01-02,\"101.5\",\"said \"\"hi\"\", then\nleft\"\n\
+                    2024-01-03,102,plain\n";
+        let prices = parse(text, "date", &["A, Inc."], &CsvOptions::default());
+        assert_eq!(prices.timestamps(), [JAN_2, JAN_2 + DAY]);
+        assert_eq!(prices.column("A, Inc.").unwrap(), [101.5, 102.0]);
+
+        // The record holding a newline counts both of its lines
+        let message = parse_error("date,a\n2024-01-02,\"1\n\"\n2024-01-03,x\n");
+        assert!(message.starts_with("line 4:"), "{}", message);
+    }
+
+    #[test]
+    fn crlf_line_endings_and_a_bom_are_accepted() {
+        let text = "\u{feff}date,a,b\r\n2024-01-02,1,2\r\n2024-01-03,3,4\r\n\r\n";
+        let prices = parse(text, "date", &["b", "a"], &CsvOptions::default());
+        assert_eq!(prices.rows(), 2);
+        assert_eq!(prices.symbols(), ["b", "a"]);
+        assert_eq!(prices.price_matrix(), [2.0, 1.0, 4.0, 3.0]);
+        assert_eq!(prices.missing, 0);
+    }
+
+    #[test]
+    fn headerless_columns_are_named_by_position() {
+        let options = CsvOptions {
+            has_header: false,
+            delimiter: b'\t',
+            ..CsvOptions::default()
+        };
+        let text = "2024-01-03\t9\t30\n2024-01-02\t8\t20\n";
+        let prices = parse(text, "0", &["2"], &options);
+        // Sorted into ascending date order by default
+        assert_eq!(prices.timestamps(), [JAN_2, JAN_2 + DAY]);
+        assert_eq!(prices.column("2").unwrap(), [20.0, 30.0]);
+
+        let unsorted = CsvOptions {
+            sort: false,
+            ..options
+        };
+        let prices = parse(text, "0", &["1"], &unsorted);
+        assert_eq!(prices.column("1").unwrap(), [9.0, 8.0]);
+    }
+
+    #[test]
+    fn dates_are_read_in_every_format() {
+        let cases = [
+            (
+                DateFormat::Iso,
+                "2024-01-02T10:00:00+01:00",
+                JAN_2 + 9.0 * 3_600_000.0,
+            ),
+            (DateFormat::Iso, "2024-01-02 00:00:01.5Z", JAN_2 + 1500.0),
+            (DateFormat::EpochMs, "1704153600000", JAN_2),
+            (DateFormat::EpochSeconds, "1704153600", JAN_2),
+            (DateFormat::parse("%d/%m/%Y").unwrap(), "02/01/2024", JAN_2),
+            (DateFormat::parse("%b %d %y").unwrap(), "Jan 2 24", JAN_2),
+            (
+                DateFormat::parse("%Y%m%d %H:%M").unwrap(),
+                "20240102 06:30",
+                JAN_2 + 23_400_000.0,
+            ),
+        ];
+        for (date_format, date, expected) in cases {
+            let options = CsvOptions {
+                date_format: date_format.clone(),
+                ..CsvOptions::default()
+            };
+            let text = format!("date,a\n{},1\n", date);
+            let prices = parse(&text, "date", &["a"], &options);
+            assert_eq!(
+                prices.timestamps(),
+                [expected],
+                "{} as {}",
+                date,
+                date_format.as_str()
+            );
+        }
+
+        assert!(DateFormat::parse("%d/%q").is_err());
+        let message = parse_error("date,a\n02/01/2024,1\n");
+        assert!(
+            message.starts_with("line 2: cannot read date"),
+            "{}",
+            message
+        );
+    }
+}
@@ -0,0 +1,351 @@
+// Trend and seasonal removal: polynomial trends, the Hodrick-Prescott filter,
+// differencing and moving-average seasonal decomposition.
+//
+// KPSS and the other stationarity tests take a deterministic trend as part of
+// their null, and a spread built from trending legs can carry a slow drift that
+// is not the mean reversion being traded. These split a series into a trend and
+// the cycle around it. Commodity and energy legs add a seasonal pattern on top,
+// which a regression reads as co-movement unless it is removed first.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::error::Error;
+use crate::json::JsonObject;
+use crate::linalg::least_squares;
+use crate::stats::{nanmean, NanPolicy};
+use crate::validate::{require_finite, require_length};
+
+const MAX_DEGREE: usize = 10;
+
+/// Residuals of `series` around its OLS line a + b·t, as scipy's `signal.detrend` with
+/// `type="linear"`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn detrend_linear(series: &[f64]) -> Result<Vec<f64>, Error> {
+    detrend_poly(series, 1)
+}
+
+/// Residuals of `series` around its least-squares polynomial trend in t of `degree` 0 (the mean)
+/// to 10.
+///
+/// Time is rescaled to [-1, 1] before the powers are taken, which keeps the regression well
+/// conditioned at high degrees without changing the fitted trend.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn detrend_poly(series: &[f64], degree: usize) -> Result<Vec<f64>, Error> {
+    if degree > MAX_DEGREE {
+        return Err(Error::new(&format!(
+            "degree must be at most {}",
+            MAX_DEGREE
+        )));
+    }
+    require_finite(series, "series")?;
+    require_length(
+        series,
+        degree + 2,
+        "series",
+        &format!("a degree {} trend", degree),
+    )?;
+    let n = series.len();
+
+    let span = (n - 1) as f64;
+    let t: Vec<f64> = (0..n).map(|i| 2.0 * i as f64 / span - 1.0).collect();
+    let columns: Vec<Vec<f64>> = (0..=degree)
+        .map(|power| t.iter().map(|v| v.powi(power as i32)).collect())
+        .collect();
+    least_squares(&columns, series)
+        .map(|fit| fit.residuals)
+        .ok_or_else(|| Error::new("trend regression failed"))
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct HpFilter {
+    trend: Vec<f64>,
+    cycle: Vec<f64>,
+    pub lambda: f64,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl HpFilter {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn trend(&self) -> Vec<f64> {
+        self.trend.clone()
+    }
+
+    // series - trend
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn cycle(&self) -> Vec<f64> {
+        self.cycle.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("trend", &self.trend)
+            .field("cycle", &self.cycle)
+            .field("lambda", self.lambda)
+            .finish()
+    }
+}
+
+/// Hodrick-Prescott decomposition of `series` with smoothing `lambda` (default 1600, the
+/// quarterly convention; 129 600 is usual for monthly data and around 10^8 for daily prices).
+///
+/// The trend τ minimises Σ(y - τ)² + λ·Σ(Δ²τ)², i.e. solves (I + λ·D'D)τ = y with D the second
+/// difference operator. The system is pentadiagonal, so it is solved by a banded Cholesky
+/// factorisation in O(n), matching statsmodels' `hpfilter`. The filter is two-sided: the trend
+/// at each point uses later observations too, so use it to study a spread, not to trade one.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn hp_filter(series: &[f64], lambda: Option<f64>) -> Result<HpFilter, Error> {
+    let lambda = lambda.unwrap_or(1600.0);
+    if !(lambda >= 0.0 && lambda.is_finite()) {
+        return Err(Error::new("lambda must be a non-negative number"));
+    }
+    require_finite(series, "series")?;
+    require_length(series, 3, "series", "the HP filter")?;
+    let n = series.len();
+
+    // Diagonals of I + λ·D'D: main, first and second sub-diagonals
+    let mut a0 = vec![1.0; n];
+    let mut a1 = vec![0.0; n - 1];
+    let mut a2 = vec![0.0; n - 2];
+    for r in 0..n - 2 {
+        a0[r] += lambda;
+        a0[r + 1] += 4.0 * lambda;
+        a0[r + 2] += lambda;
+        a1[r] -= 2.0 * lambda;
+        a1[r + 1] -= 2.0 * lambda;
+        a2[r] += lambda;
+    }
+
+    // Banded Cholesky L with diagonals l0, l1 (L[i+1][i]) and l2 (L[i+2][i])
+    let mut l0 = vec![0.0; n];
+    let mut l1 = vec![0.0; n - 1];
+    let mut l2 = vec![0.0; n - 2];
+    for i in 0..n {
+        let mut d = a0[i];
+        if i >= 1 {
+            d -= l1[i - 1] * l1[i - 1];
+        }
+        if i >= 2 {
+            d -= l2[i - 2] * l2[i - 2];
+        }
+        l0[i] = d.sqrt();
+        if i + 1 < n {
+            let mut off = a1[i];
+            if i >= 1 {
+                off -= l2[i - 1] * l1[i - 1];
+            }
+            l1[i] = off / l0[i];
+        }
+        if i + 2 < n {
+            l2[i] = a2[i] / l0[i];
+        }
+    }
+
+    let mut trend = vec![0.0; n];
+    for i in 0..n {
+        let mut v = series[i];
+        if i >= 1 {
+            v -= l1[i - 1] * trend[i - 1];
+        }
+        if i >= 2 {
+            v -= l2[i - 2] * trend[i - 2];
+        }
+        trend[i] = v / l0[i];
+    }
+    for i in (0..n).rev() {
+        let mut v = trend[i];
+        if i + 1 < n {
+            v -= l1[i] * trend[i + 1];
+        }
+        if i + 2 < n {
+            v -= l2[i] * trend[i + 2];
+        }
+        trend[i] = v / l0[i];
+    }
+
+    let cycle = series.iter().zip(&trend).map(|(y, t)| y - t).collect();
+    Ok(HpFilter {
+        trend,
+        cycle,
+        lambda,
+    })
+}
+
+/// Differences y_t - y_{t-lag}, `lag` fewer values than `series`. `lag` defaults to 1; a
+/// seasonal lag (12 for monthly data, 52 for weekly) removes a stable seasonal pattern along
+/// with the level.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn diff(series: &[f64], lag: Option<usize>) -> Result<Vec<f64>, Error> {
+    let lag = lag.unwrap_or(1);
+    if lag == 0 {
+        return Err(Error::new("lag must be at least 1"));
+    }
+    require_length(
+        series,
+        lag + 1,
+        "series",
+        &format!("a lag {} difference", lag),
+    )?;
+    Ok(series[lag..]
+        .iter()
+        .zip(series)
+        .map(|(now, before)| now - before)
+        .collect())
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct SeasonalDecomposition {
+    trend: Vec<f64>,
+    seasonal: Vec<f64>,
+    residual: Vec<f64>,
+    adjusted: Vec<f64>,
+    pub period: usize,
+    // Components multiply instead of adding up
+    pub multiplicative: bool,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl SeasonalDecomposition {
+    // Centred moving average, NaN for the half period at either end
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn trend(&self) -> Vec<f64> {
+        self.trend.clone()
+    }
+
+    // The seasonal factor of each observation's phase, repeating every period
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn seasonal(&self) -> Vec<f64> {
+        self.seasonal.clone()
+    }
+
+    // What trend and season leave, NaN where the trend is
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn residual(&self) -> Vec<f64> {
+        self.residual.clone()
+    }
+
+    // The series without its seasonal component: series - seasonal, or series / seasonal
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn adjusted(&self) -> Vec<f64> {
+        self.adjusted.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("trend", &self.trend)
+            .field("seasonal", &self.seasonal)
+            .field("residual", &self.residual)
+            .field("adjusted", &self.adjusted)
+            .field("period", self.period)
+            .field("multiplicative", self.multiplicative)
+            .finish()
+    }
+}
+
+/// Classical moving-average decomposition of `series` into trend, seasonal and residual
+/// components with a seasonal `period` of at least 2, as statsmodels' `seasonal_decompose`.
+///
+/// The trend is the centred moving average over one period (a 2×period average for an even
+/// period). The seasonal component is the mean detrended value of each phase, normalised to sum
+/// to zero over a period, or with `multiplicative` to average one; the multiplicative model
+/// suits series whose seasonal swings grow with their level and needs positive values. At least
+/// two full periods are needed.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn seasonal_decompose(
+    series: &[f64],
+    period: usize,
+    multiplicative: Option<bool>,
+) -> Result<SeasonalDecomposition, Error> {
+    let multiplicative = multiplicative.unwrap_or(false);
+    if period < 2 {
+        return Err(Error::new("period must be at least 2"));
+    }
+    require_finite(series, "series")?;
+    require_length(
+        series,
+        2 * period,
+        "series",
+        &format!("two full periods of {}", period),
+    )?;
+    let n = series.len();
+    if multiplicative && series.iter().any(|v| *v <= 0.0) {
+        return Err(Error::new(
+            "the multiplicative model needs strictly positive values",
+        ));
+    }
+
+    // Centred moving-average weights: 1/period each, or for an even period period + 1 weights
+    // with halves at the ends
+    let weights: Vec<f64> = if period.is_multiple_of(2) {
+        (0..=period)
+            .map(|i| {
+                if i == 0 || i == period {
+                    0.5 / period as f64
+                } else {
+                    1.0 / period as f64
+                }
+            })
+            .collect()
+    } else {
+        vec![1.0 / period as f64; period]
+    };
+    let half = weights.len() / 2;
+    let mut trend = vec![f64::NAN; n];
+    for (t, slot) in trend.iter_mut().enumerate().take(n - half).skip(half) {
+        *slot = weights
+            .iter()
+            .enumerate()
+            .map(|(i, w)| w * series[t + i - half])
+            .sum();
+    }
+
+    let remove = |value: f64, component: f64| {
+        if multiplicative {
+            value / component
+        } else {
+            value - component
+        }
+    };
+    let detrended: Vec<f64> = series
+        .iter()
+        .zip(&trend)
+        .map(|(&v, &t)| remove(v, t))
+        .collect();
+    let mut factors: Vec<f64> = (0..period)
+        .map(|phase| {
+            let values: Vec<f64> = detrended
+                .iter()
+                .skip(phase)
+                .step_by(period)
+                .copied()
+                .collect();
+            nanmean(&values, NanPolicy::Skip)
+        })
+        .collect();
+    let centre = nanmean(&factors, NanPolicy::Propagate);
+    for factor in factors.iter_mut() {
+        *factor = remove(*factor, centre);
+    }
+
+    let seasonal: Vec<f64> = (0..n).map(|t| factors[t % period]).collect();
+    let residual = detrended
+        .iter()
+        .zip(&seasonal)
+        .map(|(&d, &s)| remove(d, s))
+        .collect();
+    let adjusted = series
+        .iter()
+        .zip(&seasonal)
+        .map(|(&v, &s)| remove(v, s))
+        .collect();
+    Ok(SeasonalDecomposition {
+        trend,
+        seasonal,
+        residual,
+        adjusted,
+        period,
+        multiplicative,
+    })
+}
@@ -0,0 +1,402 @@
+// Residual diagnostics for fitted regressions and spreads.
+//
+// The Engle-Granger and OLS fits assume the residual spread is what the
+// downstream test needs; serial correlation left in it biases the standard errors
+// and hints that more ADF lags are required, while fat tails undermine z-score
+// thresholds calibrated on the normal distribution. A residual variance that
+// moves with the regressors or clusters in time does the same to fixed
+// thresholds, which is when GARCH-scaled ones earn their keep.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::distributions::{chi2_sf, f_sf, normal_ppf};
+use crate::error::Error;
+use crate::json::JsonObject;
+use crate::linalg::least_squares;
+use crate::stats::{excess_kurtosis, nanmean, skewness, NanPolicy};
+use crate::validate::{require_finite, require_length, require_varying};
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct LjungBoxResult {
+    statistics: Vec<f64>,
+    p_values: Vec<f64>,
+    pub nobs: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl LjungBoxResult {
+    // Q(h) for h = 1..=lags
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn statistics(&self) -> Vec<f64> {
+        self.statistics.clone()
+    }
+
+    // P(χ²_h > Q(h)); small values reject "no autocorrelation up to lag h"
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn p_values(&self) -> Vec<f64> {
+        self.p_values.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("statistics", &self.statistics)
+            .field("p_values", &self.p_values)
+            .field("nobs", self.nobs)
+            .finish()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct JarqueBeraResult {
+    pub statistic: f64,
+    // P(χ²_2 > statistic); small values reject normality
+    pub p_value: f64,
+    pub skewness: f64,
+    // Kurtosis minus 3, so 0 for a normal distribution
+    pub excess_kurtosis: f64,
+    pub nobs: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl JarqueBeraResult {
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("statistic", self.statistic)
+            .field("p_value", self.p_value)
+            .field("skewness", self.skewness)
+            .field("excess_kurtosis", self.excess_kurtosis)
+            .field("nobs", self.nobs)
+            .finish()
+    }
+}
+
+/// Outcome of a Lagrange-multiplier test for heteroskedasticity, from an auxiliary regression
+/// of the squared residuals.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct HeteroskedasticityTest {
+    // n·R² of the auxiliary regression, χ²_df under constant variance
+    pub statistic: f64,
+    // Small values reject constant variance
+    pub p_value: f64,
+    // F form of the same test, F(df, nobs - df - 1), better sized in short samples
+    pub f_statistic: f64,
+    pub f_p_value: f64,
+    // Slopes of the auxiliary regression
+    pub df: usize,
+    // Rows of the auxiliary regression
+    pub nobs: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl HeteroskedasticityTest {
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("statistic", self.statistic)
+            .field("p_value", self.p_value)
+            .field("f_statistic", self.f_statistic)
+            .field("f_p_value", self.f_p_value)
+            .field("df", self.df)
+            .field("nobs", self.nobs)
+            .finish()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct Correlogram {
+    values: Vec<f64>,
+    bounds: Vec<f64>,
+    pub nobs: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Correlogram {
+    // Coefficient at lags 0..=n_lags; lag 0 is always 1
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn values(&self) -> Vec<f64> {
+        self.values.clone()
+    }
+
+    // Half-width of the confidence band around zero at each lag (0 at lag 0); coefficients
+    // outside ±bounds are significantly non-zero
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn bounds(&self) -> Vec<f64> {
+        self.bounds.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("values", &self.values)
+            .field("bounds", &self.bounds)
+            .field("nobs", self.nobs)
+            .finish()
+    }
+}
+
+/// Ljung-Box portmanteau test of zero autocorrelation in `residuals` up to each lag
+/// h = 1..=`lags`: Q(h) = n(n+2) Σ_{k≤h} ρ̂_k² / (n-k), compared with χ²_h as
+/// statsmodels' `acorr_ljungbox` does.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn ljung_box(residuals: &[f64], lags: usize) -> Result<LjungBoxResult, Error> {
+    let rho = autocorrelations(residuals, lags)?;
+    let n = residuals.len();
+    let nf = n as f64;
+    let mut q = 0.0;
+    let mut result = LjungBoxResult {
+        statistics: Vec::with_capacity(lags),
+        p_values: Vec::with_capacity(lags),
+        nobs: n,
+    };
+    for (k, r) in rho.iter().enumerate().skip(1) {
+        q += r * r / (nf - k as f64);
+        let statistic = nf * (nf + 2.0) * q;
+        result.statistics.push(statistic);
+        result.p_values.push(chi2_sf(statistic, k as f64));
+    }
+    Ok(result)
+}
+
+/// Durbin-Watson statistic Σ(e_t - e_{t-1})² / Σe_t² of `residuals`: about 2 without first-order
+/// autocorrelation, towards 0 with positive and towards 4 with negative autocorrelation. The
+/// exact null distribution depends on the regressors, so no p-value is given; use `ljung_box`
+/// for a test. NaN for fewer than two residuals, any NaN, or all-zero residuals.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn durbin_watson(residuals: &[f64]) -> f64 {
+    if residuals.len() < 2 {
+        return f64::NAN;
+    }
+    let sum_squares: f64 = residuals.iter().map(|e| e * e).sum();
+    if sum_squares.is_nan() || sum_squares == 0.0 {
+        return f64::NAN;
+    }
+    let sum_diff_squares: f64 = residuals
+        .windows(2)
+        .map(|w| (w[1] - w[0]) * (w[1] - w[0]))
+        .sum();
+    sum_diff_squares / sum_squares
+}
+
+/// Jarque-Bera test of normality, JB = n/6 · (S² + K²/4) with S the skewness and K the excess
+/// kurtosis of `series`, compared with χ²_2 as statsmodels' `jarque_bera` does. Fat tails in a
+/// spread make z-score thresholds fire more often than their normal levels suggest.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn jarque_bera(series: &[f64]) -> Result<JarqueBeraResult, Error> {
+    require_finite(series, "series")?;
+    require_length(series, 3, "series", "the Jarque-Bera test")?;
+    require_varying(series, "series")?;
+    let skew = skewness(series);
+    let kurtosis = excess_kurtosis(series);
+
+    let n = series.len();
+    let statistic = n as f64 / 6.0 * (skew * skew + kurtosis * kurtosis / 4.0);
+    Ok(JarqueBeraResult {
+        statistic,
+        p_value: chi2_sf(statistic, 2.0),
+        skewness: skew,
+        excess_kurtosis: kurtosis,
+        nobs: n,
+    })
+}
+
+/// Breusch-Pagan test of whether the variance of `residuals` depends on the regressors.
+///
+/// `regressors` holds `residuals.len()` rows of `n_regressors` values, row-major (as for
+/// `ols_multi`), normally the x of the regression that produced the residuals. The squared
+/// residuals are regressed on a constant and the regressors and n·R² is compared with
+/// χ²_n_regressors: Koenker's studentized form, which does not assume normal errors, as
+/// statsmodels' `het_breuschpagan` does by default.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn breusch_pagan(
+    residuals: &[f64],
+    regressors: &[f64],
+    n_regressors: usize,
+) -> Result<HeteroskedasticityTest, Error> {
+    if n_regressors == 0 {
+        return Err(Error::new("n_regressors must be positive"));
+    }
+    if regressors.len() != residuals.len() * n_regressors {
+        return Err(Error::new(&format!(
+            "regressors has {} values but residuals.len() * n_regressors is {}",
+            regressors.len(),
+            residuals.len() * n_regressors
+        )));
+    }
+    require_finite(residuals, "residuals")?;
+    require_finite(regressors, "regressors")?;
+    let columns = (0..n_regressors)
+        .map(|j| {
+            regressors
+                .iter()
+                .skip(j)
+                .step_by(n_regressors)
+                .copied()
+                .collect()
+        })
+        .collect();
+    squared_residual_test(residuals, columns)
+}
+
+/// Engle's ARCH-LM test for volatility clustering in `residuals`: e_t² is regressed on a
+/// constant and e_{t-1}², …, e_{t-lags}², and n·R² is compared with χ²_lags, as statsmodels'
+/// `het_arch` does. A rejection says the spread's variance is predictable from its recent
+/// moves, so z-score thresholds scaled by a `fit_garch` volatility are worth using.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn arch_lm(residuals: &[f64], lags: usize) -> Result<HeteroskedasticityTest, Error> {
+    if lags == 0 {
+        return Err(Error::new("lags must be positive"));
+    }
+    // The auxiliary regression on the last n - lags rows needs more rows than coefficients
+    require_finite(residuals, "residuals")?;
+    require_length(
+        residuals,
+        2 * lags + 2,
+        "residuals",
+        &format!("an ARCH-LM test with {} lags", lags),
+    )?;
+    let squares: Vec<f64> = residuals.iter().map(|e| e * e).collect();
+    let n = residuals.len();
+    let columns = (1..=lags)
+        .map(|k| squares[lags - k..n - k].to_vec())
+        .collect();
+    squared_residual_test(&residuals[lags..], columns)
+}
+
+/// Sample autocorrelation function of `series` at lags 0..=`n_lags`, ρ̂_k = Σ(x_t - x̄)(x_{t-k} - x̄) /
+/// Σ(x_t - x̄)², as statsmodels' `acf` with `adjusted=False`.
+///
+/// `bounds` is the `1 - alpha` (default 95%) band under the hypothesis that the series is an
+/// MA(k-1) at lag k, using Bartlett's variance (1 + 2Σ_{j<k} ρ̂_j²) / n, which is the shaded
+/// region of a standard ACF plot.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn acf(series: &[f64], n_lags: usize, alpha: Option<f64>) -> Result<Correlogram, Error> {
+    let z = band_quantile(alpha)?;
+    let values = autocorrelations(series, n_lags)?;
+    let n = series.len() as f64;
+    let mut bounds = Vec::with_capacity(n_lags + 1);
+    bounds.push(0.0);
+    let mut sum_squares = 0.0;
+    for r in &values[1..] {
+        bounds.push(z * ((1.0 + 2.0 * sum_squares) / n).sqrt());
+        sum_squares += r * r;
+    }
+    Ok(Correlogram {
+        values,
+        bounds,
+        nobs: series.len(),
+    })
+}
+
+/// Sample partial autocorrelation function of `series` at lags 0..=`n_lags`: the last coefficient
+/// of the AR(k) Yule-Walker fit for each k, from the Durbin-Levinson recursion on the sample ACF
+/// (statsmodels' `pacf` with `method="ywm"`). Unlike an OLS fit per lag this keeps every value
+/// inside [-1, 1].
+///
+/// `bounds` is the `1 - alpha` (default 95%) band ±z/√n of white noise at every lag; the lag
+/// where the PACF last leaves it is the usual guess for an AR order.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn pacf(series: &[f64], n_lags: usize, alpha: Option<f64>) -> Result<Correlogram, Error> {
+    let z = band_quantile(alpha)?;
+    let rho = autocorrelations(series, n_lags)?;
+
+    let mut values = vec![1.0];
+    // phi[j] is the j+1-th coefficient of the current AR(k) fit
+    let mut phi: Vec<f64> = Vec::with_capacity(n_lags);
+    let mut error_variance = 1.0;
+    for k in 1..=n_lags {
+        let numerator = rho[k] - (1..k).map(|j| phi[j - 1] * rho[k - j]).sum::<f64>();
+        let reflection = if error_variance > 0.0 {
+            numerator / error_variance
+        } else {
+            f64::NAN
+        };
+        let previous = phi.clone();
+        for j in 1..k {
+            phi[j - 1] = previous[j - 1] - reflection * previous[k - j - 1];
+        }
+        phi.push(reflection);
+        error_variance *= 1.0 - reflection * reflection;
+        values.push(reflection);
+    }
+
+    let band = z / (series.len() as f64).sqrt();
+    let mut bounds = vec![band; n_lags + 1];
+    bounds[0] = 0.0;
+    Ok(Correlogram {
+        values,
+        bounds,
+        nobs: series.len(),
+    })
+}
+
+// Sample autocorrelations at lags 0..=n_lags with the full-sample mean and variance
+fn autocorrelations(series: &[f64], n_lags: usize) -> Result<Vec<f64>, Error> {
+    if n_lags == 0 {
+        return Err(Error::new("lags must be positive"));
+    }
+    require_finite(series, "series")?;
+    require_length(
+        series,
+        n_lags + 1,
+        "series",
+        &format!("autocorrelations to lag {}", n_lags),
+    )?;
+    require_varying(series, "series")?;
+
+    let n = series.len();
+    let mean = nanmean(series, NanPolicy::Propagate);
+    let centred: Vec<f64> = series.iter().map(|e| e - mean).collect();
+    let variance: f64 = centred.iter().map(|e| e * e).sum();
+    Ok((0..=n_lags)
+        .map(|k| {
+            centred[k..]
+                .iter()
+                .zip(&centred[..n - k])
+                .map(|(a, b)| a * b)
+                .sum::<f64>()
+                / variance
+        })
+        .collect())
+}
+
+// LM test from the regression of the squared residuals on a constant and `columns`
+fn squared_residual_test(
+    residuals: &[f64],
+    mut columns: Vec<Vec<f64>>,
+) -> Result<HeteroskedasticityTest, Error> {
+    let df = columns.len();
+    require_length(residuals, df + 2, "residuals", "the auxiliary regression")?;
+    let n = residuals.len();
+    let squares: Vec<f64> = residuals.iter().map(|e| e * e).collect();
+    require_varying(&squares, "the squared residuals")?;
+    let mean = nanmean(&squares, NanPolicy::Propagate);
+    let sst: f64 = squares.iter().map(|s| (s - mean) * (s - mean)).sum();
+    columns.push(vec![1.0; n]);
+    let ols = least_squares(&columns, &squares)
+        .ok_or_else(|| Error::new("auxiliary regression failed: the regressors are collinear"))?;
+
+    let r_squared = (1.0 - ols.ssr / sst).max(0.0);
+    let statistic = n as f64 * r_squared;
+    let denominator_df = (n - df - 1) as f64;
+    let f_statistic = r_squared / df as f64 / ((1.0 - r_squared) / denominator_df);
+    Ok(HeteroskedasticityTest {
+        statistic,
+        p_value: chi2_sf(statistic, df as f64),
+        f_statistic,
+        f_p_value: f_sf(f_statistic, df as f64, denominator_df),
+        df,
+        nobs: n,
+    })
+}
+
+// Two-sided normal quantile of a confidence band at level 1 - alpha (default 0.05)
+fn band_quantile(alpha: Option<f64>) -> Result<f64, Error> {
+    let alpha = alpha.unwrap_or(0.05);
+    if !(alpha > 0.0 && alpha < 1.0) {
+        return Err(Error::new("alpha must be in (0, 1)"));
+    }
+    Ok(normal_ppf(1.0 - alpha / 2.0))
+}
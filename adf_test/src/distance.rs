@@ -0,0 +1,313 @@
+// Distance-method pair selection (Gatev, Goetzmann & Rouwenhorst 2006).
+//
+// Each price series is normalised over a formation window, by default to its
+// cumulative return index p_t / p_0, and every pair is ranked by the sum of
+// squared differences (SSD) of the two normalised paths. No model is fitted, so
+// the whole universe is ranked in one pass; the pairs closest in SSD are the
+// candidates, traded when their normalised spread drifts a couple of its
+// formation-window standard deviations apart.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::error::Error;
+#[cfg(feature = "wasm")]
+use crate::get_property;
+use crate::json::{raw_array, JsonObject};
+use crate::parallel;
+use crate::stats::{nanmean, nanstd, NanPolicy};
+
+// How a price path is put on a common scale before the paths are compared
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Normalization {
+    // Cumulative return index p_t / p_0, as in Gatev et al.
+    First,
+    // Standard score (p_t - mean) / standard deviation over the window
+    ZScore,
+}
+
+impl Normalization {
+    pub fn parse(normalization: &str) -> Result<Normalization, Error> {
+        match normalization {
+            "first" => Ok(Normalization::First),
+            "zscore" => Ok(Normalization::ZScore),
+            other => Err(Error::new(&format!(
+                "unknown normalization \"{}\": expected \"first\" or \"zscore\"",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Normalization::First => "first",
+            Normalization::ZScore => "zscore",
+        }
+    }
+}
+
+/// A pair ranked by `distance_pairs`. The legs are in column order, so `y_index < x_index`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug)]
+pub struct DistancePair {
+    y_symbol: String,
+    x_symbol: String,
+    // Column of each leg in the price matrix
+    pub y_index: usize,
+    pub x_index: usize,
+    // Sum of squared differences of the normalised paths over the formation window
+    pub ssd: f64,
+    // Sample standard deviation of the normalised spread y - x over the formation window, the
+    // unit of the usual two-standard-deviation trading trigger
+    pub spread_std: f64,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl DistancePair {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn y_symbol(&self) -> String {
+        self.y_symbol.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn x_symbol(&self) -> String {
+        self.x_symbol.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("y_symbol", &self.y_symbol)
+            .field("x_symbol", &self.x_symbol)
+            .field("y_index", self.y_index)
+            .field("x_index", self.x_index)
+            .field("ssd", self.ssd)
+            .field("spread_std", self.spread_std)
+            .finish()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct DistancePairsResult {
+    pairs: Vec<DistancePair>,
+    // Pairs of the assets that were ranked, m·(m-1)/2
+    pub pairs_considered: usize,
+    // Assets left out for a missing, non-positive or constant price in the formation window
+    pub assets_excluded: usize,
+    // Rows of the formation window
+    pub formation_rows: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl DistancePairsResult {
+    // The closest pairs, smallest SSD first
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn pairs(&self) -> Vec<DistancePair> {
+        self.pairs.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field(
+                "pairs",
+                raw_array(self.pairs.iter().map(DistancePair::to_json)),
+            )
+            .field("pairs_considered", self.pairs_considered)
+            .field("assets_excluded", self.assets_excluded)
+            .field("formation_rows", self.formation_rows)
+            .finish()
+    }
+}
+
+/// Options of `distance_pairs_with_options`; the default matches an empty options object.
+#[derive(Clone, Copy, Debug)]
+pub struct DistanceOptions {
+    // First row of the formation window
+    pub formation_start: usize,
+    // Rows of the formation window; None runs to the last row
+    pub formation_length: Option<usize>,
+    pub normalization: Normalization,
+    // Number of pairs returned
+    pub top_k: usize,
+}
+
+impl Default for DistanceOptions {
+    fn default() -> DistanceOptions {
+        DistanceOptions {
+            formation_start: 0,
+            formation_length: None,
+            normalization: Normalization::First,
+            top_k: 20,
+        }
+    }
+}
+
+impl DistanceOptions {
+    #[cfg(feature = "wasm")]
+    pub(crate) fn from_js(options: &JsValue) -> Result<DistanceOptions, Error> {
+        let mut parsed = DistanceOptions::default();
+        if options.is_undefined() || options.is_null() {
+            return Ok(parsed);
+        }
+        if !options.is_object() {
+            return Err(Error::new("options must be an object"));
+        }
+
+        let count = |key: &str| -> Result<Option<usize>, Error> {
+            let value = get_property(options, key)?;
+            if value.is_undefined() || value.is_null() {
+                return Ok(None);
+            }
+            value
+                .as_f64()
+                .filter(|v| *v >= 0.0 && v.fract() == 0.0)
+                .map(|v| Some(v as usize))
+                .ok_or_else(|| Error::new(&format!("{} must be a non-negative integer", key)))
+        };
+        if let Some(value) = count("formation_start")? {
+            parsed.formation_start = value;
+        }
+        parsed.formation_length = count("formation_length")?;
+        if let Some(value) = count("top_k")? {
+            parsed.top_k = value;
+        }
+
+        let normalization = get_property(options, "normalization")?;
+        if !normalization.is_undefined() {
+            let name = normalization
+                .as_string()
+                .ok_or_else(|| Error::new("normalization must be a string"))?;
+            parsed.normalization = Normalization::parse(&name)?;
+        }
+        Ok(parsed)
+    }
+}
+
+/// Rank every pair of a price universe by the distance method of Gatev et al.
+///
+/// `price_matrix` holds one row of `symbols.len()` prices per time step, row-major (as in
+/// `screen_pairs`). Over the formation window each price path is normalised and each pair is
+/// scored by the sum of squared differences of the two paths; the `top_k` pairs with the
+/// smallest SSD are returned, closest first, with the standard deviation of their normalised
+/// spread. Assets with a missing, non-positive or constant price in the window are left out.
+///
+/// `options` is an optional object with `formation_start` (first row of the window, default 0),
+/// `formation_length` (rows of the window, default through the last row), `normalization`
+/// (`"first"` (default) for the cumulative return index p_t / p_0, or `"zscore"`) and `top_k`
+/// (default 20). Run `screen_pairs` on the same window to combine the distance ranking with the
+/// cointegration screen.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn distance_pairs(
+    price_matrix: &[f64],
+    symbols: Vec<String>,
+    options: JsValue,
+) -> Result<DistancePairsResult, Error> {
+    let options = DistanceOptions::from_js(&options)?;
+    distance_pairs_with_options(price_matrix, &symbols, &options)
+}
+
+/// `distance_pairs` for native callers, with the options as a struct.
+pub fn distance_pairs_with_options(
+    price_matrix: &[f64],
+    symbols: &[String],
+    options: &DistanceOptions,
+) -> Result<DistancePairsResult, Error> {
+    let n_assets = symbols.len();
+    if n_assets < 2 {
+        return Err(Error::new("at least two symbols are needed"));
+    }
+    if !price_matrix.len().is_multiple_of(n_assets) {
+        return Err(Error::new(
+            "price_matrix length must be a multiple of the number of symbols",
+        ));
+    }
+    let n_rows = price_matrix.len() / n_assets;
+    let start = options.formation_start;
+    let end = match options.formation_length {
+        Some(length) => start.saturating_add(length),
+        None => n_rows,
+    };
+    if end > n_rows || end < start + 2 {
+        return Err(Error::new(&format!(
+            "the formation window must cover at least two of the {} rows",
+            n_rows
+        )));
+    }
+
+    // Normalised path of every usable asset
+    let paths: Vec<(usize, Vec<f64>)> = (0..n_assets)
+        .filter_map(|asset| {
+            let prices: Vec<f64> = (start..end)
+                .map(|t| price_matrix[t * n_assets + asset])
+                .collect();
+            normalize(&prices, options.normalization).map(|path| (asset, path))
+        })
+        .collect();
+
+    let candidates: Vec<(usize, usize)> = (0..paths.len())
+        .flat_map(|i| (i + 1..paths.len()).map(move |j| (i, j)))
+        .collect();
+    let scores = parallel::map(&candidates, |&(i, j)| {
+        let (a, b) = (&paths[i].1, &paths[j].1);
+        let ssd: f64 = a.iter().zip(b).map(|(p, q)| (p - q) * (p - q)).sum();
+        (ssd, spread_std(a, b))
+    });
+
+    let mut ranked: Vec<(usize, (f64, f64))> = scores.into_iter().enumerate().collect();
+    ranked.sort_by(|a, b| a.1 .0.total_cmp(&b.1 .0));
+    ranked.truncate(options.top_k);
+    let pairs = ranked
+        .into_iter()
+        .map(|(candidate, (ssd, spread_std))| {
+            let (i, j) = candidates[candidate];
+            let (y_index, x_index) = (paths[i].0, paths[j].0);
+            DistancePair {
+                y_symbol: symbols[y_index].clone(),
+                x_symbol: symbols[x_index].clone(),
+                y_index,
+                x_index,
+                ssd,
+                spread_std,
+            }
+        })
+        .collect();
+    Ok(DistancePairsResult {
+        pairs,
+        pairs_considered: candidates.len(),
+        assets_excluded: n_assets - paths.len(),
+        formation_rows: end - start,
+    })
+}
+
+// `prices` on the scale of `normalization`, or None if a price is missing or non-positive or
+// the path is constant
+fn normalize(prices: &[f64], normalization: Normalization) -> Option<Vec<f64>> {
+    if prices.iter().any(|p| !(p.is_finite() && *p > 0.0)) {
+        return None;
+    }
+    if prices.iter().all(|&p| p == prices[0]) {
+        return None;
+    }
+    match normalization {
+        Normalization::First => Some(prices.iter().map(|p| p / prices[0]).collect()),
+        Normalization::ZScore => {
+            let mean = nanmean(prices, NanPolicy::Propagate);
+            let std = nanstd(prices, 1, NanPolicy::Propagate);
+            Some(prices.iter().map(|p| (p - mean) / std).collect())
+        }
+    }
+}
+
+// Sample standard deviation of a - b
+fn spread_std(a: &[f64], b: &[f64]) -> f64 {
+    let n = a.len() as f64;
+    let mean = a.iter().zip(b).map(|(p, q)| p - q).sum::<f64>() / n;
+    let ss: f64 = a
+        .iter()
+        .zip(b)
+        .map(|(p, q)| (p - q - mean) * (p - q - mean))
+        .sum();
+    (ss / (n - 1.0)).sqrt()
+}
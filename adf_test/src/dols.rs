@@ -0,0 +1,270 @@
+// Inference on the cointegrating vector.
+//
+// The static Engle-Granger regression estimates the hedge ratio consistently, but
+// its OLS standard errors are invalid: the regressor's innovations correlate with
+// the spread, so t-ratios do not follow any standard distribution. Dynamic OLS
+// (Stock & Watson 1993) adds leads and lags of Δx, which soaks up that
+// correlation, and scales the coefficient variance by the long-run variance of
+// the remaining residuals, after which the hedge ratio is asymptotically normal
+// and Wald tests of restrictions such as β = 1 are χ². Fully modified OLS
+// (Phillips & Hansen 1990) reaches the same limit without the extra regressors: it
+// corrects y for the long-run correlation between the spread and Δx and removes
+// the remaining endogeneity bias from the static regression's cross-products.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::distributions::{chi2_sf, normal_ppf};
+use crate::error::{Error, ErrorCode};
+use crate::hac::{long_run_variance, newey_west_bandwidth};
+use crate::json::JsonObject;
+use crate::linalg::least_squares;
+use crate::validate::{require_finite, require_same_length, require_varying};
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug)]
+pub struct CointegratingVector {
+    estimator: &'static str,
+    // β of y = intercept + β·x + spread
+    pub hedge_ratio: f64,
+    pub intercept: f64,
+    // Long-run-variance (HAC) standard error of the hedge ratio
+    pub std_error: f64,
+    // Level of the confidence interval, e.g. 0.95
+    pub confidence: f64,
+    pub ci_lower: f64,
+    pub ci_upper: f64,
+    // Leads and lags of Δx on each side of the current bar (0 for FM-OLS)
+    pub leads_lags: usize,
+    // Bartlett truncation of the residual long-run variance
+    pub bandwidth: usize,
+    // Rows of the regression, after the leads and lags (DOLS) or the first bar (FM-OLS) are
+    // trimmed
+    pub nobs: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl CointegratingVector {
+    // "dols" or "fmols"
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn estimator(&self) -> String {
+        self.estimator.to_string()
+    }
+
+    /// Wald test of the restriction that the hedge ratio equals `hedge_ratio` (1 for a plain
+    /// 1:1 spread): ((β̂ - β₀) / se)², χ² with one degree of freedom.
+    pub fn wald_test(&self, hedge_ratio: f64) -> RestrictionTest {
+        let z = (self.hedge_ratio - hedge_ratio) / self.std_error;
+        let statistic = z * z;
+        RestrictionTest {
+            statistic,
+            df: 1,
+            p_value: chi2_sf(statistic, 1.0),
+        }
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("estimator", self.estimator)
+            .field("hedge_ratio", self.hedge_ratio)
+            .field("intercept", self.intercept)
+            .field("std_error", self.std_error)
+            .field("confidence", self.confidence)
+            .field("ci_lower", self.ci_lower)
+            .field("ci_upper", self.ci_upper)
+            .field("leads_lags", self.leads_lags)
+            .field("bandwidth", self.bandwidth)
+            .field("nobs", self.nobs)
+            .finish()
+    }
+}
+
+/// Outcome of a test of restrictions on a cointegrating vector.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug)]
+pub struct RestrictionTest {
+    // χ²-distributed under the restriction
+    pub statistic: f64,
+    // Number of restrictions tested
+    pub df: usize,
+    pub p_value: f64,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl RestrictionTest {
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("statistic", self.statistic)
+            .field("df", self.df)
+            .field("p_value", self.p_value)
+            .finish()
+    }
+}
+
+/// Dynamic OLS estimate of the hedge ratio of y on x with its standard error and a
+/// `confidence` interval (default 0.95).
+///
+/// Regresses y_t on a constant, x_t and Δx_{t+j} for j = -k..=k, where k is `leads_lags`
+/// (default ⌊n^(1/3)⌋), so the first and last k + 1 bars only supply leads and lags. The
+/// standard error uses the Bartlett long-run variance of the regression residuals with the
+/// Newey-West automatic bandwidth. Use `wald_test` on the result to test a hedge ratio such as
+/// 1.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn dols_hedge_ratio(
+    y: &[f64],
+    x: &[f64],
+    leads_lags: Option<usize>,
+    confidence: Option<f64>,
+) -> Result<CointegratingVector, Error> {
+    let confidence = confidence.unwrap_or(0.95);
+    if !(confidence > 0.0 && confidence < 1.0) {
+        return Err(Error::new("confidence must be between 0 and 1"));
+    }
+    require_same_length(y, x)?;
+    require_finite(y, "y")?;
+    require_finite(x, "x")?;
+    require_varying(x, "x")?;
+    let n = y.len();
+    let k = leads_lags.unwrap_or_else(|| (n as f64).cbrt() as usize);
+
+    // Rows t = k + 1 ..= n - 1 - k, with 2 + (2k + 1) regressors
+    let rows = n.saturating_sub(2 * k + 1);
+    let params = 2 * k + 3;
+    if rows <= params {
+        return Err(Error::with_code(
+            ErrorCode::TooShort,
+            &format!(
+                "y has {} observations but DOLS with {} leads and lags needs at least {}",
+                n,
+                k,
+                2 * k + 1 + params + 1
+            ),
+        ));
+    }
+    let range = k + 1..n - k;
+    let mut columns = vec![vec![1.0; rows], x[range.clone()].to_vec()];
+    for j in 0..=2 * k {
+        // Δx_{t+j-k}
+        columns.push(
+            range
+                .clone()
+                .map(|t| x[t + j - k] - x[t + j - k - 1])
+                .collect(),
+        );
+    }
+    let ols = least_squares(&columns, &y[range])
+        .ok_or_else(|| Error::new("DOLS regression failed: x and its differences are collinear"))?;
+
+    let bandwidth = newey_west_bandwidth(&ols.residuals).min(rows - 1);
+    let omega = long_run_variance(&ols.residuals, bandwidth) * rows as f64 / (rows - params) as f64;
+    let std_error = (omega * ols.xtx_inv[params + 1]).sqrt();
+    let hedge_ratio = ols.coefficients[1];
+    let half_width = normal_ppf(0.5 + confidence / 2.0) * std_error;
+    Ok(CointegratingVector {
+        estimator: "dols",
+        hedge_ratio,
+        intercept: ols.coefficients[0],
+        std_error,
+        confidence,
+        ci_lower: hedge_ratio - half_width,
+        ci_upper: hedge_ratio + half_width,
+        leads_lags: k,
+        bandwidth,
+        nobs: rows,
+    })
+}
+
+/// Fully modified OLS estimate of the hedge ratio of y on x with its standard error and a
+/// `confidence` interval (default 0.95); the result has the same form as `dols_hedge_ratio`'s.
+///
+/// From the static regression y_t = a + b·x_t + u_t, the long-run covariances of u_t and
+/// v_t = Δx_t (Bartlett kernel, truncated at `bandwidth`, by default the Newey-West automatic
+/// bandwidth of u) give y⁺_t = y_t - Ω_uv/Ω_vv·v_t and the bias correction
+/// Δ⁺_vu = Δ_vu - Δ_vv·Ω_vu/Ω_vv, where Δ sums the covariances of v_t with u_{t+j}, j >= 0.
+/// The estimate regresses y⁺ on a constant and x with n·Δ⁺_vu taken off the x cross-product,
+/// and its variance is Ω_u·v (Z'Z)⁻¹ with Ω_u·v = Ω_uu - Ω_uv²/Ω_vv, the long-run variance of
+/// the spread left after conditioning on the regressor's innovations.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn fmols_hedge_ratio(
+    y: &[f64],
+    x: &[f64],
+    bandwidth: Option<usize>,
+    confidence: Option<f64>,
+) -> Result<CointegratingVector, Error> {
+    let confidence = confidence.unwrap_or(0.95);
+    if !(confidence > 0.0 && confidence < 1.0) {
+        return Err(Error::new("confidence must be between 0 and 1"));
+    }
+    require_same_length(y, x)?;
+    require_finite(y, "y")?;
+    require_finite(x, "x")?;
+    require_varying(x, "x")?;
+    let n = y.len();
+    if n < 5 {
+        return Err(Error::with_code(
+            ErrorCode::TooShort,
+            &format!("y has {} observations but FM-OLS needs at least 5", n),
+        ));
+    }
+
+    let fit = least_squares(&[vec![1.0; n], x.to_vec()], y)
+        .ok_or_else(|| Error::new("FM-OLS regression failed: x is constant"))?;
+    // The static residuals and the regressor's innovations on bars 1..n
+    let u = &fit.residuals[1..];
+    let v: Vec<f64> = x.windows(2).map(|w| w[1] - w[0]).collect();
+    let m = v.len();
+    let bandwidth = bandwidth
+        .unwrap_or_else(|| newey_west_bandwidth(u))
+        .min(m - 1);
+    // Σ_{j=0..=bandwidth} w_j · (1/m) Σ_t a_t b_{t+j}, with Bartlett weights w_j
+    let one_sided = |a: &[f64], b: &[f64]| {
+        (0..=bandwidth)
+            .map(|j| {
+                let weight = 1.0 - j as f64 / (bandwidth as f64 + 1.0);
+                weight
+                    * a[..m - j]
+                        .iter()
+                        .zip(&b[j..])
+                        .map(|(p, q)| p * q)
+                        .sum::<f64>()
+            })
+            .sum::<f64>()
+            / m as f64
+    };
+    let contemporaneous = u.iter().zip(&v).map(|(p, q)| p * q).sum::<f64>() / m as f64;
+    let omega_uu = long_run_variance(u, bandwidth);
+    let omega_vv = long_run_variance(&v, bandwidth);
+    let omega_uv = one_sided(u, &v) + one_sided(&v, u) - contemporaneous;
+    if omega_vv <= 0.0 {
+        return Err(Error::new(
+            "FM-OLS needs x to have a positive long-run variance of its changes",
+        ));
+    }
+    let ratio = omega_uv / omega_vv;
+    let delta_plus = one_sided(&v, u) - one_sided(&v, &v) * ratio;
+
+    let y_plus: Vec<f64> = y[1..].iter().zip(&v).map(|(y, v)| y - ratio * v).collect();
+    let corrected = least_squares(&[vec![1.0; m], x[1..].to_vec()], &y_plus)
+        .ok_or_else(|| Error::new("FM-OLS regression failed: x is constant"))?;
+    // (Z'Z)⁻¹ applied to the bias correction [0, m·Δ⁺]
+    let bias = m as f64 * delta_plus;
+    let intercept = corrected.coefficients[0] - corrected.xtx_inv[1] * bias;
+    let hedge_ratio = corrected.coefficients[1] - corrected.xtx_inv[3] * bias;
+    let omega_conditional = (omega_uu - omega_uv * ratio).max(0.0);
+    let std_error = (omega_conditional * corrected.xtx_inv[3]).sqrt();
+    let half_width = normal_ppf(0.5 + confidence / 2.0) * std_error;
+    Ok(CointegratingVector {
+        estimator: "fmols",
+        hedge_ratio,
+        intercept,
+        std_error,
+        confidence,
+        ci_lower: hedge_ratio - half_width,
+        ci_upper: hedge_ratio + half_width,
+        leads_lags: 0,
+        bandwidth,
+        nobs: m,
+    })
+}
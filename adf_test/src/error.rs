@@ -0,0 +1,230 @@
+// Typed errors for malformed caller input.
+//
+// Each variant renders a descriptive message. Entry points return the
+// message-carrying `Error`, which `AdfError` converts into with `?`; with the
+// `wasm` feature it becomes a JS `Error`, so JS callers receive the message as
+// an exception instead of a silent default or an aborted instance. The `code`
+// and `index` of an `Error` become properties of that exception, so a caller
+// can tell a short or constant series from a bad option without parsing text.
+// No input-handling path unwraps; a panic would be a bug, and the `debug`
+// feature's `set_panic_hook` reports one on the console with its message.
+
+use std::fmt;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "wasm")]
+use crate::set_property;
+
+/// What kind of problem an `Error` reports, for callers that react to it programmatically.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// Any other invalid argument; the message says which.
+    InvalidInput,
+    /// Fewer observations than the requested lags, window or regression need.
+    TooShort,
+    /// A NaN or infinite value where only finite values are accepted.
+    NonFinite,
+    /// A series with no variation, for which the statistic is undefined.
+    Constant,
+    /// Series that must be paired observation by observation differ in length.
+    LengthMismatch,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::InvalidInput => "invalid_input",
+            ErrorCode::TooShort => "too_short",
+            ErrorCode::NonFinite => "non_finite",
+            ErrorCode::Constant => "constant",
+            ErrorCode::LengthMismatch => "length_mismatch",
+        }
+    }
+}
+
+/// Error returned by every fallible public function.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    message: String,
+    code: ErrorCode,
+    // Position of the offending observation, when there is one
+    index: Option<usize>,
+}
+
+impl Error {
+    pub fn new(message: &str) -> Error {
+        Error::with_code(ErrorCode::InvalidInput, message)
+    }
+
+    pub fn with_code(code: ErrorCode, message: &str) -> Error {
+        Error {
+            message: message.to_string(),
+            code,
+            index: None,
+        }
+    }
+
+    // The same error pointing at observation `index`
+    pub(crate) fn at(mut self, index: usize) -> Error {
+        self.index = Some(index);
+        self
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<AdfError> for Error {
+    fn from(error: AdfError) -> Error {
+        let code = match error {
+            AdfError::NonNumericEntry { .. }
+            | AdfError::NonNumericValue { .. }
+            | AdfError::InvalidStatistic => ErrorCode::NonFinite,
+            AdfError::EmptyTable { .. } | AdfError::InvalidSampleSize => ErrorCode::TooShort,
+            AdfError::LengthMismatch { .. } => ErrorCode::LengthMismatch,
+            AdfError::NotAnArray
+            | AdfError::MalformedRow { .. }
+            | AdfError::UnsortedTable { .. }
+            | AdfError::NotAnObject { .. }
+            | AdfError::MissingKey { .. }
+            | AdfError::CriticalValuesLayout { .. } => ErrorCode::InvalidInput,
+        };
+        let converted = Error::with_code(code, &error.to_string());
+        match error {
+            AdfError::MalformedRow { row }
+            | AdfError::NonNumericEntry { row }
+            | AdfError::UnsortedTable { row } => converted.at(row),
+            _ => converted,
+        }
+    }
+}
+
+// A JS `Error` carrying the message, with `code` and, when known, `index` properties
+#[cfg(feature = "wasm")]
+impl From<Error> for JsValue {
+    fn from(error: Error) -> JsValue {
+        let value: JsValue = JsError::new(&error.message).into();
+        let object = value.unchecked_ref::<js_sys::Object>();
+        set_property(object, "code", error.code.as_str());
+        if let Some(index) = error.index {
+            set_property(object, "index", index as f64);
+        }
+        value
+    }
+}
+
+/// Report Rust panics through `console.error` with the panic message, its location and the JS
+/// stack, instead of the bare "unreachable executed" of a trapped instance. Call it once at
+/// startup; calling it again is harmless. A panic still aborts the call and can leave the
+/// instance unusable, so this is for finding a bug, not for recovering from one.
+#[cfg(feature = "debug")]
+#[wasm_bindgen]
+pub fn set_panic_hook() {
+    std::panic::set_hook(Box::new(|info| {
+        let stack = js_sys::Reflect::get(&js_sys::Error::new("").into(), &"stack".into())
+            .ok()
+            .and_then(|stack| stack.as_string())
+            .unwrap_or_default();
+        let message = JsValue::from_str(&format!("{}\n\nStack:\n\n{}", info, stack));
+        let error = js_sys::Reflect::get(&js_sys::global(), &"console".into())
+            .and_then(|console| js_sys::Reflect::get(&console, &"error".into()));
+        if let Ok(error) = error.and_then(|error| error.dyn_into::<js_sys::Function>()) {
+            let _ = error.call1(&JsValue::UNDEFINED, &message);
+        }
+    }));
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AdfError {
+    /// The p-value table is not an array.
+    NotAnArray,
+    /// A table row is not a two-element array.
+    MalformedRow { row: usize },
+    /// A table row contains something other than two finite numbers.
+    NonNumericEntry { row: usize },
+    /// The table has fewer than the two rows interpolation needs.
+    EmptyTable { rows: usize },
+    /// Row `row` does not have a larger statistic than the row before it.
+    UnsortedTable { row: usize },
+    /// An options or critical-value argument is not an object.
+    NotAnObject { argument: &'static str },
+    /// A required key is absent from an object argument.
+    MissingKey { key: String },
+    /// A key is present but its value is not a finite number.
+    NonNumericValue { key: String },
+    /// Parallel statistic / p-value arrays differ in length.
+    LengthMismatch { statistics: usize, p_values: usize },
+    /// The critical-value array is not `[1%, 5%, 10%]`.
+    CriticalValuesLayout { len: usize },
+    /// The test statistic is NaN.
+    InvalidStatistic,
+    /// A sample size of zero was supplied.
+    InvalidSampleSize,
+}
+
+impl fmt::Display for AdfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdfError::NotAnArray => {
+                write!(f, "p-value table must be an array of [statistic, p_value] rows")
+            }
+            AdfError::MalformedRow { row } => {
+                write!(f, "row {} must be an array of exactly two elements", row)
+            }
+            AdfError::NonNumericEntry { row } => {
+                write!(f, "row {} must contain two finite numbers", row)
+            }
+            AdfError::EmptyTable { rows } => write!(
+                f,
+                "p-value table needs at least two rows (got {})",
+                rows
+            ),
+            AdfError::UnsortedTable { row } => write!(
+                f,
+                "p-value table must be sorted by strictly increasing statistic (row {} is out of order)",
+                row
+            ),
+            AdfError::NotAnObject { argument } => write!(f, "{} must be an object", argument),
+            AdfError::MissingKey { key } => write!(f, "missing key \"{}\"", key),
+            AdfError::NonNumericValue { key } => {
+                write!(f, "\"{}\" must be a finite number", key)
+            }
+            AdfError::LengthMismatch {
+                statistics,
+                p_values,
+            } => write!(
+                f,
+                "statistics and p_values must have the same length ({} vs {})",
+                statistics, p_values
+            ),
+            AdfError::CriticalValuesLayout { len } => write!(
+                f,
+                "critical_values must hold exactly three values [1%, 5%, 10%] (got {})",
+                len
+            ),
+            AdfError::InvalidStatistic => write!(f, "test statistic must be a number, got NaN"),
+            AdfError::InvalidSampleSize => write!(f, "sample size (nobs) must be positive"),
+        }
+    }
+}
+
+impl std::error::Error for AdfError {}
@@ -0,0 +1,111 @@
+// Discrete Fourier transform of any length.
+//
+// Powers of two use the iterative radix-2 Cooley-Tukey algorithm; other lengths
+// are turned into a power-of-two circular convolution with Bluestein's chirp-z
+// identity nk = (n² + k² - (k - n)²) / 2, so every length costs O(n log n).
+
+use std::f64::consts::PI;
+
+// Forward DFT X_k = Σ x_j·e^(-2πijk/n) of the complex sequence (re, im), in place
+pub(crate) fn fft(re: &mut [f64], im: &mut [f64]) {
+    let n = re.len();
+    if n <= 1 {
+        return;
+    }
+    if n.is_power_of_two() {
+        radix2(re, im, false);
+    } else {
+        bluestein(re, im);
+    }
+}
+
+// DFT of real values, as (re, im) of all n coefficients
+pub(crate) fn real_fft(values: &[f64]) -> (Vec<f64>, Vec<f64>) {
+    let mut re = values.to_vec();
+    let mut im = vec![0.0; values.len()];
+    fft(&mut re, &mut im);
+    (re, im)
+}
+
+// Radix-2 transform of a power-of-two length; `inverse` flips the sign of the exponent
+// without the 1/n scaling
+fn radix2(re: &mut [f64], im: &mut [f64], inverse: bool) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut length = 2;
+    while length <= n {
+        let angle = sign * 2.0 * PI / length as f64;
+        for start in (0..n).step_by(length) {
+            for k in 0..length / 2 {
+                let (w_im, w_re) = (angle * k as f64).sin_cos();
+                let (a, b) = (start + k, start + k + length / 2);
+                let t_re = re[b] * w_re - im[b] * w_im;
+                let t_im = re[b] * w_im + im[b] * w_re;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        length <<= 1;
+    }
+}
+
+fn bluestein(re: &mut [f64], im: &mut [f64]) {
+    let n = re.len();
+    let m = (2 * n - 1).next_power_of_two();
+    // Chirp e^(-iπk²/n); k² is reduced mod 2n first to keep the angle accurate for long inputs
+    let chirp: Vec<(f64, f64)> = (0..n)
+        .map(|k| {
+            let angle = PI * ((k * k) % (2 * n)) as f64 / n as f64;
+            (angle.cos(), -angle.sin())
+        })
+        .collect();
+
+    let (mut a_re, mut a_im) = (vec![0.0; m], vec![0.0; m]);
+    for k in 0..n {
+        let (c_re, c_im) = chirp[k];
+        a_re[k] = re[k] * c_re - im[k] * c_im;
+        a_im[k] = re[k] * c_im + im[k] * c_re;
+    }
+    let (mut b_re, mut b_im) = (vec![0.0; m], vec![0.0; m]);
+    b_re[0] = chirp[0].0;
+    b_im[0] = -chirp[0].1;
+    for k in 1..n {
+        b_re[k] = chirp[k].0;
+        b_im[k] = -chirp[k].1;
+        b_re[m - k] = chirp[k].0;
+        b_im[m - k] = -chirp[k].1;
+    }
+
+    radix2(&mut a_re, &mut a_im, false);
+    radix2(&mut b_re, &mut b_im, false);
+    for k in 0..m {
+        let product_re = a_re[k] * b_re[k] - a_im[k] * b_im[k];
+        let product_im = a_re[k] * b_im[k] + a_im[k] * b_re[k];
+        a_re[k] = product_re;
+        a_im[k] = product_im;
+    }
+    radix2(&mut a_re, &mut a_im, true);
+
+    for k in 0..n {
+        let (conv_re, conv_im) = (a_re[k] / m as f64, a_im[k] / m as f64);
+        let (c_re, c_im) = chirp[k];
+        re[k] = conv_re * c_re - conv_im * c_im;
+        im[k] = conv_re * c_im + conv_im * c_re;
+    }
+}
@@ -0,0 +1,322 @@
+// GARCH(1,1) conditional volatility of spread changes.
+//
+//   r_t = μ + ε_t,   ε_t = σ_t·z_t,   σ²_t = ω + α·ε²_{t-1} + β·σ²_{t-1}
+//
+// Spreads have calm and turbulent regimes, so a z-score threshold calibrated on
+// the full-sample standard deviation fires too often in turbulent periods and too
+// rarely in calm ones. Dividing by σ_t instead keeps the threshold's meaning
+// constant. The parameters maximise the Gaussian likelihood with the recursion
+// started at the sample variance, searched by Nelder-Mead over an unconstrained
+// reparameterisation that keeps ω > 0, α, β ≥ 0 and α + β < 1.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::error::Error;
+use crate::json::JsonObject;
+use crate::state::{StateKind, StateReader, StateWriter};
+use crate::stats::{nanmean, nanstd, NanPolicy};
+use crate::validate::{require_finite, require_length, require_varying};
+
+const MIN_OBSERVATIONS: usize = 20;
+const MAX_EVALUATIONS: usize = 2000;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct GarchModel {
+    // 0 without a mean
+    pub mean: f64,
+    pub omega: f64,
+    pub alpha: f64,
+    pub beta: f64,
+    pub log_likelihood: f64,
+    pub aic: f64,
+    pub bic: f64,
+    pub nobs: usize,
+    // σ_{n+1}, the volatility expected for the next observation
+    pub next_volatility: f64,
+    conditional_volatility: Vec<f64>,
+    standardized_residuals: Vec<f64>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl GarchModel {
+    // σ_t for each observation, known from the observations before it
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn conditional_volatility(&self) -> Vec<f64> {
+        self.conditional_volatility.clone()
+    }
+
+    // (r_t - μ) / σ_t, roughly unit variance in every regime
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn standardized_residuals(&self) -> Vec<f64> {
+        self.standardized_residuals.clone()
+    }
+
+    // α + β: how slowly a volatility shock fades
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn persistence(&self) -> f64 {
+        self.alpha + self.beta
+    }
+
+    // √(ω / (1 - α - β)), the level forecasts revert to
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn long_run_volatility(&self) -> f64 {
+        (self.omega / (1.0 - self.alpha - self.beta)).sqrt()
+    }
+
+    /// Volatility forecasts σ_{n+1}..σ_{n+horizon}, from σ²_{n+h} = V + (α + β)^{h-1}·(σ²_{n+1} - V)
+    /// with V the long-run variance.
+    pub fn forecast_volatility(&self, horizon: usize) -> Vec<f64> {
+        let long_run = self.omega / (1.0 - self.alpha - self.beta);
+        let persistence = self.persistence();
+        let next = self.next_volatility * self.next_volatility;
+        let mut decay = 1.0;
+        (0..horizon)
+            .map(|_| {
+                let variance = long_run + decay * (next - long_run);
+                decay *= persistence;
+                variance.sqrt()
+            })
+            .collect()
+    }
+
+    /// The fitted model, in-sample volatilities included, as a compact byte buffer that
+    /// `GarchModel.deserialize` turns back into the same model without refitting.
+    pub fn serialize(&self) -> Vec<u8> {
+        StateWriter::new(StateKind::GarchModel)
+            .f64(self.mean)
+            .f64(self.omega)
+            .f64(self.alpha)
+            .f64(self.beta)
+            .f64(self.log_likelihood)
+            .f64(self.aic)
+            .f64(self.bic)
+            .usize(self.nobs)
+            .f64(self.next_volatility)
+            .f64s(&self.conditional_volatility)
+            .f64s(&self.standardized_residuals)
+            .finish()
+    }
+
+    /// A model restored from the bytes of `serialize`.
+    pub fn deserialize(bytes: &[u8]) -> Result<GarchModel, Error> {
+        let mut reader = StateReader::new(bytes, StateKind::GarchModel)?;
+        let model = GarchModel {
+            mean: reader.f64()?,
+            omega: reader.f64()?,
+            alpha: reader.f64()?,
+            beta: reader.f64()?,
+            log_likelihood: reader.f64()?,
+            aic: reader.f64()?,
+            bic: reader.f64()?,
+            nobs: reader.usize()?,
+            next_volatility: reader.f64()?,
+            conditional_volatility: reader.f64s()?,
+            standardized_residuals: reader.f64s()?,
+        };
+        reader.finish()?;
+        Ok(model)
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("mean", self.mean)
+            .field("omega", self.omega)
+            .field("alpha", self.alpha)
+            .field("beta", self.beta)
+            .field("persistence", self.persistence())
+            .field("long_run_volatility", self.long_run_volatility())
+            .field("log_likelihood", self.log_likelihood)
+            .field("aic", self.aic)
+            .field("bic", self.bic)
+            .field("nobs", self.nobs)
+            .field("next_volatility", self.next_volatility)
+            .field("conditional_volatility", &self.conditional_volatility)
+            .field("standardized_residuals", &self.standardized_residuals)
+            .finish()
+    }
+}
+
+/// Fit a GARCH(1,1) with Gaussian innovations to `returns`, typically the changes of a spread,
+/// with a constant mean unless `include_mean` is false.
+///
+/// `conditional_volatility` is the in-sample σ_t and `next_volatility` the one-step-ahead
+/// forecast; dividing a spread's deviation by them gives volatility-adjusted z-scores. The
+/// variance recursion starts at the sample variance, so estimates differ slightly from `arch`,
+/// which starts it at an exponentially weighted backcast. The information criteria count the
+/// mean, ω, α and β.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn fit_garch(returns: &[f64], include_mean: Option<bool>) -> Result<GarchModel, Error> {
+    require_finite(returns, "returns")?;
+    require_length(returns, MIN_OBSERVATIONS, "returns", "the GARCH(1, 1) fit")?;
+    require_varying(returns, "returns")?;
+    let include_mean = include_mean.unwrap_or(true);
+    let n = returns.len() as f64;
+    let sample_mean = nanmean(returns, NanPolicy::Propagate);
+    let variance = nanstd(returns, 0, NanPolicy::Propagate).powi(2);
+
+    // Scale to unit variance so the search works on comparable magnitudes
+    let scale = variance.sqrt();
+    let scaled: Vec<f64> = returns.iter().map(|r| (r - sample_mean) / scale).collect();
+    // Without a mean the raw zero is where the demeaned, scaled data puts it
+    let zero_mean = -sample_mean / scale;
+    let negative_log_likelihood = |theta: &[f64]| {
+        let (mean, parameters) = unpack(theta, include_mean, zero_mean);
+        -garch_filter(&scaled, mean, parameters).0
+    };
+    // Persistence 0.95 split 0.05/0.90, as in the usual starting values
+    let mut start = vec![(0.05f64).ln(), logit(0.95), logit(0.05 / 0.95)];
+    if include_mean {
+        start.insert(0, 0.0);
+    }
+    let theta = nelder_mead(&negative_log_likelihood, &start);
+
+    let (mean, (omega, alpha, beta)) = unpack(&theta, include_mean, zero_mean);
+    let (scaled_log_likelihood, variances, next_variance) =
+        garch_filter(&scaled, mean, (omega, alpha, beta));
+    let k = if include_mean { 4.0 } else { 3.0 };
+    // Undo the scaling: variances by scale², the likelihood by the Jacobian of r -> r/scale
+    let log_likelihood = scaled_log_likelihood - n * scale.ln();
+    let mean = mean * scale + sample_mean;
+    let conditional_volatility: Vec<f64> = variances.iter().map(|v| v.sqrt() * scale).collect();
+    let standardized_residuals = returns
+        .iter()
+        .zip(&conditional_volatility)
+        .map(|(r, sigma)| (r - mean) / sigma)
+        .collect();
+    Ok(GarchModel {
+        mean,
+        omega: omega * variance,
+        alpha,
+        beta,
+        log_likelihood,
+        aic: -2.0 * log_likelihood + 2.0 * k,
+        bic: -2.0 * log_likelihood + n.ln() * k,
+        nobs: returns.len(),
+        next_volatility: next_variance.sqrt() * scale,
+        conditional_volatility,
+        standardized_residuals,
+    })
+}
+
+// Mean and (ω, α, β) from the search vector [mean?, ln ω, logit(α + β), logit(α / (α + β))];
+// without a mean in the search, `fixed_mean` is used
+fn unpack(theta: &[f64], include_mean: bool, fixed_mean: f64) -> (f64, (f64, f64, f64)) {
+    let (mean, rest) = if include_mean {
+        (theta[0], &theta[1..])
+    } else {
+        (fixed_mean, theta)
+    };
+    let persistence = logistic(rest[1]);
+    let share = logistic(rest[2]);
+    (
+        mean,
+        (
+            rest[0].exp(),
+            persistence * share,
+            persistence * (1.0 - share),
+        ),
+    )
+}
+
+// Gaussian log-likelihood, σ²_t for every t and σ²_{n+1} of the GARCH recursion started at the
+// unconditional variance of the (unit-variance) data
+fn garch_filter(
+    returns: &[f64],
+    mean: f64,
+    (omega, alpha, beta): (f64, f64, f64),
+) -> (f64, Vec<f64>, f64) {
+    let ln_2pi = (2.0 * std::f64::consts::PI).ln();
+    let mut variance: f64 = 1.0;
+    let mut variances = Vec::with_capacity(returns.len());
+    let mut log_likelihood = 0.0;
+    for r in returns {
+        let e = r - mean;
+        variances.push(variance);
+        log_likelihood -= 0.5 * (ln_2pi + variance.ln() + e * e / variance);
+        variance = omega + alpha * e * e + beta * variance;
+    }
+    if !log_likelihood.is_finite() {
+        log_likelihood = f64::NEG_INFINITY;
+    }
+    (log_likelihood, variances, variance)
+}
+
+fn logistic(x: f64) -> f64 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+fn logit(p: f64) -> f64 {
+    (p / (1.0 - p)).ln()
+}
+
+// Nelder-Mead minimisation with the standard coefficients (1, 2, 0.5, 0.5), from a simplex of
+// unit steps around `start`
+fn nelder_mead(f: &impl Fn(&[f64]) -> f64, start: &[f64]) -> Vec<f64> {
+    let k = start.len();
+    let mut simplex: Vec<(Vec<f64>, f64)> = (0..=k)
+        .map(|i| {
+            let mut point = start.to_vec();
+            if i > 0 {
+                point[i - 1] += if point[i - 1].abs() > 1.0 {
+                    0.5 * point[i - 1].abs()
+                } else {
+                    0.5
+                };
+            }
+            let value = f(&point);
+            (point, value)
+        })
+        .collect();
+    let mut evaluations = k + 1;
+    let along = |from: &[f64], to: &[f64], t: f64| -> Vec<f64> {
+        from.iter().zip(to).map(|(a, b)| a + t * (b - a)).collect()
+    };
+
+    while evaluations < MAX_EVALUATIONS {
+        simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let (best, worst) = (simplex[0].1, simplex[k].1);
+        if (worst - best).abs() <= 1e-10 * (best.abs() + 1e-10) {
+            break;
+        }
+        let centroid: Vec<f64> = (0..k)
+            .map(|j| simplex[..k].iter().map(|(p, _)| p[j]).sum::<f64>() / k as f64)
+            .collect();
+        let reflected = along(&centroid, &simplex[k].0, -1.0);
+        let reflected_value = f(&reflected);
+        evaluations += 1;
+        if reflected_value < best {
+            let expanded = along(&centroid, &simplex[k].0, -2.0);
+            let expanded_value = f(&expanded);
+            evaluations += 1;
+            simplex[k] = if expanded_value < reflected_value {
+                (expanded, expanded_value)
+            } else {
+                (reflected, reflected_value)
+            };
+        } else if reflected_value < simplex[k - 1].1 {
+            simplex[k] = (reflected, reflected_value);
+        } else {
+            let contracted = if reflected_value < worst {
+                along(&centroid, &reflected, 0.5)
+            } else {
+                along(&centroid, &simplex[k].0, 0.5)
+            };
+            let contracted_value = f(&contracted);
+            evaluations += 1;
+            if contracted_value < reflected_value.min(worst) {
+                simplex[k] = (contracted, contracted_value);
+            } else {
+                let best_point = simplex[0].0.clone();
+                for vertex in simplex.iter_mut().skip(1) {
+                    vertex.0 = along(&best_point, &vertex.0, 0.5);
+                    vertex.1 = f(&vertex.0);
+                }
+                evaluations += k;
+            }
+        }
+    }
+    simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+    simplex.swap_remove(0).0
+}
@@ -0,0 +1,174 @@
+// ADF-GLS (DF-GLS) unit-root test of Elliott, Rothenberg & Stock (1996).
+//
+// The deterministic terms are estimated by GLS on quasi-differences
+// (1 - ᾱL) with ᾱ = 1 + c̄/T close to one, instead of by OLS inside the test
+// regression. The detrended series then gets a Dickey-Fuller regression without
+// deterministic terms. Removing the mean or trend this way leaves much more power
+// against roots just below one, which is where borderline spreads live.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::adf::{adf_regression, default_max_lag, select_lag, LagSelection, Regression};
+use crate::error::Error;
+use crate::linalg::{least_squares, CovarianceType};
+use crate::mackinnon::{mackinnon_critical_values, mackinnon_lookup};
+use crate::pvalue::{lookup_sorted, PValue};
+use crate::validate::{adf_min_length, require_finite, require_length, require_varying};
+use crate::{build_adf_result, AdfResult, CRITICAL_VALUE_LEVELS};
+
+// ERS (1996) Table 1 critical values of DF-GLS with a linear trend at the 1%, 5% and 10%
+// levels, by 1/T (T = 50, 100, 200, ∞)
+const ERS_TREND_INV_T: [f64; 4] = [1.0 / 50.0, 1.0 / 100.0, 1.0 / 200.0, 0.0];
+const ERS_TREND_CRITICAL: [[f64; 3]; 4] = [
+    [-3.77, -3.19, -2.89],
+    [-3.58, -3.03, -2.74],
+    [-3.46, -2.93, -2.64],
+    [-3.48, -2.89, -2.57],
+];
+
+/// ADF-GLS test of Elliott, Rothenberg & Stock with a constant ("c") or a constant and linear
+/// trend ("ct") removed by GLS detrending (c̄ = -7 and -13.5).
+///
+/// `max_lag` and `autolag` choose the lagged differences of the detrended regression exactly as
+/// in `adf_test_batch`: `max_lag` defaults to Schwert's rule, and with `autolag` "AIC", "BIC" or
+/// "t-stat" the lag is selected in `0..=max_lag`, otherwise `max_lag` is used as given.
+///
+/// With a constant the statistic has the no-constant Dickey-Fuller distribution, so p-value and
+/// critical values come from MacKinnon's "n" surfaces. With a trend the ERS critical values are
+/// interpolated in 1/T, and the p-value is interpolated between the 1%, 5% and 10% levels and
+/// clamped outside them (see `p_value_source`).
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn adf_gls(
+    series: &[f64],
+    trend: &str,
+    max_lag: Option<usize>,
+    autolag: Option<String>,
+) -> Result<AdfResult, Error> {
+    let trend = Regression::parse(trend)?;
+    if !matches!(trend, Regression::Constant | Regression::ConstantTrend) {
+        return Err(Error::new("adf_gls supports the \"c\" and \"ct\" trends"));
+    }
+    let method = match autolag {
+        Some(name) => LagSelection::parse(&name)?,
+        None => LagSelection::Fixed,
+    };
+    require_finite(series, "series")?;
+    require_length(
+        series,
+        adf_min_length(0, trend),
+        "series",
+        "the ADF-GLS regression",
+    )?;
+    require_varying(series, "series")?;
+
+    let detrended = gls_detrend(series, trend)
+        .ok_or_else(|| Error::new("GLS detrending failed: series too short or degenerate"))?;
+    let max_lag = max_lag.unwrap_or_else(|| default_max_lag(series.len(), trend));
+    let lags =
+        select_lag(&detrended, max_lag, Regression::NoConstant, method).ok_or_else(|| {
+            Error::new("lag selection failed: series too short for max_lag or degenerate")
+        })?;
+    let fit = adf_regression(
+        &detrended,
+        lags,
+        Regression::NoConstant,
+        CovarianceType::Classical,
+    )
+    .ok_or_else(|| {
+        Error::new(
+            "ADF-GLS regression failed: series too short for the requested lags or degenerate",
+        )
+    })?;
+
+    let (p_value, critical_values) = match trend {
+        Regression::Constant => (
+            mackinnon_lookup(fit.statistic, Regression::NoConstant, 1),
+            mackinnon_critical_values(Regression::NoConstant, 1, fit.nobs).unwrap_or([f64::NAN; 3]),
+        ),
+        _ => {
+            let critical_values = ers_trend_critical_values(fit.nobs);
+            (
+                trend_p_value(fit.statistic, critical_values),
+                critical_values,
+            )
+        }
+    };
+    Ok(
+        build_adf_result(fit.statistic, p_value, critical_values).with_fit(
+            fit.used_lag,
+            fit.nobs,
+            trend,
+        ),
+    )
+}
+
+// y minus its deterministic terms, estimated by OLS on the ᾱ-quasi-differenced data
+fn gls_detrend(series: &[f64], trend: Regression) -> Option<Vec<f64>> {
+    let n = series.len();
+    if n < 4 {
+        return None;
+    }
+    let c_bar = if trend == Regression::ConstantTrend {
+        -13.5
+    } else {
+        -7.0
+    };
+    let alpha = 1.0 + c_bar / n as f64;
+
+    let mut deterministic = vec![vec![1.0; n]];
+    if trend == Regression::ConstantTrend {
+        deterministic.push((1..=n).map(|t| t as f64).collect());
+    }
+    let quasi_difference = |values: &[f64]| -> Vec<f64> {
+        std::iter::once(values[0])
+            .chain(values.windows(2).map(|w| w[1] - alpha * w[0]))
+            .collect()
+    };
+    let columns: Vec<Vec<f64>> = deterministic.iter().map(|c| quasi_difference(c)).collect();
+    let ols = least_squares(&columns, &quasi_difference(series))?;
+
+    Some(
+        (0..n)
+            .map(|t| {
+                series[t]
+                    - deterministic
+                        .iter()
+                        .zip(&ols.coefficients)
+                        .map(|(c, b)| c[t] * b)
+                        .sum::<f64>()
+            })
+            .collect(),
+    )
+}
+
+// ERS trend critical values for `nobs` observations, linear in 1/T between the tabled sample
+// sizes and held at T = 50 for smaller samples
+fn ers_trend_critical_values(nobs: usize) -> [f64; 3] {
+    let inv_t = (1.0 / nobs as f64).min(ERS_TREND_INV_T[0]);
+    let upper = ERS_TREND_INV_T
+        .iter()
+        .position(|&x| x <= inv_t)
+        .unwrap_or(ERS_TREND_INV_T.len() - 1)
+        .max(1);
+    let (x0, x1) = (ERS_TREND_INV_T[upper - 1], ERS_TREND_INV_T[upper]);
+    let weight = (inv_t - x0) / (x1 - x0);
+    let mut critical_values = [0.0; 3];
+    for (i, value) in critical_values.iter_mut().enumerate() {
+        let (c0, c1) = (
+            ERS_TREND_CRITICAL[upper - 1][i],
+            ERS_TREND_CRITICAL[upper][i],
+        );
+        *value = c0 + weight * (c1 - c0);
+    }
+    critical_values
+}
+
+// p-value interpolated between the 1%, 5% and 10% critical values, clamped outside them
+fn trend_p_value(statistic: f64, critical_values: [f64; 3]) -> PValue {
+    lookup_sorted(
+        critical_values.len(),
+        |i| (critical_values[i], CRITICAL_VALUE_LEVELS[i]),
+        statistic,
+    )
+}
@@ -0,0 +1,113 @@
+// Granger causality between the two legs of a pair.
+//
+// x Granger-causes y when lagged values of x improve the forecast of y beyond what
+// y's own lags provide. Between the legs of a pair this shows which one leads, and
+// so which leg's moves the other tends to follow.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::distributions::f_sf;
+use crate::error::Error;
+use crate::json::JsonObject;
+use crate::linalg::least_squares;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct GrangerResult {
+    f_statistics: Vec<f64>,
+    p_values: Vec<f64>,
+    // Denominator degrees of freedom of each F test
+    df_denominators: Vec<u32>,
+    pub max_lag: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl GrangerResult {
+    // F statistic for lags 1..=max_lag
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn f_statistics(&self) -> Vec<f64> {
+        self.f_statistics.clone()
+    }
+
+    // P(F > f_statistic); small values reject "x does not Granger-cause y" at that lag
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn p_values(&self) -> Vec<f64> {
+        self.p_values.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn df_denominators(&self) -> Vec<u32> {
+        self.df_denominators.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("f_statistics", &self.f_statistics)
+            .field("p_values", &self.p_values)
+            .field("df_denominators", &self.df_denominators)
+            .field("max_lag", self.max_lag)
+            .finish()
+    }
+}
+
+/// Granger causality F tests of whether x helps predict y, for each lag order p in
+/// 1..=`max_lag`.
+///
+/// Each p compares y_t on a constant and y_{t-1..p} with the same regression plus x_{t-1..p},
+/// over the n - p observations that have p lags, and reports the F statistic with (p, n - 3p - 1)
+/// degrees of freedom for series of length n, as statsmodels' `grangercausalitytests` ("ssr_ftest"). Swap the
+/// arguments to test the other direction; the legs should be stationary, e.g. returns rather
+/// than prices.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn granger_causality(y: &[f64], x: &[f64], max_lag: usize) -> Result<GrangerResult, Error> {
+    if y.len() != x.len() {
+        return Err(Error::new("y and x must have the same length"));
+    }
+    if max_lag == 0 {
+        return Err(Error::new("max_lag must be positive"));
+    }
+    if y.iter().chain(x).any(|v| !v.is_finite()) {
+        return Err(Error::new("inputs must contain only finite values"));
+    }
+    let n = y.len();
+    if n < 3 * max_lag + 2 {
+        return Err(Error::new(&format!(
+            "at least {} observations are needed for max_lag {}",
+            3 * max_lag + 2,
+            max_lag
+        )));
+    }
+
+    let mut result = GrangerResult {
+        f_statistics: Vec::with_capacity(max_lag),
+        p_values: Vec::with_capacity(max_lag),
+        df_denominators: Vec::with_capacity(max_lag),
+        max_lag,
+    };
+    for p in 1..=max_lag {
+        let response = &y[p..];
+        let lagged = |series: &[f64], i: usize| series[p - i..n - i].to_vec();
+        let mut columns = vec![vec![1.0; n - p]];
+        columns.extend((1..=p).map(|i| lagged(y, i)));
+        let restricted = least_squares(&columns, response);
+        columns.extend((1..=p).map(|i| lagged(x, i)));
+        let unrestricted = least_squares(&columns, response);
+        let (Some(restricted), Some(unrestricted)) = (restricted, unrestricted) else {
+            return Err(Error::new(&format!(
+                "Granger regression with {} lags failed: the lags are collinear",
+                p
+            )));
+        };
+
+        let df_denominator = n - 3 * p - 1;
+        let f = ((restricted.ssr - unrestricted.ssr) / p as f64)
+            / (unrestricted.ssr / df_denominator as f64);
+        result.f_statistics.push(f);
+        result
+            .p_values
+            .push(f_sf(f, p as f64, df_denominator as f64));
+        result.df_denominators.push(df_denominator as u32);
+    }
+    Ok(result)
+}
@@ -0,0 +1,44 @@
+// Heteroskedasticity and autocorrelation consistent (HAC) variance estimates shared by
+// the tests whose statistics need a long-run variance (KPSS, Phillips-Perron) and by the
+// Newey-West regression standard errors.
+
+// Σ_{t>=lag} e_t e_{t-lag}
+pub(crate) fn autocovariance_sum(residuals: &[f64], lag: usize) -> f64 {
+    residuals[lag..]
+        .iter()
+        .zip(residuals)
+        .map(|(a, b)| a * b)
+        .sum()
+}
+
+// Newey-West long-run variance γ_0 + 2 Σ_{i=1..lags} (1 - i/(lags+1)) γ_i with Bartlett
+// weights, where γ_i are autocovariances normalised by n
+pub(crate) fn long_run_variance(residuals: &[f64], lags: usize) -> f64 {
+    let mut s = autocovariance_sum(residuals, 0);
+    for i in 1..=lags.min(residuals.len().saturating_sub(1)) {
+        s += 2.0 * autocovariance_sum(residuals, i) * (1.0 - i as f64 / (lags as f64 + 1.0));
+    }
+    s / residuals.len() as f64
+}
+
+// Newey & West (1994) automatic Bartlett bandwidth, in the form of Hobijn, Franses & Ooms
+// (1998) with n^(2/9) pilot autocovariances
+pub(crate) fn newey_west_bandwidth(residuals: &[f64]) -> usize {
+    let nobs = residuals.len() as f64;
+    let covariance_lags = nobs.powf(2.0 / 9.0) as usize;
+    let mut s0 = autocovariance_sum(residuals, 0) / nobs;
+    let mut s1 = 0.0;
+    for i in 1..=covariance_lags.min(residuals.len() - 1) {
+        let product = autocovariance_sum(residuals, i) / (nobs / 2.0);
+        s0 += product;
+        s1 += i as f64 * product;
+    }
+    let s_hat = s1 / s0;
+    let gamma_hat = 1.1447 * (s_hat * s_hat).powf(1.0 / 3.0);
+    let bandwidth = gamma_hat * nobs.powf(1.0 / 3.0);
+    if bandwidth.is_finite() && bandwidth > 0.0 {
+        bandwidth as usize
+    } else {
+        0
+    }
+}
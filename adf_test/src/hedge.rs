@@ -0,0 +1,535 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::error::Error;
+use crate::json::JsonObject;
+#[cfg(feature = "wasm")]
+use crate::set_property;
+use crate::stats::{median, nanmean, NanPolicy};
+
+/// Minimum-variance static hedge ratio: the `h` minimising Var(y - h·x), i.e. Cov(y, x) / Var(x).
+///
+/// Because the variance ignores any constant offset, this is the slope of the OLS regression
+/// of y on x *with* an intercept, and it equals the no-intercept (through-the-origin) OLS slope
+/// Σxy / Σx² only when both legs have zero mean (e.g. demeaned prices or returns). On raw price
+/// levels the no-intercept slope absorbs the level difference between the legs and can be far
+/// from this value.
+///
+/// Returns NaN if the legs differ in length, have fewer than two observations, contain NaN,
+/// or if x is constant.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn min_variance_hedge_ratio(y: &[f64], x: &[f64]) -> f64 {
+    if y.len() != x.len() || y.len() < 2 {
+        return f64::NAN;
+    }
+
+    let mean_y = nanmean(y, NanPolicy::Propagate);
+    let mean_x = nanmean(x, NanPolicy::Propagate);
+
+    let mut cov = 0.0;
+    let mut var_x = 0.0;
+    for (&yi, &xi) in y.iter().zip(x) {
+        let dx = xi - mean_x;
+        cov += (yi - mean_y) * dx;
+        var_x += dx * dx;
+    }
+
+    if var_x == 0.0 {
+        return f64::NAN;
+    }
+    cov / var_x
+}
+
+/// A static hedge ratio and intercept, defining the spread y - intercept - beta·x.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug)]
+pub struct HedgeRatio {
+    pub beta: f64,
+    pub intercept: f64,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl HedgeRatio {
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("beta", self.beta)
+            .field("intercept", self.intercept)
+            .finish()
+    }
+}
+
+/// Total-least-squares (orthogonal / Deming with equal error variances) hedge ratio.
+///
+/// OLS treats x as error-free, which biases the slope towards zero when both legs are noisy
+/// prices, and regressing x on y gives a different spread. TLS minimises perpendicular
+/// distances instead: the slope is taken from the eigenvector of the 2x2 covariance matrix of
+/// (x, y) with the largest eigenvalue. It always lies between the y-on-x OLS slope and the
+/// reciprocal of the x-on-y slope, and swapping the legs yields exactly the reciprocal slope.
+///
+/// Both fields are NaN if the legs differ in length, have fewer than two observations, contain
+/// NaN, or are uncorrelated (the principal axis is then horizontal or vertical).
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn tls_hedge_ratio(y: &[f64], x: &[f64]) -> HedgeRatio {
+    deming_fit(y, x, 1.0)
+}
+
+/// Deming regression hedge ratio for legs measured with different noise: `delta` is the ratio
+/// Var(noise in y) / Var(noise in x) of the two legs' measurement noise around the
+/// equilibrium line.
+///
+/// `delta` = 1 is `tls_hedge_ratio`; as `delta` grows the slope tends to the y-on-x OLS slope
+/// and as it shrinks to the reciprocal of the x-on-y slope. Swapping the legs and inverting
+/// `delta` gives exactly the reciprocal slope, so the spread does not depend on which leg is
+/// called y. Errors unless `delta` is positive and finite; NaN fields as for `tls_hedge_ratio`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn deming_hedge_ratio(y: &[f64], x: &[f64], delta: f64) -> Result<HedgeRatio, Error> {
+    if !(delta > 0.0 && delta.is_finite()) {
+        return Err(Error::new("delta must be positive and finite"));
+    }
+    Ok(deming_fit(y, x, delta))
+}
+
+// Deming slope (syy - δ·sxx + √((syy - δ·sxx)² + 4δ·sxy²)) / (2·sxy) and the intercept that puts
+// the line through the means
+fn deming_fit(y: &[f64], x: &[f64], delta: f64) -> HedgeRatio {
+    let undefined = HedgeRatio {
+        beta: f64::NAN,
+        intercept: f64::NAN,
+    };
+    if y.len() != x.len() || y.len() < 2 {
+        return undefined;
+    }
+
+    let mean_y = nanmean(y, NanPolicy::Propagate);
+    let mean_x = nanmean(x, NanPolicy::Propagate);
+    let (mut sxx, mut syy, mut sxy) = (0.0, 0.0, 0.0);
+    for (&yi, &xi) in y.iter().zip(x) {
+        let (dx, dy) = (xi - mean_x, yi - mean_y);
+        sxx += dx * dx;
+        syy += dy * dy;
+        sxy += dx * dy;
+    }
+    if !(sxy.is_finite() && sxy != 0.0) {
+        return undefined;
+    }
+
+    // With δ = 1 this is the principal eigenvector of [[sxx, sxy], [sxy, syy]]
+    let gap = syy - delta * sxx;
+    let beta = (gap + (gap * gap + 4.0 * delta * sxy * sxy).sqrt()) / (2.0 * sxy);
+
+    HedgeRatio {
+        beta,
+        intercept: mean_y - beta * mean_x,
+    }
+}
+
+// Huber's tuning constant: 95% efficiency at the normal distribution
+const HUBER_C: f64 = 1.345;
+// Residuals beyond this many robust standard deviations are reported as outliers
+const OUTLIER_THRESHOLD: f64 = 2.5;
+const HUBER_MAX_ITERATIONS: usize = 100;
+
+// Robust line-fitting estimator
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RobustMethod {
+    // Median of the pairwise slopes
+    TheilSen,
+    // Iteratively reweighted least squares with Huber weights
+    Huber,
+}
+
+impl RobustMethod {
+    fn parse(method: &str) -> Result<RobustMethod, Error> {
+        match method {
+            "theil-sen" => Ok(RobustMethod::TheilSen),
+            "huber" => Ok(RobustMethod::Huber),
+            other => Err(Error::new(&format!(
+                "unknown robust method \"{}\": expected \"theil-sen\" or \"huber\"",
+                other
+            ))),
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct RobustHedgeRatio {
+    pub beta: f64,
+    pub intercept: f64,
+    // Robust standard deviation of the residuals, 1.4826 times their median absolute deviation
+    pub scale: f64,
+    outliers: Vec<u32>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl RobustHedgeRatio {
+    // Indices whose residual y - intercept - beta·x exceeds 2.5 scale in absolute value
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn outliers(&self) -> Vec<u32> {
+        self.outliers.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("beta", self.beta)
+            .field("intercept", self.intercept)
+            .field("scale", self.scale)
+            .field("outliers", &self.outliers)
+            .finish()
+    }
+}
+
+/// Outlier-resistant hedge ratio of y on x with an intercept, by `method`:
+///
+/// - "theil-sen": the median of the slopes between all pairs of observations, with the median of
+///   y - beta·x as intercept. Tolerates up to 29% corrupted points, but the O(n²) pairs limit it
+///   to a few thousand observations.
+/// - "huber": iteratively reweighted least squares with Huber's weights (c = 1.345) and the scale
+///   re-estimated from the residuals' MAD at every step, starting from OLS, as statsmodels' `RLM`
+///   with `HuberT`. Nearly as efficient as OLS on clean data.
+///
+/// Bad ticks that would drag the OLS beta are then listed in `outliers`: observations whose
+/// residual is beyond 2.5 robust standard deviations of the fit.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn robust_hedge_ratio(y: &[f64], x: &[f64], method: &str) -> Result<RobustHedgeRatio, Error> {
+    let method = RobustMethod::parse(method)?;
+    if y.len() != x.len() {
+        return Err(Error::new("y and x must have the same length"));
+    }
+    if y.len() < 3 {
+        return Err(Error::new("at least three observations are needed"));
+    }
+    if y.iter().chain(x).any(|v| !v.is_finite()) {
+        return Err(Error::new("inputs must contain only finite values"));
+    }
+    if x.iter().all(|&v| v == x[0]) {
+        return Err(Error::new("x is constant"));
+    }
+
+    let (beta, intercept) = match method {
+        RobustMethod::TheilSen => theil_sen(y, x),
+        RobustMethod::Huber => huber(y, x),
+    };
+    let residuals: Vec<f64> = y
+        .iter()
+        .zip(x)
+        .map(|(yi, xi)| yi - intercept - beta * xi)
+        .collect();
+    let scale = mad_scale(&residuals);
+    let outliers = residuals
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| r.abs() > OUTLIER_THRESHOLD * scale)
+        .map(|(i, _)| i as u32)
+        .collect();
+    Ok(RobustHedgeRatio {
+        beta,
+        intercept,
+        scale,
+        outliers,
+    })
+}
+
+fn theil_sen(y: &[f64], x: &[f64]) -> (f64, f64) {
+    let mut slopes = Vec::with_capacity(y.len() * (y.len() - 1) / 2);
+    for i in 0..y.len() {
+        for j in i + 1..y.len() {
+            if x[j] != x[i] {
+                slopes.push((y[j] - y[i]) / (x[j] - x[i]));
+            }
+        }
+    }
+    let beta = median(&slopes);
+    let offsets: Vec<f64> = y.iter().zip(x).map(|(yi, xi)| yi - beta * xi).collect();
+    (beta, median(&offsets))
+}
+
+fn huber(y: &[f64], x: &[f64]) -> (f64, f64) {
+    let mut weights = vec![1.0; y.len()];
+    let (mut beta, mut intercept) = weighted_line(y, x, &weights);
+    for _ in 0..HUBER_MAX_ITERATIONS {
+        let residuals: Vec<f64> = y
+            .iter()
+            .zip(x)
+            .map(|(yi, xi)| yi - intercept - beta * xi)
+            .collect();
+        let scale = mad_scale(&residuals);
+        if scale == 0.0 {
+            // More than half the points lie exactly on the line
+            break;
+        }
+        for (w, r) in weights.iter_mut().zip(&residuals) {
+            let u = (r / scale).abs();
+            *w = if u <= HUBER_C { 1.0 } else { HUBER_C / u };
+        }
+        let (next_beta, next_intercept) = weighted_line(y, x, &weights);
+        let converged = (next_beta - beta).abs() <= 1e-10 * (1.0 + beta.abs())
+            && (next_intercept - intercept).abs() <= 1e-10 * (1.0 + intercept.abs());
+        beta = next_beta;
+        intercept = next_intercept;
+        if converged {
+            break;
+        }
+    }
+    (beta, intercept)
+}
+
+// Weighted least-squares slope and intercept of y on x
+fn weighted_line(y: &[f64], x: &[f64], weights: &[f64]) -> (f64, f64) {
+    let total: f64 = weights.iter().sum();
+    let mean = |v: &[f64]| v.iter().zip(weights).map(|(a, w)| a * w).sum::<f64>() / total;
+    let (mean_y, mean_x) = (mean(y), mean(x));
+    let (mut sxy, mut sxx) = (0.0, 0.0);
+    for ((yi, xi), w) in y.iter().zip(x).zip(weights) {
+        let dx = xi - mean_x;
+        sxy += w * dx * (yi - mean_y);
+        sxx += w * dx * dx;
+    }
+    let beta = sxy / sxx;
+    (beta, mean_y - beta * mean_x)
+}
+
+// 1.4826·median|r - median(r)|, the MAD estimate of a normal standard deviation
+fn mad_scale(residuals: &[f64]) -> f64 {
+    let centre = median(residuals);
+    let deviations: Vec<f64> = residuals.iter().map(|r| (r - centre).abs()).collect();
+    1.4826 * median(&deviations)
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(typescript_custom_section)]
+const POSITION_SIZES_TS: &'static str = r#"
+/** Object returned by `position_sizes`. */
+export interface PositionSizesObject {
+  units_y: number;
+  units_x: number;
+  value_y: number;
+  value_x: number;
+  gross_exposure: number;
+  net_exposure: number;
+}
+"#;
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "PositionSizesObject")]
+    pub type PositionSizesObject;
+}
+
+/// Per-leg position sizes for a long-spread position (long y, short x) using `capital` of
+/// gross exposure. Flip the signs for a short-spread position.
+///
+/// With `dollar_neutral` the capital is split equally so both legs have the same dollar value.
+/// Otherwise the legs are beta-weighted: `beta` is read as a price-level hedge ratio (units of
+/// x per unit of y, as produced by the cointegrating regressions), so `units_x = -beta·units_y`
+/// and the spread y - beta·x is traded exactly. Units are fractional; round them to lot sizes
+/// on the caller's side.
+///
+/// Returns `{ units_y, units_x, value_y, value_x, gross_exposure, net_exposure }`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn position_sizes(
+    beta: f64,
+    price_y: f64,
+    price_x: f64,
+    capital: f64,
+    dollar_neutral: bool,
+) -> Result<PositionSizesObject, Error> {
+    let sizes = compute_position_sizes(beta, price_y, price_x, capital, dollar_neutral)?;
+    let result = js_sys::Object::new();
+    set_property(&result, "units_y", sizes.units_y);
+    set_property(&result, "units_x", sizes.units_x);
+    set_property(&result, "value_y", sizes.value_y);
+    set_property(&result, "value_x", sizes.value_x);
+    set_property(&result, "gross_exposure", sizes.gross_exposure);
+    set_property(&result, "net_exposure", sizes.net_exposure);
+    Ok(result.unchecked_into())
+}
+
+/// Leg sizes computed by `position_sizes`.
+#[derive(Clone, Copy, Debug)]
+pub struct PositionSizes {
+    pub units_y: f64,
+    pub units_x: f64,
+    pub value_y: f64,
+    pub value_x: f64,
+    pub gross_exposure: f64,
+    pub net_exposure: f64,
+}
+
+/// `position_sizes` for native callers, returning a struct instead of a JS object.
+pub fn compute_position_sizes(
+    beta: f64,
+    price_y: f64,
+    price_x: f64,
+    capital: f64,
+    dollar_neutral: bool,
+) -> Result<PositionSizes, Error> {
+    let positive = |v: f64| v > 0.0 && v.is_finite();
+    if !positive(price_y) || !positive(price_x) {
+        return Err(Error::new("prices must be positive and finite"));
+    }
+    if !(positive(capital) || capital == 0.0) {
+        return Err(Error::new("capital must be non-negative and finite"));
+    }
+    if !beta.is_finite() {
+        return Err(Error::new("beta must be finite"));
+    }
+
+    let (units_y, units_x) = if dollar_neutral {
+        (capital / (2.0 * price_y), -capital / (2.0 * price_x))
+    } else {
+        let units_y = capital / (price_y + beta.abs() * price_x);
+        (units_y, -beta * units_y)
+    };
+    let value_y = units_y * price_y;
+    let value_x = units_x * price_x;
+
+    Ok(PositionSizes {
+        units_y,
+        units_x,
+        value_y,
+        value_x,
+        gross_exposure: value_y.abs() + value_x.abs(),
+        net_exposure: value_y + value_x,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linalg::least_squares;
+    use crate::rng::fixtures::cointegrated_pair;
+    use crate::rng::Rng;
+
+    // Random-walk x and y = 3 + 1.7·x + white noise
+    fn pair(n: usize, seed: u32) -> (Vec<f64>, Vec<f64>) {
+        let (y, x, _) = cointegrated_pair(n, 1.7, 0.0, seed);
+        (y, x)
+    }
+
+    fn demeaned(values: &[f64]) -> Vec<f64> {
+        let mean = nanmean(values, NanPolicy::Propagate);
+        values.iter().map(|v| v - mean).collect()
+    }
+
+    #[test]
+    fn min_variance_equals_no_intercept_slope_of_demeaned_legs() {
+        let (y, x) = pair(300, 1);
+        let (y, x) = (demeaned(&y), demeaned(&x));
+        let through_origin = least_squares(std::slice::from_ref(&x), &y)
+            .unwrap()
+            .coefficients[0];
+        let h = min_variance_hedge_ratio(&y, &x);
+        assert!(
+            (h - through_origin).abs() < 1e-12,
+            "{} vs {}",
+            h,
+            through_origin
+        );
+    }
+
+    #[test]
+    fn min_variance_equals_intercept_slope_of_raw_legs() {
+        let (y, x) = pair(300, 2);
+        let with_intercept = least_squares(&[x.clone(), vec![1.0; x.len()]], &y)
+            .unwrap()
+            .coefficients[0];
+        let through_origin = least_squares(std::slice::from_ref(&x), &y)
+            .unwrap()
+            .coefficients[0];
+        let h = min_variance_hedge_ratio(&y, &x);
+        assert!((h - with_intercept).abs() < 1e-10);
+        // The level offset of 3 pulls the through-origin slope away
+        assert!((h - through_origin).abs() > 1e-3);
+    }
+
+    #[test]
+    fn robust_and_classical_hedge_ratios_differ_on_contaminated_data() {
+        // Bad ticks at the ten highest x, where they have the most leverage on OLS
+        let (mut y, x) = pair(300, 9);
+        let mut order: Vec<usize> = (0..x.len()).collect();
+        order.sort_by(|a, b| x[*b].total_cmp(&x[*a]));
+        let bad_ticks = &order[..10];
+        for &i in bad_ticks {
+            y[i] -= 40.0;
+        }
+        let ols = min_variance_hedge_ratio(&y, &x);
+        for method in ["theil-sen", "huber"] {
+            let robust = robust_hedge_ratio(&y, &x, method).unwrap();
+            assert!(
+                (robust.beta - 1.7).abs() < 0.05,
+                "{} beta {}",
+                method,
+                robust.beta
+            );
+            assert!(
+                (robust.beta - ols).abs() > 0.1,
+                "{} {} vs OLS {}",
+                method,
+                robust.beta,
+                ols
+            );
+            let outliers = robust.outliers();
+            assert!(bad_ticks.iter().all(|i| outliers.contains(&(*i as u32))));
+        }
+    }
+
+    #[test]
+    fn dollar_neutral_sizes_have_zero_net_exposure() {
+        for (beta, price_y, price_x) in [(1.7, 120.0, 45.0), (-0.4, 8.5, 310.0), (0.0, 1.0, 1.0)] {
+            let sizes = compute_position_sizes(beta, price_y, price_x, 10_000.0, true).unwrap();
+            assert!(sizes.net_exposure.abs() < 1e-9);
+            assert!((sizes.gross_exposure - 10_000.0).abs() < 1e-9);
+            assert!((sizes.value_y - 5_000.0).abs() < 1e-9);
+            assert!((sizes.value_x + 5_000.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn beta_weighted_sizes_trade_the_spread_with_the_full_capital() {
+        for beta in [1.7, -0.4, 0.0] {
+            let sizes = compute_position_sizes(beta, 120.0, 45.0, 10_000.0, false).unwrap();
+            assert!((sizes.units_x + beta * sizes.units_y).abs() < 1e-9);
+            assert!((sizes.gross_exposure - 10_000.0).abs() < 1e-9);
+        }
+
+        // Dollar neutral as well when one unit of y costs beta units of x
+        let sizes = compute_position_sizes(120.0 / 45.0, 120.0, 45.0, 10_000.0, false).unwrap();
+        assert!(sizes.net_exposure.abs() < 1e-9);
+    }
+
+    #[test]
+    fn tls_lies_between_the_two_ols_slopes() {
+        // Noise on both legs, so y-on-x OLS is attenuated and x-on-y OLS overshoots
+        let (y, x) = pair(400, 4);
+        let mut rng = Rng::new(5);
+        let x: Vec<f64> = x.iter().map(|v| v + 2.0 * rng.normal()).collect();
+        let ones = vec![1.0; x.len()];
+        let y_on_x = least_squares(&[x.clone(), ones.clone()], &y)
+            .unwrap()
+            .coefficients[0];
+        let x_on_y = least_squares(&[y.clone(), ones], &x).unwrap().coefficients[0];
+
+        let tls = tls_hedge_ratio(&y, &x).beta;
+        assert!(
+            y_on_x < tls && tls < 1.0 / x_on_y,
+            "{} {} {}",
+            y_on_x,
+            tls,
+            1.0 / x_on_y
+        );
+    }
+
+    #[test]
+    fn swapping_the_legs_gives_the_reciprocal_tls_slope() {
+        let (y, x) = pair(400, 6);
+        let forward = tls_hedge_ratio(&y, &x);
+        let reverse = tls_hedge_ratio(&x, &y);
+        assert!((forward.beta * reverse.beta - 1.0).abs() < 1e-12);
+        // Same line: x = -intercept / beta + y / beta
+        assert!((reverse.intercept + forward.intercept / forward.beta).abs() < 1e-9);
+    }
+}
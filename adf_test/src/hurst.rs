@@ -0,0 +1,314 @@
+// Hurst exponent estimators.
+//
+// Rescaled range (R/S): for each chunk size the series is split into
+// non-overlapping chunks and the average R/S over the chunks is computed; H is
+// the slope of log(R/S) against log(chunk size).
+//
+// Variance of differences: the standard deviation of x_{t+τ} - x_t scales as
+// τ^H, so H is the slope of log(std) against log(τ).
+//
+// H < 0.5 suggests mean reversion, ~0.5 a random walk and H > 0.5 a trending
+// series.
+//
+// Lo's modified R/S turns the rescaled range into a test: the range of the
+// partial sums over the whole sample is scaled by a long-run standard deviation
+// that absorbs short-range autocorrelation, and compared with the distribution of
+// the range of a Brownian bridge, which it follows under short memory.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::error::Error;
+use crate::hac::{autocovariance_sum, long_run_variance};
+use crate::json::{levels, JsonObject};
+use crate::linalg::least_squares;
+use crate::stats::{nanmean, nanstd, NanPolicy};
+use crate::validate::{require_finite, require_length, require_varying};
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct HurstResult {
+    pub exponent: f64,
+    // R² of the log-log regression; low values mean no single scaling law fits
+    pub r_squared: f64,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl HurstResult {
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("exponent", self.exponent)
+            .field("r_squared", self.r_squared)
+            .finish()
+    }
+}
+
+/// Hurst exponent of `series` with `method` "rs" (rescaled range over chunk sizes
+/// `min_lag..=max_lag`) or "variance" (scaling of the standard deviation of `τ`-bar differences
+/// for `τ` in `min_lag..=max_lag`), together with the R² of the log-log fit.
+///
+/// The variance method is the one usually applied to price or spread levels; R/S is meant for
+/// increments. Both fields are NaN if fewer than three lags are usable.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn hurst_exponent(
+    series: &[f64],
+    min_lag: usize,
+    max_lag: usize,
+    method: &str,
+) -> Result<HurstResult, Error> {
+    let fit = match method {
+        "rs" => hurst_rs(series, min_lag, max_lag),
+        "variance" => hurst_variance(series, min_lag, max_lag),
+        other => {
+            return Err(Error::new(&format!(
+                "unknown Hurst method \"{}\": expected \"rs\" or \"variance\"",
+                other
+            )))
+        }
+    };
+    Ok(fit.map_or(
+        HurstResult {
+            exponent: f64::NAN,
+            r_squared: f64::NAN,
+        },
+        |fit| HurstResult {
+            exponent: fit.exponent,
+            r_squared: fit.r_squared,
+        },
+    ))
+}
+
+pub(crate) struct HurstFit {
+    pub exponent: f64,
+    // R² of the log-log regression, i.e. how well a single power law fits
+    pub r_squared: f64,
+}
+
+// Chunk sizes min_lag..=max_lag; max_lag is capped at half the series length
+pub(crate) fn hurst_rs(series: &[f64], min_lag: usize, max_lag: usize) -> Option<HurstFit> {
+    let min_lag = min_lag.max(2);
+    let max_lag = max_lag.min(series.len() / 2);
+
+    let mut log_lags = Vec::new();
+    let mut log_rs = Vec::new();
+    for lag in min_lag..=max_lag {
+        let rs: Vec<f64> = series
+            .chunks_exact(lag)
+            .filter_map(rescaled_range)
+            .collect();
+        if rs.is_empty() {
+            continue;
+        }
+        log_lags.push((lag as f64).ln());
+        log_rs.push(nanmean(&rs, NanPolicy::Propagate).ln());
+    }
+
+    log_log_slope(log_lags, log_rs)
+}
+
+// Lags min_lag..=max_lag; max_lag is capped below the series length
+pub(crate) fn hurst_variance(series: &[f64], min_lag: usize, max_lag: usize) -> Option<HurstFit> {
+    let min_lag = min_lag.max(1);
+    let max_lag = max_lag.min(series.len().saturating_sub(2));
+
+    let mut log_lags = Vec::new();
+    let mut log_std = Vec::new();
+    for lag in min_lag..=max_lag {
+        let differences: Vec<f64> = series[lag..]
+            .iter()
+            .zip(series)
+            .map(|(a, b)| a - b)
+            .collect();
+        let std = nanstd(&differences, 0, NanPolicy::Propagate);
+        if std.is_nan() || std <= 0.0 {
+            continue;
+        }
+        log_lags.push((lag as f64).ln());
+        log_std.push(std.ln());
+    }
+    log_log_slope(log_lags, log_std)
+}
+
+// Slope and R² of the regression of log_values on log_lags
+fn log_log_slope(log_lags: Vec<f64>, log_values: Vec<f64>) -> Option<HurstFit> {
+    if log_lags.len() < 3 {
+        return None;
+    }
+
+    let mean_log_value = nanmean(&log_values, NanPolicy::Propagate);
+    let sst: f64 = log_values
+        .iter()
+        .map(|v| (v - mean_log_value) * (v - mean_log_value))
+        .sum();
+    let n_points = log_lags.len();
+    let ols = least_squares(&[log_lags, vec![1.0; n_points]], &log_values)?;
+
+    Some(HurstFit {
+        exponent: ols.coefficients[0],
+        r_squared: if sst > 0.0 {
+            1.0 - ols.ssr / sst
+        } else {
+            f64::NAN
+        },
+    })
+}
+
+// Range of the cumulative mean-adjusted sum divided by the chunk's standard deviation.
+// None for a flat chunk, where R/S is undefined.
+fn rescaled_range(chunk: &[f64]) -> Option<f64> {
+    let mean = nanmean(chunk, NanPolicy::Propagate);
+    let std = nanstd(chunk, 0, NanPolicy::Propagate);
+    if std.is_nan() || std <= 0.0 {
+        return None;
+    }
+
+    let mut cumulative = 0.0;
+    let mut max_dev = f64::NEG_INFINITY;
+    let mut min_dev = f64::INFINITY;
+    for &v in chunk {
+        cumulative += v - mean;
+        max_dev = max_dev.max(cumulative);
+        min_dev = min_dev.min(cumulative);
+    }
+    Some((max_dev - min_dev) / std)
+}
+
+// Lo (1991) Table II fractiles of the range of a Brownian bridge, as (lower, upper) bounds
+// of the acceptance region at the 10%, 5% and 1% levels
+const MODIFIED_RS_LOWER: [f64; 3] = [0.861, 0.809, 0.721];
+const MODIFIED_RS_UPPER: [f64; 3] = [1.747, 1.862, 2.098];
+const MODIFIED_RS_KEYS: [&str; 3] = ["10%", "5%", "1%"];
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct ModifiedRsResult {
+    // Lo's V_n(q) = R/S(q) / √n
+    pub statistic: f64,
+    // The same with q = 0, i.e. the classical R/S of Mandelbrot, which short-range
+    // autocorrelation alone can push outside the bounds
+    pub classical_statistic: f64,
+    // Bartlett lags of the long-run standard deviation
+    pub bandwidth: usize,
+    // Two-sided p-value of `statistic` under short memory
+    pub p_value: f64,
+    // Above the 5% upper bound: persistent long memory
+    pub long_memory: bool,
+    // Below the 5% lower bound: anti-persistence, as in a strongly mean-reverting spread
+    pub anti_persistent: bool,
+    pub nobs: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ModifiedRsResult {
+    // Lower bounds of the acceptance region at the 10%, 5% and 1% levels
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn lower_critical_values(&self) -> Vec<f64> {
+        MODIFIED_RS_LOWER.to_vec()
+    }
+
+    // Upper bounds of the acceptance region at the 10%, 5% and 1% levels
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn upper_critical_values(&self) -> Vec<f64> {
+        MODIFIED_RS_UPPER.to_vec()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("statistic", self.statistic)
+            .field("classical_statistic", self.classical_statistic)
+            .field("bandwidth", self.bandwidth)
+            .field("p_value", self.p_value)
+            .field(
+                "lower_critical_values",
+                levels(&MODIFIED_RS_KEYS, &MODIFIED_RS_LOWER),
+            )
+            .field(
+                "upper_critical_values",
+                levels(&MODIFIED_RS_KEYS, &MODIFIED_RS_UPPER),
+            )
+            .field("long_memory", self.long_memory)
+            .field("anti_persistent", self.anti_persistent)
+            .field("nobs", self.nobs)
+            .finish()
+    }
+}
+
+/// Lo's (1991) modified rescaled-range test of the null of short memory in `series`, a
+/// formal counterpart to the point estimate of `hurst_exponent`.
+///
+/// The statistic is the range of the partial sums of the demeaned series divided by √n and by
+/// the Newey-West long-run standard deviation with `bandwidth` Bartlett lags, so short-range
+/// autocorrelation does not register as long memory. Without a bandwidth Andrews' (1991) AR(1)
+/// rule ⌊(3n/2)^(1/3)·|2ρ/(1 - ρ²)|^(2/3)⌋ is used, as Lo did. Under the null the statistic
+/// has the distribution of the range of a Brownian bridge; values above the upper bound
+/// suggest long memory (H > 0.5) and values below the lower bound anti-persistence (H < 0.5).
+/// Apply it to a stationary series: the spread itself if it is cointegrated, its changes if not.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn modified_rs_test(
+    series: &[f64],
+    bandwidth: Option<usize>,
+) -> Result<ModifiedRsResult, Error> {
+    require_finite(series, "series")?;
+    require_length(series, 10, "series", "the modified R/S test")?;
+    require_varying(series, "series")?;
+
+    let n = series.len();
+    let mean = nanmean(series, NanPolicy::Propagate);
+    let demeaned: Vec<f64> = series.iter().map(|v| v - mean).collect();
+    let bandwidth = bandwidth
+        .unwrap_or_else(|| andrews_bandwidth(&demeaned))
+        .min(n - 1);
+
+    let mut cumulative = 0.0;
+    let mut max_sum = 0.0f64;
+    let mut min_sum = 0.0f64;
+    for d in &demeaned {
+        cumulative += d;
+        max_sum = max_sum.max(cumulative);
+        min_sum = min_sum.min(cumulative);
+    }
+    let range = (max_sum - min_sum) / (n as f64).sqrt();
+    let statistic = range / long_run_variance(&demeaned, bandwidth).sqrt();
+    let classical_statistic = range / long_run_variance(&demeaned, 0).sqrt();
+
+    let cdf = bridge_range_cdf(statistic);
+    Ok(ModifiedRsResult {
+        statistic,
+        classical_statistic,
+        bandwidth,
+        p_value: (2.0 * cdf.min(1.0 - cdf)).min(1.0),
+        long_memory: statistic > MODIFIED_RS_UPPER[1],
+        anti_persistent: statistic < MODIFIED_RS_LOWER[1],
+        nobs: n,
+    })
+}
+
+// Andrews (1991) data-dependent Bartlett bandwidth under an AR(1) approximation
+fn andrews_bandwidth(demeaned: &[f64]) -> usize {
+    let rho = autocovariance_sum(demeaned, 1) / autocovariance_sum(demeaned, 0);
+    let alpha = (2.0 * rho / (1.0 - rho * rho)).abs();
+    let bandwidth = (1.5 * demeaned.len() as f64).cbrt() * alpha.powf(2.0 / 3.0);
+    if bandwidth.is_finite() {
+        bandwidth as usize
+    } else {
+        demeaned.len() - 1
+    }
+}
+
+// P(V <= v) for the range V of a Brownian bridge on [0, 1]:
+// 1 + 2 Σ_{k>=1} (1 - 4k²v²) exp(-2k²v²), which is zero to double precision below v = 0.3
+fn bridge_range_cdf(v: f64) -> f64 {
+    if v < 0.3 {
+        return 0.0;
+    }
+    let mut sum = 1.0;
+    for k in 1..=100 {
+        let kv2 = (k * k) as f64 * v * v;
+        let term = 2.0 * (1.0 - 4.0 * kv2) * (-2.0 * kv2).exp();
+        sum += term;
+        if term.abs() < 1e-17 {
+            break;
+        }
+    }
+    sum.clamp(0.0, 1.0)
+}
@@ -0,0 +1,913 @@
+// Johansen (1991) maximum-likelihood cointegration test for several series.
+//
+// The VECM Δx_t = Π x_{t-1} + Σ_{i=1..k} Γ_i Δx_{t-i} + deterministic terms + ε_t
+// is concentrated by regressing both Δx_t and x_{t-1} on the lagged differences
+// (and deterministic terms). The squared canonical correlations between the two
+// sets of residuals are the eigenvalues λ_1 >= .. >= λ_m, and the rank of Π is
+// tested with the trace statistic -T Σ_{j>r} ln(1 - λ_j) and the maximum
+// eigenvalue statistic -T ln(1 - λ_{r+1}). Same estimator as statsmodels'
+// `coint_johansen`.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::adf::{adf_test_with_config, AdfOptions};
+use crate::distributions::chi2_sf;
+use crate::dols::RestrictionTest;
+use crate::error::Error;
+use crate::json::{JsonObject, RawJson};
+use crate::linalg::{cholesky, cholesky_solve, least_squares, symmetric_eigen};
+#[cfg(feature = "wasm")]
+use crate::progress::{run_in_slices, ProgressOptions};
+use crate::progress::{run_to_completion, Job};
+use crate::validate::{require_finite, require_length, require_varying};
+use crate::AdfResult;
+
+// Osterwald-Lenum (1992) / MacKinnon-Haug-Michelis (1999) 90%, 95% and 99% critical values,
+// row m - r - 1 for m - r common trends under the null, as tabulated in statsmodels
+const MAX_ASSETS: usize = 12;
+type CriticalTable = [[f64; 3]; MAX_ASSETS];
+const TRACE_NONE: CriticalTable = [
+    [2.9762, 4.1296, 6.9406],
+    [10.4741, 12.3212, 16.3640],
+    [21.7781, 24.2761, 29.5147],
+    [37.0339, 40.1749, 46.5716],
+    [56.2839, 60.0627, 67.6367],
+    [79.5329, 83.9383, 92.7136],
+    [106.7351, 111.7797, 121.7375],
+    [137.9954, 143.6691, 154.7977],
+    [173.2292, 179.5199, 191.8122],
+    [212.4721, 219.4051, 232.8291],
+    [255.6732, 263.2603, 277.9962],
+    [302.9054, 311.1288, 326.9716],
+];
+const TRACE_CONSTANT: CriticalTable = [
+    [2.7055, 3.8415, 6.6349],
+    [13.4294, 15.4943, 19.9349],
+    [27.0669, 29.7961, 35.4628],
+    [44.4929, 47.8545, 54.6815],
+    [65.8202, 69.8189, 77.8202],
+    [91.1090, 95.7542, 104.9637],
+    [120.3673, 125.6185, 135.9825],
+    [153.6341, 159.5290, 171.0905],
+    [190.8714, 197.3772, 210.0366],
+    [232.1030, 239.2468, 253.2526],
+    [277.3740, 285.1402, 300.2821],
+    [326.5354, 334.9795, 351.2150],
+];
+const TRACE_TREND: CriticalTable = [
+    [2.7055, 3.8415, 6.6349],
+    [16.1619, 18.3985, 23.1485],
+    [32.0645, 35.0116, 41.0815],
+    [51.6492, 55.2459, 62.5202],
+    [75.1027, 79.3422, 87.7748],
+    [102.4674, 107.3429, 116.9829],
+    [133.7852, 139.2780, 150.0778],
+    [169.0618, 175.1584, 187.1891],
+    [208.3582, 215.1268, 228.2226],
+    [251.6293, 259.0267, 273.3838],
+    [298.8836, 306.8988, 322.4264],
+    [350.1125, 358.7190, 375.3203],
+];
+const MAX_EIGEN_NONE: CriticalTable = [
+    [2.9762, 4.1296, 6.9406],
+    [9.4748, 11.2246, 15.0923],
+    [15.7175, 17.7961, 22.2519],
+    [21.8370, 24.1592, 29.0609],
+    [27.9160, 30.4428, 35.7359],
+    [33.9271, 36.6301, 42.2333],
+    [39.9085, 42.7679, 48.6606],
+    [45.8930, 48.8795, 55.0335],
+    [51.8528, 54.9629, 61.3449],
+    [57.7954, 61.0404, 67.6415],
+    [63.7248, 67.0756, 73.8856],
+    [69.6513, 73.0946, 80.0937],
+];
+const MAX_EIGEN_CONSTANT: CriticalTable = [
+    [2.7055, 3.8415, 6.6349],
+    [12.2971, 14.2639, 18.5200],
+    [18.8928, 21.1314, 25.8650],
+    [25.1236, 27.5858, 32.7172],
+    [31.2379, 33.8777, 39.3693],
+    [37.2786, 40.0763, 45.8662],
+    [43.2947, 46.2299, 52.3069],
+    [49.2855, 52.3622, 58.6634],
+    [55.2412, 58.4332, 64.9960],
+    [61.2041, 64.5040, 71.2525],
+    [67.1307, 70.5392, 77.4877],
+    [73.0563, 76.5734, 83.7105],
+];
+const MAX_EIGEN_TREND: CriticalTable = [
+    [2.7055, 3.8415, 6.6349],
+    [15.0006, 17.1481, 21.7465],
+    [21.8731, 24.2522, 29.2631],
+    [28.2398, 30.8151, 36.1930],
+    [34.4202, 37.1646, 42.8612],
+    [40.5244, 43.4183, 49.4095],
+    [46.5583, 49.5875, 55.8171],
+    [52.5858, 55.7302, 62.1741],
+    [58.5316, 61.8051, 68.5030],
+    [64.5292, 67.9040, 74.7434],
+    [70.4630, 73.9355, 81.0678],
+    [76.4081, 79.9878, 87.2395],
+];
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct JohansenResult {
+    pub n_assets: usize,
+    // Effective sample size T after differencing and lagging
+    pub nobs: usize,
+    eigenvalues: Vec<f64>,
+    eigenvectors: Vec<f64>,
+    trace_statistics: Vec<f64>,
+    max_eigen_statistics: Vec<f64>,
+    trace_critical_values: Vec<f64>,
+    max_eigen_critical_values: Vec<f64>,
+    // Sk0 S00^-1 S0k and Skk of the concentrated VECM, m x m row-major, kept for restriction
+    // tests
+    impact: Vec<f64>,
+    skk: Vec<f64>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl JohansenResult {
+    // Eigenvalues in decreasing order
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn eigenvalues(&self) -> Vec<f64> {
+        self.eigenvalues.clone()
+    }
+
+    // n_assets x n_assets, row-major; column j is the cointegrating vector for eigenvalue j,
+    // normalised so that the vectors are orthonormal with respect to the levels' residual
+    // covariance (the sign of each column is arbitrary)
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn eigenvectors(&self) -> Vec<f64> {
+        self.eigenvectors.clone()
+    }
+
+    // Entry r tests "at most r cointegrating relations" against "n_assets"
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn trace_statistics(&self) -> Vec<f64> {
+        self.trace_statistics.clone()
+    }
+
+    // Entry r tests "r cointegrating relations" against "r + 1"
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn max_eigen_statistics(&self) -> Vec<f64> {
+        self.max_eigen_statistics.clone()
+    }
+
+    // n_assets x 3, row-major: 90%, 95% and 99% critical values for each trace statistic
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn trace_critical_values(&self) -> Vec<f64> {
+        self.trace_critical_values.clone()
+    }
+
+    // n_assets x 3, row-major: 90%, 95% and 99% critical values for each max-eigenvalue statistic
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn max_eigen_critical_values(&self) -> Vec<f64> {
+        self.max_eigen_critical_values.clone()
+    }
+
+    /// Likelihood-ratio test (Johansen 1991) that the first `rank` cointegrating vectors lie in
+    /// the span of the `n_free` columns of `h` (`n_assets` x `n_free`, row-major), i.e. the
+    /// restriction β = Hφ. The statistic T·Σ_{i<rank} ln((1 - λ*_i) / (1 - λ_i)) compares the
+    /// restricted eigenvalues λ* with the unrestricted ones and is χ² with
+    /// rank·(n_assets - n_free) degrees of freedom.
+    pub fn restriction_test(
+        &self,
+        h: &[f64],
+        n_free: usize,
+        rank: usize,
+    ) -> Result<RestrictionTest, Error> {
+        let m = self.n_assets;
+        if n_free == 0 || n_free >= m {
+            return Err(Error::new(&format!(
+                "n_free must be between 1 and {} (one less than n_assets)",
+                m - 1
+            )));
+        }
+        if rank == 0 || rank > n_free {
+            return Err(Error::new("rank must be between 1 and n_free"));
+        }
+        if h.len() != m * n_free {
+            return Err(Error::new(&format!(
+                "h has {} values but n_assets * n_free is {}",
+                h.len(),
+                m * n_free
+            )));
+        }
+        require_finite(h, "h")?;
+
+        // H' M H for an m x m matrix M, n_free x n_free row-major
+        let s = n_free;
+        let project = |matrix: &[f64]| -> Vec<f64> {
+            let mut out = vec![0.0; s * s];
+            for a in 0..s {
+                for b in 0..s {
+                    out[a * s + b] = (0..m)
+                        .flat_map(|i| (0..m).map(move |j| (i, j)))
+                        .map(|(i, j)| h[i * s + a] * matrix[i * m + j] * h[j * s + b])
+                        .sum();
+                }
+            }
+            out
+        };
+        let impact = project(&self.impact);
+        let l = cholesky(&project(&self.skk), s)
+            .ok_or_else(|| Error::new("h must have linearly independent columns"))?;
+        let half = transform_columns(&l, s, &impact, forward_substitute);
+        let mut symmetric = transform_columns(&l, s, &transpose(&half, s), forward_substitute);
+        for i in 0..s {
+            for j in (i + 1)..s {
+                let average = 0.5 * (symmetric[i * s + j] + symmetric[j * s + i]);
+                symmetric[i * s + j] = average;
+                symmetric[j * s + i] = average;
+            }
+        }
+        let (mut restricted, _) = symmetric_eigen(&symmetric, s);
+        restricted.sort_by(|a, b| b.total_cmp(a));
+
+        let statistic = self.nobs as f64
+            * (0..rank)
+                .map(|i| {
+                    let restricted = restricted[i].clamp(0.0, 1.0);
+                    ((1.0 - restricted) / (1.0 - self.eigenvalues[i])).ln()
+                })
+                .sum::<f64>();
+        let df = rank * (m - n_free);
+        Ok(RestrictionTest {
+            statistic,
+            df,
+            p_value: chi2_sf(statistic, df as f64),
+        })
+    }
+
+    /// For two assets, the `restriction_test` that the cointegrating vector is
+    /// (1, -hedge_ratio), i.e. that y - hedge_ratio·x is the stationary spread, with y the first
+    /// column of the matrix tested. χ² with one degree of freedom.
+    pub fn hedge_ratio_test(&self, hedge_ratio: f64) -> Result<RestrictionTest, Error> {
+        if self.n_assets != 2 {
+            return Err(Error::new("hedge_ratio_test needs exactly two assets"));
+        }
+        if !hedge_ratio.is_finite() {
+            return Err(Error::new("hedge_ratio must be a finite number"));
+        }
+        self.restriction_test(&[1.0, -hedge_ratio], 1, 1)
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("n_assets", self.n_assets)
+            .field("nobs", self.nobs)
+            .field("eigenvalues", &self.eigenvalues)
+            .field("eigenvectors", &self.eigenvectors)
+            .field("trace_statistics", &self.trace_statistics)
+            .field("max_eigen_statistics", &self.max_eigen_statistics)
+            .field("trace_critical_values", &self.trace_critical_values)
+            .field("max_eigen_critical_values", &self.max_eigen_critical_values)
+            .finish()
+    }
+}
+
+/// Johansen cointegration test for a basket of `n_assets` series.
+///
+/// `matrix` holds `n_obs` rows of `n_assets` prices, row-major (one row per time step).
+/// `det_order` selects the deterministic terms: -1 for none, 0 for a constant, 1 for a
+/// constant plus linear trend; `k_ar_diff` is the number of lagged differences in the VECM.
+/// Supports up to 12 assets, the extent of the critical-value tables.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn johansen_test(
+    matrix: &[f64],
+    n_assets: usize,
+    n_obs: usize,
+    det_order: i32,
+    k_ar_diff: usize,
+) -> Result<JohansenResult, Error> {
+    let mut estimation = Estimation::new(matrix, n_assets, n_obs, det_order, k_ar_diff)?;
+    run_to_completion(&mut estimation)?;
+    estimation.finish()
+}
+
+/// `johansen_test` as a Promise that runs the concentrated regressions in slices of about
+/// `slice_ms` milliseconds (default 16), yielding to the event loop between slices so a page
+/// stays responsive on a large basket; the result is the same as the synchronous one.
+///
+/// `options` is an optional object with `on_progress`, `signal` and `slice_ms`, as for
+/// `simulate_critical_values_async`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub async fn johansen_test_async(
+    matrix: Vec<f64>,
+    n_assets: usize,
+    n_obs: usize,
+    det_order: i32,
+    k_ar_diff: usize,
+    options: JsValue,
+) -> Result<JohansenResult, Error> {
+    let progress = ProgressOptions::from_js(&options)?;
+    let mut estimation = Estimation::new(&matrix, n_assets, n_obs, det_order, k_ar_diff)?;
+    run_in_slices(&mut estimation, &progress).await?;
+    estimation.finish()
+}
+
+// The `johansen_test` estimation, one concentrated regression per step: Δx_t and then x_{t-1}
+// of each asset are regressed on the lagged differences, and the last step solves the
+// eigenvalue problem
+struct Estimation {
+    trace_table: &'static CriticalTable,
+    max_eigen_table: &'static CriticalTable,
+    // One column per asset, detrended when there is a trend term
+    levels: Vec<Vec<f64>>,
+    regressors: Vec<Vec<f64>>,
+    // First VECM row, k_ar_diff + 1
+    start: usize,
+    r0: Vec<Vec<f64>>,
+    rk: Vec<Vec<f64>>,
+    result: Option<JohansenResult>,
+}
+
+impl Estimation {
+    fn new(
+        matrix: &[f64],
+        n_assets: usize,
+        n_obs: usize,
+        det_order: i32,
+        k_ar_diff: usize,
+    ) -> Result<Estimation, Error> {
+        let m = n_assets;
+        if m == 0 || m > MAX_ASSETS {
+            return Err(Error::new(&format!(
+                "n_assets must be between 1 and {}",
+                MAX_ASSETS
+            )));
+        }
+        if matrix.len() != m * n_obs {
+            return Err(Error::new(&format!(
+                "matrix has {} values but n_assets * n_obs is {}",
+                matrix.len(),
+                m * n_obs
+            )));
+        }
+        require_finite(matrix, "matrix")?;
+        let (trace_table, max_eigen_table) = match det_order {
+            -1 => (&TRACE_NONE, &MAX_EIGEN_NONE),
+            0 => (&TRACE_CONSTANT, &MAX_EIGEN_CONSTANT),
+            1 => (&TRACE_TREND, &MAX_EIGEN_TREND),
+            other => {
+                return Err(Error::new(&format!(
+                    "unsupported det_order {}: expected -1, 0 or 1",
+                    other
+                )))
+            }
+        };
+
+        // One column per asset; with a trend term the levels are detrended first
+        let mut levels: Vec<Vec<f64>> = (0..m)
+            .map(|j| (0..n_obs).map(|t| matrix[t * m + j]).collect())
+            .collect();
+        // The VECM rows left after the lags must outnumber the lagged differences, the constant
+        // and the m lagged levels
+        let constant = usize::from(det_order >= 0);
+        require_length(
+            &levels[0],
+            m * (k_ar_diff + 1) + constant + k_ar_diff + 2,
+            "matrix",
+            &format!(
+                "a VECM with {} assets and {} lagged differences",
+                m, k_ar_diff
+            ),
+        )?;
+        for (j, column) in levels.iter().enumerate() {
+            require_varying(column, &format!("series {}", j))?;
+        }
+        if det_order == 1 {
+            let trend: Vec<f64> = (0..n_obs).map(|t| t as f64).collect();
+            for column in levels.iter_mut() {
+                *column = least_squares(&[vec![1.0; n_obs], trend.clone()], column)
+                    .ok_or_else(|| Error::new("too few observations to detrend the series"))?
+                    .residuals;
+            }
+        }
+
+        // Rows t = k_ar_diff + 1 .. n_obs - 1 of Δx_t, x_{t-1} and the lagged differences
+        let start = k_ar_diff + 1;
+        let t_eff = n_obs.saturating_sub(start);
+        let mut regressors = Vec::with_capacity(m * k_ar_diff + 1);
+        for lag in 1..=k_ar_diff {
+            for column in &levels {
+                regressors.push(
+                    (start..n_obs)
+                        .map(|t| column[t - lag] - column[t - lag - 1])
+                        .collect::<Vec<f64>>(),
+                );
+            }
+        }
+        if det_order >= 0 {
+            regressors.push(vec![1.0; t_eff]);
+        }
+
+        Ok(Estimation {
+            trace_table,
+            max_eigen_table,
+            levels,
+            regressors,
+            start,
+            r0: Vec::with_capacity(m),
+            rk: Vec::with_capacity(m),
+            result: None,
+        })
+    }
+
+    fn finish(self) -> Result<JohansenResult, Error> {
+        self.result
+            .ok_or_else(|| Error::new("the Johansen estimation did not complete"))
+    }
+
+    // Moment matrices, eigenvalue problem and statistics from the concentrated residuals
+    fn solve(&self) -> Result<JohansenResult, Error> {
+        let (m, r0, rk) = (self.levels.len(), &self.r0, &self.rk);
+        let t_eff = self.levels[0].len() - self.start;
+        let (trace_table, max_eigen_table) = (self.trace_table, self.max_eigen_table);
+        let moments = |a: &[Vec<f64>], b: &[Vec<f64>]| -> Vec<f64> {
+            let mut s = vec![0.0; m * m];
+            for i in 0..m {
+                for j in 0..m {
+                    s[i * m + j] =
+                        a[i].iter().zip(&b[j]).map(|(x, y)| x * y).sum::<f64>() / t_eff as f64;
+                }
+            }
+            s
+        };
+        let s00 = moments(r0, r0);
+        let sk0 = moments(rk, r0);
+        let skk = moments(rk, rk);
+
+        // Solve |λ Skk - Sk0 S00^-1 S0k| = 0 as the symmetric problem
+        // L^-1 (Sk0 S00^-1 S0k) L^-T w = λ w with Skk = L L', then v = L^-T w
+        let singular = || Error::new("residual covariance is singular: series are collinear");
+        let l00 = cholesky(&s00, m).ok_or_else(singular)?;
+        let lkk = cholesky(&skk, m).ok_or_else(singular)?;
+        let mut product = vec![0.0; m * m];
+        for j in 0..m {
+            let s0k_column: Vec<f64> = (0..m).map(|i| sk0[j * m + i]).collect();
+            let solved = cholesky_solve(&l00, m, &s0k_column);
+            for i in 0..m {
+                product[i * m + j] = (0..m).map(|c| sk0[i * m + c] * solved[c]).sum();
+            }
+        }
+        let half = transform_columns(&lkk, m, &product, forward_substitute);
+        let mut symmetric = transform_columns(&lkk, m, &transpose(&half, m), forward_substitute);
+        for i in 0..m {
+            for j in (i + 1)..m {
+                let average = 0.5 * (symmetric[i * m + j] + symmetric[j * m + i]);
+                symmetric[i * m + j] = average;
+                symmetric[j * m + i] = average;
+            }
+        }
+        let (values, vectors) = symmetric_eigen(&symmetric, m);
+        let vectors = transform_columns(&lkk, m, &vectors, back_substitute_transpose);
+
+        let mut order: Vec<usize> = (0..m).collect();
+        order.sort_by(|&a, &b| values[b].total_cmp(&values[a]));
+        let eigenvalues: Vec<f64> = order.iter().map(|&i| values[i].clamp(0.0, 1.0)).collect();
+        let mut eigenvectors = vec![0.0; m * m];
+        for (new, &old) in order.iter().enumerate() {
+            for i in 0..m {
+                eigenvectors[i * m + new] = vectors[i * m + old];
+            }
+        }
+
+        let t = t_eff as f64;
+        let log_complements: Vec<f64> = eigenvalues.iter().map(|l| (1.0 - l).ln()).collect();
+        let trace_statistics = (0..m)
+            .map(|r| -t * log_complements[r..].iter().sum::<f64>())
+            .collect();
+        let max_eigen_statistics = log_complements.iter().map(|l| -t * l).collect();
+        let trace_critical_values = (0..m).flat_map(|r| trace_table[m - r - 1]).collect();
+        let max_eigen_critical_values = (0..m).flat_map(|r| max_eigen_table[m - r - 1]).collect();
+
+        Ok(JohansenResult {
+            n_assets: m,
+            nobs: t_eff,
+            eigenvalues,
+            eigenvectors,
+            trace_statistics,
+            max_eigen_statistics,
+            trace_critical_values,
+            max_eigen_critical_values,
+            impact: product,
+            skk,
+        })
+    }
+}
+
+impl Job for Estimation {
+    fn step(&mut self) -> Result<bool, Error> {
+        if self.result.is_some() {
+            return Ok(true);
+        }
+        let (start, n_obs) = (self.start, self.levels[0].len());
+        if self.rk.len() < self.levels.len() {
+            let column = &self.levels[self.rk.len()];
+            if self.r0.len() == self.rk.len() {
+                let delta: Vec<f64> = (start..n_obs).map(|t| column[t] - column[t - 1]).collect();
+                self.r0.push(partial_out(delta, &self.regressors)?);
+            } else {
+                let lagged: Vec<f64> = (start..n_obs).map(|t| column[t - 1]).collect();
+                self.rk.push(partial_out(lagged, &self.regressors)?);
+            }
+            return Ok(false);
+        }
+        self.result = Some(self.solve()?);
+        Ok(true)
+    }
+
+    fn fraction_done(&self) -> f64 {
+        let done = self.r0.len() + self.rk.len() + usize::from(self.result.is_some());
+        done as f64 / (2 * self.levels.len() + 1) as f64
+    }
+}
+
+// Residuals of `y` after regressing it on `regressors` (unchanged if there are none)
+fn partial_out(y: Vec<f64>, regressors: &[Vec<f64>]) -> Result<Vec<f64>, Error> {
+    if regressors.is_empty() {
+        return Ok(y);
+    }
+    least_squares(regressors, &y)
+        .map(|ols| ols.residuals)
+        .ok_or_else(|| Error::new("lagged-difference regression is singular"))
+}
+
+// Apply `solve` to every column of the m x m row-major matrix `a`
+fn transform_columns(
+    l: &[f64],
+    m: usize,
+    a: &[f64],
+    solve: fn(&[f64], usize, &[f64]) -> Vec<f64>,
+) -> Vec<f64> {
+    let mut out = vec![0.0; m * m];
+    for j in 0..m {
+        let column: Vec<f64> = (0..m).map(|i| a[i * m + j]).collect();
+        for (i, v) in solve(l, m, &column).into_iter().enumerate() {
+            out[i * m + j] = v;
+        }
+    }
+    out
+}
+
+fn transpose(a: &[f64], m: usize) -> Vec<f64> {
+    (0..m * m).map(|idx| a[(idx % m) * m + idx / m]).collect()
+}
+
+// Solve L x = b for lower-triangular L
+fn forward_substitute(l: &[f64], m: usize, b: &[f64]) -> Vec<f64> {
+    let mut x = vec![0.0; m];
+    for i in 0..m {
+        let s: f64 = (0..i).map(|c| l[i * m + c] * x[c]).sum();
+        x[i] = (b[i] - s) / l[i * m + i];
+    }
+    x
+}
+
+// Solve L' x = b for lower-triangular L
+fn back_substitute_transpose(l: &[f64], m: usize, b: &[f64]) -> Vec<f64> {
+    let mut x = vec![0.0; m];
+    for i in (0..m).rev() {
+        let s: f64 = ((i + 1)..m).map(|c| l[c * m + i] * x[c]).sum();
+        x[i] = (b[i] - s) / l[i * m + i];
+    }
+    x
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct BasketResult {
+    // Cointegrating relations the trace test finds at 95%
+    pub rank: usize,
+    // Eigenvalue of the leading cointegrating vector
+    pub eigenvalue: f64,
+    weights: Vec<f64>,
+    hedge_ratios: Vec<f64>,
+    basket: Vec<f64>,
+    adf: AdfResult,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl BasketResult {
+    // Units of each asset per unit of basket, scaled so their absolute values sum to 1 and the
+    // first is positive
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn weights(&self) -> Vec<f64> {
+        self.weights.clone()
+    }
+
+    // The same vector scaled so the first asset's weight is 1
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn hedge_ratios(&self) -> Vec<f64> {
+        self.hedge_ratios.clone()
+    }
+
+    // Σ weights_i · price_i at every row of the matrix
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn basket(&self) -> Vec<f64> {
+        self.basket.clone()
+    }
+
+    // ADF test of the basket with the default options
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn adf(&self) -> AdfResult {
+        self.adf.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("rank", self.rank)
+            .field("eigenvalue", self.eigenvalue)
+            .field("weights", &self.weights)
+            .field("hedge_ratios", &self.hedge_ratios)
+            .field("basket", &self.basket)
+            .field("adf", RawJson(self.adf.to_json()))
+            .finish()
+    }
+}
+
+/// The most mean-reverting basket of `n_assets` series and a stationarity check of it, in one
+/// call.
+///
+/// Runs `johansen_test` on `matrix` (rows of `n_assets` prices, row-major) with `det_order`
+/// (default 0) and `k_ar_diff` (default 1), takes the cointegrating vector of the largest
+/// eigenvalue as the basket weights, scales them to unit gross exposure, builds the basket
+/// Σ w_i·p_i and runs `adf_test_with_config` on it with the default options. `rank` says how
+/// many relations the trace test supports; with rank 0 the basket is only the best of a set of
+/// non-stationary combinations. The weights were fitted on the same data, so the ADF p-value
+/// overstates the evidence, as with a single Engle-Granger regression.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn best_basket(
+    matrix: &[f64],
+    n_assets: usize,
+    det_order: Option<i32>,
+    k_ar_diff: Option<usize>,
+) -> Result<BasketResult, Error> {
+    let mut estimation = basket_estimation(matrix, n_assets, det_order, k_ar_diff)?;
+    run_to_completion(&mut estimation)?;
+    basket_from(matrix, &estimation.finish()?)
+}
+
+/// `best_basket` as a Promise that runs the Johansen estimation in slices, as
+/// `johansen_test_async` does; the result is the same as the synchronous one.
+///
+/// `options` is an optional object with `on_progress`, `signal` and `slice_ms`, as for
+/// `simulate_critical_values_async`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub async fn best_basket_async(
+    matrix: Vec<f64>,
+    n_assets: usize,
+    det_order: Option<i32>,
+    k_ar_diff: Option<usize>,
+    options: JsValue,
+) -> Result<BasketResult, Error> {
+    let progress = ProgressOptions::from_js(&options)?;
+    let mut estimation = basket_estimation(&matrix, n_assets, det_order, k_ar_diff)?;
+    run_in_slices(&mut estimation, &progress).await?;
+    basket_from(&matrix, &estimation.finish()?)
+}
+
+// The Johansen estimation of `best_basket`, with its defaults and basket checks
+fn basket_estimation(
+    matrix: &[f64],
+    n_assets: usize,
+    det_order: Option<i32>,
+    k_ar_diff: Option<usize>,
+) -> Result<Estimation, Error> {
+    if n_assets < 2 {
+        return Err(Error::new("a basket needs at least two assets"));
+    }
+    if !matrix.len().is_multiple_of(n_assets) {
+        return Err(Error::new("matrix length must be a multiple of n_assets"));
+    }
+    Estimation::new(
+        matrix,
+        n_assets,
+        matrix.len() / n_assets,
+        det_order.unwrap_or(0),
+        k_ar_diff.unwrap_or(1),
+    )
+}
+
+// Basket of the leading cointegrating vector of `johansen`, fitted on `matrix`
+fn basket_from(matrix: &[f64], johansen: &JohansenResult) -> Result<BasketResult, Error> {
+    let m = johansen.n_assets;
+    let leading: Vec<f64> = (0..m).map(|i| johansen.eigenvectors[i * m]).collect();
+    let gross: f64 = leading.iter().map(|v| v.abs()).sum();
+    if !(gross > 0.0 && gross.is_finite()) {
+        return Err(Error::new("the leading cointegrating vector is degenerate"));
+    }
+    let sign = if leading[0] < 0.0 { -1.0 } else { 1.0 };
+    let weights: Vec<f64> = leading.iter().map(|v| sign * v / gross).collect();
+    let hedge_ratios = if weights[0] != 0.0 {
+        weights.iter().map(|w| w / weights[0]).collect()
+    } else {
+        vec![f64::NAN; m]
+    };
+    let basket: Vec<f64> = matrix
+        .chunks_exact(m)
+        .map(|row| row.iter().zip(&weights).map(|(p, w)| p * w).sum())
+        .collect();
+    let adf = adf_test_with_config(&basket, &AdfOptions::default())?;
+    let rank = (0..m)
+        .take_while(|&r| johansen.trace_statistics[r] > johansen.trace_critical_values[r * 3 + 1])
+        .count();
+    Ok(BasketResult {
+        rank,
+        eigenvalue: johansen.eigenvalues[0],
+        weights,
+        hedge_ratios,
+        basket,
+        adf,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::fixtures::cointegrated_pair;
+    use crate::rng::Rng;
+
+    // Three random walks and a fourth asset cointegrated with the first two, row-major
+    fn basket(n: usize, seed: u32) -> Vec<f64> {
+        let mut rng = Rng::new(seed);
+        let mut walks = [10.0, 20.0, 30.0];
+        let mut spread = 0.0;
+        let mut matrix = Vec::with_capacity(4 * n);
+        for _ in 0..n {
+            for walk in walks.iter_mut() {
+                *walk += rng.normal();
+            }
+            spread = 0.5 * spread + rng.normal();
+            matrix.extend_from_slice(&walks);
+            matrix.push(0.5 * walks[0] + 1.5 * walks[1] + spread);
+        }
+        matrix
+    }
+
+    #[test]
+    fn stepped_estimation_matches_the_synchronous_result() {
+        let matrix = basket(300, 21);
+        let johansen = johansen_test(&matrix, 4, 300, 0, 2).unwrap();
+
+        // One concentrated regression per step, as the async variants run it between yields
+        let mut estimation = Estimation::new(&matrix, 4, 300, 0, 2).unwrap();
+        let mut steps = 0;
+        let mut fraction = estimation.fraction_done();
+        while !estimation.step().unwrap() {
+            steps += 1;
+            assert!(estimation.fraction_done() > fraction);
+            fraction = estimation.fraction_done();
+        }
+        assert_eq!(steps, 2 * 4);
+        assert_eq!(estimation.fraction_done(), 1.0);
+        let stepped = estimation.finish().unwrap();
+        assert_eq!(stepped.to_json(), johansen.to_json());
+
+        let basket = best_basket(&matrix, 4, None, Some(2)).unwrap();
+        assert_eq!(
+            basket_from(&matrix, &stepped).unwrap().to_json(),
+            basket.to_json()
+        );
+    }
+
+    #[test]
+    fn single_series_eigenvalue_is_the_squared_correlation() {
+        // Without lags or deterministic terms the residuals are Δx_t and x_{t-1} themselves
+        let x = [1.0, 2.5, 1.5, 3.0, 2.0, 2.8, 4.1, 3.3, 2.2, 3.9];
+        let result = johansen_test(&x, 1, x.len(), -1, 0).unwrap();
+        let (mut s01, mut s00, mut s11) = (0.0, 0.0, 0.0);
+        for t in 1..x.len() {
+            let delta = x[t] - x[t - 1];
+            s01 += delta * x[t - 1];
+            s00 += delta * delta;
+            s11 += x[t - 1] * x[t - 1];
+        }
+        let lambda = s01 * s01 / (s00 * s11);
+        assert_eq!(result.nobs, 9);
+        assert!((result.eigenvalues()[0] - lambda).abs() < 1e-12);
+        let statistic = -9.0 * (1.0 - lambda).ln();
+        assert!((result.trace_statistics()[0] - statistic).abs() < 1e-10);
+        assert!((result.max_eigen_statistics()[0] - statistic).abs() < 1e-10);
+        assert_eq!(result.trace_critical_values(), TRACE_NONE[0].to_vec());
+        // v' Skk v = 1 with Skk = Σ x_{t-1}² / T
+        let v = result.eigenvectors()[0];
+        assert!((v * v * s11 / 9.0 - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn pair_matches_the_closed_form_eigenvalue_problem() {
+        let (y, x, _) = cointegrated_pair(200, 1.5, 0.5, 8);
+        let matrix: Vec<f64> = y.iter().zip(&x).flat_map(|(y, x)| [*y, *x]).collect();
+        let result = johansen_test(&matrix, 2, 200, -1, 0).unwrap();
+
+        // Moments of Δx_t and x_{t-1}, then the roots of |M - λ Skk| = 0 with
+        // M = Sk0 S00^-1 S0k, a quadratic in λ for two assets
+        let t_eff = 199.0;
+        let columns = [&y, &x];
+        let moment = |a: &dyn Fn(usize, usize) -> f64, b: &dyn Fn(usize, usize) -> f64| {
+            let mut s = [[0.0; 2]; 2];
+            for (i, row) in s.iter_mut().enumerate() {
+                for (j, value) in row.iter_mut().enumerate() {
+                    *value = (1..200).map(|t| a(i, t) * b(j, t)).sum::<f64>() / t_eff;
+                }
+            }
+            s
+        };
+        let delta = |i: usize, t: usize| columns[i][t] - columns[i][t - 1];
+        let lagged = |i: usize, t: usize| columns[i][t - 1];
+        let (s00, sk0, skk) = (
+            moment(&delta, &delta),
+            moment(&lagged, &delta),
+            moment(&lagged, &lagged),
+        );
+        let det = |a: [[f64; 2]; 2]| a[0][0] * a[1][1] - a[0][1] * a[1][0];
+        let inverse = {
+            let d = det(s00);
+            [[s00[1][1] / d, -s00[0][1] / d], [-s00[1][0] / d, s00[0][0] / d]]
+        };
+        let mut product = [[0.0; 2]; 2];
+        for i in 0..2 {
+            for j in 0..2 {
+                product[i][j] = (0..2)
+                    .flat_map(|a| (0..2).map(move |b| (a, b)))
+                    .map(|(a, b)| sk0[i][a] * inverse[a][b] * sk0[j][b])
+                    .sum();
+            }
+        }
+        let a = det(skk);
+        let b = -(product[0][0] * skk[1][1] + product[1][1] * skk[0][0]
+            - product[0][1] * skk[1][0]
+            - product[1][0] * skk[0][1]);
+        let c = det(product);
+        let root = (b * b - 4.0 * a * c).sqrt();
+        let expected = [(-b + root) / (2.0 * a), (-b - root) / (2.0 * a)];
+
+        let eigenvalues = result.eigenvalues();
+        let vectors = result.eigenvectors();
+        for r in 0..2 {
+            assert!(
+                (eigenvalues[r] - expected[r]).abs() < 1e-10,
+                "{:?} vs {:?}",
+                eigenvalues,
+                expected
+            );
+            // (M - λ Skk) v = 0 and v' Skk v = 1
+            let v = [vectors[r], vectors[2 + r]];
+            for i in 0..2 {
+                let residual: f64 = (0..2)
+                    .map(|j| (product[i][j] - eigenvalues[r] * skk[i][j]) * v[j])
+                    .sum();
+                assert!(residual.abs() < 1e-9, "vector {}: {}", r, residual);
+            }
+            let norm: f64 = (0..2)
+                .flat_map(|i| (0..2).map(move |j| (i, j)))
+                .map(|(i, j)| v[i] * skk[i][j] * v[j])
+                .sum();
+            assert!((norm - 1.0).abs() < 1e-9);
+            let statistic = -t_eff * (1.0 - eigenvalues[r]).ln();
+            assert!((result.max_eigen_statistics()[r] - statistic).abs() < 1e-8);
+        }
+        let trace = result.trace_statistics();
+        assert!((trace[0] - result.max_eigen_statistics().iter().sum::<f64>()).abs() < 1e-8);
+    }
+
+    #[test]
+    fn basket_with_one_relation_has_rank_one() {
+        // A 5% test: rank one is wrongly rejected on four of seeds 1..=40, including seed 1
+        for seed in 2..=6 {
+            let result = johansen_test(&basket(500, seed), 4, 500, 0, 1).unwrap();
+            let (trace, critical) = (result.trace_statistics(), result.trace_critical_values());
+            // 95% critical value for rank r is the middle of its three
+            assert!(trace[0] > critical[1], "seed {}: {:?}", seed, trace);
+            assert!(trace[1] < critical[4], "seed {}: {:?}", seed, trace);
+            let (eigen, eigen_critical) = (
+                result.max_eigen_statistics(),
+                result.max_eigen_critical_values(),
+            );
+            assert!(eigen[0] > eigen_critical[1], "seed {}: {:?}", seed, eigen);
+            assert!(eigen[1] < eigen_critical[4], "seed {}: {:?}", seed, eigen);
+
+            // The first cointegrating vector is proportional to (0.5, 1.5, 0, -1)
+            let vectors = result.eigenvectors();
+            let fourth = vectors[3 * 4];
+            let normalized: Vec<f64> = (0..4).map(|i| -vectors[i * 4] / fourth).collect();
+            for (value, expected) in normalized.iter().zip([0.5, 1.5, 0.0, -1.0]) {
+                assert!(
+                    (value - expected).abs() < 0.06,
+                    "seed {}: {:?}",
+                    seed,
+                    normalized
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,252 @@
+// JSON text in and out, for callers that pass strings rather than objects
+// (web workers over postMessage, server-side logging).
+//
+// Every result struct has a `to_json` method built on `JsonObject`. JSON has no
+// NaN or infinity, so non-finite numbers are written as null, and a null in a
+// payload's series reads back as NaN, which keeps a round trip lossless. The
+// `*_from_json` entry points take one payload object holding the series and the
+// options of the function they wrap, which they read exactly as the object
+// arguments of that function, and return the result as JSON text.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use std::fmt::Write;
+
+#[cfg(feature = "wasm")]
+use crate::error::Error;
+#[cfg(feature = "wasm")]
+use crate::get_property;
+
+// A value that can be written as JSON
+pub(crate) trait ToJson {
+    fn write_json(&self, out: &mut String);
+}
+
+impl ToJson for f64 {
+    fn write_json(&self, out: &mut String) {
+        if self.is_finite() {
+            // Shortest round-trip digits, in exponent notation for very large or small
+            // magnitudes; valid JSON for every finite value
+            let _ = write!(out, "{:?}", self);
+        } else {
+            out.push_str("null");
+        }
+    }
+}
+
+impl ToJson for f32 {
+    fn write_json(&self, out: &mut String) {
+        (*self as f64).write_json(out);
+    }
+}
+
+impl ToJson for usize {
+    fn write_json(&self, out: &mut String) {
+        let _ = write!(out, "{}", self);
+    }
+}
+
+impl ToJson for u32 {
+    fn write_json(&self, out: &mut String) {
+        let _ = write!(out, "{}", self);
+    }
+}
+
+impl ToJson for bool {
+    fn write_json(&self, out: &mut String) {
+        out.push_str(if *self { "true" } else { "false" });
+    }
+}
+
+impl ToJson for str {
+    fn write_json(&self, out: &mut String) {
+        out.push('"');
+        for c in self.chars() {
+            match c {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                c if (c as u32) < 0x20 => {
+                    let _ = write!(out, "\\u{:04x}", c as u32);
+                }
+                c => out.push(c),
+            }
+        }
+        out.push('"');
+    }
+}
+
+impl ToJson for String {
+    fn write_json(&self, out: &mut String) {
+        self.as_str().write_json(out);
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for &T {
+    fn write_json(&self, out: &mut String) {
+        (**self).write_json(out);
+    }
+}
+
+impl<T: ToJson> ToJson for Option<T> {
+    fn write_json(&self, out: &mut String) {
+        match self {
+            Some(value) => value.write_json(out),
+            None => out.push_str("null"),
+        }
+    }
+}
+
+impl<T: ToJson> ToJson for [T] {
+    fn write_json(&self, out: &mut String) {
+        out.push('[');
+        for (i, value) in self.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            value.write_json(out);
+        }
+        out.push(']');
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn write_json(&self, out: &mut String) {
+        self.as_slice().write_json(out);
+    }
+}
+
+impl<T: ToJson, const N: usize> ToJson for [T; N] {
+    fn write_json(&self, out: &mut String) {
+        self.as_slice().write_json(out);
+    }
+}
+
+// Text that is already JSON, such as the `to_json` of a nested result
+pub(crate) struct RawJson(pub String);
+
+impl ToJson for RawJson {
+    fn write_json(&self, out: &mut String) {
+        out.push_str(&self.0);
+    }
+}
+
+// Builder of one JSON object, fields in insertion order
+pub(crate) struct JsonObject {
+    out: String,
+}
+
+impl JsonObject {
+    pub(crate) fn new() -> JsonObject {
+        JsonObject {
+            out: String::from("{"),
+        }
+    }
+
+    pub(crate) fn field(mut self, key: &str, value: impl ToJson) -> JsonObject {
+        if self.out.len() > 1 {
+            self.out.push(',');
+        }
+        key.write_json(&mut self.out);
+        self.out.push(':');
+        value.write_json(&mut self.out);
+        self
+    }
+
+    pub(crate) fn finish(mut self) -> String {
+        self.out.push('}');
+        self.out
+    }
+}
+
+// JSON array of already-serialised values
+pub(crate) fn raw_array(values: impl IntoIterator<Item = String>) -> RawJson {
+    let items: Vec<RawJson> = values.into_iter().map(RawJson).collect();
+    let mut out = String::new();
+    items.write_json(&mut out);
+    RawJson(out)
+}
+
+// The payload of a `*_from_json` entry point, parsed into the plain object its fields are read
+// from
+#[cfg(feature = "wasm")]
+pub(crate) fn parse_payload(payload: &str) -> Result<JsValue, Error> {
+    let value = js_sys::JSON::parse(payload).map_err(|error| {
+        let detail = error
+            .dyn_ref::<js_sys::Error>()
+            .map(|e| String::from(e.message()))
+            .unwrap_or_default();
+        Error::new(&format!("payload is not valid JSON: {}", detail))
+    })?;
+    if !value.is_object() || js_sys::Array::is_array(&value) {
+        return Err(Error::new("payload must be a JSON object"));
+    }
+    Ok(value)
+}
+
+// Number array `key` of a payload; null entries read as NaN
+#[cfg(feature = "wasm")]
+pub(crate) fn payload_series(payload: &JsValue, key: &str) -> Result<Vec<f64>, Error> {
+    let value = get_property(payload, key)?;
+    if !js_sys::Array::is_array(&value) {
+        return Err(Error::new(&format!(
+            "payload.{} must be an array of numbers",
+            key
+        )));
+    }
+    js_sys::Array::from(&value)
+        .iter()
+        .map(|item| {
+            if item.is_null() {
+                Ok(f64::NAN)
+            } else {
+                item.as_f64().ok_or_else(|| {
+                    Error::new(&format!("payload.{} must be an array of numbers", key))
+                })
+            }
+        })
+        .collect()
+}
+
+// String array `key` of a payload
+#[cfg(feature = "wasm")]
+pub(crate) fn payload_strings(payload: &JsValue, key: &str) -> Result<Vec<String>, Error> {
+    let value = get_property(payload, key)?;
+    if !js_sys::Array::is_array(&value) {
+        return Err(Error::new(&format!(
+            "payload.{} must be an array of strings",
+            key
+        )));
+    }
+    js_sys::Array::from(&value)
+        .iter()
+        .map(|item| {
+            item.as_string()
+                .ok_or_else(|| Error::new(&format!("payload.{} must be an array of strings", key)))
+        })
+        .collect()
+}
+
+// Critical values keyed by level, as the `critical_values` getters return them
+pub(crate) fn levels(keys: &[&str], values: &[f64]) -> RawJson {
+    let object = keys
+        .iter()
+        .zip(values)
+        .fold(JsonObject::new(), |object, (key, value)| {
+            object.field(key, value)
+        });
+    RawJson(object.finish())
+}
+
+// Non-negative integer `key` of a payload
+#[cfg(feature = "wasm")]
+pub(crate) fn payload_count(payload: &JsValue, key: &str) -> Result<usize, Error> {
+    get_property(payload, key)?
+        .as_f64()
+        .filter(|v| *v >= 0.0 && v.fract() == 0.0)
+        .map(|v| v as usize)
+        .ok_or_else(|| Error::new(&format!("payload.{} must be a non-negative integer", key)))
+}
@@ -0,0 +1,231 @@
+// Kalman-filter estimate of a time-varying hedge ratio.
+//
+// The state θ_t = (β_t, α_t) follows a random walk θ_t = θ_{t-1} + w_t with
+// w_t ~ N(0, δ/(1-δ)·I), and each observation is y_t = β_t·x_t + α_t + ε_t with
+// ε_t ~ N(0, V_e). The one-step forecast error e_t = y_t - (β·x_t + α) is the
+// adaptive spread and √Q_t its predicted standard deviation, so e_t / √Q_t is a
+// ready-made z-score (Chan, "Algorithmic Trading", ch. 3).
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::error::Error;
+use crate::json::JsonObject;
+use crate::state::{StateKind, StateReader, StateWriter};
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct KalmanHedge {
+    // State mean (beta, intercept) and its 2x2 covariance, row-major
+    state: [f64; 2],
+    covariance: [f64; 4],
+    // Diagonal of the state-transition covariance, δ/(1-δ)
+    transition_variance: f64,
+    measurement_variance: f64,
+    nobs: usize,
+}
+
+/// One filter step: the state after seeing the observation, and the forecast made before it.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug)]
+pub struct KalmanEstimate {
+    pub beta: f64,
+    pub intercept: f64,
+    // y - (beta·x + intercept) using the state *before* the update
+    pub forecast_error: f64,
+    // Predicted standard deviation of forecast_error
+    pub forecast_std: f64,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl KalmanEstimate {
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("beta", self.beta)
+            .field("intercept", self.intercept)
+            .field("forecast_error", self.forecast_error)
+            .field("forecast_std", self.forecast_std)
+            .finish()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct KalmanFilterResult {
+    betas: Vec<f64>,
+    intercepts: Vec<f64>,
+    forecast_errors: Vec<f64>,
+    forecast_stds: Vec<f64>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl KalmanFilterResult {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn betas(&self) -> Vec<f64> {
+        self.betas.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn intercepts(&self) -> Vec<f64> {
+        self.intercepts.clone()
+    }
+
+    // The adaptive spread, one entry per observation
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn forecast_errors(&self) -> Vec<f64> {
+        self.forecast_errors.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn forecast_stds(&self) -> Vec<f64> {
+        self.forecast_stds.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("betas", &self.betas)
+            .field("intercepts", &self.intercepts)
+            .field("forecast_errors", &self.forecast_errors)
+            .field("forecast_stds", &self.forecast_stds)
+            .finish()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl KalmanHedge {
+    /// A filter starting from beta = intercept = 0 with zero state covariance.
+    ///
+    /// `delta` in (0, 1) sets how fast the hedge ratio may drift (1e-4 is a common choice,
+    /// smaller is stiffer); `measurement_noise` is the variance V_e of y around β·x + α.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(delta: f64, measurement_noise: f64) -> Result<KalmanHedge, Error> {
+        if !(delta > 0.0 && delta < 1.0) {
+            return Err(Error::new("delta must be in (0, 1)"));
+        }
+        if !(measurement_noise > 0.0 && measurement_noise.is_finite()) {
+            return Err(Error::new("measurement_noise must be positive and finite"));
+        }
+        Ok(KalmanHedge {
+            state: [0.0; 2],
+            covariance: [0.0; 4],
+            transition_variance: delta / (1.0 - delta),
+            measurement_variance: measurement_noise,
+            nobs: 0,
+        })
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn beta(&self) -> f64 {
+        self.state[0]
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn intercept(&self) -> f64 {
+        self.state[1]
+    }
+
+    // Number of observations absorbed so far
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn nobs(&self) -> usize {
+        self.nobs
+    }
+
+    /// The filter's state as a compact byte buffer, which `KalmanHedge.deserialize` turns back
+    /// into a filter that continues exactly where this one stopped.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut writer = StateWriter::new(StateKind::KalmanHedge);
+        for value in self.state.iter().chain(&self.covariance) {
+            writer.f64(*value);
+        }
+        writer
+            .f64(self.transition_variance)
+            .f64(self.measurement_variance)
+            .usize(self.nobs)
+            .finish()
+    }
+
+    /// A filter restored from the bytes of `serialize`.
+    pub fn deserialize(bytes: &[u8]) -> Result<KalmanHedge, Error> {
+        let mut reader = StateReader::new(bytes, StateKind::KalmanHedge)?;
+        let hedge = KalmanHedge {
+            state: [reader.f64()?, reader.f64()?],
+            covariance: [reader.f64()?, reader.f64()?, reader.f64()?, reader.f64()?],
+            transition_variance: reader.f64()?,
+            measurement_variance: reader.f64()?,
+            nobs: reader.usize()?,
+        };
+        if !(hedge.transition_variance > 0.0 && hedge.measurement_variance > 0.0) {
+            return Err(reader.invalid());
+        }
+        reader.finish()?;
+        Ok(hedge)
+    }
+
+    /// Absorb one (x, y) observation and return the updated state and the forecast error.
+    pub fn update(&mut self, x: f64, y: f64) -> Result<KalmanEstimate, Error> {
+        if !x.is_finite() || !y.is_finite() {
+            return Err(Error::new("observations must be finite"));
+        }
+        Ok(self.step(x, y))
+    }
+
+    /// Run `update` over paired series, continuing from the current state.
+    pub fn filter(&mut self, xs: &[f64], ys: &[f64]) -> Result<KalmanFilterResult, Error> {
+        if xs.len() != ys.len() {
+            return Err(Error::new("xs and ys must have the same length"));
+        }
+        if xs.iter().chain(ys).any(|v| !v.is_finite()) {
+            return Err(Error::new("observations must be finite"));
+        }
+
+        let mut result = KalmanFilterResult {
+            betas: Vec::with_capacity(xs.len()),
+            intercepts: Vec::with_capacity(xs.len()),
+            forecast_errors: Vec::with_capacity(xs.len()),
+            forecast_stds: Vec::with_capacity(xs.len()),
+        };
+        for (&x, &y) in xs.iter().zip(ys) {
+            let estimate = self.step(x, y);
+            result.betas.push(estimate.beta);
+            result.intercepts.push(estimate.intercept);
+            result.forecast_errors.push(estimate.forecast_error);
+            result.forecast_stds.push(estimate.forecast_std);
+        }
+        Ok(result)
+    }
+}
+
+impl KalmanHedge {
+    fn step(&mut self, x: f64, y: f64) -> KalmanEstimate {
+        let h = [x, 1.0];
+        let [b, a] = self.state;
+
+        // Predict: R = P + V_w
+        let mut r = self.covariance;
+        r[0] += self.transition_variance;
+        r[3] += self.transition_variance;
+
+        // Innovation and its variance Q = h R h' + V_e
+        let rh = [r[0] * h[0] + r[1] * h[1], r[2] * h[0] + r[3] * h[1]];
+        let q = h[0] * rh[0] + h[1] * rh[1] + self.measurement_variance;
+        let error = y - (b * x + a);
+
+        // Update: K = R h' / Q, θ += K e, P = R - K h R
+        let gain = [rh[0] / q, rh[1] / q];
+        self.state = [b + gain[0] * error, a + gain[1] * error];
+        self.covariance = [
+            r[0] - gain[0] * rh[0],
+            r[1] - gain[0] * rh[1],
+            r[2] - gain[1] * rh[0],
+            r[3] - gain[1] * rh[1],
+        ];
+        self.nobs += 1;
+
+        KalmanEstimate {
+            beta: self.state[0],
+            intercept: self.state[1],
+            forecast_error: error,
+            forecast_std: q.sqrt(),
+        }
+    }
+}
@@ -1,9 +1,95 @@
+// With the default `wasm` feature the public functions and result types are also exported to
+// JS through wasm-bindgen; without it the crate is a plain Rust library for native targets.
+
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
-use js_sys;
+
+use adf::{DecisionRule, Regression};
+use error::{AdfError, Error};
+use json::{JsonObject, levels};
+use mackinnon::{mackinnon_critical_values, mackinnon_lookup};
+use pvalue::{interpolate_critical_value, lookup_sorted, validate_arrays, PValue, PValueSource, PValueTable, PValueTableSet};
+
+pub mod adf;
+pub mod align;
+pub mod arma;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod backtest;
+pub mod batch;
+mod bootstrap;
+pub mod bubble;
+pub mod buffer;
+pub mod cache;
+pub mod coint;
+pub mod copula;
+pub mod correlation;
+pub mod cross_validation;
+pub mod csv;
+pub mod detrend;
+pub mod diagnostics;
+pub mod distance;
+mod distributions;
+pub mod dols;
+pub mod error;
+mod fft;
+pub mod garch;
+pub mod gls;
+pub mod granger;
+mod hac;
+pub mod hedge;
+pub mod hurst;
+pub mod johansen;
+mod json;
+pub mod kalman;
+pub mod kpss;
+mod linalg;
+mod mackinnon;
+pub mod mean_reversion;
+pub mod monitor;
+pub mod monte_carlo;
+pub mod ols;
+pub mod optimize;
+mod parallel;
+pub mod pca;
+pub mod phillips_ouliaris;
+pub mod portfolio;
+pub mod pp;
+mod precision;
+pub mod preprocess;
+mod progress;
+pub mod pvalue;
+pub mod quantile;
+pub mod regime;
+pub mod resample;
+pub mod returns;
+pub mod risk;
+mod rng;
+pub mod rolling;
+pub mod scoring;
+pub mod screen;
+pub mod series;
+pub mod signals;
+mod simd;
+pub mod sizing;
+pub mod spectrum;
+pub mod spread;
+pub mod stability;
+mod state;
+pub mod stationarity;
+pub mod stats;
+pub mod streaming;
+pub mod timing;
+pub mod validate;
+pub mod variance_ratio;
+pub mod walkforward;
+pub mod zivot_andrews;
+pub mod zscore;
 
 // This is where you will paste the content of your adf_p_value_lookup_dense.csv
 // converted into a Rust const array.
 // Ensure this data is sorted by the test_stat (first element of each inner array).
+#[allow(clippy::approx_constant)]
 const ADF_P_VALUE_LOOKUP: &[[f64; 2]] = &[
     [-4.98402287309096,0.00002],
     [-4.95836394213414,0.00004],
@@ -50007,83 +50093,487 @@ const ADF_P_VALUE_LOOKUP: &[[f64; 2]] = &[
     [2.34198462884487,1.0000]
 ];
 
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(typescript_custom_section)]
+const RESULT_OBJECTS_TS: &'static str = r#"
+/** Critical values of the ADF, Phillips-Perron and Engle-Granger tests, keyed by level. */
+export interface CriticalValues {
+  "1%": number;
+  "5%": number;
+  "10%": number;
+}
+
+/** Plain-object snapshot of an `AdfResult`, as returned by `AdfResult.to_object()`. */
+export interface AdfResultObject {
+  statistic: number;
+  p_value: number;
+  critical_values: CriticalValues;
+  is_stationary: boolean;
+  p_value_source: "interpolated" | "clamped_first" | "clamped_last";
+  p_value_clamp: number | undefined;
+  used_lag: number | undefined;
+  nobs: number | undefined;
+  regression: "n" | "c" | "ct" | "ctt" | undefined;
+  significance: number;
+  decision_rule: "p_value" | "critical_value" | "both";
+  bootstrap_p_value: number | undefined;
+}
+"#;
+
+#[cfg(feature = "wasm")]
 #[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "CriticalValues")]
+    pub type CriticalValues;
+
+    #[wasm_bindgen(typescript_type = "AdfResultObject")]
+    pub type AdfResultObject;
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone)]
 pub struct AdfResult {
     pub statistic: f64,
     pub p_value: f64,
     // Make this field private
-    critical_values: JsValue,
+    // Order is 1%, 5%, 10%
+    critical_values: [f64; 3],
     pub is_stationary: bool,
+    p_value_source: PValueSource,
+    used_lag: Option<usize>,
+    nobs: Option<usize>,
+    regression: Option<Regression>,
+    significance: f64,
+    decision_rule: DecisionRule,
+    bootstrap_p_value: Option<f64>,
 }
 
 // Add a getter method for critical_values
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl AdfResult {
+    #[cfg(feature = "wasm")]
     #[wasm_bindgen(getter)]
-    pub fn critical_values(&self) -> JsValue {
-        self.critical_values.clone() // Return a clone as JsValue is not Copy
+    pub fn critical_values(&self) -> CriticalValues {
+        critical_values_object(self.critical_values)
+    }
+
+    /// Every field of the result as a plain JS object (see `AdfResultObject`), for
+    /// `JSON.stringify`, structured cloning to workers or spreading into application state.
+    #[cfg(feature = "wasm")]
+    pub fn to_object(&self) -> AdfResultObject {
+        let object = js_sys::Object::new();
+        set_property(&object, "statistic", self.statistic);
+        set_property(&object, "p_value", self.p_value);
+        set_property(&object, "critical_values", self.critical_values());
+        set_property(&object, "is_stationary", self.is_stationary);
+        set_property(&object, "p_value_source", self.p_value_source());
+        set_property(&object, "p_value_clamp", self.p_value_clamp());
+        set_property(&object, "used_lag", self.used_lag.map(|lag| lag as f64));
+        set_property(&object, "nobs", self.nobs.map(|nobs| nobs as f64));
+        set_property(&object, "regression", self.regression());
+        set_property(&object, "significance", self.significance);
+        set_property(&object, "decision_rule", self.decision_rule());
+        set_property(&object, "bootstrap_p_value", self.bootstrap_p_value);
+        object.unchecked_into()
+    }
+
+    // Critical value at any significance level between 1% and 10% (e.g. 0.025), interpolated
+    // across the 1%/5%/10% values in the normal quantile of the level
+    pub fn critical_value(&self, alpha: f64) -> Result<f64, Error> {
+        interpolate_critical_value(&CRITICAL_VALUE_LEVELS, &self.critical_values, alpha)
+    }
+
+    // "interpolated" when the statistic is inside the lookup table's support, otherwise
+    // "clamped_first" (below the first row) or "clamped_last" (above the last row)
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn p_value_source(&self) -> String {
+        self.p_value_source.as_str().to_string()
+    }
+
+    // The boundary value `p_value` was clamped to, or undefined when it was interpolated.
+    // Lets the UI show e.g. "p-value >= 1.0" instead of presenting a clamp as exact.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn p_value_clamp(&self) -> Option<f64> {
+        match self.p_value_source {
+            PValueSource::Interpolated => None,
+            PValueSource::ClampedFirst | PValueSource::ClampedLast => Some(self.p_value),
+        }
+    }
+
+    // Number of lagged differences in the regression (the selected one when the lag was
+    // chosen automatically), or undefined when only the statistic was supplied
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn used_lag(&self) -> Option<usize> {
+        self.used_lag
+    }
+
+    // Observations used in the regression, or undefined when only the statistic was supplied
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn nobs(&self) -> Option<usize> {
+        self.nobs
     }
+
+    // Deterministic terms of the regression ("n", "c", "ct" or "ctt"), or undefined when only
+    // the statistic was supplied
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn regression(&self) -> Option<String> {
+        self.regression.map(|r| r.as_str().to_string())
+    }
+
+    // Significance level `is_stationary` was decided at
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn significance(&self) -> f64 {
+        self.significance
+    }
+
+    // Rule that produced `is_stationary`: "both" (p-value and critical value), "p_value" or
+    // "critical_value"
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn decision_rule(&self) -> String {
+        self.decision_rule.as_str().to_string()
+    }
+
+    // Bootstrap p-value of the statistic, or undefined unless a bootstrap was requested
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn bootstrap_p_value(&self) -> Option<f64> {
+        self.bootstrap_p_value
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("statistic", self.statistic)
+            .field("p_value", self.p_value)
+            .field("critical_values", levels(&CRITICAL_VALUE_KEYS, &self.critical_values))
+            .field("is_stationary", self.is_stationary)
+            .field("p_value_source", self.p_value_source())
+            .field("p_value_clamp", self.p_value_clamp())
+            .field("used_lag", self.used_lag)
+            .field("nobs", self.nobs)
+            .field("regression", self.regression())
+            .field("significance", self.significance)
+            .field("decision_rule", self.decision_rule())
+            .field("bootstrap_p_value", self.bootstrap_p_value)
+            .finish()
+    }
+}
+
+impl AdfResult {
+    // Critical values as [1%, 5%, 10%], for native callers
+    pub fn critical_values_array(&self) -> [f64; 3] {
+        self.critical_values
+    }
+
+    // Record the regression the statistic came from
+    pub(crate) fn with_fit(mut self, used_lag: usize, nobs: usize, regression: Regression) -> AdfResult {
+        self.used_lag = Some(used_lag);
+        self.nobs = Some(nobs);
+        self.regression = Some(regression);
+        self
+    }
+
+    // Attach a bootstrap p-value alongside the tabulated one
+    pub(crate) fn with_bootstrap(mut self, bootstrap_p_value: Option<f64>) -> AdfResult {
+        self.bootstrap_p_value = bootstrap_p_value;
+        self
+    }
+
+    // Re-decide `is_stationary` at `significance` under `rule`
+    pub(crate) fn with_decision(mut self, significance: f64, rule: DecisionRule) -> Result<AdfResult, Error> {
+        if !(significance > 0.0 && significance < 1.0) {
+            return Err(Error::new("significance must be between 0 and 1"));
+        }
+        let below_critical_value = |result: &AdfResult| -> Result<bool, Error> {
+            Ok(result.statistic < result.critical_value(significance)?)
+        };
+        self.is_stationary = match rule {
+            DecisionRule::PValue => self.p_value <= significance,
+            DecisionRule::CriticalValue => below_critical_value(&self)?,
+            DecisionRule::Both => self.p_value <= significance && below_critical_value(&self)?,
+        };
+        self.significance = significance;
+        self.decision_rule = rule;
+        Ok(self)
+    }
+}
+
+// p-value interpolated from the embedded lookup table, judged against the standard asymptotic
+// critical values. Kept for existing callers; the functions that run the regression themselves
+// (and `get_adf_p_value_for_sample`) use MacKinnon's smooth response surfaces instead.
+// Errors if the statistic is NaN.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn get_adf_p_value_and_stationarity(test_statistic: f64) -> Result<AdfResult, Error> {
+    check_statistic(test_statistic)?;
+    Ok(build_adf_result(test_statistic, lookup_p_value(test_statistic), DEFAULT_CRITICAL_VALUES))
 }
 
+// Same as `get_adf_p_value_and_stationarity`, but lets the caller supply their own
+// critical values as an object with "1%", "5%" and "10%" keys (e.g. taken from a paper).
+// When present they drive the stationarity decision and are echoed back in
+// `critical_values`; passing `undefined`/`null` falls back to the defaults.
+// The p-value is still interpolated from the embedded lookup table either way.
+#[cfg(feature = "wasm")]
 #[wasm_bindgen]
-pub fn get_adf_p_value_and_stationarity(test_statistic: f64) -> AdfResult {
-    let p_value = interpolate_p_value(test_statistic);
+pub fn get_adf_p_value_and_stationarity_with_overrides(
+    test_statistic: f64,
+    override_critical_values: JsValue,
+) -> Result<AdfResult, Error> {
+    let override_critical_values = if override_critical_values.is_undefined() || override_critical_values.is_null() {
+        None
+    } else {
+        Some(parse_critical_values(&override_critical_values)?)
+    };
+    get_adf_p_value_with_critical_values(test_statistic, override_critical_values)
+}
+
+// `get_adf_p_value_and_stationarity_with_overrides` for native callers, with the override as
+// [1%, 5%, 10%]
+pub fn get_adf_p_value_with_critical_values(
+    test_statistic: f64,
+    override_critical_values: Option<[f64; 3]>,
+) -> Result<AdfResult, Error> {
+    check_statistic(test_statistic)?;
+    let critical_values = override_critical_values.unwrap_or(DEFAULT_CRITICAL_VALUES);
+    Ok(build_adf_result(test_statistic, lookup_p_value(test_statistic), critical_values))
+}
+
+// Same as `get_adf_p_value_and_stationarity_with_overrides`, but the p-value table and critical
+// values come in as Float64Arrays: `statistics` and `p_values` are parallel arrays sorted by
+// strictly increasing statistic, and `critical_values` is [1%, 5%, 10%]. Everything is read as
+// plain slices, so no Reflect calls are made; suited to calling in a hot loop.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn get_adf_p_value_from_arrays(
+    test_statistic: f64,
+    statistics: &[f64],
+    p_values: &[f64],
+    critical_values: &[f64],
+) -> Result<AdfResult, Error> {
+    check_statistic(test_statistic)?;
+    validate_arrays(statistics, p_values)?;
+    let critical_values: [f64; 3] = critical_values
+        .try_into()
+        .map_err(|_| AdfError::CriticalValuesLayout { len: critical_values.len() })?;
+    if let Some(i) = critical_values.iter().position(|v| !v.is_finite()) {
+        return Err(AdfError::NonNumericValue { key: CRITICAL_VALUE_KEYS[i].to_string() }.into());
+    }
+
+    let p_value = lookup_sorted(statistics.len(), |i| (statistics[i], p_values[i]), test_statistic);
+    Ok(build_adf_result(test_statistic, p_value, critical_values))
+}
+
+// Same decision as `get_adf_p_value_and_stationarity`, but without any JS-side tables: the
+// p-value comes from MacKinnon's (1994) response surface and the critical values from
+// MacKinnon's (2010) finite-sample surface for `nobs` observations (constant-only regression),
+// both compiled into the binary. `statistic` and `nobs` are all that is needed.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn get_adf_p_value_for_sample(test_statistic: f64, nobs: usize) -> Result<AdfResult, Error> {
+    get_adf_result_for_sample(test_statistic, nobs, |statistic| {
+        Ok(mackinnon_lookup(statistic, Regression::Constant, 1))
+    })
+}
+
+// Same as `get_adf_p_value_for_sample`, but the p-value is interpolated from a caller-supplied
+// `PValueTable` instead of the embedded surface. The critical values still depend on `nobs`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn get_adf_p_value_for_sample_with_table(
+    test_statistic: f64,
+    nobs: usize,
+    table: &PValueTable,
+) -> Result<AdfResult, Error> {
+    get_adf_result_for_sample(test_statistic, nobs, |statistic| Ok(table.lookup(statistic)))
+}
+
+// Same as `get_adf_p_value_for_sample_with_table`, but with tables for several sample sizes:
+// the p-value is interpolated between the tables bracketing `nobs`, so it changes smoothly
+// with the sample size. Errors if the set is empty.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn get_adf_p_value_for_sample_with_tables(
+    test_statistic: f64,
+    nobs: usize,
+    tables: &PValueTableSet,
+) -> Result<AdfResult, Error> {
+    get_adf_result_for_sample(test_statistic, nobs, |statistic| {
+        tables
+            .lookup(statistic, nobs)
+            .ok_or(AdfError::EmptyTable { rows: 0 })
+    })
+}
+
+fn get_adf_result_for_sample(
+    test_statistic: f64,
+    nobs: usize,
+    lookup: impl FnOnce(f64) -> Result<PValue, AdfError>,
+) -> Result<AdfResult, Error> {
+    check_statistic(test_statistic)?;
+    if nobs == 0 {
+        return Err(AdfError::InvalidSampleSize.into());
+    }
+    let p_value = lookup(test_statistic)?;
+    let critical_values = mackinnon_critical_values(Regression::Constant, 1, nobs)
+        .ok_or_else(|| Error::new("no critical values for the constant-only regression"))?;
+    Ok(build_adf_result(test_statistic, p_value, critical_values))
+}
+
+// Define critical values (these are typical values for ADF, adjust if your source provides different ones)
+// Order is 1%, 5%, 10%.
+pub(crate) const DEFAULT_CRITICAL_VALUES: [f64; 3] = [-3.43, -2.86, -2.57];
+
+pub(crate) const CRITICAL_VALUE_KEYS: [&str; 3] = ["1%", "5%", "10%"];
+pub(crate) const CRITICAL_VALUE_LEVELS: [f64; 3] = [0.01, 0.05, 0.10];
 
-    // Define critical values (these are typical values for ADF, adjust if your source provides different ones)
-    // For a more robust solution, these could also be part of the lookup or passed in.
-    let critical_1_percent = -3.43; // Example critical value
-    let critical_5_percent = -2.86; // Example critical value
-    let critical_10_percent = -2.57; // Example critical value
+#[cfg(feature = "wasm")]
+// Read a {"1%": .., "5%": .., "10%": ..} object into the fixed 1%/5%/10% layout
+fn parse_critical_values(value: &JsValue) -> Result<[f64; 3], AdfError> {
+    if !value.is_object() {
+        return Err(AdfError::NotAnObject { argument: "override_critical_values" });
+    }
+
+    let mut critical_values = [0.0; 3];
+    for (slot, key) in critical_values.iter_mut().zip(CRITICAL_VALUE_KEYS) {
+        let entry = js_sys::Reflect::get(value, &JsValue::from_str(key)).unwrap_or(JsValue::UNDEFINED);
+        if entry.is_undefined() {
+            return Err(AdfError::MissingKey { key: key.to_string() });
+        }
+        *slot = entry
+            .as_f64()
+            .filter(|v| v.is_finite())
+            .ok_or_else(|| AdfError::NonNumericValue { key: key.to_string() })?;
+    }
+    Ok(critical_values)
+}
+
+fn check_statistic(test_statistic: f64) -> Result<(), AdfError> {
+    if test_statistic.is_nan() {
+        return Err(AdfError::InvalidStatistic);
+    }
+    Ok(())
+}
+
+pub(crate) fn build_adf_result(test_statistic: f64, p_value: PValue, critical_values: [f64; 3]) -> AdfResult {
+    let PValue {
+        value: p_value,
+        source: p_value_source,
+    } = p_value;
+    let critical_5_percent = critical_values[1];
 
     // Determine stationarity based on p-value and test statistic vs critical value
     // A common rule: p-value <= 0.05 AND test_statistic < critical_5_percent
     let is_stationary = p_value <= 0.05 && test_statistic < critical_5_percent;
 
-    // Create a JavaScript object for critical values
-    let critical_values_js = js_sys::Object::new();
-    js_sys::Reflect::set(&critical_values_js, &JsValue::from_str("1%"), &JsValue::from_f64(critical_1_percent)).unwrap();
-    js_sys::Reflect::set(&critical_values_js, &JsValue::from_str("5%"), &JsValue::from_f64(critical_5_percent)).unwrap();
-    js_sys::Reflect::set(&critical_values_js, &JsValue::from_str("10%"), &JsValue::from_f64(critical_10_percent)).unwrap();
-
     AdfResult {
         statistic: test_statistic,
         p_value,
-        critical_values: critical_values_js.into(),
+        critical_values,
         is_stationary,
+        p_value_source,
+        used_lag: None,
+        nobs: None,
+        regression: None,
+        significance: 0.05,
+        decision_rule: DecisionRule::Both,
+        bootstrap_p_value: None,
+    }
+}
+
+#[cfg(feature = "wasm")]
+// Create a JavaScript object for critical values
+pub(crate) fn critical_values_object(critical_values: [f64; 3]) -> CriticalValues {
+    let critical_values_js = js_sys::Object::new();
+    for (key, value) in CRITICAL_VALUE_KEYS.iter().zip(critical_values) {
+        set_property(&critical_values_js, key, value);
     }
+    critical_values_js.unchecked_into()
+}
+
+#[cfg(feature = "wasm")]
+pub(crate) fn set_property(target: &js_sys::Object, key: &str, value: impl Into<JsValue>) {
+    // Setting a plain string key on a freshly created Object cannot fail; were it to (on a frozen
+    // object), the property is left unset rather than aborting the instance
+    let _ = js_sys::Reflect::set(target, &JsValue::from_str(key), &value.into());
+}
+
+#[cfg(feature = "wasm")]
+// Read `key` from an options object; undefined when the key is missing
+pub(crate) fn get_property(source: &JsValue, key: &str) -> Result<JsValue, Error> {
+    js_sys::Reflect::get(source, &JsValue::from_str(key))
+        .map_err(|_| Error::new(&format!("could not read option \"{}\"", key)))
 }
 
 // Linear interpolation function
-fn interpolate_p_value(test_statistic: f64) -> f64 {
-    if test_statistic <= ADF_P_VALUE_LOOKUP[0][0] {
-        return ADF_P_VALUE_LOOKUP[0][1];
+pub(crate) fn lookup_p_value(test_statistic: f64) -> PValue {
+    lookup_sorted(
+        ADF_P_VALUE_LOOKUP.len(),
+        |i| (ADF_P_VALUE_LOOKUP[i][0], ADF_P_VALUE_LOOKUP[i][1]),
+        test_statistic,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overridden_critical_values_drive_the_decision() {
+        // p ≈ 0.03 and below the default 5% value of -2.86
+        let default = get_adf_p_value_with_critical_values(-3.0, None).unwrap();
+        assert!(default.p_value < 0.05);
+        assert!(default.is_stationary);
+        assert_eq!(default.critical_values_array(), DEFAULT_CRITICAL_VALUES);
+
+        // A stricter 5% value flips the decision while the p-value stays the same
+        let stricter = [-4.0, -3.5, -3.2];
+        let overridden = get_adf_p_value_with_critical_values(-3.0, Some(stricter)).unwrap();
+        assert!(!overridden.is_stationary);
+        assert_eq!(overridden.p_value, default.p_value);
+        assert_eq!(overridden.critical_values_array(), stricter);
     }
-    if test_statistic >= ADF_P_VALUE_LOOKUP[ADF_P_VALUE_LOOKUP.len() - 1][0] {
-        return ADF_P_VALUE_LOOKUP[ADF_P_VALUE_LOOKUP.len() - 1][1];
+
+    #[test]
+    fn critical_value_arrays_drive_the_decision() {
+        let statistics = [-5.0, -3.0, 0.0];
+        let p_values = [0.0, 0.03, 0.9];
+        let default = get_adf_p_value_from_arrays(-3.0, &statistics, &p_values, &DEFAULT_CRITICAL_VALUES).unwrap();
+        assert!(default.is_stationary);
+        let overridden = get_adf_p_value_from_arrays(-3.0, &statistics, &p_values, &[-4.0, -3.5, -3.2]).unwrap();
+        assert!(!overridden.is_stationary);
+        assert_eq!(overridden.p_value, 0.03);
     }
 
-    let mut low = 0;
-    let mut high = ADF_P_VALUE_LOOKUP.len() - 1;
-    let mut idx = 0;
+    #[test]
+    fn clamped_p_values_report_the_side_and_boundary_value() {
+        let first = ADF_P_VALUE_LOOKUP[0];
+        let last = ADF_P_VALUE_LOOKUP[ADF_P_VALUE_LOOKUP.len() - 1];
 
-    // Find the interval using binary search
-    while low <= high {
-        let mid = low + (high - low) / 2;
-        if ADF_P_VALUE_LOOKUP[mid][0] == test_statistic {
-            return ADF_P_VALUE_LOOKUP[mid][1];
-        } else if ADF_P_VALUE_LOOKUP[mid][0] < test_statistic {
-            idx = mid;
-            low = mid + 1;
-        } else {
-            high = mid - 1;
-        }
+        let below = get_adf_p_value_and_stationarity(first[0] - 10.0).unwrap();
+        assert_eq!(below.p_value_source(), "clamped_first");
+        assert_eq!(below.p_value_clamp(), Some(first[1]));
+        assert_eq!(below.p_value, first[1]);
+
+        let above = get_adf_p_value_and_stationarity(last[0] + 10.0).unwrap();
+        assert_eq!(above.p_value_source(), "clamped_last");
+        assert_eq!(above.p_value_clamp(), Some(last[1]));
+        assert_eq!(above.p_value, last[1]);
+
+        // The boundary rows themselves are exact table entries, not clamps
+        let on_first = get_adf_p_value_and_stationarity(first[0]).unwrap();
+        assert_eq!(on_first.p_value_source(), "interpolated");
+        assert_eq!(on_first.p_value_clamp(), None);
+
+        let midpoint = (first[0] + last[0]) / 2.0;
+        let inside = get_adf_p_value_and_stationarity(midpoint).unwrap();
+        assert_eq!(inside.p_value_source(), "interpolated");
+        assert_eq!(inside.p_value_clamp(), None);
     }
 
-    let x1 = ADF_P_VALUE_LOOKUP[idx][0];
-    let y1 = ADF_P_VALUE_LOOKUP[idx][1];
-    let x2 = ADF_P_VALUE_LOOKUP[idx + 1][0];
-    let y2 = ADF_P_VALUE_LOOKUP[idx + 1][1];
+    #[test]
+    fn empty_table_set_is_an_error_not_a_nan_p_value() {
+        let mut tables = PValueTableSet::new();
+        let error = get_adf_p_value_for_sample_with_tables(-3.0, 100, &tables).err().unwrap();
+        assert_eq!(error.code(), crate::error::ErrorCode::TooShort);
 
-    // Linear interpolation formula
-    y1 + (test_statistic - x1) * (y2 - y1) / (x2 - x1)
+        tables.add_table(100, &[-4.0, -3.0, -2.0], &[0.01, 0.04, 0.3]).unwrap();
+        let result = get_adf_p_value_for_sample_with_tables(-3.0, 100, &tables).unwrap();
+        assert_eq!(result.p_value, 0.04);
+        assert_eq!(result.p_value_source(), "interpolated");
+    }
 }
@@ -0,0 +1,388 @@
+// Small dense least-squares solver used by the regression-based tests.
+//
+// Householder QR is used instead of the normal equations so that regressions on
+// raw price levels (large, highly collinear regressors) stay well conditioned.
+// The reflections' dot products and updates run on the `simd` kernels.
+
+use crate::error::Error;
+use crate::hac::{autocovariance_sum, long_run_variance, newey_west_bandwidth};
+use crate::simd::{dot, sub_scaled};
+use crate::state::{StateReader, StateWriter};
+
+// How coefficient standard errors are estimated
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CovarianceType {
+    // Homoskedastic sigma² (X'X)^-1
+    Classical,
+    // White's heteroskedasticity-consistent estimator
+    Hc0,
+    // HC0 scaled by n / (n - k) for small samples
+    Hc1,
+    // Newey-West heteroskedasticity- and autocorrelation-consistent estimator with a Bartlett
+    // kernel, scaled by n / (n - k); the lag truncation is chosen from the data when None
+    Hac(Option<usize>),
+}
+
+pub(crate) struct LeastSquares {
+    pub coefficients: Vec<f64>,
+    pub residuals: Vec<f64>,
+    pub ssr: f64,
+    // (X'X)^-1, k x k row-major
+    pub xtx_inv: Vec<f64>,
+    pub nobs: usize,
+}
+
+impl LeastSquares {
+    pub fn n_params(&self) -> usize {
+        self.coefficients.len()
+    }
+
+    // Residual variance with the usual n - k degrees of freedom
+    pub fn sigma2(&self) -> f64 {
+        self.ssr / (self.nobs - self.n_params()) as f64
+    }
+
+    pub fn std_error(&self, j: usize) -> f64 {
+        (self.sigma2() * self.xtx_inv[j * self.n_params() + j]).sqrt()
+    }
+
+    // Standard error of coefficient j under the given covariance estimator. `columns`
+    // must be the design the fit was computed from.
+    pub fn std_error_with(&self, columns: &[Vec<f64>], j: usize, cov: CovarianceType) -> f64 {
+        let k = self.n_params();
+        let scale = match cov {
+            CovarianceType::Classical => return self.std_error(j),
+            CovarianceType::Hc0 => 1.0,
+            CovarianceType::Hc1 | CovarianceType::Hac(_) => {
+                self.nobs as f64 / (self.nobs - k) as f64
+            }
+        };
+
+        // Diagonal of the sandwich (X'X)^-1 X' Ω X (X'X)^-1 through u_t = e_t (a_j · x_t), where
+        // a_j is row j of (X'X)^-1: Σ_t u_t² for White's Ω, n times the Bartlett long-run
+        // variance of u for Newey-West
+        let row = &self.xtx_inv[j * k..(j + 1) * k];
+        let u: Vec<f64> = self
+            .residuals
+            .iter()
+            .enumerate()
+            .map(|(t, e)| e * row.iter().zip(columns).map(|(a, c)| a * c[t]).sum::<f64>())
+            .collect();
+        let meat = match cov {
+            CovarianceType::Hac(lags) => {
+                let lags = lags.unwrap_or_else(|| self.newey_west_lags(columns));
+                long_run_variance(&u, lags) * self.nobs as f64
+            }
+            _ => autocovariance_sum(&u, 0),
+        };
+        (scale * meat).sqrt()
+    }
+
+    // Newey-West lag truncation selected from the scores h_t = e_t Σ_i x_ti over the
+    // non-constant columns (just e_t when there are none)
+    pub fn newey_west_lags(&self, columns: &[Vec<f64>]) -> usize {
+        let varying: Vec<&Vec<f64>> = columns
+            .iter()
+            .filter(|c| c.iter().any(|&v| v != c[0]))
+            .collect();
+        let scores: Vec<f64> = self
+            .residuals
+            .iter()
+            .enumerate()
+            .map(|(t, e)| {
+                if varying.is_empty() {
+                    *e
+                } else {
+                    e * varying.iter().map(|c| c[t]).sum::<f64>()
+                }
+            })
+            .collect();
+        newey_west_bandwidth(&scores).min(self.nobs - 1)
+    }
+}
+
+// Solve min ||y - X b|| where X is given as a list of columns, each of length y.len().
+// Returns None when there are not more observations than regressors or X is rank deficient.
+pub(crate) fn least_squares(columns: &[Vec<f64>], y: &[f64]) -> Option<LeastSquares> {
+    let n = y.len();
+    let k = columns.len();
+    if k == 0 || n <= k || columns.iter().any(|c| c.len() != n) {
+        return None;
+    }
+
+    // Column-major working copy that is reduced to R in place
+    let mut a: Vec<Vec<f64>> = columns.to_vec();
+    let mut qty = y.to_vec();
+    let col_norms: Vec<f64> = columns.iter().map(|c| dot(c, c).sqrt()).collect();
+
+    for j in 0..k {
+        let norm = dot(&a[j][j..], &a[j][j..]).sqrt();
+        if !norm.is_finite() || norm <= 1e-12 * col_norms[j].max(f64::MIN_POSITIVE) {
+            return None;
+        }
+
+        let alpha = if a[j][j] > 0.0 { -norm } else { norm };
+        let mut v = a[j][j..].to_vec();
+        v[0] -= alpha;
+        let v_norm2 = dot(&v, &v);
+
+        if v_norm2 > 0.0 {
+            for col in a.iter_mut().skip(j) {
+                reflect(&v, v_norm2, &mut col[j..]);
+            }
+            reflect(&v, v_norm2, &mut qty[j..]);
+        }
+        a[j][j] = alpha;
+    }
+
+    // Back substitution R b = (Q'y)[..k]
+    let mut coefficients = vec![0.0; k];
+    for i in (0..k).rev() {
+        let mut s = qty[i];
+        for (j, coef) in coefficients.iter().enumerate().skip(i + 1) {
+            s -= a[j][i] * coef;
+        }
+        coefficients[i] = s / a[i][i];
+    }
+
+    // R^-1 (upper triangular), then (X'X)^-1 = R^-1 R^-T
+    let mut r_inv = vec![0.0; k * k];
+    for i in 0..k {
+        r_inv[i * k + i] = 1.0 / a[i][i];
+        for j in (i + 1)..k {
+            let mut s = 0.0;
+            for m in i..j {
+                s += r_inv[i * k + m] * a[j][m];
+            }
+            r_inv[i * k + j] = -s / a[j][j];
+        }
+    }
+    let mut xtx_inv = vec![0.0; k * k];
+    for i in 0..k {
+        for j in i..k {
+            let s: f64 = (j..k).map(|m| r_inv[i * k + m] * r_inv[j * k + m]).sum();
+            xtx_inv[i * k + j] = s;
+            xtx_inv[j * k + i] = s;
+        }
+    }
+
+    let residuals: Vec<f64> = (0..n)
+        .map(|i| {
+            y[i] - columns
+                .iter()
+                .zip(&coefficients)
+                .map(|(c, b)| c[i] * b)
+                .sum::<f64>()
+        })
+        .collect();
+    let ssr = dot(&residuals, &residuals);
+
+    Some(LeastSquares {
+        coefficients,
+        residuals,
+        ssr,
+        xtx_inv,
+        nobs: n,
+    })
+}
+
+// Apply the Householder reflection I - 2 v v' / (v'v) to `x`
+fn reflect(v: &[f64], v_norm2: f64, x: &mut [f64]) {
+    let scale = 2.0 * dot(v, x) / v_norm2;
+    sub_scaled(x, scale, v);
+}
+
+// Running X'X / X'y / y'y accumulator for regressions that grow one observation at a
+// time. Cheaper than refitting from scratch, at the cost of the normal equations'
+// conditioning, so callers should keep regressors roughly centred.
+pub(crate) struct NormalEquations {
+    k: usize,
+    xtx: Vec<f64>,
+    xty: Vec<f64>,
+    yty: f64,
+    nobs: usize,
+}
+
+impl NormalEquations {
+    pub fn new(k: usize) -> NormalEquations {
+        NormalEquations {
+            k,
+            xtx: vec![0.0; k * k],
+            xty: vec![0.0; k],
+            yty: 0.0,
+            nobs: 0,
+        }
+    }
+
+    pub fn push(&mut self, x: &[f64], y: f64) {
+        for i in 0..self.k {
+            for j in 0..self.k {
+                self.xtx[i * self.k + j] += x[i] * x[j];
+            }
+            self.xty[i] += x[i] * y;
+        }
+        self.yty += y * y;
+        self.nobs += 1;
+    }
+
+    // Remove an observation added earlier with `push`, for sliding windows
+    pub fn pop(&mut self, x: &[f64], y: f64) {
+        for i in 0..self.k {
+            for j in 0..self.k {
+                self.xtx[i * self.k + j] -= x[i] * x[j];
+            }
+            self.xty[i] -= x[i] * y;
+        }
+        self.yty -= y * y;
+        self.nobs -= 1;
+    }
+
+    pub fn nobs(&self) -> usize {
+        self.nobs
+    }
+
+    pub fn write_state(&self, writer: &mut StateWriter) {
+        writer
+            .usize(self.k)
+            .f64s(&self.xtx)
+            .f64s(&self.xty)
+            .f64(self.yty)
+            .usize(self.nobs);
+    }
+
+    // Sums written by `write_state`, which must be for `k` regressors
+    pub fn read_state(reader: &mut StateReader, k: usize) -> Result<NormalEquations, Error> {
+        let equations = NormalEquations {
+            k: reader.usize()?,
+            xtx: reader.f64s()?,
+            xty: reader.f64s()?,
+            yty: reader.f64()?,
+            nobs: reader.usize()?,
+        };
+        if equations.k != k || equations.xtx.len() != k * k || equations.xty.len() != k {
+            return Err(reader.invalid());
+        }
+        Ok(equations)
+    }
+
+    // t-ratio of coefficient j, or None while the system is singular or has no
+    // residual degrees of freedom
+    pub fn t_stat(&self, j: usize) -> Option<f64> {
+        let k = self.k;
+        if self.nobs <= k {
+            return None;
+        }
+        let l = cholesky(&self.xtx, k)?;
+        let coefficients = cholesky_solve(&l, k, &self.xty);
+        let mut unit = vec![0.0; k];
+        unit[j] = 1.0;
+        let inv_jj = cholesky_solve(&l, k, &unit)[j];
+
+        let explained: f64 = coefficients.iter().zip(&self.xty).map(|(b, v)| b * v).sum();
+        let ssr = (self.yty - explained).max(0.0);
+        let sigma2 = ssr / (self.nobs - k) as f64;
+        let t = coefficients[j] / (sigma2 * inv_jj).sqrt();
+        t.is_finite().then_some(t)
+    }
+}
+
+// Lower-triangular Cholesky factor of a k x k symmetric positive definite matrix
+pub(crate) fn cholesky(a: &[f64], k: usize) -> Option<Vec<f64>> {
+    let mut l = vec![0.0; k * k];
+    cholesky_into(a, k, &mut l).then_some(l)
+}
+
+// `cholesky` written to `l` (k x k, its upper triangle left untouched), for callers that factor
+// many small systems; false when `a` is not positive definite
+pub(crate) fn cholesky_into(a: &[f64], k: usize, l: &mut [f64]) -> bool {
+    for i in 0..k {
+        for j in 0..=i {
+            let s: f64 = (0..j).map(|m| l[i * k + m] * l[j * k + m]).sum();
+            if i == j {
+                let d = a[i * k + i] - s;
+                if d <= 1e-12 * a[i * k + i].abs() {
+                    return false;
+                }
+                l[i * k + i] = d.sqrt();
+            } else {
+                l[i * k + j] = (a[i * k + j] - s) / l[j * k + j];
+            }
+        }
+    }
+    true
+}
+
+// Solve L L' x = b given the Cholesky factor L
+pub(crate) fn cholesky_solve(l: &[f64], k: usize, b: &[f64]) -> Vec<f64> {
+    let mut x = vec![0.0; k];
+    cholesky_solve_into(l, k, b, &mut x);
+    x
+}
+
+// `cholesky_solve` written to `x`
+pub(crate) fn cholesky_solve_into(l: &[f64], k: usize, b: &[f64], x: &mut [f64]) {
+    // Forward substitution into x, then back substitution in place
+    for i in 0..k {
+        let s: f64 = (0..i).map(|m| l[i * k + m] * x[m]).sum();
+        x[i] = (b[i] - s) / l[i * k + i];
+    }
+    for i in (0..k).rev() {
+        let s: f64 = ((i + 1)..k).map(|m| l[m * k + i] * x[m]).sum();
+        x[i] = (x[i] - s) / l[i * k + i];
+    }
+}
+
+// Eigen-decomposition of a k x k symmetric matrix (row-major) by cyclic Jacobi rotations.
+// Returns the eigenvalues and a row-major matrix whose column j is the unit eigenvector
+// for eigenvalue j, in no particular order.
+pub(crate) fn symmetric_eigen(a: &[f64], k: usize) -> (Vec<f64>, Vec<f64>) {
+    let mut a = a.to_vec();
+    let mut v = vec![0.0; k * k];
+    for i in 0..k {
+        v[i * k + i] = 1.0;
+    }
+
+    let scale: f64 = a.iter().map(|x| x * x).sum();
+    for _sweep in 0..100 {
+        let off_diagonal: f64 = (0..k)
+            .flat_map(|p| ((p + 1)..k).map(move |q| (p, q)))
+            .map(|(p, q)| a[p * k + q] * a[p * k + q])
+            .sum();
+        if off_diagonal <= 1e-30 * scale {
+            break;
+        }
+
+        for p in 0..k {
+            for q in (p + 1)..k {
+                let apq = a[p * k + q];
+                if apq == 0.0 {
+                    continue;
+                }
+                // Rotation angle that zeroes a[p][q] (Numerical Recipes' convention)
+                let theta = (a[q * k + q] - a[p * k + p]) / (2.0 * apq);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+
+                for r in 0..k {
+                    let (arp, arq) = (a[r * k + p], a[r * k + q]);
+                    a[r * k + p] = c * arp - s * arq;
+                    a[r * k + q] = s * arp + c * arq;
+                }
+                for r in 0..k {
+                    let (apr, aqr) = (a[p * k + r], a[q * k + r]);
+                    a[p * k + r] = c * apr - s * aqr;
+                    a[q * k + r] = s * apr + c * aqr;
+                }
+                for r in 0..k {
+                    let (vrp, vrq) = (v[r * k + p], v[r * k + q]);
+                    v[r * k + p] = c * vrp - s * vrq;
+                    v[r * k + q] = s * vrp + c * vrq;
+                }
+            }
+        }
+    }
+
+    let eigenvalues = (0..k).map(|i| a[i * k + i]).collect();
+    (eigenvalues, v)
+}
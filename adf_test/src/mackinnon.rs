@@ -0,0 +1,228 @@
+// MacKinnon response surfaces for Dickey-Fuller / Engle-Granger distributions.
+//
+// p-values use the MacKinnon (1994) polynomial approximation
+//     p = Φ(Σ_i γ_i τ^i)
+// with separate coefficients below and above τ*, and critical values use the
+// MacKinnon (2010) finite-sample surfaces
+//     cv(T) = β∞ + β1/T + β2/T² + β3/T³.
+// Row `n_vars - 1` holds the coefficients for a cointegrating regression with
+// `n_vars` variables (1 = univariate ADF). Same coefficients as statsmodels'
+// `adfvalues` module.
+
+use crate::adf::Regression;
+use crate::distributions::normal_cdf;
+use crate::pvalue::{PValue, PValueSource};
+
+// No deterministic terms ("n")
+const TAU_STAR_NC: [f64; 6] = [-1.04, -1.53, -2.68, -3.09, -3.07, -3.77];
+const TAU_MIN_NC: [f64; 6] = [-19.04, -19.62, -21.21, -23.25, -21.63, -25.74];
+const TAU_MAX_NC: [f64; 6] = [f64::INFINITY, 1.51, 0.86, 0.88, 1.05, 1.24];
+const TAU_NC_SMALLP: [[f64; 3]; 6] = [
+    [0.6344, 1.2378, 3.2496e-2],
+    [1.9129, 1.3857, 3.5322e-2],
+    [2.7648, 1.4502, 3.4186e-2],
+    [3.4336, 1.4835, 3.19e-2],
+    [4.0999, 1.5533, 3.59e-2],
+    [4.5388, 1.5344, 2.9807e-2],
+];
+const TAU_NC_LARGEP: [[f64; 4]; 6] = [
+    [0.4797, 9.3557e-1, -0.6999e-1, 3.3066e-2],
+    [1.5578, 8.558e-1, -2.083e-1, -3.3549e-2],
+    [2.2268, 6.8093e-1, -3.2362e-1, -5.4448e-2],
+    [2.7654, 6.4502e-1, -3.0811e-1, -4.4946e-2],
+    [3.2684, 6.8051e-1, -2.6778e-1, -3.4972e-2],
+    [3.7268, 7.167e-1, -2.3648e-1, -2.8288e-2],
+];
+// Only the univariate surface is tabulated for "n"
+const TAU_NC_2010: [[f64; 4]; 3] = [
+    [-2.56574, -2.2358, -3.627, 0.0],
+    [-1.94100, -0.2686, -3.365, 31.223],
+    [-1.61682, 0.2656, -2.714, 25.364],
+];
+
+// Constant-only regression ("c")
+const TAU_STAR_C: [f64; 6] = [-1.61, -2.62, -3.13, -3.47, -3.78, -3.93];
+const TAU_MIN_C: [f64; 6] = [-18.83, -18.86, -23.48, -28.07, -25.96, -23.27];
+const TAU_MAX_C: [f64; 6] = [2.74, 0.92, 0.55, 0.61, 0.79, 1.0];
+// Coefficients of 1, τ, τ² (the τ² term is already scaled by 1e-2)
+const TAU_C_SMALLP: [[f64; 3]; 6] = [
+    [2.1659, 1.4412, 3.8269e-2],
+    [2.92, 1.5012, 3.9796e-2],
+    [3.4699, 1.4856, 3.164e-2],
+    [3.9673, 1.4777, 2.6315e-2],
+    [4.5509, 1.5338, 2.9545e-2],
+    [5.1399, 1.6036, 3.4445e-2],
+];
+// Coefficients of 1, τ, τ², τ³ (scaled by 1, 1e-1, 1e-1, 1e-2)
+const TAU_C_LARGEP: [[f64; 4]; 6] = [
+    [1.7339, 9.3202e-1, -1.2745e-1, -1.0368e-2],
+    [2.1945, 6.4695e-1, -2.9198e-1, -4.2377e-2],
+    [2.5893, 4.5168e-1, -3.6529e-1, -5.0074e-2],
+    [3.0387, 4.5452e-1, -3.3666e-1, -4.1921e-2],
+    [3.5049, 5.2098e-1, -2.9158e-1, -3.3468e-2],
+    [3.9489, 5.8933e-1, -2.5359e-1, -2.721e-2],
+];
+// β∞, β1, β2, β3 for the 1%, 5% and 10% levels
+const TAU_C_2010: [[[f64; 4]; 3]; 6] = [
+    [
+        [-3.43035, -6.5393, -16.786, -79.433],
+        [-2.86154, -2.8903, -4.234, -40.040],
+        [-2.56677, -1.5384, -2.809, 0.0],
+    ],
+    [
+        [-3.89644, -10.9519, -33.527, 0.0],
+        [-3.33613, -6.1101, -6.823, 0.0],
+        [-3.04445, -4.2412, -2.720, 0.0],
+    ],
+    [
+        [-4.29374, -14.4354, -33.195, 47.433],
+        [-3.74066, -8.5632, -10.852, 27.982],
+        [-3.45218, -6.2143, -3.718, 0.0],
+    ],
+    [
+        [-4.64332, -18.1031, -37.972, 0.0],
+        [-4.09600, -11.2349, -11.175, 0.0],
+        [-3.81020, -8.3931, -4.137, 0.0],
+    ],
+    [
+        [-4.95756, -21.8883, -45.142, 0.0],
+        [-4.41519, -14.0405, -12.575, 0.0],
+        [-4.13157, -10.7417, -3.784, 0.0],
+    ],
+    [
+        [-5.24568, -25.6688, -57.737, 88.639],
+        [-4.70693, -16.9178, -17.492, 60.007],
+        [-4.42501, -13.1875, -5.104, 27.877],
+    ],
+];
+
+// Constant and linear trend ("ct") and constant, linear and quadratic trend ("ctt").
+// Only the univariate and two-variable rows are embedded: ADF and the pairwise
+// Engle-Granger test are the only users.
+const TAU_STAR_CT: [f64; 2] = [-2.89, -3.19];
+const TAU_MIN_CT: [f64; 2] = [-16.18, -21.15];
+const TAU_MAX_CT: [f64; 2] = [0.7, 0.63];
+const TAU_CT_SMALLP: [[f64; 3]; 2] = [[3.2512, 1.6047, 4.9588e-2], [3.6646, 1.5419, 3.6448e-2]];
+const TAU_CT_LARGEP: [[f64; 4]; 2] = [
+    [2.5261, 6.1654e-1, -3.7956e-1, -6.0285e-2],
+    [2.85, 5.272e-1, -3.6622e-1, -5.1695e-2],
+];
+const TAU_CT_2010: [[[f64; 4]; 3]; 2] = [
+    [
+        [-3.95877, -9.0531, -28.428, -134.155],
+        [-3.41049, -4.3904, -9.036, -45.374],
+        [-3.12705, -2.5856, -3.925, -22.380],
+    ],
+    [
+        [-4.32762, -15.4387, -35.679, 0.0],
+        [-3.78057, -9.5106, -12.074, 0.0],
+        [-3.49631, -7.0815, -7.538, 21.892],
+    ],
+];
+
+const TAU_STAR_CTT: [f64; 2] = [-3.21, -3.51];
+const TAU_MIN_CTT: [f64; 2] = [-17.17, -21.1];
+const TAU_MAX_CTT: [f64; 2] = [0.54, 0.79];
+const TAU_CTT_SMALLP: [[f64; 3]; 2] = [[4.0003, 1.658, 4.8288e-2], [4.3534, 1.6016, 3.7947e-2]];
+const TAU_CTT_LARGEP: [[f64; 4]; 2] = [
+    [3.0778, 4.9529e-1, -4.1477e-1, -5.9359e-2],
+    [3.4713, 5.967e-1, -3.2507e-1, -4.2286e-2],
+];
+const TAU_CTT_2010: [[[f64; 4]; 3]; 2] = [
+    [
+        [-4.37113, -11.5882, -35.819, -334.047],
+        [-3.83239, -5.9057, -12.490, -118.284],
+        [-3.55326, -4.0001, -5.184, -55.120],
+    ],
+    [
+        [-4.69276, -20.2284, -64.919, 88.884],
+        [-4.15387, -13.3114, -28.402, 72.741],
+        [-3.87346, -10.4637, -17.408, 66.313],
+    ],
+];
+
+// Approximate p-value of a Dickey-Fuller type statistic. `n_vars` must be in 1..=6 for "n"
+// and "c", and 1..=2 for "ct" and "ctt".
+pub(crate) fn mackinnon_p_value(statistic: f64, regression: Regression, n_vars: usize) -> f64 {
+    mackinnon_lookup(statistic, regression, n_vars).value
+}
+
+// Same as `mackinnon_p_value`, flagging statistics beyond the surface's fitted range, where
+// the p-value is clamped to 0 or 1 like a lookup table's boundary rows
+pub(crate) fn mackinnon_lookup(statistic: f64, regression: Regression, n_vars: usize) -> PValue {
+    let i = n_vars - 1;
+    let (tau_star, tau_min, tau_max, smallp, largep) = match regression {
+        Regression::NoConstant => (
+            TAU_STAR_NC[i],
+            TAU_MIN_NC[i],
+            TAU_MAX_NC[i],
+            &TAU_NC_SMALLP[i][..],
+            &TAU_NC_LARGEP[i][..],
+        ),
+        Regression::Constant => (
+            TAU_STAR_C[i],
+            TAU_MIN_C[i],
+            TAU_MAX_C[i],
+            &TAU_C_SMALLP[i][..],
+            &TAU_C_LARGEP[i][..],
+        ),
+        Regression::ConstantTrend => (
+            TAU_STAR_CT[i],
+            TAU_MIN_CT[i],
+            TAU_MAX_CT[i],
+            &TAU_CT_SMALLP[i][..],
+            &TAU_CT_LARGEP[i][..],
+        ),
+        Regression::ConstantQuadraticTrend => (
+            TAU_STAR_CTT[i],
+            TAU_MIN_CTT[i],
+            TAU_MAX_CTT[i],
+            &TAU_CTT_SMALLP[i][..],
+            &TAU_CTT_LARGEP[i][..],
+        ),
+    };
+
+    let p_value = |value, source| PValue { value, source };
+    if statistic.is_nan() {
+        return p_value(f64::NAN, PValueSource::Interpolated);
+    }
+    if statistic > tau_max {
+        return p_value(1.0, PValueSource::ClampedLast);
+    }
+    if statistic < tau_min {
+        return p_value(0.0, PValueSource::ClampedFirst);
+    }
+    let coefficients = if statistic <= tau_star {
+        smallp
+    } else {
+        largep
+    };
+    p_value(
+        normal_cdf(polyval(coefficients, statistic)),
+        PValueSource::Interpolated,
+    )
+}
+
+// 1%, 5% and 10% critical values for a regression with `nobs` observations, or None
+// where no surface is available (multivariate "n", or "ct"/"ctt" beyond two variables).
+// The surface is a polynomial in 1/nobs, so the values change smoothly with the sample size.
+pub(crate) fn mackinnon_critical_values(
+    regression: Regression,
+    n_vars: usize,
+    nobs: usize,
+) -> Option<[f64; 3]> {
+    let surface = match regression {
+        Regression::NoConstant if n_vars == 1 => &TAU_NC_2010,
+        Regression::NoConstant => return None,
+        Regression::Constant => &TAU_C_2010[n_vars - 1],
+        Regression::ConstantTrend => TAU_CT_2010.get(n_vars - 1)?,
+        Regression::ConstantQuadraticTrend => TAU_CTT_2010.get(n_vars - 1)?,
+    };
+    let inv_t = 1.0 / nobs as f64;
+    Some(surface.map(|betas| polyval(&betas, inv_t)))
+}
+
+// Σ_i c_i x^i with coefficients in increasing order of power
+fn polyval(coefficients: &[f64], x: f64) -> f64 {
+    coefficients.iter().rev().fold(0.0, |acc, c| acc * x + c)
+}
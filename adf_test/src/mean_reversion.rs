@@ -0,0 +1,587 @@
+// Mean-reversion speed estimators for spreads.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::adf::Regression;
+use crate::distributions::student_t_cdf;
+use crate::error::Error;
+#[cfg(feature = "wasm")]
+use crate::get_property;
+use crate::json::JsonObject;
+use crate::linalg::{least_squares, LeastSquares};
+use crate::mackinnon::{mackinnon_critical_values, mackinnon_p_value};
+use crate::quantile::select_quantiles;
+#[cfg(feature = "wasm")]
+use crate::rng::parse_seed;
+use crate::rng::{Rng, DEFAULT_SEED};
+use crate::validate::{require_finite, require_length, require_varying};
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct MeanReversionResult {
+    pub half_life: f64,
+    // Slope b of Δs_t = a + b·s_{t-1}; the AR(1) coefficient is 1 + b
+    pub coefficient: f64,
+    pub std_error: f64,
+    pub t_statistic: f64,
+    pub p_value: f64,
+    pub is_mean_reverting: bool,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl MeanReversionResult {
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("half_life", self.half_life)
+            .field("coefficient", self.coefficient)
+            .field("std_error", self.std_error)
+            .field("t_statistic", self.t_statistic)
+            .field("p_value", self.p_value)
+            .field("is_mean_reverting", self.is_mean_reverting)
+            .finish()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct HalfLifeResult {
+    pub half_life: f64,
+    // Slope b of Δs_t = a + b·s_{t-1}
+    pub coefficient: f64,
+    pub std_error: f64,
+    pub nobs: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl HalfLifeResult {
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("half_life", self.half_life)
+            .field("coefficient", self.coefficient)
+            .field("std_error", self.std_error)
+            .field("nobs", self.nobs)
+            .finish()
+    }
+}
+
+pub(crate) struct HalfLifeFit {
+    pub half_life: f64,
+    // Slope of Δs_t on s_{t-1}; negative for a mean-reverting spread
+    pub coefficient: f64,
+    pub std_error: f64,
+    pub nobs: usize,
+}
+
+// Fit Δs_t = a + b·s_{t-1} + ε_t. With the implied AR(1) coefficient φ = 1 + b the
+// half-life is -ln(2) / ln(φ): infinite when φ >= 1 (no reversion) and NaN when φ <= 0
+// (the spread overshoots the mean every bar, so a half-life is meaningless).
+pub(crate) fn half_life_fit(spread: &[f64]) -> Option<HalfLifeFit> {
+    let ols = ar1_regression(spread)?;
+    let coefficient = ols.coefficients[0];
+
+    Some(HalfLifeFit {
+        half_life: ar1_half_life(1.0 + coefficient),
+        coefficient,
+        std_error: ols.std_error(0),
+        nobs: ols.nobs,
+    })
+}
+
+fn ar1_half_life(phi: f64) -> f64 {
+    if phi >= 1.0 {
+        f64::INFINITY
+    } else if phi <= 0.0 {
+        f64::NAN
+    } else {
+        -std::f64::consts::LN_2 / phi.ln()
+    }
+}
+
+// Δs_t regressed on [s_{t-1}, 1]
+fn ar1_regression(spread: &[f64]) -> Option<LeastSquares> {
+    if spread.len() < 3 {
+        return None;
+    }
+    let lagged = spread[..spread.len() - 1].to_vec();
+    let delta: Vec<f64> = spread.windows(2).map(|w| w[1] - w[0]).collect();
+    least_squares(&[lagged, vec![1.0; delta.len()]], &delta)
+}
+
+/// Half-life of mean reversion in bars from the AR(1) / discretised Ornstein-Uhlenbeck
+/// regression Δs_t = a + b·s_{t-1} + ε_t, with the fitted slope b and its standard error.
+///
+/// The half-life is -ln(2) / ln(1 + b): infinite when b >= 0 (no reversion) and NaN when
+/// b <= -1 (the spread overshoots its mean every bar). All fields are NaN (and `nobs` 0) if the
+/// spread has fewer than three points or is constant.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn half_life(spread: &[f64]) -> HalfLifeResult {
+    match half_life_fit(spread) {
+        Some(fit) => HalfLifeResult {
+            half_life: fit.half_life,
+            coefficient: fit.coefficient,
+            std_error: fit.std_error,
+            nobs: fit.nobs,
+        },
+        None => HalfLifeResult {
+            half_life: f64::NAN,
+            coefficient: f64::NAN,
+            std_error: f64::NAN,
+            nobs: 0,
+        },
+    }
+}
+
+/// Half-life plus the significance of the mean-reversion coefficient from a single AR(1)
+/// regression Δs_t = a + b·s_{t-1} + ε_t: a fast alternative to a full ADF test when only the
+/// reversion speed and whether it is real matter.
+///
+/// Under the no-reversion null the t-ratio of b follows the Dickey-Fuller distribution rather
+/// than Student's t, so it is judged against the MacKinnon 5% critical value for the sample
+/// size (about -2.86) instead of -1.645; `is_mean_reverting` is true when b is significantly
+/// negative by that standard. This is exactly a zero-lag ADF test with a constant.
+/// All fields are NaN (and `is_mean_reverting` false) if the regression cannot be fitted.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn mean_reversion_test(spread: &[f64]) -> MeanReversionResult {
+    let Some(fit) = half_life_fit(spread) else {
+        return MeanReversionResult {
+            half_life: f64::NAN,
+            coefficient: f64::NAN,
+            std_error: f64::NAN,
+            t_statistic: f64::NAN,
+            p_value: f64::NAN,
+            is_mean_reverting: false,
+        };
+    };
+
+    let t_statistic = fit.coefficient / fit.std_error;
+    let critical_5_percent =
+        mackinnon_critical_values(Regression::Constant, 1, fit.nobs).map_or(f64::NAN, |cv| cv[1]);
+
+    MeanReversionResult {
+        half_life: fit.half_life,
+        coefficient: fit.coefficient,
+        std_error: fit.std_error,
+        t_statistic,
+        p_value: mackinnon_p_value(t_statistic, Regression::Constant, 1),
+        is_mean_reverting: t_statistic < critical_5_percent,
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct OuFit {
+    // Mean-reversion speed θ, per unit of time
+    pub theta: f64,
+    // Long-run mean μ
+    pub mu: f64,
+    // Diffusion σ of ds = θ(μ - s)dt + σ dW
+    pub sigma: f64,
+    // ln(2) / θ, in units of time (bars when dt = 1)
+    pub half_life: f64,
+    // Stationary standard deviation σ / √(2θ), the natural unit for entry bands
+    pub equilibrium_std: f64,
+    pub nobs: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl OuFit {
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("theta", self.theta)
+            .field("mu", self.mu)
+            .field("sigma", self.sigma)
+            .field("half_life", self.half_life)
+            .field("equilibrium_std", self.equilibrium_std)
+            .field("nobs", self.nobs)
+            .finish()
+    }
+}
+
+/// Ornstein-Uhlenbeck parameters of a spread sampled every `dt` units of time (1 for bars,
+/// 1/252 for daily data in years).
+///
+/// Uses the exact discretisation s_{t+1} = μ(1 - e^{-θ·dt}) + e^{-θ·dt}·s_t + ε_t with
+/// Var(ε) = σ²(1 - e^{-2θ·dt}) / (2θ): the AR(1) regression of s_{t+1} on s_t is the
+/// conditional maximum-likelihood estimate, with the residual variance taken as SSR / n.
+/// Errors if `dt` is not positive and finite, the spread holds a non-finite value, has fewer
+/// than three points or is constant, or the fitted AR(1) coefficient is outside (0, 1), where
+/// no mean-reverting OU process matches the data.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn fit_ou(spread: &[f64], dt: f64) -> Result<OuFit, Error> {
+    if !(dt > 0.0 && dt.is_finite()) {
+        return Err(Error::new("dt must be positive and finite"));
+    }
+    require_finite(spread, "spread")?;
+    require_length(spread, 3, "spread", "the Ornstein-Uhlenbeck fit")?;
+    require_varying(spread, "spread")?;
+
+    let current = spread[..spread.len() - 1].to_vec();
+    let next = &spread[1..];
+    let ols = least_squares(&[current, vec![1.0; next.len()]], next)
+        .ok_or_else(|| Error::new("Ornstein-Uhlenbeck regression failed: spread is degenerate"))?;
+    let phi = ols.coefficients[0];
+    if !(phi > 0.0 && phi < 1.0) {
+        return Err(Error::new(&format!(
+            "fitted AR(1) coefficient {} is outside (0, 1), so no mean-reverting \
+             Ornstein-Uhlenbeck process matches the spread",
+            phi
+        )));
+    }
+
+    let theta = -phi.ln() / dt;
+    let residual_variance = ols.ssr / ols.nobs as f64;
+    let sigma = (residual_variance * 2.0 * theta / (1.0 - phi * phi)).sqrt();
+    Ok(OuFit {
+        theta,
+        mu: ols.coefficients[1] / (1.0 - phi),
+        sigma,
+        half_life: std::f64::consts::LN_2 / theta,
+        equilibrium_std: sigma / (2.0 * theta).sqrt(),
+        nobs: ols.nobs,
+    })
+}
+
+/// Options of `mean_reversion_probability_with_options`; the default matches an empty options
+/// object.
+#[derive(Clone, Copy, Debug)]
+pub struct ReversionOptions {
+    // Monte Carlo draws from the posterior
+    pub draws: usize,
+    pub seed: u32,
+    // Posterior mass of the central credible intervals
+    pub credibility: f64,
+    // Mean and standard deviation of a normal prior on the AR(1) coefficient; None for the
+    // flat reference prior
+    pub prior: Option<(f64, f64)>,
+}
+
+impl Default for ReversionOptions {
+    fn default() -> ReversionOptions {
+        ReversionOptions {
+            draws: 10_000,
+            seed: DEFAULT_SEED,
+            credibility: 0.95,
+            prior: None,
+        }
+    }
+}
+
+impl ReversionOptions {
+    #[cfg(feature = "wasm")]
+    pub(crate) fn from_js(options: &JsValue) -> Result<ReversionOptions, Error> {
+        let mut parsed = ReversionOptions::default();
+        if options.is_undefined() || options.is_null() {
+            return Ok(parsed);
+        }
+        if !options.is_object() {
+            return Err(Error::new("options must be an object"));
+        }
+
+        let draws = get_property(options, "draws")?;
+        if !draws.is_undefined() {
+            parsed.draws = draws
+                .as_f64()
+                .filter(|v| *v >= 1.0 && v.fract() == 0.0)
+                .ok_or_else(|| Error::new("draws must be a positive integer"))?
+                as usize;
+        }
+
+        let seed = get_property(options, "seed")?;
+        if !seed.is_undefined() {
+            parsed.seed = parse_seed(&seed)?;
+        }
+
+        let credibility = get_property(options, "credibility")?;
+        if !credibility.is_undefined() {
+            parsed.credibility = credibility
+                .as_f64()
+                .ok_or_else(|| Error::new("credibility must be a number"))?;
+        }
+
+        let prior_mean = get_property(options, "prior_mean")?;
+        let prior_std = get_property(options, "prior_std")?;
+        if !prior_std.is_undefined() {
+            let std = prior_std
+                .as_f64()
+                .ok_or_else(|| Error::new("prior_std must be a number"))?;
+            let mean = if prior_mean.is_undefined() {
+                1.0
+            } else {
+                prior_mean
+                    .as_f64()
+                    .ok_or_else(|| Error::new("prior_mean must be a number"))?
+            };
+            parsed.prior = Some((mean, std));
+        } else if !prior_mean.is_undefined() {
+            return Err(Error::new("prior_mean needs a prior_std"));
+        }
+        Ok(parsed)
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug)]
+pub struct ReversionProbability {
+    // Posterior probability that the AR(1) coefficient φ is below 1
+    pub probability: f64,
+    // Posterior mean and standard deviation of φ
+    pub phi_mean: f64,
+    pub phi_std: f64,
+    // Central credible interval of φ
+    pub phi_lower: f64,
+    pub phi_upper: f64,
+    // Posterior median half-life in bars and its credible interval: infinite where φ >= 1,
+    // NaN where φ <= 0
+    pub half_life_median: f64,
+    pub half_life_lower: f64,
+    pub half_life_upper: f64,
+    pub credibility: f64,
+    pub draws: usize,
+    // Kish effective sample size of the prior-weighted draws; `draws` under the flat prior
+    pub effective_draws: f64,
+    pub nobs: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ReversionProbability {
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("probability", self.probability)
+            .field("phi_mean", self.phi_mean)
+            .field("phi_std", self.phi_std)
+            .field("phi_lower", self.phi_lower)
+            .field("phi_upper", self.phi_upper)
+            .field("half_life_median", self.half_life_median)
+            .field("half_life_lower", self.half_life_lower)
+            .field("half_life_upper", self.half_life_upper)
+            .field("credibility", self.credibility)
+            .field("draws", self.draws)
+            .field("effective_draws", self.effective_draws)
+            .field("nobs", self.nobs)
+            .finish()
+    }
+}
+
+/// Posterior probability that a spread mean-reverts, P(φ < 1) for the AR(1) coefficient φ of
+/// s_t = a + φ·s_{t-1} + ε_t: a direct "probability of mean reversion" to show in place of a
+/// p-value, with the posterior of φ and of the half-life.
+///
+/// With the flat reference prior p(a, φ, σ²) ∝ 1/σ² the posterior is conjugate: σ² given the
+/// data is scaled inverse chi-square on n - 2 degrees of freedom and (a, φ) given σ² normal
+/// around the least-squares fit, so φ is Student's t around the fitted coefficient with its
+/// standard error as the scale, and the probability is exact. The credible intervals come from
+/// `draws` Monte Carlo draws of that posterior (seeded by `seed`, default 0, so a seed always
+/// gives the same numbers). A normal prior on φ, given by `prior_std` and `prior_mean`
+/// (default 1, a prior centred on no reversion), reweights the draws by its density and all
+/// figures, the probability included, are then read off the weighted draws.
+///
+/// `options` is an optional object with `draws` (default 10000), `seed`, `credibility` of the
+/// intervals (default 0.95), `prior_mean` and `prior_std`. The likelihood is conditioned on
+/// the first value and takes no account of the Dickey-Fuller skew of the estimate near φ = 1,
+/// so on short samples the probability reads as more confident than `mean_reversion_test`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn mean_reversion_probability(
+    spread: &[f64],
+    options: JsValue,
+) -> Result<ReversionProbability, Error> {
+    let options = ReversionOptions::from_js(&options)?;
+    mean_reversion_probability_with_options(spread, &options)
+}
+
+/// `mean_reversion_probability` for native callers, with the options as a struct.
+pub fn mean_reversion_probability_with_options(
+    spread: &[f64],
+    options: &ReversionOptions,
+) -> Result<ReversionProbability, Error> {
+    require_finite(spread, "spread")?;
+    require_length(spread, 4, "spread", "the mean-reversion posterior")?;
+    require_varying(spread, "spread")?;
+    if options.draws == 0 {
+        return Err(Error::new("draws must be at least 1"));
+    }
+    if !(options.credibility > 0.0 && options.credibility < 1.0) {
+        return Err(Error::new("credibility must be in (0, 1)"));
+    }
+    if let Some((mean, std)) = options.prior {
+        if !mean.is_finite() {
+            return Err(Error::new("prior_mean must be finite"));
+        }
+        if !(std > 0.0 && std.is_finite()) {
+            return Err(Error::new("prior_std must be a positive number"));
+        }
+    }
+    let ols = ar1_regression(spread)
+        .ok_or_else(|| Error::new("the AR(1) regression of the spread is singular"))?;
+
+    // φ = 1 + b for the slope b of Δs_t on s_{t-1}; its marginal posterior is
+    // b̂ + se·Z / √(χ²_ν / ν), drawn through a gamma variate for the chi-square
+    let slope = ols.coefficients[0];
+    let std_error = ols.std_error(0);
+    let dof = (ols.nobs - 2) as f64;
+    let mut rng = Rng::new(options.seed);
+    let phi: Vec<f64> = (0..options.draws)
+        .map(|_| {
+            let chi_squared = 2.0 * rng.gamma(0.5 * dof);
+            1.0 + slope + std_error * rng.normal() / (chi_squared / dof).sqrt()
+        })
+        .collect();
+
+    // Prior density relative to its largest value over the draws, so no weight underflows
+    let weights: Vec<f64> = match options.prior {
+        Some((mean, std)) => {
+            let log_density: Vec<f64> = phi
+                .iter()
+                .map(|p| -0.5 * ((p - mean) / std).powi(2))
+                .collect();
+            let peak = log_density
+                .iter()
+                .copied()
+                .fold(f64::NEG_INFINITY, f64::max);
+            log_density.iter().map(|l| (l - peak).exp()).collect()
+        }
+        None => vec![1.0; phi.len()],
+    };
+    let total: f64 = weights.iter().sum();
+    let phi_mean = phi.iter().zip(&weights).map(|(p, w)| w * p).sum::<f64>() / total;
+    let phi_variance = phi
+        .iter()
+        .zip(&weights)
+        .map(|(p, w)| w * (p - phi_mean).powi(2))
+        .sum::<f64>()
+        / total;
+    let effective_draws = total * total / weights.iter().map(|w| w * w).sum::<f64>();
+
+    let (probability, mut sample) = match options.prior {
+        None => (student_t_cdf(-slope / std_error, dof), phi),
+        Some(_) => {
+            let below: f64 = phi
+                .iter()
+                .zip(&weights)
+                .filter(|(p, _)| **p < 1.0)
+                .map(|(_, w)| w)
+                .sum();
+            (
+                below / total,
+                systematic_resample(&phi, &weights, total, &mut rng),
+            )
+        }
+    };
+    let tail = 0.5 * (1.0 - options.credibility);
+    let quantiles = select_quantiles(&mut sample, &[tail, 0.5, 1.0 - tail]);
+
+    // The half-life rises with φ, so its quantiles are the half-lives of φ's
+    Ok(ReversionProbability {
+        probability,
+        phi_mean,
+        phi_std: phi_variance.sqrt(),
+        phi_lower: quantiles[0],
+        phi_upper: quantiles[2],
+        half_life_median: ar1_half_life(quantiles[1]),
+        half_life_lower: ar1_half_life(quantiles[0]),
+        half_life_upper: ar1_half_life(quantiles[2]),
+        credibility: options.credibility,
+        draws: options.draws,
+        effective_draws,
+        nobs: ols.nobs,
+    })
+}
+
+// As many draws as `values`, taken in proportion to `weights` (summing to `total`) at evenly
+// spaced points of the cumulative weight from one uniform offset
+fn systematic_resample(values: &[f64], weights: &[f64], total: f64, rng: &mut Rng) -> Vec<f64> {
+    let step = total / values.len() as f64;
+    let mut point = rng.uniform() * step;
+    let mut cumulative = weights[0];
+    let mut i = 0;
+    let mut sample = Vec::with_capacity(values.len());
+    for _ in 0..values.len() {
+        while cumulative < point && i + 1 < values.len() {
+            i += 1;
+            cumulative += weights[i];
+        }
+        sample.push(values[i]);
+        point += step;
+    }
+    sample
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use crate::rng::fixtures::ar1;
+
+    #[test]
+    fn reverting_spread_is_significant() {
+        let result = mean_reversion_test(&ar1(500, 0.9, 2));
+        assert!(result.is_mean_reverting);
+        assert!(result.p_value < 0.01, "p = {}", result.p_value);
+        assert!(result.t_statistic < -2.86);
+        // Half-life of an AR(1) is ln 2 / -ln φ ≈ 6.6 bars
+        assert!((result.half_life - 6.6).abs() < 2.0, "{}", result.half_life);
+    }
+
+    #[test]
+    fn random_walk_is_not_significant() {
+        let result = mean_reversion_test(&ar1(500, 1.0, 1));
+        assert!(!result.is_mean_reverting);
+        assert!(result.p_value > 0.05, "p = {}", result.p_value);
+
+        // Across many random walks the 5% rule rejects at about its nominal rate
+        let rejections = (1..=400)
+            .filter(|&seed| mean_reversion_test(&ar1(500, 1.0, seed)).is_mean_reverting)
+            .count();
+        assert!(
+            (8..=32).contains(&rejections),
+            "{} of 400 rejected",
+            rejections
+        );
+    }
+
+    #[test]
+    fn fit_ou_recovers_the_simulated_parameters() {
+        // Exact discretisation of θ = 0.2, μ = 5, σ = 0.5 sampled at dt = 1
+        let (theta, mu, sigma) = (0.2_f64, 5.0, 0.5);
+        let phi = (-theta).exp();
+        let shock = sigma * ((1.0 - phi * phi) / (2.0 * theta)).sqrt();
+        let mut rng = Rng::new(21);
+        let mut spread = vec![mu; 5000];
+        for t in 1..spread.len() {
+            spread[t] = mu + phi * (spread[t - 1] - mu) + shock * rng.normal();
+        }
+
+        let fit = fit_ou(&spread, 1.0).unwrap();
+        assert!((fit.theta - theta).abs() < 0.03, "theta {}", fit.theta);
+        assert!((fit.mu - mu).abs() < 0.1, "mu {}", fit.mu);
+        assert!((fit.sigma - sigma).abs() < 0.03, "sigma {}", fit.sigma);
+        assert_eq!(fit.nobs, spread.len() - 1);
+        // θ scales with the sampling interval
+        let daily = fit_ou(&spread, 1.0 / 252.0).unwrap();
+        assert!((daily.theta / fit.theta - 252.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn fit_ou_rejects_bad_input() {
+        let spread = ar1(200, 0.8, 4);
+        assert!(fit_ou(&spread, 0.0).is_err());
+        assert!(fit_ou(&spread, f64::NAN).is_err());
+
+        let mut with_nan = spread.clone();
+        with_nan[7] = f64::NAN;
+        let error = fit_ou(&with_nan, 1.0).err().unwrap();
+        assert_eq!(error.code(), ErrorCode::NonFinite);
+        assert_eq!(error.index(), Some(7));
+
+        let too_short = fit_ou(&spread[..2], 1.0).err().unwrap();
+        assert_eq!(too_short.code(), ErrorCode::TooShort);
+        let constant = fit_ou(&[1.5; 50], 1.0).err().unwrap();
+        assert_eq!(constant.code(), ErrorCode::Constant);
+
+        // An explosive series has no mean-reverting OU counterpart
+        let explosive: Vec<f64> = (0..50).map(|t| 1.05_f64.powi(t)).collect();
+        assert!(fit_ou(&explosive, 1.0).is_err());
+    }
+}
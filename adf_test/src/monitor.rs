@@ -0,0 +1,398 @@
+// Early warning of a cointegration breakdown in an active pair.
+//
+// Three symptoms of a pair coming apart are measured on the most recent data and
+// each is scaled to a score between 0 (healthy) and 1 (at its alarm level):
+//
+// - the rolling Engle-Granger p-value, extrapolated one monitoring span ahead
+//   from its drift over the last span, against an alarm p-value;
+// - the Brown-Durbin-Evans CUSUM of the recursive residuals of the hedge
+//   regression, as its largest excursion relative to the significance boundary
+//   once the serial correlation of the residuals is allowed for;
+// - the expansion of the spread's variance, as the F ratio of the variance of
+//   the recent spread changes to that of the earlier ones in the window, on a log
+//   scale up to the F critical value.
+//
+// The breakdown score is their weighted mean, so several moderate symptoms raise
+// it, and a single decisive one, a weighted component at its alarm level, stops
+// trading on its own whatever the score. The components are returned alongside
+// so the cause of an alarm is visible. The scores are heuristics built from tests
+// at a fixed significance, not a probability of breakdown.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::coint::engle_granger;
+use crate::distributions::f_sf;
+use crate::error::Error;
+#[cfg(feature = "wasm")]
+use crate::get_property;
+use crate::hac::{long_run_variance, newey_west_bandwidth};
+use crate::json::JsonObject;
+use crate::rolling::rolling_engle_granger;
+use crate::stability::cusum_test;
+use crate::stats::{nanmean, nanstd, NanPolicy};
+use crate::validate::{require_finite, require_length, require_same_length};
+
+/// Options of `breakdown_risk_with_options`; the default matches an empty options object.
+#[derive(Clone, Copy, Debug)]
+pub struct BreakdownOptions {
+    // Bars of the estimation window of the cointegration and stability tests
+    pub window: usize,
+    // Monitoring span: the drift of the p-value is measured over it, and its spread changes
+    // are the "recent" ones of the variance comparison
+    pub recent: usize,
+    // Rolling p-value at which the cointegration score reaches 1
+    pub p_alarm: f64,
+    // Significance of the CUSUM boundary (0.01, 0.05 or 0.10) and of the variance F test
+    pub alpha: f64,
+    // Weights of the cointegration, CUSUM and variance scores in the composite
+    pub cointegration_weight: f64,
+    pub cusum_weight: f64,
+    pub variance_weight: f64,
+    // Composite score at or above which the pair should stop trading
+    pub threshold: f64,
+}
+
+impl Default for BreakdownOptions {
+    fn default() -> BreakdownOptions {
+        BreakdownOptions {
+            window: 250,
+            recent: 20,
+            p_alarm: 0.10,
+            alpha: 0.05,
+            cointegration_weight: 1.0,
+            cusum_weight: 1.0,
+            variance_weight: 1.0,
+            threshold: 0.5,
+        }
+    }
+}
+
+impl BreakdownOptions {
+    #[cfg(feature = "wasm")]
+    pub(crate) fn from_js(options: &JsValue) -> Result<BreakdownOptions, Error> {
+        let mut parsed = BreakdownOptions::default();
+        if options.is_undefined() || options.is_null() {
+            return Ok(parsed);
+        }
+        if !options.is_object() {
+            return Err(Error::new("options must be an object"));
+        }
+
+        let count = |key: &str| -> Result<Option<usize>, Error> {
+            let value = get_property(options, key)?;
+            if value.is_undefined() {
+                return Ok(None);
+            }
+            value
+                .as_f64()
+                .filter(|v| *v >= 0.0 && v.fract() == 0.0 && *v <= u32::MAX as f64)
+                .map(|v| Some(v as usize))
+                .ok_or_else(|| Error::new(&format!("{} must be a non-negative integer", key)))
+        };
+        let number = |key: &str| -> Result<Option<f64>, Error> {
+            let value = get_property(options, key)?;
+            if value.is_undefined() {
+                return Ok(None);
+            }
+            value
+                .as_f64()
+                .filter(|v| v.is_finite())
+                .map(Some)
+                .ok_or_else(|| Error::new(&format!("{} must be a finite number", key)))
+        };
+
+        if let Some(window) = count("window")? {
+            parsed.window = window;
+        }
+        if let Some(recent) = count("recent")? {
+            parsed.recent = recent;
+        }
+        if let Some(p_alarm) = number("p_alarm")? {
+            parsed.p_alarm = p_alarm;
+        }
+        if let Some(alpha) = number("alpha")? {
+            parsed.alpha = alpha;
+        }
+        if let Some(weight) = number("cointegration_weight")? {
+            parsed.cointegration_weight = weight;
+        }
+        if let Some(weight) = number("cusum_weight")? {
+            parsed.cusum_weight = weight;
+        }
+        if let Some(weight) = number("variance_weight")? {
+            parsed.variance_weight = weight;
+        }
+        if let Some(threshold) = number("threshold")? {
+            parsed.threshold = threshold;
+        }
+        Ok(parsed)
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug)]
+pub struct BreakdownRisk {
+    // Weighted mean of the three component scores, each in [0, 1]
+    pub score: f64,
+    // True while the score is below the threshold and no component is at its alarm level
+    pub keep_trading: bool,
+    // Components with a positive weight at their alarm level (score 1)
+    pub alarms: usize,
+    pub cointegration_score: f64,
+    // Engle-Granger p-value of the latest window, its change since the window `recent` bars
+    // earlier, and the two summed (floored at the current value), which the score is read from
+    pub p_value: f64,
+    pub p_value_drift: f64,
+    pub projected_p_value: f64,
+    pub cusum_score: f64,
+    // Largest |CUSUM| over the window relative to the boundary, corrected for the serial
+    // correlation of the recursive residuals; above 1 rejects stability
+    pub cusum_ratio: f64,
+    // Hedge ratio of the latest window and its change since the window `recent` bars earlier
+    pub hedge_ratio: f64,
+    pub hedge_ratio_drift: f64,
+    pub variance_score: f64,
+    // Variance of the recent spread changes over that of the earlier ones in the window, and
+    // the F critical value at alpha it is compared with
+    pub variance_ratio: f64,
+    pub variance_critical: f64,
+    // Bars used: window + recent
+    pub nobs: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl BreakdownRisk {
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("score", self.score)
+            .field("keep_trading", self.keep_trading)
+            .field("alarms", self.alarms)
+            .field("cointegration_score", self.cointegration_score)
+            .field("p_value", self.p_value)
+            .field("p_value_drift", self.p_value_drift)
+            .field("projected_p_value", self.projected_p_value)
+            .field("cusum_score", self.cusum_score)
+            .field("cusum_ratio", self.cusum_ratio)
+            .field("hedge_ratio", self.hedge_ratio)
+            .field("hedge_ratio_drift", self.hedge_ratio_drift)
+            .field("variance_score", self.variance_score)
+            .field("variance_ratio", self.variance_ratio)
+            .field("variance_critical", self.variance_critical)
+            .field("nobs", self.nobs)
+            .finish()
+    }
+}
+
+/// Breakdown-risk score of an active pair y ~ x from its most recent `window + recent` bars,
+/// with the three components it combines, for a daily keep-or-stop decision.
+///
+/// The cointegration component runs `engle_granger` on the latest `window` bars and on the
+/// window `recent` bars earlier, adds any rise of the p-value between them to the latest one
+/// and scores the result linearly up to `p_alarm`. The CUSUM component runs `cusum_test` at
+/// `alpha` on the latest window and scores its largest excursion relative to the boundary,
+/// after dividing it by the ratio of the long-run (Newey-West) to the per-bar standard deviation
+/// of the recursive residuals, which are serially correlated for a mean-reverting spread. The
+/// variance component compares the variance of the last `recent` changes of the latest
+/// window's spread with that of its earlier changes, scored as ln F / ln F_crit up to the F
+/// critical value at `alpha`. The score is the weighted mean of the three, and `keep_trading`
+/// is false once it reaches `threshold` or any component with a positive weight reaches 1, so
+/// one broken symptom is enough to stop a pair that the mean alone would keep trading.
+///
+/// `options` is an optional object with `window` (default 250), `recent` (default 20),
+/// `p_alarm` (default 0.10), `alpha` (0.01, 0.05 (default) or 0.10),
+/// `cointegration_weight`, `cusum_weight` and `variance_weight` (default 1 each) and
+/// `threshold` (default 0.5). Older bars are ignored, so passing the full history costs no
+/// more than passing the tail.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn breakdown_risk(y: &[f64], x: &[f64], options: JsValue) -> Result<BreakdownRisk, Error> {
+    let options = BreakdownOptions::from_js(&options)?;
+    breakdown_risk_with_options(y, x, &options)
+}
+
+/// `breakdown_risk` for native callers, with the options as a struct.
+pub fn breakdown_risk_with_options(
+    y: &[f64],
+    x: &[f64],
+    options: &BreakdownOptions,
+) -> Result<BreakdownRisk, Error> {
+    if options.recent < 5 {
+        return Err(Error::new("recent must be at least 5"));
+    }
+    if options.window < 2 * options.recent.max(10) {
+        return Err(Error::new(
+            "window must be at least twice recent, and at least 20",
+        ));
+    }
+    if !(options.p_alarm > 0.0 && options.p_alarm <= 1.0) {
+        return Err(Error::new("p_alarm must be in (0, 1]"));
+    }
+    let weights = [
+        options.cointegration_weight,
+        options.cusum_weight,
+        options.variance_weight,
+    ];
+    if weights.iter().any(|w| *w < 0.0) || weights.iter().sum::<f64>() <= 0.0 {
+        return Err(Error::new(
+            "weights must be non-negative with a positive sum",
+        ));
+    }
+    require_same_length(y, x)?;
+    require_finite(y, "y")?;
+    require_finite(x, "x")?;
+    let nobs = options.window + options.recent;
+    require_length(y, nobs, "y", "breakdown_risk with this window and recent")?;
+
+    let start = y.len() - nobs;
+    let (y, x) = (&y[start..], &x[start..]);
+    let (y_window, x_window) = (&y[options.recent..], &x[options.recent..]);
+
+    // The window `recent` bars ago and the latest one, as two six-value tuples
+    let windows = rolling_engle_granger(y, x, options.window, options.recent)?;
+    let (p_then, p_value) = (windows[4], windows[10]);
+    let (hedge_then, hedge_ratio) = (windows[1], windows[7]);
+    if p_value.is_nan() {
+        return Err(Error::new(
+            "the latest window is degenerate: x or the spread does not vary",
+        ));
+    }
+    let p_value_drift = p_value - p_then;
+    let projected_p_value = (p_value + p_value_drift.max(0.0)).min(1.0);
+    let cointegration_score = (projected_p_value / options.p_alarm).min(1.0);
+
+    let cusum = cusum_test(y_window, x_window, Some(options.alpha))?;
+    // The spread is autocorrelated, and so are the recursive residuals of its regression: their
+    // partial sums wander by the long-run rather than the per-bar standard deviation
+    let residuals = cusum.recursive_residuals();
+    let mean = nanmean(&residuals, NanPolicy::Propagate);
+    let demeaned: Vec<f64> = residuals.iter().map(|w| w - mean).collect();
+    let long_run = long_run_variance(&demeaned, newey_west_bandwidth(&demeaned));
+    let short_run = long_run_variance(&demeaned, 0);
+    let deflator = (long_run / short_run).sqrt();
+    let deflator = if deflator.is_finite() && deflator > 0.0 {
+        deflator
+    } else {
+        1.0
+    };
+    let cusum_ratio = cusum
+        .cusum()
+        .iter()
+        .zip(cusum.cusum_bounds())
+        .fold(0.0f64, |ratio, (s, bound)| ratio.max(s.abs() / bound))
+        / deflator;
+    let cusum_score = cusum_ratio.min(1.0);
+
+    let fit = engle_granger(y_window, x_window)?;
+    let changes: Vec<f64> = fit.residuals().windows(2).map(|w| w[1] - w[0]).collect();
+    let (earlier, recent) = changes.split_at(changes.len() - options.recent);
+    let variance = |values: &[f64]| nanstd(values, 1, NanPolicy::Propagate).powi(2);
+    let (recent_dof, earlier_dof) = ((recent.len() - 1) as f64, (earlier.len() - 1) as f64);
+    let variance_ratio = variance(recent) / variance(earlier);
+    let variance_critical = f_critical(options.alpha, recent_dof, earlier_dof);
+    let variance_score = if variance_ratio.is_nan() {
+        // No variation in the earlier changes: any recent variation is an expansion
+        1.0
+    } else {
+        (variance_ratio.ln() / variance_critical.ln()).clamp(0.0, 1.0)
+    };
+
+    let scores = [cointegration_score, cusum_score, variance_score];
+    let score =
+        scores.iter().zip(weights).map(|(s, w)| s * w).sum::<f64>() / weights.iter().sum::<f64>();
+    let alarms = scores
+        .iter()
+        .zip(weights)
+        .filter(|(s, w)| **s >= 1.0 && *w > 0.0)
+        .count();
+    Ok(BreakdownRisk {
+        score,
+        keep_trading: score < options.threshold && alarms == 0,
+        alarms,
+        cointegration_score,
+        p_value,
+        p_value_drift,
+        projected_p_value,
+        cusum_score,
+        cusum_ratio,
+        hedge_ratio,
+        hedge_ratio_drift: hedge_ratio - hedge_then,
+        variance_score,
+        variance_ratio,
+        variance_critical,
+        nobs,
+    })
+}
+
+// Upper `alpha` quantile of the F distribution with (d1, d2) degrees of freedom, by bisection
+// of the survival function, which falls as f rises
+fn f_critical(alpha: f64, d1: f64, d2: f64) -> f64 {
+    let (mut lower, mut upper) = (0.0, 2.0);
+    while f_sf(upper, d1, d2) > alpha && upper < 1e12 {
+        lower = upper;
+        upper *= 2.0;
+    }
+    for _ in 0..100 {
+        let middle = 0.5 * (lower + upper);
+        if f_sf(middle, d1, d2) > alpha {
+            lower = middle;
+        } else {
+            upper = middle;
+        }
+    }
+    0.5 * (lower + upper)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::fixtures::{ar1, cointegrated_pair};
+
+    // Cointegrated pair y = 3 + 2x + s with an AR(1) spread s, plus white noise over the last
+    // `recent` bars that raises the shock variance there by a factor of burst²
+    fn pair(n: usize, recent: usize, burst: f64, seed: u32) -> (Vec<f64>, Vec<f64>) {
+        let (mut y, x, _) = cointegrated_pair(n, 2.0, 0.5, seed);
+        let noise = ar1(recent + 1, 0.0, seed.wrapping_add(1000));
+        let scale = (burst * burst - 1.0).sqrt();
+        for (y, e) in y[n - recent..].iter_mut().zip(&noise[1..]) {
+            *y += scale * e;
+        }
+        (y, x)
+    }
+
+    #[test]
+    fn one_saturated_component_stops_trading() {
+        let options = BreakdownOptions::default();
+        for seed in 1..=5 {
+            let (y, x) = pair(400, options.recent, 6.0, seed);
+            let risk = breakdown_risk_with_options(&y, &x, &options).unwrap();
+            assert_eq!(risk.variance_score, 1.0, "seed {}", seed);
+            assert!(risk.alarms >= 1, "seed {}", seed);
+            assert!(!risk.keep_trading, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn healthy_pair_keeps_trading() {
+        let options = BreakdownOptions::default();
+        for seed in [1, 2, 3, 5, 6] {
+            let (y, x) = pair(400, options.recent, 1.0, seed);
+            let risk = breakdown_risk_with_options(&y, &x, &options).unwrap();
+            assert_eq!(risk.alarms, 0, "seed {}: {}", seed, risk.to_json());
+            assert!(risk.keep_trading, "seed {}: {}", seed, risk.to_json());
+            assert!(risk.score < options.threshold);
+        }
+        // The CUSUM and variance components are 5% tests, so about one healthy pair in ten
+        // raises an alarm; many more would mean a miscalibrated component
+        let stopped = (1..=400)
+            .filter(|&seed| {
+                let (y, x) = pair(400, options.recent, 1.0, seed);
+                !breakdown_risk_with_options(&y, &x, &options)
+                    .unwrap()
+                    .keep_trading
+            })
+            .count();
+        assert!(stopped <= 80, "{} of 400 healthy pairs stopped", stopped);
+    }
+}
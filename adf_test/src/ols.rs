@@ -0,0 +1,239 @@
+// Ordinary least squares with an intercept, exposed for hedge-ratio estimation.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::diagnostics::{
+    arch_lm, durbin_watson, ljung_box, HeteroskedasticityTest, LjungBoxResult,
+};
+use crate::error::Error;
+use crate::json::JsonObject;
+use crate::linalg::{least_squares, CovarianceType};
+use crate::stats::{nanmean, NanPolicy};
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct OlsResult {
+    // First slope; the hedge ratio for a single regressor
+    pub beta: f64,
+    pub intercept: f64,
+    pub intercept_std_error: f64,
+    pub intercept_t_stat: f64,
+    pub r_squared: f64,
+    pub adj_r_squared: f64,
+    pub nobs: usize,
+    coefficients: Vec<f64>,
+    std_errors: Vec<f64>,
+    t_stats: Vec<f64>,
+    residuals: Vec<f64>,
+    cov_type: CovarianceType,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl OlsResult {
+    // Slope coefficients, one per regressor (the intercept is reported separately)
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn coefficients(&self) -> Vec<f64> {
+        self.coefficients.clone()
+    }
+
+    // Standard errors of the slopes under `cov_type`
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn std_errors(&self) -> Vec<f64> {
+        self.std_errors.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn t_stats(&self) -> Vec<f64> {
+        self.t_stats.clone()
+    }
+
+    // y - intercept - Σ coefficients·x, i.e. the spread for a hedge-ratio regression
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn residuals(&self) -> Vec<f64> {
+        self.residuals.clone()
+    }
+
+    // Covariance estimator behind the standard errors: "nonrobust", "HC0", "HC1" or "HAC"
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn cov_type(&self) -> String {
+        self.cov_type.as_str().to_string()
+    }
+
+    // Newey-West lag truncation actually used, whether given or selected; None unless "HAC"
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn hac_lags(&self) -> Option<usize> {
+        match self.cov_type {
+            CovarianceType::Hac(lags) => lags,
+            _ => None,
+        }
+    }
+
+    /// `ljung_box` of the residuals.
+    pub fn ljung_box(&self, lags: usize) -> Result<LjungBoxResult, Error> {
+        ljung_box(&self.residuals, lags)
+    }
+
+    /// `durbin_watson` of the residuals.
+    pub fn durbin_watson(&self) -> f64 {
+        durbin_watson(&self.residuals)
+    }
+
+    /// `arch_lm` of the residuals.
+    pub fn arch_lm(&self, lags: usize) -> Result<HeteroskedasticityTest, Error> {
+        arch_lm(&self.residuals, lags)
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("beta", self.beta)
+            .field("intercept", self.intercept)
+            .field("intercept_std_error", self.intercept_std_error)
+            .field("intercept_t_stat", self.intercept_t_stat)
+            .field("r_squared", self.r_squared)
+            .field("adj_r_squared", self.adj_r_squared)
+            .field("nobs", self.nobs)
+            .field("coefficients", &self.coefficients)
+            .field("std_errors", &self.std_errors)
+            .field("t_stats", &self.t_stats)
+            .field("cov_type", self.cov_type())
+            .field("hac_lags", self.hac_lags())
+            .field("residuals", &self.residuals)
+            .finish()
+    }
+}
+
+/// OLS of y on x with an intercept: `beta` is the hedge ratio and `residuals` the spread, ready
+/// to pass straight into the ADF functions.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn ols(y: &[f64], x: &[f64]) -> Result<OlsResult, Error> {
+    if y.len() != x.len() {
+        return Err(Error::new("y and x must have the same length"));
+    }
+    fit(y, vec![x.to_vec()], CovarianceType::Classical)
+}
+
+/// `ols` with standard errors from the covariance estimator `cov_type`: "nonrobust"
+/// (classical), "HC0" or "HC1" (White) or "HAC" (Newey-West). The hedge-ratio regression on
+/// price levels has strongly autocorrelated residuals, which makes the classical standard errors
+/// far too small; "HAC" corrects for that.
+///
+/// `hac_lags` is the Newey-West lag truncation; when omitted it is selected with the Newey &
+/// West (1994) plug-in rule, as for `kpss_test`. It is ignored for the other estimators. The coefficients are the OLS
+/// ones in every case.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn ols_robust(
+    y: &[f64],
+    x: &[f64],
+    cov_type: &str,
+    hac_lags: Option<usize>,
+) -> Result<OlsResult, Error> {
+    let cov = parse_cov_type(cov_type, hac_lags)?;
+    if y.len() != x.len() {
+        return Err(Error::new("y and x must have the same length"));
+    }
+    fit(y, vec![x.to_vec()], cov)
+}
+
+/// OLS of y on several regressors plus an intercept, e.g. one leg against a basket.
+/// `x` holds `y.len()` rows of `n_features` values, row-major (one row per observation).
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn ols_multi(y: &[f64], x: &[f64], n_features: usize) -> Result<OlsResult, Error> {
+    fit(
+        y,
+        feature_columns(y, x, n_features)?,
+        CovarianceType::Classical,
+    )
+}
+
+/// `ols_multi` with the covariance estimator `cov_type` and optional `hac_lags`, as in
+/// `ols_robust`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn ols_multi_robust(
+    y: &[f64],
+    x: &[f64],
+    n_features: usize,
+    cov_type: &str,
+    hac_lags: Option<usize>,
+) -> Result<OlsResult, Error> {
+    let cov = parse_cov_type(cov_type, hac_lags)?;
+    fit(y, feature_columns(y, x, n_features)?, cov)
+}
+
+fn parse_cov_type(cov_type: &str, hac_lags: Option<usize>) -> Result<CovarianceType, Error> {
+    Ok(match CovarianceType::parse(cov_type)? {
+        CovarianceType::Hac(_) => CovarianceType::Hac(hac_lags),
+        cov => cov,
+    })
+}
+
+// Row-major `x` with `n_features` values per observation split into one column per feature
+fn feature_columns(y: &[f64], x: &[f64], n_features: usize) -> Result<Vec<Vec<f64>>, Error> {
+    if n_features == 0 {
+        return Err(Error::new("n_features must be positive"));
+    }
+    if x.len() != y.len() * n_features {
+        return Err(Error::new(&format!(
+            "x has {} values but y.len() * n_features is {}",
+            x.len(),
+            y.len() * n_features
+        )));
+    }
+    Ok((0..n_features)
+        .map(|j| x.iter().skip(j).step_by(n_features).copied().collect())
+        .collect())
+}
+
+fn fit(y: &[f64], mut columns: Vec<Vec<f64>>, cov: CovarianceType) -> Result<OlsResult, Error> {
+    if y.iter()
+        .chain(columns.iter().flatten())
+        .any(|v| !v.is_finite())
+    {
+        return Err(Error::new("inputs must contain only finite values"));
+    }
+    let k = columns.len();
+    columns.push(vec![1.0; y.len()]);
+    let ols = least_squares(&columns, y).ok_or_else(|| {
+        Error::new("regression failed: too few observations or collinear regressors")
+    })?;
+
+    let n = ols.nobs as f64;
+    let mean_y = nanmean(y, NanPolicy::Propagate);
+    let sst: f64 = y.iter().map(|v| (v - mean_y) * (v - mean_y)).sum();
+    let (r_squared, adj_r_squared) = if sst > 0.0 {
+        let r2 = 1.0 - ols.ssr / sst;
+        (r2, 1.0 - (1.0 - r2) * (n - 1.0) / (n - (k + 1) as f64))
+    } else {
+        (f64::NAN, f64::NAN)
+    };
+
+    // Select the Newey-West truncation once so that every coefficient uses the same one
+    let cov = match cov {
+        CovarianceType::Hac(None) => CovarianceType::Hac(Some(ols.newey_west_lags(&columns))),
+        cov => cov,
+    };
+    let std_errors: Vec<f64> = (0..=k)
+        .map(|j| ols.std_error_with(&columns, j, cov))
+        .collect();
+    let t_stats: Vec<f64> = ols
+        .coefficients
+        .iter()
+        .zip(&std_errors)
+        .map(|(b, se)| b / se)
+        .collect();
+
+    Ok(OlsResult {
+        beta: ols.coefficients[0],
+        intercept: ols.coefficients[k],
+        intercept_std_error: std_errors[k],
+        intercept_t_stat: t_stats[k],
+        r_squared,
+        adj_r_squared,
+        nobs: ols.nobs,
+        coefficients: ols.coefficients[..k].to_vec(),
+        std_errors: std_errors[..k].to_vec(),
+        t_stats: t_stats[..k].to_vec(),
+        residuals: ols.residuals,
+        cov_type: cov,
+    })
+}
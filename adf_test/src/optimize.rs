@@ -0,0 +1,460 @@
+// Grid search over the parameters of the z-score threshold strategy.
+//
+// Every combination of entry threshold, exit threshold and z-score look-back on
+// the grid is traded over the whole sample with `generate_signals` and
+// `backtest_with_options`, which here runs in one call instead of once per
+// combination across the wasm boundary. The z-score of each look-back is
+// computed once and shared by its thresholds, and the combinations fan out over
+// the cores with the `parallel` feature on native targets. The best combination is picked in
+// sample, so it is an optimistic estimate: combinations with fewer than
+// `min_trades` round trips are not eligible (a high Sharpe ratio from two trades
+// is luck), and the mean objective of the best cell's grid neighbours shows
+// whether it sits on a plateau or is an isolated spike.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::backtest::{backtest_with_options, BacktestOptions};
+use crate::error::Error;
+#[cfg(feature = "wasm")]
+use crate::get_property;
+use crate::json::JsonObject;
+use crate::parallel;
+use crate::signals::generate_signals;
+use crate::zscore::rolling_zscore;
+
+// Values per combination in `GridSearchResult::surface`
+const SURFACE_STRIDE: usize = 9;
+
+// Performance figure the grid search maximises
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GridObjective {
+    // Annualised Sharpe ratio ("sharpe")
+    Sharpe,
+    // Annualised Sortino ratio ("sortino")
+    Sortino,
+    // Compounded return over the sample ("total_return")
+    TotalReturn,
+}
+
+impl GridObjective {
+    pub fn parse(objective: &str) -> Result<GridObjective, Error> {
+        match objective {
+            "sharpe" => Ok(GridObjective::Sharpe),
+            "sortino" => Ok(GridObjective::Sortino),
+            "total_return" => Ok(GridObjective::TotalReturn),
+            other => Err(Error::new(&format!(
+                "unknown objective \"{}\": expected \"sharpe\", \"sortino\" or \"total_return\"",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GridObjective::Sharpe => "sharpe",
+            GridObjective::Sortino => "sortino",
+            GridObjective::TotalReturn => "total_return",
+        }
+    }
+}
+
+/// Options of `grid_search_with_options`; the default matches an empty options object.
+#[derive(Clone, Debug)]
+pub struct GridSearchOptions {
+    // Entry thresholds |z| tried
+    pub entries: Vec<f64>,
+    // Exit thresholds tried; a combination with exit >= entry is skipped
+    pub exits: Vec<f64>,
+    // Look-backs of the rolling z-score tried
+    pub windows: Vec<usize>,
+    // Rules of `generate_signals` shared by every combination
+    pub stop: Option<f64>,
+    pub max_holding: Option<usize>,
+    pub kill: Option<f64>,
+    // Round trips a combination needs to be eligible as the best
+    pub min_trades: usize,
+    pub objective: GridObjective,
+    // Bars per year of the Sharpe and Sortino ratios
+    pub periods_per_year: f64,
+    // Costs and starting capital of every backtest
+    pub backtest: BacktestOptions,
+}
+
+impl Default for GridSearchOptions {
+    fn default() -> GridSearchOptions {
+        GridSearchOptions {
+            entries: vec![1.0, 1.5, 2.0, 2.5, 3.0],
+            exits: vec![0.0, 0.5],
+            windows: vec![20],
+            stop: None,
+            max_holding: None,
+            kill: None,
+            min_trades: 5,
+            objective: GridObjective::Sharpe,
+            periods_per_year: 252.0,
+            backtest: BacktestOptions::default(),
+        }
+    }
+}
+
+impl GridSearchOptions {
+    #[cfg(feature = "wasm")]
+    pub(crate) fn from_js(options: &JsValue) -> Result<GridSearchOptions, Error> {
+        let mut parsed = GridSearchOptions::default();
+        if options.is_undefined() || options.is_null() {
+            return Ok(parsed);
+        }
+        if !options.is_object() {
+            return Err(Error::new("options must be an object"));
+        }
+        parsed.backtest = BacktestOptions::from_js(options)?;
+
+        let numbers = |key: &str| -> Result<Option<Vec<f64>>, Error> {
+            let value = get_property(options, key)?;
+            if value.is_undefined() {
+                return Ok(None);
+            }
+            let invalid = || Error::new(&format!("{} must be an array of finite numbers", key));
+            if !js_sys::Array::is_array(&value) {
+                return Err(invalid());
+            }
+            js_sys::Array::from(&value)
+                .iter()
+                .map(|item| item.as_f64().filter(|v| v.is_finite()).ok_or_else(invalid))
+                .collect::<Result<Vec<f64>, Error>>()
+                .map(Some)
+        };
+        let count = |key: &str| -> Result<Option<usize>, Error> {
+            let value = get_property(options, key)?;
+            if value.is_undefined() {
+                return Ok(None);
+            }
+            value
+                .as_f64()
+                .filter(|v| *v >= 0.0 && v.fract() == 0.0 && *v <= u32::MAX as f64)
+                .map(|v| Some(v as usize))
+                .ok_or_else(|| Error::new(&format!("{} must be a non-negative integer", key)))
+        };
+        let number = |key: &str| -> Result<Option<f64>, Error> {
+            let value = get_property(options, key)?;
+            if value.is_undefined() {
+                return Ok(None);
+            }
+            value
+                .as_f64()
+                .filter(|v| v.is_finite())
+                .map(Some)
+                .ok_or_else(|| Error::new(&format!("{} must be a finite number", key)))
+        };
+
+        if let Some(entries) = numbers("entries")? {
+            parsed.entries = entries;
+        }
+        if let Some(exits) = numbers("exits")? {
+            parsed.exits = exits;
+        }
+        if let Some(windows) = numbers("windows")? {
+            parsed.windows = windows
+                .iter()
+                .map(|w| {
+                    (*w >= 0.0 && w.fract() == 0.0 && *w <= u32::MAX as f64)
+                        .then_some(*w as usize)
+                        .ok_or_else(|| Error::new("windows must be non-negative integers"))
+                })
+                .collect::<Result<Vec<usize>, Error>>()?;
+        }
+        parsed.stop = number("stop")?;
+        parsed.max_holding = count("max_holding")?;
+        parsed.kill = number("kill")?;
+        if let Some(min_trades) = count("min_trades")? {
+            parsed.min_trades = min_trades;
+        }
+        if let Some(periods_per_year) = number("periods_per_year")? {
+            parsed.periods_per_year = periods_per_year;
+        }
+
+        let objective = get_property(options, "objective")?;
+        if !objective.is_undefined() {
+            let name = objective
+                .as_string()
+                .ok_or_else(|| Error::new("objective must be a string"))?;
+            parsed.objective = GridObjective::parse(&name)?;
+        }
+        Ok(parsed)
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct GridSearchResult {
+    // Parameters and in-sample objective of the best eligible combination; NaN when none is
+    // eligible
+    pub best_entry: f64,
+    pub best_exit: f64,
+    pub best_window: f64,
+    pub best_objective: f64,
+    // Mean objective of the eligible combinations next to the best one on the grid (one step
+    // along each axis, diagonals included); NaN when it has none
+    pub neighbour_objective: f64,
+    // Combinations evaluated (exit < entry) and those with at least `min_trades` round trips
+    pub evaluated: usize,
+    pub eligible: usize,
+    best: Option<usize>,
+    surface: Vec<f64>,
+    objective: GridObjective,
+    shape: [usize; 3],
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl GridSearchResult {
+    // Packed (entry, exit, window, objective, sharpe, total_return, max_drawdown, trade_count,
+    // eligible) rows, one per combination with the windows outermost and the exits innermost;
+    // a skipped combination (exit >= entry) has NaN metrics
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn surface(&self) -> Vec<f64> {
+        self.surface.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn combination_count(&self) -> usize {
+        self.surface.len() / SURFACE_STRIDE
+    }
+
+    // Row of the best combination in `surface`, if any is eligible
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn best(&self) -> Option<usize> {
+        self.best
+    }
+
+    // Objective the combinations were ranked by
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn objective(&self) -> String {
+        self.objective.as_str().to_string()
+    }
+
+    // Grid sizes along the windows, entries and exits
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn shape(&self) -> Vec<u32> {
+        self.shape.iter().map(|n| *n as u32).collect()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("best_entry", self.best_entry)
+            .field("best_exit", self.best_exit)
+            .field("best_window", self.best_window)
+            .field("best_objective", self.best_objective)
+            .field("neighbour_objective", self.neighbour_objective)
+            .field("evaluated", self.evaluated)
+            .field("eligible", self.eligible)
+            .field("best", self.best)
+            .field("objective", self.objective.as_str())
+            .field("shape", self.shape)
+            .field("surface", &self.surface)
+            .finish()
+    }
+}
+
+/// Grid search of the z-score pairs strategy on price series `prices_a` and `prices_b` with
+/// hedge ratio `hedge_ratio` (one value, or one per bar as in `backtest`), returning the
+/// in-sample performance of every combination and the best one.
+///
+/// Each combination of an entry threshold from `entries`, an exit threshold from `exits` and a
+/// look-back from `windows` trades `generate_signals(z, entry, exit, stop, max_holding, kill)` on
+/// the rolling z-score of the spread a - β·b over the look-back, simulated with `backtest`.
+/// Combinations with exit >= entry are skipped. The best combination maximises `objective`
+/// among those with at least `min_trades` round trips; the ties go to the earlier row.
+///
+/// `options` is an optional object with `entries` (default [1, 1.5, 2, 2.5, 3]), `exits`
+/// (default [0, 0.5]), `windows` (default [20]), `stop`, `max_holding`, `kill`, `min_trades`
+/// (default 5), `objective` ("sharpe" (default), "sortino" or "total_return"),
+/// `periods_per_year` (default 252) and the options of `backtest`. The best parameters are fitted
+/// to the sample they are scored on; `walk_forward` measures them out of sample.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn grid_search(
+    prices_a: &[f64],
+    prices_b: &[f64],
+    hedge_ratio: &[f64],
+    options: JsValue,
+) -> Result<GridSearchResult, Error> {
+    let options = GridSearchOptions::from_js(&options)?;
+    grid_search_with_options(prices_a, prices_b, hedge_ratio, &options)
+}
+
+/// `grid_search` for native callers, with the options as a struct.
+pub fn grid_search_with_options(
+    prices_a: &[f64],
+    prices_b: &[f64],
+    hedge_ratio: &[f64],
+    options: &GridSearchOptions,
+) -> Result<GridSearchResult, Error> {
+    let n = prices_a.len();
+    if prices_b.len() != n {
+        return Err(Error::new(
+            "prices_a and prices_b must have the same length",
+        ));
+    }
+    if hedge_ratio.len() != 1 && hedge_ratio.len() != n {
+        return Err(Error::new(
+            "hedge_ratio must hold one value or one value per bar",
+        ));
+    }
+    if options.entries.is_empty() || options.exits.is_empty() || options.windows.is_empty() {
+        return Err(Error::new("entries, exits and windows must not be empty"));
+    }
+    if options
+        .entries
+        .iter()
+        .chain(&options.exits)
+        .any(|v| !v.is_finite())
+    {
+        return Err(Error::new("entries and exits must be finite"));
+    }
+    if options.windows.iter().any(|w| *w < 2 || *w > n) {
+        return Err(Error::new(
+            "windows must be at least 2 and at most the series length",
+        ));
+    }
+    if !(options.periods_per_year > 0.0 && options.periods_per_year.is_finite()) {
+        return Err(Error::new("periods_per_year must be positive"));
+    }
+
+    let beta_at = |t: usize| hedge_ratio[if hedge_ratio.len() == 1 { 0 } else { t }];
+    let spread: Vec<f64> = (0..n)
+        .map(|t| prices_a[t] - beta_at(t) * prices_b[t])
+        .collect();
+    let zscores: Vec<Vec<f64>> = options
+        .windows
+        .iter()
+        .map(|window| rolling_zscore(&spread, *window))
+        .collect();
+
+    let shape = [
+        options.windows.len(),
+        options.entries.len(),
+        options.exits.len(),
+    ];
+    let combinations: Vec<[usize; 3]> = (0..shape[0])
+        .flat_map(|w| (0..shape[1]).flat_map(move |e| (0..shape[2]).map(move |x| [w, e, x])))
+        .collect();
+    let rows = parallel::map(&combinations, |&[w, e, x]| {
+        evaluate(
+            prices_a,
+            prices_b,
+            hedge_ratio,
+            &zscores[w],
+            options.entries[e],
+            options.exits[x],
+            options,
+        )
+    });
+
+    let mut surface = Vec::with_capacity(combinations.len() * SURFACE_STRIDE);
+    let mut objectives = Vec::with_capacity(combinations.len());
+    let mut evaluated = 0;
+    for (&[w, e, x], row) in combinations.iter().zip(rows) {
+        let metrics = row?;
+        let [objective, sharpe, total_return, max_drawdown, trades] =
+            metrics.unwrap_or([f64::NAN; 5]);
+        evaluated += usize::from(metrics.is_some());
+        let eligible = objective.is_finite() && trades >= options.min_trades as f64;
+        objectives.push(if eligible { objective } else { f64::NAN });
+        surface.extend([
+            options.entries[e],
+            options.exits[x],
+            options.windows[w] as f64,
+            objective,
+            sharpe,
+            total_return,
+            max_drawdown,
+            trades,
+            if eligible { 1.0 } else { 0.0 },
+        ]);
+    }
+
+    let best = objectives
+        .iter()
+        .enumerate()
+        .filter(|(_, o)| !o.is_nan())
+        .fold(None, |best: Option<(usize, f64)>, (i, &o)| match best {
+            Some((_, top)) if top >= o => best,
+            _ => Some((i, o)),
+        })
+        .map(|(i, _)| i);
+    let neighbour_objective = best.map_or(f64::NAN, |i| {
+        let [w, e, x] = combinations[i];
+        let near = |a: usize, b: usize| a.abs_diff(b) <= 1;
+        let (sum, count) = combinations
+            .iter()
+            .zip(&objectives)
+            .enumerate()
+            .filter(|(j, ([cw, ce, cx], o))| {
+                *j != i && near(*cw, w) && near(*ce, e) && near(*cx, x) && !o.is_nan()
+            })
+            .fold((0.0, 0usize), |(sum, count), (_, (_, o))| {
+                (sum + o, count + 1)
+            });
+        if count > 0 {
+            sum / count as f64
+        } else {
+            f64::NAN
+        }
+    });
+    let at = |column: usize| best.map_or(f64::NAN, |i| surface[i * SURFACE_STRIDE + column]);
+
+    Ok(GridSearchResult {
+        best_entry: at(0),
+        best_exit: at(1),
+        best_window: at(2),
+        best_objective: at(3),
+        neighbour_objective,
+        evaluated,
+        eligible: objectives.iter().filter(|o| !o.is_nan()).count(),
+        best,
+        objective: options.objective,
+        shape,
+        surface,
+    })
+}
+
+// Objective, Sharpe ratio, total return, maximum drawdown and round trips of one combination;
+// None when exit >= entry
+fn evaluate(
+    prices_a: &[f64],
+    prices_b: &[f64],
+    hedge_ratio: &[f64],
+    zscore: &[f64],
+    entry: f64,
+    exit: f64,
+    options: &GridSearchOptions,
+) -> Result<Option<[f64; 5]>, Error> {
+    if exit >= entry {
+        return Ok(None);
+    }
+    let signals = generate_signals(
+        zscore,
+        entry,
+        exit,
+        options.stop,
+        options.max_holding,
+        options.kill,
+    )?
+    .positions();
+    let result =
+        backtest_with_options(prices_a, prices_b, hedge_ratio, &signals, &options.backtest)?;
+    let metrics = result.performance(options.periods_per_year)?;
+    let objective = match options.objective {
+        GridObjective::Sharpe => metrics.sharpe,
+        GridObjective::Sortino => metrics.sortino,
+        GridObjective::TotalReturn => result.total_return,
+    };
+    Ok(Some([
+        objective,
+        metrics.sharpe,
+        result.total_return,
+        metrics.max_drawdown,
+        result.trade_count() as f64,
+    ]))
+}
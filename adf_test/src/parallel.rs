@@ -0,0 +1,48 @@
+// Fan-out of independent work items (one series of a batch, one pair of a screen)
+// across threads.
+//
+// With the `parallel` feature on a native target the items are split into one
+// contiguous chunk per available core and mapped on scoped threads. Everywhere
+// else, including every wasm32 build, they are mapped in order on the calling
+// thread. Threading is deliberately native-only: a wasm instance only gets
+// threads from Web Workers sharing its memory, which takes a thread pool such as
+// wasm-bindgen-rayon (with its `initThreadPool` call from JS), an atomics-enabled
+// nightly build and a cross-origin-isolated page, and this crate ships none of
+// that. On wasm32 the feature therefore changes nothing. Results come back in
+// item order either way, so callers see the same output with or without it.
+
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+pub(crate) fn map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    if threads < 2 || items.len() < 2 {
+        return items.iter().map(f).collect();
+    }
+    let chunk = items.len().div_ceil(threads);
+    let f = &f;
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(chunk)
+            .map(|part| scope.spawn(move || part.iter().map(f).collect::<Vec<R>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| match handle.join() {
+                Ok(results) => results,
+                Err(panic) => std::panic::resume_unwind(panic),
+            })
+            .collect()
+    })
+}
+
+#[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+pub(crate) fn map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    F: Fn(&T) -> R,
+{
+    items.iter().map(f).collect()
+}
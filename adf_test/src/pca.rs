@@ -0,0 +1,224 @@
+// Principal component analysis of a universe of assets.
+//
+// The leading components of a cross-section of returns are the common factors
+// (the market first, then sectors or styles). What an asset does beyond them is
+// its idiosyncratic part, and baskets built to be long an asset and short its
+// factor exposure trade that part, which is where statistical arbitrage looks for
+// mean reversion. The decomposition is that of the sample covariance (or
+// correlation) matrix.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::error::Error;
+use crate::json::JsonObject;
+use crate::linalg::symmetric_eigen;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct PcaResult {
+    pub n_assets: usize,
+    pub n_obs: usize,
+    pub n_components: usize,
+    eigenvalues: Vec<f64>,
+    explained_variance_ratio: Vec<f64>,
+    loadings: Vec<f64>,
+    scores: Vec<f64>,
+    residuals: Vec<f64>,
+    means: Vec<f64>,
+    scales: Vec<f64>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl PcaResult {
+    // Variance of each retained component, in decreasing order
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn eigenvalues(&self) -> Vec<f64> {
+        self.eigenvalues.clone()
+    }
+
+    // Share of the total variance each retained component explains
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn explained_variance_ratio(&self) -> Vec<f64> {
+        self.explained_variance_ratio.clone()
+    }
+
+    // n_assets x n_components, row-major; column j is the unit-length weight vector of
+    // component j, signed so that its largest-magnitude weight is positive
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn loadings(&self) -> Vec<f64> {
+        self.loadings.clone()
+    }
+
+    // n_obs x n_components, row-major: each observation's centred (and scaled) values
+    // projected on the loadings
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn scores(&self) -> Vec<f64> {
+        self.scores.clone()
+    }
+
+    // n_obs x n_assets, row-major, in the input's units: what the retained components leave
+    // unexplained, the idiosyncratic part of each asset
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn residuals(&self) -> Vec<f64> {
+        self.residuals.clone()
+    }
+
+    // Column means subtracted before the decomposition
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn means(&self) -> Vec<f64> {
+        self.means.clone()
+    }
+
+    // Column standard deviations the data was divided by, all 1 without standardisation
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn scales(&self) -> Vec<f64> {
+        self.scales.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("n_assets", self.n_assets)
+            .field("n_obs", self.n_obs)
+            .field("n_components", self.n_components)
+            .field("eigenvalues", &self.eigenvalues)
+            .field("explained_variance_ratio", &self.explained_variance_ratio)
+            .field("loadings", &self.loadings)
+            .field("scores", &self.scores)
+            .field("residuals", &self.residuals)
+            .field("means", &self.means)
+            .field("scales", &self.scales)
+            .finish()
+    }
+}
+
+/// Principal components of `matrix`, which holds rows of `n_assets` observations, row-major
+/// (one row per time step, as in `johansen_test`), keeping the `n_components` largest.
+///
+/// Pass returns rather than prices: the components of price levels are dominated by their
+/// trends. The columns are centred, and with `standardize` also divided by their standard
+/// deviations so that the decomposition is of the correlation matrix and volatile assets do
+/// not dominate the leading components. Variances use n - 1, as numpy's `cov` and sklearn's
+/// `PCA` do.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn pca(
+    matrix: &[f64],
+    n_assets: usize,
+    n_components: usize,
+    standardize: Option<bool>,
+) -> Result<PcaResult, Error> {
+    let m = n_assets;
+    if m == 0 {
+        return Err(Error::new("n_assets must be positive"));
+    }
+    if !matrix.len().is_multiple_of(m) {
+        return Err(Error::new(&format!(
+            "matrix has {} values, not a multiple of n_assets ({})",
+            matrix.len(),
+            m
+        )));
+    }
+    if n_components == 0 || n_components > m {
+        return Err(Error::new("n_components must be between 1 and n_assets"));
+    }
+    if matrix.iter().any(|v| !v.is_finite()) {
+        return Err(Error::new("matrix must contain only finite values"));
+    }
+    let n_obs = matrix.len() / m;
+    if n_obs < 2 {
+        return Err(Error::new("at least two observations are needed"));
+    }
+
+    let nf = n_obs as f64;
+    let means: Vec<f64> = (0..m)
+        .map(|j| (0..n_obs).map(|t| matrix[t * m + j]).sum::<f64>() / nf)
+        .collect();
+    let mut centred: Vec<f64> = matrix
+        .iter()
+        .enumerate()
+        .map(|(i, v)| v - means[i % m])
+        .collect();
+    let mut scales = vec![1.0; m];
+    if standardize.unwrap_or(false) {
+        for (j, scale) in scales.iter_mut().enumerate() {
+            let variance = (0..n_obs)
+                .map(|t| centred[t * m + j] * centred[t * m + j])
+                .sum::<f64>()
+                / (nf - 1.0);
+            if variance == 0.0 {
+                return Err(Error::new(&format!("asset {} is constant", j)));
+            }
+            *scale = variance.sqrt();
+        }
+        for (i, v) in centred.iter_mut().enumerate() {
+            *v /= scales[i % m];
+        }
+    }
+
+    let mut covariance = vec![0.0; m * m];
+    for row in centred.chunks_exact(m) {
+        for i in 0..m {
+            for j in 0..=i {
+                covariance[i * m + j] += row[i] * row[j];
+            }
+        }
+    }
+    for i in 0..m {
+        for j in 0..=i {
+            covariance[i * m + j] /= nf - 1.0;
+            covariance[j * m + i] = covariance[i * m + j];
+        }
+    }
+    let total_variance: f64 = (0..m).map(|i| covariance[i * m + i]).sum();
+    if total_variance == 0.0 {
+        return Err(Error::new("every asset is constant"));
+    }
+
+    let (values, vectors) = symmetric_eigen(&covariance, m);
+    let mut order: Vec<usize> = (0..m).collect();
+    order.sort_by(|&a, &b| values[b].total_cmp(&values[a]));
+    order.truncate(n_components);
+    let k = n_components;
+    let eigenvalues: Vec<f64> = order.iter().map(|&i| values[i].max(0.0)).collect();
+    let explained_variance_ratio = eigenvalues.iter().map(|l| l / total_variance).collect();
+    let mut loadings = vec![0.0; m * k];
+    for (new, &old) in order.iter().enumerate() {
+        let column: Vec<f64> = (0..m).map(|i| vectors[i * m + old]).collect();
+        let largest = column
+            .iter()
+            .copied()
+            .max_by(|a, b| a.abs().total_cmp(&b.abs()))
+            .unwrap_or(1.0);
+        let sign = if largest < 0.0 { -1.0 } else { 1.0 };
+        for (i, weight) in column.iter().enumerate() {
+            loadings[i * k + new] = sign * weight;
+        }
+    }
+
+    let mut scores = vec![0.0; n_obs * k];
+    let mut residuals = vec![0.0; n_obs * m];
+    for (t, row) in centred.chunks_exact(m).enumerate() {
+        for c in 0..k {
+            scores[t * k + c] = (0..m).map(|i| row[i] * loadings[i * k + c]).sum();
+        }
+        for i in 0..m {
+            let explained: f64 = (0..k)
+                .map(|c| scores[t * k + c] * loadings[i * k + c])
+                .sum();
+            residuals[t * m + i] = (row[i] - explained) * scales[i];
+        }
+    }
+
+    Ok(PcaResult {
+        n_assets: m,
+        n_obs,
+        n_components: k,
+        eigenvalues,
+        explained_variance_ratio,
+        loadings,
+        scores,
+        residuals,
+        means,
+        scales,
+    })
+}
@@ -0,0 +1,328 @@
+// Phillips-Ouliaris (1990) residual-based cointegration tests.
+//
+// Engle-Granger asks whether the regression residual has a unit root. The
+// Phillips-Ouliaris variance-ratio statistic P̂u asks instead whether the residual
+// variance is small relative to the long-run variance of y given x, and P̂z,
+// built from both legs symmetrically, does not depend on which leg is the
+// dependent variable. Serial correlation is handled non-parametrically with a
+// Newey-West long-run covariance instead of lagged differences. Both statistics
+// are large under cointegration, so the tests reject in the upper tail.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::adf::Regression;
+use crate::error::Error;
+use crate::hac::autocovariance_sum;
+use crate::json::{levels, JsonObject};
+use crate::linalg::least_squares;
+use crate::pvalue::{interpolate_critical_value, lookup_sorted, PValue, PValueSource};
+use crate::CRITICAL_VALUE_KEYS;
+use crate::CRITICAL_VALUE_LEVELS;
+#[cfg(feature = "wasm")]
+use crate::{critical_values_object, set_property, CriticalValues};
+
+// 1%, 5% and 10% critical values for one regressor, simulated from 100,000 pairs of independent
+// Gaussian random walks of length 1,000 with the default lag truncation, in the setting of
+// Phillips & Ouliaris (1990) Tables I and III
+const PU_CRITICAL_NONE: [f64; 3] = [39.77, 26.41, 20.73];
+const PU_CRITICAL_CONSTANT: [f64; 3] = [48.85, 34.55, 28.17];
+const PU_CRITICAL_TREND: [f64; 3] = [65.36, 48.93, 41.58];
+const PZ_CRITICAL_NONE: [f64; 3] = [55.82, 40.79, 34.10];
+const PZ_CRITICAL_CONSTANT: [f64; 3] = [71.50, 54.96, 47.49];
+const PZ_CRITICAL_TREND: [f64; 3] = [101.12, 81.25, 72.20];
+
+// Which Phillips-Ouliaris statistic is tested
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PoTest {
+    // Variance ratio of the cointegrating regression of y on x
+    Pu,
+    // Multivariate trace statistic, invariant to the normalisation
+    Pz,
+}
+
+impl PoTest {
+    fn parse(test: &str) -> Result<PoTest, Error> {
+        match test {
+            "Pu" => Ok(PoTest::Pu),
+            "Pz" => Ok(PoTest::Pz),
+            other => Err(Error::new(&format!(
+                "unknown Phillips-Ouliaris test \"{}\": expected \"Pu\" or \"Pz\"",
+                other
+            ))),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            PoTest::Pu => "Pu",
+            PoTest::Pz => "Pz",
+        }
+    }
+
+    fn critical_values(&self, regression: Regression) -> [f64; 3] {
+        match (self, regression) {
+            (PoTest::Pu, Regression::NoConstant) => PU_CRITICAL_NONE,
+            (PoTest::Pu, Regression::Constant) => PU_CRITICAL_CONSTANT,
+            (PoTest::Pu, _) => PU_CRITICAL_TREND,
+            (PoTest::Pz, Regression::NoConstant) => PZ_CRITICAL_NONE,
+            (PoTest::Pz, Regression::Constant) => PZ_CRITICAL_CONSTANT,
+            (PoTest::Pz, _) => PZ_CRITICAL_TREND,
+        }
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(typescript_custom_section)]
+const PHILLIPS_OULIARIS_RESULT_TS: &'static str = r#"
+/** Plain-object snapshot of a `PhillipsOuliarisResult`, as returned by `PhillipsOuliarisResult.to_object()`. */
+export interface PhillipsOuliarisResultObject {
+  statistic: number;
+  p_value: number;
+  critical_values: CriticalValues;
+  is_cointegrated: boolean;
+  lags: number;
+  nobs: number;
+  test: "Pu" | "Pz";
+  regression: "n" | "c" | "ct";
+  p_value_source: "interpolated" | "clamped_first" | "clamped_last";
+}
+"#;
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "PhillipsOuliarisResultObject")]
+    pub type PhillipsOuliarisResultObject;
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct PhillipsOuliarisResult {
+    pub statistic: f64,
+    pub p_value: f64,
+    critical_values: [f64; 3],
+    // True when no cointegration is rejected at 5% (statistic above the 5% critical value)
+    pub is_cointegrated: bool,
+    // Bartlett-kernel lag truncation of the long-run covariance
+    pub lags: usize,
+    pub nobs: usize,
+    test: PoTest,
+    regression: Regression,
+    p_value_source: PValueSource,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl PhillipsOuliarisResult {
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(getter)]
+    pub fn critical_values(&self) -> CriticalValues {
+        critical_values_object(self.critical_values)
+    }
+
+    /// Every field of the result as a plain JS object (see `PhillipsOuliarisResultObject`).
+    #[cfg(feature = "wasm")]
+    pub fn to_object(&self) -> PhillipsOuliarisResultObject {
+        let object = js_sys::Object::new();
+        set_property(&object, "statistic", self.statistic);
+        set_property(&object, "p_value", self.p_value);
+        set_property(&object, "critical_values", self.critical_values());
+        set_property(&object, "is_cointegrated", self.is_cointegrated);
+        set_property(&object, "lags", self.lags as f64);
+        set_property(&object, "nobs", self.nobs as f64);
+        set_property(&object, "test", self.test());
+        set_property(&object, "regression", self.regression());
+        set_property(&object, "p_value_source", self.p_value_source());
+        object.unchecked_into()
+    }
+
+    // Critical value at any significance level between 1% and 10%, interpolated across the
+    // 1%/5%/10% values in the normal quantile of the level
+    pub fn critical_value(&self, alpha: f64) -> Result<f64, Error> {
+        interpolate_critical_value(&CRITICAL_VALUE_LEVELS, &self.critical_values, alpha)
+    }
+
+    // "Pu" or "Pz"
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn test(&self) -> String {
+        self.test.as_str().to_string()
+    }
+
+    // Deterministic terms removed from both legs: "n", "c" or "ct"
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn regression(&self) -> String {
+        self.regression.as_str().to_string()
+    }
+
+    // "interpolated" inside the tabulated 1%-10% range, otherwise "clamped_first" (p-value
+    // is at least 0.10) or "clamped_last" (p-value is at most 0.01)
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn p_value_source(&self) -> String {
+        self.p_value_source.as_str().to_string()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("statistic", self.statistic)
+            .field("p_value", self.p_value)
+            .field(
+                "critical_values",
+                levels(&CRITICAL_VALUE_KEYS, &self.critical_values),
+            )
+            .field("is_cointegrated", self.is_cointegrated)
+            .field("lags", self.lags)
+            .field("nobs", self.nobs)
+            .field("test", self.test())
+            .field("regression", self.regression())
+            .field("p_value_source", self.p_value_source())
+            .finish()
+    }
+}
+
+impl PhillipsOuliarisResult {
+    // Critical values as [1%, 5%, 10%], for native callers
+    pub fn critical_values_array(&self) -> [f64; 3] {
+        self.critical_values
+    }
+}
+
+/// Phillips-Ouliaris cointegration test of y against x: `test` "Pu" (variance ratio) or "Pz"
+/// (trace statistic, the same whichever leg is y), with deterministic terms `regression` "n",
+/// "c" or "ct" removed from both legs.
+///
+/// The long-run covariance of the VAR(1) innovations uses a Bartlett kernel with `lags` lags,
+/// by default ⌊4·(n/100)^(1/4)⌋ (urca's "short" rule). Large statistics reject the null of no
+/// cointegration; the p-value is interpolated between the 1%, 5% and 10% critical values and
+/// clamped outside them. Requiring both this and `engle_granger` to reject guards against
+/// either test's blind spots.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn phillips_ouliaris(
+    y: &[f64],
+    x: &[f64],
+    test: &str,
+    regression: &str,
+    lags: Option<usize>,
+) -> Result<PhillipsOuliarisResult, Error> {
+    let test = PoTest::parse(test)?;
+    let regression = Regression::parse(regression)?;
+    if regression == Regression::ConstantQuadraticTrend {
+        return Err(Error::new(
+            "phillips_ouliaris supports the \"n\", \"c\" and \"ct\" regressions",
+        ));
+    }
+    if y.len() != x.len() {
+        return Err(Error::new("y and x must have the same length"));
+    }
+    if y.iter().chain(x).any(|v| !v.is_finite()) {
+        return Err(Error::new("inputs must contain only finite values"));
+    }
+    let nobs = y.len();
+    if nobs < 10 {
+        return Err(Error::new("at least ten observations are needed"));
+    }
+    let lags = lags.unwrap_or_else(|| (4.0 * (nobs as f64 / 100.0).powf(0.25)) as usize);
+
+    let (pu, pz) = statistics(y, x, regression, lags).ok_or_else(|| {
+        Error::new(
+            "Phillips-Ouliaris regressions failed: a leg is constant or the legs are collinear",
+        )
+    })?;
+    let statistic = match test {
+        PoTest::Pu => pu,
+        PoTest::Pz => pz,
+    };
+    if !statistic.is_finite() {
+        return Err(Error::new(
+            "Phillips-Ouliaris statistic is undefined for these series",
+        ));
+    }
+
+    let critical_values = test.critical_values(regression);
+    // Rows in increasing statistic: 10%, 5%, 1%
+    let PValue {
+        value: p_value,
+        source: p_value_source,
+    } = lookup_sorted(
+        critical_values.len(),
+        |i| {
+            let j = critical_values.len() - 1 - i;
+            (critical_values[j], CRITICAL_VALUE_LEVELS[j])
+        },
+        statistic,
+    );
+    Ok(PhillipsOuliarisResult {
+        statistic,
+        p_value,
+        critical_values,
+        is_cointegrated: statistic > critical_values[1],
+        lags,
+        nobs,
+        test,
+        regression,
+        p_value_source,
+    })
+}
+
+// (P̂u, P̂z) of y against x after removing the deterministic terms from both legs
+fn statistics(y: &[f64], x: &[f64], regression: Regression, lags: usize) -> Option<(f64, f64)> {
+    let n = y.len();
+    let y = detrend(y, regression)?;
+    let x = detrend(x, regression)?;
+
+    // P̂u: residual variance of the cointegrating regression against ω̂_11·2
+    let slope =
+        y.iter().zip(&x).map(|(a, b)| a * b).sum::<f64>() / x.iter().map(|b| b * b).sum::<f64>();
+    let residual_variance = y
+        .iter()
+        .zip(&x)
+        .map(|(a, b)| (a - slope * b) * (a - slope * b))
+        .sum::<f64>()
+        / n as f64;
+
+    // Innovations of the VAR(1) z_t = Π z_{t-1} + ξ_t, one regression per leg
+    let lagged = vec![y[..n - 1].to_vec(), x[..n - 1].to_vec()];
+    let xi_y = least_squares(&lagged, &y[1..])?.residuals;
+    let xi_x = least_squares(&lagged, &x[1..])?.residuals;
+    let omega = long_run_covariance(&xi_y, &xi_x, lags);
+    let omega_11_2 = omega[0] - omega[1] * omega[1] / omega[2];
+    let pu = n as f64 * omega_11_2 / residual_variance;
+
+    // P̂z = n·tr(Ω̂ M⁻¹) with M the second moments of z_t
+    let m_yy = y.iter().map(|a| a * a).sum::<f64>() / n as f64;
+    let m_xy = y.iter().zip(&x).map(|(a, b)| a * b).sum::<f64>() / n as f64;
+    let m_xx = x.iter().map(|b| b * b).sum::<f64>() / n as f64;
+    let det = m_yy * m_xx - m_xy * m_xy;
+    let trace = (omega[0] * m_xx - 2.0 * omega[1] * m_xy + omega[2] * m_yy) / det;
+    let pz = n as f64 * trace;
+    Some((pu, pz))
+}
+
+// Newey-West long-run covariance [ω_yy, ω_yx, ω_xx] of two innovation series
+fn long_run_covariance(a: &[f64], b: &[f64], lags: usize) -> [f64; 3] {
+    let n = a.len();
+    let cross = |u: &[f64], v: &[f64], lag: usize| -> f64 {
+        u[lag..].iter().zip(v).map(|(p, q)| p * q).sum::<f64>()
+    };
+    let mut omega = [
+        autocovariance_sum(a, 0),
+        cross(a, b, 0),
+        autocovariance_sum(b, 0),
+    ];
+    for lag in 1..=lags.min(n - 1) {
+        let weight = 1.0 - lag as f64 / (lags as f64 + 1.0);
+        omega[0] += 2.0 * weight * autocovariance_sum(a, lag);
+        omega[1] += weight * (cross(a, b, lag) + cross(b, a, lag));
+        omega[2] += 2.0 * weight * autocovariance_sum(b, lag);
+    }
+    omega.map(|v| v / n as f64)
+}
+
+// Series minus its OLS fit on the deterministic terms of `regression`
+fn detrend(series: &[f64], regression: Regression) -> Option<Vec<f64>> {
+    let mut columns = Vec::new();
+    regression.push_columns(&mut columns, series.len());
+    if columns.is_empty() {
+        return Some(series.to_vec());
+    }
+    least_squares(&columns, series).map(|ols| ols.residuals)
+}
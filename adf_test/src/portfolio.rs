@@ -0,0 +1,560 @@
+// Portfolio backtest of several pairs sharing one account.
+//
+// Each pair trades as in `backtest`, but the capital a trade may deploy is a
+// weight of the common equity rather than the whole of it, and every pair's
+// P&L and costs land in the same account. The weights come from the trailing
+// returns of unit positions in each spread: equal, inversely proportional to
+// their volatilities, or the equal-risk-contribution (risk parity) weights of
+// their covariance matrix, which also accounts for spreads that move together.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::backtest::{
+    performance_metrics, trailing_volatility, BacktestOptions, PerformanceMetrics,
+};
+use crate::error::Error;
+#[cfg(feature = "wasm")]
+use crate::get_property;
+use crate::json::JsonObject;
+#[cfg(feature = "wasm")]
+use crate::json::{parse_payload, payload_count, payload_series};
+use crate::risk::{var_es, ValueAtRisk};
+
+const RISK_PARITY_ITERATIONS: usize = 200;
+
+// How the capital is split between the pairs
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Allocation {
+    // 1/k each ("equal")
+    Equal,
+    // Proportional to 1/σ of each spread's unit returns ("inverse_volatility")
+    InverseVolatility,
+    // Equal contributions to the variance of the weighted unit returns ("risk_parity")
+    RiskParity,
+}
+
+impl Allocation {
+    pub fn parse(allocation: &str) -> Result<Allocation, Error> {
+        match allocation {
+            "equal" => Ok(Allocation::Equal),
+            "inverse_volatility" => Ok(Allocation::InverseVolatility),
+            "risk_parity" => Ok(Allocation::RiskParity),
+            other => Err(Error::new(&format!(
+                "unknown allocation \"{}\": expected \"equal\", \"inverse_volatility\" or \
+                 \"risk_parity\"",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Allocation::Equal => "equal",
+            Allocation::InverseVolatility => "inverse_volatility",
+            Allocation::RiskParity => "risk_parity",
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct PortfolioResult {
+    pub n_pairs: usize,
+    pub n_obs: usize,
+    pub final_equity: f64,
+    pub total_return: f64,
+    pub total_costs: f64,
+    capital: f64,
+    allocation: Allocation,
+    equity: Vec<f64>,
+    returns: Vec<f64>,
+    gross_exposure: Vec<f64>,
+    net_exposure: Vec<f64>,
+    weights: Vec<f64>,
+    pair_pnl: Vec<f64>,
+    pair_costs: Vec<f64>,
+    pair_trade_counts: Vec<u32>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl PortfolioResult {
+    // Portfolio equity at each bar's close, after costs
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn equity(&self) -> Vec<f64> {
+        self.equity.clone()
+    }
+
+    // Simple per-bar returns of the portfolio equity; the first is relative to the starting
+    // capital
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn returns(&self) -> Vec<f64> {
+        self.returns.clone()
+    }
+
+    // Sum of |notional| of every open leg at each bar's close, as a multiple of the equity
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn gross_exposure(&self) -> Vec<f64> {
+        self.gross_exposure.clone()
+    }
+
+    // Sum of the signed leg notionals at each bar's close, as a multiple of the equity: what the
+    // pairs' legs leave of directional exposure once netted against each other
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn net_exposure(&self) -> Vec<f64> {
+        self.net_exposure.clone()
+    }
+
+    // n_obs x n_pairs, row-major: the allocation a pair opening a trade on each bar receives
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn weights(&self) -> Vec<f64> {
+        self.weights.clone()
+    }
+
+    // n_obs x n_pairs, row-major: each pair's cumulative P&L net of its costs, in the units of
+    // the equity; a row sums to the equity's gain from the starting capital
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn pair_pnl(&self) -> Vec<f64> {
+        self.pair_pnl.clone()
+    }
+
+    // Each pair's final P&L over the starting capital, which sums to total_return
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn pair_contributions(&self) -> Vec<f64> {
+        let last = self.pair_pnl.len().saturating_sub(self.n_pairs);
+        self.pair_pnl[last..]
+            .iter()
+            .map(|p| p / self.capital)
+            .collect()
+    }
+
+    // Costs paid by each pair
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn pair_costs(&self) -> Vec<f64> {
+        self.pair_costs.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn pair_trade_counts(&self) -> Vec<u32> {
+        self.pair_trade_counts.clone()
+    }
+
+    // "equal", "inverse_volatility" or "risk_parity"
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn allocation(&self) -> String {
+        self.allocation.as_str().to_string()
+    }
+
+    /// `performance_metrics` of the portfolio's per-bar returns.
+    pub fn performance(&self, periods_per_year: f64) -> Result<PerformanceMetrics, Error> {
+        performance_metrics(&self.returns, periods_per_year)
+    }
+
+    /// `var_es` of the portfolio's per-bar returns.
+    pub fn var_es(&self, confidence: f64, horizon: usize) -> Result<ValueAtRisk, Error> {
+        var_es(&self.returns, confidence, horizon)
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("n_pairs", self.n_pairs)
+            .field("n_obs", self.n_obs)
+            .field("final_equity", self.final_equity)
+            .field("total_return", self.total_return)
+            .field("total_costs", self.total_costs)
+            .field("allocation", self.allocation())
+            .field("equity", &self.equity)
+            .field("returns", &self.returns)
+            .field("gross_exposure", &self.gross_exposure)
+            .field("net_exposure", &self.net_exposure)
+            .field("weights", &self.weights)
+            .field("pair_pnl", &self.pair_pnl)
+            .field("pair_contributions", self.pair_contributions())
+            .field("pair_costs", &self.pair_costs)
+            .field("pair_trade_counts", &self.pair_trade_counts)
+            .finish()
+    }
+}
+
+/// Options of `portfolio_backtest_with_options`; the default matches an empty options object.
+#[derive(Clone, Copy, Debug)]
+pub struct PortfolioOptions {
+    pub allocation: Allocation,
+    // Trailing unit returns the volatilities and covariances are estimated from
+    pub window: usize,
+    // Costs and starting capital, as for `backtest`
+    pub backtest: BacktestOptions,
+}
+
+impl Default for PortfolioOptions {
+    fn default() -> PortfolioOptions {
+        PortfolioOptions {
+            allocation: Allocation::Equal,
+            window: 60,
+            backtest: BacktestOptions::default(),
+        }
+    }
+}
+
+impl PortfolioOptions {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Result<PortfolioOptions, Error> {
+        let mut parsed = PortfolioOptions::default();
+        if options.is_undefined() || options.is_null() {
+            return Ok(parsed);
+        }
+        if !options.is_object() {
+            return Err(Error::new("options must be an object"));
+        }
+        parsed.backtest = BacktestOptions::from_js(options)?;
+
+        let allocation = get_property(options, "allocation")?;
+        if !allocation.is_undefined() {
+            let allocation = allocation
+                .as_string()
+                .ok_or_else(|| Error::new("allocation must be a string"))?;
+            parsed.allocation = Allocation::parse(&allocation)?;
+        }
+
+        let window = get_property(options, "window")?;
+        if !window.is_undefined() {
+            parsed.window = window
+                .as_f64()
+                .filter(|v| *v >= 2.0 && v.fract() == 0.0 && *v <= u32::MAX as f64)
+                .map(|v| v as usize)
+                .ok_or_else(|| Error::new("window must be an integer of at least 2"))?;
+        }
+        Ok(parsed)
+    }
+}
+
+/// Backtest `n_pairs` pairs together on one account.
+///
+/// `prices_a`, `prices_b` and `signals` hold one row of `n_pairs` values per bar, row-major (as
+/// `pca` takes its matrix): column i is the A leg, the B leg and the signal of pair i, with the
+/// conventions of `backtest`. `hedge_ratios` holds one static β per pair or a full row per bar.
+///
+/// A trade opening on a bar deploys the pair's weight on that bar times the portfolio equity
+/// (times the signal's magnitude) as gross exposure, so the pairs compete for the same capital
+/// instead of each trading all of it. With `allocation` "equal" every weight is 1/n_pairs; with
+/// "inverse_volatility" and "risk_parity" the weights come from the trailing `window` returns of
+/// a unit position in each spread, (Δa - β·Δb) / (a + |β|·b), up to and including the bar, and
+/// until `window` returns exist the weights are equal. Risk parity solves for equal risk
+/// contributions with the covariances, which down-weights spreads that move together; inverse
+/// volatility ignores them. Costs are charged per leg as in `backtest`.
+///
+/// `options` is an optional object with `allocation` (default "equal"), `window` (60) and the
+/// options of `backtest`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn portfolio_backtest(
+    prices_a: &[f64],
+    prices_b: &[f64],
+    hedge_ratios: &[f64],
+    signals: &[f64],
+    n_pairs: usize,
+    options: JsValue,
+) -> Result<PortfolioResult, Error> {
+    let options = PortfolioOptions::from_js(&options)?;
+    portfolio_backtest_with_options(prices_a, prices_b, hedge_ratios, signals, n_pairs, &options)
+}
+
+/// `portfolio_backtest` driven by JSON text: `payload` holds `prices_a`, `prices_b`,
+/// `hedge_ratios` and `signals` as the flat arrays `portfolio_backtest` takes, `n_pairs`, and
+/// `options`; the result is returned as `PortfolioResult.to_json()`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn portfolio_backtest_from_json(payload: &str) -> Result<String, Error> {
+    let payload = parse_payload(payload)?;
+    let prices_a = payload_series(&payload, "prices_a")?;
+    let prices_b = payload_series(&payload, "prices_b")?;
+    let hedge_ratios = payload_series(&payload, "hedge_ratios")?;
+    let signals = payload_series(&payload, "signals")?;
+    let n_pairs = payload_count(&payload, "n_pairs")?;
+    let options = PortfolioOptions::from_js(&get_property(&payload, "options")?)?;
+    let result = portfolio_backtest_with_options(
+        &prices_a,
+        &prices_b,
+        &hedge_ratios,
+        &signals,
+        n_pairs,
+        &options,
+    )?;
+    Ok(result.to_json())
+}
+
+/// `portfolio_backtest` for native callers, with the options as a struct.
+pub fn portfolio_backtest_with_options(
+    prices_a: &[f64],
+    prices_b: &[f64],
+    hedge_ratios: &[f64],
+    signals: &[f64],
+    n_pairs: usize,
+    options: &PortfolioOptions,
+) -> Result<PortfolioResult, Error> {
+    let costs = &options.backtest;
+    let non_negative = |v: f64| v >= 0.0 && v.is_finite();
+    if !(non_negative(costs.cost_bps) && non_negative(costs.fee) && non_negative(costs.slippage)) {
+        return Err(Error::new(
+            "cost_bps, fee and slippage must be non-negative numbers",
+        ));
+    }
+    if costs.slippage_window < 2 || options.window < 2 {
+        return Err(Error::new("slippage_window and window must be at least 2"));
+    }
+    if !(costs.capital > 0.0 && costs.capital.is_finite()) {
+        return Err(Error::new("capital must be a positive number"));
+    }
+    if costs.fill_lag() != 0 {
+        return Err(Error::new(
+            "the portfolio backtest fills at the signal bar's close: fill and delay are not supported",
+        ));
+    }
+    let k = n_pairs;
+    if k == 0 {
+        return Err(Error::new("n_pairs must be positive"));
+    }
+    let len = prices_a.len();
+    if !len.is_multiple_of(k) {
+        return Err(Error::new(&format!(
+            "prices_a has {} values, not a multiple of n_pairs ({})",
+            len, k
+        )));
+    }
+    if prices_b.len() != len || signals.len() != len {
+        return Err(Error::new(
+            "prices_a, prices_b and signals must have the same length",
+        ));
+    }
+    let n = len / k;
+    if hedge_ratios.len() != k && hedge_ratios.len() != len {
+        return Err(Error::new(
+            "hedge_ratios must hold one value per pair or one row per bar",
+        ));
+    }
+    if prices_a
+        .iter()
+        .chain(prices_b)
+        .any(|p| !(*p > 0.0 && p.is_finite()))
+    {
+        return Err(Error::new("prices must be positive and finite"));
+    }
+    if hedge_ratios.iter().chain(signals).any(|v| !v.is_finite()) {
+        return Err(Error::new(
+            "hedge_ratios and signals must contain only finite values",
+        ));
+    }
+
+    let beta_at = |t: usize, i: usize| {
+        hedge_ratios[if hedge_ratios.len() == k {
+            i
+        } else {
+            t * k + i
+        }]
+    };
+    let column =
+        |matrix: &[f64], i: usize| -> Vec<f64> { (0..n).map(|t| matrix[t * k + i]).collect() };
+    let (volatility_a, volatility_b): (Vec<Vec<f64>>, Vec<Vec<f64>>) = if costs.slippage > 0.0 {
+        (0..k)
+            .map(|i| {
+                (
+                    trailing_volatility(&column(prices_a, i), costs.slippage_window),
+                    trailing_volatility(&column(prices_b, i), costs.slippage_window),
+                )
+            })
+            .unzip()
+    } else {
+        (vec![vec![0.0; n]; k], vec![vec![0.0; n]; k])
+    };
+    // Return at bar t of a unit position in pair i opened at bar t - 1, n x k row-major with a
+    // zero first row
+    let mut unit_returns = vec![0.0; len];
+    for t in 1..n {
+        for i in 0..k {
+            let (a0, a1) = (prices_a[(t - 1) * k + i], prices_a[t * k + i]);
+            let (b0, b1) = (prices_b[(t - 1) * k + i], prices_b[t * k + i]);
+            let beta = beta_at(t - 1, i);
+            unit_returns[t * k + i] = (a1 - a0 - beta * (b1 - b0)) / (a0 + beta.abs() * b0);
+        }
+    }
+
+    let cost_rate = costs.cost_bps / 10_000.0;
+    let mut equity = costs.capital;
+    let mut total_costs = 0.0;
+    let mut equity_curve = Vec::with_capacity(n);
+    let mut returns = Vec::with_capacity(n);
+    let mut gross_exposure = Vec::with_capacity(n);
+    let mut net_exposure = Vec::with_capacity(n);
+    let mut weights = Vec::with_capacity(len);
+    let mut pair_pnl = Vec::with_capacity(len);
+    let mut cumulative_pnl = vec![0.0; k];
+    let mut pair_costs = vec![0.0; k];
+    let mut pair_trade_counts = vec![0u32; k];
+    // Open trade of each pair: direction and leg units
+    let mut direction = vec![0.0; k];
+    let mut units = vec![(0.0, 0.0); k];
+
+    for t in 0..n {
+        let previous = equity;
+        if t > 0 {
+            for i in 0..k {
+                let (units_a, units_b) = units[i];
+                let pnl = units_a * (prices_a[t * k + i] - prices_a[(t - 1) * k + i])
+                    + units_b * (prices_b[t * k + i] - prices_b[(t - 1) * k + i]);
+                cumulative_pnl[i] += pnl;
+                equity += pnl;
+            }
+        }
+
+        let weight = if t >= options.window {
+            allocation_weights(
+                options.allocation,
+                &unit_returns[(t + 1 - options.window) * k..(t + 1) * k],
+                k,
+            )
+        } else {
+            vec![1.0 / k as f64; k]
+        };
+
+        // Closes first, so that the capital they release is in the equity the entries size from
+        let cost = |i: usize, (ua, ub): (f64, f64)| {
+            let (pa, pb) = (prices_a[t * k + i], prices_b[t * k + i]);
+            let (notional_a, notional_b) = (ua.abs() * pa, ub.abs() * pb);
+            costs.fee
+                + cost_rate * (notional_a + notional_b)
+                + costs.slippage
+                    * (volatility_a[i][t] * notional_a + volatility_b[i][t] * notional_b)
+        };
+        let target = |i: usize| {
+            let signal = signals[t * k + i];
+            if signal == 0.0 {
+                0.0
+            } else {
+                signal.signum()
+            }
+        };
+        for i in 0..k {
+            if direction[i] != 0.0 && target(i) != direction[i] {
+                let paid = cost(i, units[i]);
+                equity -= paid;
+                total_costs += paid;
+                pair_costs[i] += paid;
+                cumulative_pnl[i] -= paid;
+                direction[i] = 0.0;
+                units[i] = (0.0, 0.0);
+            }
+        }
+        let sizing_equity = equity;
+        for i in 0..k {
+            // A wiped-out account cannot open new trades
+            if direction[i] == 0.0 && target(i) != 0.0 && sizing_equity > 0.0 {
+                let (pa, pb) = (prices_a[t * k + i], prices_b[t * k + i]);
+                let beta = beta_at(t, i);
+                let units_a =
+                    signals[t * k + i] * weight[i] * sizing_equity / (pa + beta.abs() * pb);
+                direction[i] = target(i);
+                units[i] = (units_a, -beta * units_a);
+                let paid = cost(i, units[i]);
+                equity -= paid;
+                total_costs += paid;
+                pair_costs[i] += paid;
+                cumulative_pnl[i] -= paid;
+                pair_trade_counts[i] += 1;
+            }
+        }
+
+        let (mut gross, mut net) = (0.0, 0.0);
+        for (i, &(units_a, units_b)) in units.iter().enumerate() {
+            let value_a = units_a * prices_a[t * k + i];
+            let value_b = units_b * prices_b[t * k + i];
+            gross += value_a.abs() + value_b.abs();
+            net += value_a + value_b;
+        }
+        gross_exposure.push(gross / equity);
+        net_exposure.push(net / equity);
+        equity_curve.push(equity);
+        returns.push(equity / previous - 1.0);
+        weights.extend_from_slice(&weight);
+        pair_pnl.extend_from_slice(&cumulative_pnl);
+    }
+
+    Ok(PortfolioResult {
+        n_pairs: k,
+        n_obs: n,
+        final_equity: equity,
+        total_return: equity / costs.capital - 1.0,
+        total_costs,
+        capital: costs.capital,
+        allocation: options.allocation,
+        equity: equity_curve,
+        returns,
+        gross_exposure,
+        net_exposure,
+        weights,
+        pair_pnl,
+        pair_costs,
+        pair_trade_counts,
+    })
+}
+
+// Weights summing to 1 from `returns`, rows of k unit returns. A spread with no variance in the
+// window gets no weight unless every spread is flat, when the weights are equal
+fn allocation_weights(allocation: Allocation, returns: &[f64], k: usize) -> Vec<f64> {
+    let equal = vec![1.0 / k as f64; k];
+    if allocation == Allocation::Equal {
+        return equal;
+    }
+    let rows = returns.len() / k;
+    let means: Vec<f64> = (0..k)
+        .map(|i| (0..rows).map(|t| returns[t * k + i]).sum::<f64>() / rows as f64)
+        .collect();
+    let mut covariance = vec![0.0; k * k];
+    for row in returns.chunks_exact(k) {
+        for i in 0..k {
+            for j in 0..k {
+                covariance[i * k + j] += (row[i] - means[i]) * (row[j] - means[j]);
+            }
+        }
+    }
+    for c in covariance.iter_mut() {
+        *c /= (rows - 1) as f64;
+    }
+    let live: Vec<bool> = (0..k).map(|i| covariance[i * k + i] > 0.0).collect();
+    if !live.contains(&true) {
+        return equal;
+    }
+
+    let mut weights: Vec<f64> = (0..k)
+        .map(|i| {
+            if live[i] {
+                1.0 / covariance[i * k + i].sqrt()
+            } else {
+                0.0
+            }
+        })
+        .collect();
+    if allocation == Allocation::RiskParity {
+        // Cyclical coordinate descent on Σw·(Σw)_i - 1/k = 0 (Griveau-Billion, Richard &
+        // Roncalli, 2013), from the inverse-volatility weights
+        for _ in 0..RISK_PARITY_ITERATIONS {
+            let mut change: f64 = 0.0;
+            for i in (0..k).filter(|&i| live[i]) {
+                let variance = covariance[i * k + i];
+                let cross: f64 = (0..k)
+                    .filter(|&j| j != i)
+                    .map(|j| covariance[i * k + j] * weights[j])
+                    .sum();
+                let budget = 1.0 / k as f64;
+                let updated =
+                    (-cross + (cross * cross + 4.0 * variance * budget).sqrt()) / (2.0 * variance);
+                change = change.max((updated - weights[i]).abs() / updated);
+                weights[i] = updated;
+            }
+            if change < 1e-10 {
+                break;
+            }
+        }
+    }
+    let total: f64 = weights.iter().sum();
+    weights.iter().map(|w| w / total).collect()
+}
@@ -0,0 +1,197 @@
+// Phillips-Perron unit-root test.
+//
+// Instead of adding lagged differences like ADF, PP fits the plain Dickey-Fuller
+// regression y_t = ρ·y_{t-1} + deterministic terms + u_t and corrects the t-ratio
+// of ρ for serial correlation and heteroskedasticity in u_t non-parametrically,
+// using a Newey-West long-run variance. The corrected Z_tau statistic has the
+// same limiting distribution as the ADF statistic, so the MacKinnon surfaces apply.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::adf::Regression;
+use crate::error::Error;
+use crate::hac::long_run_variance;
+use crate::json::{levels, JsonObject};
+use crate::linalg::least_squares;
+use crate::mackinnon::{mackinnon_critical_values, mackinnon_p_value};
+use crate::pvalue::interpolate_critical_value;
+use crate::validate::{adf_min_length, require_finite, require_length, require_varying};
+use crate::CRITICAL_VALUE_KEYS;
+use crate::CRITICAL_VALUE_LEVELS;
+#[cfg(feature = "wasm")]
+use crate::{critical_values_object, set_property, CriticalValues};
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(typescript_custom_section)]
+const PP_RESULT_TS: &'static str = r#"
+/** Plain-object snapshot of a `PpResult`, as returned by `PpResult.to_object()`. */
+export interface PpResultObject {
+  statistic: number;
+  p_value: number;
+  critical_values: CriticalValues;
+  is_stationary: boolean;
+  lags: number;
+  nobs: number;
+}
+"#;
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "PpResultObject")]
+    pub type PpResultObject;
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct PpResult {
+    pub statistic: f64,
+    pub p_value: f64,
+    critical_values: [f64; 3],
+    pub is_stationary: bool,
+    // Bartlett-kernel bandwidth of the long-run variance
+    pub lags: usize,
+    pub nobs: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl PpResult {
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(getter)]
+    pub fn critical_values(&self) -> CriticalValues {
+        critical_values_object(self.critical_values)
+    }
+
+    /// Every field of the result as a plain JS object (see `PpResultObject`).
+    #[cfg(feature = "wasm")]
+    pub fn to_object(&self) -> PpResultObject {
+        let object = js_sys::Object::new();
+        set_property(&object, "statistic", self.statistic);
+        set_property(&object, "p_value", self.p_value);
+        set_property(&object, "critical_values", self.critical_values());
+        set_property(&object, "is_stationary", self.is_stationary);
+        set_property(&object, "lags", self.lags as f64);
+        set_property(&object, "nobs", self.nobs as f64);
+        object.unchecked_into()
+    }
+
+    // Critical value at any significance level between 1% and 10%, interpolated across the
+    // 1%/5%/10% values in the normal quantile of the level
+    pub fn critical_value(&self, alpha: f64) -> Result<f64, Error> {
+        interpolate_critical_value(&CRITICAL_VALUE_LEVELS, &self.critical_values, alpha)
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("statistic", self.statistic)
+            .field("p_value", self.p_value)
+            .field(
+                "critical_values",
+                levels(&CRITICAL_VALUE_KEYS, &self.critical_values),
+            )
+            .field("is_stationary", self.is_stationary)
+            .field("lags", self.lags)
+            .field("nobs", self.nobs)
+            .finish()
+    }
+}
+
+impl PpResult {
+    // Critical values as [1%, 5%, 10%], for native callers
+    pub fn critical_values_array(&self) -> [f64; 3] {
+        self.critical_values
+    }
+}
+
+/// Phillips-Perron Z_tau test for a unit root, with the deterministic terms given by `trend`
+/// (same codes as the ADF functions).
+///
+/// The long-run variance uses `ceil(12·(n/100)^(1/4))` Newey-West lags with n the length of
+/// `series`, as in the `arch` package. Errors if the series is too short for the regression
+/// (three observations with a constant, one more per trend term). Useful as a cross-check of ADF on spreads whose residuals are serially correlated
+/// or heteroskedastic, since no lag order has to be chosen for the regression itself.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn pp_test(series: &[f64], trend: &str) -> Result<PpResult, Error> {
+    let regression = Regression::parse(trend)?;
+    require_finite(series, "series")?;
+    require_length(
+        series,
+        adf_min_length(0, regression),
+        "series",
+        "the Phillips-Perron regression",
+    )?;
+    require_varying(series, "series")?;
+
+    let nobs = series.len() - 1;
+    let mut columns = vec![series[..nobs].to_vec()];
+    regression.push_columns(&mut columns, nobs);
+    let ols = least_squares(&columns, &series[1..]).ok_or_else(|| {
+        Error::new("Phillips-Perron regression failed: series too short or degenerate")
+    })?;
+
+    let lags = (12.0 * (series.len() as f64 / 100.0).powf(0.25)).ceil() as usize;
+    let n = nobs as f64;
+    let gamma0 = ols.ssr / n;
+    let lambda2 = long_run_variance(&ols.residuals, lags);
+    let s = ols.sigma2().sqrt();
+    let sigma = ols.std_error(0);
+    let t = (ols.coefficients[0] - 1.0) / sigma;
+
+    let lambda = lambda2.sqrt();
+    let statistic =
+        gamma0.sqrt() * t / lambda - 0.5 * (lambda2 - gamma0) / lambda * (n * sigma / s);
+    if !statistic.is_finite() {
+        return Err(Error::new(
+            "Phillips-Perron statistic is undefined: series is degenerate",
+        ));
+    }
+
+    let p_value = mackinnon_p_value(statistic, regression, 1);
+    let critical_values = mackinnon_critical_values(regression, 1, nobs)
+        .ok_or_else(|| Error::new("no critical values for this regression"))?;
+
+    Ok(PpResult {
+        statistic,
+        p_value,
+        critical_values,
+        is_stationary: p_value <= 0.05 && statistic < critical_values[1],
+        lags,
+        nobs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use crate::rng::Rng;
+
+    #[test]
+    fn too_short_series_is_an_error_not_a_panic() {
+        for (trend, needed) in [("n", 3), ("c", 4), ("ct", 5), ("ctt", 6)] {
+            for n in 0..needed {
+                let error = pp_test(&vec![1.0; n], trend).err().unwrap();
+                assert_eq!(error.code(), ErrorCode::TooShort, "{} with {}", trend, n);
+            }
+        }
+    }
+
+    #[test]
+    fn bandwidth_follows_the_sample_length() {
+        // 12·(n/100)^(1/4) is just above 12 at n = 101 but would be exactly 12 at the 100
+        // regression observations
+        let mut rng = Rng::new(1);
+        let mut level = 0.0;
+        let series: Vec<f64> = (0..101)
+            .map(|_| {
+                level += rng.normal();
+                level
+            })
+            .collect();
+        let result = pp_test(&series, "c").unwrap();
+        assert_eq!(result.nobs, 100);
+        assert_eq!(result.lags, 13);
+        assert_eq!(pp_test(&series[..100], "c").unwrap().lags, 12);
+    }
+}
@@ -0,0 +1,46 @@
+// Storage precision of series and results.
+//
+// The `_f32` entry points take Float32Arrays and return them, halving the memory
+// of large price matrices and rolling outputs on memory-constrained clients.
+// Only the storage is single precision: every value is widened to f64 as it is
+// read, all sums, regressions and test statistics are accumulated in f64, and
+// results are narrowed only when they are stored.
+
+use std::borrow::Cow;
+
+pub(crate) trait Real: Copy + Send + Sync {
+    fn widen(self) -> f64;
+
+    fn narrow(value: f64) -> Self;
+
+    // `values` as f64, borrowed when they already are
+    fn widen_slice(values: &[Self]) -> Cow<'_, [f64]>;
+}
+
+impl Real for f64 {
+    fn widen(self) -> f64 {
+        self
+    }
+
+    fn narrow(value: f64) -> f64 {
+        value
+    }
+
+    fn widen_slice(values: &[f64]) -> Cow<'_, [f64]> {
+        Cow::Borrowed(values)
+    }
+}
+
+impl Real for f32 {
+    fn widen(self) -> f64 {
+        self as f64
+    }
+
+    fn narrow(value: f64) -> f32 {
+        value as f32
+    }
+
+    fn widen_slice(values: &[f32]) -> Cow<'_, [f64]> {
+        Cow::Owned(values.iter().map(|&v| v as f64).collect())
+    }
+}
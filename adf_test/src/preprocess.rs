@@ -0,0 +1,430 @@
+// Missing-data handling applied to price series before they reach a test.
+//
+// NaN and ±infinity are both treated as missing. Without explicit handling a
+// single gap poisons every difference and regression it touches, so callers pick
+// a policy here and get back the cleaned series together with a count of the
+// points that were filled or dropped.
+//
+// Bad ticks are the other hazard: one misprinted price is a jump in and out that
+// the regressions read as a real move. Winsorizing and the MAD outlier filter tame
+// them, and both report the indices they changed so the cleaning can be audited.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::error::{Error, ErrorCode};
+use crate::json::JsonObject;
+use crate::quantile::select_quantiles;
+use crate::stats::median;
+
+// Scales the median absolute deviation to the standard deviation of Gaussian data
+const MAD_SCALE: f64 = 1.4826;
+
+/// What to do with missing observations.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MissingPolicy {
+    /// Fail with an error naming the first missing index.
+    Raise,
+    /// Remove missing observations.
+    Drop,
+    /// Carry the last valid observation forward; leading gaps are dropped.
+    ForwardFill,
+    /// Fill interior gaps on the straight line between their neighbours; leading and trailing
+    /// gaps, which have only one neighbour, are dropped.
+    Interpolate,
+}
+
+impl MissingPolicy {
+    pub fn parse(policy: &str) -> Result<MissingPolicy, Error> {
+        match policy {
+            "raise" => Ok(MissingPolicy::Raise),
+            "drop" => Ok(MissingPolicy::Drop),
+            "ffill" => Ok(MissingPolicy::ForwardFill),
+            "interpolate" => Ok(MissingPolicy::Interpolate),
+            other => Err(Error::new(&format!(
+                "unknown missing-data policy \"{}\": expected \"raise\", \"drop\", \"ffill\" or \"interpolate\"",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MissingPolicy::Raise => "raise",
+            MissingPolicy::Drop => "drop",
+            MissingPolicy::ForwardFill => "ffill",
+            MissingPolicy::Interpolate => "interpolate",
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct CleanedSeries {
+    values: Vec<f64>,
+    // Missing observations in the input
+    pub missing: usize,
+    // Missing observations replaced by a filled value
+    pub filled: usize,
+    // Observations removed from the output (missing ones that could not be filled)
+    pub dropped: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl CleanedSeries {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn values(&self) -> Vec<f64> {
+        self.values.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("values", &self.values)
+            .field("missing", self.missing)
+            .field("filled", self.filled)
+            .field("dropped", self.dropped)
+            .finish()
+    }
+}
+
+impl CleanedSeries {
+    // The cleaned values without copying, for native callers
+    pub fn as_slice(&self) -> &[f64] {
+        &self.values
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct CleanedPair {
+    y: Vec<f64>,
+    x: Vec<f64>,
+    // Rows where at least one leg was missing
+    pub missing: usize,
+    // Individual values replaced by a filled value, over both legs
+    pub filled: usize,
+    // Rows removed from both legs
+    pub dropped: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl CleanedPair {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn y(&self) -> Vec<f64> {
+        self.y.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn x(&self) -> Vec<f64> {
+        self.x.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("y", &self.y)
+            .field("x", &self.x)
+            .field("missing", self.missing)
+            .field("filled", self.filled)
+            .field("dropped", self.dropped)
+            .finish()
+    }
+}
+
+/// Apply a missing-data `policy` ("raise", "drop", "ffill" or "interpolate") to `series`.
+///
+/// The result holds the cleaned values and how many observations were missing, filled and
+/// dropped, so gaps are reported rather than silently propagating into a test statistic.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn clean_series(series: &[f64], policy: &str) -> Result<CleanedSeries, Error> {
+    clean_series_with(series, MissingPolicy::parse(policy)?)
+}
+
+/// `clean_series` for native callers, with the policy as an enum.
+pub fn clean_series_with(series: &[f64], policy: MissingPolicy) -> Result<CleanedSeries, Error> {
+    let missing = count_missing(series, policy, "series")?;
+    let (filled_values, filled) = fill(series, policy);
+    let values: Vec<f64> = filled_values
+        .into_iter()
+        .filter(|v| v.is_finite())
+        .collect();
+    Ok(CleanedSeries {
+        dropped: series.len() - values.len(),
+        values,
+        missing,
+        filled,
+    })
+}
+
+/// Apply a missing-data `policy` to two aligned legs. Each leg is filled on its own, then every
+/// row where either leg is still missing is dropped from both, so the legs stay aligned.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn clean_pair(y: &[f64], x: &[f64], policy: &str) -> Result<CleanedPair, Error> {
+    clean_pair_with(y, x, MissingPolicy::parse(policy)?)
+}
+
+/// `clean_pair` for native callers, with the policy as an enum.
+pub fn clean_pair_with(y: &[f64], x: &[f64], policy: MissingPolicy) -> Result<CleanedPair, Error> {
+    if y.len() != x.len() {
+        return Err(Error::new("y and x must have the same length"));
+    }
+    count_missing(y, policy, "y")?;
+    count_missing(x, policy, "x")?;
+    let missing = y
+        .iter()
+        .zip(x)
+        .filter(|(a, b)| !a.is_finite() || !b.is_finite())
+        .count();
+
+    let len = y.len();
+    let (filled_y, filled_in_y) = fill(y, policy);
+    let (filled_x, filled_in_x) = fill(x, policy);
+    let (y, x): (Vec<f64>, Vec<f64>) = filled_y
+        .into_iter()
+        .zip(filled_x)
+        .filter(|(a, b)| a.is_finite() && b.is_finite())
+        .unzip();
+    Ok(CleanedPair {
+        dropped: len - y.len(),
+        y,
+        x,
+        missing,
+        filled: filled_in_y + filled_in_x,
+    })
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct Winsorized {
+    values: Vec<f64>,
+    indices: Vec<u32>,
+    // Values below this were raised to it
+    pub lower: f64,
+    // Values above this were lowered to it
+    pub upper: f64,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Winsorized {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn values(&self) -> Vec<f64> {
+        self.values.clone()
+    }
+
+    // Positions of the values that were clipped, in increasing order
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn indices(&self) -> Vec<u32> {
+        self.indices.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("values", &self.values)
+            .field("indices", &self.indices)
+            .field("lower", self.lower)
+            .field("upper", self.upper)
+            .finish()
+    }
+}
+
+/// Clip `series` to its `lower_q` and `upper_q` quantiles, e.g. 0.01 and 0.99.
+///
+/// The bounds interpolate linearly between order statistics (numpy's default) of the finite
+/// values; missing values are left in place for `clean_series`. Winsorize returns or spread
+/// changes rather than price levels, whose extremes are usually genuine.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn winsorize(series: &[f64], lower_q: f64, upper_q: f64) -> Result<Winsorized, Error> {
+    if !((0.0..=1.0).contains(&lower_q) && (0.0..=1.0).contains(&upper_q) && lower_q < upper_q) {
+        return Err(Error::new(
+            "lower_q and upper_q must be in [0, 1] with lower_q < upper_q",
+        ));
+    }
+    let mut finite: Vec<f64> = series.iter().copied().filter(|v| v.is_finite()).collect();
+    if finite.is_empty() {
+        return Err(Error::new("series has no finite values"));
+    }
+    let bounds = select_quantiles(&mut finite, &[lower_q, upper_q]);
+    let (lower, upper) = (bounds[0], bounds[1]);
+
+    let mut indices = Vec::new();
+    let values = series
+        .iter()
+        .enumerate()
+        .map(|(i, &v)| {
+            if v.is_finite() && (v < lower || v > upper) {
+                indices.push(i as u32);
+                v.clamp(lower, upper)
+            } else {
+                v
+            }
+        })
+        .collect();
+    Ok(Winsorized {
+        values,
+        indices,
+        lower,
+        upper,
+    })
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct OutlierResult {
+    values: Vec<f64>,
+    indices: Vec<u32>,
+    scores: Vec<f64>,
+    pub threshold: f64,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl OutlierResult {
+    // The series with each outlier replaced by the median it was compared to
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn values(&self) -> Vec<f64> {
+        self.values.clone()
+    }
+
+    // Positions of the outliers, in increasing order
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn indices(&self) -> Vec<u32> {
+        self.indices.clone()
+    }
+
+    // Robust z-score of every observation: |x - median| / (1.4826·MAD), NaN for missing values
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn scores(&self) -> Vec<f64> {
+        self.scores.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("values", &self.values)
+            .field("indices", &self.indices)
+            .field("scores", &self.scores)
+            .field("threshold", self.threshold)
+            .finish()
+    }
+}
+
+/// Flag observations whose distance from the median exceeds `threshold` (default 3.5) times the
+/// median absolute deviation scaled to a standard deviation, and replace them by that median.
+///
+/// Without `half_window` the median and MAD are those of the whole series, which suits returns
+/// and spread changes. With it they are taken over the `half_window` observations on either side
+/// and the point itself (a Hampel filter), which finds bad ticks in price levels: a misprint
+/// stands out from its neighbours even when it is unremarkable for the series as a whole. Missing
+/// values are skipped. Where the MAD is zero any deviation from the median counts as an outlier.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn flag_outliers(
+    series: &[f64],
+    threshold: Option<f64>,
+    half_window: Option<usize>,
+) -> Result<OutlierResult, Error> {
+    let threshold = threshold.unwrap_or(3.5);
+    if !(threshold > 0.0 && threshold.is_finite()) {
+        return Err(Error::new("threshold must be a positive number"));
+    }
+    if half_window == Some(0) {
+        return Err(Error::new("half_window must be at least 1"));
+    }
+    let finite =
+        |values: &[f64]| -> Vec<f64> { values.iter().copied().filter(|v| v.is_finite()).collect() };
+    // Median and scaled MAD of the finite values in `values`
+    let centre_and_scale = |values: &[f64]| {
+        let values = finite(values);
+        let centre = median(&values);
+        let deviations: Vec<f64> = values.iter().map(|v| (v - centre).abs()).collect();
+        (centre, MAD_SCALE * median(&deviations))
+    };
+    let n = series.len();
+    let reference: Vec<(f64, f64)> = match half_window {
+        None => vec![centre_and_scale(series); n],
+        Some(h) => (0..n)
+            .map(|i| centre_and_scale(&series[i.saturating_sub(h)..(i + h + 1).min(n)]))
+            .collect(),
+    };
+
+    let mut values = series.to_vec();
+    let mut indices = Vec::new();
+    let mut scores = vec![f64::NAN; n];
+    for (i, (&x, &(centre, scale))) in series.iter().zip(&reference).enumerate() {
+        if !x.is_finite() {
+            continue;
+        }
+        let deviation = (x - centre).abs();
+        scores[i] = if deviation == 0.0 {
+            0.0
+        } else {
+            deviation / scale
+        };
+        if scores[i] > threshold {
+            indices.push(i as u32);
+            values[i] = centre;
+        }
+    }
+    Ok(OutlierResult {
+        values,
+        indices,
+        scores,
+        threshold,
+    })
+}
+
+// Number of missing entries, or the error `Raise` calls for when there is any
+fn count_missing(values: &[f64], policy: MissingPolicy, name: &str) -> Result<usize, Error> {
+    let missing = values.iter().filter(|v| !v.is_finite()).count();
+    if missing > 0 && policy == MissingPolicy::Raise {
+        let first = values.iter().position(|v| !v.is_finite()).unwrap_or(0);
+        return Err(Error::with_code(
+            ErrorCode::NonFinite,
+            &format!(
+                "{} has {} missing value(s), the first at index {}; choose a policy of \"drop\", \"ffill\" or \"interpolate\"",
+                name, missing, first
+            ),
+        )
+        .at(first));
+    }
+    Ok(missing)
+}
+
+// Fill what `policy` can fill, leaving the unfillable gaps as NaN, and count the fills
+fn fill(values: &[f64], policy: MissingPolicy) -> (Vec<f64>, usize) {
+    let mut out: Vec<f64> = values
+        .iter()
+        .map(|&v| if v.is_finite() { v } else { f64::NAN })
+        .collect();
+    let mut filled = 0;
+    match policy {
+        MissingPolicy::Raise | MissingPolicy::Drop => {}
+        MissingPolicy::ForwardFill => {
+            let mut last = f64::NAN;
+            for v in out.iter_mut() {
+                if v.is_nan() {
+                    if !last.is_nan() {
+                        *v = last;
+                        filled += 1;
+                    }
+                } else {
+                    last = *v;
+                }
+            }
+        }
+        MissingPolicy::Interpolate => {
+            let mut previous: Option<usize> = None;
+            for i in 0..out.len() {
+                if out[i].is_nan() {
+                    continue;
+                }
+                if let Some(p) = previous {
+                    let (start, end) = (out[p], out[i]);
+                    let span = (i - p) as f64;
+                    for (offset, v) in out[p + 1..i].iter_mut().enumerate() {
+                        *v = start + (end - start) * (offset + 1) as f64 / span;
+                        filled += 1;
+                    }
+                }
+                previous = Some(i);
+            }
+        }
+    }
+    (out, filled)
+}
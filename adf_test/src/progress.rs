@@ -0,0 +1,197 @@
+// Incremental execution of long computations, for the async wasm variants.
+//
+// A long computation is written as a `Job` that does one unit of work (one
+// simulated series, one walk-forward segment, one concentrated regression of a
+// Johansen test) per `step`. The synchronous entry
+// points run a job to completion in one go; the async ones run it in time slices,
+// and between slices report progress to a JS callback, check an abort signal
+// and give the event loop a macrotask turn (setTimeout 0) so the page can paint
+// and handle input.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::error::Error;
+#[cfg(feature = "wasm")]
+use crate::get_property;
+
+// Default wall-clock time of one slice of work between yields, in milliseconds
+#[cfg(feature = "wasm")]
+const DEFAULT_SLICE_MS: f64 = 16.0;
+
+pub(crate) trait Job {
+    // Do the next unit of work; true once the job is complete
+    fn step(&mut self) -> Result<bool, Error>;
+
+    // Share of the work done, in [0, 1]; only the async variants report it
+    #[cfg_attr(not(feature = "wasm"), allow(dead_code))]
+    fn fraction_done(&self) -> f64;
+}
+
+// Run `job` to completion on the calling thread
+pub(crate) fn run_to_completion(job: &mut impl Job) -> Result<(), Error> {
+    while !job.step()? {}
+    Ok(())
+}
+
+// Options shared by the async variants
+#[cfg(feature = "wasm")]
+pub(crate) struct ProgressOptions {
+    // Called with the percent complete (0 to 100) after every slice
+    on_progress: Option<js_sys::Function>,
+    // An AbortSignal, or any object with a boolean `aborted` property
+    signal: Option<JsValue>,
+    slice_ms: f64,
+}
+
+#[cfg(feature = "wasm")]
+impl ProgressOptions {
+    // Read `on_progress`, `signal` and `slice_ms` from an options object that may also hold
+    // the options of the computation itself
+    pub(crate) fn from_js(options: &JsValue) -> Result<ProgressOptions, Error> {
+        let mut parsed = ProgressOptions {
+            on_progress: None,
+            signal: None,
+            slice_ms: DEFAULT_SLICE_MS,
+        };
+        if options.is_undefined() || options.is_null() {
+            return Ok(parsed);
+        }
+        if !options.is_object() {
+            return Err(Error::new("options must be an object"));
+        }
+
+        let on_progress = get_property(options, "on_progress")?;
+        if !on_progress.is_undefined() && !on_progress.is_null() {
+            parsed.on_progress = Some(
+                on_progress
+                    .dyn_into::<js_sys::Function>()
+                    .map_err(|_| Error::new("on_progress must be a function"))?,
+            );
+        }
+        let signal = get_property(options, "signal")?;
+        if !signal.is_undefined() && !signal.is_null() {
+            if !signal.is_object() {
+                return Err(Error::new("signal must be an AbortSignal"));
+            }
+            parsed.signal = Some(signal);
+        }
+        let slice_ms = get_property(options, "slice_ms")?;
+        if !slice_ms.is_undefined() {
+            parsed.slice_ms = slice_ms
+                .as_f64()
+                .filter(|v| *v > 0.0 && v.is_finite())
+                .ok_or_else(|| Error::new("slice_ms must be a positive number"))?;
+        }
+        Ok(parsed)
+    }
+
+    fn check_aborted(&self) -> Result<(), Error> {
+        match &self.signal {
+            Some(signal) if get_property(signal, "aborted")?.is_truthy() => {
+                Err(Error::new("the computation was aborted"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn report(&self, fraction: f64) -> Result<(), Error> {
+        if let Some(callback) = &self.on_progress {
+            callback
+                .call1(&JsValue::NULL, &JsValue::from_f64(100.0 * fraction))
+                .map_err(|_| Error::new("on_progress threw an exception"))?;
+        }
+        Ok(())
+    }
+}
+
+// Run `job` in slices of about `slice_ms`, reporting progress and yielding to the event loop
+// between slices; fails as soon as the signal is found aborted
+#[cfg(feature = "wasm")]
+pub(crate) async fn run_in_slices(
+    job: &mut impl Job,
+    options: &ProgressOptions,
+) -> Result<(), Error> {
+    options.check_aborted()?;
+    options.report(0.0)?;
+    loop {
+        let started = js_sys::Date::now();
+        while js_sys::Date::now() - started < options.slice_ms {
+            if job.step()? {
+                return options.report(1.0);
+            }
+        }
+        options.report(job.fraction_done())?;
+        yield_to_event_loop().await?;
+        options.check_aborted()?;
+    }
+}
+
+// Resolve on the next macrotask through the global setTimeout, which browsers, workers and
+// Node all provide
+#[cfg(feature = "wasm")]
+async fn yield_to_event_loop() -> Result<(), Error> {
+    let global = js_sys::global();
+    let set_timeout = get_property(&global, "setTimeout")?
+        .dyn_into::<js_sys::Function>()
+        .map_err(|_| Error::new("setTimeout is not available"))?;
+    let mut schedule = |resolve: js_sys::Function, reject: js_sys::Function| {
+        if set_timeout
+            .call2(&global, &resolve, &JsValue::from_f64(0.0))
+            .is_err()
+        {
+            let _ = reject.call0(&JsValue::NULL);
+        }
+    };
+    let promise = js_sys::Promise::new(&mut schedule);
+    wasm_bindgen_futures::JsFuture::from(promise)
+        .await
+        .map(|_| ())
+        .map_err(|_| Error::new("could not yield to the event loop"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Counts to `target`, failing on step `fail_at` if set
+    struct Counter {
+        count: usize,
+        target: usize,
+        fail_at: Option<usize>,
+    }
+
+    impl Job for Counter {
+        fn step(&mut self) -> Result<bool, Error> {
+            if Some(self.count) == self.fail_at {
+                return Err(Error::new("step failed"));
+            }
+            self.count += 1;
+            Ok(self.count == self.target)
+        }
+
+        fn fraction_done(&self) -> f64 {
+            self.count as f64 / self.target as f64
+        }
+    }
+
+    #[test]
+    fn run_to_completion_steps_until_done_and_stops_on_error() {
+        let mut job = Counter {
+            count: 0,
+            target: 7,
+            fail_at: None,
+        };
+        run_to_completion(&mut job).unwrap();
+        assert_eq!(job.count, 7);
+        assert_eq!(job.fraction_done(), 1.0);
+
+        let mut failing = Counter {
+            count: 0,
+            target: 7,
+            fail_at: Some(3),
+        };
+        assert!(run_to_completion(&mut failing).is_err());
+        assert_eq!(failing.count, 3);
+    }
+}
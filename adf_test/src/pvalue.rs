@@ -0,0 +1,391 @@
+// Sorted (statistic, p-value) tables and the interpolation shared with the embedded lookup.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::distributions::normal_ppf;
+use crate::error::{AdfError, Error};
+
+/// A caller-supplied p-value table, validated once and reusable across many lookups.
+///
+/// Built from a JS array of `[statistic, p_value]` rows sorted by strictly increasing
+/// statistic; lookups interpolate exactly like the embedded ADF table.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct PValueTable {
+    rows: Vec<(f64, f64)>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl PValueTable {
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(constructor)]
+    pub fn new(table: JsValue) -> Result<PValueTable, Error> {
+        Ok(PValueTable::from_js(&table)?)
+    }
+
+    /// Build a table from parallel Float64Arrays of statistics (strictly increasing) and
+    /// p-values, without going through nested JS arrays.
+    pub fn from_arrays(statistics: &[f64], p_values: &[f64]) -> Result<PValueTable, Error> {
+        validate_arrays(statistics, p_values)?;
+        Ok(PValueTable {
+            rows: statistics
+                .iter()
+                .copied()
+                .zip(p_values.iter().copied())
+                .collect(),
+        })
+    }
+
+    /// Interpolated p-value, clamped to the first/last row outside the table's support.
+    pub fn p_value(&self, statistic: f64) -> f64 {
+        self.lookup(statistic).value
+    }
+
+    /// "interpolated", or "clamped_first" / "clamped_last" when `statistic` falls outside
+    /// the table and `p_value` returned the boundary row's value.
+    pub fn p_value_source(&self, statistic: f64) -> String {
+        self.lookup(statistic).source.as_str().to_string()
+    }
+}
+
+/// P-value tables for several sample sizes, e.g. simulated at n = 25, 50, 100, 250, 500.
+///
+/// Lookups interpolate between the two tables that bracket the requested sample size, linearly
+/// in 1/n (the variable finite-sample response surfaces are polynomial in), so p-values vary
+/// smoothly with n instead of jumping from one table to the next. Outside the tabulated sample
+/// sizes the nearest table is used as is.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct PValueTableSet {
+    // Sorted by strictly increasing sample size
+    tables: Vec<(usize, PValueTable)>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl PValueTableSet {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> PValueTableSet {
+        PValueTableSet { tables: Vec::new() }
+    }
+
+    /// Add the table for sample size `nobs`, given as parallel Float64Arrays like
+    /// `PValueTable.from_arrays`. Errors if `nobs` is zero or already has a table.
+    pub fn add_table(
+        &mut self,
+        nobs: usize,
+        statistics: &[f64],
+        p_values: &[f64],
+    ) -> Result<(), Error> {
+        if nobs == 0 {
+            return Err(AdfError::InvalidSampleSize.into());
+        }
+        let table = PValueTable::from_arrays(statistics, p_values)?;
+        match self.tables.binary_search_by_key(&nobs, |(n, _)| *n) {
+            Ok(_) => Err(Error::new(&format!(
+                "a table for nobs = {} was already added",
+                nobs
+            ))),
+            Err(i) => {
+                self.tables.insert(i, (nobs, table));
+                Ok(())
+            }
+        }
+    }
+
+    /// Number of tables added so far.
+    pub fn len(&self) -> usize {
+        self.tables.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    /// p-value for `statistic` at sample size `nobs`; NaN while the set is empty.
+    pub fn p_value(&self, statistic: f64, nobs: usize) -> f64 {
+        self.lookup(statistic, nobs)
+            .map_or(f64::NAN, |p_value| p_value.value)
+    }
+}
+
+impl PValueTableSet {
+    // A lookup clamped in either bracketing table is reported as clamped
+    pub(crate) fn lookup(&self, statistic: f64, nobs: usize) -> Option<PValue> {
+        let last = self.tables.len().checked_sub(1)?;
+        let upper = self.tables.partition_point(|(n, _)| *n < nobs);
+        if upper == 0 || upper > last || self.tables[upper].0 == nobs {
+            return Some(self.tables[upper.min(last)].1.lookup(statistic));
+        }
+
+        let (n0, low) = &self.tables[upper - 1];
+        let (n1, high) = &self.tables[upper];
+        let (p0, p1) = (low.lookup(statistic), high.lookup(statistic));
+        let weight = (1.0 / *n0 as f64 - 1.0 / nobs as f64) / (1.0 / *n0 as f64 - 1.0 / *n1 as f64);
+        let source = if p0.source != PValueSource::Interpolated {
+            p0.source
+        } else {
+            p1.source
+        };
+        Some(PValue {
+            value: p0.value + weight * (p1.value - p0.value),
+            source,
+        })
+    }
+}
+
+impl PValueTable {
+    pub(crate) fn lookup(&self, statistic: f64) -> PValue {
+        lookup_sorted(self.rows.len(), |i| self.rows[i], statistic)
+    }
+
+    #[cfg(feature = "wasm")]
+    fn from_js(table: &JsValue) -> Result<PValueTable, AdfError> {
+        if !js_sys::Array::is_array(table) {
+            return Err(AdfError::NotAnArray);
+        }
+
+        let table = js_sys::Array::from(table);
+        let mut rows = Vec::with_capacity(table.length() as usize);
+        for (i, row) in table.iter().enumerate() {
+            if !js_sys::Array::is_array(&row) {
+                return Err(AdfError::MalformedRow { row: i });
+            }
+            let row = js_sys::Array::from(&row);
+            if row.length() != 2 {
+                return Err(AdfError::MalformedRow { row: i });
+            }
+            let statistic = row.get(0).as_f64().filter(|v| v.is_finite());
+            let p_value = row.get(1).as_f64().filter(|v| v.is_finite());
+            match (statistic, p_value) {
+                (Some(statistic), Some(p_value)) => rows.push((statistic, p_value)),
+                _ => return Err(AdfError::NonNumericEntry { row: i }),
+            }
+        }
+
+        PValueTable::from_rows(rows)
+    }
+
+    #[cfg(feature = "wasm")]
+    pub(crate) fn from_rows(rows: Vec<(f64, f64)>) -> Result<PValueTable, AdfError> {
+        if rows.len() < 2 {
+            return Err(AdfError::EmptyTable { rows: rows.len() });
+        }
+        if let Some(i) = rows.windows(2).position(|w| w[1].0 <= w[0].0) {
+            return Err(AdfError::UnsortedTable { row: i + 1 });
+        }
+        Ok(PValueTable { rows })
+    }
+}
+
+// Check that parallel statistic / p-value arrays form a usable table: same length, at least
+// two rows, finite entries and strictly increasing statistics
+pub(crate) fn validate_arrays(statistics: &[f64], p_values: &[f64]) -> Result<(), AdfError> {
+    if statistics.len() != p_values.len() {
+        return Err(AdfError::LengthMismatch {
+            statistics: statistics.len(),
+            p_values: p_values.len(),
+        });
+    }
+    if statistics.len() < 2 {
+        return Err(AdfError::EmptyTable {
+            rows: statistics.len(),
+        });
+    }
+    if let Some(row) = statistics
+        .iter()
+        .zip(p_values)
+        .position(|(s, p)| !s.is_finite() || !p.is_finite())
+    {
+        return Err(AdfError::NonNumericEntry { row });
+    }
+    if let Some(i) = statistics.windows(2).position(|w| w[1] <= w[0]) {
+        return Err(AdfError::UnsortedTable { row: i + 1 });
+    }
+    Ok(())
+}
+
+// Critical value at significance `alpha` from values tabulated at `levels` (ascending), found
+// by linear interpolation in the normal quantile of the level, which is close to linear for
+// these tests and therefore much more accurate than interpolating in alpha itself. Exact at
+// the tabulated levels; an error outside their range.
+pub(crate) fn interpolate_critical_value(
+    levels: &[f64],
+    values: &[f64],
+    alpha: f64,
+) -> Result<f64, Error> {
+    if let Some(j) = levels.iter().position(|level| *level == alpha) {
+        return Ok(values[j]);
+    }
+    let (first, last) = (levels[0], levels[levels.len() - 1]);
+    let i = levels
+        .windows(2)
+        .position(|w| alpha >= w[0] && alpha <= w[1])
+        .ok_or_else(|| {
+            Error::new(&format!(
+                "alpha must be between {} and {} (got {})",
+                first, last, alpha
+            ))
+        })?;
+    let (z0, z1) = (normal_ppf(levels[i]), normal_ppf(levels[i + 1]));
+    Ok(values[i] + (normal_ppf(alpha) - z0) * (values[i + 1] - values[i]) / (z1 - z0))
+}
+
+// Where a looked-up p-value came from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum PValueSource {
+    // Inside the table's support (interpolated or an exact row)
+    Interpolated,
+    // Statistic below the first row; p-value clamped to the first row's value
+    ClampedFirst,
+    // Statistic above the last row; p-value clamped to the last row's value
+    ClampedLast,
+}
+
+impl PValueSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PValueSource::Interpolated => "interpolated",
+            PValueSource::ClampedFirst => "clamped_first",
+            PValueSource::ClampedLast => "clamped_last",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct PValue {
+    pub value: f64,
+    pub source: PValueSource,
+}
+
+// Linear interpolation over `len` rows sorted by statistic, where `row(i)` yields
+// (statistic, p_value). Values outside the table are clamped to the boundary rows
+// and flagged as such. A NaN statistic yields a NaN p-value.
+pub(crate) fn lookup_sorted(
+    len: usize,
+    row: impl Fn(usize) -> (f64, f64),
+    test_statistic: f64,
+) -> PValue {
+    let interpolated = |value| PValue {
+        value,
+        source: PValueSource::Interpolated,
+    };
+
+    if test_statistic.is_nan() {
+        return interpolated(f64::NAN);
+    }
+    let (first_x, first_y) = row(0);
+    if test_statistic <= first_x {
+        let source = if test_statistic < first_x {
+            PValueSource::ClampedFirst
+        } else {
+            PValueSource::Interpolated
+        };
+        return PValue {
+            value: first_y,
+            source,
+        };
+    }
+    let (last_x, last_y) = row(len - 1);
+    if test_statistic >= last_x {
+        let source = if test_statistic > last_x {
+            PValueSource::ClampedLast
+        } else {
+            PValueSource::Interpolated
+        };
+        return PValue {
+            value: last_y,
+            source,
+        };
+    }
+
+    let mut low = 0;
+    let mut high = len - 1;
+    let mut idx = 0;
+
+    // Find the interval using binary search
+    while low <= high {
+        let mid = low + (high - low) / 2;
+        let (x_mid, y_mid) = row(mid);
+        if x_mid == test_statistic {
+            return interpolated(y_mid);
+        } else if x_mid < test_statistic {
+            idx = mid;
+            low = mid + 1;
+        } else {
+            high = mid - 1;
+        }
+    }
+
+    let (x1, y1) = row(idx);
+    let (x2, y2) = row(idx + 1);
+
+    // Linear interpolation formula
+    interpolated(y1 + (test_statistic - x1) * (y2 - y1) / (x2 - x1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lookup_p_value, ADF_P_VALUE_LOOKUP};
+
+    #[test]
+    fn construction_rejects_malformed_tables() {
+        let statistics = [-3.0, -2.0, -1.0];
+        let p_values = [0.01, 0.1, 0.5];
+        assert!(PValueTable::from_arrays(&statistics, &p_values).is_ok());
+
+        assert!(matches!(
+            validate_arrays(&statistics, &p_values[..2]),
+            Err(AdfError::LengthMismatch {
+                statistics: 3,
+                p_values: 2
+            })
+        ));
+        assert!(matches!(
+            validate_arrays(&[-3.0], &[0.01]),
+            Err(AdfError::EmptyTable { rows: 1 })
+        ));
+        assert!(matches!(
+            validate_arrays(&[-3.0, f64::NAN, -1.0], &p_values),
+            Err(AdfError::NonNumericEntry { row: 1 })
+        ));
+        assert!(matches!(
+            validate_arrays(&statistics, &[0.01, 0.1, f64::INFINITY]),
+            Err(AdfError::NonNumericEntry { row: 2 })
+        ));
+        assert!(matches!(
+            validate_arrays(&[-3.0, -1.0, -2.0], &p_values),
+            Err(AdfError::UnsortedTable { row: 2 })
+        ));
+        // Ties are out of order too
+        assert!(matches!(
+            validate_arrays(&[-3.0, -3.0, -1.0], &p_values),
+            Err(AdfError::UnsortedTable { row: 1 })
+        ));
+        assert!(PValueTable::from_arrays(&[-3.0, -1.0, -2.0], &p_values).is_err());
+    }
+
+    #[test]
+    fn lookups_match_the_built_in_table() {
+        let statistics: Vec<f64> = ADF_P_VALUE_LOOKUP.iter().map(|row| row[0]).collect();
+        let p_values: Vec<f64> = ADF_P_VALUE_LOOKUP.iter().map(|row| row[1]).collect();
+        let table = PValueTable::from_arrays(&statistics, &p_values).unwrap();
+
+        let first = statistics[0];
+        let last = statistics[statistics.len() - 1];
+        let mut probes: Vec<f64> = (0..=400)
+            .map(|i| first - 1.0 + (last - first + 2.0) * i as f64 / 400.0)
+            .collect();
+        probes.extend([first, last, statistics[statistics.len() / 2]]);
+        for statistic in probes {
+            let expected = lookup_p_value(statistic);
+            let found = table.lookup(statistic);
+            assert_eq!(found.value, expected.value, "statistic {}", statistic);
+            assert_eq!(found.source, expected.source, "statistic {}", statistic);
+            assert_eq!(table.p_value(statistic), expected.value);
+            assert_eq!(table.p_value_source(statistic), expected.source.as_str());
+        }
+        assert_eq!(table.lookup(first - 1.0).source, PValueSource::ClampedFirst);
+        assert_eq!(table.lookup(last + 1.0).source, PValueSource::ClampedLast);
+    }
+}
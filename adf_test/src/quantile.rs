@@ -0,0 +1,106 @@
+// Quantiles and the empirical distribution function of a series.
+//
+// Spread percentiles, adaptive entry thresholds and winsorization bounds all need a
+// few order statistics of a sample, never the whole ordering, so the values are
+// partitioned with quickselect around just the ranks the requested levels touch:
+// O(n) for one quantile and O(n log m) for m of them, against O(n log n) for a
+// sort. Levels interpolate linearly between order statistics, numpy's default.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::error::Error;
+
+/// Quantile of the finite values of `series` at level `q` in [0, 1], interpolated linearly
+/// between order statistics as numpy's `quantile` does by default; missing values are skipped.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn quantile(series: &[f64], q: f64) -> Result<f64, Error> {
+    Ok(quantiles(series, &[q])?[0])
+}
+
+/// `quantile` at each of `qs`, in the order given, from a single partitioning of the values.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn quantiles(series: &[f64], qs: &[f64]) -> Result<Vec<f64>, Error> {
+    if let Some(i) = qs.iter().position(|q| !(0.0..=1.0).contains(q)) {
+        return Err(Error::new("quantile levels must be in [0, 1]").at(i));
+    }
+    let mut values: Vec<f64> = series.iter().copied().filter(|v| v.is_finite()).collect();
+    if values.is_empty() {
+        return Err(Error::new("series has no finite values"));
+    }
+    Ok(select_quantiles(&mut values, qs))
+}
+
+/// Empirical distribution function of the finite values of `series` at each of `points`: the
+/// share of values at or below the point, so the percentile rank of a spread reading is
+/// `ecdf(history, [reading])`. NaN points give NaN.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn ecdf(series: &[f64], points: &[f64]) -> Result<Vec<f64>, Error> {
+    let values: Vec<f64> = series.iter().copied().filter(|v| v.is_finite()).collect();
+    if values.is_empty() {
+        return Err(Error::new("series has no finite values"));
+    }
+    // Only the points are sorted; each value is counted at the first point it does not exceed
+    let mut order: Vec<usize> = (0..points.len()).filter(|&i| !points[i].is_nan()).collect();
+    order.sort_unstable_by(|&a, &b| points[a].total_cmp(&points[b]));
+    let mut counts = vec![0usize; order.len() + 1];
+    for v in &values {
+        counts[order.partition_point(|&i| points[i] < *v)] += 1;
+    }
+
+    let mut shares = vec![f64::NAN; points.len()];
+    let mut at_or_below = 0;
+    for (&i, count) in order.iter().zip(&counts) {
+        at_or_below += count;
+        shares[i] = at_or_below as f64 / values.len() as f64;
+    }
+    Ok(shares)
+}
+
+// Interpolated quantiles of `values` at `levels` in [0, 1]; `values` must be non-empty and free
+// of NaN, and is left partially ordered
+pub(crate) fn select_quantiles(values: &mut [f64], levels: &[f64]) -> Vec<f64> {
+    let last = (values.len() - 1) as f64;
+    let mut ranks: Vec<usize> = levels
+        .iter()
+        .flat_map(|level| {
+            let position = level * last;
+            [position.floor() as usize, position.ceil() as usize]
+        })
+        .collect();
+    ranks.sort_unstable();
+    ranks.dedup();
+    let mut order_statistics = vec![0.0; ranks.len()];
+    select_ranks(values, 0, &ranks, &mut order_statistics);
+
+    let at = |rank: usize| order_statistics[ranks.binary_search(&rank).unwrap_or(0)];
+    levels
+        .iter()
+        .map(|level| {
+            let position = level * last;
+            let below = position.floor() as usize;
+            let lower = at(below);
+            lower + (position - below as f64) * (at(position.ceil() as usize) - lower)
+        })
+        .collect()
+}
+
+// Place the order statistic of every one of the sorted, distinct `ranks` (counted from `offset`,
+// the rank of values[0]) into `out`, selecting the middle rank and recursing on each side
+fn select_ranks(values: &mut [f64], offset: usize, ranks: &[usize], out: &mut [f64]) {
+    if ranks.is_empty() {
+        return;
+    }
+    let mid = ranks.len() / 2;
+    let local = ranks[mid] - offset;
+    let (below, &mut value, above) = values.select_nth_unstable_by(local, f64::total_cmp);
+    out[mid] = value;
+    let (out_below, out_above) = out.split_at_mut(mid);
+    select_ranks(below, offset, &ranks[..mid], out_below);
+    select_ranks(
+        above,
+        ranks[mid] + 1,
+        &ranks[mid + 1..],
+        &mut out_above[1..],
+    );
+}
@@ -0,0 +1,319 @@
+// Two-state regime detection for a spread.
+//
+// A pair that trades well for a year can stop mean-reverting after a merger, an
+// index change or a credit event, and its spread then moves in larger, more
+// persistent steps. A Markov-switching model (Hamilton 1989) with a calm and a
+// broken regime, each with its own mean and variance, picks this up: fitted by
+// EM, it gives every bar the probability of being in the broken regime, from the
+// data up to that bar (filtered, usable for trading) or from the whole sample
+// (smoothed, for labelling history). Trading can then be switched off while the
+// broken regime is likely.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::error::Error;
+use crate::json::JsonObject;
+use crate::stats::{nanmean, nanstd, NanPolicy};
+use crate::validate::{require_finite, require_length, require_varying};
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct RegimeResult {
+    // Mean and standard deviation of the series in each regime; the broken regime is the one
+    // with the larger standard deviation
+    pub calm_mean: f64,
+    pub calm_std: f64,
+    pub broken_mean: f64,
+    pub broken_std: f64,
+    // Probability of staying in each regime from one bar to the next
+    pub calm_persistence: f64,
+    pub broken_persistence: f64,
+    pub log_likelihood: f64,
+    pub iterations: usize,
+    pub converged: bool,
+    filtered: Vec<f64>,
+    smoothed: Vec<f64>,
+    labels: Vec<u32>,
+    switch_points: Vec<u32>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl RegimeResult {
+    // P(broken regime at bar t | observations up to t), for decisions taken at bar t
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn broken_probability(&self) -> Vec<f64> {
+        self.filtered.clone()
+    }
+
+    // P(broken regime at bar t | every observation), for describing the history
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn smoothed_probability(&self) -> Vec<f64> {
+        self.smoothed.clone()
+    }
+
+    // Most likely regime of each bar from the smoothed probability: 0 calm, 1 broken
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn labels(&self) -> Vec<u32> {
+        self.labels.clone()
+    }
+
+    // Bars whose label differs from the previous bar's
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn switch_points(&self) -> Vec<u32> {
+        self.switch_points.clone()
+    }
+
+    // Average number of bars spent in the calm regime before switching, 1 / (1 - persistence)
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn calm_duration(&self) -> f64 {
+        1.0 / (1.0 - self.calm_persistence)
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn broken_duration(&self) -> f64 {
+        1.0 / (1.0 - self.broken_persistence)
+    }
+
+    /// One flag per bar, 1 where the filtered broken-regime probability reaches `threshold`
+    /// (default 0.5) and 0 elsewhere: the `broken` input of `generate_regime_signals`.
+    pub fn broken(&self, threshold: Option<f64>) -> Result<Vec<f64>, Error> {
+        let threshold = check_threshold(threshold)?;
+        Ok(self
+            .filtered
+            .iter()
+            .map(|&p| if p >= threshold { 1.0 } else { 0.0 })
+            .collect())
+    }
+
+    /// `positions` (such as `SignalResult.positions`, one per bar) with trading switched off in
+    /// the broken regime, ready for `backtest`: flat on every bar whose filtered broken-regime
+    /// probability reaches `threshold` (default 0.5), and a position closed that way is not
+    /// taken up again until the input goes flat or changes side.
+    pub fn gate_positions(
+        &self,
+        positions: &[f64],
+        threshold: Option<f64>,
+    ) -> Result<Vec<f64>, Error> {
+        if positions.len() != self.filtered.len() {
+            return Err(Error::new("positions must hold one value per bar"));
+        }
+        let broken = self.broken(threshold)?;
+        // Side of the position closed by the broken regime, 0 when none is held back
+        let mut blocked = 0.0;
+        Ok(positions
+            .iter()
+            .zip(&broken)
+            .map(|(&position, &broken)| {
+                let side = if position == 0.0 {
+                    0.0
+                } else {
+                    position.signum()
+                };
+                if broken == 1.0 {
+                    blocked = side;
+                    0.0
+                } else if side != 0.0 && side == blocked {
+                    0.0
+                } else {
+                    blocked = 0.0;
+                    position
+                }
+            })
+            .collect())
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("calm_mean", self.calm_mean)
+            .field("calm_std", self.calm_std)
+            .field("broken_mean", self.broken_mean)
+            .field("broken_std", self.broken_std)
+            .field("calm_persistence", self.calm_persistence)
+            .field("broken_persistence", self.broken_persistence)
+            .field("log_likelihood", self.log_likelihood)
+            .field("iterations", self.iterations)
+            .field("converged", self.converged)
+            .field("broken_probability", &self.filtered)
+            .field("smoothed_probability", &self.smoothed)
+            .field("labels", &self.labels)
+            .field("switch_points", &self.switch_points)
+            .finish()
+    }
+}
+
+/// Fit a two-state Markov-switching mean/variance model to `series` by EM.
+///
+/// Each bar is drawn from N(μ_k, σ_k²) for the regime k it is in, and the regime follows a
+/// Markov chain. Pass the spread's changes (or the pair's returns) to detect bursts of
+/// volatility and drift, or the spread itself to detect level shifts. EM runs until the
+/// log-likelihood improves by less than 1e-8 relative, or for `max_iterations` (default 500).
+/// The regime with the larger σ is reported as broken. The filtered probabilities only use
+/// data up to each bar, but the parameters are estimated on the whole series, so for an
+/// out-of-sample test fit on a formation window.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn markov_regimes(
+    series: &[f64],
+    max_iterations: Option<usize>,
+) -> Result<RegimeResult, Error> {
+    require_finite(series, "series")?;
+    require_length(series, 20, "series", "a regime model")?;
+    require_varying(series, "series")?;
+    let max_iterations = max_iterations.unwrap_or(500);
+    if max_iterations == 0 {
+        return Err(Error::new("max_iterations must be at least 1"));
+    }
+
+    let n = series.len();
+    let mean = nanmean(series, NanPolicy::Propagate);
+    let std = nanstd(series, 1, NanPolicy::Propagate);
+    let variance_floor = 1e-8 * std * std;
+    let mut means = [mean, mean];
+    let mut variances = [0.25 * std * std, 4.0 * std * std];
+    // transition[j][k] = P(regime k next | regime j now)
+    let mut transition = [[0.95, 0.05], [0.05, 0.95]];
+    let mut initial = [0.5, 0.5];
+
+    let mut log_likelihood = f64::NEG_INFINITY;
+    let mut iterations = 0;
+    let mut converged = false;
+    let mut pass = forward_backward(series, &means, &variances, &transition, &initial);
+    while iterations < max_iterations {
+        iterations += 1;
+        let (_, smoothed, joint, ll) = &pass;
+        let ll = *ll;
+        for k in 0..2 {
+            let weight: f64 = smoothed.iter().map(|g| g[k]).sum();
+            means[k] = smoothed
+                .iter()
+                .zip(series)
+                .map(|(g, y)| g[k] * y)
+                .sum::<f64>()
+                / weight;
+            variances[k] = (smoothed
+                .iter()
+                .zip(series)
+                .map(|(g, y)| g[k] * (y - means[k]) * (y - means[k]))
+                .sum::<f64>()
+                / weight)
+                .max(variance_floor);
+            let leaving: f64 = smoothed[..n - 1].iter().map(|g| g[k]).sum();
+            for j in 0..2 {
+                transition[k][j] = joint[k][j] / leaving;
+            }
+        }
+        initial = smoothed[0];
+        pass = forward_backward(series, &means, &variances, &transition, &initial);
+        let improvement = pass.3 - ll;
+        log_likelihood = pass.3;
+        if improvement.abs() < 1e-8 * (1.0 + ll.abs()) {
+            converged = true;
+            break;
+        }
+    }
+    if !log_likelihood.is_finite() {
+        return Err(Error::new("the regime model failed to converge"));
+    }
+
+    let (mut filtered, mut smoothed, _, _) = pass;
+    // Regime 1 is the broken one
+    if variances[0] > variances[1] {
+        means.swap(0, 1);
+        variances.swap(0, 1);
+        transition = [
+            [transition[1][1], transition[1][0]],
+            [transition[0][1], transition[0][0]],
+        ];
+        for p in filtered.iter_mut().chain(smoothed.iter_mut()) {
+            p.swap(0, 1);
+        }
+    }
+    let labels: Vec<u32> = smoothed.iter().map(|g| u32::from(g[1] > 0.5)).collect();
+    let switch_points = (1..n)
+        .filter(|&t| labels[t] != labels[t - 1])
+        .map(|t| t as u32)
+        .collect();
+    Ok(RegimeResult {
+        calm_mean: means[0],
+        calm_std: variances[0].sqrt(),
+        broken_mean: means[1],
+        broken_std: variances[1].sqrt(),
+        calm_persistence: transition[0][0],
+        broken_persistence: transition[1][1],
+        log_likelihood,
+        iterations,
+        converged,
+        filtered: filtered.iter().map(|p| p[1]).collect(),
+        smoothed: smoothed.iter().map(|g| g[1]).collect(),
+        labels,
+        switch_points,
+    })
+}
+
+// Validated broken-regime probability threshold, default 0.5
+fn check_threshold(threshold: Option<f64>) -> Result<f64, Error> {
+    let threshold = threshold.unwrap_or(0.5);
+    if !(threshold > 0.0 && threshold <= 1.0) {
+        return Err(Error::new("threshold must be in (0, 1]"));
+    }
+    Ok(threshold)
+}
+
+// Filtered probabilities, smoothed probabilities, expected transition counts summed over time
+// and the log-likelihood, by the scaled forward-backward recursions
+#[allow(clippy::type_complexity)]
+fn forward_backward(
+    series: &[f64],
+    means: &[f64; 2],
+    variances: &[f64; 2],
+    transition: &[[f64; 2]; 2],
+    initial: &[f64; 2],
+) -> (Vec<[f64; 2]>, Vec<[f64; 2]>, [[f64; 2]; 2], f64) {
+    let n = series.len();
+    // Densities of each bar under each regime, scaled by the larger of the two so outliers do
+    // not underflow; the scale goes back into the log-likelihood
+    let mut density = Vec::with_capacity(n);
+    let mut log_scale = Vec::with_capacity(n);
+    for &y in series {
+        let log_density = [0, 1].map(|k| {
+            let z = y - means[k];
+            -0.5 * (z * z / variances[k] + variances[k].ln())
+        });
+        let top = log_density[0].max(log_density[1]);
+        density.push(log_density.map(|l| (l - top).exp()));
+        log_scale.push(top);
+    }
+
+    let mut filtered = Vec::with_capacity(n);
+    let mut totals = Vec::with_capacity(n);
+    let mut log_likelihood = -0.5 * (2.0 * std::f64::consts::PI).ln() * n as f64;
+    for t in 0..n {
+        let prior = if t == 0 {
+            *initial
+        } else {
+            let p: &[f64; 2] = &filtered[t - 1];
+            [0, 1].map(|k| p[0] * transition[0][k] + p[1] * transition[1][k])
+        };
+        let joint = [0, 1].map(|k| prior[k] * density[t][k]);
+        let total = joint[0] + joint[1];
+        filtered.push(joint.map(|a| a / total));
+        totals.push(total);
+        log_likelihood += total.ln() + log_scale[t];
+    }
+
+    let mut smoothed = vec![[0.0; 2]; n];
+    let mut counts = [[0.0; 2]; 2];
+    let mut backward = [1.0, 1.0];
+    smoothed[n - 1] = filtered[n - 1];
+    for t in (0..n - 1).rev() {
+        let next = [0, 1].map(|k| density[t + 1][k] * backward[k] / totals[t + 1]);
+        for (j, row) in counts.iter_mut().enumerate() {
+            for (k, count) in row.iter_mut().enumerate() {
+                *count += filtered[t][j] * transition[j][k] * next[k];
+            }
+        }
+        backward = [0, 1].map(|j| transition[j][0] * next[0] + transition[j][1] * next[1]);
+        smoothed[t] = [0, 1].map(|j| filtered[t][j] * backward[j]);
+    }
+    (filtered, smoothed, counts, log_likelihood)
+}
@@ -0,0 +1,257 @@
+// Calendar resampling of timestamped series.
+//
+// Cointegration found on daily bars may vanish at weekly frequency and vice
+// versa, so series are aggregated here into UTC hours, UTC days, ISO weeks or
+// calendar months before testing. Timestamps are epoch milliseconds as produced by JS `Date`.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::align::check_series;
+use crate::error::Error;
+use crate::json::JsonObject;
+use crate::stats::{nanmean, NanPolicy};
+
+const MS_PER_HOUR: f64 = 3_600_000.0;
+const MS_PER_DAY: f64 = 86_400_000.0;
+
+// Length of the aggregation periods
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ResampleRule {
+    // UTC clock hours
+    Hourly,
+    // UTC calendar days
+    Daily,
+    // ISO weeks, Monday to Sunday
+    Weekly,
+    // Calendar months
+    Monthly,
+}
+
+impl ResampleRule {
+    fn parse(rule: &str) -> Result<ResampleRule, Error> {
+        match rule {
+            "hourly" => Ok(ResampleRule::Hourly),
+            "daily" => Ok(ResampleRule::Daily),
+            "weekly" => Ok(ResampleRule::Weekly),
+            "monthly" => Ok(ResampleRule::Monthly),
+            other => Err(Error::new(&format!(
+                "unknown resampling rule \"{}\": expected \"hourly\", \"daily\", \"weekly\" or \"monthly\"",
+                other
+            ))),
+        }
+    }
+
+    // Index of the period containing `timestamp`, increasing with time
+    fn period(&self, timestamp: f64) -> i64 {
+        let days = (timestamp / MS_PER_DAY).floor() as i64;
+        match self {
+            ResampleRule::Hourly => (timestamp / MS_PER_HOUR).floor() as i64,
+            ResampleRule::Daily => days,
+            // 1970-01-01 was a Thursday, so weeks counted from Monday 1969-12-29
+            ResampleRule::Weekly => (days + 3).div_euclid(7),
+            ResampleRule::Monthly => {
+                let (year, month) = year_month(days);
+                year * 12 + month
+            }
+        }
+    }
+}
+
+// How the observations of a period are combined into one value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Aggregation {
+    First,
+    Last,
+    Mean,
+}
+
+impl Aggregation {
+    fn parse(how: &str) -> Result<Aggregation, Error> {
+        match how {
+            "first" => Ok(Aggregation::First),
+            "last" => Ok(Aggregation::Last),
+            "mean" => Ok(Aggregation::Mean),
+            other => Err(Error::new(&format!(
+                "unknown aggregation \"{}\": expected \"first\", \"last\" or \"mean\"",
+                other
+            ))),
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct ResampledSeries {
+    timestamps: Vec<f64>,
+    values: Vec<f64>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ResampledSeries {
+    // Timestamp of the last observation in each period
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn timestamps(&self) -> Vec<f64> {
+        self.timestamps.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn values(&self) -> Vec<f64> {
+        self.values.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("timestamps", &self.timestamps)
+            .field("values", &self.values)
+            .finish()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct OhlcSeries {
+    timestamps: Vec<f64>,
+    open: Vec<f64>,
+    high: Vec<f64>,
+    low: Vec<f64>,
+    close: Vec<f64>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl OhlcSeries {
+    // Timestamp of the last observation in each period
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn timestamps(&self) -> Vec<f64> {
+        self.timestamps.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn open(&self) -> Vec<f64> {
+        self.open.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn high(&self) -> Vec<f64> {
+        self.high.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn low(&self) -> Vec<f64> {
+        self.low.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn close(&self) -> Vec<f64> {
+        self.close.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("timestamps", &self.timestamps)
+            .field("open", &self.open)
+            .field("high", &self.high)
+            .field("low", &self.low)
+            .field("close", &self.close)
+            .finish()
+    }
+}
+
+/// Aggregate `values` observed at epoch-millisecond `timestamps` (strictly increasing) into
+/// `rule` periods: "hourly" (UTC clock hours), "daily" (UTC days), "weekly" (ISO weeks, Monday
+/// to Sunday) or "monthly".
+///
+/// Each period with at least one observation yields one value, combined by `how`: "last"
+/// (default, the closing price), "first" or "mean". Periods without observations are skipped
+/// rather than filled, and each output is stamped with the time of the period's last
+/// observation, so two legs resampled alike can still be joined with `align_series`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn resample(
+    timestamps: &[f64],
+    values: &[f64],
+    rule: &str,
+    how: Option<String>,
+) -> Result<ResampledSeries, Error> {
+    let rule = ResampleRule::parse(rule)?;
+    let how = Aggregation::parse(how.as_deref().unwrap_or("last"))?;
+    check_values(timestamps, values)?;
+
+    let mut resampled = ResampledSeries {
+        timestamps: Vec::new(),
+        values: Vec::new(),
+    };
+    for range in periods(timestamps, rule) {
+        let slice = &values[range.clone()];
+        resampled.timestamps.push(timestamps[range.end - 1]);
+        resampled.values.push(match how {
+            Aggregation::First => slice[0],
+            Aggregation::Last => slice[slice.len() - 1],
+            Aggregation::Mean => nanmean(slice, NanPolicy::Propagate),
+        });
+    }
+    Ok(resampled)
+}
+
+/// Open, high, low and close of `values` in each `rule` period, with the timestamps and rules of
+/// `resample`. Downsamples a price series to bars, e.g. daily closes to weekly OHLC.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn resample_ohlc(timestamps: &[f64], values: &[f64], rule: &str) -> Result<OhlcSeries, Error> {
+    let rule = ResampleRule::parse(rule)?;
+    check_values(timestamps, values)?;
+
+    let mut bars = OhlcSeries {
+        timestamps: Vec::new(),
+        open: Vec::new(),
+        high: Vec::new(),
+        low: Vec::new(),
+        close: Vec::new(),
+    };
+    for range in periods(timestamps, rule) {
+        let slice = &values[range.clone()];
+        bars.timestamps.push(timestamps[range.end - 1]);
+        bars.open.push(slice[0]);
+        bars.high
+            .push(slice.iter().copied().fold(f64::NEG_INFINITY, f64::max));
+        bars.low
+            .push(slice.iter().copied().fold(f64::INFINITY, f64::min));
+        bars.close.push(slice[slice.len() - 1]);
+    }
+    Ok(bars)
+}
+
+fn check_values(timestamps: &[f64], values: &[f64]) -> Result<(), Error> {
+    check_series(timestamps, values, "series")?;
+    if values.iter().any(|v| !v.is_finite()) {
+        return Err(Error::new("values must be finite"));
+    }
+    Ok(())
+}
+
+// Index ranges of the consecutive observations falling in the same period
+fn periods(timestamps: &[f64], rule: ResampleRule) -> Vec<std::ops::Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    for i in 1..=timestamps.len() {
+        if i == timestamps.len() || rule.period(timestamps[i]) != rule.period(timestamps[start]) {
+            ranges.push(start..i);
+            start = i;
+        }
+    }
+    ranges
+}
+
+// Proleptic Gregorian (year, month 0-11) of a day count since 1970-01-01 (Hinnant's
+// `civil_from_days`)
+fn year_month(days: i64) -> (i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // Months counted from March
+    let mp = (5 * day_of_year + 2) / 153;
+    let month = if mp < 10 { mp + 2 } else { mp - 10 };
+    let year = year_of_era + era * 400 + if month <= 1 { 1 } else { 0 };
+    (year, month)
+}
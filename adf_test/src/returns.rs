@@ -0,0 +1,71 @@
+// Price transforms applied before testing: log-prices and returns.
+//
+// Doing them here keeps a whole price series on the wasm side instead of
+// transforming it in JS and copying it across once per transform.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::error::Error;
+
+/// Natural logarithm of each price. Errors if any price is not strictly positive and finite.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn log_prices(prices: &[f64]) -> Result<Vec<f64>, Error> {
+    checked_log(prices, "prices")
+}
+
+/// One-period simple returns p_t / p_{t-1} - 1, one fewer than `prices`. Errors if any price is
+/// not strictly positive and finite.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn simple_returns(prices: &[f64]) -> Result<Vec<f64>, Error> {
+    checked_log(prices, "prices")?;
+    Ok(prices.windows(2).map(|w| w[1] / w[0] - 1.0).collect())
+}
+
+/// One-period log returns ln(p_t / p_{t-1}), one fewer than `prices`; they add up over time, so
+/// their sum is the log return of the whole period. Errors if any price is not strictly positive
+/// and finite.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn log_returns(prices: &[f64]) -> Result<Vec<f64>, Error> {
+    let logs = checked_log(prices, "prices")?;
+    Ok(logs.windows(2).map(|w| w[1] - w[0]).collect())
+}
+
+/// Compounded return up to each period of the simple `returns`, Π_{s≤t}(1 + r_s) - 1. For log
+/// returns use a running sum instead. Errors if any return is not finite.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn cumulative_returns(returns: &[f64]) -> Result<Vec<f64>, Error> {
+    if let Some(i) = returns.iter().position(|r| !r.is_finite()) {
+        return Err(Error::new(&format!(
+            "returns must be finite (found {} at index {})",
+            returns[i], i
+        )));
+    }
+    let mut growth = 1.0;
+    Ok(returns
+        .iter()
+        .map(|r| {
+            growth *= 1.0 + r;
+            growth - 1.0
+        })
+        .collect())
+}
+
+// ln of every price, or an error naming `name` and the first price that is not strictly positive
+// and finite
+pub(crate) fn checked_log(prices: &[f64], name: &str) -> Result<Vec<f64>, Error> {
+    prices
+        .iter()
+        .enumerate()
+        .map(|(i, &p)| {
+            if p > 0.0 && p.is_finite() {
+                Ok(p.ln())
+            } else {
+                Err(Error::new(&format!(
+                    "{} must contain strictly positive, finite prices (found {} at index {})",
+                    name, p, i
+                )))
+            }
+        })
+        .collect()
+}
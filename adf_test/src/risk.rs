@@ -0,0 +1,108 @@
+// Value at risk and expected shortfall of a strategy's return series.
+//
+// Both are reported as positive fractions of the equity lost over a horizon of
+// several bars. The historical estimates read the loss distribution straight off
+// the returns compounded over every overlapping window of the horizon, so fat
+// tails and skew are kept as they were; the parametric ones assume normal returns
+// with the sample mean and volatility scaled by the square root of the horizon,
+// which is smooth on short samples but understates the tails of a spread strategy.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::distributions::normal_ppf;
+use crate::error::Error;
+use crate::json::JsonObject;
+use crate::quantile::select_quantiles;
+use crate::stats::{nanmean, nanstd, NanPolicy};
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug)]
+pub struct ValueAtRisk {
+    pub confidence: f64,
+    // Bars the losses are measured over
+    pub horizon: usize,
+    // Loss exceeded with probability 1 - confidence, from the empirical distribution
+    pub historical_var: f64,
+    // Mean loss in the worst 1 - confidence of the horizon windows
+    pub historical_es: f64,
+    // The same two under normal returns
+    pub parametric_var: f64,
+    pub parametric_es: f64,
+    // Worst compounded return over any horizon window
+    pub worst_return: f64,
+    // Overlapping horizon windows in the sample
+    pub windows: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ValueAtRisk {
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("confidence", self.confidence)
+            .field("horizon", self.horizon)
+            .field("historical_var", self.historical_var)
+            .field("historical_es", self.historical_es)
+            .field("parametric_var", self.parametric_var)
+            .field("parametric_es", self.parametric_es)
+            .field("worst_return", self.worst_return)
+            .field("windows", self.windows)
+            .finish()
+    }
+}
+
+/// Historical and parametric value at risk and expected shortfall of a simple-returns series,
+/// such as `backtest(...).returns` for one pair or `portfolio_backtest(...).returns` for a book
+/// of pairs, at `confidence` (e.g. 0.95 or 0.99) over `horizon` bars.
+///
+/// The historical VaR is the loss at the 1 - `confidence` quantile of the returns compounded
+/// over each overlapping window of `horizon` bars (linear interpolation between order
+/// statistics, as `quantile`), and the historical ES the mean loss of the windows at or beyond
+/// it. The parametric figures take the per-bar returns as normal with their sample mean μ and
+/// standard deviation σ, scaled to μ·h and σ·√h over the horizon. Losses are positive
+/// fractions of the equity; a negative VaR means even the tail quantile is a gain.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn var_es(returns: &[f64], confidence: f64, horizon: usize) -> Result<ValueAtRisk, Error> {
+    if !(confidence > 0.0 && confidence < 1.0) {
+        return Err(Error::new("confidence must be in (0, 1)"));
+    }
+    if horizon == 0 {
+        return Err(Error::new("horizon must be at least 1"));
+    }
+    if returns.iter().any(|r| !r.is_finite()) {
+        return Err(Error::new("returns must contain only finite values"));
+    }
+    if returns.len() <= horizon {
+        return Err(Error::new("returns must hold more values than horizon"));
+    }
+
+    // Compounded return of every window of `horizon` bars
+    let mut window_returns: Vec<f64> = returns
+        .windows(horizon)
+        .map(|window| window.iter().map(|r| 1.0 + r).product::<f64>() - 1.0)
+        .collect();
+    let tail = 1.0 - confidence;
+    let threshold = select_quantiles(&mut window_returns, &[tail])[0];
+    let (tail_sum, tail_count) = window_returns
+        .iter()
+        .filter(|r| **r <= threshold)
+        .fold((0.0, 0usize), |(sum, count), r| (sum + r, count + 1));
+    let worst_return = window_returns.iter().copied().fold(f64::INFINITY, f64::min);
+
+    let h = horizon as f64;
+    let mean = nanmean(returns, NanPolicy::Propagate) * h;
+    let std = nanstd(returns, 1, NanPolicy::Propagate) * h.sqrt();
+    let z = normal_ppf(tail);
+    let density = (-0.5 * z * z).exp() / (2.0 * std::f64::consts::PI).sqrt();
+    Ok(ValueAtRisk {
+        confidence,
+        horizon,
+        historical_var: -threshold,
+        historical_es: -tail_sum / tail_count as f64,
+        parametric_var: -(mean + std * z),
+        parametric_es: -(mean - std * density / tail),
+        worst_return,
+        windows: window_returns.len(),
+    })
+}
@@ -0,0 +1,168 @@
+// Seedable pseudo-random numbers for the bootstrap and simulation features.
+//
+// xoshiro256** (Blackman & Vigna) seeded through SplitMix64: fast, statistically
+// solid and, unlike a platform RNG, identical on every target for the same seed.
+// Every simulation API takes a 32-bit seed (default `DEFAULT_SEED`) and draws only
+// from this generator, with no entropy from the host. The floating-point work on
+// top of it, including the logarithms of the normal draws, is compiled into the
+// module, so a seed gives bit-identical p-values and tables in every browser and
+// across runs.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "wasm")]
+use crate::error::Error;
+
+pub(crate) const DEFAULT_SEED: u32 = 0;
+
+pub(crate) struct Rng {
+    state: [u64; 4],
+    // Second normal of the last polar-method pair
+    spare_normal: Option<f64>,
+}
+
+impl Rng {
+    pub fn new(seed: u32) -> Rng {
+        let mut x = seed as u64;
+        let mut split_mix = || {
+            x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = x;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        };
+        Rng {
+            state: [split_mix(), split_mix(), split_mix(), split_mix()],
+            spare_normal: None,
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    // Uniform on [0, 1) with 53 random bits
+    pub fn uniform(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    // Uniform integer in 0..n (n > 0), without modulo bias
+    pub fn below(&mut self, n: usize) -> usize {
+        let n = n as u64;
+        let zone = u64::MAX - u64::MAX % n;
+        loop {
+            let r = self.next_u64();
+            if r < zone {
+                return (r % n) as usize;
+            }
+        }
+    }
+
+    // Standard normal by Marsaglia's polar method
+    pub fn normal(&mut self) -> f64 {
+        if let Some(z) = self.spare_normal.take() {
+            return z;
+        }
+        loop {
+            let u = 2.0 * self.uniform() - 1.0;
+            let v = 2.0 * self.uniform() - 1.0;
+            let s = u * u + v * v;
+            if s > 0.0 && s < 1.0 {
+                let factor = (-2.0 * s.ln() / s).sqrt();
+                self.spare_normal = Some(v * factor);
+                return u * factor;
+            }
+        }
+    }
+
+    // Gamma with unit scale by Marsaglia and Tsang's squeeze method, boosted through
+    // Gamma(shape + 1)·U^(1/shape) for shapes below one
+    pub fn gamma(&mut self, shape: f64) -> f64 {
+        if shape < 1.0 {
+            let u = self.uniform();
+            return self.gamma(shape + 1.0) * u.powf(1.0 / shape);
+        }
+        let d = shape - 1.0 / 3.0;
+        let c = 1.0 / (9.0 * d).sqrt();
+        loop {
+            let z = self.normal();
+            let v = 1.0 + c * z;
+            if v <= 0.0 {
+                continue;
+            }
+            let v = v * v * v;
+            let u = self.uniform();
+            if u < 1.0 - 0.0331 * z.powi(4) || u.ln() < 0.5 * z * z + d * (1.0 - v + v.ln()) {
+                return d * v;
+            }
+        }
+    }
+}
+
+// Seed from a JS options value: an integer in 0..=u32::MAX, the range positional `seed`
+// arguments accept
+#[cfg(feature = "wasm")]
+pub(crate) fn parse_seed(value: &JsValue) -> Result<u32, Error> {
+    value
+        .as_f64()
+        .filter(|v| *v >= 0.0 && v.fract() == 0.0 && *v <= u32::MAX as f64)
+        .map(|v| v as u32)
+        .ok_or_else(|| Error::new("seed must be an integer between 0 and 4294967295"))
+}
+
+// Synthetic series shared by the unit tests, drawn from one `Rng` so every module exercises
+// the same processes
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::Rng;
+
+    // Fixed 80-point series that the statsmodels reference vectors of the ADF and KPSS tests
+    // are computed on; scripts/statsmodels_vectors.py holds a copy
+    pub(crate) const STATSMODELS_SERIES: [f64; 80] = [
+        9.561, 9.362, 8.781, 9.879, 11.229, 12.623, 13.149, 11.176, 9.944, 9.691, 10.7, 11.127,
+        12.443, 11.257, 10.163, 8.625, 7.436, 6.158, 7.381, 7.53, 8.372, 8.465, 8.241, 8.174,
+        8.271, 8.253, 6.736, 6.227, 7.228, 8.189, 7.631, 6.32, 7.51, 9.943, 9.516, 9.22, 11.944,
+        14.364, 12.88, 11.415, 12.17, 13.241, 13.815, 13.938, 14.241, 11.411, 10.092, 8.697, 9.3,
+        11.154, 12.25, 12.385, 12.276, 12.218, 10.812, 9.753, 9.716, 10.624, 9.965, 11.332, 12.496,
+        13.744, 14.442, 14.961, 13.798, 11.063, 8.244, 6.781, 6.11, 7.017, 6.619, 6.859, 7.157,
+        7.509, 8.618, 8.779, 7.842, 8.111, 10.333, 9.494,
+    ];
+
+    // AR(1) spread s_t = φ·s_{t-1} + ε_t; φ = 1 is a random walk
+    pub(crate) fn ar1(n: usize, phi: f64, seed: u32) -> Vec<f64> {
+        let mut rng = Rng::new(seed);
+        let mut s = vec![0.0; n];
+        for t in 1..n {
+            s[t] = phi * s[t - 1] + rng.normal();
+        }
+        s
+    }
+
+    // Cointegrated pair y = 3 + β·x + s with x a random walk from 50 and s an AR(1) spread with
+    // coefficient `phi` (0 for white noise), returned as (y, x, s)
+    pub(crate) fn cointegrated_pair(
+        n: usize,
+        beta: f64,
+        phi: f64,
+        seed: u32,
+    ) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+        let mut rng = Rng::new(seed);
+        let (mut x, mut s) = (vec![50.0; n], vec![0.0; n]);
+        for t in 1..n {
+            x[t] = x[t - 1] + rng.normal();
+            s[t] = phi * s[t - 1] + rng.normal();
+        }
+        let y = x.iter().zip(&s).map(|(x, s)| 3.0 + beta * x + s).collect();
+        (y, x, s)
+    }
+}
@@ -0,0 +1,1204 @@
+// Rolling-window versions of the single-series tests.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use std::collections::VecDeque;
+
+use crate::adf::{adf_p_value_and_critical_values, adf_regression, default_max_lag, Regression};
+use crate::buffer::Float64Buffer;
+use crate::error::Error;
+use crate::hurst::hurst_variance;
+use crate::json::JsonObject;
+use crate::linalg::{cholesky_into, cholesky_solve_into, CovarianceType};
+use crate::mackinnon::{mackinnon_critical_values, mackinnon_p_value};
+use crate::precision::Real;
+
+// Slots per window in the array returned by `rolling_adf`
+const ROLLING_ADF_STRIDE: usize = 4;
+// Slots per window in the array returned by `rolling_engle_granger`
+const ROLLING_EG_STRIDE: usize = 6;
+// Slots per window in the array returned by `rolling_hurst`
+const ROLLING_HURST_STRIDE: usize = 3;
+// Smallest window `rolling_hurst` accepts: its lags 2..=window/4 must give three points
+const MIN_HURST_WINDOW: usize = 16;
+
+/// ADF test (constant, Schwert's default lag for the window length) on every window
+/// `series[end + 1 - window..=end]`, advancing the window by `step` bars.
+///
+/// Returns a flat Float64Array of `(index, statistic, p_value, is_stationary)` tuples, four
+/// values per window, where `index` is the window's last bar and `is_stationary` is 1 or 0.
+/// Windows whose regression fails have NaN statistic and p-value. Watching the statistic drift
+/// towards zero is an early sign that a relationship is breaking down.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn rolling_adf(series: &[f64], window: usize, step: usize) -> Result<Vec<f64>, Error> {
+    let mut out = Vec::new();
+    rolling_adf_to(series, window, step, &mut out)?;
+    Ok(out)
+}
+
+/// `rolling_adf` of the values in `series`, written to `out` (resized to four values per window)
+/// instead of a new array. `out` must be a different buffer from `series`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn rolling_adf_into(
+    series: &Float64Buffer,
+    window: usize,
+    step: usize,
+    out: &mut Float64Buffer,
+) -> Result<(), Error> {
+    rolling_adf_to(series.as_slice(), window, step, out.output())
+}
+
+// `rolling_adf` written to `out`, replacing its contents
+fn rolling_adf_to(
+    series: &[f64],
+    window: usize,
+    step: usize,
+    out: &mut Vec<f64>,
+) -> Result<(), Error> {
+    out.clear();
+    if window < 3 {
+        return Err(Error::new("window must be at least 3"));
+    }
+    if step == 0 {
+        return Err(Error::new("step must be positive"));
+    }
+    adf_windows_to(series, window, step, false, out);
+    Ok(())
+}
+
+/// `rolling_adf` on expanding windows `series[0..=end]`, from the first `min_obs` bars and then
+/// every `step` bars, each with Schwert's default lag for its own length.
+///
+/// Returns the same `(index, statistic, p_value, is_stationary)` tuples as `rolling_adf`. Each
+/// value uses all the history up to its bar, so the statistic charts how the evidence against a
+/// unit root has accumulated rather than how it holds up locally.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn expanding_adf(series: &[f64], min_obs: usize, step: usize) -> Result<Vec<f64>, Error> {
+    if min_obs < 3 {
+        return Err(Error::new("min_obs must be at least 3"));
+    }
+    if step == 0 {
+        return Err(Error::new("step must be positive"));
+    }
+    let mut out = Vec::new();
+    adf_windows_to(series, min_obs, step, true, &mut out);
+    Ok(out)
+}
+
+// ADF tuples of windows of `window` bars, or from bar 0 when `expanding`, with their first end
+// at bar `window - 1` and then every `step` bars, appended to `out`
+fn adf_windows_to(series: &[f64], window: usize, step: usize, expanding: bool, out: &mut Vec<f64>) {
+    if window > series.len() {
+        return;
+    }
+
+    let regression = Regression::Constant;
+    let windows = (series.len() - window) / step + 1;
+    out.reserve(windows * ROLLING_ADF_STRIDE);
+    for first in (0..=series.len() - window).step_by(step) {
+        let end = first + window - 1;
+        let start = if expanding { 0 } else { first };
+        let fit = adf_regression(
+            &series[start..=end],
+            default_max_lag(end + 1 - start, regression),
+            regression,
+            CovarianceType::Classical,
+        );
+        let (statistic, p_value, is_stationary) = match fit {
+            Some(fit) => {
+                let (p_value, critical_values) = adf_p_value_and_critical_values(&fit);
+                let is_stationary = p_value.value <= 0.05 && fit.statistic < critical_values[1];
+                (fit.statistic, p_value.value, is_stationary)
+            }
+            None => (f64::NAN, f64::NAN, false),
+        };
+        out.extend_from_slice(&[
+            end as f64,
+            statistic,
+            p_value,
+            if is_stationary { 1.0 } else { 0.0 },
+        ]);
+    }
+}
+
+/// Engle-Granger test (constant in the cointegrating regression, Dickey-Fuller on its residuals,
+/// as `engle_granger`) on every window `[end + 1 - window, end]` of the pair, advancing the
+/// window by `step` bars.
+///
+/// Returns a flat Float64Array of `(index, hedge_ratio, intercept, statistic, p_value,
+/// is_cointegrated)` tuples, six values per window, where `index` is the window's last bar and
+/// `is_cointegrated` is 1 or 0. Windows where x or the residuals are degenerate have NaN in
+/// place of the test results. Rather than refitting each window, the means and co-moments both
+/// regressions need are updated as bars enter and leave, so the whole pass costs O(n)
+/// regardless of the window length; a hedge ratio that drifts while the statistic rises
+/// towards zero is the usual picture of a pair that is coming apart.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn rolling_engle_granger(
+    y: &[f64],
+    x: &[f64],
+    window: usize,
+    step: usize,
+) -> Result<Vec<f64>, Error> {
+    if window < 4 {
+        return Err(Error::new("window must be at least 4"));
+    }
+    engle_granger_windows(y, x, window, step, false)
+}
+
+/// `rolling_engle_granger` on expanding windows `[0, end]` of the pair, from the first `min_obs`
+/// bars and then every `step` bars, returning the same six-value tuples.
+///
+/// Each window uses all the history up to its bar, so a falling statistic shows the evidence for
+/// cointegration accumulating, and the hedge ratio settling shows the estimate stabilising. The
+/// moments only grow, so the whole pass is still O(n).
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn expanding_engle_granger(
+    y: &[f64],
+    x: &[f64],
+    min_obs: usize,
+    step: usize,
+) -> Result<Vec<f64>, Error> {
+    if min_obs < 4 {
+        return Err(Error::new("min_obs must be at least 4"));
+    }
+    engle_granger_windows(y, x, min_obs, step, true)
+}
+
+// Engine of `rolling_engle_granger` and `expanding_engle_granger`: windows of `window` bars, or
+// from bar 0 when `expanding`, with their first end at bar `window - 1` and then every `step` bars
+fn engle_granger_windows(
+    y: &[f64],
+    x: &[f64],
+    window: usize,
+    step: usize,
+    expanding: bool,
+) -> Result<Vec<f64>, Error> {
+    if y.len() != x.len() {
+        return Err(Error::new("y and x must have the same length"));
+    }
+    if step == 0 {
+        return Err(Error::new("step must be positive"));
+    }
+    if y.iter().chain(x).any(|v| !v.is_finite()) {
+        return Err(Error::new("y and x must be finite"));
+    }
+    if window > y.len() {
+        return Ok(Vec::new());
+    }
+
+    let regression = Regression::Constant;
+    // Rows of the residual regression: fixed for rolling windows, growing for expanding ones
+    let critical_values_for =
+        |rows: usize| mackinnon_critical_values(regression, 2, rows).unwrap_or([f64::NAN; 3]);
+    let rolling_critical_values = critical_values_for(window - 1);
+    // Levels over the whole window, for the cointegrating regression
+    let mut levels = WindowMoments::default();
+    // (y_{t-1}, x_{t-1}, Δy_t, Δx_t) for the window's Dickey-Fuller rows t = start + 1..=end
+    let mut rows = RowMoments::default();
+    let row = |t: usize| [y[t - 1], x[t - 1], y[t] - y[t - 1], x[t] - x[t - 1]];
+
+    let windows = (y.len() - window) / step + 1;
+    let mut out = Vec::with_capacity(windows * ROLLING_EG_STRIDE);
+    for end in 0..y.len() {
+        levels.add(x[end], y[end]);
+        if end >= 1 {
+            rows.add(row(end));
+        }
+        if !expanding && end >= window {
+            let start = end - window;
+            levels.remove(x[start], y[start]);
+            rows.remove(row(start + 1));
+        }
+        if end + 1 < window || !(end + 1 - window).is_multiple_of(step) {
+            continue;
+        }
+
+        let (hedge_ratio, intercept, statistic) = window_engle_granger(&levels, &rows);
+        let p_value = if statistic.is_nan() {
+            f64::NAN
+        } else {
+            mackinnon_p_value(statistic, regression, 2)
+        };
+        let critical_values = if expanding {
+            critical_values_for(end)
+        } else {
+            rolling_critical_values
+        };
+        let is_cointegrated = p_value <= 0.05 && statistic < critical_values[1];
+        out.extend_from_slice(&[
+            end as f64,
+            hedge_ratio,
+            intercept,
+            statistic,
+            p_value,
+            if is_cointegrated { 1.0 } else { 0.0 },
+        ]);
+    }
+    Ok(out)
+}
+
+// Hedge ratio, intercept and residual Dickey-Fuller statistic of one window from its moments.
+// With u = y - a - b·x every sum the residual regression needs is a quadratic form in the
+// tracked co-moments: Σu_{t-1}², ΣΔu_t·u_{t-1} and ΣΔu_t².
+fn window_engle_granger(levels: &WindowMoments, rows: &RowMoments) -> (f64, f64, f64) {
+    if !WindowMoments::varies(levels.m2_x, levels.mean_x, levels.n) {
+        return (f64::NAN, f64::NAN, f64::NAN);
+    }
+    let b = levels.c_xy / levels.m2_x;
+    let a = levels.mean_y - b * levels.mean_x;
+
+    // Weights picking u_{t-1} = y_{t-1} - b·x_{t-1} (minus a) and Δu_t = Δy_t - b·Δx_t
+    let lagged = [1.0, -b, 0.0, 0.0];
+    let change = [0.0, 0.0, 1.0, -b];
+    let m = rows.n as f64;
+    let lagged_mean = rows.combined_mean(&lagged) - a;
+    let change_mean = rows.combined_mean(&change);
+    let s_uu = rows.combined_cross(&lagged, &lagged) + m * lagged_mean * lagged_mean;
+    let s_du = rows.combined_cross(&change, &lagged) + m * change_mean * lagged_mean;
+    let s_dd = rows.combined_cross(&change, &change) + m * change_mean * change_mean;
+    if s_uu <= 0.0 {
+        return (b, a, f64::NAN);
+    }
+
+    let rho = s_du / s_uu;
+    let sigma2 = (s_dd - rho * s_du).max(0.0) / (m - 1.0);
+    let statistic = rho / (sigma2 / s_uu).sqrt();
+    (
+        b,
+        a,
+        if statistic.is_finite() {
+            statistic
+        } else {
+            f64::NAN
+        },
+    )
+}
+
+// Means and co-moments of the pairs currently in a window, updated in O(1) as pairs enter and
+// leave (Welford's updates, which avoid the cancellation of raw sums of squares on price levels)
+#[derive(Default)]
+struct WindowMoments {
+    n: usize,
+    mean_x: f64,
+    mean_y: f64,
+    m2_x: f64,
+    m2_y: f64,
+    c_xy: f64,
+}
+
+impl WindowMoments {
+    fn add(&mut self, x: f64, y: f64) {
+        self.n += 1;
+        let n = self.n as f64;
+        let dx = x - self.mean_x;
+        let dy = y - self.mean_y;
+        self.mean_x += dx / n;
+        self.mean_y += dy / n;
+        self.m2_x += dx * (x - self.mean_x);
+        self.m2_y += dy * (y - self.mean_y);
+        self.c_xy += dx * (y - self.mean_y);
+    }
+
+    fn remove(&mut self, x: f64, y: f64) {
+        self.n -= 1;
+        if self.n == 0 {
+            *self = WindowMoments::default();
+            return;
+        }
+        let n = self.n as f64;
+        let (old_mean_x, old_mean_y) = (self.mean_x, self.mean_y);
+        self.mean_x -= (x - old_mean_x) / n;
+        self.mean_y -= (y - old_mean_y) / n;
+        self.m2_x -= (x - self.mean_x) * (x - old_mean_x);
+        self.m2_y -= (y - self.mean_y) * (y - old_mean_y);
+        self.c_xy -= (x - self.mean_x) * (y - old_mean_y);
+    }
+
+    // Incremental updates leave rounding residue where an exact computation gives zero, so a
+    // series whose variance is below ~1e-12 of its squared level counts as constant
+    fn varies(m2: f64, mean: f64, n: usize) -> bool {
+        m2 > 1e-12 * n as f64 * mean * mean && m2 > 0.0
+    }
+}
+
+/// Hurst exponent (variance-of-differences method, as `hurst_exponent` with "variance", over
+/// lags 2 to window / 4) of every window `series[end + 1 - window..=end]`, advancing the window
+/// by `step` bars.
+///
+/// Returns a flat Float64Array of `(index, exponent, r_squared)` tuples, three values per
+/// window, where `index` is the window's last bar. Windows with fewer than three usable lags
+/// (a flat stretch, or NaN values) have NaN exponent and R². Charted over time, a spread's
+/// exponent moving from below 0.5 towards or above it marks the shift from mean reversion to
+/// trending.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn rolling_hurst(series: &[f64], window: usize, step: usize) -> Result<Vec<f64>, Error> {
+    if window < MIN_HURST_WINDOW {
+        return Err(Error::new(&format!(
+            "window must be at least {}",
+            MIN_HURST_WINDOW
+        )));
+    }
+    if step == 0 {
+        return Err(Error::new("step must be positive"));
+    }
+    if window > series.len() {
+        return Ok(Vec::new());
+    }
+
+    let max_lag = window / 4;
+    let windows = (series.len() - window) / step + 1;
+    let mut out = Vec::with_capacity(windows * ROLLING_HURST_STRIDE);
+    for start in (0..=series.len() - window).step_by(step) {
+        let end = start + window - 1;
+        let (exponent, r_squared) = match hurst_variance(&series[start..=end], 2, max_lag) {
+            Some(fit) if fit.exponent.is_finite() => (fit.exponent, fit.r_squared),
+            _ => (f64::NAN, f64::NAN),
+        };
+        out.extend_from_slice(&[end as f64, exponent, r_squared]);
+    }
+    Ok(out)
+}
+
+/// Pearson correlation of `x` and `y` over a trailing `window` of bars (current bar included).
+///
+/// Aligned with the input: the first `window - 1` entries, windows containing a NaN or infinite
+/// value and windows where either series is constant are NaN. The window statistics are updated
+/// incrementally as bars enter and leave, so the cost is O(n) regardless of the window length.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn rolling_correlation(x: &[f64], y: &[f64], window: usize) -> Result<Vec<f64>, Error> {
+    let mut out = Vec::new();
+    rolling_moments(x, y, window, correlation_of, &mut out)?;
+    Ok(out)
+}
+
+/// `rolling_correlation` of the values in `x` and `y`, written to `out` (resized to their
+/// length) instead of a new array. `out` must be a different buffer from the inputs.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn rolling_correlation_into(
+    x: &Float64Buffer,
+    y: &Float64Buffer,
+    window: usize,
+    out: &mut Float64Buffer,
+) -> Result<(), Error> {
+    rolling_moments(
+        x.as_slice(),
+        y.as_slice(),
+        window,
+        correlation_of,
+        out.output(),
+    )
+}
+
+/// Hedge ratio Cov(y, x) / Var(x) over a trailing `window` of bars, i.e. the OLS slope of y on
+/// x with an intercept in each window (see `min_variance_hedge_ratio`).
+///
+/// Aligned with the input like `rolling_correlation`; windows where x is constant are NaN.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn rolling_beta(y: &[f64], x: &[f64], window: usize) -> Result<Vec<f64>, Error> {
+    let mut out = Vec::new();
+    rolling_moments(x, y, window, beta_of, &mut out)?;
+    Ok(out)
+}
+
+/// `rolling_beta` of the values in `y` on those in `x`, written to `out` (resized to their
+/// length) instead of a new array. `out` must be a different buffer from the inputs.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn rolling_beta_into(
+    y: &Float64Buffer,
+    x: &Float64Buffer,
+    window: usize,
+    out: &mut Float64Buffer,
+) -> Result<(), Error> {
+    rolling_moments(x.as_slice(), y.as_slice(), window, beta_of, out.output())
+}
+
+/// `rolling_correlation` of two Float32Arrays, returned as one: half the memory of
+/// `rolling_correlation`, with the window moments still accumulated in f64.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn rolling_correlation_f32(x: &[f32], y: &[f32], window: usize) -> Result<Vec<f32>, Error> {
+    let mut out = Vec::new();
+    rolling_moments(x, y, window, correlation_of, &mut out)?;
+    Ok(out)
+}
+
+/// `rolling_beta` of two Float32Arrays, returned as one: half the memory of `rolling_beta`, with
+/// the window moments still accumulated in f64.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn rolling_beta_f32(y: &[f32], x: &[f32], window: usize) -> Result<Vec<f32>, Error> {
+    let mut out = Vec::new();
+    rolling_moments(x, y, window, beta_of, &mut out)?;
+    Ok(out)
+}
+
+/// Pearson correlation of `x` and `y` over all the bars up to and including each bar, from the
+/// first `min_obs` bars on.
+///
+/// Aligned with the input like `rolling_correlation`: the first `min_obs - 1` entries are NaN,
+/// and once a NaN or infinite value has entered the history every later entry is NaN.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn expanding_correlation(x: &[f64], y: &[f64], min_obs: usize) -> Result<Vec<f64>, Error> {
+    if min_obs < 2 {
+        return Err(Error::new("min_obs must be at least 2"));
+    }
+    let mut out = Vec::new();
+    moment_windows(x, y, min_obs, true, correlation_of, &mut out)?;
+    Ok(out)
+}
+
+/// Hedge ratio Cov(y, x) / Var(x) over all the bars up to and including each bar, from the first
+/// `min_obs` bars on: the full-sample OLS slope as it would have been estimated at that bar.
+///
+/// Aligned with the input like `expanding_correlation`; bars where x has been constant so far are
+/// NaN.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn expanding_beta(y: &[f64], x: &[f64], min_obs: usize) -> Result<Vec<f64>, Error> {
+    if min_obs < 2 {
+        return Err(Error::new("min_obs must be at least 2"));
+    }
+    let mut out = Vec::new();
+    moment_windows(x, y, min_obs, true, beta_of, &mut out)?;
+    Ok(out)
+}
+
+// Slope and intercept of the OLS regression of y on x over a trailing `window`, aligned with the
+// input and NaN where `rolling_beta` is
+pub(crate) fn rolling_regression(
+    y: &[f64],
+    x: &[f64],
+    window: usize,
+) -> Result<(Vec<f64>, Vec<f64>), Error> {
+    let (mut betas, mut intercepts) = (Vec::new(), Vec::new());
+    rolling_moments(x, y, window, beta_of, &mut betas)?;
+    rolling_moments(
+        x,
+        y,
+        window,
+        |m| m.mean_y - beta_of(m) * m.mean_x,
+        &mut intercepts,
+    )?;
+    Ok((betas, intercepts))
+}
+
+fn correlation_of(m: &WindowMoments) -> f64 {
+    if WindowMoments::varies(m.m2_x, m.mean_x, m.n) && WindowMoments::varies(m.m2_y, m.mean_y, m.n)
+    {
+        (m.c_xy / (m.m2_x * m.m2_y).sqrt()).clamp(-1.0, 1.0)
+    } else {
+        f64::NAN
+    }
+}
+
+fn beta_of(m: &WindowMoments) -> f64 {
+    if WindowMoments::varies(m.m2_x, m.mean_x, m.n) {
+        m.c_xy / m.m2_x
+    } else {
+        f64::NAN
+    }
+}
+
+// Slide a window over the pairs, evaluating `statistic` on every window of finite pairs and
+// writing the results to `out` in the storage precision of `T`. Other pairs are kept out of the
+// moments and only counted, so a gap does not poison later windows.
+fn rolling_moments<T: Real>(
+    x: &[T],
+    y: &[T],
+    window: usize,
+    statistic: impl Fn(&WindowMoments) -> f64,
+    out: &mut Vec<T>,
+) -> Result<(), Error> {
+    if window < 2 {
+        return Err(Error::new("window must be at least 2"));
+    }
+    moment_windows(x, y, window, false, statistic, out)
+}
+
+// Engine of `rolling_moments` and the expanding statistics: windows of `window` bars, or from
+// bar 0 when `expanding`, evaluated from bar `window - 1` on
+fn moment_windows<T: Real>(
+    x: &[T],
+    y: &[T],
+    window: usize,
+    expanding: bool,
+    statistic: impl Fn(&WindowMoments) -> f64,
+    out: &mut Vec<T>,
+) -> Result<(), Error> {
+    if x.len() != y.len() {
+        return Err(Error::new("x and y must have the same length"));
+    }
+
+    let valid = |i: usize| x[i].widen().is_finite() && y[i].widen().is_finite();
+    out.clear();
+    out.resize(x.len(), T::narrow(f64::NAN));
+    let mut moments = WindowMoments::default();
+    let mut missing = 0;
+    for i in 0..x.len() {
+        if valid(i) {
+            moments.add(x[i].widen(), y[i].widen());
+        } else {
+            missing += 1;
+        }
+        if !expanding && i >= window {
+            let leaving = i - window;
+            if valid(leaving) {
+                moments.remove(x[leaving].widen(), y[leaving].widen());
+            } else {
+                missing -= 1;
+            }
+        }
+        if i + 1 >= window && missing == 0 {
+            out[i] = T::narrow(statistic(&moments));
+        }
+    }
+    Ok(())
+}
+
+// Means and co-moment matrix of the Dickey-Fuller rows in a window, with the same incremental
+// updates as `WindowMoments`
+#[derive(Default)]
+struct RowMoments {
+    n: usize,
+    mean: [f64; 4],
+    cross: [[f64; 4]; 4],
+}
+
+impl RowMoments {
+    fn add(&mut self, row: [f64; 4]) {
+        self.n += 1;
+        let n = self.n as f64;
+        let delta: [f64; 4] = std::array::from_fn(|i| row[i] - self.mean[i]);
+        for (mean, d) in self.mean.iter_mut().zip(&delta) {
+            *mean += d / n;
+        }
+        for (i, cross) in self.cross.iter_mut().enumerate() {
+            for (j, c) in cross.iter_mut().enumerate() {
+                *c += delta[i] * (row[j] - self.mean[j]);
+            }
+        }
+    }
+
+    fn remove(&mut self, row: [f64; 4]) {
+        self.n -= 1;
+        if self.n == 0 {
+            *self = RowMoments::default();
+            return;
+        }
+        let n = self.n as f64;
+        let old_mean = self.mean;
+        for (mean, v) in self.mean.iter_mut().zip(&row) {
+            *mean -= (v - *mean) / n;
+        }
+        for (i, cross) in self.cross.iter_mut().enumerate() {
+            for (j, c) in cross.iter_mut().enumerate() {
+                *c -= (row[i] - self.mean[i]) * (row[j] - old_mean[j]);
+            }
+        }
+    }
+
+    // Mean of the linear combination w·row
+    fn combined_mean(&self, w: &[f64; 4]) -> f64 {
+        w.iter().zip(&self.mean).map(|(w, m)| w * m).sum()
+    }
+
+    // Centred cross-product Σ(v·row - mean)(w·row - mean) of two linear combinations
+    fn combined_cross(&self, v: &[f64; 4], w: &[f64; 4]) -> f64 {
+        let mut sum = 0.0;
+        for (i, cross) in self.cross.iter().enumerate() {
+            for (j, c) in cross.iter().enumerate() {
+                sum += v[i] * w[j] * c;
+            }
+        }
+        sum
+    }
+}
+
+// Slots per window in the arrays returned by `rolling_apply` and `expanding_apply`
+const ROLLING_APPLY_STRIDE: usize = 2;
+
+// Statistic evaluated on each window by `rolling_apply`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RollingStatistic {
+    Mean,
+    // Sample standard deviation (divisor n - 1)
+    Std,
+    Min,
+    Max,
+    // Skewness from the biased central moments, as `skewness`
+    Skew,
+    // Last value of the window in standard deviations from the window mean
+    ZScore,
+    // ADF statistic with a constant and Schwert's default lag for the window length
+    Adf,
+}
+
+impl RollingStatistic {
+    pub fn parse(stat: &str) -> Result<RollingStatistic, Error> {
+        match stat {
+            "mean" => Ok(RollingStatistic::Mean),
+            "std" => Ok(RollingStatistic::Std),
+            "min" => Ok(RollingStatistic::Min),
+            "max" => Ok(RollingStatistic::Max),
+            "skew" => Ok(RollingStatistic::Skew),
+            "zscore" => Ok(RollingStatistic::ZScore),
+            "adf" => Ok(RollingStatistic::Adf),
+            other => Err(Error::new(&format!(
+                "unknown statistic \"{}\": expected \"mean\", \"std\", \"min\", \"max\", \"skew\", \"zscore\" or \"adf\"",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RollingStatistic::Mean => "mean",
+            RollingStatistic::Std => "std",
+            RollingStatistic::Min => "min",
+            RollingStatistic::Max => "max",
+            RollingStatistic::Skew => "skew",
+            RollingStatistic::ZScore => "zscore",
+            RollingStatistic::Adf => "adf",
+        }
+    }
+
+    // Fewest observations for which the statistic is defined
+    fn min_window(&self) -> usize {
+        match self {
+            RollingStatistic::Mean | RollingStatistic::Min | RollingStatistic::Max => 1,
+            RollingStatistic::Std | RollingStatistic::Skew | RollingStatistic::ZScore => 2,
+            RollingStatistic::Adf => 3,
+        }
+    }
+}
+
+/// `stat` ("mean", "std", "min", "max", "skew", "zscore" or "adf") of every window
+/// `series[end + 1 - window..=end]`, advancing the window by `step` bars.
+///
+/// Returns a flat Float64Array of `(index, value)` pairs, two values per window, where `index`
+/// is the window's last bar; `rolling_apply(s, w, 1, "zscore")` holds the defined entries of
+/// `zscore(s, w)`. Windows containing a NaN or infinite value, and windows where the statistic
+/// is undefined (a constant window for "std", "skew" and "zscore", a failed regression for
+/// "adf"), are NaN. The moments and the running minimum and maximum are updated as bars enter
+/// and leave, so every statistic but "adf" costs O(n) whatever the window length; "adf" fits
+/// each emitted window as `rolling_adf` does and reports its statistic.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn rolling_apply(
+    series: &[f64],
+    window: usize,
+    step: usize,
+    stat: &str,
+) -> Result<Vec<f64>, Error> {
+    let stat = RollingStatistic::parse(stat)?;
+    if window < stat.min_window() {
+        return Err(Error::new(&format!(
+            "window must be at least {} for \"{}\"",
+            stat.min_window(),
+            stat.as_str()
+        )));
+    }
+    apply_windows(series, window, step, stat, false)
+}
+
+/// `rolling_apply` on expanding windows `series[0..=end]`, from the first `min_periods` bars
+/// and then every `step` bars, so each value uses all the history up to its bar.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn expanding_apply(
+    series: &[f64],
+    min_periods: usize,
+    step: usize,
+    stat: &str,
+) -> Result<Vec<f64>, Error> {
+    let stat = RollingStatistic::parse(stat)?;
+    if min_periods < stat.min_window() {
+        return Err(Error::new(&format!(
+            "min_periods must be at least {} for \"{}\"",
+            stat.min_window(),
+            stat.as_str()
+        )));
+    }
+    apply_windows(series, min_periods, step, stat, true)
+}
+
+// Engine of `rolling_apply` and `expanding_apply`: windows of `window` bars, or from bar 0 when
+// `expanding`, with their first end at bar `window - 1` and then every `step` bars
+fn apply_windows(
+    series: &[f64],
+    window: usize,
+    step: usize,
+    stat: RollingStatistic,
+    expanding: bool,
+) -> Result<Vec<f64>, Error> {
+    if step == 0 {
+        return Err(Error::new("step must be positive"));
+    }
+    if window > series.len() {
+        return Ok(Vec::new());
+    }
+
+    let windows = (series.len() - window) / step + 1;
+    let mut out = Vec::with_capacity(windows * ROLLING_APPLY_STRIDE);
+    let mut moments = SeriesMoments::default();
+    // Indices of the window whose values rise (for the minimum) or fall (for the maximum) from
+    // the front, so the front is the extreme
+    let mut lows: VecDeque<usize> = VecDeque::new();
+    let mut highs: VecDeque<usize> = VecDeque::new();
+    let mut missing = 0;
+    let incremental = stat != RollingStatistic::Adf;
+    for end in 0..series.len() {
+        let value = series[end];
+        let start = if expanding {
+            0
+        } else {
+            (end + 1).saturating_sub(window)
+        };
+        if incremental {
+            if value.is_finite() {
+                moments.add(value);
+                while lows.back().is_some_and(|&i| series[i] >= value) {
+                    lows.pop_back();
+                }
+                lows.push_back(end);
+                while highs.back().is_some_and(|&i| series[i] <= value) {
+                    highs.pop_back();
+                }
+                highs.push_back(end);
+            } else {
+                missing += 1;
+            }
+            if !expanding && end >= window {
+                let leaving = series[end - window];
+                if leaving.is_finite() {
+                    moments.remove(leaving);
+                } else {
+                    missing -= 1;
+                }
+            }
+            while lows.front().is_some_and(|&i| i < start) {
+                lows.pop_front();
+            }
+            while highs.front().is_some_and(|&i| i < start) {
+                highs.pop_front();
+            }
+        } else if !value.is_finite() {
+            missing += 1;
+        }
+        if !expanding && !incremental && end >= window && !series[end - window].is_finite() {
+            missing -= 1;
+        }
+
+        if end + 1 < window || !(end + 1 - window).is_multiple_of(step) {
+            continue;
+        }
+        let result = if missing > 0 {
+            f64::NAN
+        } else {
+            match stat {
+                RollingStatistic::Mean => moments.mean,
+                RollingStatistic::Std => moments.std(),
+                RollingStatistic::Min => series[lows[0]],
+                RollingStatistic::Max => series[highs[0]],
+                RollingStatistic::Skew => moments.skewness(),
+                RollingStatistic::ZScore => (value - moments.mean) / moments.std(),
+                RollingStatistic::Adf => window_adf_statistic(&series[start..=end]),
+            }
+        };
+        out.extend_from_slice(&[end as f64, result]);
+    }
+    Ok(out)
+}
+
+// ADF statistic of one window, as in `rolling_adf`; NaN when the regression fails
+fn window_adf_statistic(window: &[f64]) -> f64 {
+    let regression = Regression::Constant;
+    let lags = default_max_lag(window.len(), regression);
+    adf_regression(window, lags, regression, CovarianceType::Classical)
+        .map_or(f64::NAN, |fit| fit.statistic)
+}
+
+// Mean and second and third central sums of the values in a window, with the same incremental
+// updates as `WindowMoments` extended to the third moment
+#[derive(Default)]
+struct SeriesMoments {
+    n: usize,
+    mean: f64,
+    m2: f64,
+    m3: f64,
+}
+
+impl SeriesMoments {
+    fn add(&mut self, x: f64) {
+        let previous = self.n as f64;
+        self.n += 1;
+        let n = self.n as f64;
+        let delta = x - self.mean;
+        let delta_n = delta / n;
+        let term = delta * delta_n * previous;
+        self.mean += delta_n;
+        self.m3 += term * delta_n * (n - 2.0) - 3.0 * delta_n * self.m2;
+        self.m2 += term;
+    }
+
+    fn remove(&mut self, x: f64) {
+        if self.n <= 1 {
+            *self = SeriesMoments::default();
+            return;
+        }
+        let n = self.n as f64;
+        let previous = n - 1.0;
+        self.n -= 1;
+        let old_mean = (n * self.mean - x) / previous;
+        let delta = x - old_mean;
+        let delta_n = delta / n;
+        let term = delta * delta_n * previous;
+        self.mean = old_mean;
+        self.m2 -= term;
+        self.m3 -= term * delta_n * (n - 2.0) - 3.0 * delta_n * self.m2;
+    }
+
+    fn std(&self) -> f64 {
+        if WindowMoments::varies(self.m2, self.mean, self.n) {
+            (self.m2 / (self.n - 1) as f64).sqrt()
+        } else {
+            f64::NAN
+        }
+    }
+
+    fn skewness(&self) -> f64 {
+        if WindowMoments::varies(self.m2, self.mean, self.n) {
+            let n = self.n as f64;
+            (self.m3 / n) / (self.m2 / n).powf(1.5)
+        } else {
+            f64::NAN
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct RollingOls {
+    // Regressors besides the intercept
+    pub n_regressors: usize,
+    pub window: usize,
+    intercepts: Vec<f64>,
+    coefficients: Vec<f64>,
+    r_squared: Vec<f64>,
+    residuals: Vec<f64>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl RollingOls {
+    // Intercept of the regression on the window ending at each bar
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn intercepts(&self) -> Vec<f64> {
+        self.intercepts.clone()
+    }
+
+    // n_regressors slopes per bar, row-major, in the column order of the regressors
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn coefficients(&self) -> Vec<f64> {
+        self.coefficients.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn r_squared(&self) -> Vec<f64> {
+        self.r_squared.clone()
+    }
+
+    // y minus the fit of the window ending at the same bar: the rolling-hedge spread
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn residuals(&self) -> Vec<f64> {
+        self.residuals.clone()
+    }
+
+    /// The slope on regressor `j` at every bar.
+    pub fn coefficient(&self, j: usize) -> Result<Vec<f64>, Error> {
+        if j >= self.n_regressors {
+            return Err(Error::new("j must be less than n_regressors"));
+        }
+        Ok(self
+            .coefficients
+            .iter()
+            .skip(j)
+            .step_by(self.n_regressors)
+            .copied()
+            .collect())
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("n_regressors", self.n_regressors)
+            .field("window", self.window)
+            .field("intercepts", &self.intercepts)
+            .field("coefficients", &self.coefficients)
+            .field("r_squared", &self.r_squared)
+            .field("residuals", &self.residuals)
+            .finish()
+    }
+}
+
+/// OLS regression of `y` on an intercept and `n_regressors` regressors over a trailing `window`
+/// of bars (current bar included), such as a hedge against several legs at once.
+///
+/// `regressors` holds one row of `n_regressors` values per bar, row-major. Aligned with the
+/// input: the first `window - 1` bars, windows containing a NaN or infinite value and windows
+/// where the regressors are collinear or constant are NaN. The means and the centred
+/// cross-product matrix of the window are updated by a rank-one step as each bar enters and
+/// leaves, and only the k x k system is solved per bar, so the cost is O(n·k³) whatever the
+/// window length; a 30,000-bar series with a 200-bar window takes a few milliseconds.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn rolling_ols(
+    y: &[f64],
+    regressors: &[f64],
+    n_regressors: usize,
+    window: usize,
+) -> Result<RollingOls, Error> {
+    let k = n_regressors;
+    if k == 0 {
+        return Err(Error::new("n_regressors must be at least 1"));
+    }
+    if regressors.len() != y.len() * k {
+        return Err(Error::new(
+            "regressors must hold n_regressors values per observation of y",
+        ));
+    }
+    if window < k + 2 {
+        return Err(Error::new("window must exceed n_regressors + 1"));
+    }
+
+    let n = y.len();
+    // Regressors then y of bar t, in a buffer reused across bars
+    let mut buffer = vec![0.0; k + 1];
+    let row = |buffer: &mut Vec<f64>, t: usize| {
+        buffer[..k].copy_from_slice(&regressors[t * k..(t + 1) * k]);
+        buffer[k] = y[t];
+    };
+    let valid =
+        |t: usize| y[t].is_finite() && regressors[t * k..(t + 1) * k].iter().all(|v| v.is_finite());
+    let mut result = RollingOls {
+        n_regressors: k,
+        window,
+        intercepts: vec![f64::NAN; n],
+        coefficients: vec![f64::NAN; n * k],
+        r_squared: vec![f64::NAN; n],
+        residuals: vec![f64::NAN; n],
+    };
+    let mut moments = Comoments::new(k + 1);
+    let mut slopes = vec![0.0; k];
+    let mut missing = 0;
+    for t in 0..n {
+        if valid(t) {
+            row(&mut buffer, t);
+            moments.add(&buffer);
+        } else {
+            missing += 1;
+        }
+        if t >= window {
+            let leaving = t - window;
+            if valid(leaving) {
+                row(&mut buffer, leaving);
+                moments.remove(&buffer);
+            } else {
+                missing -= 1;
+            }
+        }
+        if t + 1 < window || missing > 0 {
+            continue;
+        }
+        if let Some((intercept, r_squared)) = moments.regression(&mut slopes) {
+            let fitted: f64 = intercept
+                + slopes
+                    .iter()
+                    .zip(&regressors[t * k..(t + 1) * k])
+                    .map(|(b, x)| b * x)
+                    .sum::<f64>();
+            result.intercepts[t] = intercept;
+            result.coefficients[t * k..(t + 1) * k].copy_from_slice(&slopes);
+            result.r_squared[t] = r_squared;
+            result.residuals[t] = y[t] - fitted;
+        }
+    }
+    Ok(result)
+}
+
+// Means and centred cross-products of `dim`-vectors in a window, the general form of
+// `RowMoments`, updated by rank-one steps as vectors enter and leave
+struct Comoments {
+    dim: usize,
+    n: usize,
+    mean: Vec<f64>,
+    // dim x dim, row-major
+    cross: Vec<f64>,
+    // Deviations from the mean before an update
+    delta: Vec<f64>,
+    // The regression's normal equations and the Cholesky factor of sxx, reused across windows
+    sxx: Vec<f64>,
+    sxy: Vec<f64>,
+    factor: Vec<f64>,
+}
+
+impl Comoments {
+    fn new(dim: usize) -> Comoments {
+        Comoments {
+            dim,
+            n: 0,
+            mean: vec![0.0; dim],
+            cross: vec![0.0; dim * dim],
+            delta: vec![0.0; dim],
+            sxx: vec![0.0; (dim - 1) * (dim - 1)],
+            sxy: vec![0.0; dim - 1],
+            factor: vec![0.0; (dim - 1) * (dim - 1)],
+        }
+    }
+
+    fn add(&mut self, row: &[f64]) {
+        self.n += 1;
+        let n = self.n as f64;
+        for ((d, v), mean) in self.delta.iter_mut().zip(row).zip(self.mean.iter_mut()) {
+            *d = v - *mean;
+            *mean += *d / n;
+        }
+        for (cross, d) in self.cross.chunks_exact_mut(self.dim).zip(&self.delta) {
+            for ((c, v), m) in cross.iter_mut().zip(row).zip(&self.mean) {
+                *c += d * (v - m);
+            }
+        }
+    }
+
+    fn remove(&mut self, row: &[f64]) {
+        self.n -= 1;
+        if self.n == 0 {
+            *self = Comoments::new(self.dim);
+            return;
+        }
+        let n = self.n as f64;
+        // delta holds the deviations from the old mean, and the mean is then updated
+        for ((d, v), mean) in self.delta.iter_mut().zip(row).zip(self.mean.iter_mut()) {
+            *d = v - *mean;
+            *mean -= *d / n;
+        }
+        for (cross, (v, m)) in self
+            .cross
+            .chunks_exact_mut(self.dim)
+            .zip(row.iter().zip(&self.mean))
+        {
+            for (c, d) in cross.iter_mut().zip(&self.delta) {
+                *c -= (v - m) * d;
+            }
+        }
+    }
+
+    // Intercept, slopes and R² of the last component on the others, or None when the others
+    // are constant or collinear in the window
+    fn regression(&mut self, slopes: &mut [f64]) -> Option<(f64, f64)> {
+        let (k, dim) = (self.dim - 1, self.dim);
+        let at = |i: usize, j: usize| self.cross[i * dim + j];
+        if (0..k).any(|i| !WindowMoments::varies(at(i, i), self.mean[i], self.n)) {
+            return None;
+        }
+        for (c, s) in self.sxx.iter_mut().enumerate() {
+            *s = self.cross[(c / k) * dim + c % k];
+        }
+        for (i, s) in self.sxy.iter_mut().enumerate() {
+            *s = self.cross[i * dim + k];
+        }
+        if !cholesky_into(&self.sxx, k, &mut self.factor) {
+            return None;
+        }
+        cholesky_solve_into(&self.factor, k, &self.sxy, slopes);
+        let intercept = self.mean[k]
+            - slopes
+                .iter()
+                .zip(&self.mean)
+                .map(|(b, m)| b * m)
+                .sum::<f64>();
+        let syy = at(k, k);
+        let explained: f64 = slopes.iter().zip(&self.sxy).map(|(b, s)| b * s).sum();
+        let r_squared = if syy > 0.0 {
+            (explained / syy).clamp(0.0, 1.0)
+        } else {
+            f64::NAN
+        };
+        Some((intercept, r_squared))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adf::adf_test;
+    use crate::coint::engle_granger;
+    use crate::hurst::hurst_exponent;
+    use crate::rng::fixtures::{ar1, cointegrated_pair};
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-8 * b.abs().max(1.0)
+    }
+
+    #[test]
+    fn adf_windows_match_the_one_shot_test() {
+        let series = ar1(300, 0.9, 11);
+        let (window, step) = (100, 7);
+        let rolling = rolling_adf(&series, window, step).unwrap();
+        let expanding = expanding_adf(&series, window, step).unwrap();
+        assert_eq!(rolling.len(), ((300 - window) / step + 1) * ROLLING_ADF_STRIDE);
+        assert_eq!(expanding.len(), rolling.len());
+        for (k, (tuple, grown)) in rolling
+            .chunks_exact(ROLLING_ADF_STRIDE)
+            .zip(expanding.chunks_exact(ROLLING_ADF_STRIDE))
+            .enumerate()
+        {
+            let end = window - 1 + k * step;
+            for (tuple, start) in [(tuple, end + 1 - window), (grown, 0)] {
+                let single = adf_test(&series[start..=end], None).unwrap();
+                assert_eq!(tuple[0], end as f64);
+                assert_eq!(tuple[1], single.statistic, "window ending at {}", end);
+                assert_eq!(tuple[2], single.p_value, "window ending at {}", end);
+                assert_eq!(tuple[3] == 1.0, single.is_stationary);
+            }
+        }
+    }
+
+    #[test]
+    fn engle_granger_windows_match_the_one_shot_test() {
+        let (y, x, _) = cointegrated_pair(400, 1.5, 0.6, 12);
+        let (window, step) = (120, 9);
+        let rolling = rolling_engle_granger(&y, &x, window, step).unwrap();
+        let expanding = expanding_engle_granger(&y, &x, window, step).unwrap();
+        assert_eq!(rolling.len(), ((400 - window) / step + 1) * ROLLING_EG_STRIDE);
+        assert_eq!(expanding.len(), rolling.len());
+        for (k, (tuple, grown)) in rolling
+            .chunks_exact(ROLLING_EG_STRIDE)
+            .zip(expanding.chunks_exact(ROLLING_EG_STRIDE))
+            .enumerate()
+        {
+            let end = window - 1 + k * step;
+            for (tuple, start) in [(tuple, end + 1 - window), (grown, 0)] {
+                let single = engle_granger(&y[start..=end], &x[start..=end]).unwrap();
+                let case = format!("window {}..={}: {:?}", start, end, tuple);
+                assert_eq!(tuple[0], end as f64);
+                assert!(close(tuple[1], single.hedge_ratio), "{}", case);
+                assert!(close(tuple[2], single.intercept), "{}", case);
+                assert!(close(tuple[3], single.statistic), "{}", case);
+                assert!(close(tuple[4], single.p_value), "{}", case);
+                assert_eq!(tuple[5] == 1.0, single.is_cointegrated, "{}", case);
+            }
+        }
+        // The p-values of the first two windows sit at slots 4 and 10, where `breakdown_risk`
+        // reads them
+        let first = engle_granger(&y[..window], &x[..window]).unwrap();
+        let second = engle_granger(&y[step..step + window], &x[step..step + window]).unwrap();
+        assert!(close(rolling[4], first.p_value) && close(rolling[10], second.p_value));
+    }
+
+    #[test]
+    fn hurst_windows_match_the_one_shot_exponent() {
+        let series = ar1(250, 0.95, 13);
+        let (window, step) = (64, 10);
+        let rolling = rolling_hurst(&series, window, step).unwrap();
+        assert_eq!(rolling.len(), ((250 - window) / step + 1) * ROLLING_HURST_STRIDE);
+        for (k, tuple) in rolling.chunks_exact(ROLLING_HURST_STRIDE).enumerate() {
+            let (start, end) = (k * step, k * step + window - 1);
+            let single = hurst_exponent(&series[start..=end], 2, window / 4, "variance").unwrap();
+            assert_eq!(tuple, [end as f64, single.exponent, single.r_squared]);
+        }
+    }
+}
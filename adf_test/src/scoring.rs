@@ -0,0 +1,417 @@
+// Ranking of candidate pairs by a weighted score of their screening metrics.
+//
+// The metrics a screen produces live on unrelated scales (a p-value, a half-life in
+// bars, a Sharpe ratio), so each is first mapped to a component in [0, 1] that is
+// higher for the better pair: by default its percentile rank across the candidates,
+// which no single outlier can stretch, or optionally a min-max rescaling. The score
+// is the weighted mean of the components, and the scoreboard lists the pairs best
+// first.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::correlation::average_ranks;
+use crate::error::Error;
+#[cfg(feature = "wasm")]
+use crate::get_property;
+use crate::json::{raw_array, JsonObject, RawJson};
+
+// Component names, in the order of `PairScore::components`
+const COMPONENTS: [&str; 5] = [
+    "p_value",
+    "half_life",
+    "hurst",
+    "spread_volatility",
+    "sharpe",
+];
+// Whether a higher value of each metric is better
+const HIGHER_IS_BETTER: [bool; 5] = [false, false, false, true, true];
+
+// How a metric is mapped to its [0, 1] component
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScoreNormalization {
+    // Percentile rank across the pairs, ties sharing their average rank ("rank")
+    Rank,
+    // Linear from the worst value (0) to the best (1) ("minmax")
+    MinMax,
+}
+
+impl ScoreNormalization {
+    pub fn parse(normalization: &str) -> Result<ScoreNormalization, Error> {
+        match normalization {
+            "rank" => Ok(ScoreNormalization::Rank),
+            "minmax" => Ok(ScoreNormalization::MinMax),
+            other => Err(Error::new(&format!(
+                "unknown normalization \"{}\": expected \"rank\" or \"minmax\"",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScoreNormalization::Rank => "rank",
+            ScoreNormalization::MinMax => "minmax",
+        }
+    }
+}
+
+/// Weights of `score_pairs_with_weights`; the default matches an empty weights object.
+#[derive(Clone, Copy, Debug)]
+pub struct ScoreWeights {
+    // Lower cointegration p-values are better
+    pub p_value: f64,
+    // Shorter half-lives are better
+    pub half_life: f64,
+    // Lower Hurst exponents (stronger mean reversion) are better
+    pub hurst: f64,
+    // Wider spread swings, more room over the costs, are better
+    pub spread_volatility: f64,
+    // Higher backtest Sharpe ratios are better
+    pub sharpe: f64,
+    pub normalization: ScoreNormalization,
+}
+
+impl Default for ScoreWeights {
+    fn default() -> ScoreWeights {
+        ScoreWeights {
+            p_value: 1.0,
+            half_life: 1.0,
+            hurst: 1.0,
+            spread_volatility: 1.0,
+            sharpe: 1.0,
+            normalization: ScoreNormalization::Rank,
+        }
+    }
+}
+
+impl ScoreWeights {
+    #[cfg(feature = "wasm")]
+    pub(crate) fn from_js(weights: &JsValue) -> Result<ScoreWeights, Error> {
+        let mut parsed = ScoreWeights::default();
+        if weights.is_undefined() || weights.is_null() {
+            return Ok(parsed);
+        }
+        if !weights.is_object() {
+            return Err(Error::new("weights must be an object"));
+        }
+
+        for (name, weight) in COMPONENTS.iter().zip(parsed.weights_mut()) {
+            let value = get_property(weights, name)?;
+            if !value.is_undefined() {
+                *weight = value
+                    .as_f64()
+                    .ok_or_else(|| Error::new(&format!("{} must be a number", name)))?;
+            }
+        }
+
+        let normalization = get_property(weights, "normalization")?;
+        if !normalization.is_undefined() {
+            let name = normalization
+                .as_string()
+                .ok_or_else(|| Error::new("normalization must be a string"))?;
+            parsed.normalization = ScoreNormalization::parse(&name)?;
+        }
+        Ok(parsed)
+    }
+
+    fn weights(&self) -> [f64; 5] {
+        [
+            self.p_value,
+            self.half_life,
+            self.hurst,
+            self.spread_volatility,
+            self.sharpe,
+        ]
+    }
+
+    #[cfg(feature = "wasm")]
+    fn weights_mut(&mut self) -> [&mut f64; 5] {
+        [
+            &mut self.p_value,
+            &mut self.half_life,
+            &mut self.hurst,
+            &mut self.spread_volatility,
+            &mut self.sharpe,
+        ]
+    }
+}
+
+/// One pair of a `PairScoreboard`, with its metrics as given and its component scores.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug)]
+pub struct PairScore {
+    label: String,
+    // Position of the pair in the inputs
+    pub index: usize,
+    // 1 for the best pair
+    pub rank: usize,
+    // Weighted mean of the components, in [0, 1]
+    pub score: f64,
+    pub p_value: f64,
+    pub half_life: f64,
+    pub hurst: f64,
+    pub spread_volatility: f64,
+    pub sharpe: f64,
+    components: [f64; 5],
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl PairScore {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn label(&self) -> String {
+        self.label.clone()
+    }
+
+    // Component scores in [0, 1], higher is better, in the order p_value, half_life, hurst,
+    // spread_volatility, sharpe; NaN for a metric that was not supplied
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn components(&self) -> Vec<f64> {
+        self.components.to_vec()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("label", &self.label)
+            .field("index", self.index)
+            .field("rank", self.rank)
+            .field("score", self.score)
+            .field("p_value", self.p_value)
+            .field("half_life", self.half_life)
+            .field("hurst", self.hurst)
+            .field("spread_volatility", self.spread_volatility)
+            .field("sharpe", self.sharpe)
+            .field("components", self.components.as_slice())
+            .finish()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct PairScoreboard {
+    entries: Vec<PairScore>,
+    weights: ScoreWeights,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl PairScoreboard {
+    // The pairs, best first
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn entries(&self) -> Vec<PairScore> {
+        self.entries.clone()
+    }
+
+    // Input positions of the pairs, best first
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn ranking(&self) -> Vec<u32> {
+        self.entries.iter().map(|e| e.index as u32).collect()
+    }
+
+    // Score of each pair in input order
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn scores(&self) -> Vec<f64> {
+        let mut scores = vec![f64::NAN; self.entries.len()];
+        for entry in &self.entries {
+            scores[entry.index] = entry.score;
+        }
+        scores
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn normalization(&self) -> String {
+        self.weights.normalization.as_str().to_string()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        let weights = self.weights.weights();
+        let mut weights_json = JsonObject::new();
+        for (name, weight) in COMPONENTS.iter().zip(weights) {
+            weights_json = weights_json.field(name, weight);
+        }
+        JsonObject::new()
+            .field(
+                "entries",
+                raw_array(self.entries.iter().map(PairScore::to_json)),
+            )
+            .field("ranking", self.ranking())
+            .field("normalization", self.weights.normalization.as_str())
+            .field("weights", RawJson(weights_json.finish()))
+            .finish()
+    }
+}
+
+/// Rank candidate pairs by a weighted score of their screening metrics, best first.
+///
+/// `labels` names each pair (such as "KO/PEP") and fixes their number; the cointegration
+/// `p_values`, spread `half_lives` in bars, `hurst` exponents, `spread_volatility` and backtest
+/// `sharpe` ratios hold one value per pair in the same order, or are empty to leave that metric
+/// out. Each metric becomes a component in [0, 1] that is higher for the better pair (lower
+/// p-value, half-life and Hurst exponent; higher spread volatility and Sharpe ratio), and the
+/// score is the weighted mean of the components. A pair whose metric is NaN gets that
+/// component's worst value, 0. Ties are broken by the lower p-value, then the input order.
+///
+/// `weights` is an optional object with a non-negative weight for each of `p_value`,
+/// `half_life`, `hurst`, `spread_volatility` and `sharpe` (default 1 each) and `normalization`:
+/// `"rank"` (default) for each value's percentile rank across the pairs, or `"minmax"` for a
+/// linear rescaling from the worst value to the best.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn score_pairs(
+    labels: Vec<String>,
+    p_values: &[f64],
+    half_lives: &[f64],
+    hurst: &[f64],
+    spread_volatility: &[f64],
+    sharpe: &[f64],
+    weights: JsValue,
+) -> Result<PairScoreboard, Error> {
+    let weights = ScoreWeights::from_js(&weights)?;
+    score_pairs_with_weights(
+        &labels,
+        [p_values, half_lives, hurst, spread_volatility, sharpe],
+        &weights,
+    )
+}
+
+/// `score_pairs` for native callers, with the metrics in the order p-values, half-lives, Hurst
+/// exponents, spread volatilities and Sharpe ratios, and the weights as a struct.
+pub fn score_pairs_with_weights(
+    labels: &[String],
+    metrics: [&[f64]; 5],
+    weights: &ScoreWeights,
+) -> Result<PairScoreboard, Error> {
+    let n = labels.len();
+    if n == 0 {
+        return Err(Error::new("at least one pair is needed"));
+    }
+    for (name, values) in COMPONENTS.iter().zip(&metrics) {
+        if !values.is_empty() && values.len() != n {
+            return Err(Error::new(&format!(
+                "{} must hold one value per pair, or none",
+                name
+            )));
+        }
+    }
+    let weight_values = weights.weights();
+    for (name, weight) in COMPONENTS.iter().zip(weight_values) {
+        if !(weight >= 0.0 && weight.is_finite()) {
+            return Err(Error::new(&format!(
+                "the {} weight must be a non-negative number",
+                name
+            )));
+        }
+    }
+    // Only supplied metrics count towards the score
+    let total_weight: f64 = weight_values
+        .iter()
+        .zip(&metrics)
+        .filter(|(_, values)| !values.is_empty())
+        .map(|(w, _)| w)
+        .sum();
+    if total_weight <= 0.0 {
+        return Err(Error::new(
+            "at least one supplied metric needs a positive weight",
+        ));
+    }
+
+    let components: Vec<Vec<f64>> = metrics
+        .iter()
+        .zip(HIGHER_IS_BETTER)
+        .map(|(values, higher)| {
+            if values.is_empty() {
+                vec![f64::NAN; n]
+            } else {
+                component(values, higher, weights.normalization)
+            }
+        })
+        .collect();
+    let metric = |k: usize, i: usize| metrics[k].get(i).copied().unwrap_or(f64::NAN);
+
+    let mut entries: Vec<PairScore> = (0..n)
+        .map(|i| {
+            let points: [f64; 5] = std::array::from_fn(|k| components[k][i]);
+            let score = points
+                .iter()
+                .zip(weight_values)
+                .zip(&metrics)
+                .filter(|(_, values)| !values.is_empty())
+                .map(|((c, w), _)| c * w)
+                .sum::<f64>()
+                / total_weight;
+            PairScore {
+                label: labels[i].clone(),
+                index: i,
+                rank: 0,
+                score,
+                p_value: metric(0, i),
+                half_life: metric(1, i),
+                hurst: metric(2, i),
+                spread_volatility: metric(3, i),
+                sharpe: metric(4, i),
+                components: points,
+            }
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(a.p_value.total_cmp(&b.p_value))
+            .then(a.index.cmp(&b.index))
+    });
+    for (rank, entry) in entries.iter_mut().enumerate() {
+        entry.rank = rank + 1;
+    }
+    Ok(PairScoreboard {
+        entries,
+        weights: *weights,
+    })
+}
+
+// [0, 1] component of each value, 1 for the best; NaN values get 0, and when every finite value
+// is the same they all get 1
+fn component(
+    values: &[f64],
+    higher_is_better: bool,
+    normalization: ScoreNormalization,
+) -> Vec<f64> {
+    let finite: Vec<usize> = (0..values.len())
+        .filter(|&i| values[i].is_finite())
+        .collect();
+    let oriented = |v: f64| if higher_is_better { v } else { -v };
+    let mut out = vec![0.0; values.len()];
+    if finite.is_empty() {
+        return out;
+    }
+    match normalization {
+        ScoreNormalization::Rank => {
+            let ranks = average_ranks(
+                &finite
+                    .iter()
+                    .map(|&i| oriented(values[i]))
+                    .collect::<Vec<_>>(),
+            );
+            let span = (finite.len() - 1) as f64;
+            for (&i, rank) in finite.iter().zip(ranks) {
+                out[i] = if span > 0.0 { (rank - 1.0) / span } else { 1.0 };
+            }
+        }
+        ScoreNormalization::MinMax => {
+            let (low, high) =
+                finite
+                    .iter()
+                    .fold((f64::INFINITY, f64::NEG_INFINITY), |(l, h), &i| {
+                        let v = oriented(values[i]);
+                        (l.min(v), h.max(v))
+                    });
+            for &i in &finite {
+                out[i] = if high > low {
+                    (oriented(values[i]) - low) / (high - low)
+                } else {
+                    1.0
+                };
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,829 @@
+// Pair screening over a universe of price series in a single call.
+//
+// Every pair is first filtered on price correlation, which is cheap; only the
+// survivors get the Engle-Granger regression and the half-life fit of their
+// residual spread. Doing the whole funnel in Rust replaces the dozens of wasm
+// calls per pair a JS-side loop needs, and with the `parallel` feature on a
+// native target the pairs are screened on several threads (wasm32 builds stay
+// single-threaded, see `parallel`).
+//
+// `cointegration_matrix` runs the same universe through every pairwise test
+// without filtering, for a heatmap of the cointegration of the whole universe.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::adf::Regression;
+#[cfg(feature = "arrow")]
+use crate::arrow::{write_arrow, OutputColumn};
+use crate::coint::{engle_granger_fit, CointResult};
+use crate::correlation::{correlation_with, CorrelationMethod};
+use crate::error::Error;
+#[cfg(feature = "wasm")]
+use crate::get_property;
+use crate::johansen::johansen_test;
+#[cfg(feature = "wasm")]
+use crate::json::{parse_payload, payload_series, payload_strings};
+use crate::json::{raw_array, JsonObject};
+use crate::mean_reversion::half_life_fit;
+use crate::parallel;
+use crate::precision::Real;
+
+/// A pair that passed every filter of `screen_pairs`, oriented as y = intercept + hedge_ratio·x.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug)]
+pub struct ScreenedPair {
+    y_symbol: String,
+    x_symbol: String,
+    // Column of each leg in the price matrix
+    pub y_index: usize,
+    pub x_index: usize,
+    pub correlation: f64,
+    pub hedge_ratio: f64,
+    pub intercept: f64,
+    // Engle-Granger residual statistic and its MacKinnon p-value
+    pub statistic: f64,
+    pub p_value: f64,
+    // Half-life of the residual spread in bars
+    pub half_life: f64,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ScreenedPair {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn y_symbol(&self) -> String {
+        self.y_symbol.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn x_symbol(&self) -> String {
+        self.x_symbol.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("y_symbol", &self.y_symbol)
+            .field("x_symbol", &self.x_symbol)
+            .field("y_index", self.y_index)
+            .field("x_index", self.x_index)
+            .field("correlation", self.correlation)
+            .field("hedge_ratio", self.hedge_ratio)
+            .field("intercept", self.intercept)
+            .field("statistic", self.statistic)
+            .field("p_value", self.p_value)
+            .field("half_life", self.half_life)
+            .finish()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct PairScreenResult {
+    pairs: Vec<ScreenedPair>,
+    // Number of distinct pairs in the universe, n·(n-1)/2
+    pub pairs_considered: usize,
+    // Pairs whose |correlation| reached min_correlation
+    pub passed_correlation: usize,
+    // Of those, pairs whose Engle-Granger p-value was at most max_p_value
+    pub passed_cointegration: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl PairScreenResult {
+    // Qualifying pairs, best (lowest p-value) first
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn pairs(&self) -> Vec<ScreenedPair> {
+        self.pairs.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field(
+                "pairs",
+                raw_array(self.pairs.iter().map(ScreenedPair::to_json)),
+            )
+            .field("pairs_considered", self.pairs_considered)
+            .field("passed_correlation", self.passed_correlation)
+            .field("passed_cointegration", self.passed_cointegration)
+            .finish()
+    }
+
+    /// The qualifying pairs as an Arrow IPC stream of one row per pair, with the fields of
+    /// `ScreenedPair` as columns.
+    #[cfg(feature = "arrow")]
+    pub fn to_arrow(&self) -> Vec<u8> {
+        let float = |f: fn(&ScreenedPair) -> f64| self.pairs.iter().map(f).collect::<Vec<f64>>();
+        let (correlation, hedge_ratio, intercept) = (
+            float(|p| p.correlation),
+            float(|p| p.hedge_ratio),
+            float(|p| p.intercept),
+        );
+        let (statistic, p_value, half_life) = (
+            float(|p| p.statistic),
+            float(|p| p.p_value),
+            float(|p| p.half_life),
+        );
+        write_arrow(&[
+            (
+                "y_symbol",
+                OutputColumn::Utf8(self.pairs.iter().map(|p| p.y_symbol.as_str()).collect()),
+            ),
+            (
+                "x_symbol",
+                OutputColumn::Utf8(self.pairs.iter().map(|p| p.x_symbol.as_str()).collect()),
+            ),
+            (
+                "y_index",
+                OutputColumn::Int64(self.pairs.iter().map(|p| p.y_index as i64).collect()),
+            ),
+            (
+                "x_index",
+                OutputColumn::Int64(self.pairs.iter().map(|p| p.x_index as i64).collect()),
+            ),
+            ("correlation", OutputColumn::Float64(&correlation)),
+            ("hedge_ratio", OutputColumn::Float64(&hedge_ratio)),
+            ("intercept", OutputColumn::Float64(&intercept)),
+            ("statistic", OutputColumn::Float64(&statistic)),
+            ("p_value", OutputColumn::Float64(&p_value)),
+            ("half_life", OutputColumn::Float64(&half_life)),
+        ])
+    }
+}
+
+/// Filters of `screen_pairs_with_criteria`; the default matches an empty criteria object.
+#[derive(Clone, Copy, Debug)]
+pub struct ScreeningCriteria {
+    // Minimum absolute correlation of the price levels
+    pub min_correlation: f64,
+    pub correlation_method: CorrelationMethod,
+    // Largest Engle-Granger p-value accepted
+    pub max_p_value: f64,
+    // Accepted half-life range of the residual spread, in bars
+    pub min_half_life: f64,
+    pub max_half_life: f64,
+    // Run the cointegrating regression on log-prices
+    pub log_prices: bool,
+    // Keep only the best `max_results` pairs
+    pub max_results: Option<usize>,
+}
+
+impl Default for ScreeningCriteria {
+    fn default() -> ScreeningCriteria {
+        ScreeningCriteria {
+            min_correlation: 0.7,
+            correlation_method: CorrelationMethod::Pearson,
+            max_p_value: 0.05,
+            min_half_life: 1.0,
+            max_half_life: 252.0,
+            log_prices: false,
+            max_results: None,
+        }
+    }
+}
+
+impl ScreeningCriteria {
+    #[cfg(feature = "wasm")]
+    pub(crate) fn from_js(criteria: &JsValue) -> Result<ScreeningCriteria, Error> {
+        let mut parsed = ScreeningCriteria::default();
+        if criteria.is_undefined() || criteria.is_null() {
+            return Ok(parsed);
+        }
+        if !criteria.is_object() {
+            return Err(Error::new("criteria must be an object"));
+        }
+
+        let number = |key: &str| -> Result<Option<f64>, Error> {
+            let value = get_property(criteria, key)?;
+            if value.is_undefined() {
+                return Ok(None);
+            }
+            value
+                .as_f64()
+                .map(Some)
+                .ok_or_else(|| Error::new(&format!("{} must be a number", key)))
+        };
+        if let Some(value) = number("min_correlation")? {
+            parsed.min_correlation = value;
+        }
+        if let Some(value) = number("max_p_value")? {
+            parsed.max_p_value = value;
+        }
+        if let Some(value) = number("min_half_life")? {
+            parsed.min_half_life = value;
+        }
+        if let Some(value) = number("max_half_life")? {
+            parsed.max_half_life = value;
+        }
+
+        let correlation_method = get_property(criteria, "correlation_method")?;
+        if !correlation_method.is_undefined() {
+            let name = correlation_method
+                .as_string()
+                .ok_or_else(|| Error::new("correlation_method must be a string"))?;
+            parsed.correlation_method = CorrelationMethod::parse(&name)?;
+        }
+
+        let log_prices = get_property(criteria, "log_prices")?;
+        if !log_prices.is_undefined() {
+            parsed.log_prices = log_prices
+                .as_bool()
+                .ok_or_else(|| Error::new("log_prices must be a boolean"))?;
+        }
+
+        let max_results = get_property(criteria, "max_results")?;
+        if !max_results.is_undefined() && !max_results.is_null() {
+            let value = max_results
+                .as_f64()
+                .filter(|v| *v >= 0.0 && v.fract() == 0.0)
+                .ok_or_else(|| Error::new("max_results must be a non-negative integer"))?;
+            parsed.max_results = Some(value as usize);
+        }
+        Ok(parsed)
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        if !(0.0..=1.0).contains(&self.min_correlation) {
+            return Err(Error::new("min_correlation must be between 0 and 1"));
+        }
+        if !(self.max_p_value > 0.0 && self.max_p_value <= 1.0) {
+            return Err(Error::new("max_p_value must be in (0, 1]"));
+        }
+        if self.min_half_life.is_nan()
+            || self.max_half_life.is_nan()
+            || self.min_half_life > self.max_half_life
+        {
+            return Err(Error::new(
+                "min_half_life and max_half_life must form a non-empty range",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Screen every pair of a price universe for trading candidates.
+///
+/// `price_matrix` holds one row of `symbols.len()` prices per time step, row-major (as in
+/// `johansen_test`). Each pair whose absolute price correlation reaches `min_correlation` gets
+/// an Engle-Granger test in both orientations, keeping the one with the lower p-value; pairs
+/// with a p-value at most `max_p_value` and a residual half-life inside
+/// `[min_half_life, max_half_life]` bars qualify and are returned best p-value first.
+///
+/// `criteria` is an optional object with `min_correlation` (default 0.7), `correlation_method`
+/// (`"pearson"` (default), `"spearman"` or `"kendall"`, as in `correlation`), `max_p_value` (0.05),
+/// `min_half_life` (1), `max_half_life` (252), `log_prices` (false: test price levels) and
+/// `max_results` (all qualifying pairs). Clean gaps with `clean_series` first: a pair touching
+/// a NaN fails the correlation filter.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn screen_pairs(
+    price_matrix: &[f64],
+    symbols: Vec<String>,
+    criteria: JsValue,
+) -> Result<PairScreenResult, Error> {
+    let criteria = ScreeningCriteria::from_js(&criteria)?;
+    screen_pairs_with_criteria(price_matrix, &symbols, &criteria)
+}
+
+/// `screen_pairs` driven by JSON text: `payload` is
+/// `{"price_matrix": [...], "symbols": [...], "criteria": {...}}` and the result is returned as
+/// `PairScreenResult.to_json()`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn screen_pairs_from_json(payload: &str) -> Result<String, Error> {
+    let payload = parse_payload(payload)?;
+    let price_matrix = payload_series(&payload, "price_matrix")?;
+    let symbols = payload_strings(&payload, "symbols")?;
+    let criteria = ScreeningCriteria::from_js(&get_property(&payload, "criteria")?)?;
+    Ok(screen_pairs_with_criteria(&price_matrix, &symbols, &criteria)?.to_json())
+}
+
+/// `screen_pairs` for native callers, with the criteria as a struct.
+pub fn screen_pairs_with_criteria(
+    price_matrix: &[f64],
+    symbols: &[String],
+    criteria: &ScreeningCriteria,
+) -> Result<PairScreenResult, Error> {
+    screen_matrix(price_matrix, symbols, criteria)
+}
+
+/// `screen_pairs` on a Float32Array price matrix, which takes half the memory of the f64 one for
+/// large universes. The matrix is split into per-asset columns kept in f32 (log-prices too, when
+/// requested); each pair's two columns are widened to f64 for its correlation, Engle-Granger
+/// test and half-life, so every statistic reported is computed in double precision.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn screen_pairs_f32(
+    price_matrix: &[f32],
+    symbols: Vec<String>,
+    criteria: JsValue,
+) -> Result<PairScreenResult, Error> {
+    let criteria = ScreeningCriteria::from_js(&criteria)?;
+    screen_pairs_f32_with_criteria(price_matrix, &symbols, &criteria)
+}
+
+/// `screen_pairs_f32` for native callers, with the criteria as a struct.
+pub fn screen_pairs_f32_with_criteria(
+    price_matrix: &[f32],
+    symbols: &[String],
+    criteria: &ScreeningCriteria,
+) -> Result<PairScreenResult, Error> {
+    screen_matrix(price_matrix, symbols, criteria)
+}
+
+fn screen_matrix<T: Real>(
+    price_matrix: &[T],
+    symbols: &[String],
+    criteria: &ScreeningCriteria,
+) -> Result<PairScreenResult, Error> {
+    criteria.validate()?;
+    let n_assets = symbols.len();
+    if n_assets < 2 {
+        return Err(Error::new("at least two symbols are needed"));
+    }
+    if !price_matrix.len().is_multiple_of(n_assets) {
+        return Err(Error::new(
+            "price_matrix length must be a multiple of the number of symbols",
+        ));
+    }
+    let columns = columns(price_matrix, n_assets, criteria.log_prices)?;
+
+    let candidates: Vec<(usize, usize)> = (0..n_assets)
+        .flat_map(|i| (i + 1..n_assets).map(move |j| (i, j)))
+        .collect();
+    let outcomes = parallel::map(&candidates, |&(i, j)| {
+        screen_pair(&columns, symbols, criteria, i, j)
+    });
+
+    let mut pairs = Vec::new();
+    let mut passed_correlation = 0;
+    let mut passed_cointegration = 0;
+    for outcome in outcomes {
+        match outcome {
+            Outcome::Uncorrelated => {}
+            Outcome::NotCointegrated => passed_correlation += 1,
+            Outcome::HalfLifeOutOfRange => {
+                passed_correlation += 1;
+                passed_cointegration += 1;
+            }
+            Outcome::Qualified(pair) => {
+                passed_correlation += 1;
+                passed_cointegration += 1;
+                pairs.push(pair);
+            }
+        }
+    }
+
+    pairs.sort_by(|a, b| {
+        a.p_value
+            .total_cmp(&b.p_value)
+            .then(a.half_life.total_cmp(&b.half_life))
+    });
+    if let Some(max_results) = criteria.max_results {
+        pairs.truncate(max_results);
+    }
+    Ok(PairScreenResult {
+        pairs,
+        pairs_considered: n_assets * (n_assets - 1) / 2,
+        passed_correlation,
+        passed_cointegration,
+    })
+}
+
+// How far a pair got through the screening funnel
+enum Outcome {
+    Uncorrelated,
+    NotCointegrated,
+    HalfLifeOutOfRange,
+    Qualified(ScreenedPair),
+}
+
+// Run columns i and j through the correlation, cointegration and half-life filters
+fn screen_pair<T: Real>(
+    columns: &[Vec<T>],
+    symbols: &[String],
+    criteria: &ScreeningCriteria,
+    i: usize,
+    j: usize,
+) -> Outcome {
+    let (a, b) = (T::widen_slice(&columns[i]), T::widen_slice(&columns[j]));
+    let correlation = correlation_with(&a, &b, criteria.correlation_method);
+    if correlation.is_nan() || correlation.abs() < criteria.min_correlation {
+        return Outcome::Uncorrelated;
+    }
+
+    let Some((y_index, x_index, coint)) = best_orientation(&a, &b, i, j) else {
+        return Outcome::NotCointegrated;
+    };
+    if coint.p_value > criteria.max_p_value {
+        return Outcome::NotCointegrated;
+    }
+
+    let half_life = half_life_fit(&coint.residuals()).map_or(f64::NAN, |h| h.half_life);
+    if !(half_life >= criteria.min_half_life && half_life <= criteria.max_half_life) {
+        return Outcome::HalfLifeOutOfRange;
+    }
+    Outcome::Qualified(ScreenedPair {
+        y_symbol: symbols[y_index].clone(),
+        x_symbol: symbols[x_index].clone(),
+        y_index,
+        x_index,
+        correlation,
+        hedge_ratio: coint.hedge_ratio,
+        intercept: coint.intercept,
+        statistic: coint.statistic,
+        p_value: coint.p_value,
+        half_life,
+    })
+}
+
+// Split the row-major matrix into one series per asset, log-transformed when requested
+fn columns<T: Real>(
+    price_matrix: &[T],
+    n_assets: usize,
+    log_prices: bool,
+) -> Result<Vec<Vec<T>>, Error> {
+    let mut columns = vec![Vec::with_capacity(price_matrix.len() / n_assets); n_assets];
+    for row in price_matrix.chunks_exact(n_assets) {
+        for (column, &price) in columns.iter_mut().zip(row) {
+            column.push(price);
+        }
+    }
+    if log_prices {
+        for (asset, column) in columns.iter_mut().enumerate() {
+            for price in column.iter_mut() {
+                // NaN gaps stay NaN and are rejected by the correlation filter
+                let value = price.widen();
+                if value <= 0.0 {
+                    return Err(Error::new(&format!(
+                        "log_prices needs positive prices, but column {} has {}",
+                        asset, value
+                    )));
+                }
+                *price = T::narrow(value.ln());
+            }
+        }
+    }
+    Ok(columns)
+}
+
+// Engle-Granger of columns i (values `a`) and j (`b`) in both orientations; the test is not
+// symmetric, so keep the stronger result
+fn best_orientation(
+    a: &[f64],
+    b: &[f64],
+    i: usize,
+    j: usize,
+) -> Option<(usize, usize, CointResult)> {
+    let forward = engle_granger_fit(a, b, Regression::Constant).ok();
+    let reverse = engle_granger_fit(b, a, Regression::Constant).ok();
+    match (forward, reverse) {
+        (Some(f), Some(r)) if r.p_value < f.p_value => Some((j, i, r)),
+        (Some(f), _) => Some((i, j, f)),
+        (None, Some(r)) => Some((j, i, r)),
+        (None, None) => None,
+    }
+}
+
+// Pairwise statistic of `cointegration_matrix`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatrixMethod {
+    // Engle-Granger p-value of the row asset regressed on the column asset ("engle_granger")
+    EngleGranger,
+    // Johansen trace statistic for no cointegration, the same in both orientations ("johansen")
+    Johansen,
+}
+
+impl MatrixMethod {
+    pub fn parse(method: &str) -> Result<MatrixMethod, Error> {
+        match method {
+            "engle_granger" => Ok(MatrixMethod::EngleGranger),
+            "johansen" => Ok(MatrixMethod::Johansen),
+            other => Err(Error::new(&format!(
+                "unknown method \"{}\": expected \"engle_granger\" or \"johansen\"",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MatrixMethod::EngleGranger => "engle_granger",
+            MatrixMethod::Johansen => "johansen",
+        }
+    }
+}
+
+/// Options of `cointegration_matrix_with_options`; the default matches an empty options object.
+#[derive(Clone, Copy, Debug)]
+pub struct CointMatrixOptions {
+    pub method: MatrixMethod,
+    // Deterministic terms of the Engle-Granger regressions
+    pub regression: Regression,
+    // Deterministic terms (-1, 0 or 1) and lagged differences of the Johansen VECM
+    pub det_order: i32,
+    pub k_ar_diff: usize,
+    // Test log-prices instead of price levels
+    pub log_prices: bool,
+    // Give both cells of a pair the lower Engle-Granger p-value of its two orientations
+    pub symmetric: bool,
+}
+
+impl Default for CointMatrixOptions {
+    fn default() -> CointMatrixOptions {
+        CointMatrixOptions {
+            method: MatrixMethod::EngleGranger,
+            regression: Regression::Constant,
+            det_order: 0,
+            k_ar_diff: 1,
+            log_prices: false,
+            symmetric: false,
+        }
+    }
+}
+
+impl CointMatrixOptions {
+    #[cfg(feature = "wasm")]
+    pub(crate) fn from_js(options: &JsValue) -> Result<CointMatrixOptions, Error> {
+        let mut parsed = CointMatrixOptions::default();
+        if options.is_undefined() || options.is_null() {
+            return Ok(parsed);
+        }
+        if !options.is_object() {
+            return Err(Error::new("options must be an object"));
+        }
+
+        let string = |key: &str| -> Result<Option<String>, Error> {
+            let value = get_property(options, key)?;
+            if value.is_undefined() {
+                return Ok(None);
+            }
+            value
+                .as_string()
+                .map(Some)
+                .ok_or_else(|| Error::new(&format!("{} must be a string", key)))
+        };
+        let boolean = |key: &str| -> Result<Option<bool>, Error> {
+            let value = get_property(options, key)?;
+            if value.is_undefined() {
+                return Ok(None);
+            }
+            value
+                .as_bool()
+                .map(Some)
+                .ok_or_else(|| Error::new(&format!("{} must be a boolean", key)))
+        };
+        if let Some(method) = string("method")? {
+            parsed.method = MatrixMethod::parse(&method)?;
+        }
+        if let Some(regression) = string("regression")? {
+            parsed.regression = Regression::parse(&regression)?;
+        }
+        if let Some(log_prices) = boolean("log_prices")? {
+            parsed.log_prices = log_prices;
+        }
+        if let Some(symmetric) = boolean("symmetric")? {
+            parsed.symmetric = symmetric;
+        }
+
+        let det_order = get_property(options, "det_order")?;
+        if !det_order.is_undefined() {
+            parsed.det_order = det_order
+                .as_f64()
+                .filter(|v| [-1.0, 0.0, 1.0].contains(v))
+                .ok_or_else(|| Error::new("det_order must be -1, 0 or 1"))?
+                as i32;
+        }
+        let k_ar_diff = get_property(options, "k_ar_diff")?;
+        if !k_ar_diff.is_undefined() {
+            parsed.k_ar_diff = k_ar_diff
+                .as_f64()
+                .filter(|v| *v >= 0.0 && v.fract() == 0.0 && *v <= u32::MAX as f64)
+                .ok_or_else(|| Error::new("k_ar_diff must be a non-negative integer"))?
+                as usize;
+        }
+        Ok(parsed)
+    }
+}
+
+/// Pairwise cointegration statistics of a universe, laid out for a heatmap.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug)]
+pub struct CointegrationMatrix {
+    symbols: Vec<String>,
+    values: Vec<f64>,
+    method: MatrixMethod,
+    pub symmetric: bool,
+    // 5% critical value of the Johansen trace statistic, the colour scale's natural midpoint;
+    // NaN for Engle-Granger p-values
+    pub critical_value: f64,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl CointegrationMatrix {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn symbols(&self) -> Vec<String> {
+        self.symbols.clone()
+    }
+
+    // symbols.len() x symbols.len(), row-major: entry (i, j) is the statistic of asset i on
+    // asset j, NaN on the diagonal and for pairs that could not be tested
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn values(&self) -> Vec<f64> {
+        self.values.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn size(&self) -> usize {
+        self.symbols.len()
+    }
+
+    // "engle_granger" for p-values, "johansen" for trace statistics
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn method(&self) -> String {
+        self.method.as_str().to_string()
+    }
+
+    /// Entry (`row`, `column`) of `values`.
+    pub fn get(&self, row: usize, column: usize) -> f64 {
+        let n = self.symbols.len();
+        if row < n && column < n {
+            self.values[row * n + column]
+        } else {
+            f64::NAN
+        }
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("symbols", &self.symbols)
+            .field("method", self.method.as_str())
+            .field("symmetric", self.symmetric)
+            .field("critical_value", self.critical_value)
+            .field("values", &self.values)
+            .finish()
+    }
+}
+
+/// Cointegration statistic of every pair of a price universe as a flat matrix for a heatmap.
+///
+/// `price_matrix` holds one row of `symbols.len()` prices per time step, row-major (as in
+/// `screen_pairs`). With the default method `"engle_granger"`, entry (i, j) is the MacKinnon
+/// p-value of the Engle-Granger test with asset i as y and asset j as x; the test is not
+/// symmetric, and `symmetric` gives both entries of a pair the lower of its two p-values, as
+/// `screen_pairs` keeps the better orientation. With `"johansen"`, both entries are the trace
+/// statistic of `johansen_test` on the two columns for no cointegrating relation, compared
+/// against `critical_value`. A pair with a NaN or an untestable series gets NaN entries.
+///
+/// `options` is an optional object with `method`, `regression` (Engle-Granger terms, default
+/// "c"), `det_order` (Johansen terms, default 0), `k_ar_diff` (default 1), `log_prices`
+/// (default false) and `symmetric` (default false).
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn cointegration_matrix(
+    price_matrix: &[f64],
+    symbols: Vec<String>,
+    options: JsValue,
+) -> Result<CointegrationMatrix, Error> {
+    let options = CointMatrixOptions::from_js(&options)?;
+    cointegration_matrix_with_options(price_matrix, &symbols, &options)
+}
+
+/// `cointegration_matrix` for native callers, with the options as a struct.
+pub fn cointegration_matrix_with_options(
+    price_matrix: &[f64],
+    symbols: &[String],
+    options: &CointMatrixOptions,
+) -> Result<CointegrationMatrix, Error> {
+    let n_assets = symbols.len();
+    if n_assets < 2 {
+        return Err(Error::new("at least two symbols are needed"));
+    }
+    if !price_matrix.len().is_multiple_of(n_assets) {
+        return Err(Error::new(
+            "price_matrix length must be a multiple of the number of symbols",
+        ));
+    }
+    if !(-1..=1).contains(&options.det_order) {
+        return Err(Error::new("det_order must be -1, 0 or 1"));
+    }
+    let columns = columns(price_matrix, n_assets, options.log_prices)?;
+
+    let mut values = vec![f64::NAN; n_assets * n_assets];
+    let mut critical_value = f64::NAN;
+    match options.method {
+        MatrixMethod::EngleGranger => {
+            let ordered: Vec<(usize, usize)> = (0..n_assets)
+                .flat_map(|i| (0..n_assets).filter(move |j| *j != i).map(move |j| (i, j)))
+                .collect();
+            let p_values = parallel::map(&ordered, |&(i, j)| {
+                engle_granger_fit(&columns[i], &columns[j], options.regression)
+                    .map_or(f64::NAN, |coint| coint.p_value)
+            });
+            for (&(i, j), p_value) in ordered.iter().zip(p_values) {
+                values[i * n_assets + j] = p_value;
+            }
+            if options.symmetric {
+                for i in 0..n_assets {
+                    for j in i + 1..n_assets {
+                        let (forward, reverse) =
+                            (values[i * n_assets + j], values[j * n_assets + i]);
+                        // A failed orientation leaves the other one's p-value
+                        let lower = forward.min(reverse);
+                        values[i * n_assets + j] = lower;
+                        values[j * n_assets + i] = lower;
+                    }
+                }
+            }
+        }
+        MatrixMethod::Johansen => {
+            let pairs: Vec<(usize, usize)> = (0..n_assets)
+                .flat_map(|i| (i + 1..n_assets).map(move |j| (i, j)))
+                .collect();
+            let n_obs = columns[0].len();
+            let tests = parallel::map(&pairs, |&(i, j)| {
+                let matrix: Vec<f64> = columns[i]
+                    .iter()
+                    .zip(&columns[j])
+                    .flat_map(|(a, b)| [*a, *b])
+                    .collect();
+                johansen_test(&matrix, 2, n_obs, options.det_order, options.k_ar_diff).ok()
+            });
+            for (&(i, j), test) in pairs.iter().zip(tests) {
+                if let Some(test) = test {
+                    let trace = test.trace_statistics()[0];
+                    values[i * n_assets + j] = trace;
+                    values[j * n_assets + i] = trace;
+                    critical_value = test.trace_critical_values()[1];
+                }
+            }
+        }
+    }
+
+    Ok(CointegrationMatrix {
+        symbols: symbols.to_vec(),
+        values,
+        method: options.method,
+        symmetric: options.symmetric || options.method == MatrixMethod::Johansen,
+        critical_value,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::fixtures::{ar1, cointegrated_pair};
+
+    // Five assets, row-major: independent random walks except asset 3 = 3 + 1.5·asset 1 plus an
+    // AR(1) spread
+    fn universe(n: usize, seed: u32) -> (Vec<f64>, Vec<String>) {
+        let (y, x, _) = cointegrated_pair(n, 1.5, 0.8, seed);
+        let walks: Vec<Vec<f64>> = (1..=3)
+            .map(|k| ar1(n, 1.0, seed + 100 * k).iter().map(|v| v + 100.0).collect())
+            .collect();
+        let matrix = (0..n)
+            .flat_map(|t| [walks[0][t], x[t], walks[1][t], y[t], walks[2][t]])
+            .collect();
+        let symbols = ["AAA", "BBB", "CCC", "DDD", "EEE"].map(String::from).to_vec();
+        (matrix, symbols)
+    }
+
+    #[test]
+    fn planted_pair_is_found_in_both_precisions() {
+        let criteria = ScreeningCriteria {
+            min_correlation: 0.5,
+            ..ScreeningCriteria::default()
+        };
+        for seed in 1..=5 {
+            let (matrix, symbols) = universe(500, seed);
+            let wide = screen_pairs_with_criteria(&matrix, &symbols, &criteria).unwrap();
+            let narrow_matrix: Vec<f32> = matrix.iter().map(|&v| v as f32).collect();
+            let narrow =
+                screen_pairs_f32_with_criteria(&narrow_matrix, &symbols, &criteria).unwrap();
+
+            // The planted pair ranks first; a spurious pair of walks may follow it
+            let best = &wide.pairs()[0];
+            assert!(
+                [(3, 1), (1, 3)].contains(&(best.y_index, best.x_index)),
+                "seed {}: {}",
+                seed,
+                wide.to_json()
+            );
+            assert_eq!(wide.pairs_considered, 10);
+            assert_eq!(narrow.passed_correlation, wide.passed_correlation);
+            assert_eq!(narrow.passed_cointegration, wide.passed_cointegration);
+            assert_eq!(narrow.pairs().len(), wide.pairs().len(), "seed {}", seed);
+            for (narrow, wide) in narrow.pairs().iter().zip(&wide.pairs()) {
+                assert_eq!(
+                    (narrow.y_symbol(), narrow.x_symbol()),
+                    (wide.y_symbol(), wide.x_symbol())
+                );
+                let close = |a: f64, b: f64| (a - b).abs() <= 1e-4 * b.abs().max(1.0);
+                assert!(close(narrow.hedge_ratio, wide.hedge_ratio), "seed {}", seed);
+                assert!(close(narrow.statistic, wide.statistic), "seed {}", seed);
+                assert!(close(narrow.half_life, wide.half_life), "seed {}", seed);
+            }
+        }
+    }
+}
@@ -0,0 +1,267 @@
+// Timestamped series.
+//
+// Most functions take bare value arrays and pair observations by position, which
+// leaves the caller to keep each array's timestamps alongside it. A `Series` holds
+// both, so slicing by date, resampling and taking returns keep them in step, and
+// two legs are joined on their timestamps before any pair statistic is computed.
+// The tests are available as methods; any other function takes `series.values`.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::adf::{adf_test_with_config, AdfOptions};
+use crate::align::{align_series, check_series, AlignedPair};
+use crate::coint::{engle_granger, engle_granger_timed, CointResult, EgOptions};
+use crate::correlation::correlation;
+use crate::error::Error;
+use crate::hedge::min_variance_hedge_ratio;
+use crate::hurst::{hurst_exponent, HurstResult};
+use crate::json::JsonObject;
+use crate::kpss::{kpss_test, KpssResult};
+use crate::mean_reversion::{half_life, HalfLifeResult};
+use crate::resample::resample;
+use crate::returns::{checked_log, log_returns, simple_returns};
+use crate::stationarity::{
+    stationarity_report_with_config, StationarityOptions, StationarityReport,
+};
+use crate::timing::Stopwatch;
+use crate::variance_ratio::{variance_ratio_test, VarianceRatioResult};
+use crate::zscore::rolling_zscore;
+use crate::AdfResult;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug)]
+pub struct Series {
+    timestamps: Vec<f64>,
+    values: Vec<f64>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Series {
+    /// A series of `values` observed at `timestamps`, which are numbers in any unit (typically
+    /// epoch milliseconds) and must be finite and strictly increasing. Values may be NaN for
+    /// missing observations.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(timestamps: Vec<f64>, values: Vec<f64>) -> Result<Series, Error> {
+        check_series(&timestamps, &values, "series")?;
+        Ok(Series { timestamps, values })
+    }
+
+    // Number of observations
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn length(&self) -> usize {
+        self.values.len()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn timestamps(&self) -> Vec<f64> {
+        self.timestamps.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn values(&self) -> Vec<f64> {
+        self.values.clone()
+    }
+
+    /// The observations with timestamps from `start` to `end`, both inclusive; a missing bound
+    /// leaves that side open. The result may be empty.
+    pub fn slice_by_date(&self, start: Option<f64>, end: Option<f64>) -> Result<Series, Error> {
+        if start.is_some_and(f64::is_nan) || end.is_some_and(f64::is_nan) {
+            return Err(Error::new("start and end must be numbers"));
+        }
+        let from = start.map_or(0, |start| self.timestamps.partition_point(|&t| t < start));
+        let to = end.map_or(self.timestamps.len(), |end| {
+            self.timestamps.partition_point(|&t| t <= end)
+        });
+        let to = to.max(from);
+        Ok(Series {
+            timestamps: self.timestamps[from..to].to_vec(),
+            values: self.values[from..to].to_vec(),
+        })
+    }
+
+    /// Inner join with `other` on the timestamps, as `align_series` with this series as y and
+    /// `other` as x.
+    pub fn align_with(&self, other: &Series, tolerance: Option<f64>) -> Result<AlignedPair, Error> {
+        align_series(
+            &self.timestamps,
+            &self.values,
+            &other.timestamps,
+            &other.values,
+            tolerance,
+        )
+    }
+
+    /// The series aggregated into `rule` periods ("daily", "weekly" or "monthly") by `how`
+    /// ("last", "first" or "mean"), as `resample` does; timestamps must be epoch milliseconds.
+    pub fn resample(&self, rule: &str, how: Option<String>) -> Result<Series, Error> {
+        let resampled = resample(&self.timestamps, &self.values, rule, how)?;
+        Ok(Series {
+            timestamps: resampled.timestamps(),
+            values: resampled.values(),
+        })
+    }
+
+    /// One-period returns, `kind` "simple" (default) or "log", each stamped with the time of the
+    /// later of its two prices, so the result is one observation shorter. Errors if any value is
+    /// not strictly positive and finite.
+    pub fn returns(&self, kind: Option<String>) -> Result<Series, Error> {
+        let values = match kind.as_deref().unwrap_or("simple") {
+            "simple" => simple_returns(&self.values)?,
+            "log" => log_returns(&self.values)?,
+            other => {
+                return Err(Error::new(&format!(
+                    "unknown returns kind \"{}\": expected \"simple\" or \"log\"",
+                    other
+                )))
+            }
+        };
+        Ok(Series {
+            timestamps: self.timestamps.iter().skip(1).copied().collect(),
+            values,
+        })
+    }
+
+    /// Natural logarithm of each value, on the same timestamps. Errors if any value is not
+    /// strictly positive and finite.
+    pub fn log(&self) -> Result<Series, Error> {
+        Ok(Series {
+            timestamps: self.timestamps.clone(),
+            values: checked_log(&self.values, "series")?,
+        })
+    }
+
+    /// Rolling z-score over a trailing `window`, as `zscore`, on the same timestamps.
+    pub fn zscore(&self, window: usize) -> Series {
+        Series {
+            timestamps: self.timestamps.clone(),
+            values: rolling_zscore(&self.values, window),
+        }
+    }
+
+    /// `adf_test_with_options` on the values.
+    #[cfg(feature = "wasm")]
+    pub fn adf_test(&self, options: JsValue) -> Result<AdfResult, Error> {
+        self.adf_test_with_config(&AdfOptions::from_js(&options)?)
+    }
+
+    /// `adf_test` with the options as an `AdfOptions` object.
+    pub fn adf_test_with_config(&self, options: &AdfOptions) -> Result<AdfResult, Error> {
+        adf_test_with_config(&self.values, options)
+    }
+
+    /// `kpss_test` on the values.
+    pub fn kpss_test(&self, regression: &str) -> Result<KpssResult, Error> {
+        kpss_test(&self.values, regression)
+    }
+
+    /// `stationarity_report` on the values.
+    #[cfg(feature = "wasm")]
+    pub fn stationarity_report(&self, options: JsValue) -> Result<StationarityReport, Error> {
+        self.stationarity_report_with_config(&StationarityOptions::from_js(&options)?)
+    }
+
+    /// `half_life` of the values, in observations.
+    pub fn half_life(&self) -> HalfLifeResult {
+        half_life(&self.values)
+    }
+
+    /// `hurst_exponent` of the values.
+    pub fn hurst_exponent(
+        &self,
+        min_lag: usize,
+        max_lag: usize,
+        method: &str,
+    ) -> Result<HurstResult, Error> {
+        hurst_exponent(&self.values, min_lag, max_lag, method)
+    }
+
+    /// `variance_ratio_test` on the values.
+    pub fn variance_ratio_test(&self, horizons: &[u32]) -> Result<VarianceRatioResult, Error> {
+        variance_ratio_test(&self.values, horizons)
+    }
+
+    /// `engle_granger` of this series (y) on `x`, after joining them on their timestamps within
+    /// `tolerance` (default 0).
+    pub fn engle_granger(&self, x: &Series, tolerance: Option<f64>) -> Result<CointResult, Error> {
+        let aligned = self.align_with(x, tolerance)?;
+        let (y, x) = aligned.as_slices();
+        engle_granger(y, x)
+    }
+
+    /// `engle_granger_with_options` of this series (y) on `x`, after joining them on their
+    /// timestamps within `tolerance` (default 0). With the `timings` option the join is timed
+    /// too, as the "alignment" stage.
+    #[cfg(feature = "wasm")]
+    pub fn engle_granger_with_options(
+        &self,
+        x: &Series,
+        tolerance: Option<f64>,
+        options: JsValue,
+    ) -> Result<CointResult, Error> {
+        self.engle_granger_with_config(x, tolerance, &EgOptions::from_js(&options)?)
+    }
+
+    /// `engle_granger_with_options` with the options as an `EgOptions` object.
+    pub fn engle_granger_with_config(
+        &self,
+        x: &Series,
+        tolerance: Option<f64>,
+        options: &EgOptions,
+    ) -> Result<CointResult, Error> {
+        let mut stopwatch = Stopwatch::new(options.timings);
+        let aligned = self.align_with(x, tolerance)?;
+        stopwatch.lap("alignment");
+        let (y, x) = aligned.as_slices();
+        engle_granger_timed(y, x, options, stopwatch)
+    }
+
+    /// `min_variance_hedge_ratio` of this series (y) against `x`, after joining them on their
+    /// timestamps within `tolerance` (default 0).
+    pub fn hedge_ratio(&self, x: &Series, tolerance: Option<f64>) -> Result<f64, Error> {
+        let aligned = self.align_with(x, tolerance)?;
+        let (y, x) = aligned.as_slices();
+        Ok(min_variance_hedge_ratio(y, x))
+    }
+
+    /// `correlation` ("pearson", "spearman" or "kendall") with `other`, after joining them on
+    /// their timestamps within `tolerance` (default 0).
+    pub fn correlation(
+        &self,
+        other: &Series,
+        method: &str,
+        tolerance: Option<f64>,
+    ) -> Result<f64, Error> {
+        let aligned = self.align_with(other, tolerance)?;
+        let (y, x) = aligned.as_slices();
+        correlation(y, x, method)
+    }
+
+    /// Every field of the series as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("timestamps", &self.timestamps)
+            .field("values", &self.values)
+            .finish()
+    }
+}
+
+impl Series {
+    // A series whose timestamps are already known to be valid
+    pub(crate) fn from_parts(timestamps: Vec<f64>, values: Vec<f64>) -> Series {
+        Series { timestamps, values }
+    }
+
+    // The values without copying, for native callers
+    pub fn as_slice(&self) -> &[f64] {
+        &self.values
+    }
+
+    /// `stationarity_report` for native callers, with the options as a struct.
+    pub fn stationarity_report_with_config(
+        &self,
+        options: &StationarityOptions,
+    ) -> Result<StationarityReport, Error> {
+        stationarity_report_with_config(&self.values, options)
+    }
+}
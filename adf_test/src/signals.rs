@@ -0,0 +1,782 @@
+// Entry/exit logic on z-score series.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::error::Error;
+#[cfg(feature = "wasm")]
+use crate::get_property;
+use crate::json::JsonObject;
+use crate::quantile::select_quantiles;
+use crate::zscore::rolling_zscore;
+
+// Number of round-trip trades for a simple threshold strategy: open when |z| reaches
+// `entry` (short the spread above, long below), close once z gets back to `exit` on
+// the same side of zero or crosses through it. NaN z-scores are skipped.
+pub(crate) fn count_trades(zscore: &[f64], entry: f64, exit: f64) -> usize {
+    let mut trades = 0;
+    let mut direction = 0.0;
+    for &z in zscore {
+        if z.is_nan() {
+            continue;
+        }
+        if direction == 0.0 {
+            if z.abs() >= entry {
+                direction = z.signum();
+                trades += 1;
+            }
+        } else if z * direction <= exit {
+            direction = 0.0;
+        }
+    }
+    trades
+}
+
+/// Z-score entry threshold that produces roughly `target_trades` round trips over the sample.
+///
+/// The spread is normalised with a rolling z-score over `window` bars and trades are counted
+/// with an exit at the mean (z = 0). The threshold is found by bisection between 0 and the
+/// largest |z|; since the trade count only falls roughly monotonically as the threshold rises,
+/// the threshold whose count is closest to the target is returned (the higher one on ties).
+/// Returns NaN when the z-score series has no finite values.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn threshold_for_frequency(spread: &[f64], window: usize, target_trades: usize) -> f64 {
+    let zscore = rolling_zscore(spread, window);
+    let max_abs = zscore
+        .iter()
+        .filter(|z| z.is_finite())
+        .fold(f64::NAN, |m, z| z.abs().max(m));
+    if max_abs.is_nan() {
+        return f64::NAN;
+    }
+
+    let distance = |threshold: f64| count_trades(&zscore, threshold, 0.0).abs_diff(target_trades);
+
+    let mut low = 0.0;
+    let mut high = max_abs;
+    let mut best = (distance(high), high);
+    let low_distance = distance(low);
+    if low_distance < best.0 {
+        best = (low_distance, low);
+    }
+
+    for _ in 0..60 {
+        let mid = 0.5 * (low + high);
+        let trades = count_trades(&zscore, mid, 0.0);
+        let d = trades.abs_diff(target_trades);
+        if d < best.0 || (d == best.0 && mid > best.1) {
+            best = (d, mid);
+        }
+        if trades > target_trades {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    best.1
+}
+
+// Values per trade in `SignalResult::trades`
+const TRADE_STRIDE: usize = 4;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct SignalResult {
+    positions: Vec<f64>,
+    trades: Vec<f64>,
+    halted_at: Option<usize>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl SignalResult {
+    // Spread position held after each bar: +1 long, -1 short, 0 flat
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn positions(&self) -> Vec<f64> {
+        self.positions.clone()
+    }
+
+    // Packed (entry_index, exit_index, direction, stopped) rows; exit_index is NaN for a trade
+    // still open at the last bar and stopped says what closed it: 0 the exit threshold, 1 the
+    // stop, 2 the holding-period limit, 3 the kill switch, 4 the broken regime
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn trades(&self) -> Vec<f64> {
+        self.trades.clone()
+    }
+
+    // Bar on which the kill switch stopped all trading, if it did
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn halted_at(&self) -> Option<usize> {
+        self.halted_at
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn trade_count(&self) -> usize {
+        self.trades.len() / TRADE_STRIDE
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("positions", &self.positions)
+            .field("trades", &self.trades)
+            .field("halted_at", self.halted_at)
+            .finish()
+    }
+}
+
+/// Position series and trade list for a threshold strategy on a z-score series.
+///
+/// A position opens when |z| reaches `entry`: short the spread (-1) above the mean, long (+1)
+/// below it. It closes once z gets back to `exit` on the same side of zero or crosses through
+/// it, or is stopped out when |z| reaches `stop` against the position, or after being held
+/// for `max_holding` bars. After a stop or a time stop the same side is not re-entered until |z|
+/// has fallen back below `entry`. When |z| reaches `kill`, the spread is taken to have broken
+/// away: any open position is closed and no trade is opened for the rest of the series. NaN
+/// z-scores hold the current position. Each rule is off when its argument is omitted.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn generate_signals(
+    zscore: &[f64],
+    entry: f64,
+    exit: f64,
+    stop: Option<f64>,
+    max_holding: Option<usize>,
+    kill: Option<f64>,
+) -> Result<SignalResult, Error> {
+    let (stop, max_holding, kill) = check_rules(entry, exit, stop, max_holding, kill)?;
+    Ok(threshold_signals(
+        zscore,
+        |_| entry,
+        |_| exit,
+        |_| false,
+        stop,
+        max_holding,
+        kill,
+    ))
+}
+
+// The stop, holding limit and kill level of `generate_signals`, each infinite when omitted,
+// after checking them against `entry` and `exit`
+fn check_rules(
+    entry: f64,
+    exit: f64,
+    stop: Option<f64>,
+    max_holding: Option<usize>,
+    kill: Option<f64>,
+) -> Result<(f64, usize, f64), Error> {
+    let stop = stop.unwrap_or(f64::INFINITY);
+    let max_holding = max_holding.unwrap_or(usize::MAX);
+    let kill = kill.unwrap_or(f64::INFINITY);
+    if !(entry.is_finite() && exit.is_finite()) || exit >= entry {
+        return Err(Error::new(
+            "entry and exit must be finite with exit < entry",
+        ));
+    }
+    if stop.is_nan() || stop <= entry {
+        return Err(Error::new("stop must be greater than entry"));
+    }
+    if max_holding == 0 {
+        return Err(Error::new("max_holding must be at least 1"));
+    }
+    if kill.is_nan() || kill <= entry {
+        return Err(Error::new("kill must be greater than entry"));
+    }
+    Ok((stop, max_holding, kill))
+}
+
+/// `generate_signals` switched off in the broken regime: `broken` holds one flag per bar, such
+/// as `markov_regimes(...).broken()`, and on a bar where it is non-zero any open position is
+/// closed (stopped = 4) and none is opened. A side closed that way, like one stopped out, is
+/// not re-entered until |z| has fallen back below `entry`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn generate_regime_signals(
+    zscore: &[f64],
+    entry: f64,
+    exit: f64,
+    broken: &[f64],
+    stop: Option<f64>,
+    max_holding: Option<usize>,
+    kill: Option<f64>,
+) -> Result<SignalResult, Error> {
+    if broken.len() != zscore.len() {
+        return Err(Error::new("broken must hold one flag per bar"));
+    }
+    let (stop, max_holding, kill) = check_rules(entry, exit, stop, max_holding, kill)?;
+    Ok(threshold_signals(
+        zscore,
+        |_| entry,
+        |_| exit,
+        |i| broken[i] != 0.0,
+        stop,
+        max_holding,
+        kill,
+    ))
+}
+
+/// Rolling entry and exit thresholds from the empirical distribution of |z|.
+///
+/// The threshold on each bar is the `entry_quantile` quantile of |z| over the `window` bars
+/// before it (linear interpolation between order statistics, as numpy's default), and likewise
+/// the exit threshold with `exit_quantile`; without `exit_quantile` the exit is at the mean, 0.
+/// A fixed ±2 fires far more often on a fat-tailed spread than on a calm one, while a quantile
+/// keeps the share of bars beyond the threshold roughly constant as the distribution changes.
+/// NaN z-scores in a window are skipped, and a threshold is NaN until its window is full and
+/// holds at least two finite values. Works equally on `zscore` and `ew_zscore` output.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn adaptive_thresholds(
+    zscore: &[f64],
+    window: usize,
+    entry_quantile: f64,
+    exit_quantile: Option<f64>,
+) -> Result<AdaptiveThresholds, Error> {
+    let in_unit = |q: f64| q > 0.0 && q < 1.0;
+    if !in_unit(entry_quantile) {
+        return Err(Error::new("entry_quantile must be in (0, 1)"));
+    }
+    if let Some(exit_quantile) = exit_quantile {
+        if !in_unit(exit_quantile) || exit_quantile >= entry_quantile {
+            return Err(Error::new(
+                "exit_quantile must be in (0, 1) and below entry_quantile",
+            ));
+        }
+    }
+    if window < 2 {
+        return Err(Error::new("window must be at least 2"));
+    }
+
+    let n = zscore.len();
+    let mut entry = vec![f64::NAN; n];
+    let mut exit = vec![
+        if exit_quantile.is_some() {
+            f64::NAN
+        } else {
+            0.0
+        };
+        n
+    ];
+    let levels = [entry_quantile, exit_quantile.unwrap_or(0.0)];
+    let mut magnitudes = Vec::with_capacity(window);
+    for t in window..n {
+        magnitudes.clear();
+        magnitudes.extend(
+            zscore[t - window..t]
+                .iter()
+                .filter(|z| !z.is_nan())
+                .map(|z| z.abs()),
+        );
+        if magnitudes.len() < 2 {
+            continue;
+        }
+        let levels = if exit_quantile.is_some() {
+            &levels[..]
+        } else {
+            &levels[..1]
+        };
+        let thresholds = select_quantiles(&mut magnitudes, levels);
+        entry[t] = thresholds[0];
+        if exit_quantile.is_some() {
+            exit[t] = thresholds[1];
+        }
+    }
+    Ok(AdaptiveThresholds { entry, exit })
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct AdaptiveThresholds {
+    entry: Vec<f64>,
+    exit: Vec<f64>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl AdaptiveThresholds {
+    // Entry threshold on |z| for each bar
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn entry(&self) -> Vec<f64> {
+        self.entry.clone()
+    }
+
+    // Exit threshold for each bar, on the same side of zero as the entry
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn exit(&self) -> Vec<f64> {
+        self.exit.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("entry", &self.entry)
+            .field("exit", &self.exit)
+            .finish()
+    }
+}
+
+/// `generate_signals` with the entry and exit thresholds of `adaptive_thresholds(zscore, window,
+/// entry_quantile, exit_quantile)` in place of fixed levels.
+///
+/// No position opens while the entry threshold is NaN, and an open position holds while the
+/// exit threshold is. `stop` and `kill` are fixed levels and should lie above the thresholds
+/// that `entry_quantile` produces.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn generate_adaptive_signals(
+    zscore: &[f64],
+    window: usize,
+    entry_quantile: f64,
+    exit_quantile: Option<f64>,
+    stop: Option<f64>,
+    max_holding: Option<usize>,
+    kill: Option<f64>,
+) -> Result<SignalResult, Error> {
+    let thresholds = adaptive_thresholds(zscore, window, entry_quantile, exit_quantile)?;
+    let stop = stop.unwrap_or(f64::INFINITY);
+    let max_holding = max_holding.unwrap_or(usize::MAX);
+    let kill = kill.unwrap_or(f64::INFINITY);
+    if stop.is_nan() || kill.is_nan() {
+        return Err(Error::new("stop and kill must be numbers"));
+    }
+    if max_holding == 0 {
+        return Err(Error::new("max_holding must be at least 1"));
+    }
+    Ok(threshold_signals(
+        zscore,
+        |i| thresholds.entry[i],
+        |i| thresholds.exit[i],
+        |_| false,
+        stop,
+        max_holding,
+        kill,
+    ))
+}
+
+/// Thresholds of `detect_crossings_with_thresholds`; the default matches an empty thresholds
+/// object.
+#[derive(Clone, Copy, Debug)]
+pub struct CrossingThresholds {
+    // Level of |z| that opens a position
+    pub entry: f64,
+    // Level z gets back to, on the same side of zero, that closes it
+    pub exit: f64,
+    // Level of |z| against the position that stops it out
+    pub stop: Option<f64>,
+}
+
+impl Default for CrossingThresholds {
+    fn default() -> CrossingThresholds {
+        CrossingThresholds {
+            entry: 2.0,
+            exit: 0.0,
+            stop: None,
+        }
+    }
+}
+
+impl CrossingThresholds {
+    #[cfg(feature = "wasm")]
+    pub(crate) fn from_js(thresholds: &JsValue) -> Result<CrossingThresholds, Error> {
+        let mut parsed = CrossingThresholds::default();
+        if thresholds.is_undefined() || thresholds.is_null() {
+            return Ok(parsed);
+        }
+        if !thresholds.is_object() {
+            return Err(Error::new("thresholds must be an object"));
+        }
+
+        let number = |key: &str| -> Result<Option<f64>, Error> {
+            let value = get_property(thresholds, key)?;
+            if value.is_undefined() || value.is_null() {
+                return Ok(None);
+            }
+            value
+                .as_f64()
+                .map(Some)
+                .ok_or_else(|| Error::new(&format!("{} must be a number", key)))
+        };
+        if let Some(value) = number("entry")? {
+            parsed.entry = value;
+        }
+        if let Some(value) = number("exit")? {
+            parsed.exit = value;
+        }
+        parsed.stop = number("stop")?;
+        Ok(parsed)
+    }
+
+    // The levels z can cross, ascending, with their kinds
+    fn levels(&self) -> Vec<(f64, CrossingKind)> {
+        let mut levels = vec![
+            (-self.entry, CrossingKind::Entry),
+            (self.entry, CrossingKind::Entry),
+        ];
+        if self.exit == 0.0 {
+            levels.push((0.0, CrossingKind::Exit));
+        } else {
+            let exit = self.exit.abs();
+            levels.extend([(-exit, CrossingKind::Exit), (exit, CrossingKind::Exit)]);
+        }
+        if let Some(stop) = self.stop {
+            levels.extend([(-stop, CrossingKind::Stop), (stop, CrossingKind::Stop)]);
+        }
+        levels.sort_by(|a, b| a.0.total_cmp(&b.0));
+        levels
+    }
+}
+
+// Which threshold a crossing is of
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CrossingKind {
+    Entry,
+    Exit,
+    Stop,
+}
+
+impl CrossingKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CrossingKind::Entry => "entry",
+            CrossingKind::Exit => "exit",
+            CrossingKind::Stop => "stop",
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct ThresholdCrossings {
+    indices: Vec<u32>,
+    levels: Vec<f64>,
+    kinds: Vec<String>,
+    directions: Vec<f64>,
+    // Spread position of the threshold strategy after the last bar: +1 long, -1 short, 0 flat
+    pub position: f64,
+    entry_index: Option<usize>,
+    last_index: Option<usize>,
+    // Last non-NaN z-score (NaN when there is none)
+    pub last_zscore: f64,
+    // Band of the last z-score, signed by its side of zero: 0 within the exit level, 1 between
+    // exit and entry, 2 beyond entry, 3 beyond the stop
+    pub zone: i32,
+    // Whether the position changed on the last bar, so an entry, exit or stop is due now
+    pub actionable: bool,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ThresholdCrossings {
+    // Bar of each crossing, in the order they happened
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn indices(&self) -> Vec<u32> {
+        self.indices.clone()
+    }
+
+    // Signed level crossed, e.g. -2 for the lower entry band
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn levels(&self) -> Vec<f64> {
+        self.levels.clone()
+    }
+
+    // "entry", "exit" or "stop"
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn kinds(&self) -> Vec<String> {
+        self.kinds.clone()
+    }
+
+    // +1 when z crossed the level upwards, -1 downwards; a crossing away from zero (direction
+    // equal to the sign of the level) is the one that triggers an entry or a stop
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn directions(&self) -> Vec<f64> {
+        self.directions.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn count(&self) -> usize {
+        self.indices.len()
+    }
+
+    // Bar on which the open position was entered, if one is open
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn entry_index(&self) -> Option<usize> {
+        self.entry_index
+    }
+
+    // Bar of the last non-NaN z-score
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn last_index(&self) -> Option<usize> {
+        self.last_index
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("indices", &self.indices)
+            .field("levels", &self.levels)
+            .field("kinds", &self.kinds)
+            .field("directions", &self.directions)
+            .field("position", self.position)
+            .field("entry_index", self.entry_index)
+            .field("last_index", self.last_index)
+            .field("last_zscore", self.last_zscore)
+            .field("zone", self.zone as f64)
+            .field("actionable", self.actionable)
+            .finish()
+    }
+}
+
+/// Every crossing of the entry, exit and stop levels by a z-score series, and the live state of
+/// the threshold strategy at its last bar.
+///
+/// The levels are ±`entry`, ±`exit` (one level when `exit` is 0) and ±`stop`. A level is crossed
+/// on the bar where z reaches it from the side nearer zero or leaves it back towards zero, so a
+/// band that is touched and left gives one crossing each way; a jump across several levels on one
+/// bar gives a crossing for each, in the order the path meets them. NaN z-scores are skipped and
+/// the next value is compared with the last non-NaN one.
+///
+/// The state is that of `generate_signals(zscore, entry, exit, stop)` after the last bar: the
+/// position, the bar it was entered on, the zone of the last z-score and whether the position
+/// changed on that bar, which is what a monitor needs to flag a pair as actionable.
+///
+/// `thresholds` is an optional object with `entry` (default 2), `exit` (default 0) and `stop`
+/// (default none), constrained as in `generate_signals`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn detect_crossings(zscore: &[f64], thresholds: JsValue) -> Result<ThresholdCrossings, Error> {
+    let thresholds = CrossingThresholds::from_js(&thresholds)?;
+    detect_crossings_with_thresholds(zscore, &thresholds)
+}
+
+/// `detect_crossings` for native callers, with the thresholds as a struct.
+pub fn detect_crossings_with_thresholds(
+    zscore: &[f64],
+    thresholds: &CrossingThresholds,
+) -> Result<ThresholdCrossings, Error> {
+    let (stop, max_holding, kill) = check_rules(
+        thresholds.entry,
+        thresholds.exit,
+        thresholds.stop,
+        None,
+        None,
+    )?;
+    if thresholds.entry <= 0.0 {
+        return Err(Error::new("entry must be positive"));
+    }
+    let levels = thresholds.levels();
+
+    // Which side of a level z is on, true being away from zero; exact zeros keep the side of
+    // the zero level
+    let outside = |z: f64, level: f64| -> Option<bool> {
+        if level > 0.0 {
+            Some(z >= level)
+        } else if level < 0.0 {
+            Some(z <= level)
+        } else if z != 0.0 {
+            Some(z > 0.0)
+        } else {
+            None
+        }
+    };
+    let mut sides: Vec<Option<bool>> = vec![None; levels.len()];
+    let (mut indices, mut crossed, mut kinds, mut directions) =
+        (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    let mut previous: Option<f64> = None;
+    let mut last_index = None;
+    for (i, &z) in zscore.iter().enumerate() {
+        if z.is_nan() {
+            continue;
+        }
+        let rising = previous.is_some_and(|p| z > p);
+        // Upward moves meet the levels from the bottom, downward ones from the top
+        for step in 0..levels.len() {
+            let k = if rising {
+                step
+            } else {
+                levels.len() - 1 - step
+            };
+            let (level, kind) = levels[k];
+            let Some(side) = outside(z, level) else {
+                continue;
+            };
+            if sides[k].is_some_and(|s| s != side) {
+                indices.push(i as u32);
+                crossed.push(level);
+                kinds.push(kind.as_str().to_string());
+                directions.push(if rising { 1.0 } else { -1.0 });
+            }
+            sides[k] = Some(side);
+        }
+        previous = Some(z);
+        last_index = Some(i);
+    }
+
+    let signals = threshold_signals(
+        zscore,
+        |_| thresholds.entry,
+        |_| thresholds.exit,
+        |_| false,
+        stop,
+        max_holding,
+        kill,
+    );
+    let position = signals.positions.last().copied().unwrap_or(0.0);
+    let before = match signals.positions.len() {
+        0 | 1 => 0.0,
+        n => signals.positions[n - 2],
+    };
+    let entry_index = signals
+        .trades
+        .chunks_exact(TRADE_STRIDE)
+        .last()
+        .filter(|trade| trade[1].is_nan())
+        .map(|trade| trade[0] as usize);
+    let last_zscore = previous.unwrap_or(f64::NAN);
+    let magnitude = last_zscore.abs();
+    let band = if thresholds.stop.is_some_and(|stop| magnitude >= stop) {
+        3
+    } else if magnitude >= thresholds.entry {
+        2
+    } else if magnitude > thresholds.exit.abs() {
+        1
+    } else {
+        0
+    };
+    Ok(ThresholdCrossings {
+        indices,
+        levels: crossed,
+        kinds,
+        directions,
+        position,
+        entry_index,
+        last_index,
+        last_zscore,
+        zone: if last_zscore < 0.0 { -band } else { band },
+        actionable: last_index.is_some_and(|i| i + 1 == zscore.len()) && position != before,
+    })
+}
+
+// State machine of `generate_signals`, with the entry and exit thresholds of each bar given by
+// `entry` and `exit`, and no trading on the bars where `broken` is true
+fn threshold_signals(
+    zscore: &[f64],
+    entry: impl Fn(usize) -> f64,
+    exit: impl Fn(usize) -> f64,
+    broken: impl Fn(usize) -> bool,
+    stop: f64,
+    max_holding: usize,
+    kill: f64,
+) -> SignalResult {
+    let mut positions = Vec::with_capacity(zscore.len());
+    let mut trades = Vec::new();
+    // Sign of z at entry (the position is its opposite), 0 when flat
+    let mut direction = 0.0;
+    // Side that was last stopped out and is blocked until |z| drops below entry
+    let mut blocked = 0.0;
+    let mut entered = 0;
+    let mut halted_at = None;
+
+    for (i, &z) in zscore.iter().enumerate() {
+        let (entry, exit) = (entry(i), exit(i));
+        if halted_at.is_none() && z.abs() >= kill {
+            if direction != 0.0 {
+                let trade = trades.len() - TRADE_STRIDE;
+                trades[trade + 1] = i as f64;
+                trades[trade + 3] = 3.0;
+                direction = 0.0;
+            }
+            halted_at = Some(i);
+        }
+        if halted_at.is_none() && broken(i) {
+            if direction != 0.0 {
+                let trade = trades.len() - TRADE_STRIDE;
+                trades[trade + 1] = i as f64;
+                trades[trade + 3] = 4.0;
+                blocked = direction;
+                direction = 0.0;
+            }
+        } else if halted_at.is_none() && !z.is_nan() {
+            if blocked != 0.0 && z * blocked < entry {
+                blocked = 0.0;
+            }
+            if direction == 0.0 {
+                if z.abs() >= entry && z.signum() != blocked {
+                    direction = z.signum();
+                    entered = i;
+                    trades.extend([i as f64, f64::NAN, -direction, 0.0]);
+                }
+            } else if z * direction >= stop || z * direction <= exit || i - entered >= max_holding {
+                let reason = if z * direction >= stop {
+                    1.0
+                } else if z * direction <= exit {
+                    0.0
+                } else {
+                    2.0
+                };
+                let trade = trades.len() - TRADE_STRIDE;
+                trades[trade + 1] = i as f64;
+                trades[trade + 3] = reason;
+                if reason != 0.0 {
+                    blocked = direction;
+                }
+                direction = 0.0;
+            }
+        }
+        positions.push(if direction == 0.0 { 0.0 } else { -direction });
+    }
+
+    SignalResult {
+        positions,
+        trades,
+        halted_at,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::{backtest_with_options, BacktestOptions};
+    use crate::rng::fixtures::cointegrated_pair;
+
+    // AR(1) spread and a random-walk second leg, with prices_a = 3 + 1.5·prices_b + spread
+    fn pair(n: usize, seed: u32) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+        let (prices_a, prices_b, spread) = cointegrated_pair(n, 1.5, 0.9, seed);
+        (spread, prices_a, prices_b)
+    }
+
+    #[test]
+    fn threshold_yields_the_target_trade_count_in_the_backtest() {
+        let (spread, prices_a, prices_b) = pair(2000, 5);
+        let window = 30;
+        let zscore = rolling_zscore(&spread, window);
+        for target in [10, 25, 50] {
+            let threshold = threshold_for_frequency(&spread, window, target);
+            assert!(threshold.is_finite() && threshold > 0.0);
+            let signals = generate_signals(&zscore, threshold, 0.0, None, None, None).unwrap();
+            let result = backtest_with_options(
+                &prices_a,
+                &prices_b,
+                &[1.5],
+                &signals.positions(),
+                &BacktestOptions::default(),
+            )
+            .unwrap();
+            let trades = result.trade_count();
+            assert!(
+                trades.abs_diff(target) <= (target / 10).max(1),
+                "target {} gave {} trades at threshold {}",
+                target,
+                trades,
+                threshold
+            );
+        }
+    }
+
+    #[test]
+    fn fewer_trades_need_a_higher_threshold() {
+        let (spread, _, _) = pair(2000, 6);
+        let frequent = threshold_for_frequency(&spread, 30, 60);
+        let rare = threshold_for_frequency(&spread, 30, 10);
+        assert!(rare > frequent, "{} vs {}", rare, frequent);
+    }
+
+    #[test]
+    fn no_bars_means_no_crossings() {
+        let crossings =
+            detect_crossings_with_thresholds(&[], &CrossingThresholds::default()).unwrap();
+        assert_eq!(crossings.count(), 0);
+        assert_eq!(crossings.last_index(), None);
+        assert_eq!(crossings.position, 0.0);
+        assert!(!crossings.actionable);
+    }
+}
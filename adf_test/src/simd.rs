@@ -0,0 +1,220 @@
+// Vector kernels for the inner loops of the least-squares solver and the variance.
+//
+// The slices are processed four values at a time on four independent lanes: f64x2
+// vectors in a wasm32 build with the `simd` feature compiled with
+// `-C target-feature=+simd128`, and four scalar accumulators otherwise, which the
+// compiler maps onto the host's vector registers where it can. Every build uses
+// the same lane layout and reduction order, so all of them give identical results.
+// The lane operations are forced inline, since the size-optimised release profile
+// would otherwise leave them as calls in the innermost loops.
+//
+// Long sums are compensated: the lanes add up blocks of 64 values and the block
+// totals are accumulated with Neumaier's compensated summation, so a sum's
+// rounding error is bounded by the block length instead of growing with the length
+// of the series, at the speed of a plain sum. A statistic near a decision
+// threshold then does not flip between builds, or on a reordered input, over the
+// last bits of a long accumulation. (Wasm arithmetic itself is IEEE-754 and
+// identical in every engine, so a given build gives the same bits in every
+// browser.)
+
+#[cfg(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128"))]
+use core::arch::wasm32::*;
+
+// Four f64 lanes as two wasm simd128 vectors
+#[cfg(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128"))]
+#[derive(Clone, Copy)]
+struct Lanes([v128; 2]);
+
+#[cfg(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128"))]
+impl Lanes {
+    #[inline(always)]
+    fn splat(value: f64) -> Lanes {
+        Lanes([f64x2_splat(value); 2])
+    }
+
+    // The first four values of `values`
+    #[inline(always)]
+    fn load(values: &[f64]) -> Lanes {
+        Lanes([f64x2(values[0], values[1]), f64x2(values[2], values[3])])
+    }
+
+    #[inline(always)]
+    fn store(self, values: &mut [f64]) {
+        values[0] = f64x2_extract_lane::<0>(self.0[0]);
+        values[1] = f64x2_extract_lane::<1>(self.0[0]);
+        values[2] = f64x2_extract_lane::<0>(self.0[1]);
+        values[3] = f64x2_extract_lane::<1>(self.0[1]);
+    }
+
+    #[inline(always)]
+    fn add(self, other: Lanes) -> Lanes {
+        Lanes([
+            f64x2_add(self.0[0], other.0[0]),
+            f64x2_add(self.0[1], other.0[1]),
+        ])
+    }
+
+    #[inline(always)]
+    fn sub(self, other: Lanes) -> Lanes {
+        Lanes([
+            f64x2_sub(self.0[0], other.0[0]),
+            f64x2_sub(self.0[1], other.0[1]),
+        ])
+    }
+
+    #[inline(always)]
+    fn mul(self, other: Lanes) -> Lanes {
+        Lanes([
+            f64x2_mul(self.0[0], other.0[0]),
+            f64x2_mul(self.0[1], other.0[1]),
+        ])
+    }
+}
+
+// Four f64 lanes as independent accumulators
+#[cfg(not(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128")))]
+#[derive(Clone, Copy)]
+struct Lanes([f64; 4]);
+
+#[cfg(not(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128")))]
+impl Lanes {
+    #[inline(always)]
+    fn splat(value: f64) -> Lanes {
+        Lanes([value; 4])
+    }
+
+    // The first four values of `values`
+    #[inline(always)]
+    fn load(values: &[f64]) -> Lanes {
+        Lanes([values[0], values[1], values[2], values[3]])
+    }
+
+    #[inline(always)]
+    fn store(self, values: &mut [f64]) {
+        values[..4].copy_from_slice(&self.0);
+    }
+
+    #[inline(always)]
+    fn add(self, other: Lanes) -> Lanes {
+        Lanes([
+            self.0[0] + other.0[0],
+            self.0[1] + other.0[1],
+            self.0[2] + other.0[2],
+            self.0[3] + other.0[3],
+        ])
+    }
+
+    #[inline(always)]
+    fn sub(self, other: Lanes) -> Lanes {
+        Lanes([
+            self.0[0] - other.0[0],
+            self.0[1] - other.0[1],
+            self.0[2] - other.0[2],
+            self.0[3] - other.0[3],
+        ])
+    }
+
+    #[inline(always)]
+    fn mul(self, other: Lanes) -> Lanes {
+        Lanes([
+            self.0[0] * other.0[0],
+            self.0[1] * other.0[1],
+            self.0[2] * other.0[2],
+            self.0[3] * other.0[3],
+        ])
+    }
+}
+
+// Values summed plainly on the lanes before their total moves into the compensated sum
+const BLOCK: usize = 64;
+
+// Running sum with Neumaier's compensation
+#[derive(Clone, Copy, Default)]
+pub(crate) struct CompensatedSum {
+    sum: f64,
+    compensation: f64,
+}
+
+impl CompensatedSum {
+    pub(crate) fn add(&mut self, value: f64) {
+        let total = self.sum + value;
+        // The low-order bits lost from the smaller of the two terms
+        self.compensation += if self.sum.abs() >= value.abs() {
+            (self.sum - total) + value
+        } else {
+            (value - total) + self.sum
+        };
+        self.sum = total;
+    }
+
+    pub(crate) fn value(&self) -> f64 {
+        self.sum + self.compensation
+    }
+
+    // Add the four lane totals of a block
+    #[inline(always)]
+    fn add_lanes(&mut self, lanes: Lanes) {
+        let mut values = [0.0; 4];
+        lanes.store(&mut values);
+        for v in values {
+            self.add(v);
+        }
+    }
+}
+
+// Σ a_i·b_i over two slices of the same length
+pub(crate) fn dot(a: &[f64], b: &[f64]) -> f64 {
+    debug_assert_eq!(a.len(), b.len());
+    let mut total = CompensatedSum::default();
+    for (block_a, block_b) in a.chunks(BLOCK).zip(b.chunks(BLOCK)) {
+        let (chunks_a, chunks_b) = (block_a.chunks_exact(4), block_b.chunks_exact(4));
+        let tail = chunks_a.remainder().iter().zip(chunks_b.remainder());
+        let mut acc = Lanes::splat(0.0);
+        for (x, y) in chunks_a.zip(chunks_b) {
+            acc = acc.add(Lanes::load(x).mul(Lanes::load(y)));
+        }
+        total.add_lanes(acc);
+        for (x, y) in tail {
+            total.add(x * y);
+        }
+    }
+    total.value()
+}
+
+// x_i -= scale·v_i over two slices of the same length
+pub(crate) fn sub_scaled(x: &mut [f64], scale: f64, v: &[f64]) {
+    debug_assert_eq!(x.len(), v.len());
+    let factor = Lanes::splat(scale);
+    let mut chunks_x = x.chunks_exact_mut(4);
+    let mut chunks_v = v.chunks_exact(4);
+    for (xs, vs) in chunks_x.by_ref().zip(chunks_v.by_ref()) {
+        Lanes::load(xs).sub(factor.mul(Lanes::load(vs))).store(xs);
+    }
+    for (xi, vi) in chunks_x
+        .into_remainder()
+        .iter_mut()
+        .zip(chunks_v.remainder())
+    {
+        *xi -= scale * vi;
+    }
+}
+
+// Σ (v_i - mean)²
+pub(crate) fn sum_squared_deviations(values: &[f64], mean: f64) -> f64 {
+    let centre = Lanes::splat(mean);
+    let mut total = CompensatedSum::default();
+    for block in values.chunks(BLOCK) {
+        let chunks = block.chunks_exact(4);
+        let tail = chunks.remainder();
+        let mut acc = Lanes::splat(0.0);
+        for chunk in chunks {
+            let deviation = Lanes::load(chunk).sub(centre);
+            acc = acc.add(deviation.mul(deviation));
+        }
+        total.add_lanes(acc);
+        for v in tail {
+            total.add((v - mean) * (v - mean));
+        }
+    }
+    total.value()
+}
@@ -0,0 +1,242 @@
+// Position sizing: from ±1 signals to sized spread positions for `backtest`.
+//
+// A sized position is the gross exposure as a multiple of the equity. Volatility
+// targeting scales it so the strategy's risk stays near a chosen annualised level
+// whatever the spread's regime; the Kelly fraction scales it to the growth-optimal
+// bet implied by past trade outcomes, which is far too aggressive when those
+// outcomes are estimated, hence the multiplier and the cap.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::error::Error;
+#[cfg(feature = "wasm")]
+use crate::get_property;
+use crate::stats::{nanmean, nanstd, NanPolicy};
+
+/// Options of `volatility_target_positions_with_options`; the default matches an empty options
+/// object.
+#[derive(Clone, Copy, Debug)]
+pub struct VolatilityTargetOptions {
+    // Annualised volatility aimed for, as a fraction (0.10 = 10%)
+    pub target_volatility: f64,
+    // Trailing returns of the realised-volatility estimate
+    pub window: usize,
+    pub periods_per_year: f64,
+    // Largest size a position may reach, however calm the spread
+    pub max_leverage: f64,
+}
+
+impl Default for VolatilityTargetOptions {
+    fn default() -> VolatilityTargetOptions {
+        VolatilityTargetOptions {
+            target_volatility: 0.1,
+            window: 20,
+            periods_per_year: 252.0,
+            max_leverage: 2.0,
+        }
+    }
+}
+
+impl VolatilityTargetOptions {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Result<VolatilityTargetOptions, Error> {
+        let mut parsed = VolatilityTargetOptions::default();
+        if options.is_undefined() || options.is_null() {
+            return Ok(parsed);
+        }
+        if !options.is_object() {
+            return Err(Error::new("options must be an object"));
+        }
+
+        let positive = |key: &str| -> Result<Option<f64>, Error> {
+            let value = get_property(options, key)?;
+            if value.is_undefined() {
+                return Ok(None);
+            }
+            value
+                .as_f64()
+                .filter(|v| *v > 0.0 && v.is_finite())
+                .map(Some)
+                .ok_or_else(|| Error::new(&format!("{} must be a positive number", key)))
+        };
+        if let Some(target_volatility) = positive("target_volatility")? {
+            parsed.target_volatility = target_volatility;
+        }
+        if let Some(periods_per_year) = positive("periods_per_year")? {
+            parsed.periods_per_year = periods_per_year;
+        }
+        if let Some(max_leverage) = positive("max_leverage")? {
+            parsed.max_leverage = max_leverage;
+        }
+
+        let window = get_property(options, "window")?;
+        if !window.is_undefined() {
+            parsed.window = window
+                .as_f64()
+                .filter(|v| *v >= 2.0 && v.fract() == 0.0 && *v <= u32::MAX as f64)
+                .map(|v| v as usize)
+                .ok_or_else(|| Error::new("window must be an integer of at least 2"))?;
+        }
+        Ok(parsed)
+    }
+}
+
+/// Scale `signals` so that a position's volatility is about `target_volatility` a year.
+///
+/// The volatility is that of a unit position's per-bar return, the spread change
+/// Δa - β·Δb over the gross notional a + |β|·b of the previous bar, measured over the trailing
+/// `window` returns ending at each bar and annualised with `periods_per_year`. Each signal is
+/// multiplied by target / volatility, capped at `max_leverage`, and bars with fewer than
+/// `window` returns behind them are sized 0. `hedge_ratio` is one value or one per bar, as for
+/// `backtest`, which the result plugs into unchanged; since the backtester fixes a trade's size on
+/// its entry bar, the size tracks the volatility from one trade to the next.
+///
+/// `options` is an optional object with `target_volatility` (default 0.1), `window` (20),
+/// `periods_per_year` (252) and `max_leverage` (2).
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn volatility_target_positions(
+    prices_a: &[f64],
+    prices_b: &[f64],
+    hedge_ratio: &[f64],
+    signals: &[f64],
+    options: JsValue,
+) -> Result<Vec<f64>, Error> {
+    let options = VolatilityTargetOptions::from_js(&options)?;
+    volatility_target_positions_with_options(prices_a, prices_b, hedge_ratio, signals, &options)
+}
+
+/// `volatility_target_positions` for native callers, with the options as a struct.
+pub fn volatility_target_positions_with_options(
+    prices_a: &[f64],
+    prices_b: &[f64],
+    hedge_ratio: &[f64],
+    signals: &[f64],
+    options: &VolatilityTargetOptions,
+) -> Result<Vec<f64>, Error> {
+    let positive = |v: f64| v > 0.0 && v.is_finite();
+    if !positive(options.target_volatility) {
+        return Err(Error::new("target_volatility must be a positive number"));
+    }
+    if !positive(options.periods_per_year) {
+        return Err(Error::new("periods_per_year must be a positive number"));
+    }
+    if !positive(options.max_leverage) {
+        return Err(Error::new("max_leverage must be a positive number"));
+    }
+    if options.window < 2 {
+        return Err(Error::new("window must be at least 2"));
+    }
+    let n = prices_a.len();
+    if prices_b.len() != n || signals.len() != n {
+        return Err(Error::new(
+            "prices_a, prices_b and signals must have the same length",
+        ));
+    }
+    if hedge_ratio.len() != 1 && hedge_ratio.len() != n {
+        return Err(Error::new(
+            "hedge_ratio must hold one value or one value per bar",
+        ));
+    }
+    if prices_a.iter().chain(prices_b).any(|p| !positive(*p)) {
+        return Err(Error::new("prices must be positive and finite"));
+    }
+    if hedge_ratio.iter().chain(signals).any(|v| !v.is_finite()) {
+        return Err(Error::new(
+            "hedge_ratio and signals must contain only finite values",
+        ));
+    }
+
+    let beta_at = |t: usize| hedge_ratio[if hedge_ratio.len() == 1 { 0 } else { t }];
+    // Return at bar t of a unit position opened at bar t - 1
+    let unit_returns: Vec<f64> = (1..n)
+        .map(|t| {
+            let beta = beta_at(t - 1);
+            (prices_a[t] - prices_a[t - 1] - beta * (prices_b[t] - prices_b[t - 1]))
+                / (prices_a[t - 1] + beta.abs() * prices_b[t - 1])
+        })
+        .collect();
+
+    let annualize = options.periods_per_year.sqrt();
+    Ok((0..n)
+        .map(|t| {
+            if t < options.window {
+                return 0.0;
+            }
+            let volatility = nanstd(
+                &unit_returns[t - options.window..t],
+                1,
+                NanPolicy::Propagate,
+            ) * annualize;
+            let leverage = if volatility > 0.0 {
+                (options.target_volatility / volatility).min(options.max_leverage)
+            } else {
+                options.max_leverage
+            };
+            signals[t] * leverage
+        })
+        .collect())
+}
+
+/// Kelly fraction of the equity to deploy, from the returns of past trades (such as
+/// `backtest(...).trade_returns`), times `multiplier` (default 0.5, half Kelly) and capped at
+/// `cap` (default 1).
+///
+/// With p the share of winning trades, W their mean return and L the mean loss of the others,
+/// the growth-optimal exposure is p/L - (1 - p)/W. Spread trades win and lose small fractions of
+/// the exposure, so the full fraction is usually a large multiple of the equity and very
+/// sensitive to estimation error in p, W and L. Without losing trades the fraction is `cap`,
+/// without winners (or with a negative edge) 0.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn kelly_fraction(
+    trade_returns: &[f64],
+    multiplier: Option<f64>,
+    cap: Option<f64>,
+) -> Result<f64, Error> {
+    let multiplier = multiplier.unwrap_or(0.5);
+    let cap = cap.unwrap_or(1.0);
+    if !(multiplier > 0.0 && multiplier.is_finite()) {
+        return Err(Error::new("multiplier must be a positive number"));
+    }
+    if !(cap > 0.0 && cap.is_finite()) {
+        return Err(Error::new("cap must be a positive number"));
+    }
+    if trade_returns.is_empty() {
+        return Err(Error::new("trade_returns is empty"));
+    }
+    if trade_returns.iter().any(|r| !r.is_finite()) {
+        return Err(Error::new("trade_returns must contain only finite values"));
+    }
+
+    let wins: Vec<f64> = trade_returns.iter().copied().filter(|r| *r > 0.0).collect();
+    let losses: Vec<f64> = trade_returns.iter().copied().filter(|r| *r < 0.0).collect();
+    if wins.is_empty() {
+        return Ok(0.0);
+    }
+    if losses.is_empty() {
+        return Ok(cap);
+    }
+    let p = wins.len() as f64 / trade_returns.len() as f64;
+    let mean_win = nanmean(&wins, NanPolicy::Propagate);
+    let mean_loss = -nanmean(&losses, NanPolicy::Propagate);
+    let kelly = p / mean_loss - (1.0 - p) / mean_win;
+    Ok((multiplier * kelly).clamp(0.0, cap))
+}
+
+/// `signals` scaled by `kelly_fraction(trade_returns, multiplier, cap)`, ready for `backtest`.
+///
+/// Estimate the trade returns on data before the signals to keep the sizing out of sample.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn kelly_positions(
+    signals: &[f64],
+    trade_returns: &[f64],
+    multiplier: Option<f64>,
+    cap: Option<f64>,
+) -> Result<Vec<f64>, Error> {
+    if signals.iter().any(|s| !s.is_finite()) {
+        return Err(Error::new("signals must contain only finite values"));
+    }
+    let fraction = kelly_fraction(trade_returns, multiplier, cap)?;
+    Ok(signals.iter().map(|s| s * fraction).collect())
+}
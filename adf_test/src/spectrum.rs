@@ -0,0 +1,145 @@
+// Spectral density estimates: the periodogram and Welch's averaged periodogram.
+//
+// A spread that reverts on a regular schedule (a monthly roll, a quarterly
+// report) concentrates its variance at that frequency. The raw periodogram
+// resolves every Fourier frequency but its estimate at each is as noisy as a
+// single squared observation; Welch trades resolution for variance by averaging
+// the periodograms of overlapping tapered segments.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::error::Error;
+use crate::fft::real_fft;
+use crate::json::JsonObject;
+use crate::stats::{nanmean, NanPolicy};
+use crate::validate::{require_finite, require_length};
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct Spectrum {
+    frequencies: Vec<f64>,
+    power: Vec<f64>,
+    // Frequency of the largest power above zero frequency
+    pub dominant_frequency: f64,
+    // 1 / dominant_frequency, in the units of 1 / fs (bars with the default fs)
+    pub dominant_period: f64,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Spectrum {
+    // 0, fs/n, 2·fs/n, ... up to the Nyquist frequency fs/2
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn frequencies(&self) -> Vec<f64> {
+        self.frequencies.clone()
+    }
+
+    // One-sided power spectral density at each frequency, in units² per unit of fs
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn power(&self) -> Vec<f64> {
+        self.power.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("frequencies", &self.frequencies)
+            .field("power", &self.power)
+            .field("dominant_frequency", self.dominant_frequency)
+            .field("dominant_period", self.dominant_period)
+            .finish()
+    }
+}
+
+/// Periodogram of `series` sampled at `fs` observations per unit of time (default 1, so that
+/// frequencies are in cycles per bar), as scipy's `signal.periodogram` with its defaults:
+/// constant detrending, no taper, density scaling, one-sided.
+///
+/// The power integrates to the variance of the series over [0, fs/2]. Computed with an FFT of
+/// the series' own length, so any length costs O(n log n).
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn periodogram(series: &[f64], fs: Option<f64>) -> Result<Spectrum, Error> {
+    let fs = checked_fs(fs)?;
+    check_series(series, 2)?;
+    let n = series.len();
+    let mut power = vec![0.0; n / 2 + 1];
+    accumulate(&mut power, series, &vec![1.0; n], fs);
+    Ok(spectrum(power, n, fs))
+}
+
+/// Welch's power spectral density of `series` from Hann-tapered segments of `segment_length`
+/// observations overlapping by half, as scipy's `signal.welch` with its defaults (constant
+/// detrending of each segment, density scaling, mean of the segment periodograms).
+///
+/// Frequencies are multiples of fs / `segment_length`, so shorter segments give a smoother but
+/// coarser spectrum; `segment_length` at most the series length, and a cycle needs
+/// a segment several periods long to show up.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn welch(series: &[f64], segment_length: usize, fs: Option<f64>) -> Result<Spectrum, Error> {
+    let fs = checked_fs(fs)?;
+    check_series(series, 2)?;
+    if segment_length < 2 || segment_length > series.len() {
+        return Err(Error::new(
+            "segment_length must be between 2 and the series length",
+        ));
+    }
+
+    let m = segment_length;
+    // Periodic Hann window, scipy's default for spectral estimation
+    let window: Vec<f64> = (0..m)
+        .map(|i| 0.5 - 0.5 * (2.0 * std::f64::consts::PI * i as f64 / m as f64).cos())
+        .collect();
+    let step = m - m / 2;
+    let segments = (series.len() - m) / step + 1;
+    let mut power = vec![0.0; m / 2 + 1];
+    for s in 0..segments {
+        accumulate(&mut power, &series[s * step..s * step + m], &window, fs);
+    }
+    for p in power.iter_mut() {
+        *p /= segments as f64;
+    }
+    Ok(spectrum(power, m, fs))
+}
+
+fn checked_fs(fs: Option<f64>) -> Result<f64, Error> {
+    let fs = fs.unwrap_or(1.0);
+    if !(fs > 0.0 && fs.is_finite()) {
+        return Err(Error::new("fs must be a positive number"));
+    }
+    Ok(fs)
+}
+
+fn check_series(series: &[f64], minimum: usize) -> Result<(), Error> {
+    require_finite(series, "series")?;
+    require_length(series, minimum, "series", "the spectral estimate")
+}
+
+// Add the one-sided density periodogram of `segment`, demeaned and multiplied by `window`, to
+// `power`
+fn accumulate(power: &mut [f64], segment: &[f64], window: &[f64], fs: f64) {
+    let n = segment.len();
+    let mean = nanmean(segment, NanPolicy::Propagate);
+    let tapered: Vec<f64> = segment
+        .iter()
+        .zip(window)
+        .map(|(v, w)| (v - mean) * w)
+        .collect();
+    let scale = 1.0 / (fs * window.iter().map(|w| w * w).sum::<f64>());
+    let (re, im) = real_fft(&tapered);
+    for (k, p) in power.iter_mut().enumerate() {
+        // Every bin but zero and Nyquist also stands for its negative frequency
+        let fold = if k == 0 || 2 * k == n { 1.0 } else { 2.0 };
+        *p += fold * scale * (re[k] * re[k] + im[k] * im[k]);
+    }
+}
+
+fn spectrum(power: Vec<f64>, n: usize, fs: f64) -> Spectrum {
+    let frequencies: Vec<f64> = (0..power.len()).map(|k| k as f64 * fs / n as f64).collect();
+    let dominant = (1..power.len()).max_by(|&a, &b| power[a].total_cmp(&power[b]));
+    let dominant_frequency = dominant.map_or(f64::NAN, |k| frequencies[k]);
+    Spectrum {
+        frequencies,
+        power,
+        dominant_frequency,
+        dominant_period: 1.0 / dominant_frequency,
+    }
+}
@@ -0,0 +1,539 @@
+// Construction of a spread from two legs, and diagnostics for a single, already
+// constructed spread series.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::adf::{adf_regression, adf_result_from_fit, recommended_sample, Regression};
+use crate::error::Error;
+#[cfg(feature = "wasm")]
+use crate::get_property;
+use crate::hedge::{min_variance_hedge_ratio, tls_hedge_ratio};
+use crate::hurst::hurst_variance;
+use crate::json::{JsonObject, RawJson};
+use crate::kalman::KalmanHedge;
+use crate::linalg::CovarianceType;
+use crate::mean_reversion::half_life_fit;
+use crate::rolling::rolling_regression;
+#[cfg(feature = "wasm")]
+use crate::set_property;
+use crate::stats::{nanmean, nanstd, NanPolicy};
+use crate::validate::{require_finite, require_length, require_same_length, require_varying};
+use crate::zscore::rolling_zscore;
+use crate::AdfResult;
+
+pub(crate) struct CrossingStats {
+    pub mean_crossings: usize,
+    // Average spacing between consecutive mean crossings, NaN with fewer than two crossings
+    pub avg_bars_between_crossings: f64,
+}
+
+// Count how often the spread crosses its sample mean. Points exactly on the mean
+// keep the previous side so they don't register as two crossings.
+pub(crate) fn crossing_stats(spread: &[f64]) -> CrossingStats {
+    let mean = nanmean(spread, NanPolicy::Skip);
+    let mut crossing_indices = Vec::new();
+    let mut side = 0.0;
+    for (i, &v) in spread.iter().enumerate() {
+        let d = v - mean;
+        if d.is_nan() || d == 0.0 {
+            continue;
+        }
+        let current = d.signum();
+        if side != 0.0 && current != side {
+            crossing_indices.push(i);
+        }
+        side = current;
+    }
+
+    let avg_bars_between_crossings = if crossing_indices.len() >= 2 {
+        (crossing_indices[crossing_indices.len() - 1] - crossing_indices[0]) as f64
+            / (crossing_indices.len() - 1) as f64
+    } else {
+        f64::NAN
+    };
+
+    CrossingStats {
+        mean_crossings: crossing_indices.len(),
+        avg_bars_between_crossings,
+    }
+}
+
+/// Annualised volatility of spread changes: the sample standard deviation of the bar-to-bar
+/// differences scaled by √`bars_per_year` (e.g. 252 for daily bars). Differences rather than
+/// levels are used because the level of a mean-reverting spread is bounded while its changes
+/// carry the risk of a position. A constant spread has zero volatility; NaN is returned for
+/// fewer than three points, a NaN in the spread, or a non-positive `bars_per_year`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn spread_volatility(spread: &[f64], bars_per_year: f64) -> f64 {
+    if spread.len() < 3 || bars_per_year.is_nan() || bars_per_year <= 0.0 {
+        return f64::NAN;
+    }
+    let changes: Vec<f64> = spread.windows(2).map(|w| w[1] - w[0]).collect();
+    nanstd(&changes, 1, NanPolicy::Propagate) * bars_per_year.sqrt()
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(typescript_custom_section)]
+const SPREAD_ANALYSIS_TS: &'static str = r#"
+/** Object returned by `analyze_spread`. */
+export interface SpreadAnalysisObject {
+  adf: AdfResult;
+  adf_used_lag: number;
+  adf_nobs: number;
+  below_min_reliable_sample: boolean;
+  half_life: number;
+  half_life_coefficient: number;
+  half_life_std_error: number;
+  hurst: number;
+  hurst_r_squared: number;
+  current_zscore: number;
+  zscore: Float64Array;
+  mean_crossings: number;
+  avg_bars_between_crossings: number;
+}
+"#;
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "SpreadAnalysisObject")]
+    pub type SpreadAnalysisObject;
+}
+
+/// All single-series diagnostics for a pre-computed spread in one call: ADF (with `max_lag`
+/// lagged differences), half-life, Hurst exponent (variance method over lags 2 to
+/// min(len / 4, 100), as `hurst_exponent` with "variance"), rolling z-score over `window` bars
+/// and mean-crossing statistics. Use this when the spread comes from an external model.
+/// `below_min_reliable_sample` flags spreads shorter than `min_reliable_sample` suggests.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn analyze_spread(
+    spread: &[f64],
+    window: usize,
+    max_lag: usize,
+    regression: &str,
+) -> Result<SpreadAnalysisObject, Error> {
+    let analysis = spread_analysis(spread, window, max_lag, regression)?;
+
+    let result = js_sys::Object::new();
+    set_property(&result, "adf", analysis.adf);
+    set_property(&result, "adf_used_lag", analysis.adf_used_lag as f64);
+    set_property(&result, "adf_nobs", analysis.adf_nobs as f64);
+    set_property(
+        &result,
+        "below_min_reliable_sample",
+        analysis.below_min_reliable_sample,
+    );
+    set_property(&result, "half_life", analysis.half_life);
+    set_property(
+        &result,
+        "half_life_coefficient",
+        analysis.half_life_coefficient,
+    );
+    set_property(&result, "half_life_std_error", analysis.half_life_std_error);
+    set_property(&result, "hurst", analysis.hurst);
+    set_property(&result, "hurst_r_squared", analysis.hurst_r_squared);
+    set_property(&result, "current_zscore", analysis.current_zscore);
+    set_property(
+        &result,
+        "zscore",
+        js_sys::Float64Array::from(analysis.zscore.as_slice()),
+    );
+    set_property(&result, "mean_crossings", analysis.mean_crossings as f64);
+    set_property(
+        &result,
+        "avg_bars_between_crossings",
+        analysis.avg_bars_between_crossings,
+    );
+    Ok(result.unchecked_into())
+}
+
+/// Diagnostics computed by `analyze_spread`, with the same field names.
+pub struct SpreadAnalysis {
+    pub adf: AdfResult,
+    pub adf_used_lag: usize,
+    pub adf_nobs: usize,
+    pub below_min_reliable_sample: bool,
+    pub half_life: f64,
+    pub half_life_coefficient: f64,
+    pub half_life_std_error: f64,
+    pub hurst: f64,
+    pub hurst_r_squared: f64,
+    pub current_zscore: f64,
+    pub zscore: Vec<f64>,
+    pub mean_crossings: usize,
+    pub avg_bars_between_crossings: f64,
+}
+
+impl SpreadAnalysis {
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("adf", RawJson(self.adf.to_json()))
+            .field("adf_used_lag", self.adf_used_lag)
+            .field("adf_nobs", self.adf_nobs)
+            .field("below_min_reliable_sample", self.below_min_reliable_sample)
+            .field("half_life", self.half_life)
+            .field("half_life_coefficient", self.half_life_coefficient)
+            .field("half_life_std_error", self.half_life_std_error)
+            .field("hurst", self.hurst)
+            .field("hurst_r_squared", self.hurst_r_squared)
+            .field("current_zscore", self.current_zscore)
+            .field("zscore", &self.zscore)
+            .field("mean_crossings", self.mean_crossings)
+            .field(
+                "avg_bars_between_crossings",
+                self.avg_bars_between_crossings,
+            )
+            .finish()
+    }
+}
+
+/// `analyze_spread` for native callers, returning a struct instead of a JS object.
+pub fn spread_analysis(
+    spread: &[f64],
+    window: usize,
+    max_lag: usize,
+    regression: &str,
+) -> Result<SpreadAnalysis, Error> {
+    let regression = Regression::parse(regression)?;
+    let adf = adf_regression(spread, max_lag, regression, CovarianceType::Classical).ok_or_else(
+        || Error::new("ADF regression failed: series too short for max_lag or degenerate"),
+    )?;
+
+    let half_life = half_life_fit(spread);
+    let (half_life, half_life_coefficient, half_life_std_error) = half_life
+        .map_or((f64::NAN, f64::NAN, f64::NAN), |h| {
+            (h.half_life, h.coefficient, h.std_error)
+        });
+    // Variance of differences, which applies to levels; R/S would need the increments
+    let (hurst, hurst_r_squared) = hurst_variance(spread, 2, (spread.len() / 4).min(100))
+        .map_or((f64::NAN, f64::NAN), |h| (h.exponent, h.r_squared));
+    let zscore = rolling_zscore(spread, window);
+    let crossings = crossing_stats(spread);
+
+    Ok(SpreadAnalysis {
+        adf: adf_result_from_fit(&adf),
+        adf_used_lag: adf.used_lag,
+        adf_nobs: adf.nobs,
+        below_min_reliable_sample: spread.len()
+            < recommended_sample(max_lag, regression.deterministic_terms()),
+        half_life,
+        half_life_coefficient,
+        half_life_std_error,
+        hurst,
+        hurst_r_squared,
+        current_zscore: zscore.last().copied().unwrap_or(f64::NAN),
+        zscore,
+        mean_crossings: crossings.mean_crossings,
+        avg_bars_between_crossings: crossings.avg_bars_between_crossings,
+    })
+}
+
+// Source of the hedge ratio in `build_spread`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BetaMode {
+    // One OLS fit of y on x with an intercept over the whole sample
+    Static,
+    // OLS over a trailing window, current bar included
+    Rolling,
+    // One total-least-squares fit, symmetric in the legs
+    Tls,
+    // Kalman-filter state from the bars before the current one
+    Kalman,
+}
+
+impl BetaMode {
+    pub fn parse(mode: &str) -> Result<BetaMode, Error> {
+        match mode {
+            "static" => Ok(BetaMode::Static),
+            "rolling" => Ok(BetaMode::Rolling),
+            "tls" => Ok(BetaMode::Tls),
+            "kalman" => Ok(BetaMode::Kalman),
+            other => Err(Error::new(&format!(
+                "unknown beta_mode \"{}\": expected \"static\", \"rolling\", \"tls\" or \"kalman\"",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BetaMode::Static => "static",
+            BetaMode::Rolling => "rolling",
+            BetaMode::Tls => "tls",
+            BetaMode::Kalman => "kalman",
+        }
+    }
+}
+
+/// Options of `build_spread_with_options`; the default matches `build_spread(y, x, "static")`
+/// with an empty options object.
+#[derive(Clone, Copy, Debug)]
+pub struct SpreadOptions {
+    pub beta_mode: BetaMode,
+    // Trailing bars of the rolling regression
+    pub window: usize,
+    // State drift and measurement variance of the Kalman filter, as in `KalmanHedge::new`
+    pub delta: f64,
+    pub measurement_noise: f64,
+}
+
+impl Default for SpreadOptions {
+    fn default() -> SpreadOptions {
+        SpreadOptions {
+            beta_mode: BetaMode::Static,
+            window: 60,
+            delta: 1e-4,
+            measurement_noise: 1e-3,
+        }
+    }
+}
+
+impl SpreadOptions {
+    #[cfg(feature = "wasm")]
+    pub(crate) fn from_js(beta_mode: &str, options: &JsValue) -> Result<SpreadOptions, Error> {
+        let mut parsed = SpreadOptions {
+            beta_mode: BetaMode::parse(beta_mode)?,
+            ..SpreadOptions::default()
+        };
+        if options.is_undefined() || options.is_null() {
+            return Ok(parsed);
+        }
+        if !options.is_object() {
+            return Err(Error::new("options must be an object"));
+        }
+
+        let window = get_property(options, "window")?;
+        if !window.is_undefined() {
+            parsed.window = window
+                .as_f64()
+                .filter(|v| *v >= 2.0 && v.fract() == 0.0 && *v <= u32::MAX as f64)
+                .ok_or_else(|| Error::new("window must be an integer of at least 2"))?
+                as usize;
+        }
+        let number = |key: &str| -> Result<Option<f64>, Error> {
+            let value = get_property(options, key)?;
+            if value.is_undefined() {
+                return Ok(None);
+            }
+            value
+                .as_f64()
+                .map(Some)
+                .ok_or_else(|| Error::new(&format!("{} must be a number", key)))
+        };
+        if let Some(delta) = number("delta")? {
+            parsed.delta = delta;
+        }
+        if let Some(noise) = number("measurement_noise")? {
+            parsed.measurement_noise = noise;
+        }
+        Ok(parsed)
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct SpreadSeries {
+    beta_mode: BetaMode,
+    spread: Vec<f64>,
+    betas: Vec<f64>,
+    intercepts: Vec<f64>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl SpreadSeries {
+    // "static", "rolling", "tls" or "kalman"
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn beta_mode(&self) -> String {
+        self.beta_mode.as_str().to_string()
+    }
+
+    // y - intercept - beta·x at each bar, with that bar's beta and intercept
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn spread(&self) -> Vec<f64> {
+        self.spread.clone()
+    }
+
+    // Hedge ratio used at each bar
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn betas(&self) -> Vec<f64> {
+        self.betas.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn intercepts(&self) -> Vec<f64> {
+        self.intercepts.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("beta_mode", self.beta_mode.as_str())
+            .field("spread", &self.spread)
+            .field("betas", &self.betas)
+            .field("intercepts", &self.intercepts)
+            .finish()
+    }
+}
+
+/// The spread y - intercept - beta·x of two legs, with the hedge ratio path that built it.
+///
+/// `beta_mode` picks the hedge ratio: `"static"` fits OLS of y on x with an intercept once over
+/// the whole sample (`min_variance_hedge_ratio`), `"tls"` fits `tls_hedge_ratio` once,
+/// `"rolling"` refits OLS on the trailing `window` bars (`rolling_beta`, NaN until the window
+/// fills), and `"kalman"` uses the state of a `KalmanHedge` filter before each bar, so the
+/// spread is its one-step forecast error and contains no look-ahead. The static fits see the
+/// whole sample and the rolling one the current bar.
+///
+/// `options` is an optional object with `window` (default 60) for `"rolling"`, and `delta`
+/// (default 1e-4) and `measurement_noise` (default 1e-3) for `"kalman"`. Building every spread
+/// here keeps the statistics computed on it consistent across callers.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn build_spread(
+    y: &[f64],
+    x: &[f64],
+    beta_mode: &str,
+    options: JsValue,
+) -> Result<SpreadSeries, Error> {
+    let options = SpreadOptions::from_js(beta_mode, &options)?;
+    build_spread_with_options(y, x, &options)
+}
+
+/// `build_spread` for native callers, with the mode and options as a struct.
+pub fn build_spread_with_options(
+    y: &[f64],
+    x: &[f64],
+    options: &SpreadOptions,
+) -> Result<SpreadSeries, Error> {
+    require_same_length(y, x)?;
+    require_finite(y, "y")?;
+    require_finite(x, "x")?;
+    require_length(y, 2, "y", "a spread")?;
+    require_varying(x, "x")?;
+
+    let n = y.len();
+    let (betas, intercepts) = match options.beta_mode {
+        BetaMode::Static => {
+            let beta = min_variance_hedge_ratio(y, x);
+            let intercept =
+                nanmean(y, NanPolicy::Propagate) - beta * nanmean(x, NanPolicy::Propagate);
+            (vec![beta; n], vec![intercept; n])
+        }
+        BetaMode::Tls => {
+            let fit = tls_hedge_ratio(y, x);
+            (vec![fit.beta; n], vec![fit.intercept; n])
+        }
+        BetaMode::Rolling => {
+            if options.window < 2 {
+                return Err(Error::new("window must be at least 2"));
+            }
+            rolling_regression(y, x, options.window)?
+        }
+        BetaMode::Kalman => {
+            let mut filter = KalmanHedge::new(options.delta, options.measurement_noise)?;
+            let fit = filter.filter(x, y)?;
+            // The forecast at bar t uses the state after bar t - 1, starting from zero
+            let previous = |path: &[f64]| -> Vec<f64> {
+                std::iter::once(0.0)
+                    .chain(path[..n - 1].iter().copied())
+                    .collect()
+            };
+            return Ok(SpreadSeries {
+                beta_mode: options.beta_mode,
+                betas: previous(&fit.betas()),
+                intercepts: previous(&fit.intercepts()),
+                spread: fit.forecast_errors(),
+            });
+        }
+    };
+    let spread = (0..n)
+        .map(|t| y[t] - intercepts[t] - betas[t] * x[t])
+        .collect();
+    Ok(SpreadSeries {
+        beta_mode: options.beta_mode,
+        spread,
+        betas,
+        intercepts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adf::{adf_test_with_config, AdfOptions};
+    use crate::hurst::hurst_exponent;
+    use crate::mean_reversion::half_life;
+    use crate::rng::fixtures::ar1;
+
+    fn same(a: f64, b: f64) -> bool {
+        a == b || (a.is_nan() && b.is_nan())
+    }
+
+    #[test]
+    fn every_metric_matches_its_standalone_helper() {
+        let spread = ar1(500, 0.9, 3);
+        let analysis = spread_analysis(&spread, 20, 2, "c").unwrap();
+
+        let options = AdfOptions::default()
+            .with_max_lag(Some(2))
+            .with_autolag(None)
+            .unwrap()
+            .with_regression("c")
+            .unwrap();
+        let adf = adf_test_with_config(&spread, &options).unwrap();
+        assert!(same(analysis.adf.statistic, adf.statistic));
+        assert!(same(analysis.adf.p_value, adf.p_value));
+        assert_eq!(analysis.adf.is_stationary, adf.is_stationary);
+        assert_eq!(analysis.adf_used_lag, 2);
+
+        let half_life = half_life(&spread);
+        assert!(same(analysis.half_life, half_life.half_life));
+        assert!(same(analysis.half_life_coefficient, half_life.coefficient));
+        assert!(same(analysis.half_life_std_error, half_life.std_error));
+
+        let hurst = hurst_exponent(&spread, 2, 100, "variance").unwrap();
+        assert!(same(analysis.hurst, hurst.exponent));
+        assert!(same(analysis.hurst_r_squared, hurst.r_squared));
+
+        let zscore = rolling_zscore(&spread, 20);
+        assert_eq!(analysis.zscore.len(), zscore.len());
+        assert!(analysis
+            .zscore
+            .iter()
+            .zip(&zscore)
+            .all(|(a, b)| same(*a, *b)));
+        assert!(same(analysis.current_zscore, zscore[zscore.len() - 1]));
+
+        let crossings = crossing_stats(&spread);
+        assert_eq!(analysis.mean_crossings, crossings.mean_crossings);
+        assert!(same(
+            analysis.avg_bars_between_crossings,
+            crossings.avg_bars_between_crossings
+        ));
+    }
+
+    #[test]
+    fn mean_reverting_spread_has_hurst_below_one_half() {
+        let analysis = spread_analysis(&ar1(2000, 0.9, 4), 20, 1, "c").unwrap();
+        assert!(analysis.hurst < 0.5, "H = {}", analysis.hurst);
+    }
+
+    #[test]
+    fn spread_volatility_scales_with_the_square_root_of_bars_per_year() {
+        let spread = ar1(500, 0.9, 6);
+        let per_bar = spread_volatility(&spread, 1.0);
+        let changes: Vec<f64> = spread.windows(2).map(|w| w[1] - w[0]).collect();
+        assert!((per_bar - nanstd(&changes, 1, NanPolicy::Propagate)).abs() < 1e-12);
+
+        for bars_per_year in [12.0, 52.0, 252.0, 252.0 * 390.0] {
+            let annualised = spread_volatility(&spread, bars_per_year);
+            assert!((annualised / per_bar - f64::sqrt(bars_per_year)).abs() < 1e-9);
+        }
+
+        assert_eq!(spread_volatility(&[3.0; 10], 252.0), 0.0);
+        assert!(spread_volatility(&spread, 0.0).is_nan());
+        assert!(spread_volatility(&spread[..2], 252.0).is_nan());
+    }
+}
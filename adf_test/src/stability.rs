@@ -0,0 +1,244 @@
+// Parameter-stability tests of the cointegrating regression from recursive residuals.
+//
+// A hedge ratio estimated once over the whole sample hides a relationship that
+// drifted or broke. Brown, Durbin & Evans (1975) refit the regression on an
+// expanding sample and standardise each one-step-ahead forecast error; under
+// constant parameters these recursive residuals are independent with mean zero,
+// so their cumulative sum (CUSUM) wanders off when the mean shifts and their
+// cumulative sum of squares (CUSUM of squares) bends when the variance does.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::error::Error;
+use crate::json::JsonObject;
+use crate::stats::{nanstd, NanPolicy};
+
+// Regressors of the cointegrating regression: constant and x
+const K: usize = 2;
+
+// Brown-Durbin-Evans CUSUM boundary coefficients a at the 1%, 5% and 10% levels
+const CUSUM_LEVELS: [(f64, f64); 3] = [(0.01, 1.143), (0.05, 0.948), (0.10, 0.850)];
+// Two-sided Kolmogorov-Smirnov quantiles at the same levels, the asymptotic CUSUM-of-squares
+// boundary in units of 1/√((n-k)/2)
+const CUSUM_SQUARES_LEVELS: [(f64, f64); 3] = [(0.01, 1.6276), (0.05, 1.3581), (0.10, 1.2239)];
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct CusumResult {
+    indices: Vec<u32>,
+    recursive_residuals: Vec<f64>,
+    hedge_ratios: Vec<f64>,
+    cusum: Vec<f64>,
+    cusum_bounds: Vec<f64>,
+    cusum_squares: Vec<f64>,
+    cusum_squares_lower: Vec<f64>,
+    cusum_squares_upper: Vec<f64>,
+    // True when the CUSUM path stays inside ±cusum_bounds
+    pub cusum_stable: bool,
+    // True when the CUSUM-of-squares path stays between its lower and upper bounds
+    pub cusum_squares_stable: bool,
+    pub significance: f64,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl CusumResult {
+    // Observation each point of the paths belongs to
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn indices(&self) -> Vec<u32> {
+        self.indices.clone()
+    }
+
+    // One-step-ahead forecast errors scaled to the variance of the regression errors
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn recursive_residuals(&self) -> Vec<f64> {
+        self.recursive_residuals.clone()
+    }
+
+    // Hedge ratio estimated on the observations up to and including each index
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn hedge_ratios(&self) -> Vec<f64> {
+        self.hedge_ratios.clone()
+    }
+
+    // Cumulative sum of the standardised recursive residuals
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn cusum(&self) -> Vec<f64> {
+        self.cusum.clone()
+    }
+
+    // Upper CUSUM boundary at each index; the lower boundary is its negative
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn cusum_bounds(&self) -> Vec<f64> {
+        self.cusum_bounds.clone()
+    }
+
+    // Share of the total sum of squared recursive residuals reached at each index, from near 0
+    // to 1
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn cusum_squares(&self) -> Vec<f64> {
+        self.cusum_squares.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn cusum_squares_lower(&self) -> Vec<f64> {
+        self.cusum_squares_lower.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn cusum_squares_upper(&self) -> Vec<f64> {
+        self.cusum_squares_upper.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("indices", &self.indices)
+            .field("recursive_residuals", &self.recursive_residuals)
+            .field("hedge_ratios", &self.hedge_ratios)
+            .field("cusum", &self.cusum)
+            .field("cusum_bounds", &self.cusum_bounds)
+            .field("cusum_squares", &self.cusum_squares)
+            .field("cusum_squares_lower", &self.cusum_squares_lower)
+            .field("cusum_squares_upper", &self.cusum_squares_upper)
+            .field("cusum_stable", self.cusum_stable)
+            .field("cusum_squares_stable", self.cusum_squares_stable)
+            .field("significance", self.significance)
+            .finish()
+    }
+}
+
+/// CUSUM and CUSUM-of-squares tests of Brown, Durbin & Evans (1975) for the stability of the
+/// cointegrating regression y = intercept + hedge_ratio·x, at `alpha` 0.01, 0.05 (default) or
+/// 0.10.
+///
+/// The regression is refitted recursively, one observation at a time, starting from the first
+/// observations where x varies; every later observation contributes one recursive residual and
+/// one point of the `hedge_ratios` path. The CUSUM residuals are standardised by their own
+/// standard deviation, as statsmodels' `recursive_olsresiduals` does, and its boundaries are the
+/// straight lines ±a(√N + 2j/√N) for the j-th of N residuals. The CUSUM-of-squares boundaries
+/// are j/N ± c with the asymptotic Kolmogorov value of c, slightly wider than Durbin's
+/// finite-sample table below about 100 residuals. A path leaving its band dates the
+/// instability.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn cusum_test(y: &[f64], x: &[f64], alpha: Option<f64>) -> Result<CusumResult, Error> {
+    let significance = alpha.unwrap_or(0.05);
+    let level_coefficient = |table: &[(f64, f64); 3]| {
+        table
+            .iter()
+            .find(|(level, _)| (level - significance).abs() < 1e-12)
+            .map(|&(_, coefficient)| coefficient)
+            .ok_or_else(|| Error::new("alpha must be 0.01, 0.05 or 0.10"))
+    };
+    let a = level_coefficient(&CUSUM_LEVELS)?;
+    let ks = level_coefficient(&CUSUM_SQUARES_LEVELS)?;
+    if y.len() != x.len() {
+        return Err(Error::new("y and x must have the same length"));
+    }
+    if y.iter().chain(x).any(|v| !v.is_finite()) {
+        return Err(Error::new("y and x must be finite"));
+    }
+
+    // Centring on the first observation changes only the intercept, and keeps the recursion
+    // conditioned on price levels
+    let xc: Vec<f64> = x.iter().map(|v| v - x[0]).collect();
+    let yc: Vec<f64> = y.iter().map(|v| v - y[0]).collect();
+    let start = xc
+        .iter()
+        .position(|&v| v != 0.0)
+        .map(|i| (i + 1).max(K))
+        .ok_or_else(|| Error::new("x is constant"))?;
+    let n = y.len();
+    if n < start + 3 {
+        return Err(Error::new(
+            "series too short: at least three observations are needed after the first two \
+             distinct values of x",
+        ));
+    }
+
+    // Initial fit β = (Z'Z)^-1 Z'y, exact when start = K, then recursive least squares with
+    // p = (Z'Z)^-1
+    let (s0, s1) = (start as f64, xc[..start].iter().sum::<f64>());
+    let s2: f64 = xc[..start].iter().map(|v| v * v).sum();
+    let det = s0 * s2 - s1 * s1;
+    if det.is_nan() || det.abs() <= 1e-12 * s2.max(f64::MIN_POSITIVE) {
+        return Err(Error::new("initial regression failed: x is degenerate"));
+    }
+    let mut p = [[s2 / det, -s1 / det], [-s1 / det, s0 / det]];
+    let (t0, t1) = (
+        yc[..start].iter().sum::<f64>(),
+        xc[..start]
+            .iter()
+            .zip(&yc[..start])
+            .map(|(x, y)| x * y)
+            .sum::<f64>(),
+    );
+    let mut beta = [p[0][0] * t0 + p[0][1] * t1, p[1][0] * t0 + p[1][1] * t1];
+
+    let size = n - start;
+    let mut indices = Vec::with_capacity(size);
+    let mut recursive_residuals = Vec::with_capacity(size);
+    let mut hedge_ratios = Vec::with_capacity(size);
+    for t in start..n {
+        let z = [1.0, xc[t]];
+        let pz = [
+            p[0][0] * z[0] + p[0][1] * z[1],
+            p[1][0] * z[0] + p[1][1] * z[1],
+        ];
+        let f = 1.0 + z[0] * pz[0] + z[1] * pz[1];
+        let error = yc[t] - (beta[0] * z[0] + beta[1] * z[1]);
+        recursive_residuals.push(error / f.sqrt());
+        for i in 0..K {
+            beta[i] += pz[i] / f * error;
+            for j in 0..K {
+                p[i][j] -= pz[i] * pz[j] / f;
+            }
+        }
+        indices.push(t as u32);
+        hedge_ratios.push(beta[1]);
+    }
+
+    let nf = size as f64;
+    let sigma = nanstd(&recursive_residuals, 1, NanPolicy::Propagate);
+    let sum_squares: f64 = recursive_residuals.iter().map(|w| w * w).sum();
+    if sigma == 0.0 || sum_squares == 0.0 {
+        return Err(Error::new(
+            "recursive residuals are all zero: y is an exact line in x",
+        ));
+    }
+
+    let c = ks / (nf / 2.0).sqrt();
+    let mut cusum = Vec::with_capacity(size);
+    let mut cusum_bounds = Vec::with_capacity(size);
+    let mut cusum_squares = Vec::with_capacity(size);
+    let mut cusum_squares_lower = Vec::with_capacity(size);
+    let mut cusum_squares_upper = Vec::with_capacity(size);
+    let (mut sum, mut sum_sq) = (0.0, 0.0);
+    for (j, w) in recursive_residuals.iter().enumerate() {
+        let j = (j + 1) as f64;
+        sum += w / sigma;
+        sum_sq += w * w;
+        cusum.push(sum);
+        cusum_bounds.push(a * (nf.sqrt() + 2.0 * j / nf.sqrt()));
+        cusum_squares.push(sum_sq / sum_squares);
+        cusum_squares_lower.push(j / nf - c);
+        cusum_squares_upper.push(j / nf + c);
+    }
+    let cusum_stable = cusum.iter().zip(&cusum_bounds).all(|(s, b)| s.abs() <= *b);
+    let cusum_squares_stable = (0..size).all(|i| {
+        cusum_squares[i] >= cusum_squares_lower[i] && cusum_squares[i] <= cusum_squares_upper[i]
+    });
+
+    Ok(CusumResult {
+        indices,
+        recursive_residuals,
+        hedge_ratios,
+        cusum,
+        cusum_bounds,
+        cusum_squares,
+        cusum_squares_lower,
+        cusum_squares_upper,
+        cusum_stable,
+        cusum_squares_stable,
+        significance,
+    })
+}
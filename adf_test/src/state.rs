@@ -0,0 +1,161 @@
+// Byte encoding of the stateful objects, for storing them (in IndexedDB, say) and
+// restoring them later without refitting on the full history.
+//
+// A buffer starts with the magic bytes "ADFS", a format version and a tag naming
+// the type it holds, followed by the object's fields in a fixed order: numbers as
+// little-endian f64 or u64, flags as one byte, and vectors as a u64 length and
+// their values. Every f64 keeps its exact bits, so a restored object continues
+// exactly where the original stopped. Reading checks the header and the length of
+// everything it takes, so a truncated, foreign or mismatched buffer is an error
+// rather than a garbled object.
+
+use crate::error::Error;
+
+const MAGIC: [u8; 4] = *b"ADFS";
+const VERSION: u8 = 1;
+
+// Type tag of each serializable object
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum StateKind {
+    KalmanHedge = 1,
+    StreamingAdf = 2,
+    ArmaModel = 3,
+    GarchModel = 4,
+}
+
+impl StateKind {
+    fn name(self) -> &'static str {
+        match self {
+            StateKind::KalmanHedge => "KalmanHedge",
+            StateKind::StreamingAdf => "StreamingAdf",
+            StateKind::ArmaModel => "ArmaModel",
+            StateKind::GarchModel => "GarchModel",
+        }
+    }
+}
+
+pub(crate) struct StateWriter {
+    bytes: Vec<u8>,
+}
+
+impl StateWriter {
+    pub(crate) fn new(kind: StateKind) -> StateWriter {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend([VERSION, kind as u8]);
+        StateWriter { bytes }
+    }
+
+    pub(crate) fn f64(&mut self, value: f64) -> &mut StateWriter {
+        self.bytes.extend(value.to_le_bytes());
+        self
+    }
+
+    pub(crate) fn usize(&mut self, value: usize) -> &mut StateWriter {
+        self.bytes.extend((value as u64).to_le_bytes());
+        self
+    }
+
+    pub(crate) fn bool(&mut self, value: bool) -> &mut StateWriter {
+        self.bytes.push(u8::from(value));
+        self
+    }
+
+    pub(crate) fn f64s(&mut self, values: &[f64]) -> &mut StateWriter {
+        self.usize(values.len());
+        for value in values {
+            self.f64(*value);
+        }
+        self
+    }
+
+    pub(crate) fn finish(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.bytes)
+    }
+}
+
+pub(crate) struct StateReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    kind: StateKind,
+}
+
+impl<'a> StateReader<'a> {
+    // A reader past the header, which must name `kind`
+    pub(crate) fn new(bytes: &'a [u8], kind: StateKind) -> Result<StateReader<'a>, Error> {
+        if bytes.len() < MAGIC.len() + 2 || bytes[..MAGIC.len()] != MAGIC {
+            return Err(Error::new("not a serialized state buffer"));
+        }
+        let version = bytes[MAGIC.len()];
+        if version != VERSION {
+            return Err(Error::new(&format!(
+                "unsupported state format version {}: expected {}",
+                version, VERSION
+            )));
+        }
+        if bytes[MAGIC.len() + 1] != kind as u8 {
+            return Err(Error::new(&format!(
+                "the buffer does not hold a serialized {}",
+                kind.name()
+            )));
+        }
+        Ok(StateReader {
+            bytes,
+            position: MAGIC.len() + 2,
+            kind,
+        })
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        let end = self.position + N;
+        let Some(chunk) = self.bytes.get(self.position..end) else {
+            return Err(self.invalid());
+        };
+        self.position = end;
+        let mut array = [0; N];
+        array.copy_from_slice(chunk);
+        Ok(array)
+    }
+
+    pub(crate) fn f64(&mut self) -> Result<f64, Error> {
+        self.take().map(f64::from_le_bytes)
+    }
+
+    pub(crate) fn usize(&mut self) -> Result<usize, Error> {
+        let value = u64::from_le_bytes(self.take()?);
+        usize::try_from(value).map_err(|_| self.invalid())
+    }
+
+    pub(crate) fn bool(&mut self) -> Result<bool, Error> {
+        match self.take::<1>()?[0] {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(self.invalid()),
+        }
+    }
+
+    pub(crate) fn f64s(&mut self) -> Result<Vec<f64>, Error> {
+        let len = self.usize()?;
+        // A corrupt length must not allocate more than the buffer could hold
+        if len > (self.bytes.len() - self.position) / 8 {
+            return Err(self.invalid());
+        }
+        (0..len).map(|_| self.f64()).collect()
+    }
+
+    // Fail if any bytes are left over
+    pub(crate) fn finish(self) -> Result<(), Error> {
+        if self.position == self.bytes.len() {
+            Ok(())
+        } else {
+            Err(self.invalid())
+        }
+    }
+
+    // The error for a buffer whose contents break the object's invariants
+    pub(crate) fn invalid(&self) -> Error {
+        Error::new(&format!(
+            "the serialized {} is truncated or corrupt",
+            self.kind.name()
+        ))
+    }
+}
@@ -0,0 +1,445 @@
+// Decisions built on several unit-root tests: whether a series is stationary and
+// how often it must be differenced before it is.
+//
+// ADF and PP take a unit root as the null hypothesis and KPSS takes stationarity,
+// so each alone can only fail to reject; reading them together separates a clear
+// verdict from a lack of power. Pairs trading needs both legs integrated of order
+// one, so instruments are classified this way before they are paired.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::adf::{adf_test_with_config, AdfOptions, DecisionRule, LagSelection, Regression};
+use crate::error::Error;
+#[cfg(feature = "wasm")]
+use crate::get_property;
+#[cfg(feature = "wasm")]
+use crate::json::{parse_payload, payload_series};
+use crate::json::{raw_array, JsonObject, RawJson};
+use crate::kpss::{kpss_test, KpssResult};
+use crate::pp::pp_test;
+use crate::resample::resample;
+use crate::AdfResult;
+
+const DEFAULT_MAX_D: usize = 2;
+
+// Test behind a stationarity decision
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DifferencingTest {
+    Adf,
+    Kpss,
+    Pp,
+}
+
+impl DifferencingTest {
+    fn parse(test: &str) -> Result<DifferencingTest, Error> {
+        match test {
+            "adf" => Ok(DifferencingTest::Adf),
+            "kpss" => Ok(DifferencingTest::Kpss),
+            "pp" => Ok(DifferencingTest::Pp),
+            other => Err(Error::new(&format!(
+                "unknown test \"{}\": expected \"adf\", \"kpss\" or \"pp\"",
+                other
+            ))),
+        }
+    }
+
+    // Whether `series` looks stationary around a constant at level `alpha`. ADF and PP have a
+    // unit root as the null, which must be rejected; KPSS has stationarity as the null, which
+    // must not be.
+    fn is_stationary(&self, series: &[f64], alpha: f64) -> Result<bool, Error> {
+        Ok(match self {
+            DifferencingTest::Adf => {
+                adf_test_with_config(series, &AdfOptions::default())?.p_value <= alpha
+            }
+            DifferencingTest::Kpss => kpss_test(series, "c")?.p_value >= alpha,
+            DifferencingTest::Pp => pp_test(series, "c")?.p_value <= alpha,
+        })
+    }
+}
+
+/// Number of differences needed to make `series` stationary, as pmdarima's `ndiffs`.
+///
+/// The series is differenced until `test` ("kpss" by default, "adf" or "pp", each with a constant
+/// and the defaults of `kpss_test`, `adf_test_with_options` and `pp_test`) judges it stationary
+/// at level `alpha` (default 0.05), up to `max_d` (default 2) times. A constant series, or one
+/// that becomes constant, needs no further differences. KPSS p-values are clamped to
+/// [0.01, 0.10], so with "kpss" `alpha` should lie inside that range. Errors from the test, e.g.
+/// a series too short once differenced, are returned as they are.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn ndiffs(
+    series: &[f64],
+    test: Option<String>,
+    alpha: Option<f64>,
+    max_d: Option<usize>,
+) -> Result<usize, Error> {
+    let test = DifferencingTest::parse(test.as_deref().unwrap_or("kpss"))?;
+    let alpha = alpha.unwrap_or(0.05);
+    if !(alpha > 0.0 && alpha < 1.0) {
+        return Err(Error::new("alpha must be in (0, 1)"));
+    }
+    let max_d = max_d.unwrap_or(DEFAULT_MAX_D);
+    if series.iter().any(|v| !v.is_finite()) {
+        return Err(Error::new("series must be finite"));
+    }
+
+    let mut current = series.to_vec();
+    let mut d = 0;
+    while d < max_d && !is_constant(&current) && !test.is_stationary(&current, alpha)? {
+        current = current.windows(2).map(|w| w[1] - w[0]).collect();
+        d += 1;
+    }
+    Ok(d)
+}
+
+fn is_constant(series: &[f64]) -> bool {
+    series.iter().all(|&v| v == series[0])
+}
+
+// Combined reading of ADF and KPSS
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum StationarityVerdict {
+    // Stationary around a constant: ADF rejects a unit root and KPSS does not reject stationarity
+    Stationary,
+    // Not stationary around a constant but stationary around a linear trend
+    TrendStationary,
+    // ADF does not reject a unit root and KPSS rejects stationarity, even allowing for a trend
+    UnitRoot,
+    // The tests disagree
+    Inconclusive,
+}
+
+impl StationarityVerdict {
+    fn as_str(&self) -> &'static str {
+        match self {
+            StationarityVerdict::Stationary => "stationary",
+            StationarityVerdict::TrendStationary => "trend_stationary",
+            StationarityVerdict::UnitRoot => "unit_root",
+            StationarityVerdict::Inconclusive => "inconclusive",
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone)]
+pub struct StationarityReport {
+    verdict: StationarityVerdict,
+    adf: AdfResult,
+    kpss: KpssResult,
+    // Level both tests were judged at
+    pub significance: f64,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl StationarityReport {
+    // "stationary", "trend_stationary", "unit_root" or "inconclusive"
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn verdict(&self) -> String {
+        self.verdict.as_str().to_string()
+    }
+
+    // ADF result behind the verdict; its `regression` is "c" or "ct"
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn adf(&self) -> AdfResult {
+        self.adf.clone()
+    }
+
+    // KPSS result with the same deterministic terms as `adf`
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn kpss(&self) -> KpssResult {
+        self.kpss.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("verdict", self.verdict())
+            .field("adf", RawJson(self.adf.to_json()))
+            .field("kpss", RawJson(self.kpss.to_json()))
+            .field("significance", self.significance)
+            .finish()
+    }
+}
+
+/// Options of `stationarity_report_with_config`; the default matches an empty options object.
+#[derive(Clone, Copy, Debug)]
+pub struct StationarityOptions {
+    pub significance: f64,
+    pub max_lag: Option<usize>,
+    pub autolag: LagSelection,
+    pub trend: bool,
+}
+
+impl Default for StationarityOptions {
+    fn default() -> StationarityOptions {
+        StationarityOptions {
+            significance: 0.05,
+            max_lag: None,
+            autolag: LagSelection::Aic,
+            trend: true,
+        }
+    }
+}
+
+impl StationarityOptions {
+    #[cfg(feature = "wasm")]
+    pub(crate) fn from_js(options: &JsValue) -> Result<StationarityOptions, Error> {
+        let mut parsed = StationarityOptions::default();
+        if options.is_undefined() || options.is_null() {
+            return Ok(parsed);
+        }
+        if !options.is_object() {
+            return Err(Error::new("options must be an object"));
+        }
+
+        let significance = get_property(options, "significance")?;
+        if !significance.is_undefined() {
+            parsed.significance = significance
+                .as_f64()
+                .ok_or_else(|| Error::new("significance must be a number"))?;
+        }
+
+        let max_lag = get_property(options, "max_lag")?;
+        if !max_lag.is_undefined() && !max_lag.is_null() {
+            let value = max_lag
+                .as_f64()
+                .filter(|v| *v >= 0.0 && v.fract() == 0.0)
+                .ok_or_else(|| Error::new("max_lag must be a non-negative integer"))?;
+            parsed.max_lag = Some(value as usize);
+        }
+
+        let autolag = get_property(options, "autolag")?;
+        if autolag.is_null() {
+            parsed.autolag = LagSelection::Fixed;
+        } else if !autolag.is_undefined() {
+            let name = autolag
+                .as_string()
+                .ok_or_else(|| Error::new("autolag must be a string or null"))?;
+            parsed.autolag = LagSelection::parse(&name)?;
+        }
+
+        let trend = get_property(options, "trend")?;
+        if !trend.is_undefined() {
+            parsed.trend = trend
+                .as_bool()
+                .ok_or_else(|| Error::new("trend must be a boolean"))?;
+        }
+        Ok(parsed)
+    }
+}
+
+/// ADF and KPSS run together on `series`, with a combined verdict. Options, all optional:
+///
+/// - `significance`: level both tests are judged at (default 0.05; KPSS p-values are clamped to
+///   [0.01, 0.10], so keep it inside that range)
+/// - `max_lag`, `autolag`: ADF lag choice as in `adf_test_with_options`
+/// - `trend`: whether to try a linear trend when the series is not stationary around a constant
+///   (default true)
+///
+/// Both tests first include a constant. If ADF rejects a unit root and KPSS does not reject
+/// stationarity the verdict is "stationary". Otherwise, with `trend`, both are rerun with a
+/// constant and trend, giving "trend_stationary" when they now agree on stationarity. The
+/// verdict is "unit_root" when ADF does not reject and KPSS rejects in the last specification
+/// tried, and "inconclusive" when the tests disagree. `adf` and `kpss` hold the results of that
+/// last specification.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn stationarity_report(series: &[f64], options: JsValue) -> Result<StationarityReport, Error> {
+    stationarity_report_with_config(series, &StationarityOptions::from_js(&options)?)
+}
+
+/// `stationarity_report` driven by JSON text: `payload` is `{"series": [...], "options": {...}}`
+/// and the report is returned as `StationarityReport.to_json()`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn stationarity_report_from_json(payload: &str) -> Result<String, Error> {
+    let payload = parse_payload(payload)?;
+    let series = payload_series(&payload, "series")?;
+    let options = StationarityOptions::from_js(&get_property(&payload, "options")?)?;
+    Ok(stationarity_report_with_config(&series, &options)?.to_json())
+}
+
+/// `stationarity_report` for native callers, with the options as a struct.
+pub fn stationarity_report_with_config(
+    series: &[f64],
+    options: &StationarityOptions,
+) -> Result<StationarityReport, Error> {
+    let significance = options.significance;
+    if !(significance > 0.0 && significance < 1.0) {
+        return Err(Error::new("significance must be in (0, 1)"));
+    }
+
+    let run = |regression: Regression| -> Result<(bool, bool, AdfResult, KpssResult), Error> {
+        let adf = adf_test_with_config(
+            series,
+            &AdfOptions {
+                max_lag: options.max_lag,
+                autolag: options.autolag,
+                regression,
+                significance,
+                decision_rule: DecisionRule::PValue,
+                ..AdfOptions::default()
+            },
+        )?;
+        let kpss = kpss_test(series, regression.as_str())?;
+        Ok((adf.is_stationary, kpss.p_value >= significance, adf, kpss))
+    };
+
+    let (mut adf_stationary, mut kpss_stationary, mut adf, mut kpss) = run(Regression::Constant)?;
+    let mut verdict = StationarityVerdict::Stationary;
+    if !(adf_stationary && kpss_stationary) && options.trend {
+        (adf_stationary, kpss_stationary, adf, kpss) = run(Regression::ConstantTrend)?;
+        verdict = StationarityVerdict::TrendStationary;
+    }
+    if !(adf_stationary && kpss_stationary) {
+        verdict = if !adf_stationary && !kpss_stationary {
+            StationarityVerdict::UnitRoot
+        } else {
+            StationarityVerdict::Inconclusive
+        };
+    }
+    Ok(StationarityReport {
+        verdict,
+        adf,
+        kpss,
+        significance,
+    })
+}
+
+/// `stationarity_report` of one resampled frequency in a `multi_timeframe_adf` comparison.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone)]
+pub struct TimeframeReport {
+    rule: String,
+    report: Option<StationarityReport>,
+    error: Option<String>,
+    // Bars of the resampled series
+    pub nobs: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl TimeframeReport {
+    // Resampling rule of this row, as passed in
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn rule(&self) -> String {
+        self.rule.clone()
+    }
+
+    // Undefined when the tests could not run at this frequency, e.g. too few bars
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn report(&self) -> Option<StationarityReport> {
+        self.report.clone()
+    }
+
+    // Why `report` is missing
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn error(&self) -> Option<String> {
+        self.error.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("rule", &self.rule)
+            .field("nobs", self.nobs)
+            .field("report", self.report.as_ref().map(|r| RawJson(r.to_json())))
+            .field("error", self.error.as_deref())
+            .finish()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct MultiTimeframeReport {
+    timeframes: Vec<TimeframeReport>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl MultiTimeframeReport {
+    // One row per rule, in the order given
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn timeframes(&self) -> Vec<TimeframeReport> {
+        self.timeframes.clone()
+    }
+
+    // Verdict of each row, "" where the tests could not run
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn verdicts(&self) -> Vec<String> {
+        self.column(|r| r.verdict(), String::new())
+    }
+
+    // ADF p-value of each row; NaN where the tests could not run
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn adf_p_values(&self) -> Vec<f64> {
+        self.column(|r| r.adf.p_value, f64::NAN)
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn kpss_p_values(&self) -> Vec<f64> {
+        self.column(|r| r.kpss.p_value, f64::NAN)
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field(
+                "timeframes",
+                raw_array(self.timeframes.iter().map(TimeframeReport::to_json)),
+            )
+            .finish()
+    }
+}
+
+impl MultiTimeframeReport {
+    fn column<T: Clone>(&self, value: impl Fn(&StationarityReport) -> T, missing: T) -> Vec<T> {
+        self.timeframes
+            .iter()
+            .map(|t| t.report.as_ref().map_or(missing.clone(), &value))
+            .collect()
+    }
+}
+
+/// `stationarity_report` of `values` (observed at epoch-millisecond `timestamps`, strictly
+/// increasing) resampled to each of `rules`, e.g. `["hourly", "daily", "weekly"]`, in one call.
+///
+/// Each rule is a `resample` rule and takes the last value of every period, the closing price.
+/// `options` are those of `stationarity_report` and apply to every frequency. A frequency with
+/// too few bars for the tests gets a row with `error` set instead of failing the whole call; an
+/// unknown rule or malformed input still fails it.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn multi_timeframe_adf(
+    timestamps: &[f64],
+    values: &[f64],
+    rules: Vec<String>,
+    options: JsValue,
+) -> Result<MultiTimeframeReport, Error> {
+    let options = StationarityOptions::from_js(&options)?;
+    multi_timeframe_adf_with_config(timestamps, values, &rules, &options)
+}
+
+/// `multi_timeframe_adf` for native callers, with the options as a struct.
+pub fn multi_timeframe_adf_with_config(
+    timestamps: &[f64],
+    values: &[f64],
+    rules: &[String],
+    options: &StationarityOptions,
+) -> Result<MultiTimeframeReport, Error> {
+    if rules.is_empty() {
+        return Err(Error::new("at least one rule is needed"));
+    }
+    let mut timeframes = Vec::with_capacity(rules.len());
+    for rule in rules {
+        let resampled = resample(timestamps, values, rule, None)?;
+        let (report, error) = match stationarity_report_with_config(&resampled.values(), options) {
+            Ok(report) => (Some(report), None),
+            Err(error) => (None, Some(error.message().to_string())),
+        };
+        timeframes.push(TimeframeReport {
+            rule: rule.clone(),
+            report,
+            error,
+            nobs: resampled.values().len(),
+        });
+    }
+    Ok(MultiTimeframeReport { timeframes })
+}
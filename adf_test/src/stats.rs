@@ -0,0 +1,180 @@
+// NaN-aware reductions shared by the rest of the crate.
+//
+// Every mean / standard deviation in the crate goes through these helpers so a
+// stray NaN is handled the same way everywhere instead of poisoning some
+// computations and tripping `unwrap`s in others. Their sums are compensated (see
+// `simd`), so they stay accurate to the last bits on long series. Cross-moments
+// of two series (Σ x·y / n) and the column means of packed row-major matrices are
+// not reductions of one series and are computed where they are used.
+
+use crate::simd::{dot, sum_squared_deviations, CompensatedSum};
+
+/// How reductions treat NaN entries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NanPolicy {
+    /// Ignore NaN entries and reduce over the remaining values.
+    Skip,
+    /// Return NaN as soon as any entry is NaN.
+    Propagate,
+}
+
+/// Arithmetic mean. Returns NaN for an empty input (or when every value was skipped).
+pub fn nanmean(values: &[f64], policy: NanPolicy) -> f64 {
+    let mut sum = CompensatedSum::default();
+    let mut count = 0usize;
+    for &v in values {
+        if v.is_nan() {
+            match policy {
+                NanPolicy::Skip => continue,
+                NanPolicy::Propagate => return f64::NAN,
+            }
+        }
+        sum.add(v);
+        count += 1;
+    }
+
+    if count == 0 {
+        return f64::NAN;
+    }
+    sum.value() / count as f64
+}
+
+/// Standard deviation with `ddof` delta degrees of freedom (0 = population, 1 = sample).
+/// Returns NaN when fewer than `ddof + 1` values are available.
+pub fn nanstd(values: &[f64], ddof: usize, policy: NanPolicy) -> f64 {
+    let mean = nanmean(values, policy);
+    if mean.is_nan() {
+        return f64::NAN;
+    }
+
+    // Propagate already bailed out in nanmean, so without NaNs the vector kernel applies
+    let (sum_sq, count) = if policy == NanPolicy::Propagate {
+        (sum_squared_deviations(values, mean), values.len())
+    } else {
+        let mut sum_sq = CompensatedSum::default();
+        let mut count = 0usize;
+        for &v in values {
+            if v.is_nan() {
+                continue;
+            }
+            sum_sq.add((v - mean) * (v - mean));
+            count += 1;
+        }
+        (sum_sq.value(), count)
+    };
+
+    if count <= ddof {
+        return f64::NAN;
+    }
+    (sum_sq / (count - ddof) as f64).sqrt()
+}
+
+/// Pearson correlation of two equally long series. Returns NaN if the lengths differ, fewer
+/// than two pairs are given, any value is NaN, or either series is constant.
+pub fn pearson(x: &[f64], y: &[f64]) -> f64 {
+    if x.len() != y.len() || x.len() < 2 {
+        return f64::NAN;
+    }
+    let mean_x = nanmean(x, NanPolicy::Propagate);
+    let mean_y = nanmean(y, NanPolicy::Propagate);
+    if mean_x.is_nan() || mean_y.is_nan() {
+        return f64::NAN;
+    }
+    let dx: Vec<f64> = x.iter().map(|v| v - mean_x).collect();
+    let dy: Vec<f64> = y.iter().map(|v| v - mean_y).collect();
+    let (sxy, sxx, syy) = (dot(&dx, &dy), dot(&dx, &dx), dot(&dy, &dy));
+    if sxx == 0.0 || syy == 0.0 {
+        return f64::NAN;
+    }
+    (sxy / (sxx * syy).sqrt()).clamp(-1.0, 1.0)
+}
+
+/// Sample skewness m₃ / m₂^{3/2} from the biased central moments (as `scipy.stats.skew`).
+/// Returns NaN for fewer than two values, any NaN, or a constant series.
+pub fn skewness(values: &[f64]) -> f64 {
+    let (m2, m3, _) = central_moments(values);
+    m3 / m2.powf(1.5)
+}
+
+/// Sample excess kurtosis m₄ / m₂² - 3 from the biased central moments (as
+/// `scipy.stats.kurtosis`), 0 for a normal distribution. Returns NaN for fewer than two values,
+/// any NaN, or a constant series.
+pub fn excess_kurtosis(values: &[f64]) -> f64 {
+    let (m2, _, m4) = central_moments(values);
+    m4 / (m2 * m2) - 3.0
+}
+
+// Second, third and fourth central moments with divisor n; all NaN when they are undefined
+fn central_moments(values: &[f64]) -> (f64, f64, f64) {
+    let mean = nanmean(values, NanPolicy::Propagate);
+    if values.len() < 2 || mean.is_nan() {
+        return (f64::NAN, f64::NAN, f64::NAN);
+    }
+    let (mut m2, mut m3, mut m4) = (
+        CompensatedSum::default(),
+        CompensatedSum::default(),
+        CompensatedSum::default(),
+    );
+    for &v in values {
+        let d = v - mean;
+        let d2 = d * d;
+        m2.add(d2);
+        m3.add(d2 * d);
+        m4.add(d2 * d2);
+    }
+    let (m2, m3, m4) = (m2.value(), m3.value(), m4.value());
+    if m2 == 0.0 {
+        return (f64::NAN, f64::NAN, f64::NAN);
+    }
+    let n = values.len() as f64;
+    (m2 / n, m3 / n, m4 / n)
+}
+
+/// Median, averaging the two middle values for an even count. Returns NaN for an empty input or
+/// when any value is NaN.
+pub fn median(values: &[f64]) -> f64 {
+    if values.is_empty() || values.iter().any(|v| v.is_nan()) {
+        return f64::NAN;
+    }
+    let mut sorted = values.to_vec();
+    let mid = sorted.len() / 2;
+    let (lower, &mut upper, _) = sorted.select_nth_unstable_by(mid, f64::total_cmp);
+    if values.len().is_multiple_of(2) {
+        let below = lower.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        0.5 * (below + upper)
+    } else {
+        upper
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skip_ignores_nans() {
+        let values = [1.0, f64::NAN, 2.0, 3.0, f64::NAN, 6.0];
+        assert_eq!(nanmean(&values, NanPolicy::Skip), 3.0);
+        let clean = [1.0, 2.0, 3.0, 6.0];
+        assert_eq!(
+            nanstd(&values, 1, NanPolicy::Skip),
+            nanstd(&clean, 1, NanPolicy::Propagate)
+        );
+        assert!((nanstd(&clean, 0, NanPolicy::Skip) - 3.5f64.sqrt()).abs() < 1e-15);
+    }
+
+    #[test]
+    fn propagate_returns_nan() {
+        let values = [1.0, f64::NAN, 2.0];
+        assert!(nanmean(&values, NanPolicy::Propagate).is_nan());
+        assert!(nanstd(&values, 0, NanPolicy::Propagate).is_nan());
+    }
+
+    #[test]
+    fn empty_or_all_skipped_is_nan() {
+        assert!(nanmean(&[], NanPolicy::Skip).is_nan());
+        assert!(nanmean(&[f64::NAN, f64::NAN], NanPolicy::Skip).is_nan());
+        // Fewer values than ddof + 1
+        assert!(nanstd(&[1.0, f64::NAN], 1, NanPolicy::Skip).is_nan());
+    }
+}
@@ -0,0 +1,237 @@
+// ADF test over a stream of prices.
+//
+// Each new price adds one row to the ADF regression, so the regression's normal
+// equations are kept as running sums and the test is re-solved from them on
+// demand (O(k³) for k regressors, independent of the history length) instead of
+// refitting the whole series after every tick.
+
+use std::collections::VecDeque;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::adf::{adf_result_from_fit, AdfFit, Regression};
+use crate::error::Error;
+use crate::linalg::NormalEquations;
+use crate::state::{StateKind, StateReader, StateWriter};
+use crate::AdfResult;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct StreamingAdf {
+    lags: usize,
+    regression: Regression,
+    window: Option<usize>,
+    equations: NormalEquations,
+    // The last lags + 1 prices, oldest first, enough to build the next row
+    recent: VecDeque<f64>,
+    // Rows currently in the regression, kept only with a window so they can be removed again
+    rows: VecDeque<(Vec<f64>, f64)>,
+    // First price seen; levels are centred on it to keep the normal equations conditioned
+    shift: Option<f64>,
+    // Rows ever added, the trend index of the next one
+    rows_seen: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl StreamingAdf {
+    /// A streaming ADF test with a fixed number of `lags` lagged differences and deterministic
+    /// terms `regression` ("n", "c" or "ct").
+    ///
+    /// With `window` the regression covers only the latest `window` observations (rows that
+    /// slide out are subtracted from the running sums); without it every price pushed so far is
+    /// used. A row needs `lags + 1` earlier prices, so the first `lags + 1` pushes only fill the
+    /// buffer.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(
+        lags: usize,
+        regression: &str,
+        window: Option<usize>,
+    ) -> Result<StreamingAdf, Error> {
+        let regression = Regression::parse(regression)?;
+        if regression == Regression::ConstantQuadraticTrend {
+            return Err(Error::new(
+                "StreamingAdf supports the \"n\", \"c\" and \"ct\" regressions",
+            ));
+        }
+        let k = lags + 1 + regression.deterministic_terms();
+        if let Some(window) = window {
+            if window <= k + 1 {
+                return Err(Error::new(&format!(
+                    "window must exceed {} (the number of regressors plus one)",
+                    k + 1
+                )));
+            }
+        }
+        Ok(StreamingAdf {
+            lags,
+            regression,
+            window,
+            equations: NormalEquations::new(k),
+            recent: VecDeque::with_capacity(lags + 2),
+            rows: VecDeque::new(),
+            shift: None,
+            rows_seen: 0,
+        })
+    }
+
+    /// Add the next price.
+    pub fn push(&mut self, value: f64) -> Result<(), Error> {
+        if !value.is_finite() {
+            return Err(Error::new("prices must be finite"));
+        }
+        let shift = *self.shift.get_or_insert(match self.regression {
+            // Without a constant the level cannot be shifted without changing the model
+            Regression::NoConstant => 0.0,
+            _ => value,
+        });
+        self.recent.push_back(value - shift);
+        if self.recent.len() <= self.lags + 1 {
+            return Ok(());
+        }
+        if self.recent.len() > self.lags + 2 {
+            self.recent.pop_front();
+        }
+
+        // recent = [y_{t-p-1}, ..., y_{t-1}, y_t]
+        let p = self.lags;
+        let level = |back: usize| self.recent[p + 1 - back];
+        let mut row = Vec::with_capacity(self.equations_k());
+        row.push(level(1));
+        for i in 1..=p {
+            row.push(level(i) - level(i + 1));
+        }
+        self.rows_seen += 1;
+        let terms = self.regression.deterministic_terms();
+        if terms >= 1 {
+            row.push(1.0);
+        }
+        if terms >= 2 {
+            row.push(self.rows_seen as f64);
+        }
+        let response = level(0) - level(1);
+
+        self.equations.push(&row, response);
+        if let Some(window) = self.window {
+            self.rows.push_back((row, response));
+            if self.rows.len() > window {
+                if let Some((old_row, old_response)) = self.rows.pop_front() {
+                    self.equations.pop(&old_row, old_response);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Add several prices in order, as repeated `push` calls.
+    pub fn push_many(&mut self, values: &[f64]) -> Result<(), Error> {
+        values.iter().try_for_each(|&value| self.push(value))
+    }
+
+    /// The ADF test on the current regression sample, with the same p-value and critical values
+    /// as `adf_test_with_options` using a fixed lag.
+    pub fn current(&self) -> Result<AdfResult, Error> {
+        let statistic = self.equations.t_stat(0).ok_or_else(|| {
+            Error::new("not enough observations yet, or the regression is degenerate")
+        })?;
+        Ok(adf_result_from_fit(&AdfFit {
+            statistic,
+            used_lag: self.lags,
+            nobs: self.equations.nobs(),
+            regression: self.regression,
+        }))
+    }
+
+    // Observations currently in the regression
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn nobs(&self) -> usize {
+        self.equations.nobs()
+    }
+
+    /// The test's running sums and buffered prices as a compact byte buffer, which
+    /// `StreamingAdf.deserialize` turns back into a test that continues exactly where this one
+    /// stopped.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut writer = StateWriter::new(StateKind::StreamingAdf);
+        writer
+            .usize(self.lags)
+            .usize(self.regression.deterministic_terms())
+            .bool(self.window.is_some())
+            .usize(self.window.unwrap_or(0));
+        self.equations.write_state(&mut writer);
+        writer
+            .f64s(&self.recent.iter().copied().collect::<Vec<f64>>())
+            .usize(self.rows.len());
+        for (row, response) in &self.rows {
+            writer.f64s(row).f64(*response);
+        }
+        writer
+            .bool(self.shift.is_some())
+            .f64(self.shift.unwrap_or(0.0))
+            .usize(self.rows_seen)
+            .finish()
+    }
+
+    /// A streaming test restored from the bytes of `serialize`.
+    pub fn deserialize(bytes: &[u8]) -> Result<StreamingAdf, Error> {
+        let mut reader = StateReader::new(bytes, StateKind::StreamingAdf)?;
+        let lags = reader.usize()?;
+        let regression = match reader.usize()? {
+            0 => Regression::NoConstant,
+            1 => Regression::Constant,
+            2 => Regression::ConstantTrend,
+            _ => return Err(reader.invalid()),
+        };
+        let k = lags
+            .checked_add(1 + regression.deterministic_terms())
+            .ok_or_else(|| reader.invalid())?;
+        let has_window = reader.bool()?;
+        let window = reader.usize()?;
+        let window = has_window.then_some(window);
+        let equations = NormalEquations::read_state(&mut reader, k)?;
+        let recent = reader.f64s()?;
+        let row_count = reader.usize()?;
+        if window.is_some_and(|window| window <= k + 1 || row_count > window)
+            || (window.is_none() && row_count > 0)
+            || recent.len() > lags + 2
+        {
+            return Err(reader.invalid());
+        }
+        let mut rows = VecDeque::new();
+        for _ in 0..row_count {
+            let row = reader.f64s()?;
+            if row.len() != k {
+                return Err(reader.invalid());
+            }
+            rows.push_back((row, reader.f64()?));
+        }
+        let has_shift = reader.bool()?;
+        let shift = reader.f64()?;
+        let rows_seen = reader.usize()?;
+        reader.finish()?;
+        Ok(StreamingAdf {
+            lags,
+            regression,
+            window,
+            equations,
+            recent: recent.into(),
+            rows,
+            shift: has_shift.then_some(shift),
+            rows_seen,
+        })
+    }
+
+    /// Forget every price pushed so far.
+    pub fn reset(&mut self) {
+        self.equations = NormalEquations::new(self.equations_k());
+        self.recent.clear();
+        self.rows.clear();
+        self.shift = None;
+        self.rows_seen = 0;
+    }
+}
+
+impl StreamingAdf {
+    fn equations_k(&self) -> usize {
+        self.lags + 1 + self.regression.deterministic_terms()
+    }
+}
@@ -0,0 +1,145 @@
+// Wall-clock timings of the stages of a pipeline.
+//
+// With the `timings` option set, a pipeline records how long each of its stages
+// took and returns the durations with its result, so a slow call in production
+// can be broken down without attaching a profiler. The clock is the page's
+// `performance.now()` (reached through js-sys) in the browser and in workers,
+// `Date.now()` where that is missing, and `std::time::Instant` off wasm. A
+// wasm32 build without the `wasm` feature has no clock it can reach, so its
+// durations are NaN. When the option is off no clock is read at all.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::json::JsonObject;
+
+/// Milliseconds spent in each stage of a timed call, in the order the stages ran.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug)]
+pub struct StageTimings {
+    stages: Vec<String>,
+    milliseconds: Vec<f64>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl StageTimings {
+    // Stage names, e.g. "alignment", "regression", "residual_test", "p_value"
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn stages(&self) -> Vec<String> {
+        self.stages.clone()
+    }
+
+    // Wall-clock duration of each stage
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn milliseconds(&self) -> Vec<f64> {
+        self.milliseconds.clone()
+    }
+
+    // Sum over the stages
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn total_ms(&self) -> f64 {
+        self.milliseconds.iter().sum()
+    }
+
+    /// Milliseconds of the stage called `stage`, or undefined if it did not run.
+    pub fn stage_ms(&self, stage: &str) -> Option<f64> {
+        self.stages
+            .iter()
+            .position(|s| s == stage)
+            .map(|i| self.milliseconds[i])
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("stages", &self.stages)
+            .field("milliseconds", &self.milliseconds)
+            .field("total_ms", self.total_ms())
+            .finish()
+    }
+}
+
+// Records the time between successive calls to `lap`; a disabled stopwatch records nothing
+pub(crate) struct Stopwatch {
+    timings: Option<StageTimings>,
+    mark: f64,
+}
+
+impl Stopwatch {
+    pub(crate) fn new(enabled: bool) -> Stopwatch {
+        Stopwatch {
+            timings: enabled.then(|| StageTimings {
+                stages: Vec::new(),
+                milliseconds: Vec::new(),
+            }),
+            mark: if enabled { now_ms() } else { 0.0 },
+        }
+    }
+
+    // Close `stage`, which ran since the previous lap or since the stopwatch was started
+    pub(crate) fn lap(&mut self, stage: &str) {
+        if let Some(timings) = &mut self.timings {
+            let now = now_ms();
+            timings.stages.push(stage.to_string());
+            timings.milliseconds.push(now - self.mark);
+            self.mark = now;
+        }
+    }
+
+    pub(crate) fn finish(self) -> Option<StageTimings> {
+        self.timings
+    }
+}
+
+// A monotonic clock in milliseconds
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+fn now_ms() -> f64 {
+    use crate::get_property;
+    let performance = get_property(&js_sys::global(), "performance")
+        .ok()
+        .filter(|p| p.is_object());
+    performance
+        .and_then(|performance| {
+            let now = get_property(&performance, "now").ok()?;
+            now.dyn_into::<js_sys::Function>()
+                .ok()?
+                .call0(&performance)
+                .ok()?
+                .as_f64()
+        })
+        .unwrap_or_else(js_sys::Date::now)
+}
+
+// `Instant::now` panics on wasm32-unknown-unknown, and without js-sys there is no other clock
+#[cfg(all(not(feature = "wasm"), target_arch = "wasm32"))]
+fn now_ms() -> f64 {
+    f64::NAN
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now_ms() -> f64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_secs_f64() * 1e3
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stopwatch_records_each_lap_in_order() {
+        let mut stopwatch = Stopwatch::new(true);
+        stopwatch.lap("alignment");
+        stopwatch.lap("regression");
+        let timings = stopwatch.finish().unwrap();
+        assert_eq!(timings.stages(), ["alignment", "regression"]);
+        assert!(timings.milliseconds().iter().all(|&ms| ms >= 0.0));
+        assert_eq!(timings.stage_ms("p_value"), None);
+
+        let mut disabled = Stopwatch::new(false);
+        disabled.lap("alignment");
+        assert!(disabled.finish().is_none());
+    }
+}
@@ -0,0 +1,208 @@
+// Input checks run before the tests fit anything.
+//
+// A series that is too short for its lags, holds a NaN, or does not vary at all
+// makes the regressions singular, so the tests would fail with a generic
+// "regression failed" or return NaN statistics. Checking up front reports the
+// reason instead, with an `ErrorCode` and the index of the offending
+// observation where there is one.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::adf::Regression;
+use crate::error::{Error, ErrorCode};
+
+/// Check `series` before testing it: every value must be finite, the values must not all be
+/// equal, and there must be at least `min_length` of them (default 3). The first problem found
+/// is returned as an error with its `code` ("non_finite", "constant" or "too_short") and, for a
+/// non-finite value, the `index` of the first one.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn validate_series(series: &[f64], min_length: Option<usize>) -> Result<(), Error> {
+    require_finite(series, "series")?;
+    require_length(series, min_length.unwrap_or(3), "series", "the test")?;
+    require_varying(series, "series")
+}
+
+// Every value of `values` finite, or a NonFinite error at the first that is not
+pub(crate) fn require_finite(values: &[f64], name: &str) -> Result<(), Error> {
+    match values.iter().position(|v| !v.is_finite()) {
+        Some(i) => Err(Error::with_code(
+            ErrorCode::NonFinite,
+            &format!(
+                "{} must contain only finite values (found {} at index {})",
+                name, values[i], i
+            ),
+        )
+        .at(i)),
+        None => Ok(()),
+    }
+}
+
+// At least `needed` observations for `purpose`
+pub(crate) fn require_length(
+    values: &[f64],
+    needed: usize,
+    name: &str,
+    purpose: &str,
+) -> Result<(), Error> {
+    if values.len() < needed {
+        return Err(Error::with_code(
+            ErrorCode::TooShort,
+            &format!(
+                "{} has {} observations but {} needs at least {}",
+                name,
+                values.len(),
+                purpose,
+                needed
+            ),
+        ));
+    }
+    Ok(())
+}
+
+// Not every value equal to the first
+pub(crate) fn require_varying(values: &[f64], name: &str) -> Result<(), Error> {
+    if values.len() > 1 && values.iter().all(|&v| v == values[0]) {
+        return Err(Error::with_code(
+            ErrorCode::Constant,
+            &format!("{} is constant, so the test statistic is undefined", name),
+        ));
+    }
+    Ok(())
+}
+
+// Series paired observation by observation
+pub(crate) fn require_same_length(y: &[f64], x: &[f64]) -> Result<(), Error> {
+    if y.len() != x.len() {
+        return Err(Error::with_code(
+            ErrorCode::LengthMismatch,
+            &format!(
+                "y and x must have the same length ({} vs {})",
+                y.len(),
+                x.len()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+// Observations the ADF regression with `lags` lagged differences needs for one residual degree
+// of freedom: n - 1 - lags rows against 1 + lags + terms regressors
+pub(crate) fn adf_min_length(lags: usize, regression: Regression) -> usize {
+    2 * lags + regression.deterministic_terms() + 3
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    type Check = fn(&[f64]) -> (ErrorCode, Option<usize>);
+
+    fn failure<T>(result: Result<T, Error>) -> (ErrorCode, Option<usize>) {
+        match result {
+            Ok(_) => panic!("expected an error"),
+            Err(error) => (error.code(), error.index()),
+        }
+    }
+
+    #[test]
+    fn every_test_reports_bad_input_with_the_same_code_and_index() {
+        let mut rng = Rng::new(7);
+        let mut walk = vec![0.0; 200];
+        for t in 1..walk.len() {
+            walk[t] = walk[t - 1] + rng.normal();
+        }
+        let mut with_nan = walk.clone();
+        with_nan[17] = f64::NAN;
+        let constant = vec![1.5; 200];
+        let short = &walk[..2];
+
+        let checks: Vec<(&str, Check)> = vec![
+            ("pp_test", |s| failure(crate::pp::pp_test(s, "c"))),
+            ("adf_gls", |s| {
+                failure(crate::gls::adf_gls(s, "c", None, None))
+            }),
+            ("zivot_andrews", |s| {
+                failure(crate::zivot_andrews::zivot_andrews(s, "c", 1, None))
+            }),
+            ("fit_arma", |s| {
+                failure(crate::arma::fit_arma(s, 1, 0, None))
+            }),
+            ("fit_garch", |s| failure(crate::garch::fit_garch(s, None))),
+            ("jarque_bera", |s| {
+                failure(crate::diagnostics::jarque_bera(s))
+            }),
+            ("acf", |s| failure(crate::diagnostics::acf(s, 5, None))),
+            ("johansen_test", |s| {
+                let matrix: Vec<f64> = s.iter().flat_map(|&v| [v, v]).collect();
+                let (code, index) =
+                    failure(crate::johansen::johansen_test(&matrix, 2, s.len(), 0, 1));
+                (code, index.map(|i| i / 2))
+            }),
+        ];
+        for (name, check) in &checks {
+            assert_eq!(
+                check(&with_nan),
+                (ErrorCode::NonFinite, Some(17)),
+                "{}",
+                name
+            );
+            assert_eq!(check(short), (ErrorCode::TooShort, None), "{}", name);
+            assert_eq!(check(&constant), (ErrorCode::Constant, None), "{}", name);
+        }
+
+        let nan_only: Vec<(&str, Check)> = vec![
+            ("variance_ratio_test", |s| {
+                failure(crate::variance_ratio::variance_ratio_test(s, &[2]))
+            }),
+            ("periodogram", |s| {
+                failure(crate::spectrum::periodogram(s, None))
+            }),
+            ("detrend_poly", |s| {
+                failure(crate::detrend::detrend_poly(s, 2))
+            }),
+            ("hp_filter", |s| failure(crate::detrend::hp_filter(s, None))),
+            ("arch_lm", |s| failure(crate::diagnostics::arch_lm(s, 2))),
+        ];
+        for (name, check) in &nan_only {
+            assert_eq!(
+                check(&with_nan),
+                (ErrorCode::NonFinite, Some(17)),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn table_and_statistic_errors_keep_their_code_and_row() {
+        use crate::pvalue::PValueTable;
+
+        let table = |statistics: &[f64], p_values: &[f64]| {
+            failure(PValueTable::from_arrays(statistics, p_values))
+        };
+        assert_eq!(
+            table(&[-3.0, -2.0], &[0.01]),
+            (ErrorCode::LengthMismatch, None)
+        );
+        assert_eq!(table(&[-3.0], &[0.01]), (ErrorCode::TooShort, None));
+        assert_eq!(
+            table(&[-3.0, f64::NAN, -1.0], &[0.01, 0.05, 0.5]),
+            (ErrorCode::NonFinite, Some(1))
+        );
+        assert_eq!(
+            table(&[-3.0, -1.0, -2.0], &[0.01, 0.05, 0.5]),
+            (ErrorCode::InvalidInput, Some(2))
+        );
+
+        assert_eq!(
+            failure(crate::get_adf_p_value_and_stationarity(f64::NAN)),
+            (ErrorCode::NonFinite, None)
+        );
+        assert_eq!(
+            failure(crate::get_adf_p_value_for_sample(-3.0, 0)),
+            (ErrorCode::TooShort, None)
+        );
+    }
+}
@@ -0,0 +1,139 @@
+// Lo-MacKinlay (1988) variance ratio test.
+//
+// Under a random walk the variance of q-period increments is q times the
+// variance of one-period increments, so VR(q) = Var(q-period) / (q·Var(1-period))
+// is 1. Mean reversion at horizon q pulls the ratio below 1 and momentum pushes it
+// above. The overlapping, bias-corrected estimator is used, with the
+// heteroskedasticity-robust asymptotic variance so that volatility clustering
+// does not masquerade as a rejection.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::distributions::erfc;
+use crate::error::Error;
+use crate::json::JsonObject;
+use crate::validate::{require_finite, require_varying};
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct VarianceRatioResult {
+    horizons: Vec<u32>,
+    variance_ratios: Vec<f64>,
+    z_scores: Vec<f64>,
+    p_values: Vec<f64>,
+    // Number of one-period increments
+    pub nobs: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl VarianceRatioResult {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn horizons(&self) -> Vec<u32> {
+        self.horizons.clone()
+    }
+
+    // VR(q) per horizon; below 1 indicates mean reversion at that horizon
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn variance_ratios(&self) -> Vec<f64> {
+        self.variance_ratios.clone()
+    }
+
+    // Heteroskedasticity-robust z*(q), asymptotically standard normal under the random walk
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn z_scores(&self) -> Vec<f64> {
+        self.z_scores.clone()
+    }
+
+    // Two-sided p-values of z_scores
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn p_values(&self) -> Vec<f64> {
+        self.p_values.clone()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("horizons", &self.horizons)
+            .field("variance_ratios", &self.variance_ratios)
+            .field("z_scores", &self.z_scores)
+            .field("p_values", &self.p_values)
+            .field("nobs", self.nobs)
+            .finish()
+    }
+}
+
+/// Lo-MacKinlay variance ratio test of the random-walk hypothesis for each of `horizons`
+/// (each at least 2 and below the number of increments).
+///
+/// `series` is the level whose increments are tested, typically log-prices or a spread. Uses
+/// overlapping q-period increments with the bias-corrected variances and the
+/// heteroskedasticity-robust z*(q) of Lo & MacKinlay (1988), as `arch`'s `VarianceRatio` with
+/// `robust=True, debiased=True`. The p-values are two-sided.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn variance_ratio_test(series: &[f64], horizons: &[u32]) -> Result<VarianceRatioResult, Error> {
+    require_finite(series, "series")?;
+    if horizons.is_empty() {
+        return Err(Error::new("at least one horizon is needed"));
+    }
+    let n = series.len().saturating_sub(1);
+    if let Some(&q) = horizons.iter().find(|&&q| q < 2 || q as usize >= n) {
+        return Err(Error::new(&format!(
+            "horizon {} must be at least 2 and below the number of increments ({})",
+            q, n
+        )));
+    }
+
+    let increments: Vec<f64> = series.windows(2).map(|w| w[1] - w[0]).collect();
+    require_varying(&increments, "the series increments")?;
+    let nf = n as f64;
+    let mean = (series[n] - series[0]) / nf;
+    let demeaned: Vec<f64> = increments.iter().map(|r| r - mean).collect();
+    let squares: Vec<f64> = demeaned.iter().map(|d| d * d).collect();
+    let sum_squares: f64 = squares.iter().sum();
+    let variance_1 = sum_squares / (nf - 1.0);
+
+    let mut result = VarianceRatioResult {
+        horizons: horizons.to_vec(),
+        variance_ratios: Vec::with_capacity(horizons.len()),
+        z_scores: Vec::with_capacity(horizons.len()),
+        p_values: Vec::with_capacity(horizons.len()),
+        nobs: n,
+    };
+    for &q in horizons {
+        let q = q as usize;
+        let qf = q as f64;
+        let m = qf * (nf - qf + 1.0) * (1.0 - qf / nf);
+        let variance_q: f64 = (q..=n)
+            .map(|t| {
+                let d = series[t] - series[t - q] - qf * mean;
+                d * d
+            })
+            .sum::<f64>()
+            / m;
+        let vr = variance_q / variance_1;
+
+        // θ(q) = Σ_j [2(q-j)/q]² δ_j with δ_j = n·Σ_t e²_t e²_{t-j} / (Σ_t e²_t)²
+        let theta: f64 = (1..q)
+            .map(|j| {
+                let delta = nf
+                    * squares[j..]
+                        .iter()
+                        .zip(&squares[..n - j])
+                        .map(|(a, b)| a * b)
+                        .sum::<f64>()
+                    / (sum_squares * sum_squares);
+                let weight = 2.0 * (qf - j as f64) / qf;
+                weight * weight * delta
+            })
+            .sum();
+        // √n·(VR - 1) / √θ is asymptotically standard normal
+        let z = (vr - 1.0) * (nf / theta).sqrt();
+
+        result.variance_ratios.push(vr);
+        result.z_scores.push(z);
+        result
+            .p_values
+            .push(erfc(z.abs() / std::f64::consts::SQRT_2));
+    }
+    Ok(result)
+}
@@ -0,0 +1,526 @@
+// Walk-forward (out-of-sample) evaluation of a pairs strategy.
+//
+// History is cut into consecutive test windows, each preceded by a training
+// window. The hedge ratio and, optionally, the entry threshold are estimated on
+// the training window only and then traded unchanged over the test window, so
+// every bar of the stitched equity curve is traded with parameters that were
+// known before it. Rolling training windows track a drifting relationship;
+// anchored (expanding) ones use all the history available at each point.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "arrow")]
+use crate::arrow::{write_arrow, OutputColumn};
+use crate::backtest::{
+    backtest_with_options, performance_metrics, BacktestOptions, PerformanceMetrics,
+};
+use crate::error::Error;
+#[cfg(feature = "wasm")]
+use crate::get_property;
+use crate::hedge::min_variance_hedge_ratio;
+use crate::json::JsonObject;
+#[cfg(feature = "wasm")]
+use crate::json::{parse_payload, payload_series};
+#[cfg(feature = "wasm")]
+use crate::progress::{run_in_slices, ProgressOptions};
+use crate::progress::{run_to_completion, Job};
+use crate::signals::{generate_signals, threshold_for_frequency};
+use crate::zscore::rolling_zscore;
+
+// Values per segment in `WalkForwardResult::segments`
+const SEGMENT_STRIDE: usize = 9;
+// Values per trade in `WalkForwardResult::trades`, as in `BacktestResult::trades`
+const TRADE_STRIDE: usize = 6;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct WalkForwardResult {
+    // First bar of the first test window, where the stitched curves start
+    pub start: usize,
+    pub final_equity: f64,
+    pub total_return: f64,
+    pub total_costs: f64,
+    // Closed trades with positive P&L over all closed trades (NaN when none closed)
+    pub win_rate: f64,
+    // Share of segments whose test window made money
+    pub profitable_segments: f64,
+    segments: Vec<f64>,
+    equity: Vec<f64>,
+    returns: Vec<f64>,
+    trades: Vec<f64>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl WalkForwardResult {
+    // Packed (train_start, test_start, test_end, hedge_ratio, entry, total_return, sharpe,
+    // max_drawdown, trade_count) rows, one per segment; test_end is exclusive and the metrics
+    // are those of the test window alone
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn segments(&self) -> Vec<f64> {
+        self.segments.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn segment_count(&self) -> usize {
+        self.segments.len() / SEGMENT_STRIDE
+    }
+
+    // Out-of-sample equity at each bar's close from `start` on, compounded across segments
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn equity(&self) -> Vec<f64> {
+        self.equity.clone()
+    }
+
+    // Simple per-bar returns of the stitched equity curve
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn returns(&self) -> Vec<f64> {
+        self.returns.clone()
+    }
+
+    // Every segment's trades in the layout of `BacktestResult::trades`, with indices into the
+    // full price series
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn trades(&self) -> Vec<f64> {
+        self.trades.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn trade_count(&self) -> usize {
+        self.trades.len() / TRADE_STRIDE
+    }
+
+    /// `performance_metrics` of the stitched out-of-sample returns.
+    pub fn performance(&self, periods_per_year: f64) -> Result<PerformanceMetrics, Error> {
+        performance_metrics(&self.returns, periods_per_year)
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("start", self.start)
+            .field("final_equity", self.final_equity)
+            .field("total_return", self.total_return)
+            .field("total_costs", self.total_costs)
+            .field("win_rate", self.win_rate)
+            .field("profitable_segments", self.profitable_segments)
+            .field("segment_count", self.segment_count())
+            .field("segments", &self.segments)
+            .field("equity", &self.equity)
+            .field("returns", &self.returns)
+            .field("trades", &self.trades)
+            .finish()
+    }
+
+    /// The stitched curves as an Arrow IPC stream with the columns `equity` and `returns`,
+    /// preceded by a millisecond `timestamp` column when `timestamps` is given. `timestamps`
+    /// holds one value per bar of the input prices; those from `start` on are used.
+    #[cfg(feature = "arrow")]
+    pub fn to_arrow(&self, timestamps: Option<Vec<f64>>) -> Result<Vec<u8>, Error> {
+        let mut columns = Vec::new();
+        if let Some(timestamps) = &timestamps {
+            if timestamps.len() != self.start + self.equity.len() {
+                return Err(Error::new(
+                    "timestamps must hold one value per bar of the input prices",
+                ));
+            }
+            columns.push((
+                "timestamp",
+                OutputColumn::TimestampMs(&timestamps[self.start..]),
+            ));
+        }
+        columns.extend([
+            ("equity", OutputColumn::Float64(&self.equity)),
+            ("returns", OutputColumn::Float64(&self.returns)),
+        ]);
+        Ok(write_arrow(&columns))
+    }
+}
+
+/// Options of `walk_forward_with_options`; the default matches an empty options object.
+#[derive(Clone, Copy, Debug)]
+pub struct WalkForwardOptions {
+    pub train_window: usize,
+    pub test_window: usize,
+    // Training windows grow from the first bar instead of rolling
+    pub anchored: bool,
+    pub zscore_window: usize,
+    pub entry: f64,
+    pub exit: f64,
+    pub stop: Option<f64>,
+    pub max_holding: Option<usize>,
+    pub kill: Option<f64>,
+    // When set, the entry threshold of each segment is recalibrated on its training window to
+    // give about this many round trips there, replacing `entry`
+    pub target_trades: Option<usize>,
+    // Bars per year of the per-segment Sharpe ratios
+    pub periods_per_year: f64,
+    // Costs and starting capital of the test-window backtests
+    pub backtest: BacktestOptions,
+}
+
+impl Default for WalkForwardOptions {
+    fn default() -> WalkForwardOptions {
+        WalkForwardOptions {
+            train_window: 252,
+            test_window: 63,
+            anchored: false,
+            zscore_window: 20,
+            entry: 2.0,
+            exit: 0.0,
+            stop: None,
+            max_holding: None,
+            kill: None,
+            target_trades: None,
+            periods_per_year: 252.0,
+            backtest: BacktestOptions::default(),
+        }
+    }
+}
+
+impl WalkForwardOptions {
+    #[cfg(feature = "wasm")]
+    pub(crate) fn from_js(options: &JsValue) -> Result<WalkForwardOptions, Error> {
+        let mut parsed = WalkForwardOptions::default();
+        if options.is_undefined() || options.is_null() {
+            return Ok(parsed);
+        }
+        if !options.is_object() {
+            return Err(Error::new("options must be an object"));
+        }
+        parsed.backtest = BacktestOptions::from_js(options)?;
+
+        let count = |key: &str| -> Result<Option<usize>, Error> {
+            let value = get_property(options, key)?;
+            if value.is_undefined() {
+                return Ok(None);
+            }
+            value
+                .as_f64()
+                .filter(|v| *v >= 0.0 && v.fract() == 0.0 && *v <= u32::MAX as f64)
+                .map(|v| Some(v as usize))
+                .ok_or_else(|| Error::new(&format!("{} must be a non-negative integer", key)))
+        };
+        let number = |key: &str| -> Result<Option<f64>, Error> {
+            let value = get_property(options, key)?;
+            if value.is_undefined() {
+                return Ok(None);
+            }
+            value
+                .as_f64()
+                .filter(|v| v.is_finite())
+                .map(Some)
+                .ok_or_else(|| Error::new(&format!("{} must be a finite number", key)))
+        };
+
+        if let Some(train_window) = count("train_window")? {
+            parsed.train_window = train_window;
+        }
+        if let Some(test_window) = count("test_window")? {
+            parsed.test_window = test_window;
+        }
+        if let Some(zscore_window) = count("zscore_window")? {
+            parsed.zscore_window = zscore_window;
+        }
+        parsed.target_trades = count("target_trades")?;
+        parsed.max_holding = count("max_holding")?;
+        if let Some(entry) = number("entry")? {
+            parsed.entry = entry;
+        }
+        if let Some(exit) = number("exit")? {
+            parsed.exit = exit;
+        }
+        parsed.stop = number("stop")?;
+        parsed.kill = number("kill")?;
+        if let Some(periods_per_year) = number("periods_per_year")? {
+            parsed.periods_per_year = periods_per_year;
+        }
+
+        let anchored = get_property(options, "anchored")?;
+        if !anchored.is_undefined() {
+            parsed.anchored = anchored
+                .as_bool()
+                .ok_or_else(|| Error::new("anchored must be a boolean"))?;
+        }
+        Ok(parsed)
+    }
+}
+
+/// Walk-forward evaluation of the z-score pairs strategy on price series `prices_a` and
+/// `prices_b`.
+///
+/// The bars after the first `train_window` are cut into consecutive test windows of
+/// `test_window` bars (the last one may be shorter). For each, the minimum-variance hedge ratio
+/// is estimated on the preceding `train_window` bars, or on all earlier bars when `anchored`, and
+/// the test window is traded with `generate_signals(z, entry, exit, stop, max_holding, kill)`
+/// on the rolling z-score over `zscore_window` bars of the spread a - β·b and simulated with
+/// `backtest`. With
+/// `target_trades`, the entry threshold is recalibrated on every training window with
+/// `threshold_for_frequency`. The z-score of the first test bars looks back into the training
+/// window, which is information available at the time. Each segment is closed out on its last
+/// bar, so segments are independent and the stitched equity compounds their returns.
+///
+/// `options` is an optional object with the fields of `WalkForwardOptions` (`train_window`
+/// 252, `test_window` 63, `anchored` false, `zscore_window` 20, `entry` 2, `exit` 0, `stop`,
+/// `max_holding`, `kill`, `target_trades`, `periods_per_year` 252) and the options of `backtest`.
+/// The costs apply to every test window, and the slippage volatility is estimated within it.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn walk_forward(
+    prices_a: &[f64],
+    prices_b: &[f64],
+    options: JsValue,
+) -> Result<WalkForwardResult, Error> {
+    let options = WalkForwardOptions::from_js(&options)?;
+    walk_forward_with_options(prices_a, prices_b, &options)
+}
+
+/// `walk_forward` driven by JSON text: `payload` is
+/// `{"prices_a": [...], "prices_b": [...], "options": {...}}` and the result is returned as
+/// `WalkForwardResult.to_json()`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn walk_forward_from_json(payload: &str) -> Result<String, Error> {
+    let payload = parse_payload(payload)?;
+    let prices_a = payload_series(&payload, "prices_a")?;
+    let prices_b = payload_series(&payload, "prices_b")?;
+    let options = WalkForwardOptions::from_js(&get_property(&payload, "options")?)?;
+    Ok(walk_forward_with_options(&prices_a, &prices_b, &options)?.to_json())
+}
+
+/// `walk_forward` for native callers, with the options as a struct.
+pub fn walk_forward_with_options(
+    prices_a: &[f64],
+    prices_b: &[f64],
+    options: &WalkForwardOptions,
+) -> Result<WalkForwardResult, Error> {
+    let mut walk = WalkForward::new(prices_a, prices_b, options)?;
+    run_to_completion(&mut walk)?;
+    Ok(walk.finish())
+}
+
+/// `walk_forward` as a Promise that evaluates one segment at a time in slices of about
+/// `slice_ms` milliseconds (default 16), yielding to the event loop between slices so a page
+/// stays responsive; the result is the same as the synchronous one.
+///
+/// `options` takes the options of `walk_forward` plus `on_progress`, called with the percent of
+/// the test bars done after every slice, `signal`, an `AbortSignal` that rejects the Promise
+/// once aborted, and `slice_ms`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub async fn walk_forward_async(
+    prices_a: Vec<f64>,
+    prices_b: Vec<f64>,
+    options: JsValue,
+) -> Result<WalkForwardResult, Error> {
+    let progress = ProgressOptions::from_js(&options)?;
+    let options = WalkForwardOptions::from_js(&options)?;
+    let mut walk = WalkForward::new(&prices_a, &prices_b, &options)?;
+    run_in_slices(&mut walk, &progress).await?;
+    Ok(walk.finish())
+}
+
+// State of a walk-forward run between segments
+struct WalkForward<'a> {
+    prices_a: &'a [f64],
+    prices_b: &'a [f64],
+    options: &'a WalkForwardOptions,
+    capital: f64,
+    segments: Vec<f64>,
+    equity: Vec<f64>,
+    returns: Vec<f64>,
+    trades: Vec<f64>,
+    total_costs: f64,
+    wins: usize,
+    closed: usize,
+    profitable: usize,
+    // First bar of the next test window; n once every segment is done
+    test_start: usize,
+}
+
+impl<'a> WalkForward<'a> {
+    fn new(
+        prices_a: &'a [f64],
+        prices_b: &'a [f64],
+        options: &'a WalkForwardOptions,
+    ) -> Result<WalkForward<'a>, Error> {
+        let n = prices_a.len();
+        if prices_b.len() != n {
+            return Err(Error::new(
+                "prices_a and prices_b must have the same length",
+            ));
+        }
+        if options.zscore_window < 2 {
+            return Err(Error::new("zscore_window must be at least 2"));
+        }
+        if options.train_window < options.zscore_window {
+            return Err(Error::new("train_window must be at least zscore_window"));
+        }
+        if options.test_window == 0 {
+            return Err(Error::new("test_window must be positive"));
+        }
+        if n <= options.train_window {
+            return Err(Error::new(
+                "series too short: at least one bar is needed after the first training window",
+            ));
+        }
+        if prices_a
+            .iter()
+            .chain(prices_b)
+            .any(|p| !(*p > 0.0 && p.is_finite()))
+        {
+            return Err(Error::new("prices must be positive and finite"));
+        }
+        let bars = n - options.train_window;
+        Ok(WalkForward {
+            prices_a,
+            prices_b,
+            options,
+            capital: options.backtest.capital,
+            segments: Vec::new(),
+            equity: Vec::with_capacity(bars),
+            returns: Vec::with_capacity(bars),
+            trades: Vec::new(),
+            total_costs: 0.0,
+            wins: 0,
+            closed: 0,
+            profitable: 0,
+            test_start: options.train_window,
+        })
+    }
+
+    fn finish(self) -> WalkForwardResult {
+        let segment_count = self.segments.len() / SEGMENT_STRIDE;
+        WalkForwardResult {
+            start: self.options.train_window,
+            final_equity: self.capital,
+            total_return: self.capital / self.options.backtest.capital - 1.0,
+            total_costs: self.total_costs,
+            win_rate: if self.closed > 0 {
+                self.wins as f64 / self.closed as f64
+            } else {
+                f64::NAN
+            },
+            profitable_segments: self.profitable as f64 / segment_count as f64,
+            segments: self.segments,
+            equity: self.equity,
+            returns: self.returns,
+            trades: self.trades,
+        }
+    }
+}
+
+impl Job for WalkForward<'_> {
+    // Estimate, trade and record the next segment
+    fn step(&mut self) -> Result<bool, Error> {
+        let (prices_a, prices_b, options) = (self.prices_a, self.prices_b, self.options);
+        let n = prices_a.len();
+        let test_start = self.test_start;
+        if test_start >= n {
+            return Ok(true);
+        }
+        let test_end = (test_start + options.test_window).min(n);
+        let train_start = if options.anchored {
+            0
+        } else {
+            test_start - options.train_window
+        };
+        let segment = self.segments.len() / SEGMENT_STRIDE;
+
+        let beta = min_variance_hedge_ratio(
+            &prices_a[train_start..test_start],
+            &prices_b[train_start..test_start],
+        );
+        if !beta.is_finite() {
+            return Err(Error::new(&format!(
+                "segment {}: prices_b is constant over the training window",
+                segment
+            )));
+        }
+        let spread_at = |t: usize| prices_a[t] - beta * prices_b[t];
+
+        let entry = match options.target_trades {
+            Some(target) => {
+                let train: Vec<f64> = (train_start..test_start).map(spread_at).collect();
+                threshold_for_frequency(&train, options.zscore_window, target)
+            }
+            None => options.entry,
+        };
+        if entry.is_nan() || entry <= options.exit {
+            return Err(Error::new(&format!(
+                "segment {}: the entry threshold ({}) must exceed exit",
+                segment, entry
+            )));
+        }
+
+        // z-scores of the test bars, with their look-back windows reaching into the training data
+        let lookback = test_start + 1 - options.zscore_window;
+        let spread: Vec<f64> = (lookback..test_end).map(spread_at).collect();
+        let zscore = &rolling_zscore(&spread, options.zscore_window)[test_start - lookback..];
+        let mut signals = generate_signals(
+            zscore,
+            entry,
+            options.exit,
+            options.stop,
+            options.max_holding,
+            options.kill,
+        )?
+        .positions();
+        if let Some(last) = signals.last_mut() {
+            *last = 0.0;
+        }
+
+        let result = backtest_with_options(
+            &prices_a[test_start..test_end],
+            &prices_b[test_start..test_end],
+            &[beta],
+            &signals,
+            &BacktestOptions {
+                capital: self.capital,
+                ..options.backtest
+            },
+        )?;
+        let metrics = result.performance(options.periods_per_year)?;
+
+        for trade in result.trades().chunks_exact(TRADE_STRIDE) {
+            self.closed += 1;
+            if trade[4] > 0.0 {
+                self.wins += 1;
+            }
+            self.trades.extend([
+                trade[0] + test_start as f64,
+                trade[1] + test_start as f64,
+                trade[2],
+                trade[3],
+                trade[4],
+                trade[5],
+            ]);
+        }
+        if result.total_return > 0.0 {
+            self.profitable += 1;
+        }
+        self.segments.extend([
+            train_start as f64,
+            test_start as f64,
+            test_end as f64,
+            beta,
+            entry,
+            result.total_return,
+            metrics.sharpe,
+            metrics.max_drawdown,
+            result.trade_count() as f64,
+        ]);
+        self.total_costs += result.total_costs;
+        self.capital = result.final_equity;
+        self.equity.extend(result.equity());
+        self.returns.extend(result.returns());
+
+        // A wiped-out account cannot trade the following segments
+        self.test_start = if self.capital <= 0.0 { n } else { test_end };
+        Ok(self.test_start >= n)
+    }
+
+    fn fraction_done(&self) -> f64 {
+        let first = self.options.train_window;
+        (self.test_start - first) as f64 / (self.prices_a.len() - first) as f64
+    }
+}
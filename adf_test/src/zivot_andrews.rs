@@ -0,0 +1,283 @@
+// Zivot-Andrews (1992) unit-root test with one endogenous structural break.
+//
+// A single break in the intercept and/or the trend makes a stationary series
+// look like a unit root to ADF. Zivot-Andrews fits the ADF regression with
+// break dummies at every candidate date and keeps the most negative t-ratio, so
+// the test allows for the break and also dates it.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::error::{Error, ErrorCode};
+use crate::json::{levels, JsonObject};
+use crate::linalg::least_squares;
+use crate::pvalue::{interpolate_critical_value, lookup_sorted, PValue, PValueSource};
+use crate::validate::{require_finite, require_varying};
+use crate::CRITICAL_VALUE_KEYS;
+use crate::CRITICAL_VALUE_LEVELS;
+#[cfg(feature = "wasm")]
+use crate::{critical_values_object, set_property, CriticalValues};
+
+// Zivot & Andrews (1992) asymptotic 1%, 5% and 10% critical values (Tables 2-4)
+const ZA_CRITICAL_LEVEL: [f64; 3] = [-5.34, -4.80, -4.58];
+const ZA_CRITICAL_TREND: [f64; 3] = [-4.93, -4.42, -4.11];
+const ZA_CRITICAL_BOTH: [f64; 3] = [-5.57, -5.08, -4.82];
+const DEFAULT_TRIM: f64 = 0.15;
+
+// What may shift at the break
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BreakType {
+    // Intercept ("c", model A)
+    Level,
+    // Trend slope ("t", model B)
+    Trend,
+    // Both ("ct", model C)
+    LevelTrend,
+}
+
+impl BreakType {
+    fn parse(regression: &str) -> Result<BreakType, Error> {
+        match regression {
+            "c" => Ok(BreakType::Level),
+            "t" => Ok(BreakType::Trend),
+            "ct" => Ok(BreakType::LevelTrend),
+            other => Err(Error::new(&format!(
+                "unknown break type \"{}\": expected \"c\", \"t\" or \"ct\"",
+                other
+            ))),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            BreakType::Level => "c",
+            BreakType::Trend => "t",
+            BreakType::LevelTrend => "ct",
+        }
+    }
+
+    fn critical_values(&self) -> [f64; 3] {
+        match self {
+            BreakType::Level => ZA_CRITICAL_LEVEL,
+            BreakType::Trend => ZA_CRITICAL_TREND,
+            BreakType::LevelTrend => ZA_CRITICAL_BOTH,
+        }
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(typescript_custom_section)]
+const ZIVOT_ANDREWS_RESULT_TS: &'static str = r#"
+/** Plain-object snapshot of a `ZivotAndrewsResult`, as returned by `ZivotAndrewsResult.to_object()`. */
+export interface ZivotAndrewsResultObject {
+  statistic: number;
+  p_value: number;
+  critical_values: CriticalValues;
+  is_stationary: boolean;
+  break_index: number;
+  lags: number;
+  nobs: number;
+  regression: "c" | "t" | "ct";
+  p_value_source: "interpolated" | "clamped_first" | "clamped_last";
+}
+"#;
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "ZivotAndrewsResultObject")]
+    pub type ZivotAndrewsResultObject;
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct ZivotAndrewsResult {
+    // Minimum ADF t-ratio over the candidate break dates
+    pub statistic: f64,
+    pub p_value: f64,
+    critical_values: [f64; 3],
+    // True when the unit root is rejected at 5%
+    pub is_stationary: bool,
+    // Index of the first observation of the new regime (the break dummies are 1 from here on)
+    pub break_index: usize,
+    pub lags: usize,
+    // Observations in each break regression
+    pub nobs: usize,
+    regression: BreakType,
+    p_value_source: PValueSource,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ZivotAndrewsResult {
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(getter)]
+    pub fn critical_values(&self) -> CriticalValues {
+        critical_values_object(self.critical_values)
+    }
+
+    /// Every field of the result as a plain JS object (see `ZivotAndrewsResultObject`).
+    #[cfg(feature = "wasm")]
+    pub fn to_object(&self) -> ZivotAndrewsResultObject {
+        let object = js_sys::Object::new();
+        set_property(&object, "statistic", self.statistic);
+        set_property(&object, "p_value", self.p_value);
+        set_property(&object, "critical_values", self.critical_values());
+        set_property(&object, "is_stationary", self.is_stationary);
+        set_property(&object, "break_index", self.break_index as f64);
+        set_property(&object, "lags", self.lags as f64);
+        set_property(&object, "nobs", self.nobs as f64);
+        set_property(&object, "regression", self.regression());
+        set_property(&object, "p_value_source", self.p_value_source());
+        object.unchecked_into()
+    }
+
+    // Critical value at any significance level between 1% and 10%, interpolated across the
+    // 1%/5%/10% values in the normal quantile of the level
+    pub fn critical_value(&self, alpha: f64) -> Result<f64, Error> {
+        interpolate_critical_value(&CRITICAL_VALUE_LEVELS, &self.critical_values, alpha)
+    }
+
+    // What was allowed to break: "c" (level), "t" (trend) or "ct" (both)
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn regression(&self) -> String {
+        self.regression.as_str().to_string()
+    }
+
+    // "interpolated" inside the tabulated 1%-10% range, otherwise "clamped_first" (p-value
+    // is at most 0.01) or "clamped_last" (p-value is at least 0.10)
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn p_value_source(&self) -> String {
+        self.p_value_source.as_str().to_string()
+    }
+
+    /// Every field of the result as JSON text, with non-finite numbers as null.
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .field("statistic", self.statistic)
+            .field("p_value", self.p_value)
+            .field(
+                "critical_values",
+                levels(&CRITICAL_VALUE_KEYS, &self.critical_values),
+            )
+            .field("is_stationary", self.is_stationary)
+            .field("break_index", self.break_index)
+            .field("lags", self.lags)
+            .field("nobs", self.nobs)
+            .field("regression", self.regression())
+            .field("p_value_source", self.p_value_source())
+            .finish()
+    }
+}
+
+impl ZivotAndrewsResult {
+    // Critical values as [1%, 5%, 10%], for native callers
+    pub fn critical_values_array(&self) -> [f64; 3] {
+        self.critical_values
+    }
+}
+
+/// Zivot-Andrews test of a unit root against stationarity around a single break in the level
+/// ("c"), the trend ("t") or both ("ct"), with `lags` lagged differences.
+///
+/// Every break date leaving a fraction `trim` (default 0.15) of the sample on each side is tried;
+/// the statistic is the smallest ADF t-ratio and `break_index` where it occurs, i.e. the most
+/// likely date the relationship changed. Critical values are the asymptotic ones of Zivot &
+/// Andrews (1992); the p-value is interpolated between the 1%, 5% and 10% levels and clamped
+/// outside them. Cost is one regression per candidate date, so keep series to a few thousand
+/// points.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn zivot_andrews(
+    series: &[f64],
+    regression: &str,
+    lags: usize,
+    trim: Option<f64>,
+) -> Result<ZivotAndrewsResult, Error> {
+    let break_type = BreakType::parse(regression)?;
+    let trim = trim.unwrap_or(DEFAULT_TRIM);
+    if !(trim > 0.0 && trim < 0.5) {
+        return Err(Error::new("trim must be in (0, 0.5)"));
+    }
+    require_finite(series, "series")?;
+    require_varying(series, "series")?;
+
+    let n = series.len();
+    let first_break = (trim * n as f64).ceil() as usize;
+    let last_break = ((1.0 - trim) * n as f64).floor() as usize;
+    // Regression rows are t = lags + 1..n; a break needs rows on both of its sides
+    if first_break <= lags + 1 || first_break > last_break || last_break >= n {
+        return Err(Error::with_code(
+            ErrorCode::TooShort,
+            &format!(
+                "series has {} observations, too few for {} lags with trim {}",
+                n, lags, trim
+            ),
+        ));
+    }
+
+    let diff: Vec<f64> = series.windows(2).map(|w| w[1] - w[0]).collect();
+    let rows: Vec<usize> = (lags + 1..n).collect();
+    let nobs = rows.len();
+    let response: Vec<f64> = rows.iter().map(|&t| diff[t - 1]).collect();
+    // Columns shared by every candidate break: y_{t-1}, lagged differences, constant, trend
+    let mut base = vec![rows.iter().map(|&t| series[t - 1]).collect::<Vec<f64>>()];
+    for i in 1..=lags {
+        base.push(rows.iter().map(|&t| diff[t - 1 - i]).collect());
+    }
+    base.push(vec![1.0; nobs]);
+    base.push(rows.iter().map(|&t| t as f64).collect());
+
+    let mut best: Option<(f64, usize)> = None;
+    for break_index in first_break..=last_break {
+        let mut columns = base.clone();
+        if break_type != BreakType::Trend {
+            columns.push(
+                rows.iter()
+                    .map(|&t| if t >= break_index { 1.0 } else { 0.0 })
+                    .collect(),
+            );
+        }
+        if break_type != BreakType::Level {
+            columns.push(
+                rows.iter()
+                    .map(|&t| {
+                        if t >= break_index {
+                            (t + 1 - break_index) as f64
+                        } else {
+                            0.0
+                        }
+                    })
+                    .collect(),
+            );
+        }
+        let Some(ols) = least_squares(&columns, &response) else {
+            continue;
+        };
+        let t_ratio = ols.coefficients[0] / ols.std_error(0);
+        if t_ratio.is_finite() && best.is_none_or(|(b, _)| t_ratio < b) {
+            best = Some((t_ratio, break_index));
+        }
+    }
+    let (statistic, break_index) = best.ok_or_else(|| {
+        Error::new("every break regression failed: series too short or degenerate")
+    })?;
+
+    let critical_values = break_type.critical_values();
+    let PValue {
+        value: p_value,
+        source: p_value_source,
+    } = lookup_sorted(
+        critical_values.len(),
+        |i| (critical_values[i], CRITICAL_VALUE_LEVELS[i]),
+        statistic,
+    );
+    Ok(ZivotAndrewsResult {
+        statistic,
+        p_value,
+        critical_values,
+        is_stationary: statistic < critical_values[1],
+        break_index,
+        lags,
+        nobs,
+        regression: break_type,
+        p_value_source,
+    })
+}
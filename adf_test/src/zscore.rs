@@ -0,0 +1,111 @@
+// Spread normalisation.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::buffer::Float64Buffer;
+use crate::precision::Real;
+use crate::stats::{nanmean, nanstd, NanPolicy};
+
+// Rolling z-score over a trailing window (sample standard deviation, matching the
+// app's JS worker). The output is aligned with the input: the first window - 1
+// entries, and any window with zero variance, are NaN.
+pub(crate) fn rolling_zscore(series: &[f64], window: usize) -> Vec<f64> {
+    let mut out = Vec::new();
+    rolling_zscore_to(series, window, &mut out);
+    out
+}
+
+// `rolling_zscore` written to `out`, replacing its contents, in the storage precision of `T`
+fn rolling_zscore_to<T: Real>(series: &[T], window: usize, out: &mut Vec<T>) {
+    out.clear();
+    out.resize(series.len(), T::narrow(f64::NAN));
+    if window < 2 || window > series.len() {
+        return;
+    }
+
+    for (i, slot) in out.iter_mut().enumerate().skip(window - 1) {
+        let slice = T::widen_slice(&series[i + 1 - window..=i]);
+        let mean = nanmean(&slice, NanPolicy::Propagate);
+        let std = nanstd(&slice, 1, NanPolicy::Propagate);
+        if std > 0.0 {
+            *slot = T::narrow((series[i].widen() - mean) / std);
+        }
+    }
+}
+
+/// Rolling z-score of a spread over a trailing `window` (sample standard deviation, current
+/// bar included). Aligned with the input: the first `window - 1` entries, and any window with
+/// zero variance, are NaN; every entry is NaN if `window < 2` or exceeds the series length.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn zscore(spread: &[f64], window: usize) -> Vec<f64> {
+    rolling_zscore(spread, window)
+}
+
+/// `zscore` of the values in `spread`, written to `out` (resized to the spread's length) instead
+/// of a new array. `out` must be a different buffer from `spread`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn zscore_into(spread: &Float64Buffer, window: usize, out: &mut Float64Buffer) {
+    rolling_zscore_to(spread.as_slice(), window, out.output());
+}
+
+/// `zscore` of a Float32Array, returned as one: half the memory of `zscore`, with each window's
+/// mean and standard deviation still computed in f64.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn zscore_f32(spread: &[f32], window: usize) -> Vec<f32> {
+    let mut out = Vec::new();
+    rolling_zscore_to(spread, window, &mut out);
+    out
+}
+
+/// Exponentially weighted z-score with the given `halflife` in bars.
+///
+/// Mean and bias-corrected standard deviation follow pandas'
+/// `ewm(halflife=..., adjust=True).mean()` / `.std()`, so the decay is α = 1 - 0.5^(1/halflife)
+/// and the current bar is included. The first entry (a single observation has no spread) and
+/// zero-variance points are NaN. A NaN input yields NaN and is left out of the averages, though
+/// older weights still decay past it. Every entry is NaN if `halflife` is not positive.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn ew_zscore(spread: &[f64], halflife: f64) -> Vec<f64> {
+    let mut out = vec![f64::NAN; spread.len()];
+    if !(halflife > 0.0 && halflife.is_finite()) {
+        return out;
+    }
+    let decay = 0.5f64.powf(1.0 / halflife);
+
+    // Running weighted mean and (biased) variance, plus Σw and Σw² for the bias correction
+    let mut mean = f64::NAN;
+    let mut variance = 0.0;
+    let mut sum_weights = 0.0;
+    let mut sum_squared_weights = 0.0;
+
+    for (slot, &x) in out.iter_mut().zip(spread) {
+        sum_weights *= decay;
+        sum_squared_weights *= decay * decay;
+        if x.is_nan() {
+            continue;
+        }
+        if mean.is_nan() {
+            mean = x;
+            sum_weights = 1.0;
+            sum_squared_weights = 1.0;
+            continue;
+        }
+
+        let old_weight = sum_weights;
+        sum_weights += 1.0;
+        sum_squared_weights += 1.0;
+        let old_mean = mean;
+        mean = (old_weight * old_mean + x) / sum_weights;
+        variance = (old_weight * (variance + (old_mean - mean) * (old_mean - mean))
+            + (x - mean) * (x - mean))
+            / sum_weights;
+
+        let denominator = sum_weights * sum_weights - sum_squared_weights;
+        let std = (variance * sum_weights * sum_weights / denominator).sqrt();
+        if std > 0.0 {
+            *slot = (x - mean) / std;
+        }
+    }
+    out
+}